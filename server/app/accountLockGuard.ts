@@ -0,0 +1,48 @@
+import type { NextFunction, Request, Response } from "express";
+import { db } from "../db.js";
+import { getSettings } from "../modules/chat/routeHelpers.js";
+import { lockedError, sendAppError } from "../services/appError.js";
+import { isAccountLocked, touchAccountActivity } from "../services/accountLock.js";
+
+export function accountExists(): boolean {
+  return Boolean(db.prepare("SELECT 1 FROM accounts LIMIT 1").get());
+}
+
+/** Without an account there is nothing to unlock, so nothing is ever locked. */
+export function isAppLocked(): boolean {
+  return accountExists() && isAccountLocked(getSettings().autoLockMinutes);
+}
+
+// Only changes count as activity; background polling must not keep the account unlocked.
+export function trackAccountActivity(req: Request, _res: Response, next: NextFunction) {
+  if (req.method !== "GET" && req.method !== "HEAD" && req.method !== "OPTIONS") {
+    touchAccountActivity(getSettings().autoLockMinutes);
+  }
+  next();
+}
+
+/** Routes that expose or replace all user data answer 423 with `code: "locked"` so the UI can ask for the password. */
+export function requireUnlockedAccount(_req: Request, res: Response, next: NextFunction) {
+  if (isAppLocked()) {
+    sendAppError(res, lockedError());
+    return;
+  }
+  next();
+}
+
+// Reachable while locked: the unlock and lock endpoints themselves, plus health and update checks,
+// none of which return user data. `/status` stays locked since it reports the database path and content counts.
+const LOCK_EXEMPT_API_PATHS = new Set(["/account/unlock", "/account/lock", "/account/status", "/health"]);
+
+function isLockExemptApiPath(path: string): boolean {
+  return LOCK_EXEMPT_API_PATHS.has(path) || path === "/updates" || path.startsWith("/updates/");
+}
+
+/** Mounted on `/api` so every data route, including avatar and upload files, is behind the account lock. */
+export function requireUnlockedApi(req: Request, res: Response, next: NextFunction) {
+  if (isLockExemptApiPath(req.path)) {
+    next();
+    return;
+  }
+  requireUnlockedAccount(req, res, next);
+}
@@ -3,6 +3,8 @@ import { createServer, type Server as HttpServer } from "http";
 import { tmpdir } from "os";
 import { join } from "path";
 import type { AddressInfo } from "net";
+import extractZip from "extract-zip";
+import mammoth from "mammoth";
 import { afterAll, beforeAll, describe, expect, it, vi } from "vitest";
 
 interface JsonRequestInit extends Omit<RequestInit, "body"> {
@@ -17,12 +19,18 @@ describe.sequential("createApp integration", () => {
   let mockProviderBaseUrl = "";
   let mockMcpScriptPath = "";
   let lastCompactionPromptText = "";
+  let lastBeatDraftPromptText = "";
+  let lastSceneFromChatPromptText = "";
   let lastAgentPromptStackText = "";
   let lastBuildDirectToolChoice = "";
   let lastPlannerResponseFormat: unknown = null;
   let lastChatTemplateMessages: Array<{ role?: unknown; content?: unknown }> = [];
+  let lastSamplerRequestBody: Record<string, unknown> = {};
+  let lastModelsRequestHeaders: Record<string, unknown> = {};
+  let lastGeminiRequest: { url: string; body: Record<string, unknown> } | null = null;
   let lastSttMultipartBody = "";
   let lastTtsRequestBody: Record<string, unknown> = {};
+  let embeddedInputCount = 0;
   let createApp: typeof import("./createApp.js").createApp;
   let db: typeof import("../db.js").db;
   let newId: typeof import("../db.js").newId;
@@ -167,6 +175,12 @@ process.stdin.on("data", (chunk) => {
 
     mockProviderServer = await listen(createServer(async (req, res) => {
       if (req.method === "GET" && req.url === "/v1/models") {
+        lastModelsRequestHeaders = { ...req.headers };
+        if (req.headers.authorization === "Bearer revoked-key") {
+          res.statusCode = 401;
+          res.end(JSON.stringify({ error: "invalid api key" }));
+          return;
+        }
         res.setHeader("Content-Type", "application/json");
         res.end(JSON.stringify({
           data: [
@@ -176,6 +190,58 @@ process.stdin.on("data", (chunk) => {
         }));
         return;
       }
+      if (req.url?.startsWith("/gemini/v1beta/models")) {
+        const url = new URL(req.url, "http://mock");
+        res.setHeader("Content-Type", req.method === "GET" ? "application/json" : "text/event-stream");
+        if (url.searchParams.get("key") !== "AIza-test") {
+          res.statusCode = 400;
+          res.end(JSON.stringify({ error: { message: "API key not valid" } }));
+          return;
+        }
+        if (req.method === "GET") {
+          res.end(JSON.stringify({
+            models: [
+              { name: "models/gemini-test", inputTokenLimit: 32768, supportedGenerationMethods: ["generateContent"] },
+              { name: "models/embedding-test", supportedGenerationMethods: ["embedContent"] }
+            ]
+          }));
+          return;
+        }
+        const body = await readJsonBody(req);
+        if (url.pathname.endsWith(":generateContent")) {
+          res.setHeader("Content-Type", "application/json");
+          res.end(JSON.stringify({ candidates: [{ content: { role: "model", parts: [{ text: "Tide Talk" }] }, finishReason: "STOP" }] }));
+          return;
+        }
+        lastGeminiRequest = { url: url.pathname + url.search, body };
+        const chunks = [
+          { candidates: [{ content: { role: "model", parts: [{ text: "Weighing the tide.", thought: true }] } }] },
+          { candidates: [{ content: { role: "model", parts: [{ text: "The tide is " }] } }] },
+          { candidates: [{ content: { role: "model", parts: [{ text: "turning." }] }, finishReason: "STOP" }] }
+        ];
+        res.end(chunks.map((chunk) => `data: ${JSON.stringify(chunk)}\r\n\r\n`).join(""));
+        return;
+      }
+      if (req.method === "GET" && req.url?.startsWith("/query-auth/v1/models")) {
+        const key = new URL(req.url, "http://mock").searchParams.get("api_key");
+        res.statusCode = key === "query-secret" && !req.headers.authorization ? 200 : 401;
+        res.setHeader("Content-Type", "application/json");
+        res.end(JSON.stringify(res.statusCode === 200 ? { data: [{ id: "query-model" }] } : { error: "missing key" }));
+        return;
+      }
+      if (req.method === "POST" && req.url === "/v1/moderations") {
+        const body = await readJsonBody(req);
+        const flagged = String(body.input || "").includes("forbidden-phrase");
+        res.setHeader("Content-Type", "application/json");
+        res.end(JSON.stringify({ results: [{ flagged, categories: { harassment: flagged, violence: false } }] }));
+        return;
+      }
+      if (req.method === "POST" && req.url === "/failing/v1/chat/completions") {
+        res.statusCode = 500;
+        res.setHeader("Content-Type", "application/json");
+        res.end(JSON.stringify({ error: { message: "upstream overloaded" } }));
+        return;
+      }
       if (req.method === "POST" && req.url === "/v1/chat/completions") {
         const body = await readJsonBody(req);
         const messages = Array.isArray(body.messages) ? body.messages : [];
@@ -206,9 +272,12 @@ process.stdin.on("data", (chunk) => {
             return flattenMockContent((message as { content?: unknown }).content);
           })
           .join("\n\n");
-        if (promptText.includes("single-system-template-check")) {
+        if (promptText.includes("single-system-template-check") || promptText.includes("Keeps the persona on regenerate")) {
           lastChatTemplateMessages = messages as Array<{ role?: unknown; content?: unknown }>;
         }
+        if (promptText.includes("chat-sampler-override-check")) {
+          lastSamplerRequestBody = body;
+        }
         const hasPriorAssistantCapabilityContext = messages.some((message) => (
           message
           && typeof message === "object"
@@ -216,6 +285,12 @@ process.stdin.on("data", (chunk) => {
           && flattenMockContent((message as { content?: unknown }).content).includes("I can answer questions, inspect the workspace when tools are enabled")
         ));
 
+        if (promptText.includes("[Chat Transcript]")) {
+          lastSceneFromChatPromptText = promptText;
+        }
+        if (promptText.includes("[Beats To Cover]")) {
+          lastBeatDraftPromptText = promptText;
+        }
         if (promptText.includes("compact-history-agent-task")) {
           lastCompactionPromptText = promptText;
         }
@@ -631,6 +706,23 @@ process.stdin.on("data", (chunk) => {
           return;
         }
 
+        if (body.stream === true && promptText.includes("client-dice-tool-check")) {
+          res.setHeader("Content-Type", "text/event-stream");
+          if (toolMessages.length === 0 && toolDefinitions.length > 0) {
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: "Rolling for you." } }] })}\n\n`);
+            res.write(`data: ${JSON.stringify({
+              choices: [{ delta: { tool_calls: [{ index: 0, id: "call-dice-1", type: "function", function: { name: "roll_dice", arguments: "{\"sides\":" } }] } }]
+            })}\n\n`);
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { tool_calls: [{ index: 0, function: { arguments: "20}" } }] } }] })}\n\n`);
+          } else {
+            const answered = messages.find((message) => (message as { role?: unknown }).role === "tool") as { tool_call_id?: unknown; content?: unknown } | undefined;
+            lastChatTemplateMessages = messages as Array<{ role?: unknown; content?: unknown }>;
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: `The die shows ${String(answered?.content)} (${String(answered?.tool_call_id)}).` } }] })}\n\n`);
+          }
+          res.write("data: [DONE]\n\n");
+          res.end();
+          return;
+        }
         if (body.stream === true) {
           if (promptText.includes("Abort this stream before output")) {
             await sleep(250);
@@ -645,6 +737,41 @@ process.stdin.on("data", (chunk) => {
             res.end();
             return;
           }
+          if (promptText.includes("cancel-partial-stream-check")) {
+            res.setHeader("Content-Type", "text/event-stream");
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: "Partial reply before cancel" } }] })}\n\n`);
+            await sleep(1500);
+            if (res.destroyed || res.writableEnded) return;
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: " that should never arrive" } }] })}\n\n`);
+            res.write("data: [DONE]\n\n");
+            res.end();
+            return;
+          }
+          if (promptText.includes("client-drop-stream-check")) {
+            res.setHeader("Content-Type", "text/event-stream");
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: "Started before the drop" } }] })}\n\n`);
+            await sleep(300);
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: " and finished after it" } }] })}\n\n`);
+            res.write("data: [DONE]\n\n");
+            res.end();
+            return;
+          }
+          if (promptText.includes("reasoning-only-stream-check")) {
+            res.setHeader("Content-Type", "text/event-stream");
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { reasoning_content: "Weighing how the keeper " } }] })}\n\n`);
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { reasoning_content: "would answer.", content: "" } }] })}\n\n`);
+            res.write("data: [DONE]\n\n");
+            res.end();
+            return;
+          }
+          if (promptText.includes("[Continue] Your previous reply was cut off")) {
+            res.setHeader("Content-Type", "text/event-stream");
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: "ered over " } }] })}\n\n`);
+            res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: "the harbour." } }] })}\n\n`);
+            res.write("data: [DONE]\n\n");
+            res.end();
+            return;
+          }
           if (promptText.includes("compact-history-agent-task")) {
             res.setHeader("Content-Type", "text/event-stream");
             res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: "History compacted cleanly." } }] })}\n\n`);
@@ -1018,6 +1145,9 @@ process.stdin.on("data", (chunk) => {
           }
           res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: "MOCK " } }] })}\n\n`);
           res.write(`data: ${JSON.stringify({ choices: [{ delta: { content: "STREAM RESPONSE" } }] })}\n\n`);
+          if ((body.stream_options as { include_usage?: unknown } | undefined)?.include_usage === true) {
+            res.write(`data: ${JSON.stringify({ choices: [], usage: { prompt_tokens: 42, completion_tokens: 3, total_tokens: 45 } })}\n\n`);
+          }
           res.write("data: [DONE]\n\n");
           res.end();
           return;
@@ -1499,6 +1629,8 @@ process.stdin.on("data", (chunk) => {
             ? "History compacted cleanly."
           : promptText.includes("simple-capability-agent-task")
             ? "I can answer questions, inspect the workspace when tools are enabled, run bounded task flows, and help with implementation, review, and research."
+          : promptText.includes("[Outline Task]") && promptText.includes("outline-mock-premise")
+            ? "1. The Letter — Mara finds the letter.\n2. Refusal — She burns it."
           : promptText.includes("Required JSON keys:")
           ? JSON.stringify({
             name: "Mock Character",
@@ -1547,6 +1679,17 @@ process.stdin.on("data", (chunk) => {
         res.end(Buffer.from("FAKE_MP3_DATA"));
         return;
       }
+      if (req.method === "POST" && req.url === "/v1/embeddings") {
+        const body = await readJsonBody(req);
+        const input = Array.isArray(body.input) ? body.input.map((item) => String(item)) : [String(body.input ?? "")];
+        embeddedInputCount += input.length;
+        const buckets = [/betray|traitor|dagger/i, /storm|rain|sea/i];
+        res.setHeader("Content-Type", "application/json");
+        res.end(JSON.stringify({
+          data: input.map((text) => ({ embedding: [...buckets.map((pattern) => (pattern.test(text) ? 1 : 0)), 0.1] }))
+        }));
+        return;
+      }
       if (req.method === "POST" && req.url === "/v1/audio/transcriptions") {
         const chunks: Buffer[] = [];
         for await (const chunk of req) chunks.push(Buffer.isBuffer(chunk) ? chunk : Buffer.from(chunk));
@@ -1601,6 +1744,18 @@ process.stdin.on("data", (chunk) => {
     expect(compressed.summary).toContain("user: Hello integration");
     expect(compressed.summary).toContain("assistant: [No provider configured] Echo: Hello integration");
 
+    const partiallyCompressed = await postJson(`/api/chats/${created.id}/compress`, { keepRecent: 1 });
+    expect(partiallyCompressed.summary).toContain("user: Hello integration");
+    expect(partiallyCompressed.summary).not.toContain("assistant:");
+    const summaryEntries = db.prepare("SELECT content FROM rp_memory_entries WHERE chat_id = ? AND role = 'summary' ORDER BY rowid ASC")
+      .all(created.id) as Array<{ content: string }>;
+    expect(summaryEntries.map((entry) => entry.content)).toEqual([compressed.summary, partiallyCompressed.summary]);
+
+    const nothingToCondense = await postJson(`/api/chats/${created.id}/compress`, { keepRecent: 2 });
+    expect(nothingToCondense.summary).toBe(partiallyCompressed.summary);
+    expect(db.prepare("SELECT context_summary FROM chats WHERE id = ?").get(created.id)).toEqual({ context_summary: partiallyCompressed.summary });
+    expect(db.prepare("SELECT COUNT(*) AS count FROM rp_memory_entries WHERE chat_id = ? AND role = 'summary'").get(created.id)).toEqual({ count: 2 });
+
     const regenerated = await postJson(`/api/chats/${created.id}/regenerate`, {});
     expect(regenerated).toHaveLength(2);
     expect(regenerated[1]).toMatchObject({
@@ -1630,6 +1785,29 @@ process.stdin.on("data", (chunk) => {
     expect(enableResponse.ok).toBe(true);
   });
 
+  it("summarizes app state for bug reports without exposing provider secrets", async () => {
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", fullLocalMode: false });
+    const chatCount = (db.prepare("SELECT COUNT(*) AS count FROM chats").get() as { count: number }).count;
+
+    const response = await fetch(`${baseUrl}/api/status`);
+    expect(response.ok).toBe(true);
+    const body = await response.text();
+    const status = JSON.parse(body) as Record<string, unknown>;
+
+    expect(status).toMatchObject({
+      dbPath: join(dataDir, "vellum.db"),
+      chatCount,
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      fullLocalMode: false
+    });
+    expect(Number(status.schemaVersion)).toBeGreaterThan(0);
+    expect(Number(status.providerCount)).toBeGreaterThan(0);
+    expect(typeof status.appVersion).toBe("string");
+    expect(body).not.toContain("test-key");
+    expect(body).not.toContain(mockProviderBaseUrl);
+  });
+
   it("exports a stable chat JSON bundle with branches and messages", async () => {
     await updateSettings({
       activeProviderId: null,
@@ -1669,6 +1847,85 @@ process.stdin.on("data", (chunk) => {
     expect(exported.messagesByBranch?.[branchId]).toHaveLength(2);
   });
 
+  it("writes a Markdown transcript with scene annotations and records the export", async () => {
+    await updateSettings({ activeProviderId: null, activeModel: null });
+    const created = await postJson("/api/chats", { title: "Markdown Chat" });
+    const timeline = await postJson(`/api/chats/${created.id}/send`, { content: "Describe the harbour" });
+    await postJson("/api/rp/scene-state", { chatId: created.id, mood: "tense", pacing: "slow", intensity: 0.7, variables: { weather: "fog" } });
+    await postJson("/api/rp/author-note", { chatId: created.id, authorNote: "Keep the fog thick" });
+
+    const outputPath = await postJson(`/api/chats/${created.id}/export/markdown`, { branchId: timeline[0].branchId }) as string;
+    expect(outputPath).toMatch(/Markdown Chat\.vellium-chat\.md$/);
+    const markdown = readFileSync(outputPath, "utf8");
+    expect(markdown).toContain("# Markdown Chat");
+    expect(markdown).toContain("> **Scene:** mood tense · pacing slow · intensity 70%");
+    expect(markdown).toContain("> **Scene variables:** weather: fog");
+    expect(markdown).toContain("> **Author's note:** Keep the fog thick");
+    expect(markdown).toContain(`### User · user · ${timeline[0].createdAt}\n\nDescribe the harbour`);
+    expect(markdown).toContain("[No provider configured] Echo: Describe the harbour");
+    expect(markdown.indexOf("Describe the harbour")).toBeLessThan(markdown.indexOf("Echo: Describe the harbour"));
+    expect(db.prepare("SELECT export_type, output_path FROM chat_exports WHERE chat_id = ?").all(created.id))
+      .toEqual([{ export_type: "markdown", output_path: outputPath }]);
+
+    const missing = await requestJson("/api/chats/missing-chat/export/markdown", { method: "POST", body: {} });
+    expect(missing.status).toBe(404);
+  });
+
+  it("exports a chat as a message array or per-turn JSONL without deleted or tool records", async () => {
+    await updateSettings({ activeProviderId: null, activeModel: null });
+    const created = await postJson("/api/chats", { title: "Dataset Chat" });
+    await postJson(`/api/chats/${created.id}/send`, { content: "First prompt" });
+    const timeline = await postJson(`/api/chats/${created.id}/send`, { content: "Second prompt" });
+    const branchId = timeline[0].branchId as string;
+    db.prepare(
+      "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, parent_id, sort_order, created_at) VALUES (?, ?, ?, 'tool', ?, 1, ?, 99, ?)"
+    ).run(`dataset-tool-${created.id}`, created.id, branchId, "reasoning trace", timeline[1].id, "2026-01-01T00:00:00.000Z");
+    const deleted = await postJson(`/api/chats/${created.id}/send`, { content: "Deleted prompt" });
+    await requestJson(`/api/messages/${deleted[deleted.length - 2].id}`, { method: "DELETE" });
+    await requestJson(`/api/messages/${deleted[deleted.length - 1].id}`, { method: "DELETE" });
+
+    const conversationPath = await postJson(`/api/chats/${created.id}/export/jsonl`, { branchId, format: "conversation" }) as string;
+    expect(conversationPath).toMatch(/Dataset Chat\.vellium-chat\.json$/);
+    expect(JSON.parse(readFileSync(conversationPath, "utf8"))).toEqual([
+      { role: "user", content: "First prompt" },
+      { role: "assistant", content: "[No provider configured] Echo: First prompt" },
+      { role: "user", content: "Second prompt" },
+      { role: "assistant", content: "[No provider configured] Echo: Second prompt" }
+    ]);
+
+    const turnsPath = await postJson(`/api/chats/${created.id}/export/jsonl`, { branchId, format: "turns" }) as string;
+    const lines = readFileSync(turnsPath, "utf8").trim().split("\n").map((line) => JSON.parse(line) as { messages: unknown[] });
+    expect(lines.map((line) => line.messages.length)).toEqual([2, 4]);
+    expect(db.prepare("SELECT export_type FROM chat_exports WHERE chat_id = ? ORDER BY created_at, export_type").all(created.id))
+      .toEqual(expect.arrayContaining([{ export_type: "json" }, { export_type: "jsonl" }]));
+
+    const invalid = await requestJson(`/api/chats/${created.id}/export/jsonl`, { method: "POST", body: { format: "csv" } });
+    expect(invalid.status).toBe(400);
+  });
+
+  it("previews the assembled prompt without contacting the provider or adding messages", async () => {
+    await updateSettings({ activeProviderId: null, activeModel: null });
+    const created = await postJson("/api/chats", { title: "Preview Chat" });
+    await requestJson(`/api/chats/${created.id}/system-prompt`, { method: "PUT", body: { systemPrompt: "You narrate a foggy harbour." } });
+    const timeline = await postJson(`/api/chats/${created.id}/send`, { content: "Where is the ferry?" });
+
+    const preview = await postJson(`/api/chats/${created.id}/preview-prompt`, {
+      branchId: timeline[0].branchId,
+      userPersona: { name: "Mara" }
+    }) as { messages: Array<{ role: string; content: string }>; estimatedTokens: number; keptMessages: number; omittedMessages: number };
+
+    expect(preview.messages[0].role).toBe("system");
+    expect(preview.messages[0].content).toContain("You narrate a foggy harbour.");
+    expect(preview.messages.some((message) => message.role === "user" && message.content.includes("Where is the ferry?"))).toBe(true);
+    expect(preview.estimatedTokens).toBeGreaterThan(0);
+    expect(preview.keptMessages).toBe(2);
+    expect(preview.omittedMessages).toBe(0);
+    expect(db.prepare("SELECT COUNT(*) AS count FROM messages WHERE chat_id = ?").get(created.id)).toEqual({ count: 2 });
+
+    const missing = await requestJson("/api/chats/missing-chat/preview-prompt", { method: "POST", body: {} });
+    expect(missing.status).toBe(404);
+  });
+
   it("renames and safely deletes chat branches without losing the remaining timeline", async () => {
     await updateSettings({ activeProviderId: null, activeModel: null });
     const created = await postJson("/api/chats", { title: "Branch Manager" });
@@ -1690,7 +1947,15 @@ process.stdin.on("data", (chunk) => {
     expect(deletedResponse.status).toBe(200);
     const deleted = await deletedResponse.json() as { activeBranchId: string; branches: Array<{ id: string; name: string }> };
     expect(deleted.activeBranchId).toBe(forked.id);
-    expect(deleted.branches).toEqual([{ id: forked.id, chatId: created.id, name: "Better ending", parentMessageId: timeline[1].id, createdAt: expect.any(String) }]);
+    expect(deleted.branches).toEqual([{
+      id: forked.id,
+      chatId: created.id,
+      name: "Better ending",
+      parentMessageId: timeline[1].id,
+      messageCount: 2,
+      active: true,
+      createdAt: expect.any(String)
+    }]);
 
     const remainingTimeline = await parseJsonResponse(
       `/api/chats/${created.id}/timeline`,
@@ -1707,6 +1972,118 @@ process.stdin.on("data", (chunk) => {
     expect(await lastDeleteResponse.json()).toMatchObject({ error: "The last branch cannot be deleted" });
   });
 
+  it("lists branches with message counts and persists the active branch for sends and timelines", async () => {
+    await updateSettings({ activeProviderId: null, activeModel: null });
+    const created = await postJson("/api/chats", { title: "Active Branch" });
+    const timeline = await postJson(`/api/chats/${created.id}/send`, { content: "Root message" });
+    const rootBranchId = timeline[0].branchId as string;
+    const forked = await postJson(`/api/chats/${created.id}/fork`, { parentMessageId: timeline[0].id, name: "Side path" });
+    expect(forked).toMatchObject({ messageCount: 1, active: true });
+
+    const listBranches = async () => parseJsonResponse(
+      `/api/chats/${created.id}/branches`,
+      await fetch(`${baseUrl}/api/chats/${created.id}/branches`)
+    ) as Promise<Array<{ id: string; name: string; messageCount: number; active: boolean }>>;
+    expect((await listBranches()).map((branch) => [branch.name, branch.messageCount, branch.active])).toEqual([
+      ["main", 2, false],
+      ["Side path", 1, true]
+    ]);
+
+    const sideTimeline = await postJson(`/api/chats/${created.id}/send`, { content: "Sent without a branch id" });
+    expect(sideTimeline.every((message: { branchId: string }) => message.branchId === forked.id)).toBe(true);
+
+    const activated = await postJson(`/api/chats/${created.id}/branches/${rootBranchId}/activate`, {});
+    expect(activated.activeBranchId).toBe(rootBranchId);
+    const defaultTimeline = await parseJsonResponse(
+      `/api/chats/${created.id}/timeline`,
+      await fetch(`${baseUrl}/api/chats/${created.id}/timeline`)
+    );
+    expect(defaultTimeline.map((message: { content: string }) => message.content)).toEqual([
+      "Root message",
+      "[No provider configured] Echo: Root message"
+    ]);
+    expect((await listBranches()).find((branch) => branch.active)?.id).toBe(rootBranchId);
+
+    const missing = await requestJson(`/api/chats/${created.id}/branches/missing-branch/activate`, { method: "POST" });
+    expect(missing.status).toBe(404);
+  });
+
+  it("shows the full conversation leading into a two-level fork", async () => {
+    await updateSettings({ activeProviderId: null, activeModel: null });
+    const created = await postJson("/api/chats", { title: "Nested Forks" });
+    const root = await postJson(`/api/chats/${created.id}/send`, { content: "First turn" });
+    const rootBranchId = root[0].branchId as string;
+
+    const child = await postJson(`/api/chats/${created.id}/fork`, { parentMessageId: root[1].id, name: "Child" });
+    const childTimeline = await postJson(`/api/chats/${created.id}/send`, { content: "Child turn", branchId: child.id });
+    expect(childTimeline.map((message: { content: string }) => message.content)).toEqual([
+      "First turn",
+      "[No provider configured] Echo: First turn",
+      "Child turn",
+      "[No provider configured] Echo: Child turn"
+    ]);
+
+    const grandchild = await postJson(`/api/chats/${created.id}/fork`, { parentMessageId: childTimeline[2].id, name: "Grandchild" });
+    const timelineOf = async (branchId: string) => parseJsonResponse(
+      `/api/chats/${created.id}/timeline`,
+      await fetch(`${baseUrl}/api/chats/${created.id}/timeline?branchId=${branchId}`)
+    ) as Promise<Array<{ content: string; branchId: string }>>;
+    const fresh = await timelineOf(grandchild.id);
+    expect(fresh.map((message) => message.content)).toEqual(["First turn", "[No provider configured] Echo: First turn", "Child turn"]);
+    expect(fresh.every((message) => message.branchId === grandchild.id)).toBe(true);
+
+    await postJson(`/api/chats/${created.id}/send`, { content: "Grandchild turn", branchId: grandchild.id });
+    expect((await timelineOf(grandchild.id)).map((message) => message.content)).toEqual([
+      "First turn",
+      "[No provider configured] Echo: First turn",
+      "Child turn",
+      "Grandchild turn",
+      "[No provider configured] Echo: Grandchild turn"
+    ]);
+    expect(await timelineOf(child.id)).toHaveLength(4);
+    expect(await timelineOf(rootBranchId)).toHaveLength(2);
+  });
+
+  it("merges a fork's new messages onto a diverged target branch and drops the fork", async () => {
+    await updateSettings({ activeProviderId: null, activeModel: null });
+    const created = await postJson("/api/chats", { title: "Merge Branches" });
+    const root = await postJson(`/api/chats/${created.id}/send`, { content: "Shared turn" });
+    const rootBranchId = root[0].branchId as string;
+    const fork = await postJson(`/api/chats/${created.id}/fork`, { parentMessageId: root[1].id, name: "Experiment" });
+    await postJson(`/api/chats/${created.id}/send`, { content: "Fork turn", branchId: fork.id });
+    await postJson(`/api/chats/${created.id}/send`, { content: "Main turn", branchId: rootBranchId });
+
+    const sameBranch = await requestJson(`/api/chats/${created.id}/branches/${fork.id}/merge`, {
+      method: "POST",
+      body: { targetBranchId: fork.id }
+    });
+    expect(sameBranch.status).toBe(400);
+    const missing = await requestJson(`/api/chats/${created.id}/branches/${fork.id}/merge`, {
+      method: "POST",
+      body: { targetBranchId: "missing-branch" }
+    });
+    expect(missing.status).toBe(404);
+
+    const merged = await postJson(`/api/chats/${created.id}/branches/${fork.id}/merge`, {
+      targetBranchId: rootBranchId,
+      deleteSource: true
+    });
+    expect(merged).toMatchObject({ ok: true, appended: 2, sourceDeleted: true, activeBranchId: rootBranchId });
+    expect(merged.branches.map((branch: { id: string }) => branch.id)).toEqual([rootBranchId]);
+    const timeline = merged.timeline as Array<{ content: string; branchId: string; createdAt: string }>;
+    expect(timeline.map((message) => message.content)).toEqual([
+      "Shared turn",
+      "[No provider configured] Echo: Shared turn",
+      "Main turn",
+      "[No provider configured] Echo: Main turn",
+      "Fork turn",
+      "[No provider configured] Echo: Fork turn"
+    ]);
+    expect(timeline.every((message) => message.branchId === rootBranchId)).toBe(true);
+    expect(timeline.map((message) => message.createdAt)).toEqual([...timeline.map((message) => message.createdAt)].sort());
+    expect(db.prepare("SELECT COUNT(*) AS count FROM messages WHERE branch_id = ?").get(fork.id)).toEqual({ count: 0 });
+  });
+
   it("sends exactly one combined system message before chat history", async () => {
     await updateSettings({
       activeProviderId: "mock-openai",
@@ -1745,274 +2122,2050 @@ process.stdin.on("data", (chunk) => {
     await updateSettings({ rpReasoningEnabled: false });
   });
 
-  it("persists manual character ordering and prepends new characters", async () => {
-    const importCharacter = (name: string) => postJson("/api/characters/import", {
-      rawJson: JSON.stringify({
-        spec: "chara_card_v2",
-        spec_version: "2.0",
-        data: { name, first_mes: "Hello" }
-      })
+  it("falls back to the next provider in the chain when the primary fails before streaming", async () => {
+    db.prepare(`
+      INSERT OR REPLACE INTO providers (id, name, base_url, api_key_cipher, full_local_only, provider_type)
+      VALUES (?, ?, ?, ?, 0, 'openai')
+    `).run("failing-openai", "Failing OpenAI", `${mockProviderBaseUrl}/failing/v1`, "test-key");
+    await updateSettings({
+      activeProviderId: "failing-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      streamRetryAttempts: 0,
+      providerFallbacks: [{ providerId: "missing-provider", modelId: "ghost" }, { providerId: "mock-openai", modelId: "mock-model" }]
     });
-    const first = await importCharacter("Manual Order First");
-    const second = await importCharacter("Manual Order Second");
-    const before = await parseJsonResponse(
-      "/api/characters",
-      await fetch(`${baseUrl}/api/characters`)
-    ) as Array<{ id: string; sortOrder: number }>;
-    expect(before.slice(0, 2).map((character) => character.id)).toEqual([second.id, first.id]);
+    const created = await postJson("/api/chats", { title: "Provider Fallback" });
 
-    const originalIds = before.map((character) => character.id);
-    const reversedIds = [...originalIds].reverse();
-    const reorderResponse = await requestJson("/api/characters/reorder", {
-      method: "PATCH",
-      body: { characterIds: reversedIds }
+    const sendResponse = await fetch(`${baseUrl}/api/chats/${created.id}/send`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ content: "fallback-chain-check" })
     });
-    expect(reorderResponse.ok).toBe(true);
-    expect((await reorderResponse.json() as Array<{ id: string }>).map((character) => character.id)).toEqual(reversedIds);
+    const events = (await sendResponse.text()).split("\n\n")
+      .filter((block) => block.startsWith("data: "))
+      .map((block) => JSON.parse(block.slice(6)) as Record<string, unknown>);
 
-    const persisted = await parseJsonResponse(
-      "/api/characters",
-      await fetch(`${baseUrl}/api/characters`)
-    ) as Array<{ id: string }>;
-    expect(persisted.map((character) => character.id)).toEqual(reversedIds);
+    expect(events.find((event) => event.type === "provider_fallback")).toMatchObject({
+      fromProviderId: "failing-openai",
+      toProviderId: "mock-openai",
+      toModel: "mock-model",
+      reason: "[API Error: 500] upstream overloaded"
+    });
+    const reply = db.prepare("SELECT content FROM messages WHERE chat_id = ? AND role = 'assistant'").get(created.id) as { content: string };
+    expect(reply.content).not.toMatch(/^\[Error\]/);
+    expect(reply.content.trim()).not.toBe("");
 
-    const newest = await importCharacter("Manual Order Newest");
-    const afterCreate = await parseJsonResponse(
-      "/api/characters",
-      await fetch(`${baseUrl}/api/characters`)
-    ) as Array<{ id: string }>;
-    expect(afterCreate[0]?.id).toBe(newest.id);
-    expect(afterCreate.slice(1).map((character) => character.id)).toEqual(reversedIds);
+    await updateSettings({ activeProviderId: "mock-openai", streamRetryAttempts: 2, providerFallbacks: [] });
   });
 
-  it("initializes a new chat from the first character's enabled scene defaults", async () => {
-    const primary = await postJson("/api/characters/import", {
-      rawJson: JSON.stringify({
-        spec: "chara_card_v2",
-        spec_version: "2.0",
-        data: {
-          name: "Scene Defaults Primary",
-          first_mes: "Ready.",
-          extensions: {
-            vellium_scene_state: {
-              enabled: true,
-              mood: "watchful",
-              pacing: "fast",
-              intensity: 0.82,
-              variables: {
-                dialogueStyle: "formal",
-                initiative: "88",
-                descriptiveness: "64",
-                unpredictability: "37",
-                emotionalDepth: "79"
-              }
-            }
-          }
-        }
-      })
-    });
-    const secondary = await postJson("/api/characters/import", {
-      rawJson: JSON.stringify({
-        spec: "chara_card_v2",
-        spec_version: "2.0",
-        data: {
-          name: "Scene Defaults Secondary",
-          extensions: {
-            vellium_scene_state: {
-              enabled: true,
-              mood: "secondary-mood",
-              pacing: "slow",
-              intensity: 0.2,
-              variables: {}
-            }
-          }
-        }
-      })
+  it("refuses flagged messages in Moderated mode without saving them", async () => {
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", toolCallingEnabled: false, censorshipMode: "Moderated" });
+    const created = await postJson("/api/chats", { title: "Moderated Chat" });
+
+    const refused = await requestJson(`/api/chats/${created.id}/send`, { method: "POST", body: { content: "say the forbidden-phrase" } });
+    expect(refused.status).toBe(422);
+    expect(await refused.json()).toEqual({
+      error: "Message was flagged by moderation (harassment)",
+      code: "moderation",
+      categories: ["harassment"]
     });
+    expect(db.prepare("SELECT COUNT(*) AS count FROM messages WHERE chat_id = ?").get(created.id)).toEqual({ count: 0 });
 
-    const created = await postJson("/api/chats", {
-      title: "Character Scene Defaults",
-      characterIds: [primary.id, secondary.id]
+    const allowed = await fetch(`${baseUrl}/api/chats/${created.id}/send`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ content: "a friendly hello" })
+    });
+    expect(allowed.ok).toBe(true);
+    await allowed.text();
+    expect(db.prepare("SELECT content FROM messages WHERE chat_id = ? AND role = 'user'").all(created.id)).toEqual([{ content: "a friendly hello" }]);
+
+    const userMessage = db.prepare("SELECT id FROM messages WHERE chat_id = ? AND role = 'user'").get(created.id) as { id: string };
+    const edited = await requestJson(`/api/messages/${userMessage.id}`, { method: "PATCH", body: { content: "now the forbidden-phrase" } });
+    expect(edited.status).toBe(422);
+    expect(db.prepare("SELECT content FROM messages WHERE id = ?").get(userMessage.id)).toEqual({ content: "a friendly hello" });
+
+    // A message stored before Moderated mode was on is checked again when it is resent.
+    db.prepare("UPDATE messages SET deleted = 1 WHERE chat_id = ? AND role = 'assistant'").run(created.id);
+    db.prepare("UPDATE messages SET content = ? WHERE id = ?").run("stored forbidden-phrase", userMessage.id);
+    const resent = await requestJson(`/api/chats/${created.id}/regenerate`, { method: "POST", body: {} });
+    expect(resent.status).toBe(422);
+    expect(await resent.json()).toMatchObject({ code: "moderation" });
+    await updateSettings({ censorshipMode: "Unfiltered" });
+  });
+
+  it("does not send messages to a remote provider for moderation in Full Local Mode", async () => {
+    db.prepare(`
+      INSERT OR REPLACE INTO providers (id, name, base_url, api_key_cipher, full_local_only, provider_type)
+      VALUES (?, ?, ?, ?, 0, 'openai')
+    `).run("remote-openai", "Remote OpenAI", "https://moderation-remote.example.com/v1", "test-key");
+    await updateSettings({ activeProviderId: "remote-openai", activeModel: "mock-model", censorshipMode: "Moderated", fullLocalMode: true });
+    const created = await postJson("/api/chats", { title: "Local Moderation" });
+    const fetchSpy = vi.spyOn(globalThis, "fetch");
+
+    try {
+      const refused = await requestJson(`/api/chats/${created.id}/send`, { method: "POST", body: { content: "private words" } });
+      expect(refused.status).toBe(400);
+      expect(await refused.json()).toEqual({ error: "Moderation endpoint blocked by Full Local Mode", code: "not_configured" });
+      expect(fetchSpy.mock.calls.some(([url]) => String(url).includes("moderation-remote.example.com"))).toBe(false);
+      expect(db.prepare("SELECT COUNT(*) AS count FROM messages WHERE chat_id = ?").get(created.id)).toEqual({ count: 0 });
+    } finally {
+      fetchSpy.mockRestore();
+      await updateSettings({ activeProviderId: "mock-openai", censorshipMode: "Unfiltered", fullLocalMode: false });
+    }
+  });
+
+  it("stores the author's note depth and injects the note that far from the end", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      rpReasoningEnabled: false
     });
-    const sceneState = await parseJsonResponse(
-      `/api/rp/scene-state/${created.id}`,
-      await fetch(`${baseUrl}/api/rp/scene-state/${created.id}`)
+    const created = await postJson("/api/chats", { title: "Author Note Depth" });
+    const branchId = (db.prepare("SELECT id FROM branches WHERE chat_id = ?").get(created.id) as { id: string }).id;
+    const insert = db.prepare(
+      "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, sort_order, created_at) VALUES (?, ?, ?, ?, ?, 1, ?, ?)"
     );
-
-    expect(sceneState).toMatchObject({
-      chatId: created.id,
-      mood: "watchful",
-      pacing: "fast",
-      intensity: 0.82,
-      variables: {
-        dialogueStyle: "formal",
-        initiative: "88",
-        descriptiveness: "64",
-        unpredictability: "37",
-        emotionalDepth: "79"
-      },
-      chatMode: "rp",
-      pureChatMode: false
+    ["First question", "First answer", "Second question", "Second answer"].forEach((content, index) => {
+      insert.run(`depth-${index}-${created.id}`, created.id, branchId, index % 2 === 0 ? "user" : "assistant", content, index + 1, `2026-01-01T00:00:0${index}.000Z`);
     });
-  });
 
-  it("exports multi-character chats with explicit participants and speakers", async () => {
-    await updateSettings({ activeProviderId: null, activeModel: null });
-    const importCharacter = (name: string, greeting: string) => postJson("/api/characters/import", {
-      rawJson: JSON.stringify({
-        spec: "chara_card_v2",
-        spec_version: "2.0",
-        data: { name, first_mes: greeting }
-      })
+    const invalid = await requestJson("/api/rp/author-note", { method: "POST", body: { chatId: created.id, authorNote: "x", depth: -1 } });
+    expect(invalid.status).toBe(400);
+    await postJson("/api/rp/author-note", { chatId: created.id, authorNote: "Keep the lantern lit", depth: 2 });
+    expect(await (await requestJson(`/api/rp/author-note/${created.id}`)).json()).toEqual({ authorNote: "Keep the lantern lit", depth: 2 });
+
+    lastChatTemplateMessages = [];
+    const response = await requestJson(`/api/chats/${created.id}/send`, {
+      method: "POST",
+      body: { content: "single-system-template-check" }
     });
-    const [alice, bob] = await Promise.all([
-      importCharacter("Alice Export", "Alice joined the room."),
-      importCharacter("Bob Export", "Bob joined the room.")
+    expect(response.ok).toBe(true);
+    await response.text();
+    const contents = lastChatTemplateMessages.map((message) => String(message.content || ""));
+    expect(contents.slice(1)).toEqual([
+      "First question",
+      "First answer",
+      "Second question",
+      "[Author's Note: Keep the lantern lit]",
+      "Second answer",
+      "single-system-template-check"
     ]);
-    const created = await postJson("/api/chats", {
-      title: "Multi Character Export",
-      characterIds: [alice.id, bob.id]
-    });
-    const timeline = await postJson(`/api/chats/${created.id}/send`, {
-      content: "Hello, everyone.",
-      userPersona: { name: "Reader" }
+    expect(contents[0]).not.toContain("Keep the lantern lit");
+  });
+
+  it("injects the content policy into the composed prompt only in Filtered mode", async () => {
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", toolCallingEnabled: false, censorshipMode: "Filtered" });
+    try {
+      const created = await postJson("/api/chats", { title: "Censorship Mode" });
+      const send = async () => {
+        lastChatTemplateMessages = [];
+        const response = await requestJson(`/api/chats/${created.id}/send`, {
+          method: "POST",
+          body: { content: "single-system-template-check" }
+        });
+        expect(response.ok).toBe(true);
+        await response.text();
+        return String(lastChatTemplateMessages[0]?.content || "");
+      };
+
+      expect(await send()).toContain("[Content Policy]\nKeep all content PG-13.");
+      await updateSettings({ censorshipMode: "Unfiltered" });
+      expect(await send()).not.toContain("[Content Policy]");
+    } finally {
+      await updateSettings({ censorshipMode: "Unfiltered" });
+    }
+  });
+
+  it("keeps pinned messages in the prompt when history is trimmed", async () => {
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", toolCallingEnabled: false, contextMaxMessages: 2 });
+    try {
+      const created = await postJson("/api/chats", { title: "Pinned History" });
+      const branchId = (db.prepare("SELECT id FROM branches WHERE chat_id = ?").get(created.id) as { id: string }).id;
+      const insert = db.prepare(
+        "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, sort_order, created_at) VALUES (?, ?, ?, ?, ?, 1, ?, ?)"
+      );
+      ["The vault code is 4417", "Noted", "Small talk", "More small talk"].forEach((content, index) => {
+        insert.run(`pin-${index}-${created.id}`, created.id, branchId, index % 2 === 0 ? "user" : "assistant", content, index + 1, `2026-01-01T00:00:0${index}.000Z`);
+      });
+
+      const pinned = await postJson(`/api/messages/pin-0-${created.id}/pin`, {});
+      expect(pinned.timeline[0]).toMatchObject({ id: `pin-0-${created.id}`, pinned: true });
+      expect((await requestJson("/api/messages/missing-message/pin", { method: "POST", body: {} })).status).toBe(404);
+
+      lastChatTemplateMessages = [];
+      const response = await requestJson(`/api/chats/${created.id}/send`, {
+        method: "POST",
+        body: { content: "single-system-template-check" }
+      });
+      expect(response.ok).toBe(true);
+      await response.text();
+      expect(lastChatTemplateMessages.slice(1).map((message) => String(message.content || ""))).toEqual([
+        "The vault code is 4417",
+        "More small talk",
+        "single-system-template-check"
+      ]);
+
+      const unpinned = await postJson(`/api/messages/pin-0-${created.id}/unpin`, {});
+      expect(unpinned.timeline[0].pinned).toBeUndefined();
+    } finally {
+      await updateSettings({ contextMaxMessages: 0 });
+    }
+  });
+
+  it("lets a chat override the settings response language", async () => {
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", toolCallingEnabled: false, responseLanguage: "English" });
+    const created = await postJson("/api/chats", { title: "Response Language" });
+    const send = async () => {
+      lastChatTemplateMessages = [];
+      const response = await requestJson(`/api/chats/${created.id}/send`, {
+        method: "POST",
+        body: { content: "single-system-template-check" }
+      });
+      expect(response.ok).toBe(true);
+      await response.text();
+      return String(lastChatTemplateMessages[0]?.content || "");
+    };
+
+    expect(await send()).not.toContain("Always respond in");
+    const saved = await requestJson(`/api/chats/${created.id}/response-language`, { method: "PUT", body: { responseLanguage: " German " } });
+    expect(await saved.json()).toEqual({ ok: true, responseLanguage: "German" });
+    expect(await send()).toContain("Always respond in German.");
+    const chats = await (await requestJson("/api/chats")).json() as Array<{ id: string; responseLanguage: string }>;
+    expect(chats.find((chat) => chat.id === created.id)?.responseLanguage).toBe("German");
+
+    await requestJson(`/api/chats/${created.id}/response-language`, { method: "PUT", body: { responseLanguage: "" } });
+    expect(await send()).not.toContain("Always respond in");
+    expect((await requestJson("/api/chats/missing-chat/response-language", { method: "PUT", body: { responseLanguage: "French" } })).status).toBe(404);
+  });
+
+  it("uses the persona pinned to a chat over the one sent with the request", async () => {
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", toolCallingEnabled: false });
+    const pinned = await postJson("/api/personas", { name: "Pinned Wanderer", description: "A cartographer with ink-stained hands" });
+    const created = await postJson("/api/chats", { title: "Pinned Persona" });
+
+    const missing = await requestJson(`/api/chats/${created.id}/persona`, { method: "PUT", body: { personaId: "missing-persona" } });
+    expect(missing.status).toBe(404);
+    const pin = await requestJson(`/api/chats/${created.id}/persona`, { method: "PUT", body: { personaId: pinned.id } });
+    expect(await pin.json()).toEqual({ ok: true, personaId: pinned.id });
+    const chats = await (await requestJson("/api/chats")).json() as Array<{ id: string; personaId: string | null }>;
+    expect(chats.find((chat) => chat.id === created.id)?.personaId).toBe(pinned.id);
+
+    lastChatTemplateMessages = [];
+    const response = await requestJson(`/api/chats/${created.id}/send`, {
+      method: "POST",
+      body: { content: "single-system-template-check", userPersona: { name: "Request Persona", description: "Sent by the client" } }
     });
-    const branchId = timeline[0]?.branchId;
-    const response = await fetch(`${baseUrl}/api/chats/${created.id}/export/json?branchId=${encodeURIComponent(branchId)}`);
     expect(response.ok).toBe(true);
+    await response.text();
+    const systemContent = String(lastChatTemplateMessages[0]?.content || "");
+    expect(systemContent).toContain("[User Persona]\nName: Pinned Wanderer\nDescription: A cartographer with ink-stained hands");
+    expect(systemContent).not.toContain("Request Persona");
+
+    await fetch(`${baseUrl}/api/personas/${pinned.id}`, { method: "DELETE" });
+    const after = await (await requestJson("/api/chats")).json() as Array<{ id: string; personaId: string | null }>;
+    expect(after.find((chat) => chat.id === created.id)?.personaId).toBeNull();
+  });
 
-    const exported = await response.json() as {
-      version: number;
-      chat: { characterId: string | null; characterIds: string[] };
-      participants: Array<{ kind: string; name: string; characterId: string | null }>;
-      conversation: {
-        multiCharacter: boolean;
-        branchId: string;
-        messages: Array<{ sequence: number; speakerName: string; characterId: string | null; content: string }>;
-      };
+  it("recalls relevant long-term memories into the prompt and lets them be edited", async () => {
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", toolCallingEnabled: false });
+    const created = await postJson("/api/chats", { title: "Memory Recall" });
+    db.prepare("UPDATE chats SET context_summary = ? WHERE id = ?").run("Latest summary already in context", created.id);
+    const insert = db.prepare("INSERT INTO rp_memory_entries (id, chat_id, role, content, created_at) VALUES (?, ?, ?, ?, ?)");
+    insert.run(`mem-a-${created.id}`, created.id, "summary", "The crew argued about the signal template at the harbour", "2026-01-01T00:00:01.000Z");
+    insert.run(`mem-b-${created.id}`, created.id, "summary", "Mara bought bread at dawn", "2026-01-01T00:00:02.000Z");
+    insert.run(`mem-c-${created.id}`, created.id, "summary", "Latest summary already in context", "2026-01-01T00:00:03.000Z");
+
+    lastChatTemplateMessages = [];
+    const response = await requestJson(`/api/chats/${created.id}/send`, {
+      method: "POST",
+      body: { content: "single-system-template-check" }
+    });
+    expect(response.ok).toBe(true);
+    await response.text();
+    const systemContent = String(lastChatTemplateMessages[0]?.content || "");
+    expect(systemContent).toContain("[Long-term Memory]");
+    expect(systemContent).toContain("- The crew argued about the signal template at the harbour");
+    expect(systemContent).not.toContain("Mara bought bread");
+    expect(systemContent.match(/Latest summary already in context/g)?.length ?? 0).toBeLessThanOrEqual(1);
+
+    const listed = await (await requestJson(`/api/rp/memory/${created.id}`)).json() as Array<{ id: string; content: string }>;
+    expect(listed.map((entry) => entry.id)).toEqual([`mem-a-${created.id}`, `mem-b-${created.id}`, `mem-c-${created.id}`]);
+
+    const updated = await requestJson(`/api/rp/memory/${created.id}/mem-b-${created.id}`, { method: "PATCH", body: { content: "Mara bought a lantern" } });
+    expect(updated.ok).toBe(true);
+    expect((await requestJson(`/api/rp/memory/${created.id}/mem-b-${created.id}`, { method: "PATCH", body: { content: "  " } })).status).toBe(400);
+    const removed = await requestJson(`/api/rp/memory/${created.id}/mem-a-${created.id}`, { method: "DELETE" });
+    expect(removed.ok).toBe(true);
+    expect((await requestJson(`/api/rp/memory/${created.id}/missing`, { method: "DELETE" })).status).toBe(404);
+    const after = await (await requestJson(`/api/rp/memory/${created.id}`)).json() as Array<{ id: string; content: string }>;
+    expect(after.map((entry) => entry.content)).toEqual(["Mara bought a lantern", "Latest summary already in context"]);
+  });
+
+  it("composes chat requests from the per-chat prompt block stack", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      defaultSystemPrompt: "Global default instruction"
+    });
+    const created = await postJson("/api/chats", { title: "Per-chat Blocks" });
+    await postJson("/api/rp/author-note", {
+      chatId: created.id,
+      authorNote: "Disabled author note text"
+    });
+    const saveResponse = await requestJson(`/api/rp/blocks/${created.id}`, {
+      method: "PUT",
+      body: {
+        blocks: [
+          { id: "chat-system", kind: "system", enabled: true, order: 1, content: "Per-chat system rule" },
+          { id: "chat-note", kind: "author_note", enabled: false, order: 2, content: "" },
+          { id: "chat-history", kind: "history", enabled: true, order: 3, content: "" },
+          { id: "chat-late", kind: "jailbreak", enabled: true, order: 4, content: "After-history nudge" }
+        ]
+      }
+    });
+    expect(saveResponse.ok).toBe(true);
+
+    lastChatTemplateMessages = [];
+    const sendResponse = await requestJson(`/api/chats/${created.id}/send`, {
+      method: "POST",
+      body: { content: "per-chat-blocks-check" }
+    });
+    expect(sendResponse.ok).toBe(true);
+    await sendResponse.text();
+
+    const systemContent = String(lastChatTemplateMessages[0]?.content || "");
+    expect(lastChatTemplateMessages[0]?.role).toBe("system");
+    expect(systemContent).toContain("Per-chat system rule");
+    expect(systemContent).not.toContain("Global default instruction");
+    expect(systemContent).not.toContain("Never break character");
+    expect(systemContent).not.toContain("Disabled author note text");
+    expect(systemContent.indexOf("[Post-History Instructions]")).toBeGreaterThan(systemContent.indexOf("Per-chat system rule"));
+    expect(systemContent).toContain("[Post-History Instructions]\nAfter-history nudge");
+  });
+
+  it("uses a chat's system prompt override unless the active character brings its own", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      defaultSystemPrompt: "Global default instruction"
+    });
+    const created = await postJson("/api/chats", { title: "System Prompt Override" });
+    const sendAndReadSystem = async () => {
+      lastChatTemplateMessages = [];
+      const response = await requestJson(`/api/chats/${created.id}/send`, {
+        method: "POST",
+        body: { content: "single-system-template-check" }
+      });
+      expect(response.ok).toBe(true);
+      await response.text();
+      return String(lastChatTemplateMessages.find((message) => message.role === "system")?.content || "");
     };
-    expect(exported.version).toBe(2);
-    expect(exported.chat).toMatchObject({
-      characterId: alice.id,
-      characterIds: [alice.id, bob.id]
+
+    const missing = await requestJson("/api/chats/missing-chat/system-prompt", { method: "PUT", body: { systemPrompt: "x" } });
+    expect(missing.status).toBe(404);
+
+    const saved = await requestJson(`/api/chats/${created.id}/system-prompt`, {
+      method: "PUT",
+      body: { systemPrompt: "  Chat-level narrator rule  " }
     });
-    expect(exported.participants).toEqual(expect.arrayContaining([
-      expect.objectContaining({ kind: "character", name: "Alice Export", characterId: alice.id }),
-      expect.objectContaining({ kind: "character", name: "Bob Export", characterId: bob.id }),
-      expect.objectContaining({ kind: "user", name: "Reader", characterId: null })
-    ]));
-    expect(exported.conversation).toMatchObject({ multiCharacter: true, branchId });
-    expect(exported.conversation.messages).toEqual(expect.arrayContaining([
-      expect.objectContaining({ sequence: 1, speakerName: "Alice Export", characterId: alice.id, content: "Alice joined the room." }),
-      expect.objectContaining({ speakerName: "Reader", characterId: null, content: "Hello, everyone." })
-    ]));
+    expect(await parseJsonResponse(`/api/chats/${created.id}/system-prompt`, saved)).toEqual({ ok: true, systemPrompt: "Chat-level narrator rule" });
+    const listed = await (await requestJson("/api/chats")).json() as Array<{ id: string; systemPrompt?: string }>;
+    expect(listed.find((chat) => chat.id === created.id)?.systemPrompt).toBe("Chat-level narrator rule");
+
+    let systemContent = await sendAndReadSystem();
+    expect(systemContent).toContain("Chat-level narrator rule");
+    expect(systemContent).not.toContain("Global default instruction");
+
+    const character = await postJson("/api/characters/import", {
+      rawJson: JSON.stringify({
+        spec: "chara_card_v2",
+        spec_version: "2.0",
+        data: { name: "Card Prompt Keeper", first_mes: "Hello", system_prompt: "Card-level keeper rule" }
+      })
+    });
+    const attached = await requestJson(`/api/chats/${created.id}/characters`, {
+      method: "PATCH",
+      body: { characterIds: [character.id] }
+    });
+    expect(attached.ok).toBe(true);
+    systemContent = await sendAndReadSystem();
+    expect(systemContent).toContain("Card-level keeper rule");
+    expect(systemContent).not.toContain("Chat-level narrator rule");
+
+    await requestJson(`/api/chats/${created.id}/characters`, { method: "PATCH", body: { characterIds: [] } });
+    await requestJson(`/api/chats/${created.id}/system-prompt`, { method: "PUT", body: { systemPrompt: "" } });
+    expect(db.prepare("SELECT system_prompt FROM chats WHERE id = ?").get(created.id)).toEqual({ system_prompt: null });
+    systemContent = await sendAndReadSystem();
+    expect(systemContent).toContain("Global default instruction");
   });
 
-  it("translates messages and synthesizes TTS against a local mock provider", async () => {
+  it("validates a chat's sampler override and sends it over the global sampler", async () => {
     await updateSettings({
-      activeProviderId: null,
-      activeModel: null,
-      translateProviderId: "mock-openai",
-      translateModel: "mock-model",
-      ttsBaseUrl: mockProviderBaseUrl,
-      ttsApiKey: "test-key",
-      ttsModel: "tts-1",
-      ttsVoice: "alloy"
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      samplerConfig: { temperature: 0.9, topP: 1, maxTokens: 2048, stop: [] }
     });
+    const created = await postJson("/api/chats", { title: "Sampler Override" });
+    const send = async () => {
+      lastSamplerRequestBody = {};
+      const response = await requestJson(`/api/chats/${created.id}/send`, {
+        method: "POST",
+        body: { content: "chat-sampler-override-check" }
+      });
+      expect(response.ok).toBe(true);
+      await response.text();
+      return lastSamplerRequestBody;
+    };
 
-    const created = await postJson("/api/chats", { title: "Media Chat" });
-    const timeline = await postJson(`/api/chats/${created.id}/send`, {
-      content: "Need media handlers"
+    const saved = await requestJson(`/api/chats/${created.id}/sampler`, {
+      method: "PATCH",
+      body: { samplerConfig: { temperature: 9, topP: "0.5", maxTokens: 300.7, presencePenalty: "loud", stop: ["\nUser:", ""], bogus: true } }
+    });
+    expect(await parseJsonResponse(`/api/chats/${created.id}/sampler`, saved)).toEqual({
+      ok: true,
+      samplerConfig: { temperature: 5, topP: 0.5, maxTokens: 300, stop: ["\nUser:"] }
     });
-    const userMessageId = timeline[0]?.id;
-    expect(userMessageId).toEqual(expect.any(String));
 
-    const translated = await postJson(`/api/chats/messages/${userMessageId}/translate`, {
-      targetLanguage: "German"
+    let body = await send();
+    expect(body).toMatchObject({ temperature: 5, top_p: 0.5, max_tokens: 300, stop: ["\nUser:"] });
+
+    const cleared = await requestJson(`/api/chats/${created.id}/sampler`, { method: "PATCH", body: { samplerConfig: null } });
+    expect(await parseJsonResponse(`/api/chats/${created.id}/sampler`, cleared)).toEqual({ ok: true, samplerConfig: null });
+    expect(db.prepare("SELECT sampler_config FROM chats WHERE id = ?").get(created.id)).toEqual({ sampler_config: null });
+    body = await send();
+    expect(body).toMatchObject({ temperature: 0.9, top_p: 1, max_tokens: 2048 });
+    expect(body).not.toHaveProperty("stop");
+  });
+
+  it("raises sampling temperature with scene intensity", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      samplerConfig: { temperature: 0.9, topP: 1, maxTokens: 2048, stop: [] }
     });
-    expect(translated).toEqual({ translation: "MOCK RESPONSE" });
+    const created = await postJson("/api/chats", { title: "Scene Intensity" });
+    await postJson("/api/rp/scene-state", { chatId: created.id, mood: "tense", pacing: "fast", intensity: 1, variables: {} });
 
-    const audioResponse = await fetch(`${baseUrl}/api/chats/messages/${userMessageId}/tts`, {
-      method: "POST"
+    lastSamplerRequestBody = {};
+    const response = await requestJson(`/api/chats/${created.id}/send`, {
+      method: "POST",
+      body: { content: "chat-sampler-override-check" }
     });
-    expect(audioResponse.ok).toBe(true);
-    expect(audioResponse.headers.get("content-type")).toContain("audio/mpeg");
-    const audioBuffer = Buffer.from(await audioResponse.arrayBuffer());
-    expect(audioBuffer.toString("utf-8")).toBe("FAKE_MP3_DATA");
+    expect(response.ok).toBe(true);
+    await response.text();
+    expect(lastSamplerRequestBody.temperature).toBe(1.1);
+  });
 
-    const realtimeResponse = await fetch(`${baseUrl}/api/chats/tts/realtime`, {
+  it("keeps streamed reasoning out of the reply and flags a reasoning-only turn", async () => {
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", toolCallingEnabled: false });
+    const created = await postJson("/api/chats", { title: "Reasoning Only" });
+    const response = await requestJson(`/api/chats/${created.id}/send`, {
       method: "POST",
-      headers: { "Content-Type": "application/json" },
-      body: JSON.stringify({ input: `${"First phrase ".repeat(20)}. ${"Second phrase ".repeat(20)}!` })
+      body: { content: "reasoning-only-stream-check" }
     });
-    expect(realtimeResponse.ok).toBe(true);
-    expect(realtimeResponse.headers.get("content-type")).toContain("application/x-ndjson");
-    const realtimeEvents = (await realtimeResponse.text()).trim().split("\n").map((line) => JSON.parse(line));
-    const audioEvents = realtimeEvents.filter((event) => event.type === "audio");
-    expect(audioEvents).toHaveLength(2);
-    expect(Buffer.from(audioEvents[0].audioBase64, "base64").toString("utf8")).toBe("PCM_ONE");
-    expect(audioEvents[0]).toMatchObject({ contentType: "audio/pcm", format: "pcm", sampleRate: 24_000 });
-    expect(lastTtsRequestBody).toMatchObject({ response_format: "pcm", stream_format: "sse" });
-    expect(realtimeEvents.at(-1)).toMatchObject({ type: "done", count: audioEvents.length });
+    expect(response.ok).toBe(true);
+    const events = (await response.text())
+      .split("\n")
+      .filter((line) => line.startsWith("data: "))
+      .map((line) => JSON.parse(line.slice(6)) as { type: string; phase?: string; result?: string; delta?: string });
+    expect(events.filter((event) => event.type === "delta")).toEqual([]);
+    expect(events.find((event) => event.type === "tool" && event.phase === "done")?.result).toBe("Weighing how the keeper would answer.");
+
+    const rows = db.prepare("SELECT role, content FROM messages WHERE chat_id = ? AND role != 'user' ORDER BY sort_order")
+      .all(created.id) as Array<{ role: string; content: string }>;
+    expect(rows.map((row) => row.role)).toEqual(["assistant", "tool"]);
+    expect(rows[0].content).toBe("[Error] The model returned reasoning but no visible reply");
+    expect(rows[1].content).toContain("Weighing how the keeper would answer.");
+  });
+
+  it("applies a chat's style preset to the system prompt on send", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      rpReasoningEnabled: false
+    });
+    const presets = await (await requestJson("/api/rp/presets")).json() as Array<{ id: string; name: string; style: unknown }>;
+    expect(presets.find((preset) => preset.id === "terse")).toMatchObject({ name: "Terse", style: { paragraphLength: "short" } });
+
+    const created = await postJson("/api/chats", { title: "Preset Style" });
+    const applied = await postJson("/api/rp/apply-preset", { chatId: created.id, presetId: "terse" });
+    expect(applied).toMatchObject({ ok: true, presetId: "terse", sceneState: { pacing: "fast" } });
+    expect(await (await requestJson(`/api/chats/${created.id}/preset`)).json()).toEqual({ presetId: "terse" });
+
+    lastChatTemplateMessages = [];
+    const response = await requestJson(`/api/chats/${created.id}/send`, {
+      method: "POST",
+      body: { content: "single-system-template-check" }
+    });
+    expect(response.ok).toBe(true);
+    await response.text();
+    const systemContent = String(lastChatTemplateMessages.find((message) => message.role === "system")?.content || "");
+    expect(systemContent).toContain("[Style Preset: Terse]");
+    expect(systemContent).toContain("Keep paragraphs short: one to three sentences each.");
+  });
+
+  it("auto-titles a chat from its first exchange with the active model", async () => {
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", fullLocalMode: false });
+    const created = await postJson("/api/chats", { title: "New Chat" });
+    const empty = await requestJson(`/api/chats/${created.id}/autotitle`, { method: "POST", body: {} });
+    expect(empty.status).toBe(400);
+
+    const branchId = (db.prepare("SELECT id FROM branches WHERE chat_id = ?").get(created.id) as { id: string }).id;
+    const insert = db.prepare(
+      "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, sort_order, created_at) VALUES (?, ?, ?, ?, ?, 1, ?, ?)"
+    );
+    insert.run(`title-u-${created.id}`, created.id, branchId, "user", "How do lighthouses work?", 1, "2026-01-01T00:00:00.000Z");
+    insert.run(`title-a-${created.id}`, created.id, branchId, "assistant", "A lamp and a lens.", 2, "2026-01-01T00:00:01.000Z");
+
+    const titled = await postJson(`/api/chats/${created.id}/autotitle`, {});
+    expect(titled).toEqual({ ok: true, title: "MOCK RESPONSE" });
+    const row = db.prepare("SELECT title FROM chats WHERE id = ?").get(created.id) as { title: string };
+    expect(row.title).toBe("MOCK RESPONSE");
+  });
+
+  it("refuses one-shot completions on a local-only provider that points at a remote URL", async () => {
+    db.prepare(
+      "INSERT OR REPLACE INTO providers (id, name, base_url, api_key_cipher, full_local_only, provider_type) VALUES (?, ?, ?, ?, 1, 'openai')"
+    ).run("remote-local-only", "Remote Local Only", "https://example.com/v1", "test-key");
+    await updateSettings({ activeProviderId: "remote-local-only", activeModel: "remote-model", fullLocalMode: false });
+    const created = await postJson("/api/chats", { title: "New Chat" });
+    const branchId = (db.prepare("SELECT id FROM branches WHERE chat_id = ?").get(created.id) as { id: string }).id;
+    db.prepare(
+      "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, sort_order, created_at) VALUES (?, ?, ?, 'user', ?, 1, 1, ?)"
+    ).run(`local-only-u-${created.id}`, created.id, branchId, "Name this chat", "2026-01-01T00:00:00.000Z");
+
+    const blocked = await requestJson(`/api/chats/${created.id}/autotitle`, { method: "POST", body: {} });
+    expect(blocked.status).toBe(400);
+    expect(await blocked.json()).toEqual({ error: "Provider requires localhost endpoint", code: "not_configured" });
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model" });
+  });
+
+  it("searches message content across chats and keeps the index in sync with edits and deletes", async () => {
+    const created = await postJson("/api/chats", { title: "Searchable Saga" });
+    const branchId = (db.prepare("SELECT id FROM branches WHERE chat_id = ?").get(created.id) as { id: string }).id;
+    const insert = db.prepare(
+      "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, sort_order, created_at) VALUES (?, ?, ?, ?, ?, 1, ?, ?)"
+    );
+    insert.run(`search-1-${created.id}`, created.id, branchId, "user", "Where did the quillfeather crown go?", 1, "2026-02-01T00:00:00.000Z");
+    insert.run(`search-2-${created.id}`, created.id, branchId, "assistant", "The crown sank with the barge.", 2, "2026-02-01T00:00:01.000Z");
+
+    const search = async (q: string) => {
+      const path = `/api/chats/search?q=${encodeURIComponent(q)}`;
+      return parseJsonResponse(path, await fetch(`${baseUrl}${path}`));
+    };
+
+    const hits = await search("quillfeather crown");
+    expect(hits).toHaveLength(1);
+    expect(hits[0]).toMatchObject({
+      chatTitle: "Searchable Saga",
+      message: { id: `search-1-${created.id}`, chatId: created.id, role: "user" }
+    });
+    expect(hits[0].snippet).toContain("quillfeather crown");
+
+    await requestJson(`/api/messages/search-2-${created.id}`, { method: "PATCH", body: { content: "The quillfeather barge drifted east." } });
+    expect((await search("quillfeather")).map((hit: { message: { id: string } }) => hit.message.id).sort())
+      .toEqual([`search-1-${created.id}`, `search-2-${created.id}`].sort());
+    expect(await search("sank")).toEqual([]);
+
+    await requestJson(`/api/messages/search-1-${created.id}`, { method: "DELETE" });
+    expect((await search("quillfeather")).map((hit: { message: { id: string } }) => hit.message.id)).toEqual([`search-2-${created.id}`]);
+
+    const missing = await fetch(`${baseUrl}/api/chats/search?q=`);
+    expect(missing.status).toBe(400);
+  });
+
+  it("trashes a chat and purges it together with its branches, messages, scene state, memory and bindings", async () => {
+    const created = await postJson("/api/chats", { title: "Doomed Chat" });
+    const survivor = await postJson("/api/chats", { title: "Surviving Chat" });
+    for (const chatId of [created.id, survivor.id]) {
+      await postJson("/api/rp/scene-state", { chatId, chatMode: "rp", mood: "calm" });
+      await postJson("/api/rp/author-note", { chatId, authorNote: "Note" });
+      db.prepare("INSERT INTO chat_rag_bindings (chat_id, enabled, collection_ids, updated_at) VALUES (?, 1, '[]', ?)")
+        .run(chatId, new Date().toISOString());
+    }
+    const rootBranchId = (db.prepare("SELECT id FROM branches WHERE chat_id = ?").get(created.id) as { id: string }).id;
+    db.prepare(
+      "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, created_at) VALUES (?, ?, ?, 'user', 'bye', 1, ?)"
+    ).run(`msg-${created.id}`, created.id, rootBranchId, new Date().toISOString());
+
+    const deleted = await parseJsonResponse(
+      `/api/chats/${created.id}`,
+      await requestJson(`/api/chats/${created.id}`, { method: "DELETE" })
+    );
+    expect(deleted).toEqual({ ok: true, deleted: true });
+    const listed = await (await requestJson("/api/chats")).json() as Array<{ id: string }>;
+    expect(listed.map((chat) => chat.id)).not.toContain(created.id);
+    const trash = await (await requestJson("/api/chats/trash")).json() as Array<{ id: string; deletedAt: string }>;
+    expect(trash.find((chat) => chat.id === created.id)?.deletedAt).toBeTruthy();
+    expect((await requestJson(`/api/chats/${survivor.id}/purge`, { method: "DELETE" })).status).toBe(404);
+
+    const purged = await parseJsonResponse(
+      `/api/chats/${created.id}/purge`,
+      await requestJson(`/api/chats/${created.id}/purge`, { method: "DELETE" })
+    );
+    expect(purged).toEqual({ ok: true, deleted: true });
+
+    const countRows = (table: string, chatId: string) =>
+      (db.prepare(`SELECT COUNT(*) AS count FROM ${table} WHERE chat_id = ?`).get(chatId) as { count: number }).count;
+    for (const table of ["messages", "branches", "rp_scene_state", "rp_memory_entries", "chat_rag_bindings"]) {
+      expect(countRows(table, created.id)).toBe(0);
+    }
+    expect(db.prepare("SELECT id FROM chats WHERE id = ?").get(created.id)).toBeUndefined();
+    expect(countRows("rp_scene_state", survivor.id)).toBe(1);
+    expect(countRows("chat_rag_bindings", survivor.id)).toBe(1);
+
+    const again = await parseJsonResponse(
+      `/api/chats/${created.id}`,
+      await requestJson(`/api/chats/${created.id}`, { method: "DELETE" })
+    );
+    expect(again).toEqual({ ok: true, deleted: false });
+  });
+
+  it("keeps swiped replies as alternatives and switches the selected one", async () => {
+    await updateSettings({ activeProviderId: "", activeModel: "" });
+    const empty = await postJson("/api/chats", { title: "No Swipes" });
+    expect((await requestJson(`/api/chats/${empty.id}/swipe`, { method: "POST", body: {} })).status).toBe(409);
+
+    const created = await postJson("/api/chats", { title: "Swipe Chat" });
+    const [, firstReply] = await postJson(`/api/chats/${created.id}/send`, { content: "Swipe me" });
+    const swiped = await postJson(`/api/chats/${created.id}/swipe`, {});
+    expect(swiped).toHaveLength(2);
+    expect(swiped[1]).toMatchObject({ role: "assistant", swipeIndex: 2, swipeCount: 2 });
+    expect(swiped[1].id).not.toBe(firstReply.id);
+    expect(db.prepare("SELECT deleted FROM messages WHERE id = ?").get(firstReply.id)).toEqual({ deleted: 2 });
+
+    const selected = await postJson(`/api/messages/${firstReply.id}/select-swipe`, {});
+    expect(selected.timeline).toHaveLength(2);
+    expect(selected.timeline[1]).toMatchObject({ id: firstReply.id, swipeIndex: 1, swipeCount: 2 });
+    const trash = await (await requestJson(`/api/chats/${created.id}/deleted-messages`)).json();
+    expect(trash).toEqual([]);
+    expect((await requestJson("/api/messages/missing-swipe/select-swipe", { method: "POST", body: {} })).status).toBe(404);
+  });
+
+  it("pages a long timeline backwards with a before cursor", async () => {
+    const created = await postJson("/api/chats", { title: "Paged Chat" });
+    const branchId = (db.prepare("SELECT id FROM branches WHERE chat_id = ?").get(created.id) as { id: string }).id;
+    const insert = db.prepare(
+      "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, created_at, sort_order) VALUES (?, ?, ?, 'user', ?, 1, ?, ?)"
+    );
+    // Shared timestamps make the id tie-breaker part of the cursor.
+    for (let index = 1; index <= 7; index += 1) {
+      insert.run(`page-${index}-${created.id}`, created.id, branchId, `m${index}`, "2026-01-01T00:00:00.000Z", index);
+    }
+    const contents = (page: { messages: Array<{ content: string }> }) => page.messages.map((message) => message.content);
+
+    const newest = await (await requestJson(`/api/chats/${created.id}/timeline?limit=3`)).json();
+    expect(contents(newest)).toEqual(["m5", "m6", "m7"]);
+    expect(newest.hasMore).toBe(true);
+    const middle = await (await requestJson(`/api/chats/${created.id}/timeline?limit=3&before=${newest.messages[0].id}`)).json();
+    expect(contents(middle)).toEqual(["m2", "m3", "m4"]);
+    const oldest = await (await requestJson(`/api/chats/${created.id}/timeline?limit=3&before=${middle.messages[0].id}`)).json();
+    expect(oldest).toMatchObject({ hasMore: false });
+    expect(contents(oldest)).toEqual(["m1"]);
+
+    const full = await (await requestJson(`/api/chats/${created.id}/timeline`)).json();
+    expect(full).toHaveLength(7);
+    expect((await requestJson(`/api/chats/${created.id}/timeline?limit=0`)).status).toBe(400);
+    expect((await requestJson(`/api/chats/${created.id}/timeline?before=missing`)).status).toBe(404);
+  });
+
+  it("restores trashed chats and deleted messages, and purges deleted messages for good", async () => {
+    const created = await postJson("/api/chats", { title: "Undo Chat" });
+    const branchId = (db.prepare("SELECT id FROM branches WHERE chat_id = ?").get(created.id) as { id: string }).id;
+    const insert = db.prepare(
+      "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, parent_id, created_at, sort_order) VALUES (?, ?, ?, ?, ?, 1, ?, ?, ?)"
+    );
+    insert.run(`undo-1-${created.id}`, created.id, branchId, "user", "first", null, "2026-01-01T00:00:01.000Z", 1);
+    insert.run(`undo-2-${created.id}`, created.id, branchId, "assistant", "second", `undo-1-${created.id}`, "2026-01-01T00:00:02.000Z", 2);
+    insert.run(`undo-tool-${created.id}`, created.id, branchId, "tool", "{}", `undo-2-${created.id}`, "2026-01-01T00:00:02.500Z", 3);
+    insert.run(`undo-3-${created.id}`, created.id, branchId, "user", "third", `undo-2-${created.id}`, "2026-01-01T00:00:03.000Z", 4);
+
+    await requestJson(`/api/messages/undo-2-${created.id}`, { method: "DELETE" });
+    const trashed = await (await requestJson(`/api/chats/${created.id}/deleted-messages`)).json() as Array<{ id: string }>;
+    expect(trashed.map((message) => message.id)).toEqual([`undo-2-${created.id}`]);
+
+    const restored = await (await requestJson(`/api/messages/undo-2-${created.id}/restore`, { method: "POST" })).json();
+    expect(restored.timeline.map((message: { content: string }) => message.content)).toEqual(["first", "second", "{}", "third"]);
+    expect((await requestJson(`/api/messages/undo-2-${created.id}/restore`, { method: "POST" })).status).toBe(404);
+
+    await requestJson(`/api/messages/undo-3-${created.id}`, { method: "DELETE" });
+    const purged = await (await requestJson(`/api/chats/${created.id}/deleted-messages`, { method: "DELETE" })).json();
+    expect(purged).toEqual({ ok: true, purged: 1 });
+    expect(db.prepare("SELECT id FROM messages WHERE id = ?").get(`undo-3-${created.id}`)).toBeUndefined();
+
+    await requestJson(`/api/chats/${created.id}`, { method: "DELETE" });
+    expect((await (await requestJson(`/api/chats/${created.id}/restore`, { method: "POST" })).json())).toEqual({ ok: true });
+    const listed = await (await requestJson("/api/chats")).json() as Array<{ id: string }>;
+    expect(listed.map((chat) => chat.id)).toContain(created.id);
+    expect((await requestJson(`/api/chats/${created.id}/restore`, { method: "POST" })).status).toBe(404);
+  });
+
+  it("changes the account password only when the current password matches", async () => {
+    await postJson("/api/account/create", { password: "first-pass" });
+    const denied = await requestJson("/api/account/change-password", {
+      method: "POST",
+      body: { oldPassword: "wrong", newPassword: "second-pass" }
+    });
+    expect(denied.status).toBe(403);
+    expect(await postJson("/api/account/unlock", { password: "first-pass" })).toBe(true);
+
+    expect(await postJson("/api/account/change-password", { oldPassword: "first-pass", newPassword: "second-pass" }))
+      .toEqual({ ok: true });
+    expect(await postJson("/api/account/unlock", { password: "first-pass" })).toBe(false);
+    expect(await postJson("/api/account/unlock", { password: "second-pass" })).toBe(true);
+
+    await postJson("/api/account/lock", {});
+    const lockedStatus = await (await fetch(`${baseUrl}/api/account/status`)).json();
+    expect(lockedStatus).toMatchObject({ hasAccount: true, locked: true, autoLockMinutes: 15 });
+    const blocked = await requestJson("/api/backup/export", { method: "POST", body: { password: "x" } });
+    expect(blocked.status).toBe(423);
+    expect(await blocked.json()).toMatchObject({ code: "locked" });
+    const keysBlocked = await requestJson("/api/settings/export", { method: "POST", body: { includeKeys: true } });
+    expect(keysBlocked.status).toBe(423);
+    for (const path of ["/api/chats", "/api/settings", "/api/providers", "/api/avatars/missing.png"]) {
+      const response = await fetch(`${baseUrl}${path}`);
+      expect(response.status, path).toBe(423);
+      expect(await response.json()).toMatchObject({ code: "locked" });
+    }
+    expect((await requestJson("/api/chats", { method: "POST", body: { title: "While Locked" } })).status).toBe(423);
+    expect((await requestJson("/api/account/create", { method: "POST", body: { password: "takeover" } })).status).toBe(423);
+    expect((await requestJson("/api/account/rotate-recovery", { method: "POST", body: { newRecoveryKey: "takeover" } })).status)
+      .toBe(423);
+    expect((await fetch(`${baseUrl}/api/health`)).status).toBe(200);
+    expect((await fetch(`${baseUrl}/api/status`)).status).toBe(423);
+
+    expect(await postJson("/api/account/unlock", { password: "second-pass" })).toBe(true);
+    expect((await fetch(`${baseUrl}/api/chats`)).status).toBe(200);
+    expect(await (await fetch(`${baseUrl}/api/account/status`)).json()).toMatchObject({ locked: false });
+  });
+
+  it("exports an encrypted backup and restores it over later changes", async () => {
+    const kept = await postJson("/api/chats", { title: "Backed Up Chat" });
+    const avatarPath = join(dataDir, "avatars", "backed-up-avatar.png");
+    writeFileSync(avatarPath, Buffer.from([137, 80, 78, 71]));
+    const exported = await postJson("/api/backup/export", { password: "backup-pass" });
+    expect(exported.path).toBe(join(dataDir, "backups", exported.fileName));
+    expect(readFileSync(exported.path, "utf8")).not.toContain("Backed Up Chat");
+    rmSync(avatarPath);
+
+    const later = await postJson("/api/chats", { title: "Created After Backup" });
+    const wrong = await requestJson("/api/backup/import", {
+      method: "POST",
+      body: { path: exported.fileName, password: "nope" }
+    });
+    expect(wrong.status).toBe(400);
+    const outside = await requestJson("/api/backup/import", {
+      method: "POST",
+      body: { path: "../vellum.db", password: "backup-pass" }
+    });
+    expect(outside.status).toBe(404);
+
+    const uploaded = await fetch(`${baseUrl}/api/backup/upload`, {
+      method: "POST",
+      headers: { "Content-Type": "application/octet-stream" },
+      body: readFileSync(exported.path)
+    });
+    expect(uploaded.status).toBe(200);
+    const { fileName } = await uploaded.json() as { fileName: string };
+    expect(fileName).not.toBe(exported.fileName);
+
+    const restored = await postJson("/api/backup/import", { path: fileName, password: "backup-pass" });
+    expect(restored.ok).toBe(true);
+    expect(restored.files).toBeGreaterThanOrEqual(1);
+    expect(readFileSync(avatarPath)).toEqual(Buffer.from([137, 80, 78, 71]));
+    expect(db.prepare("SELECT id FROM chats WHERE id = ?").get(kept.id)).toBeTruthy();
+    expect(db.prepare("SELECT id FROM chats WHERE id = ?").get(later.id)).toBeUndefined();
+
+    const listed = await (await fetch(`${baseUrl}/api/backup`)).json() as { fileNames: string[] };
+    expect(listed.fileNames).toContain(exported.fileName);
+  });
+
+  it("folds saved scene state and scene variables into the chat request", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      defaultSystemPrompt: "Scene base instruction"
+    });
+    const created = await postJson("/api/chats", { title: "Scene State Chat" });
+    await postJson("/api/rp/scene-state", {
+      chatId: created.id,
+      chatMode: "rp",
+      mood: "tense",
+      pacing: "slow",
+      intensity: 0.9,
+      variables: { location: "the flooded archive" }
+    });
+
+    lastChatTemplateMessages = [];
+    const sendResponse = await requestJson(`/api/chats/${created.id}/send`, {
+      method: "POST",
+      body: { content: "We search {{location}} for {{unknownMacro}}" }
+    });
+    expect(sendResponse.ok).toBe(true);
+    await sendResponse.text();
+
+    const systemContent = String(lastChatTemplateMessages[0]?.content || "");
+    expect(systemContent).toContain("Current mood: tense");
+    expect(systemContent).toContain("Pacing: slow");
+    expect(systemContent).toContain("Intensity: ");
+    expect(systemContent.indexOf("Scene base instruction")).toBeLessThan(systemContent.indexOf("[Scene State]"));
+    expect(lastChatTemplateMessages.filter((message) => message.role === "user").at(-1)?.content)
+      .toBe("We search the flooded archive for {{unknownMacro}}");
+  });
+
+  it("persists manual character ordering and prepends new characters", async () => {
+    const importCharacter = (name: string) => postJson("/api/characters/import", {
+      rawJson: JSON.stringify({
+        spec: "chara_card_v2",
+        spec_version: "2.0",
+        data: { name, first_mes: "Hello" }
+      })
+    });
+    const first = await importCharacter("Manual Order First");
+    const second = await importCharacter("Manual Order Second");
+    const before = await parseJsonResponse(
+      "/api/characters",
+      await fetch(`${baseUrl}/api/characters`)
+    ) as Array<{ id: string; sortOrder: number }>;
+    expect(before.slice(0, 2).map((character) => character.id)).toEqual([second.id, first.id]);
+
+    const originalIds = before.map((character) => character.id);
+    const reversedIds = [...originalIds].reverse();
+    const reorderResponse = await requestJson("/api/characters/reorder", {
+      method: "PATCH",
+      body: { characterIds: reversedIds }
+    });
+    expect(reorderResponse.ok).toBe(true);
+    expect((await reorderResponse.json() as Array<{ id: string }>).map((character) => character.id)).toEqual(reversedIds);
+
+    const persisted = await parseJsonResponse(
+      "/api/characters",
+      await fetch(`${baseUrl}/api/characters`)
+    ) as Array<{ id: string }>;
+    expect(persisted.map((character) => character.id)).toEqual(reversedIds);
+
+    const newest = await importCharacter("Manual Order Newest");
+    const afterCreate = await parseJsonResponse(
+      "/api/characters",
+      await fetch(`${baseUrl}/api/characters`)
+    ) as Array<{ id: string }>;
+    expect(afterCreate[0]?.id).toBe(newest.id);
+    expect(afterCreate.slice(1).map((character) => character.id)).toEqual(reversedIds);
+  });
+
+  it("expands greeting macros with the character and default persona when a chat starts", async () => {
+    const character = await postJson("/api/characters/import", {
+      rawJson: JSON.stringify({
+        spec: "chara_card_v2",
+        spec_version: "2.0",
+        data: { name: "Macro Greeter", first_mes: "Welcome, {{user}}. I am {{char}}. {{unknown_macro}}" }
+      })
+    });
+    const persona = await postJson("/api/personas", { name: "Macro Reader", isDefault: true });
+
+    const created = await postJson("/api/chats", { title: "Macro Greeting", characterId: character.id });
+    const timeline = await parseJsonResponse(
+      `/api/chats/${created.id}/timeline`,
+      await fetch(`${baseUrl}/api/chats/${created.id}/timeline`)
+    );
+    expect(timeline[0]).toMatchObject({
+      role: "assistant",
+      content: "Welcome, Macro Reader. I am Macro Greeter. {{unknown_macro}}"
+    });
+
+    await fetch(`${baseUrl}/api/personas/${persona.id}`, { method: "DELETE" });
+  });
+
+  it("imports a V2 card fixture with its character_book, greetings and system prompt", async () => {
+    const rawJson = readFileSync(new URL("./fixtures/character-card-v2.json", import.meta.url), "utf8");
+    const character = await postJson("/api/characters/import", { rawJson });
+    expect(character).toMatchObject({
+      name: "Harbor Keeper",
+      greeting: "Lamp's lit. Who's asking?",
+      systemPrompt: "Stay in character as the harbor keeper.",
+      alternateGreetings: ["Another ship on the rocks, then?", "You're soaked. Come in."],
+      postHistoryInstructions: "Keep replies under three paragraphs."
+    });
+    expect(character.lorebookId).toEqual(expect.any(String));
+
+    const lorebook = await parseJsonResponse(
+      `/api/lorebooks/${character.lorebookId}`,
+      await fetch(`${baseUrl}/api/lorebooks/${character.lorebookId}`)
+    );
+    expect(lorebook).toMatchObject({ name: "Harbor Lore", sourceCharacterId: character.id });
+    expect(lorebook.entries).toEqual([
+      expect.objectContaining({ id: "0", name: "Lighthouse", keys: ["lighthouse", "lamp"], position: "before_char", insertionOrder: 10 }),
+      expect.objectContaining({ id: "1", name: "The Teeth", secondaryKeys: ["storm"], selective: true, position: "after_char" })
+    ]);
+
+    const exported = await fetch(`${baseUrl}/api/characters/${character.id}/export/json`);
+    expect(await exported.text()).toBe(rawJson);
+  });
+
+  it("imports a character card embedded in a PNG tEXt chunk and exports it back as a PNG card", async () => {
+    const rawJson = readFileSync(new URL("./fixtures/character-card-v2.json", import.meta.url), "utf8");
+    const chunk = (type: string, data: Buffer) => {
+      const length = Buffer.alloc(4);
+      length.writeUInt32BE(data.length);
+      return Buffer.concat([length, Buffer.from(type, "latin1"), data, Buffer.alloc(4)]);
+    };
+    const png = Buffer.concat([
+      Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]),
+      chunk("IHDR", Buffer.alloc(13)),
+      chunk("tEXt", Buffer.from(`chara\0${Buffer.from(rawJson, "utf8").toString("base64")}`, "latin1")),
+      chunk("IEND", Buffer.alloc(0))
+    ]);
+
+    const character = await postJson("/api/characters/import/png", { base64Data: png.toString("base64") });
+    expect(character).toMatchObject({ name: "Harbor Keeper", systemPrompt: "Stay in character as the harbor keeper." });
+    expect(character.lorebookId).toEqual(expect.any(String));
+    expect(character.avatarUrl).toMatch(/^\/api\/avatars\/.+\.png$/);
+
+    const avatar = await fetch(`${baseUrl}${character.avatarUrl}`);
+    expect(Buffer.from(await avatar.arrayBuffer()).equals(png)).toBe(true);
+
+    const missingCard = await fetch(`${baseUrl}/api/characters/import/png`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ base64Data: Buffer.concat([png.subarray(0, 33), chunk("IEND", Buffer.alloc(0))]).toString("base64") })
+    });
+    expect(missingCard.status).toBe(400);
+
+    const exported = await fetch(`${baseUrl}/api/characters/${character.id}/export/png`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({})
+    });
+    expect(exported.status).toBe(200);
+    expect(exported.headers.get("content-type")).toContain("image/png");
+    const exportedPng = Buffer.from(await exported.arrayBuffer());
+    const reimported = await postJson("/api/characters/import/png", { base64Data: exportedPng.toString("base64") });
+    expect(reimported.cardJson).toBe(rawJson);
+    expect(reimported.name).toBe("Harbor Keeper");
+  });
+
+  it("imports V3 and V1 cards and exports a V2 view on request", async () => {
+    const v3 = await postJson("/api/characters/import", {
+      rawJson: JSON.stringify({
+        spec: "chara_card_v3",
+        spec_version: "3.0",
+        data: {
+          name: "Spec Three",
+          nickname: "Tri",
+          description: "",
+          personality: "",
+          scenario: "",
+          first_mes: "Hello.",
+          mes_example: "",
+          group_only_greetings: ["Hello, everyone."],
+          assets: [{ type: "icon", uri: "ccdefault:", name: "main", ext: "png" }]
+        }
+      })
+    });
+    expect(v3).toMatchObject({
+      cardSpec: "chara_card_v3",
+      nickname: "Tri",
+      groupOnlyGreetings: ["Hello, everyone."],
+      assets: [{ type: "icon", uri: "ccdefault:", name: "main", ext: "png" }]
+    });
+
+    const updated = await parseJsonResponse(
+      `/api/characters/${v3.id}`,
+      await requestJson(`/api/characters/${v3.id}`, { method: "PUT", body: { scenario: "A stage." } })
+    );
+    expect(updated.cardSpec).toBe("chara_card_v3");
+    expect(JSON.parse(updated.cardJson)).toMatchObject({ spec: "chara_card_v3", spec_version: "3.0", data: { nickname: "Tri" } });
+
+    const v2View = await fetch(`${baseUrl}/api/characters/${v3.id}/export/json?spec=v2`);
+    const v2Card = JSON.parse(await v2View.text());
+    expect(v2Card).toMatchObject({ spec: "chara_card_v2", spec_version: "2.0", data: { name: "Spec Three", scenario: "A stage." } });
+    expect(v2Card.data).not.toHaveProperty("nickname");
+
+    const v1 = await postJson("/api/characters/import", {
+      rawJson: JSON.stringify({ name: "Spec One", first_mes: "Old format.", personality: "Plain" })
+    });
+    expect(v1).toMatchObject({ name: "Spec One", greeting: "Old format.", personality: "Plain", cardSpec: "chara_card_v2" });
+    expect(JSON.parse(v1.cardJson).spec).toBe("chara_card_v2");
+
+    const converted = await postJson("/api/characters/convert-v1", {
+      rawJson: JSON.stringify({ name: "Convert Me", first_mes: "Hi.", description: "Flat." })
+    });
+    expect(converted.card).toMatchObject({ spec: "chara_card_v2", spec_version: "2.0", data: { name: "Convert Me", description: "Flat." } });
+    expect(JSON.parse(converted.rawJson)).toEqual(converted.card);
+    const notV1 = await requestJson("/api/characters/convert-v1", { method: "POST", body: { rawJson: JSON.stringify({ spec: "chara_card_v2", data: {} }) } });
+    expect(notV1.status).toBe(400);
+  });
+
+  it("initializes a new chat from the first character's enabled scene defaults", async () => {
+    const primary = await postJson("/api/characters/import", {
+      rawJson: JSON.stringify({
+        spec: "chara_card_v2",
+        spec_version: "2.0",
+        data: {
+          name: "Scene Defaults Primary",
+          first_mes: "Ready.",
+          extensions: {
+            vellium_scene_state: {
+              enabled: true,
+              mood: "watchful",
+              pacing: "fast",
+              intensity: 0.82,
+              variables: {
+                dialogueStyle: "formal",
+                initiative: "88",
+                descriptiveness: "64",
+                unpredictability: "37",
+                emotionalDepth: "79"
+              }
+            }
+          }
+        }
+      })
+    });
+    const secondary = await postJson("/api/characters/import", {
+      rawJson: JSON.stringify({
+        spec: "chara_card_v2",
+        spec_version: "2.0",
+        data: {
+          name: "Scene Defaults Secondary",
+          extensions: {
+            vellium_scene_state: {
+              enabled: true,
+              mood: "secondary-mood",
+              pacing: "slow",
+              intensity: 0.2,
+              variables: {}
+            }
+          }
+        }
+      })
+    });
+
+    const created = await postJson("/api/chats", {
+      title: "Character Scene Defaults",
+      characterIds: [primary.id, secondary.id]
+    });
+    const sceneState = await parseJsonResponse(
+      `/api/rp/scene-state/${created.id}`,
+      await fetch(`${baseUrl}/api/rp/scene-state/${created.id}`)
+    );
+
+    expect(sceneState).toMatchObject({
+      chatId: created.id,
+      mood: "watchful",
+      pacing: "fast",
+      intensity: 0.82,
+      variables: {
+        dialogueStyle: "formal",
+        initiative: "88",
+        descriptiveness: "64",
+        unpredictability: "37",
+        emotionalDepth: "79"
+      },
+      chatMode: "rp",
+      pureChatMode: false
+    });
+  });
+
+  it("exports multi-character chats with explicit participants and speakers", async () => {
+    await updateSettings({ activeProviderId: null, activeModel: null });
+    const importCharacter = (name: string, greeting: string) => postJson("/api/characters/import", {
+      rawJson: JSON.stringify({
+        spec: "chara_card_v2",
+        spec_version: "2.0",
+        data: { name, first_mes: greeting }
+      })
+    });
+    const [alice, bob] = await Promise.all([
+      importCharacter("Alice Export", "Alice joined the room."),
+      importCharacter("Bob Export", "Bob joined the room.")
+    ]);
+    const created = await postJson("/api/chats", {
+      title: "Multi Character Export",
+      characterIds: [alice.id, bob.id]
+    });
+    const timeline = await postJson(`/api/chats/${created.id}/send`, {
+      content: "Hello, everyone.",
+      userPersona: { name: "Reader" }
+    });
+    const branchId = timeline[0]?.branchId;
+    const response = await fetch(`${baseUrl}/api/chats/${created.id}/export/json?branchId=${encodeURIComponent(branchId)}`);
+    expect(response.ok).toBe(true);
+
+    const exported = await response.json() as {
+      version: number;
+      chat: { characterId: string | null; characterIds: string[] };
+      participants: Array<{ kind: string; name: string; characterId: string | null }>;
+      conversation: {
+        multiCharacter: boolean;
+        branchId: string;
+        messages: Array<{ sequence: number; speakerName: string; characterId: string | null; content: string }>;
+      };
+    };
+    expect(exported.version).toBe(2);
+    expect(exported.chat).toMatchObject({
+      characterId: alice.id,
+      characterIds: [alice.id, bob.id]
+    });
+    expect(exported.participants).toEqual(expect.arrayContaining([
+      expect.objectContaining({ kind: "character", name: "Alice Export", characterId: alice.id }),
+      expect.objectContaining({ kind: "character", name: "Bob Export", characterId: bob.id }),
+      expect.objectContaining({ kind: "user", name: "Reader", characterId: null })
+    ]));
+    expect(exported.conversation).toMatchObject({ multiCharacter: true, branchId });
+    expect(exported.conversation.messages).toEqual(expect.arrayContaining([
+      expect.objectContaining({ sequence: 1, speakerName: "Alice Export", characterId: alice.id, content: "Alice joined the room." }),
+      expect.objectContaining({ speakerName: "Reader", characterId: null, content: "Hello, everyone." })
+    ]));
+  });
+
+  it("refuses remote compress and translate providers in Full Local Mode", async () => {
+    await postJson("/api/providers", {
+      id: "remote-helper-provider",
+      name: "Remote Helper Provider",
+      baseUrl: "https://helpers.example.com/v1",
+      apiKey: "test-key",
+      providerType: "openai",
+      manualModels: ["remote-model"]
+    });
+    await updateSettings({
+      activeProviderId: null,
+      activeModel: null,
+      fullLocalMode: true,
+      compressProviderId: "remote-helper-provider",
+      compressModel: "remote-model",
+      translateProviderId: "remote-helper-provider",
+      translateModel: "remote-model"
+    });
+    const created = await postJson("/api/chats", { title: "Local Only Helpers" });
+    const timeline = await postJson(`/api/chats/${created.id}/send`, { content: "Stay on this machine" });
+
+    const compressed = await requestJson(`/api/chats/${created.id}/compress`, { method: "POST", body: {} });
+    expect(compressed.status).toBe(400);
+    expect(await compressed.json()).toMatchObject({ error: "Provider blocked by Full Local Mode", code: "not_configured" });
+    const stored = db.prepare("SELECT context_summary FROM chats WHERE id = ?").get(created.id) as { context_summary: string | null };
+    expect(stored.context_summary || "").toBe("");
+
+    const translated = await postJson(`/api/chats/messages/${timeline[0].id}/translate`, { targetLanguage: "German" });
+    expect(translated).toEqual({ translation: "Stay on this machine" });
+
+    await updateSettings({
+      fullLocalMode: false,
+      compressProviderId: null,
+      compressModel: null,
+      translateProviderId: null,
+      translateModel: null
+    });
+  });
+
+  it("translates messages and synthesizes TTS against a local mock provider", async () => {
+    await updateSettings({
+      activeProviderId: null,
+      activeModel: null,
+      translateProviderId: "mock-openai",
+      translateModel: "mock-model",
+      ttsBaseUrl: mockProviderBaseUrl,
+      ttsApiKey: "test-key",
+      ttsModel: "tts-1",
+      ttsVoice: "alloy"
+    });
+
+    const created = await postJson("/api/chats", { title: "Media Chat" });
+    const timeline = await postJson(`/api/chats/${created.id}/send`, {
+      content: "Need media handlers"
+    });
+    const userMessageId = timeline[0]?.id;
+    expect(userMessageId).toEqual(expect.any(String));
+
+    const translated = await postJson(`/api/chats/messages/${userMessageId}/translate`, {
+      targetLanguage: "German"
+    });
+    expect(translated).toEqual({ translation: "MOCK RESPONSE" });
+
+    const audioResponse = await fetch(`${baseUrl}/api/chats/messages/${userMessageId}/tts`, {
+      method: "POST"
+    });
+    expect(audioResponse.ok).toBe(true);
+    expect(audioResponse.headers.get("content-type")).toContain("audio/mpeg");
+    const audioBuffer = Buffer.from(await audioResponse.arrayBuffer());
+    expect(audioBuffer.toString("utf-8")).toBe("FAKE_MP3_DATA");
+
+    const realtimeResponse = await fetch(`${baseUrl}/api/chats/tts/realtime`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ input: `${"First phrase ".repeat(20)}. ${"Second phrase ".repeat(20)}!` })
+    });
+    expect(realtimeResponse.ok).toBe(true);
+    expect(realtimeResponse.headers.get("content-type")).toContain("application/x-ndjson");
+    const realtimeEvents = (await realtimeResponse.text()).trim().split("\n").map((line) => JSON.parse(line));
+    const audioEvents = realtimeEvents.filter((event) => event.type === "audio");
+    expect(audioEvents).toHaveLength(2);
+    expect(Buffer.from(audioEvents[0].audioBase64, "base64").toString("utf8")).toBe("PCM_ONE");
+    expect(audioEvents[0]).toMatchObject({ contentType: "audio/pcm", format: "pcm", sampleRate: 24_000 });
+    expect(lastTtsRequestBody).toMatchObject({ response_format: "pcm", stream_format: "sse" });
+    expect(realtimeEvents.at(-1)).toMatchObject({ type: "done", count: audioEvents.length });
+  });
+
+  it("discovers and uses a Whisper-compatible STT endpoint", async () => {
+    await updateSettings({
+      sttSource: "whisper",
+      sttBaseUrl: mockProviderBaseUrl,
+      sttApiKey: "test-key",
+      sttModel: "whisper-1",
+      sttLanguage: "en"
+    });
+
+    const modelsResponse = await requestJson("/api/settings/stt/models", {
+      method: "POST",
+      body: {}
+    });
+    expect(modelsResponse.ok).toBe(true);
+    expect(await modelsResponse.json()).toContainEqual({ id: "mock-model" });
+
+    const transcript = await postJson("/api/live/transcribe", {
+      audioBase64: Buffer.from("fake webm audio").toString("base64"),
+      mimeType: "audio/webm;codecs=opus",
+      filename: "live-recording.webm"
+    });
+    expect(transcript).toEqual({ text: "Recognized live speech" });
+    expect(lastSttMultipartBody).toContain('name="model"');
+    expect(lastSttMultipartBody).toContain("whisper-1");
+    expect(lastSttMultipartBody).toContain('name="language"');
+    expect(lastSttMultipartBody).toContain("fake webm audio");
+  });
+
+  it("generates writer drafts through the mock provider and exports markdown", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model"
+    });
+
+    const project = await postJson("/api/writer/projects", {
+      name: "Mock Novel",
+      description: "Integration project"
+    });
+    const chapter = await postJson("/api/writer/chapters", {
+      projectId: project.id,
+      title: "Chapter One"
+    });
+
+    const character = await postJson("/api/characters/import", {
+      rawJson: JSON.stringify({ spec: "chara_card_v2", spec_version: "2.0", data: { name: "Draft Lead", first_mes: "Hello" } })
+    });
+    const draft = await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, {
+      prompt: "Write the opening scene",
+      characterId: character.id
+    });
+    expect(draft.content).toBe("MOCK RESPONSE");
+    expect(draft).toMatchObject({ characterId: character.id, chatId: null });
+    const opened = await parseJsonResponse(
+      `/api/writer/projects/${project.id}`,
+      await fetch(`${baseUrl}/api/writer/projects/${project.id}`)
+    ) as { scenes: Array<{ id: string; characterId: string | null; chatId: string | null }> };
+    expect(opened.scenes.find((scene) => scene.id === draft.id)).toMatchObject({ characterId: character.id, chatId: null });
+    const unknownCharacter = await requestJson(`/api/writer/chapters/${chapter.id}/generate-draft`, {
+      method: "POST",
+      body: { prompt: "Write", characterId: "missing-character" }
+    });
+    expect(unknownCharacter.status).toBe(404);
+
+    const markdownResponse = await fetch(`${baseUrl}/api/writer/projects/${project.id}/export/markdown/download`, {
+      method: "POST"
+    });
+    expect(markdownResponse.ok).toBe(true);
+    expect(markdownResponse.headers.get("content-type")).toContain("text/markdown");
+    const markdown = await markdownResponse.text();
+    expect(markdown).toContain("# Mock Novel");
+    expect(markdown).toContain("## Chapter One");
+    expect(markdown).toContain("MOCK RESPONSE");
+  });
+
+  it("continues a cut-off assistant reply in place", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model"
+    });
+    const chat = await postJson("/api/chats", { title: "Continue Chat" });
+    const empty = await requestJson(`/api/chats/${chat.id}/continue`, { method: "POST", body: {} });
+    expect(empty.status).toBe(409);
+
+    const sent = await fetch(`${baseUrl}/api/chats/${chat.id}/send`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ content: "Describe the lanterns" })
+    });
+    await sent.text();
+    const tail = db.prepare(
+      "SELECT id, branch_id FROM messages WHERE chat_id = ? AND role = 'assistant' AND deleted = 0 ORDER BY sort_order DESC LIMIT 1"
+    ).get(chat.id) as { id: string; branch_id: string };
+    db.prepare("UPDATE messages SET content = 'The lanterns flick', token_count = 3, truncated = 1 WHERE id = ?").run(tail.id);
+    const messageCount = (db.prepare("SELECT COUNT(*) AS count FROM messages WHERE chat_id = ?").get(chat.id) as { count: number }).count;
+
+    const continued = await fetch(`${baseUrl}/api/chats/${chat.id}/continue`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ branchId: tail.branch_id })
+    });
+    expect(continued.headers.get("content-type")).toContain("text/event-stream");
+    const events = await continued.text();
+    expect(events).toContain("ered over");
+    expect(events).toContain(`"messageId":"${tail.id}"`);
+
+    const row = db.prepare("SELECT content, token_count, truncated FROM messages WHERE id = ?").get(tail.id) as {
+      content: string;
+      token_count: number;
+      truncated: number;
+    };
+    expect(row.content).toBe("The lanterns flickered over the harbour.");
+    expect(row.token_count).toBeGreaterThan(3);
+    expect(row.truncated).toBe(0);
+    expect(db.prepare("SELECT COUNT(*) AS count FROM messages WHERE chat_id = ?").get(chat.id)).toEqual({ count: messageCount });
+  });
+
+  it("drafts a writer scene from a chat branch and links it to the chat", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model"
+    });
+    const chat = await postJson("/api/chats", { title: "Ferry Chat" });
+    await postJson(`/api/chats/${chat.id}/send`, { content: "Mira asks about the last ferry" });
+    const project = await postJson("/api/writer/projects", { name: "Bridge Novel", description: "" });
+    const chapter = await postJson("/api/writer/chapters", { projectId: project.id, title: "Crossing" });
+
+    const scene = await postJson(`/api/writer/chapters/${chapter.id}/scenes/from-chat`, { chatId: chat.id });
+    const chatTitle = (db.prepare("SELECT title FROM chats WHERE id = ?").get(chat.id) as { title: string }).title;
+    expect(scene).toMatchObject({ chapterId: chapter.id, chatId: chat.id, title: chatTitle, content: "MOCK RESPONSE" });
+    expect(lastSceneFromChatPromptText).toContain(": Mira asks about the last ferry");
+    expect(lastSceneFromChatPromptText).toContain(": MOCK RESPONSE");
+    expect(db.prepare("SELECT chat_id FROM writer_scenes WHERE id = ?").get(scene.id)).toEqual({ chat_id: chat.id });
+
+    const missingChatId = await requestJson(`/api/writer/chapters/${chapter.id}/scenes/from-chat`, { method: "POST", body: {} });
+    expect(missingChatId.status).toBe(400);
+    const badBranch = await requestJson(`/api/writer/chapters/${chapter.id}/scenes/from-chat`, {
+      method: "POST",
+      body: { chatId: chat.id, branchId: "missing-branch" }
+    });
+    expect(badBranch.status).toBe(404);
+    await requestJson(`/api/chats/${chat.id}`, { method: "DELETE" });
+    const trashed = await requestJson(`/api/writer/chapters/${chapter.id}/scenes/from-chat`, {
+      method: "POST",
+      body: { chatId: chat.id }
+    });
+    expect(trashed.status).toBe(404);
+  });
+
+  it("attaches a POV character to a scene and checks its dialogue tags", async () => {
+    const character = await postJson("/api/characters/import", {
+      rawJson: JSON.stringify({ spec: "chara_card_v2", spec_version: "2.0", data: { name: "Mara Voss", first_mes: "Hello", personality: "Dry, watchful" } })
+    });
+    const project = await postJson("/api/writer/projects", { name: "POV Novel", description: "" });
+    const chapter = await postJson("/api/writer/chapters", { projectId: project.id, title: "Harbour" });
+    const sceneId = `pov-scene-${project.id}`;
+    db.prepare(
+      "INSERT INTO writer_scenes (id, chapter_id, title, content, goals, conflicts, outcomes, created_at, position) VALUES (?, ?, ?, ?, '', '', '', ?, 1)"
+    ).run(sceneId, chapter.id, "Fog", [
+      "I reached the harbour before the fog lifted.",
+      "The boats were still tied up and I counted them twice.",
+      "\"We leave at noon,\" Mara said to the boy.",
+      "I did not wait for an answer and walked on."
+    ].join("\n"), new Date().toISOString());
+
+    const before = await postJson(`/api/writer/projects/${project.id}/consistency`, {}) as Array<{ message: string }>;
+    expect(before.some((issue) => issue.message.includes("dialogue tag"))).toBe(false);
+
+    const updated = await requestJson(`/api/writer/scenes/${sceneId}/character`, { method: "PUT", body: { characterId: character.id } });
+    expect(updated.status).toBe(200);
+    expect(await updated.json()).toMatchObject({ id: sceneId, characterId: character.id });
+
+    const after = await postJson(`/api/writer/projects/${project.id}/consistency`, {}) as Array<{ category: string; message: string }>;
+    expect(after).toContainEqual(expect.objectContaining({
+      category: "pov",
+      message: "Scene 'Fog' is narrated by Mara Voss in first person but has the dialogue tag \"Mara said\""
+    }));
+
+    const unknown = await requestJson(`/api/writer/scenes/${sceneId}/character`, { method: "PUT", body: { characterId: "missing-character" } });
+    expect(unknown.status).toBe(404);
+    const cleared = await requestJson(`/api/writer/scenes/${sceneId}/character`, { method: "PUT", body: { characterId: null } });
+    expect(await cleared.json()).toMatchObject({ characterId: null });
+  });
+
+  it("expands several writer scenes concurrently with a status per scene", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model"
+    });
+    const project = await postJson("/api/writer/projects", { name: "Batch Novel", description: "" });
+    const chapter = await postJson("/api/writer/chapters", { projectId: project.id, title: "Stubs" });
+    const insertScene = db.prepare(
+      "INSERT INTO writer_scenes (id, chapter_id, title, content, goals, conflicts, outcomes, created_at, position) VALUES (?, ?, ?, ?, '', '', '', ?, ?)"
+    );
+    const firstId = `batch-a-${project.id}`;
+    const secondId = `batch-b-${project.id}`;
+    insertScene.run(firstId, chapter.id, "Stub A", "She opens the gate.", new Date().toISOString(), 1);
+    insertScene.run(secondId, chapter.id, "Stub B", "He waits by the well.", new Date().toISOString(), 2);
+
+    const batch = await postJson("/api/writer/scenes/batch-expand", { sceneIds: [firstId, "missing-scene", secondId] }) as {
+      results: Array<{ sceneId: string; status: string; scene?: { content: string } }>;
+    };
+    expect(batch.results.map((result) => [result.sceneId, result.status])).toEqual([
+      [firstId, "ok"],
+      ["missing-scene", "not_found"],
+      [secondId, "ok"]
+    ]);
+    expect(batch.results[0].scene?.content).toBe("MOCK RESPONSE");
+    expect(db.prepare("SELECT content FROM writer_scenes WHERE id = ?").get(secondId)).toEqual({ content: "MOCK RESPONSE" });
+    expect(db.prepare("SELECT COUNT(*) AS count FROM writer_scene_revisions WHERE scene_id = ?").get(firstId)).toEqual({ count: 1 });
+
+    const streamed = await fetch(`${baseUrl}/api/writer/scenes/batch-expand`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ sceneIds: [firstId, secondId], stream: true })
+    });
+    expect(streamed.headers.get("content-type")).toContain("text/event-stream");
+    const events = (await streamed.text())
+      .split("\n\n")
+      .filter((block) => block.startsWith("data: "))
+      .map((block) => JSON.parse(block.slice(6)) as { type: string; chatId?: string; sceneId?: string; results?: unknown[] });
+    expect(events[0]).toMatchObject({ type: "batch", sceneIds: [firstId, secondId] });
+    expect(events.some((event) => event.type === "delta" && event.chatId === secondId)).toBe(true);
+    expect(events.filter((event) => event.type === "scene_result").map((event) => event.sceneId).sort()).toEqual([firstId, secondId].sort());
+    expect(events.at(-1)).toMatchObject({ type: "done", results: [expect.objectContaining({ status: "ok" }), expect.objectContaining({ status: "ok" })] });
+
+    const empty = await requestJson("/api/writer/scenes/batch-expand", { method: "POST", body: { sceneIds: [] } });
+    expect(empty.status).toBe(400);
+  });
+
+  it("exports a writer project as one self-contained HTML page", async () => {
+    const project = await postJson("/api/writer/projects", { name: "Harbor <Tales>", description: "" });
+    const first = await postJson("/api/writer/chapters", { projectId: project.id, title: "Arrival" });
+    const second = await postJson("/api/writer/chapters", { projectId: project.id, title: "Departure" });
+    const insertScene = db.prepare(
+      "INSERT INTO writer_scenes (id, chapter_id, title, content, goals, conflicts, outcomes, created_at, position) VALUES (?, ?, ?, ?, '', '', '', ?, ?)"
+    );
+    insertScene.run(`html-a-${project.id}`, first.id, "The Pier", "The **tide** was <script>alert(1)</script> low.\n\n***\n\nShe waited.", new Date().toISOString(), 1);
+    insertScene.run(`html-b-${project.id}`, second.id, "", "The ship *left*.", new Date().toISOString(), 1);
+
+    const outputPath = await postJson(`/api/writer/projects/${project.id}/export/html`, {}) as string;
+    expect(outputPath.endsWith(".html")).toBe(true);
+    const html = readFileSync(outputPath, "utf8");
+    expect(html).toContain("<title>Harbor &lt;Tales&gt;</title>");
+    expect(html).toContain("<a href=\"#chapter-1\">Arrival</a>");
+    expect(html).toContain("<h2 id=\"chapter-2\">Departure</h2>");
+    expect(html).toContain("<h3>The Pier</h3>");
+    expect(html).toContain("<p>The <strong>tide</strong> was &lt;script&gt;alert(1)&lt;/script&gt; low.</p>");
+    expect(html).toContain("<hr>");
+    expect(html).toContain("<p>The ship <em>left</em>.</p>");
+    expect(html).not.toContain("<script>");
+    expect(db.prepare("SELECT export_type, output_path FROM writer_exports WHERE project_id = ?").get(project.id))
+      .toEqual({ export_type: "html", output_path: outputPath });
+
+    const missing = await requestJson("/api/writer/projects/missing-project/export/html", { method: "POST", body: {} });
+    expect(missing.status).toBe(404);
+  });
+
+  it("streams writer drafts from the active provider and respects Full Local Mode", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model"
+    });
+
+    const project = await postJson("/api/writer/projects", {
+      name: "Streaming Novel",
+      description: "Streaming draft project"
+    });
+    const chapter = await postJson("/api/writer/chapters", {
+      projectId: project.id,
+      title: "Streamed Chapter"
+    });
+
+    const streamResponse = await requestJson(`/api/writer/chapters/${chapter.id}/generate-draft`, {
+      method: "POST",
+      body: { prompt: "Stream the opening scene", stream: true }
+    });
+    expect(streamResponse.ok).toBe(true);
+    expect(streamResponse.headers.get("content-type")).toContain("text/event-stream");
+    const streamBody = await streamResponse.text();
+    expect(streamBody).toContain("\"type\":\"delta\"");
+    expect(streamBody).toContain("\"type\":\"scene\"");
+    expect(streamBody).toContain("\"type\":\"done\"");
+
+    const opened = await parseJsonResponse(
+      `/api/writer/projects/${project.id}`,
+      await fetch(`${baseUrl}/api/writer/projects/${project.id}`)
+    );
+    expect(opened.scenes).toHaveLength(1);
+    expect(opened.scenes[0]).toMatchObject({ chapterId: chapter.id, content: "MOCK STREAM RESPONSE" });
+
+    await postJson("/api/providers", {
+      id: "remote-writer-provider",
+      name: "Remote Writer Provider",
+      baseUrl: "https://example.com/v1",
+      apiKey: "test-key",
+      proxyUrl: null,
+      fullLocalOnly: false,
+      providerType: "openai",
+      adapterId: null,
+      manualModels: ["remote-model"]
+    });
+    await updateSettings({
+      activeProviderId: "remote-writer-provider",
+      activeModel: "remote-model",
+      fullLocalMode: true
+    });
+    const blocked = await requestJson(`/api/writer/chapters/${chapter.id}/generate-draft`, {
+      method: "POST",
+      body: { prompt: "Should not leave the machine", stream: true }
+    });
+    expect(blocked.status).toBe(400);
+    expect(await blocked.json()).toEqual({ error: "Provider blocked by Full Local Mode" });
+    await updateSettings({ fullLocalMode: false });
+  });
+
+  it("keeps capped scene revisions and restores them as undo", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      writerSceneRevisionLimit: 20
+    });
+
+    const project = await postJson("/api/writer/projects", {
+      name: "Revision Novel",
+      description: "Revision project"
+    });
+    const chapter = await postJson("/api/writer/chapters", {
+      projectId: project.id,
+      title: "Revision Chapter"
+    });
+    const scene = await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, {
+      prompt: "Write something to revise"
+    });
+
+    const editResponse = await requestJson(`/api/writer/scenes/${scene.id}`, {
+      method: "PATCH",
+      body: { content: "Hand edited draft" }
+    });
+    expect(editResponse.ok).toBe(true);
+    const rewritten = await postJson(`/api/writer/scenes/${scene.id}/rewrite`, {});
+    expect(rewritten.content).toBe("MOCK RESPONSE");
+
+    const revisions = await parseJsonResponse(
+      `/api/writer/scenes/${scene.id}/revisions`,
+      await fetch(`${baseUrl}/api/writer/scenes/${scene.id}/revisions`)
+    );
+    expect(revisions.map((revision: { reason: string; content: string }) => [revision.reason, revision.content])).toEqual([
+      ["rewrite", "Hand edited draft"],
+      ["edit", "MOCK RESPONSE"]
+    ]);
+
+    const restored = await postJson(`/api/writer/scenes/${scene.id}/revisions/${revisions[0].id}/restore`, {});
+    expect(restored.content).toBe("Hand edited draft");
+    const afterRestore = await parseJsonResponse(
+      `/api/writer/scenes/${scene.id}/revisions`,
+      await fetch(`${baseUrl}/api/writer/scenes/${scene.id}/revisions`)
+    );
+    expect(afterRestore.map((revision: { reason: string }) => revision.reason)).toEqual(["restore", "edit"]);
+
+    const missing = await requestJson(`/api/writer/scenes/${scene.id}/revisions/missing-revision/restore`, { method: "POST" });
+    expect(missing.status).toBe(404);
+
+    await updateSettings({ writerSceneRevisionLimit: 1 });
+    await postJson(`/api/writer/scenes/${scene.id}/expand`, {});
+    const capped = await parseJsonResponse(
+      `/api/writer/scenes/${scene.id}/revisions`,
+      await fetch(`${baseUrl}/api/writer/scenes/${scene.id}/revisions`)
+    );
+    expect(capped).toHaveLength(1);
+    expect(capped[0]).toMatchObject({ reason: "expand", content: "Hand edited draft" });
+    await updateSettings({ writerSceneRevisionLimit: 20 });
+  });
+
+  it("reorders and deletes writer scenes and opens projects in scene order", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model"
+    });
+
+    const project = await postJson("/api/writer/projects", {
+      name: "Reorder Novel",
+      description: "Scene order project"
+    });
+    const chapter = await postJson("/api/writer/chapters", {
+      projectId: project.id,
+      title: "Reorder Chapter"
+    });
+    const first = await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, { prompt: "First" });
+    const second = await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, { prompt: "Second" });
+    const third = await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, { prompt: "Third" });
+
+    const reordered = await postJson("/api/writer/scenes/reorder", {
+      chapterId: chapter.id,
+      orderedIds: [third.id, first.id, second.id]
+    });
+    expect(reordered).toEqual({ ok: true });
+    const opened = await parseJsonResponse(
+      `/api/writer/projects/${project.id}`,
+      await fetch(`${baseUrl}/api/writer/projects/${project.id}`)
+    );
+    expect(opened.scenes.map((scene: { id: string; position: number }) => [scene.id, scene.position])).toEqual([
+      [third.id, 1],
+      [first.id, 2],
+      [second.id, 3]
+    ]);
+
+    const deleted = await requestJson(`/api/writer/scenes/${first.id}`, { method: "DELETE" });
+    expect(deleted.ok).toBe(true);
+    const afterDelete = await parseJsonResponse(
+      `/api/writer/projects/${project.id}`,
+      await fetch(`${baseUrl}/api/writer/projects/${project.id}`)
+    );
+    expect(afterDelete.scenes.map((scene: { id: string }) => scene.id)).toEqual([third.id, second.id]);
+
+    const missing = await requestJson("/api/writer/scenes/reorder", {
+      method: "POST",
+      body: { chapterId: "missing-chapter", orderedIds: [] }
+    });
+    expect(missing.status).toBe(404);
+  });
+
+  it("deletes a chapter with its scenes and revisions and closes the position gap", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model"
+    });
+
+    const project = await postJson("/api/writer/projects", {
+      name: "Chapter Delete Novel",
+      description: "Chapter delete project"
+    });
+    const first = await postJson("/api/writer/chapters", { projectId: project.id, title: "One" });
+    const middle = await postJson("/api/writer/chapters", { projectId: project.id, title: "Two" });
+    const last = await postJson("/api/writer/chapters", { projectId: project.id, title: "Three" });
+    const scene = await postJson(`/api/writer/chapters/${middle.id}/generate-draft`, { prompt: "Doomed scene" });
+    await requestJson(`/api/writer/scenes/${scene.id}`, { method: "PATCH", body: { content: "Edited" } });
+    expect(db.prepare("SELECT COUNT(*) AS count FROM writer_scene_revisions WHERE scene_id = ?").get(scene.id)).toEqual({ count: 1 });
+
+    const deleted = await parseJsonResponse(
+      `/api/writer/chapters/${middle.id}`,
+      await requestJson(`/api/writer/chapters/${middle.id}`, { method: "DELETE" })
+    );
+    expect(deleted.chapters.map((chapter: { id: string; position: number }) => [chapter.id, chapter.position])).toEqual([
+      [first.id, 1],
+      [last.id, 2]
+    ]);
+    expect(db.prepare("SELECT COUNT(*) AS count FROM writer_scenes WHERE id = ?").get(scene.id)).toEqual({ count: 0 });
+    expect(db.prepare("SELECT COUNT(*) AS count FROM writer_scene_revisions WHERE scene_id = ?").get(scene.id)).toEqual({ count: 0 });
+
+    const missing = await requestJson(`/api/writer/chapters/${middle.id}`, { method: "DELETE" });
+    expect(missing.status).toBe(404);
+  });
+
+  it("streams per-scene consistency progress and saves the final report", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model"
+    });
+
+    const project = await postJson("/api/writer/projects", {
+      name: "Consistency Stream Novel",
+      description: "Consistency stream project"
+    });
+    const chapter = await postJson("/api/writer/chapters", { projectId: project.id, title: "One" });
+    const first = await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, { prompt: "Opening" });
+    const second = await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, { prompt: "Follow-up" });
+    await requestJson(`/api/writer/scenes/${first.id}`, { method: "PATCH", body: { content: "The pier is quiet. [TODO] add the storm." } });
+    await requestJson(`/api/writer/scenes/${second.id}`, { method: "PATCH", body: { content: "The ferry leaves at dawn." } });
+
+    const response = await requestJson(`/api/writer/projects/${project.id}/consistency/stream`, { method: "POST", body: {} });
+    expect(response.headers.get("content-type")).toContain("application/x-ndjson");
+    const events = (await response.text()).trim().split("\n").map((line) => JSON.parse(line) as {
+      type: string;
+      sceneId?: string;
+      checked?: number;
+      total?: number;
+      issuesFound?: number;
+      issues?: Array<{ category: string; message: string }>;
+    });
+
+    expect(events.slice(0, 2)).toEqual([
+      { type: "progress", sceneId: first.id, checked: 1, total: 2, issuesFound: 1 },
+      { type: "progress", sceneId: second.id, checked: 2, total: 2, issuesFound: 1 }
+    ]);
+    const report = events.at(-1);
+    expect(report?.type).toBe("report");
+    expect(report?.issues?.some((issue) => issue.category === "facts" && issue.message.includes("TODO"))).toBe(true);
+    const saved = db.prepare("SELECT payload FROM writer_consistency_reports WHERE project_id = ?").all(project.id) as Array<{ payload: string }>;
+    expect(saved.map((row) => JSON.parse(row.payload))).toEqual([report?.issues]);
+
+    const missing = await requestJson("/api/writer/projects/missing-project/consistency/stream", { method: "POST", body: {} });
+    expect(missing.status).toBe(404);
+  });
+
+  it("lists stored consistency reports and filters one by severity and category", async () => {
+    const project = await postJson("/api/writer/projects", {
+      name: "Consistency Report Novel",
+      description: "Consistency report project"
+    });
+    const issues = [
+      { id: "issue-todo", projectId: project.id, severity: "medium", category: "facts", message: "Scene 'A' still contains TODO markers" },
+      { id: "issue-pov", projectId: project.id, severity: "low", category: "pov", message: "Scene 'B' shifts POV" },
+      { id: "issue-name", projectId: project.id, severity: "high", category: "names", message: "Mara is spelled Marra" }
+    ];
+    db.prepare("INSERT INTO writer_consistency_reports (id, project_id, payload, created_at) VALUES (?, ?, ?, ?)")
+      .run(`report-old-${project.id}`, project.id, "[]", "2026-01-01T00:00:00.000Z");
+    db.prepare("INSERT INTO writer_consistency_reports (id, project_id, payload, created_at) VALUES (?, ?, ?, ?)")
+      .run(`report-new-${project.id}`, project.id, JSON.stringify(issues), "2026-01-02T00:00:00.000Z");
+
+    const reports = await (await requestJson(`/api/writer/projects/${project.id}/consistency/reports`)).json();
+    expect(reports).toEqual([
+      {
+        id: `report-new-${project.id}`,
+        projectId: project.id,
+        createdAt: "2026-01-02T00:00:00.000Z",
+        issueCount: 3,
+        severityCounts: { low: 1, medium: 1, high: 1 }
+      },
+      {
+        id: `report-old-${project.id}`,
+        projectId: project.id,
+        createdAt: "2026-01-01T00:00:00.000Z",
+        issueCount: 0,
+        severityCounts: { low: 0, medium: 0, high: 0 }
+      }
+    ]);
+
+    const full = await (await requestJson(`/api/writer/consistency/reports/report-new-${project.id}`)).json();
+    expect(full.issues.map((issue: { id: string }) => issue.id)).toEqual(["issue-todo", "issue-pov", "issue-name"]);
+    const filtered = await (await requestJson(
+      `/api/writer/consistency/reports/report-new-${project.id}?severity=medium,high&category=facts`
+    )).json();
+    expect(filtered.issues.map((issue: { id: string }) => issue.id)).toEqual(["issue-todo"]);
+
+    const invalid = await requestJson(`/api/writer/consistency/reports/report-new-${project.id}?severity=urgent`);
+    expect(invalid.status).toBe(400);
+    expect((await requestJson("/api/writer/consistency/reports/missing-report")).status).toBe(404);
+    expect((await requestJson("/api/writer/projects/missing-project/consistency/reports")).status).toBe(404);
+  });
+
+
+  it("hands client tool calls back to the caller and continues the turn with the submitted result", async () => {
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", toolCallingEnabled: false });
+    const chat = await postJson("/api/chats", { title: "Client Tool Chat" });
+    const tools = [{
+      type: "function",
+      function: { name: "roll_dice", description: "Roll a die", parameters: { type: "object", properties: { sides: { type: "number" } } } }
+    }];
+
+    const invalid = await requestJson(`/api/chats/${chat.id}/send`, { method: "POST", body: { content: "hi", tools: [{ function: { name: "bad name" } }] } });
+    expect(invalid.status).toBe(400);
+
+    const sendResponse = await requestJson(`/api/chats/${chat.id}/send`, {
+      method: "POST",
+      body: { content: "client-dice-tool-check roll a d20", tools }
+    });
+    const events = String(await sendResponse.text())
+      .split("\n")
+      .filter((line) => line.startsWith("data:"))
+      .map((line) => JSON.parse(line.slice(5)) as Record<string, unknown>);
+    expect(events.find((event) => event.type === "tool_call")).toMatchObject({ callId: "call-dice-1", name: "roll_dice", args: "{\"sides\":20}" });
+    expect(events.at(-1)).toMatchObject({ type: "done", pendingToolCalls: ["call-dice-1"] });
+
+    const pendingRow = db.prepare("SELECT content, parent_id FROM messages WHERE chat_id = ? AND role = 'tool'").get(chat.id) as { content: string; parent_id: string };
+    expect(JSON.parse(pendingRow.content)).toMatchObject({ kind: "tool_call", callId: "call-dice-1", client: true, pending: true });
+
+    const missing = await requestJson(`/api/chats/${chat.id}/tool-results`, { method: "POST", body: { toolCallId: "nope", result: "1" } });
+    expect(missing.status).toBe(404);
+
+    const continued = await requestJson(`/api/chats/${chat.id}/tool-results`, {
+      method: "POST",
+      body: { toolCallId: "call-dice-1", result: "17", tools }
+    });
+    expect(collectSseDeltas(await continued.text())).toContain("The die shows 17 (call-dice-1).");
+    expect(lastChatTemplateMessages.slice(-2)).toMatchObject([
+      { role: "assistant", tool_calls: [{ id: "call-dice-1", function: { name: "roll_dice" } }] },
+      { role: "tool", tool_call_id: "call-dice-1", content: "17" }
+    ]);
+
+    const timeline = await (await requestJson(`/api/chats/${chat.id}/timeline`)).json() as Array<{ role: string; content: string; parentId: string | null }>;
+    const toolMessage = timeline.find((message) => message.role === "tool");
+    expect(JSON.parse(String(toolMessage?.content))).toMatchObject({ result: "17", pending: false });
+    expect(timeline.at(-1)).toMatchObject({ role: "assistant", content: "The die shows 17 (call-dice-1).", parentId: pendingRow.parent_id });
+
+    const again = await requestJson(`/api/chats/${chat.id}/tool-results`, { method: "POST", body: { toolCallId: "call-dice-1", result: "3" } });
+    expect(again.status).toBe(409);
+  });
+
+  it("stores images sent with a message as attachments and rejects unreadable ones", async () => {
+    await updateSettings({ activeProviderId: "", activeModel: "" });
+    const chat = await postJson("/api/chats", { title: "Image Input Chat" });
+    const rejected = await requestJson(`/api/chats/${chat.id}/send`, {
+      method: "POST",
+      body: { content: "describe this", images: [join(dataDir, "missing-reference.png")] }
+    });
+    expect(rejected.status).toBe(400);
+    const outsideImage = join(dataDir, "outside-uploads.png");
+    writeFileSync(outsideImage, Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]));
+    const outside = await requestJson(`/api/chats/${chat.id}/send`, {
+      method: "POST",
+      body: { content: "describe this", images: [outsideImage] }
+    });
+    expect(outside.status).toBe(400);
+    expect(await outside.json()).toMatchObject({ error: "images[0] must be a file in the uploads folder" });
+    const tooMany = await requestJson(`/api/chats/${chat.id}/send`, {
+      method: "POST",
+      body: {
+        content: "describe these",
+        attachments: Array.from({ length: 10 }, (_, index) => ({ type: "text", filename: `note-${index}.txt`, content: "x" })),
+        images: Array.from({ length: 3 }, () => "data:image/png;base64,iVBORw0KGgo=")
+      }
+    });
+    expect(tooMany.status).toBe(400);
+    expect(await tooMany.json()).toMatchObject({
+      error: "A message can carry at most 12 attachments and images; this one has 13",
+      code: "validation"
+    });
+    expect(db.prepare("SELECT COUNT(*) AS count FROM messages WHERE chat_id = ?").get(chat.id)).toEqual({ count: 0 });
+
+    const timeline = await postJson(`/api/chats/${chat.id}/send`, {
+      content: "describe this",
+      images: ["data:image/png;base64,iVBORw0KGgo="]
+    }) as Array<{ role: string; attachments?: Array<{ type: string; mimeType?: string }> }>;
+    expect(timeline.find((message) => message.role === "user")?.attachments).toMatchObject([{ type: "image", mimeType: "image/png" }]);
+  });
+
+  it("sets a chat's active character and seeds its greeting into an empty chat", async () => {
+    const character = await postJson("/api/characters/import", {
+      rawJson: JSON.stringify({
+        spec: "chara_card_v2",
+        spec_version: "2.0",
+        data: { name: "Harbor Keeper", first_mes: "Welcome to {{char}}'s harbor." }
+      })
+    });
+    const chat = await postJson("/api/chats", { title: "Character Link Chat" });
+
+    const missingChat = await requestJson("/api/chats/missing-chat/character", { method: "PUT", body: { characterId: character.id } });
+    expect(missingChat.status).toBe(404);
+    const missingCharacter = await requestJson(`/api/chats/${chat.id}/character`, { method: "PUT", body: { characterId: "missing-character" } });
+    expect(missingCharacter.status).toBe(404);
+
+    const linked = await (await requestJson(`/api/chats/${chat.id}/character`, { method: "PUT", body: { characterId: character.id } })).json();
+    expect(linked).toMatchObject({ ok: true, characterId: character.id, characterIds: [character.id] });
+    expect(linked.timeline).toMatchObject([{ role: "assistant", content: "Welcome to Harbor Keeper's harbor.", characterName: "Harbor Keeper" }]);
+    expect(db.prepare("SELECT character_id FROM chats WHERE id = ?").get(chat.id)).toEqual({ character_id: character.id });
+
+    const relinked = await (await requestJson(`/api/chats/${chat.id}/character`, { method: "PUT", body: { characterId: character.id } })).json();
+    expect(relinked.timeline).toHaveLength(1);
+
+    const cleared = await (await requestJson(`/api/chats/${chat.id}/character`, { method: "PUT", body: { characterId: null } })).json();
+    expect(cleared).toMatchObject({ characterId: null, characterIds: [] });
+  });
+
+  it("opens a new chat with the alternate greeting picked by index", async () => {
+    const character = await postJson("/api/characters/import", {
+      rawJson: JSON.stringify({
+        spec: "chara_card_v2",
+        spec_version: "2.0",
+        data: {
+          name: "Lantern Guide",
+          first_mes: "The lantern flickers.",
+          alternate_greetings: ["{{char}} raises the lantern toward you.", "Fog rolls in."]
+        }
+      })
+    });
+
+    const outOfRange = await requestJson("/api/chats", { method: "POST", body: { title: "Greeting Pick", characterId: character.id, greetingIndex: 3 } });
+    expect(outOfRange.status).toBe(400);
+    const fractional = await requestJson("/api/chats", { method: "POST", body: { title: "Greeting Pick", characterId: character.id, greetingIndex: 1.5 } });
+    expect(fractional.status).toBe(400);
+
+    const chat = await postJson("/api/chats", { title: "Greeting Pick", characterId: character.id, greetingIndex: 1 });
+    const timeline = await (await requestJson(`/api/chats/${chat.id}/timeline`)).json();
+    expect(timeline).toMatchObject([{ role: "assistant", content: "Lantern Guide raises the lantern toward you." }]);
+
+    const mainChat = await postJson("/api/chats", { title: "Greeting Pick", characterId: character.id, greetingIndex: 0 });
+    const mainTimeline = await (await requestJson(`/api/chats/${mainChat.id}/timeline`)).json();
+    expect(mainTimeline).toMatchObject([{ role: "assistant", content: "The lantern flickers." }]);
+  });
+
+  it("ranks scenes by embedding similarity and re-embeds only changed content", async () => {
+    await updateSettings({ ragProviderId: "mock-openai", ragModel: "" });
+    const unconfigured = await requestJson("/api/rag/semantic-search", { method: "POST", body: { query: "betrayal", scope: "scenes" } });
+    expect(unconfigured.status).toBe(400);
+    const badScope = await requestJson("/api/rag/semantic-search", { method: "POST", body: { query: "betrayal", scope: "lorebooks" } });
+    expect(badScope.status).toBe(400);
+
+    await updateSettings({ ragProviderId: "mock-openai", ragModel: "mock-embed" });
+    const project = await postJson("/api/writer/projects", { name: "Semantic Novel", description: "Semantic search project" });
+    const chapter = await postJson("/api/writer/chapters", { projectId: project.id, title: "Act One" });
+    const insertScene = db.prepare(
+      "INSERT INTO writer_scenes (id, chapter_id, title, content, goals, conflicts, outcomes, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    );
+    const foreshadow = `scene-foreshadow-${project.id}`;
+    const harbor = `scene-harbor-${project.id}`;
+    insertScene.run(foreshadow, chapter.id, "The Toast", "Ilya hides a dagger under the table while raising his glass.", "", "", "", "2026-03-01T00:00:00.000Z");
+    insertScene.run(harbor, chapter.id, "The Harbor", "The ships wait out the weather.", "", "", "", "2026-03-02T00:00:00.000Z");
+
+    const search = () => requestJson("/api/rag/semantic-search", {
+      method: "POST",
+      body: { query: "the scene where the betrayal is foreshadowed", scope: "scenes", limit: 3 }
+    }).then((response) => response.json());
+    const first = await search();
+    expect(first[0]).toMatchObject({ scope: "scenes", id: foreshadow, title: "The Toast", parentId: chapter.id });
+    expect(first[0].similarity).toBeGreaterThan(0.9);
+    expect(first.find((hit: { id: string }) => hit.id === harbor)?.similarity ?? 0).toBeLessThan(0.2);
+
+    embeddedInputCount = 0;
+    await search();
+    expect(embeddedInputCount).toBe(1);
+
+    await requestJson(`/api/writer/scenes/${harbor}`, { method: "PATCH", body: { content: "A traitor slips aboard before the storm." } });
+    embeddedInputCount = 0;
+    const afterEdit = await search();
+    expect(embeddedInputCount).toBe(2);
+    expect(afterEdit.map((hit: { id: string }) => hit.id).slice(0, 2).sort()).toEqual([foreshadow, harbor].sort());
+    await updateSettings({ ragModel: "" });
+  });
+  it("stores a typed beat sheet and drafts chapters with their planned beats", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model"
+    });
+
+    const project = await postJson("/api/writer/projects", {
+      name: "Beat Novel",
+      description: "Beat sheet project"
+    });
+    const chapter = await postJson("/api/writer/chapters", { projectId: project.id, title: "Inciting Incident" });
+    const empty = await parseJsonResponse(
+      `/api/writer/projects/${project.id}/beats`,
+      await fetch(`${baseUrl}/api/writer/projects/${project.id}/beats`)
+    );
+    expect(empty).toEqual({ beats: [] });
+
+    const saved = await parseJsonResponse(
+      `/api/writer/projects/${project.id}/beats`,
+      await requestJson(`/api/writer/projects/${project.id}/beats`, {
+        method: "PUT",
+        body: {
+          beats: [
+            { title: "The letter arrives", summary: "Mara finds her brother's letter", targetChapterId: chapter.id },
+            { title: "Refusal", summary: "She burns it", targetChapterId: "missing-chapter" },
+            { title: "", summary: "" }
+          ]
+        }
+      })
+    );
+    expect(saved.beats).toHaveLength(2);
+    expect(saved.beats[0]).toMatchObject({ title: "The letter arrives", targetChapterId: chapter.id });
+    expect(saved.beats[1]).toMatchObject({ title: "Refusal", targetChapterId: null });
+    expect(typeof saved.beats[0].id).toBe("string");
+
+    const loaded = await parseJsonResponse(
+      `/api/writer/projects/${project.id}/beats`,
+      await fetch(`${baseUrl}/api/writer/projects/${project.id}/beats`)
+    );
+    expect(loaded).toEqual(saved);
+
+    lastBeatDraftPromptText = "";
+    await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, { prompt: "Open the book" });
+    expect(lastBeatDraftPromptText).toContain("The letter arrives: Mara finds her brother's letter");
+    expect(lastBeatDraftPromptText).not.toContain("She burns it");
+
+    lastBeatDraftPromptText = "";
+    await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, { prompt: "Next", beatId: saved.beats[1].id });
+    expect(lastBeatDraftPromptText).toContain("Refusal: She burns it");
+    expect(lastBeatDraftPromptText).not.toContain("The letter arrives");
+
+    const missing = await requestJson("/api/writer/projects/missing-project/beats", { method: "PUT", body: { beats: [] } });
+    expect(missing.status).toBe(404);
   });
 
-  it("discovers and uses a Whisper-compatible STT endpoint", async () => {
+  it("generates an outline into new chapters with optional stub scenes", async () => {
     await updateSettings({
-      sttSource: "whisper",
-      sttBaseUrl: mockProviderBaseUrl,
-      sttApiKey: "test-key",
-      sttModel: "whisper-1",
-      sttLanguage: "en"
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model"
     });
 
-    const modelsResponse = await requestJson("/api/settings/stt/models", {
+    const project = await postJson("/api/writer/projects", {
+      name: "Outline Novel",
+      description: "Outline project"
+    });
+    const existing = await postJson("/api/writer/chapters", { projectId: project.id, title: "Prologue" });
+
+    const outlined = await postJson(`/api/writer/projects/${project.id}/generate-outline`, {
+      premise: "outline-mock-premise: a lighthouse keeper's secret",
+      withScenes: true
+    });
+    expect(outlined.chapters.map((chapter: { title: string; position: number }) => [chapter.title, chapter.position])).toEqual([
+      ["The Letter", existing.position + 1],
+      ["Refusal", existing.position + 2]
+    ]);
+    expect(outlined.scenes).toHaveLength(2);
+    expect(outlined.scenes[0]).toMatchObject({ chapterId: outlined.chapters[0].id, goals: "Mara finds the letter.", position: 1 });
+
+    const notAnOutline = await requestJson(`/api/writer/projects/${project.id}/generate-outline`, {
       method: "POST",
-      body: {}
+      body: { premise: "plain premise" }
     });
-    expect(modelsResponse.ok).toBe(true);
-    expect(await modelsResponse.json()).toContainEqual({ id: "mock-model" });
+    expect(notAnOutline.status).toBe(502);
 
-    const transcript = await postJson("/api/live/transcribe", {
-      audioBase64: Buffer.from("fake webm audio").toString("base64"),
-      mimeType: "audio/webm;codecs=opus",
-      filename: "live-recording.webm"
+    const noPremise = await requestJson(`/api/writer/projects/${project.id}/generate-outline`, { method: "POST", body: {} });
+    expect(noPremise.status).toBe(400);
+
+    // The remote provider saved by the draft gating test is not localhost, so Full Local Mode refuses it.
+    await updateSettings({
+      activeProviderId: "remote-writer-provider",
+      activeModel: "remote-model",
+      fullLocalMode: true
     });
-    expect(transcript).toEqual({ text: "Recognized live speech" });
-    expect(lastSttMultipartBody).toContain('name="model"');
-    expect(lastSttMultipartBody).toContain("whisper-1");
-    expect(lastSttMultipartBody).toContain('name="language"');
-    expect(lastSttMultipartBody).toContain("fake webm audio");
+    const blocked = await requestJson(`/api/writer/projects/${project.id}/generate-outline`, {
+      method: "POST",
+      body: { premise: "outline-mock-premise" }
+    });
+    expect(blocked.status).toBe(400);
+    expect(await blocked.json()).toEqual({ error: "Provider blocked by Full Local Mode" });
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model", fullLocalMode: false });
   });
 
-  it("generates writer drafts through the mock provider and exports markdown", async () => {
+  it("stores model scene summaries and falls back to the opening lines offline", async () => {
     await updateSettings({
       activeProviderId: "mock-openai",
       activeModel: "mock-model"
     });
 
     const project = await postJson("/api/writer/projects", {
-      name: "Mock Novel",
-      description: "Integration project"
+      name: "Summary Novel",
+      description: "Scene summary project"
     });
-    const chapter = await postJson("/api/writer/chapters", {
-      projectId: project.id,
-      title: "Chapter One"
+    const chapter = await postJson("/api/writer/chapters", { projectId: project.id, title: "Summary Chapter" });
+    const scene = await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, { prompt: "Summarize me" });
+    await requestJson(`/api/writer/scenes/${scene.id}`, {
+      method: "PATCH",
+      body: { content: "Line one.\nLine two.\n\nLine three.\nLine four." }
     });
+    const summarize = async (query = "") => parseJsonResponse(
+      `/api/writer/scenes/${scene.id}/summarize${query}`,
+      await fetch(`${baseUrl}/api/writer/scenes/${scene.id}/summarize${query}`)
+    );
 
-    const draft = await postJson(`/api/writer/chapters/${chapter.id}/generate-draft`, {
-      prompt: "Write the opening scene"
-    });
-    expect(draft.content).toBe("MOCK RESPONSE");
+    expect(await summarize("?sentences=2")).toBe("MOCK RESPONSE");
+    db.prepare("UPDATE writer_scene_summaries SET summary = 'CACHED SUMMARY' WHERE scene_id = ?").run(scene.id);
+    expect(await summarize("?sentences=2")).toBe("CACHED SUMMARY");
+    expect(await summarize("?sentences=2&force=1")).toBe("MOCK RESPONSE");
 
-    const markdownResponse = await fetch(`${baseUrl}/api/writer/projects/${project.id}/export/markdown/download`, {
-      method: "POST"
-    });
-    expect(markdownResponse.ok).toBe(true);
-    expect(markdownResponse.headers.get("content-type")).toContain("text/markdown");
-    const markdown = await markdownResponse.text();
-    expect(markdown).toContain("# Mock Novel");
-    expect(markdown).toContain("## Chapter One");
-    expect(markdown).toContain("MOCK RESPONSE");
+    await updateSettings({ activeProviderId: null, activeModel: null });
+    expect(await summarize()).toBe("Line one.\nLine two.\nLine three.");
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model" });
+
+    await requestJson(`/api/writer/scenes/${scene.id}`, { method: "DELETE" });
+    expect(db.prepare("SELECT COUNT(*) AS count FROM writer_scene_summaries WHERE scene_id = ?").get(scene.id)).toEqual({ count: 0 });
   });
 
   it("runs writer summary lenses end-to-end and reuses the cached result", async () => {
@@ -2049,6 +4202,33 @@ process.stdin.on("data", (chunk) => {
     expect(secondRun.lens.output).toBe("MOCK RESPONSE");
   });
 
+  it("leaves out stream_options when the usage request is turned off", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      apiParamPolicy: { openai: { streamUsage: false } }
+    });
+    try {
+      const created = await postJson("/api/chats", { title: "No Usage Chat" });
+      const sendResponse = await requestJson(`/api/chats/${created.id}/send`, {
+        method: "POST",
+        body: { content: "Stream without usage" }
+      });
+      expect(sendResponse.ok).toBe(true);
+      expect(await sendResponse.text()).toContain("STREAM RESPONSE");
+
+      const timeline = await parseJsonResponse(
+        `/api/chats/${created.id}/timeline`,
+        await fetch(`${baseUrl}/api/chats/${created.id}/timeline`)
+      );
+      expect(timeline[1]).toMatchObject({ content: "MOCK STREAM RESPONSE" });
+      expect(timeline[1].promptTokens).toBeUndefined();
+    } finally {
+      await updateSettings({ apiParamPolicy: { openai: { streamUsage: true } } });
+    }
+  });
+
   it("streams chat completions with an active provider and persists regenerated assistant output", async () => {
     await updateSettings({
       activeProviderId: "mock-openai",
@@ -2077,15 +4257,20 @@ process.stdin.on("data", (chunk) => {
     expect(timelineAfterSend).toHaveLength(2);
     expect(timelineAfterSend[1]).toMatchObject({
       role: "assistant",
-      content: "MOCK STREAM RESPONSE"
+      content: "MOCK STREAM RESPONSE",
+      tokenCount: 3,
+      promptTokens: 42,
+      completionTokens: 3
     });
 
+    lastChatTemplateMessages = [];
     const regenerateResponse = await requestJson(`/api/chats/${created.id}/regenerate`, {
       method: "POST",
-      body: {}
+      body: { userPersona: { name: "Regen Persona", description: "Keeps the persona on regenerate" } }
     });
     expect(regenerateResponse.ok).toBe(true);
     const regenerateBody = await regenerateResponse.text();
+    expect(String(lastChatTemplateMessages[0]?.content || "")).toContain("Keeps the persona on regenerate");
     expect(regenerateBody).toContain("\"type\":\"delta\"");
     expect(regenerateBody).toContain("MOCK ");
     expect(regenerateBody).toContain("STREAM RESPONSE");
@@ -2098,7 +4283,8 @@ process.stdin.on("data", (chunk) => {
     expect(timelineAfterRegenerate).toHaveLength(2);
     expect(timelineAfterRegenerate[1]).toMatchObject({
       role: "assistant",
-      content: "MOCK STREAM RESPONSE"
+      content: "MOCK STREAM RESPONSE",
+      parentId: timelineAfterRegenerate[0].id
     });
   });
 
@@ -2163,6 +4349,78 @@ process.stdin.on("data", (chunk) => {
     });
   });
 
+  it("rejects unknown theme, density and censorship values and clamps the font scale", async () => {
+    const before = await (await fetch(`${baseUrl}/api/settings`)).json() as Record<string, unknown>;
+
+    const rejected = await requestJson("/api/settings", {
+      method: "PATCH",
+      body: { theme: "drak", density: "compact", fontScale: 1.2 }
+    });
+    expect(rejected.status).toBe(400);
+    expect((await rejected.json()).error).toBe("theme must be one of: dark, light, custom");
+    const unchanged = await (await fetch(`${baseUrl}/api/settings`)).json() as Record<string, unknown>;
+    expect(unchanged).toMatchObject({ theme: before.theme, density: before.density, fontScale: before.fontScale });
+
+    const bothInvalid = await requestJson("/api/settings", { method: "PATCH", body: { density: "tight", censorshipMode: "Off" } });
+    expect(bothInvalid.status).toBe(400);
+    expect((await bothInvalid.json()).error).toContain("censorshipMode must be one of: Filtered, Unfiltered");
+
+    const accepted = await requestJson("/api/settings", { method: "PATCH", body: { theme: "light", fontScale: 9 } });
+    expect(accepted.ok).toBe(true);
+    expect(await accepted.json()).toMatchObject({ theme: "light", density: before.density, fontScale: 1.5 });
+    const lowered = await requestJson("/api/settings", { method: "PATCH", body: { fontScale: 0.1 } });
+    expect((await lowered.json()).fontScale).toBe(0.65);
+
+    await updateSettings({ theme: before.theme, fontScale: before.fontScale });
+  });
+
+  it("exports settings with masked provider keys and merges an imported file", async () => {
+    const before = await (await fetch(`${baseUrl}/api/settings`)).json() as Record<string, unknown>;
+    await postJson("/api/providers", { id: "transfer-provider", name: "Transfer", baseUrl: "https://transfer.example.com/v1", apiKey: "sk-transfer-secret-key" });
+
+    const exported = await postJson("/api/settings/export", {}) as {
+      format: string;
+      settings: Record<string, unknown>;
+      providers: Array<{ id: string; apiKey?: string; apiKeyMasked?: string }>;
+    };
+    expect(exported.format).toBe("vellium-settings");
+    expect(JSON.stringify(exported)).not.toContain("sk-transfer-secret-key");
+    expect(exported.settings.mcpServers).toBeUndefined();
+    const withKeys = await postJson("/api/settings/export", { includeKeys: true }) as typeof exported;
+    expect(withKeys.providers.find((provider) => provider.id === "transfer-provider")?.apiKey).toBe("sk-transfer-secret-key");
+
+    const invalid = await requestJson("/api/settings/import", {
+      method: "POST",
+      body: { data: { ...exported, settings: { theme: "drak" } } }
+    });
+    expect(invalid.status).toBe(400);
+
+    const imported = await requestJson("/api/settings/import", {
+      method: "POST",
+      body: {
+        data: {
+          ...exported,
+          settings: { density: "compact", responseLanguage: "French", notASetting: true },
+          providers: [
+            { id: "transfer-provider", name: "Transfer Renamed", baseUrl: "https://transfer.example.com/v1", apiKeyMasked: "sk-t***-key" },
+            { id: "transfer-invalid", name: "Invalid", baseUrl: "javascript:alert(1)" }
+          ]
+        }
+      }
+    });
+    expect(imported.ok).toBe(true);
+    const result = await imported.json() as { settings: Record<string, unknown>; providers: number; ignoredFields: string[] };
+    expect(result.providers).toBe(1);
+    expect(result.ignoredFields).toEqual(["notASetting", "providers[1]"]);
+    expect(db.prepare("SELECT id FROM providers WHERE id = ?").get("transfer-invalid")).toBeUndefined();
+    expect(result.settings).toMatchObject({ density: "compact", responseLanguage: "French", theme: before.theme });
+    const provider = db.prepare("SELECT name, api_key_cipher FROM providers WHERE id = ?").get("transfer-provider");
+    expect(provider).toEqual({ name: "Transfer Renamed", api_key_cipher: "sk-transfer-secret-key" });
+
+    db.prepare("DELETE FROM providers WHERE id = ?").run("transfer-provider");
+    await updateSettings({ density: before.density, responseLanguage: before.responseLanguage });
+  });
+
   it("keeps agent routes disabled until the feature flag is enabled", async () => {
     await updateSettings({
       agentsEnabled: false
@@ -3807,6 +6065,24 @@ process.stdin.on("data", (chunk) => {
     expect(docxResponse.headers.get("content-disposition")).toContain(".docx");
     const docxBuffer = Buffer.from(await docxResponse.arrayBuffer());
     expect(docxBuffer.length).toBeGreaterThan(100);
+    expect(docxBuffer.subarray(0, 2).toString("latin1")).toBe("PK");
+    const docxHtml = (await mammoth.convertToHtml({ buffer: docxBuffer })).value;
+    expect(docxHtml).toContain("<h1>Docx Novel</h1>");
+    expect(docxHtml).toContain("<h2>Docx Chapter</h2>");
+
+    const exportedPath = await postJson(`/api/writer/projects/${project.id}/export/docx`, {});
+    expect(String(exportedPath)).toMatch(/\.docx$/);
+    const unzippedDir = mkdtempSync(join(tmpdir(), "slv-docx-"));
+    try {
+      await extractZip(String(exportedPath), { dir: unzippedDir });
+      expect(existsSync(join(unzippedDir, "word", "document.xml"))).toBe(true);
+      expect(readFileSync(join(unzippedDir, "word", "document.xml"), "utf-8")).toContain("Docx Chapter");
+    } finally {
+      rmSync(unzippedDir, { recursive: true, force: true });
+    }
+    const exportTypes = db.prepare("SELECT export_type FROM writer_exports WHERE project_id = ?")
+      .all(project.id) as Array<{ export_type: string }>;
+    expect(exportTypes.map((row) => row.export_type)).toEqual(["docx", "docx"]);
   });
 
   it("routes provider preview test and models requests to the preview handlers instead of :id routes", async () => {
@@ -3828,6 +6104,19 @@ process.stdin.on("data", (chunk) => {
     const previewTest = await postJson("/api/providers/preview/test", previewPayload);
     expect(previewTest).toEqual({ ok: true });
 
+    const missingCatalog = await requestJson("/api/providers/preview/models", {
+      method: "POST",
+      body: { ...previewPayload, baseUrl: `${mockProviderBaseUrl}/missing-catalog` }
+    });
+    expect(missingCatalog.status).toBe(400);
+    expect(await missingCatalog.json()).toMatchObject({
+      error: `Model endpoint returned HTTP 404: ${mockProviderBaseUrl}/missing-catalog/v1/models — not found`,
+      code: "provider",
+      providerStatus: 404,
+      upstreamStatus: 404,
+      upstreamBody: "not found"
+    });
+
     const blockedPreviewTest = await postJson("/api/providers/preview/test", {
       ...previewPayload,
       baseUrl: "https://example.com/v1",
@@ -3835,10 +6124,291 @@ process.stdin.on("data", (chunk) => {
     });
     expect(blockedPreviewTest).toEqual({
       ok: false,
+      error: "Provider is set to Local-only. Disable Local-only for external URLs.",
+      code: "not_configured"
+    });
+  });
+
+  it("tests saved provider connections against the live model endpoint", async () => {
+    const providerPayload = {
+      id: "connection-test-provider",
+      name: "Connection Test Provider",
+      baseUrl: `${mockProviderBaseUrl}/v1`,
+      apiKey: "test-key",
+      proxyUrl: null,
+      fullLocalOnly: false,
+      providerType: "openai",
+      adapterId: null,
+      manualModels: ["manual-model"]
+    };
+    await postJson("/api/providers", providerPayload);
+
+    const connected = await postJson("/api/providers/connection-test-provider/test", {});
+    expect(connected).toMatchObject({ ok: true, reachable: true, authorized: true, status: 200 });
+    expect(connected.latencyMs).toBeGreaterThanOrEqual(0);
+
+    await postJson("/api/providers", { ...providerPayload, apiKey: "revoked-key" });
+    const rejected = await postJson("/api/providers/connection-test-provider/test", {});
+    expect(rejected).toMatchObject({ ok: false, reachable: true, authorized: false, status: 401 });
+
+    await postJson("/api/providers", { ...providerPayload, baseUrl: "https://example.com/v1", fullLocalOnly: true });
+    const blocked = await postJson("/api/providers/connection-test-provider/test", {});
+    expect(blocked).toEqual({
+      ok: false,
+      reachable: false,
+      authorized: false,
+      status: null,
+      latencyMs: null,
+      blockedBy: "localOnly",
       error: "Provider is set to Local-only. Disable Local-only for external URLs."
     });
   });
 
+  it("surfaces provider errors from desktop pet replies instead of an empty reply", async () => {
+    await postJson("/api/providers", {
+      id: "pet-missing-endpoint",
+      name: "Pet Missing Endpoint",
+      baseUrl: `${mockProviderBaseUrl}/missing-endpoint/v1`,
+      apiKey: "test-key",
+      proxyUrl: null,
+      fullLocalOnly: false,
+      providerType: "openai",
+      adapterId: null,
+      manualModels: ["mock-model"]
+    });
+    await updateSettings({ activeProviderId: "pet-missing-endpoint", activeModel: "mock-model", fullLocalMode: false });
+    try {
+      const response = await requestJson("/api/chats/desktop-pet/reply", {
+        method: "POST",
+        body: { content: "Hello pet", pet: { name: "Pip" } }
+      });
+      expect(response.ok).toBe(false);
+      expect((await response.json()).error).toContain("404");
+    } finally {
+      await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model" });
+    }
+  });
+
+  it("persists custom provider headers and sends them with model requests", async () => {
+    const providerPayload = {
+      id: "custom-headers-provider",
+      name: "Custom Headers Provider",
+      baseUrl: `${mockProviderBaseUrl}/v1`,
+      apiKey: "test-key",
+      proxyUrl: null,
+      fullLocalOnly: false,
+      providerType: "openai",
+      adapterId: null,
+      manualModels: []
+    };
+    const saved = await postJson("/api/providers", {
+      ...providerPayload,
+      headers: { "X-Title": "Vellium", authorization: "Bearer gateway-token", "Bad Header": "ignored" }
+    });
+    expect(saved.headers).toEqual({ "X-Title": "********", authorization: "Bear***oken" });
+    const listed = await parseJsonResponse("/api/providers", await fetch(`${baseUrl}/api/providers`));
+    expect(listed.find((provider: { id: string }) => provider.id === "custom-headers-provider")?.headers)
+      .toEqual({ "X-Title": "********", authorization: "Bear***oken" });
+
+    await parseJsonResponse(
+      "/api/providers/custom-headers-provider/models",
+      await fetch(`${baseUrl}/api/providers/custom-headers-provider/models`)
+    );
+    expect(lastModelsRequestHeaders["x-title"]).toBe("Vellium");
+    expect(lastModelsRequestHeaders.authorization).toBe("Bearer gateway-token");
+
+    const resaved = await postJson("/api/providers", providerPayload);
+    expect(resaved.headers).toEqual({ "X-Title": "********", authorization: "Bear***oken" });
+
+    await postJson("/api/providers", { ...providerPayload, headers: { ...saved.headers, "X-Title": "Renamed" } });
+    const stored = db.prepare("SELECT extra_headers FROM providers WHERE id = ?")
+      .get("custom-headers-provider") as { extra_headers: string };
+    expect(JSON.parse(stored.extra_headers)).toEqual({ "X-Title": "Renamed", authorization: "Bearer gateway-token" });
+  });
+
+  it("stores a provider's requests-per-minute limit and keeps it when a save omits it", async () => {
+    const providerPayload = {
+      id: "rate-limited-provider",
+      name: "Rate Limited Provider",
+      baseUrl: `${mockProviderBaseUrl}/v1`,
+      apiKey: "test-key",
+      proxyUrl: null,
+      fullLocalOnly: false,
+      providerType: "openai",
+      adapterId: null,
+      manualModels: []
+    };
+    const saved = await postJson("/api/providers", { ...providerPayload, rateLimitRpm: 30 });
+    expect(saved.rateLimitRpm).toBe(30);
+
+    const invalid = await requestJson("/api/providers", { method: "POST", body: { ...providerPayload, rateLimitRpm: 2.5 } });
+    expect(invalid.status).toBe(400);
+
+    const resaved = await postJson("/api/providers", providerPayload);
+    expect(resaved.rateLimitRpm).toBe(30);
+    const cleared = await postJson("/api/providers", { ...providerPayload, rateLimitRpm: null });
+    expect(cleared.rateLimitRpm).toBeNull();
+  });
+
+  it("caches fetched model lists and refreshes them on request", async () => {
+    await postJson("/api/providers", {
+      id: "cached-models-provider",
+      name: "Cached Models Provider",
+      baseUrl: `${mockProviderBaseUrl}/v1`,
+      apiKey: "test-key",
+      proxyUrl: null,
+      fullLocalOnly: false,
+      providerType: "openai",
+      adapterId: null,
+      manualModels: [],
+      headers: { "X-Title": "cache-check" }
+    });
+    const fetchModels = async (query = "") => parseJsonResponse(
+      "/api/providers/cached-models-provider/models",
+      await fetch(`${baseUrl}/api/providers/cached-models-provider/models${query}`)
+    );
+    const expected = [{ id: "mock-model" }, { id: "mock-secondary-model" }];
+
+    lastModelsRequestHeaders = {};
+    expect(await fetchModels()).toEqual(expected);
+    expect(lastModelsRequestHeaders["x-title"]).toBe("cache-check");
+
+    lastModelsRequestHeaders = {};
+    expect(await fetchModels()).toEqual(expected);
+    expect(lastModelsRequestHeaders["x-title"]).toBeUndefined();
+
+    expect(await fetchModels("?refresh=1")).toEqual(expected);
+    expect(lastModelsRequestHeaders["x-title"]).toBe("cache-check");
+
+    db.prepare("UPDATE providers SET base_url = ? WHERE id = ?")
+      .run(`${mockProviderBaseUrl}/missing-catalog`, "cached-models-provider");
+    expect(await fetchModels("?refresh=1")).toEqual(expected);
+  });
+
+  it("lists and streams from a native Gemini provider", async () => {
+    await postJson("/api/providers", {
+      id: "gemini-provider",
+      name: "Gemini",
+      baseUrl: `${mockProviderBaseUrl}/gemini/v1beta`,
+      apiKey: "AIza-test",
+      proxyUrl: null,
+      fullLocalOnly: false,
+      providerType: "gemini",
+      adapterId: null,
+      manualModels: []
+    });
+    const models = await requestJson("/api/providers/gemini-provider/models");
+    expect(await models.json()).toEqual([{ id: "gemini-test", contextLength: 32768 }]);
+    expect(await postJson("/api/providers/gemini-provider/test", {})).toMatchObject({ ok: true });
+
+    await updateSettings({ activeProviderId: "gemini-provider", activeModel: "gemini-test" });
+    const chat = await postJson("/api/chats", { title: "Gemini Chat" });
+    const withTools = await requestJson(`/api/chats/${chat.id}/send`, {
+      method: "POST",
+      body: { content: "roll a die", tools: [{ type: "function", function: { name: "roll_dice" } }] }
+    });
+    expect(withTools.status).toBe(400);
+    expect(await withTools.json()).toEqual({ error: "Client tools need an OpenAI-compatible provider", code: "validation" });
+    expect(db.prepare("SELECT COUNT(*) AS count FROM messages WHERE chat_id = ?").get(chat.id)).toEqual({ count: 0 });
+    const sent = await fetch(`${baseUrl}/api/chats/${chat.id}/send`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ content: "Is the tide coming in?" })
+    });
+    const events = await sent.text();
+    expect(events).toContain('"delta":"The tide is "');
+    expect(lastGeminiRequest?.url).toBe("/gemini/v1beta/models/gemini-test:streamGenerateContent?alt=sse&key=AIza-test");
+    const contents = lastGeminiRequest?.body.contents as Array<{ role: string; parts: Array<{ text?: string }> }>;
+    expect(contents[contents.length - 1]).toMatchObject({ role: "user" });
+    expect(JSON.stringify(contents)).toContain("Is the tide coming in?");
+
+    const reply = db.prepare(
+      "SELECT content FROM messages WHERE chat_id = ? AND role = 'assistant' AND deleted = 0 ORDER BY sort_order DESC LIMIT 1"
+    ).get(chat.id) as { content: string };
+    expect(reply.content).toBe("The tide is turning.");
+    await updateSettings({ activeProviderId: "mock-openai", activeModel: "mock-model" });
+  });
+
+  it("sends the API key as a query parameter for providers in query auth mode", async () => {
+    const payload = {
+      id: "query-auth-provider",
+      name: "Query Auth Provider",
+      baseUrl: `${mockProviderBaseUrl}/query-auth/v1`,
+      apiKey: "query-secret",
+      proxyUrl: null,
+      fullLocalOnly: false,
+      providerType: "openai",
+      adapterId: null,
+      manualModels: [],
+      authMode: "query",
+      authParam: "api_key"
+    };
+    const saved = await postJson("/api/providers", payload);
+    expect(saved).toMatchObject({ authMode: "query", authParam: "api_key" });
+
+    const models = await requestJson("/api/providers/query-auth-provider/models");
+    expect(await models.json()).toEqual([{ id: "query-model" }]);
+    const tested = await postJson("/api/providers/query-auth-provider/test", {});
+    expect(tested).toMatchObject({ ok: true, authorized: true });
+
+    const bearer = await postJson("/api/providers/preview/test", { ...payload, authMode: "bearer" });
+    expect(bearer.ok).toBe(false);
+    const kept = await postJson("/api/providers", { ...payload, name: "Renamed", authMode: undefined, authParam: undefined });
+    expect(kept).toMatchObject({ name: "Renamed", authMode: "query", authParam: "api_key" });
+  });
+
+  it("filters and counts a provider model list server-side", async () => {
+    const search = (query: string) => requestJson(`/api/providers/mock-openai/models/search${query}`);
+
+    const filtered = await search("?filter=SECONDARY");
+    expect(filtered.status).toBe(200);
+    expect(await filtered.json()).toEqual({ models: [{ id: "mock-secondary-model" }], total: 2, matched: 1 });
+
+    const limited = await search("?filter=mock&limit=1");
+    expect(await limited.json()).toEqual({ models: [{ id: "mock-model" }], total: 2, matched: 2 });
+
+    const missing = await search("?filter=x");
+    expect(await missing.json()).toMatchObject({ models: [], matched: 0 });
+    expect((await requestJson("/api/providers/no-such-provider/models/search")).status).toBe(404);
+  });
+
+  it("stores favorite and hidden model flags and applies them to fetched lists", async () => {
+    await postJson("/api/providers", {
+      id: "model-prefs-provider",
+      name: "Model Prefs Provider",
+      baseUrl: `${mockProviderBaseUrl}/v1`,
+      apiKey: "test-key",
+      proxyUrl: null,
+      fullLocalOnly: false,
+      providerType: "openai",
+      adapterId: null,
+      manualModels: []
+    });
+    const setFlag = (flag: "favorite" | "hidden", body: Record<string, unknown>) =>
+      requestJson(`/api/providers/model-prefs-provider/models/${flag}`, { method: "PUT", body });
+    const fetchModels = async (query = "") => (await requestJson(`/api/providers/model-prefs-provider/models${query}`)).json();
+
+    const favorite = await setFlag("favorite", { modelId: "mock-secondary-model", favorite: true });
+    expect(await favorite.json()).toEqual({ modelId: "mock-secondary-model", favorite: true, hidden: false });
+    await setFlag("hidden", { modelId: "mock-model", hidden: true });
+
+    expect(await fetchModels()).toEqual([{ id: "mock-secondary-model", favorite: true }]);
+    expect(await fetchModels("?includeHidden=1")).toEqual([
+      { id: "mock-model", hidden: true },
+      { id: "mock-secondary-model", favorite: true }
+    ]);
+
+    await setFlag("hidden", { modelId: "mock-model", hidden: false });
+    const search = await (await requestJson("/api/providers/model-prefs-provider/models/search")).json();
+    expect(search.models.map((model: { id: string }) => model.id)).toEqual(["mock-secondary-model", "mock-model"]);
+    expect(db.prepare("SELECT COUNT(*) AS count FROM model_prefs WHERE provider_id = ?").get("model-prefs-provider"))
+      .toEqual({ count: 1 });
+
+    expect((await setFlag("favorite", { modelId: "mock-model" })).status).toBe(400);
+    expect((await requestJson("/api/providers/missing/models/hidden", { method: "PUT", body: { modelId: "x", hidden: true } })).status)
+      .toBe(404);
+  });
+
   it("uses manual fallback models when a provider model endpoint cannot be loaded", async () => {
     const fallbackPayload = {
       baseUrl: `${mockProviderBaseUrl}/missing-catalog`,
@@ -4079,6 +6649,82 @@ process.stdin.on("data", (chunk) => {
     });
   });
 
+  it("finishes and saves the reply when the client connection drops mid-stream", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      fullLocalMode: false,
+      mcpServers: []
+    });
+
+    const created = await postJson("/api/chats", { title: "Dropped Connection Chat" });
+    const clientAbort = new AbortController();
+    const sendResponse = await fetch(`${baseUrl}/api/chats/${created.id}/send`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ content: "client-drop-stream-check" }),
+      signal: clientAbort.signal
+    });
+    expect(sendResponse.ok).toBe(true);
+    await sleep(100);
+    clientAbort.abort();
+    await sleep(600);
+
+    const timeline = await parseJsonResponse(
+      `/api/chats/${created.id}/timeline`,
+      await fetch(`${baseUrl}/api/chats/${created.id}/timeline`)
+    );
+    expect(timeline).toHaveLength(2);
+    expect(timeline[1]).toMatchObject({ role: "assistant", content: "Started before the drop and finished after it" });
+    expect(timeline[1].truncated).toBeFalsy();
+  });
+
+  it("keeps partial chat and writer output as truncated when a stream is cancelled", async () => {
+    await updateSettings({
+      activeProviderId: "mock-openai",
+      activeModel: "mock-model",
+      toolCallingEnabled: false,
+      fullLocalMode: false,
+      mcpServers: []
+    });
+
+    const created = await postJson("/api/chats", { title: "Cancel Partial Chat" });
+    const sendResponsePromise = requestJson(`/api/chats/${created.id}/send`, {
+      method: "POST",
+      body: { content: "cancel-partial-stream-check" }
+    });
+    await sleep(200);
+    expect(await postJson(`/api/chats/${created.id}/abort`, {})).toMatchObject({ ok: true, interrupted: true });
+    const sendBody = await (await sendResponsePromise).text();
+    expect(sendBody).toContain("\"interrupted\":true");
+    const timeline = await parseJsonResponse(
+      `/api/chats/${created.id}/timeline`,
+      await fetch(`${baseUrl}/api/chats/${created.id}/timeline`)
+    );
+    expect(timeline).toHaveLength(2);
+    expect(timeline[1]).toMatchObject({ role: "assistant", content: "Partial reply before cancel", truncated: true });
+
+    const project = await postJson("/api/writer/projects", { name: "Cancelled Draft", description: "" });
+    const chapter = await postJson("/api/writer/chapters", { projectId: project.id, title: "Cut Short" });
+    expect(await postJson(`/api/writer/streams/${chapter.id}/abort`, {})).toEqual({ ok: true, interrupted: false });
+    const draftResponsePromise = requestJson(`/api/writer/chapters/${chapter.id}/generate-draft`, {
+      method: "POST",
+      body: { prompt: "cancel-partial-stream-check", stream: true }
+    });
+    await sleep(200);
+    expect(await postJson(`/api/writer/streams/${chapter.id}/abort`, {})).toEqual({ ok: true, interrupted: true });
+    const events = (await (await draftResponsePromise).text())
+      .split("\n")
+      .filter((line) => line.startsWith("data: "))
+      .map((line) => JSON.parse(line.slice(6)) as { type: string; truncated?: boolean; interrupted?: boolean; scene?: { content: string } });
+    expect(events.find((event) => event.type === "scene")).toMatchObject({
+      truncated: true,
+      scene: { content: "Partial reply before cancel" }
+    });
+    expect(events.at(-1)).toMatchObject({ type: "done", interrupted: true });
+  });
+
   it("round-trips DOCX export back into a new imported writer project", async () => {
     await updateSettings({
       activeProviderId: "mock-openai",
@@ -9,6 +9,7 @@ import { fileURLToPath } from "url";
 import { DATA_DIR, DEFAULT_SETTINGS, UPLOADS_DIR, db, newId } from "../db.js";
 import accountRoutes from "../routes/account.js";
 import agentRoutes from "../routes/agents.js";
+import backupRoutes from "../routes/backup.js";
 import characterRoutes from "../routes/characters.js";
 import chatRoutes from "../routes/chats.js";
 import lorebookRoutes from "../routes/lorebooks.js";
@@ -24,7 +25,9 @@ import rpRoutes from "../routes/rp.js";
 import settingsRoutes from "../routes/settings.js";
 import updateRoutes from "../routes/updates.js";
 import writerRoutes from "../routes/writer.js";
+import { collectAppStatus } from "../services/appStatus.js";
 import { isAllowedRequestOrigin } from "./requestOrigin.js";
+import { requireUnlockedApi, trackAccountActivity } from "./accountLockGuard.js";
 import { buildPermissionsPolicy } from "./permissionsPolicy.js";
 
 const __dirname = dirname(fileURLToPath(import.meta.url));
@@ -290,6 +293,7 @@ function registerUploadRoute(app: express.Express) {
 function registerRoutes(app: express.Express) {
   app.use("/api/agents", agentRoutes);
   app.use("/api/account", accountRoutes);
+  app.use("/api/backup", backupRoutes);
   app.use("/api/settings", settingsRoutes);
   app.use("/api/updates", updateRoutes);
   app.use("/api/plugins", pluginRoutes);
@@ -369,6 +373,7 @@ export function createApp() {
     next();
   });
   app.use(express.json({ limit: "32mb" }));
+  app.use("/api", trackAccountActivity, requireUnlockedApi);
 
   app.use("/api/avatars", express.static(join(DATA_DIR, "avatars"), {
     setHeaders: setUploadResponseHeaders
@@ -384,6 +389,10 @@ export function createApp() {
     res.json({ status: "ok" });
   });
 
+  app.get("/api/status", (_req, res) => {
+    res.json(collectAppStatus(db));
+  });
+
   registerFrontendStatic(app);
 
   return app;
@@ -1,20 +1,26 @@
 import Database from "better-sqlite3";
 import { DEFAULT_SETTINGS, migrateDefaultSystemPrompt } from "./db/defaultSettings.js";
 import { applyMigrations } from "./db/migrations.js";
-import { ensureDataDirs, resolveDbPath, DATA_DIR, AVATARS_DIR, UPLOADS_DIR, PLUGINS_DIR, BUNDLED_PLUGINS_DIR } from "./db/paths.js";
+import { ensureDataDirs, resolveDbPath, DATA_DIR, AVATARS_DIR, UPLOADS_DIR, PLUGINS_DIR, BUNDLED_PLUGINS_DIR, BACKUPS_DIR } from "./db/paths.js";
 import { applySchema, applySchemaIndexes } from "./db/schema.js";
-import { hashSecret, isLocalhostUrl, maskApiKey, needsSecretRehash, newId, now, roughTokenCount, verifySecret } from "./db/utils.js";
+import { countTokens, hashSecret, isLocalhostUrl, maskApiKey, needsSecretRehash, newId, now, roughTokenCount, verifySecret } from "./db/utils.js";
 
 ensureDataDirs();
 
+// The one connection for the whole process; routes and services import it instead of opening their own.
 const db = new Database(resolveDbPath());
 db.pragma("journal_mode = WAL");
 db.pragma("foreign_keys = ON");
+// Streaming writes overlap reads from other requests; wait briefly instead of failing with SQLITE_BUSY.
+db.pragma("busy_timeout = 5000");
 
 applySchema(db);
 applySchemaIndexes(db);
 applyMigrations(db);
 
+// Closing checkpoints the WAL back into the main file so a copied database is complete.
+process.once("exit", closeDatabase);
+
 reconcileKoboldProviderLocalMode();
 backfillMessageSortOrder();
 backfillCharacterSortOrder();
@@ -105,6 +111,10 @@ function ensureDefaultSettingsRow() {
   }
 }
 
+export function closeDatabase() {
+  if (db.open) db.close();
+}
+
 export function nextSortOrder(chatId: string, branchId: string): number {
   const row = db.prepare(
     "SELECT MAX(sort_order) as mx FROM messages WHERE chat_id = ? AND branch_id = ?"
@@ -119,6 +129,7 @@ export {
   UPLOADS_DIR,
   PLUGINS_DIR,
   BUNDLED_PLUGINS_DIR,
+  BACKUPS_DIR,
   DEFAULT_SETTINGS,
   newId,
   now,
@@ -126,6 +137,7 @@ export {
   verifySecret,
   needsSecretRehash,
   roughTokenCount,
+  countTokens,
   maskApiKey,
   isLocalhostUrl
 };
@@ -0,0 +1,109 @@
+import Database from "better-sqlite3";
+import { existsSync, mkdirSync, mkdtempSync, readFileSync, writeFileSync } from "fs";
+import { tmpdir } from "os";
+import { join } from "path";
+import { describe, expect, it } from "vitest";
+import {
+  BACKUP_FORMAT_VERSION,
+  collectBackupPayload,
+  decryptBackup,
+  encryptBackup,
+  listBackupTables,
+  restoreBackupPayload,
+  type BackupPayload
+} from "./backup.js";
+
+function createDb() {
+  const db = new Database(":memory:");
+  db.pragma("foreign_keys = ON");
+  db.exec(`
+    CREATE TABLE chats (id TEXT PRIMARY KEY, title TEXT NOT NULL);
+    CREATE TABLE messages (
+      id TEXT PRIMARY KEY, chat_id TEXT NOT NULL, content TEXT NOT NULL,
+      FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE
+    );
+    CREATE TABLE rag_vectors (id TEXT PRIMARY KEY, vector BLOB);
+    CREATE VIRTUAL TABLE message_fts USING fts5(content, content='messages', content_rowid='rowid');
+  `);
+  return db;
+}
+
+describe("encrypted backups", () => {
+  it("skips FTS tables and round-trips rows, including blobs, through the encrypted file", () => {
+    const source = createDb();
+    source.exec("INSERT INTO chats VALUES ('c1', 'Harbor'); INSERT INTO messages VALUES ('m1', 'c1', 'hello')");
+    source.prepare("INSERT INTO rag_vectors VALUES ('v1', ?)").run(Buffer.from([1, 2, 3]));
+    expect(listBackupTables(source)).toEqual(["chats", "messages", "rag_vectors"]);
+
+    const file = encryptBackup(collectBackupPayload(source), "hunter2");
+    expect(JSON.parse(file)).toMatchObject({ format: "vellium-backup", version: BACKUP_FORMAT_VERSION });
+    expect(file).not.toContain("Harbor");
+
+    const target = createDb();
+    target.exec("INSERT INTO chats VALUES ('old', 'Replaced')");
+    const result = restoreBackupPayload(target, decryptBackup(file, "hunter2"));
+    expect(result).toMatchObject({ tables: 3, rows: 3, skippedTables: [] });
+    expect(target.prepare("SELECT id, title FROM chats").all()).toEqual([{ id: "c1", title: "Harbor" }]);
+    expect((target.prepare("SELECT vector FROM rag_vectors").get() as { vector: Buffer }).vector).toEqual(Buffer.from([1, 2, 3]));
+  });
+
+  it("rejects a wrong password and a newer format version", () => {
+    const file = encryptBackup(collectBackupPayload(createDb()), "right");
+    expect(() => decryptBackup(file, "wrong")).toThrow("Wrong password or corrupted backup");
+    const newer = JSON.stringify({ ...JSON.parse(file), version: BACKUP_FORMAT_VERSION + 1 });
+    expect(() => decryptBackup(newer, "right")).toThrow("newer than this app supports");
+    expect(() => decryptBackup("not json", "right")).toThrow("Not a Vellium backup file");
+  });
+
+  it("restores older backups into newer columns and rolls back on broken references", () => {
+    const target = createDb();
+    target.exec("ALTER TABLE chats ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0");
+    const payload = {
+      format: "vellium-backup" as const,
+      version: BACKUP_FORMAT_VERSION,
+      schemaVersion: 0,
+      createdAt: "2026-01-01T00:00:00.000Z",
+      tables: { chats: [{ id: "c1", title: "Old" }], retired_table: [{ id: "x" }] },
+      files: {}
+    };
+    expect(restoreBackupPayload(target, payload).skippedTables).toEqual(["retired_table"]);
+    expect(target.prepare("SELECT pinned FROM chats").get()).toEqual({ pinned: 0 });
+
+    expect(() => restoreBackupPayload(target, {
+      ...payload,
+      tables: { chats: [], messages: [{ id: "m1", chat_id: "missing", content: "orphan" }] }
+    })).toThrow();
+    expect(target.prepare("SELECT id FROM chats").all()).toEqual([{ id: "c1" }]);
+  });
+
+  it("archives avatar and upload files and only restores them inside those folders", () => {
+    const sourceDir = mkdtempSync(join(tmpdir(), "vellium-backup-src-"));
+    mkdirSync(join(sourceDir, "avatars"), { recursive: true });
+    mkdirSync(join(sourceDir, "uploads", "nested"), { recursive: true });
+    mkdirSync(join(sourceDir, "plugins"), { recursive: true });
+    writeFileSync(join(sourceDir, "avatars", "hero.png"), Buffer.from([137, 80, 78, 71]));
+    writeFileSync(join(sourceDir, "uploads", "nested", "notes.txt"), "field notes");
+    writeFileSync(join(sourceDir, "plugins", "skipped.js"), "ignored");
+
+    const payload = decryptBackup(encryptBackup(collectBackupPayload(createDb(), sourceDir), "pw"), "pw");
+    expect(Object.keys(payload.files).sort()).toEqual(["avatars/hero.png", "uploads/nested/notes.txt"]);
+
+    const targetDir = mkdtempSync(join(tmpdir(), "vellium-backup-dst-"));
+    const result = restoreBackupPayload(createDb(), {
+      ...payload,
+      files: { ...payload.files, "avatars/../escape.txt": "eA==", "../outside.txt": "eA==", "plugins/evil.js": "eA==" }
+    }, targetDir);
+    expect(result.files).toBe(2);
+    expect(readFileSync(join(targetDir, "avatars", "hero.png"))).toEqual(Buffer.from([137, 80, 78, 71]));
+    expect(readFileSync(join(targetDir, "uploads", "nested", "notes.txt"), "utf8")).toBe("field notes");
+    expect(existsSync(join(targetDir, "escape.txt"))).toBe(false);
+    expect(existsSync(join(targetDir, "plugins", "evil.js"))).toBe(false);
+  });
+
+  it("reads version 1 backups, which carried no files", () => {
+    const v1: Partial<BackupPayload> = { ...collectBackupPayload(createDb()), version: 1 };
+    delete v1.files;
+    const legacy = decryptBackup(encryptBackup(v1 as BackupPayload, "pw"), "pw");
+    expect(legacy).toMatchObject({ version: 2, files: {} });
+  });
+});
@@ -0,0 +1,228 @@
+import { createCipheriv, createDecipheriv, randomBytes, scryptSync } from "crypto";
+import { existsSync, mkdirSync, readdirSync, readFileSync, writeFileSync } from "fs";
+import { dirname, isAbsolute, join, relative, resolve, sep } from "path";
+import type Database from "better-sqlite3";
+import { getSchemaVersion } from "./migrations.js";
+
+export const BACKUP_FORMAT = "vellium-backup";
+export const BACKUP_FORMAT_VERSION = 2;
+
+const SCRYPT_PARAMS = { N: 1 << 15, r: 8, p: 1 };
+const SCRYPT_MAXMEM = 64 * 1024 * 1024;
+// Bookkeeping that belongs to the running database rather than the user's data.
+const SKIPPED_TABLES = new Set(["schema_version"]);
+// Data-directory folders whose files the database points at by name (character avatars, chat uploads).
+export const BACKUP_FILE_DIRS = ["avatars", "uploads"] as const;
+
+type BackupRow = Record<string, unknown>;
+
+export interface BackupPayload {
+  format: typeof BACKUP_FORMAT;
+  version: number;
+  schemaVersion: number;
+  createdAt: string;
+  tables: Record<string, BackupRow[]>;
+  /** Base64 file contents keyed by their `/`-separated path under the data directory, e.g. `avatars/a.png`. */
+  files: Record<string, string>;
+}
+
+/** Plaintext envelope: the header says how to derive the key and which payload version is inside. */
+export interface BackupFile {
+  format: typeof BACKUP_FORMAT;
+  version: number;
+  kdf: { name: "scrypt"; salt: string; N: number; r: number; p: number };
+  cipher: "aes-256-gcm";
+  iv: string;
+  tag: string;
+  data: string;
+}
+
+export interface BackupRestoreResult {
+  tables: number;
+  rows: number;
+  skippedTables: string[];
+  files: number;
+}
+
+// Payload upgrades keyed by the version they upgrade from; add one whenever BACKUP_FORMAT_VERSION grows.
+const PAYLOAD_UPGRADES: Record<number, (payload: BackupPayload) => BackupPayload> = {
+  1: (payload) => ({ ...payload, version: 2, files: {} })
+};
+
+/** Regular tables only: FTS virtual tables and their shadow tables are rebuilt from `messages` by triggers. */
+export function listBackupTables(db: Database.Database): string[] {
+  const rows = db.prepare(
+    "SELECT name, sql FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
+  ).all() as Array<{ name: string; sql: string | null }>;
+  const virtualTables = rows.filter((row) => /^CREATE VIRTUAL TABLE/i.test(row.sql || "")).map((row) => row.name);
+  return rows
+    .map((row) => row.name)
+    .filter((name) => !SKIPPED_TABLES.has(name))
+    .filter((name) => !virtualTables.some((table) => name === table || name.startsWith(`${table}_`)));
+}
+
+function encodeValue(value: unknown): unknown {
+  return Buffer.isBuffer(value) ? { $base64: value.toString("base64") } : value;
+}
+
+function decodeValue(value: unknown): unknown {
+  if (value && typeof value === "object" && typeof (value as { $base64?: unknown }).$base64 === "string") {
+    return Buffer.from((value as { $base64: string }).$base64, "base64");
+  }
+  return value;
+}
+
+function listFilesRecursive(dir: string): string[] {
+  if (!existsSync(dir)) return [];
+  return readdirSync(dir, { withFileTypes: true }).flatMap((entry) => {
+    const path = join(dir, entry.name);
+    if (entry.isDirectory()) return listFilesRecursive(path);
+    return entry.isFile() ? [path] : [];
+  });
+}
+
+export function collectBackupFiles(dataDir: string): Record<string, string> {
+  const files: Record<string, string> = {};
+  for (const folder of BACKUP_FILE_DIRS) {
+    for (const path of listFilesRecursive(join(dataDir, folder))) {
+      files[relative(dataDir, path).split(sep).join("/")] = readFileSync(path).toString("base64");
+    }
+  }
+  return files;
+}
+
+/** Only paths that stay inside one of `BACKUP_FILE_DIRS` are written; anything else in the archive is ignored. */
+function resolveBackupFilePath(dataDir: string, name: string): string | null {
+  const folder = BACKUP_FILE_DIRS.find((item) => name.startsWith(`${item}/`));
+  if (!folder) return null;
+  const root = join(dataDir, folder);
+  const resolved = resolve(dataDir, name);
+  const fromRoot = relative(root, resolved);
+  if (!fromRoot || fromRoot.startsWith("..") || isAbsolute(fromRoot)) return null;
+  return resolved;
+}
+
+export function restoreBackupFiles(dataDir: string, files: Record<string, string> | undefined): number {
+  let written = 0;
+  for (const [name, content] of Object.entries(files || {})) {
+    const path = typeof content === "string" ? resolveBackupFilePath(dataDir, name) : null;
+    if (!path) continue;
+    mkdirSync(dirname(path), { recursive: true });
+    writeFileSync(path, Buffer.from(content, "base64"));
+    written += 1;
+  }
+  return written;
+}
+
+/** With a `dataDir`, the files in `BACKUP_FILE_DIRS` are archived next to the rows that reference them. */
+export function collectBackupPayload(db: Database.Database, dataDir?: string): BackupPayload {
+  const tables: Record<string, BackupRow[]> = {};
+  for (const table of listBackupTables(db)) {
+    const rows = db.prepare(`SELECT * FROM "${table}"`).all() as BackupRow[];
+    tables[table] = rows.map((row) => Object.fromEntries(Object.entries(row).map(([key, value]) => [key, encodeValue(value)])));
+  }
+  return {
+    format: BACKUP_FORMAT,
+    version: BACKUP_FORMAT_VERSION,
+    schemaVersion: getSchemaVersion(db),
+    createdAt: new Date().toISOString(),
+    tables,
+    files: dataDir ? collectBackupFiles(dataDir) : {}
+  };
+}
+
+function deriveBackupKey(password: string, salt: Buffer, params = SCRYPT_PARAMS): Buffer {
+  return scryptSync(password, salt, 32, { ...params, maxmem: SCRYPT_MAXMEM });
+}
+
+export function encryptBackup(payload: BackupPayload, password: string): string {
+  if (!password) throw new Error("Backup password is required");
+  const salt = randomBytes(16);
+  const iv = randomBytes(12);
+  const cipher = createCipheriv("aes-256-gcm", deriveBackupKey(password, salt), iv);
+  const data = Buffer.concat([cipher.update(JSON.stringify(payload), "utf8"), cipher.final()]);
+  const file: BackupFile = {
+    format: BACKUP_FORMAT,
+    version: payload.version,
+    kdf: { name: "scrypt", salt: salt.toString("base64"), ...SCRYPT_PARAMS },
+    cipher: "aes-256-gcm",
+    iv: iv.toString("base64"),
+    tag: cipher.getAuthTag().toString("base64"),
+    data: data.toString("base64")
+  };
+  return JSON.stringify(file);
+}
+
+export function decryptBackup(raw: string, password: string): BackupPayload {
+  let file: BackupFile;
+  try {
+    file = JSON.parse(raw) as BackupFile;
+  } catch {
+    throw new Error("Not a Vellium backup file");
+  }
+  if (file?.format !== BACKUP_FORMAT || file.kdf?.name !== "scrypt" || file.cipher !== "aes-256-gcm") {
+    throw new Error("Not a Vellium backup file");
+  }
+  if (!Number.isInteger(file.version) || file.version > BACKUP_FORMAT_VERSION) {
+    throw new Error(`Backup format version ${file.version} is newer than this app supports (${BACKUP_FORMAT_VERSION})`);
+  }
+
+  let payload: BackupPayload;
+  try {
+    const key = deriveBackupKey(password, Buffer.from(file.kdf.salt, "base64"), { N: file.kdf.N, r: file.kdf.r, p: file.kdf.p });
+    const decipher = createDecipheriv("aes-256-gcm", key, Buffer.from(file.iv, "base64"));
+    decipher.setAuthTag(Buffer.from(file.tag, "base64"));
+    const plain = Buffer.concat([decipher.update(Buffer.from(file.data, "base64")), decipher.final()]);
+    payload = JSON.parse(plain.toString("utf8")) as BackupPayload;
+  } catch {
+    throw new Error("Wrong password or corrupted backup");
+  }
+
+  for (let version = payload.version; version < BACKUP_FORMAT_VERSION; version += 1) {
+    const upgrade = PAYLOAD_UPGRADES[version];
+    if (upgrade) payload = upgrade(payload);
+  }
+  return payload;
+}
+
+/**
+ * Replaces every backed-up table's rows in one transaction. Only columns the current schema still has
+ * are written, so backups from older schemas restore with defaults for newer columns. Foreign keys are
+ * checked at commit, after all tables are filled. Archived files are written into `dataDir` only once the
+ * rows have committed, overwriting files with the same name.
+ */
+export function restoreBackupPayload(db: Database.Database, payload: BackupPayload, dataDir?: string): BackupRestoreResult {
+  const currentTables = listBackupTables(db);
+  const skippedTables = Object.keys(payload.tables || {}).filter((table) => !currentTables.includes(table));
+  let tables = 0;
+  let rows = 0;
+
+  db.transaction(() => {
+    db.pragma("defer_foreign_keys = ON");
+    for (const table of currentTables) db.prepare(`DELETE FROM "${table}"`).run();
+    for (const table of currentTables) {
+      const backupRows = payload.tables?.[table];
+      if (!Array.isArray(backupRows)) continue;
+      const columns = (db.prepare(`PRAGMA table_info("${table}")`).all() as Array<{ name: string }>).map((column) => column.name);
+      const inserts = new Map<string, Database.Statement>();
+      tables += 1;
+      for (const row of backupRows) {
+        const present = columns.filter((column) => Object.prototype.hasOwnProperty.call(row, column));
+        if (present.length === 0) continue;
+        const key = present.join("\u0000");
+        let insert = inserts.get(key);
+        if (!insert) {
+          insert = db.prepare(
+            `INSERT INTO "${table}" (${present.map((column) => `"${column}"`).join(", ")}) VALUES (${present.map(() => "?").join(", ")})`
+          );
+          inserts.set(key, insert);
+        }
+        insert.run(...present.map((column) => decodeValue(row[column])));
+        rows += 1;
+      }
+    }
+  })();
+
+  const files = dataDir ? restoreBackupFiles(dataDir, payload.files) : 0;
+  return { tables, rows, skippedTables, files };
+}
@@ -35,6 +35,7 @@ export function migrateDefaultSystemPrompt(value: unknown): string {
 export const DEFAULT_SETTINGS = {
   onboardingCompleted: false,
   checkForUpdates: true,
+  autoLockMinutes: 15,
   agentsEnabled: false,
   agentWorkspaceToolsEnabled: true,
   agentCommandToolEnabled: true,
@@ -55,6 +56,8 @@ export const DEFAULT_SETTINGS = {
   simpleModeWallpaperBlur: 0,
   simpleModeWallpaperPosition: "center" as "center" | "top" | "bottom",
   censorshipMode: "Unfiltered",
+  moderationUrl: "",
+  moderationApiKey: "",
   fullLocalMode: false,
   useAlternateGreetings: false,
   responseLanguage: "English",
@@ -77,6 +80,7 @@ export const DEFAULT_SETTINGS = {
   interfaceLanguage: "en",
   activeProviderId: null,
   activeModel: null,
+  providerFallbacks: [] as Array<{ providerId: string; modelId: string }>,
   ttsBaseUrl: "",
   ttsApiKey: "",
   ttsAdapterId: null as string | null,
@@ -91,6 +95,9 @@ export const DEFAULT_SETTINGS = {
   compressProviderId: null,
   compressModel: null,
   translationTimeoutSeconds: 120,
+  modelFetchTimeoutSeconds: 30,
+  streamRetryAttempts: 2,
+  writerSceneRevisionLimit: 20,
   translationTemperature: 0.2,
   translationMaxTokens: 2048,
   compressionTemperature: 0.3,
@@ -128,7 +135,8 @@ export const DEFAULT_SETTINGS = {
       frequencyPenalty: true,
       presencePenalty: true,
       maxTokens: true,
-      stop: true
+      stop: true,
+      streamUsage: true
     },
     kobold: {
       sendSampler: true,
@@ -157,6 +165,9 @@ export const DEFAULT_SETTINGS = {
   includeReasoningInContext: true,
   contextMaxMessages: 0,
   reasoningMaxChars: 12000,
+  loreScanDepth: 10,
+  loreTokenBudget: 2048,
+  // 0 means "use the model's reported context length"; any other value is a hard cap.
   contextWindowSize: 8192,
   contextTailBudgetWithSummaryPercent: 35,
   contextTailBudgetWithoutSummaryPercent: 75,
@@ -0,0 +1,100 @@
+import Database from "better-sqlite3";
+import { describe, expect, it } from "vitest";
+import { applySchemaMigrations, getSchemaVersion, SCHEMA_MIGRATIONS } from "./migrations.js";
+
+function columnNames(db: Database.Database, table: string): string[] {
+  return (db.prepare(`PRAGMA table_info(${table})`).all() as Array<{ name: string }>).map((row) => row.name);
+}
+
+describe("schema migrations", () => {
+  it("adds the writer_scenes columns an upgraded database is missing and records the version", () => {
+    const db = new Database(":memory:");
+    db.exec(`CREATE TABLE writer_scenes (
+      id TEXT PRIMARY KEY, chapter_id TEXT NOT NULL, title TEXT NOT NULL, content TEXT NOT NULL,
+      goals TEXT NOT NULL, conflicts TEXT NOT NULL, outcomes TEXT NOT NULL, created_at TEXT NOT NULL
+    )`);
+
+    applySchemaMigrations(db);
+    expect(columnNames(db, "writer_scenes")).toEqual(expect.arrayContaining(["character_id", "chat_id"]));
+    expect(getSchemaVersion(db)).toBe(SCHEMA_MIGRATIONS[SCHEMA_MIGRATIONS.length - 1].version);
+
+    applySchemaMigrations(db);
+    const rows = db.prepare("SELECT version FROM schema_version").all();
+    expect(rows).toHaveLength(SCHEMA_MIGRATIONS.length);
+    db.close();
+  });
+
+  it("rebuilds chat child tables with a cascading foreign key, dropping orphans and keeping indexes", () => {
+    const db = new Database(":memory:");
+    db.pragma("foreign_keys = ON");
+    db.exec(`
+      CREATE TABLE chats (id TEXT PRIMARY KEY, title TEXT NOT NULL);
+      CREATE TABLE messages (id TEXT PRIMARY KEY, chat_id TEXT NOT NULL, content TEXT NOT NULL);
+      CREATE INDEX idx_messages_chat ON messages(chat_id);
+      INSERT INTO chats (id, title) VALUES ('c1', 'Kept');
+      INSERT INTO messages (id, chat_id, content) VALUES ('m1', 'c1', 'hello'), ('m2', 'gone', 'orphan');
+    `);
+
+    applySchemaMigrations(db, SCHEMA_MIGRATIONS.filter((migration) => migration.name === "chat_child_foreign_keys"));
+    expect(db.prepare("SELECT id FROM messages").all()).toEqual([{ id: "m1" }]);
+    expect(db.prepare("SELECT name FROM sqlite_master WHERE name = 'idx_messages_chat'").get()).toBeTruthy();
+    expect(() => db.prepare("INSERT INTO messages (id, chat_id, content) VALUES ('m3', 'gone', 'x')").run()).toThrow();
+
+    db.prepare("DELETE FROM chats WHERE id = 'c1'").run();
+    expect(db.prepare("SELECT COUNT(*) AS count FROM messages").get()).toEqual({ count: 0 });
+    db.close();
+  });
+
+  it("indexes the timeline and writer chapter lookups", () => {
+    const db = new Database(":memory:");
+    db.exec(`
+      CREATE TABLE messages (id TEXT PRIMARY KEY, chat_id TEXT, branch_id TEXT, sort_order INTEGER, created_at TEXT);
+      CREATE TABLE writer_chapters (id TEXT PRIMARY KEY, project_id TEXT, position INTEGER);
+    `);
+
+    applySchemaMigrations(db, SCHEMA_MIGRATIONS.filter((migration) => migration.name === "hot_query_indexes"));
+    const plan = db.prepare(
+      "EXPLAIN QUERY PLAN SELECT * FROM messages WHERE chat_id = ? AND branch_id = ? ORDER BY sort_order ASC"
+    ).all("c1", "b1") as Array<{ detail: string }>;
+    expect(plan.map((row) => row.detail).join(" ")).toContain("idx_messages_timeline");
+    expect(db.prepare("SELECT name FROM sqlite_master WHERE name = 'idx_writer_chapters_project'").get()).toBeTruthy();
+    db.close();
+  });
+
+  it("numbers existing writer scenes per chapter in their old created_at order", () => {
+    const db = new Database(":memory:");
+    db.exec(`
+      CREATE TABLE writer_scenes (id TEXT PRIMARY KEY, chapter_id TEXT NOT NULL, created_at TEXT NOT NULL);
+      INSERT INTO writer_scenes (id, chapter_id, created_at) VALUES
+        ('late', 'ch1', '2026-01-03'), ('early', 'ch1', '2026-01-01'), ('only', 'ch2', '2026-01-02');
+    `);
+
+    applySchemaMigrations(db, SCHEMA_MIGRATIONS.filter((migration) => migration.name === "writer_scene_positions"));
+    expect(db.prepare("SELECT id, position FROM writer_scenes ORDER BY chapter_id, position").all()).toEqual([
+      { id: "early", position: 1 },
+      { id: "late", position: 2 },
+      { id: "only", position: 1 }
+    ]);
+    expect(db.prepare("SELECT name FROM sqlite_master WHERE name = 'idx_writer_scenes_position'").get()).toBeTruthy();
+    db.close();
+  });
+
+  it("rolls back a failing step and leaves earlier steps applied", () => {
+    const db = new Database(":memory:");
+    expect(() => applySchemaMigrations(db, [
+      { version: 1, name: "create_notes", up: (conn) => conn.exec("CREATE TABLE notes (id TEXT PRIMARY KEY)") },
+      {
+        version: 2,
+        name: "broken",
+        up: (conn) => {
+          conn.exec("ALTER TABLE notes ADD COLUMN body TEXT");
+          conn.exec("ALTER TABLE missing_table ADD COLUMN body TEXT");
+        }
+      }
+    ])).toThrow("Schema migration 2 (broken) failed");
+
+    expect(getSchemaVersion(db)).toBe(1);
+    expect(columnNames(db, "notes")).toEqual(["id"]);
+    db.close();
+  });
+});
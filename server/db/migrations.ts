@@ -28,12 +28,16 @@ const MIGRATIONS = [
   "ALTER TABLE messages ADD COLUMN generation_started_at TEXT",
   "ALTER TABLE messages ADD COLUMN generation_completed_at TEXT",
   "ALTER TABLE messages ADD COLUMN generation_duration_ms INTEGER",
+  "ALTER TABLE messages ADD COLUMN truncated INTEGER NOT NULL DEFAULT 0",
+  "ALTER TABLE messages ADD COLUMN prompt_tokens INTEGER",
+  "ALTER TABLE messages ADD COLUMN completion_tokens INTEGER",
   "ALTER TABLE writer_projects ADD COLUMN character_ids TEXT NOT NULL DEFAULT '[]'",
   "ALTER TABLE writer_projects ADD COLUMN notes_json TEXT NOT NULL DEFAULT '{}'",
   "ALTER TABLE writer_chapters ADD COLUMN settings_json TEXT NOT NULL DEFAULT '{}'",
   "ALTER TABLE providers ADD COLUMN provider_type TEXT NOT NULL DEFAULT 'openai'",
   "ALTER TABLE providers ADD COLUMN adapter_id TEXT",
   "ALTER TABLE providers ADD COLUMN manual_models TEXT NOT NULL DEFAULT '[]'",
+  "ALTER TABLE providers ADD COLUMN extra_headers TEXT NOT NULL DEFAULT '{}'",
   "ALTER TABLE agent_threads ADD COLUMN mode TEXT NOT NULL DEFAULT 'build'",
   "ALTER TABLE agent_threads ADD COLUMN hero_character_id TEXT",
   "ALTER TABLE agent_threads ADD COLUMN workspace_root TEXT NOT NULL DEFAULT ''",
@@ -42,6 +46,216 @@ const MIGRATIONS = [
   "ALTER TABLE agent_threads ADD COLUMN developer_prompt TEXT NOT NULL DEFAULT ''"
 ];
 
+export interface SchemaMigration {
+  version: number;
+  name: string;
+  up: (db: Database.Database) => void;
+}
+
+function hasColumn(db: Database.Database, table: string, column: string): boolean {
+  const columns = db.prepare(`PRAGMA table_info(${table})`).all() as Array<{ name: string }>;
+  return columns.some((item) => item.name === column);
+}
+
+function ensureColumn(db: Database.Database, table: string, column: string, definition: string) {
+  if (!hasColumn(db, table, column)) db.exec(`ALTER TABLE ${table} ADD COLUMN ${column} ${definition}`);
+}
+
+/**
+ * SQLite cannot add a constraint to an existing table, so the table is recreated from its current
+ * definition plus the FOREIGN KEY clause. Rows whose parent is already gone are dropped, rowids are
+ * kept (message_fts relies on them) and the table's indexes and triggers are recreated.
+ */
+function rebuildWithChatForeignKey(db: Database.Database, table: string) {
+  const row = db.prepare("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?").get(table) as { sql: string } | undefined;
+  if (!row || /REFERENCES\s+chats\b/i.test(row.sql)) return;
+  const dependents = db.prepare(
+    "SELECT sql FROM sqlite_master WHERE tbl_name = ? AND type IN ('index', 'trigger') AND sql IS NOT NULL"
+  ).all(table) as Array<{ sql: string }>;
+  const columns = (db.prepare(`PRAGMA table_info(${table})`).all() as Array<{ name: string }>)
+    .map((column) => `"${column.name}"`)
+    .join(", ");
+  const definition = row.sql.slice(row.sql.indexOf("("), row.sql.lastIndexOf(")")).trimEnd();
+  const rebuilt = `${table}__fk_rebuild`;
+  db.exec(`CREATE TABLE ${rebuilt} ${definition},\n    FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE\n  )`);
+  db.exec(
+    `INSERT INTO ${rebuilt} (rowid, ${columns}) SELECT rowid, ${columns} FROM ${table} WHERE chat_id IN (SELECT id FROM chats)`
+  );
+  db.exec(`DROP TABLE ${table}`);
+  db.exec(`ALTER TABLE ${rebuilt} RENAME TO ${table}`);
+  for (const dependent of dependents) db.exec(dependent.sql);
+}
+
+function tableExists(db: Database.Database, table: string): boolean {
+  return Boolean(db.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?").get(table));
+}
+
+// Ordered, run-once steps recorded in `schema_version`. Append new steps with the next version;
+// never edit a step that has shipped. The legacy MIGRATIONS list above stays for old databases.
+export const SCHEMA_MIGRATIONS: SchemaMigration[] = [
+  {
+    version: 1,
+    name: "reconcile_writer_scenes",
+    up: (db) => {
+      // Fresh databases got these columns from CREATE TABLE; databases created before they existed never did.
+      ensureColumn(db, "writer_scenes", "character_id", "TEXT");
+      ensureColumn(db, "writer_scenes", "chat_id", "TEXT");
+      db.exec("CREATE INDEX IF NOT EXISTS idx_writer_scenes_chapter ON writer_scenes(chapter_id, created_at)");
+    }
+  },
+  {
+    version: 2,
+    name: "chat_child_foreign_keys",
+    up: (db) => {
+      for (const table of ["branches", "messages", "rp_scene_state", "rp_memory_entries", "prompt_blocks"]) {
+        if (tableExists(db, table)) rebuildWithChatForeignKey(db, table);
+      }
+      if (tableExists(db, "message_fts")) db.exec("INSERT INTO message_fts(message_fts) VALUES ('rebuild')");
+    }
+  },
+  {
+    version: 3,
+    name: "hot_query_indexes",
+    up: (db) => {
+      // Timeline reads filter by chat + branch and order by sort_order; writer views walk chapters by position.
+      if (tableExists(db, "messages")) {
+        db.exec("CREATE INDEX IF NOT EXISTS idx_messages_timeline ON messages(chat_id, branch_id, sort_order, created_at)");
+      }
+      if (tableExists(db, "branches")) {
+        db.exec("CREATE INDEX IF NOT EXISTS idx_branches_chat ON branches(chat_id, created_at)");
+      }
+      if (tableExists(db, "writer_chapters")) {
+        db.exec("CREATE INDEX IF NOT EXISTS idx_writer_chapters_project ON writer_chapters(project_id, position)");
+      }
+      if (tableExists(db, "writer_scenes")) {
+        db.exec("CREATE INDEX IF NOT EXISTS idx_writer_scenes_chapter ON writer_scenes(chapter_id, created_at)");
+      }
+    }
+  },
+  {
+    version: 4,
+    name: "writer_scene_positions",
+    up: (db) => {
+      if (!tableExists(db, "writer_scenes")) return;
+      // Scenes used to be ordered by created_at; number them in that order so existing books read the same.
+      ensureColumn(db, "writer_scenes", "position", "INTEGER NOT NULL DEFAULT 0");
+      db.exec(`
+        UPDATE writer_scenes SET position = (
+          SELECT ordered.rank FROM (
+            SELECT id, ROW_NUMBER() OVER (PARTITION BY chapter_id ORDER BY created_at ASC, id ASC) AS rank
+            FROM writer_scenes
+          ) AS ordered
+          WHERE ordered.id = writer_scenes.id
+        )
+      `);
+      db.exec("CREATE INDEX IF NOT EXISTS idx_writer_scenes_position ON writer_scenes(chapter_id, position)");
+    }
+  },
+  {
+    version: 5,
+    name: "chat_active_branch",
+    up: (db) => {
+      // NULL keeps the old default: the chat's earliest branch.
+      if (tableExists(db, "chats")) ensureColumn(db, "chats", "active_branch_id", "TEXT");
+    }
+  },
+  {
+    version: 6,
+    name: "chat_system_prompt",
+    up: (db) => {
+      // NULL means the chat has no override and uses the settings default.
+      if (tableExists(db, "chats")) ensureColumn(db, "chats", "system_prompt", "TEXT");
+    }
+  },
+  {
+    version: 7,
+    name: "chat_author_note_depth",
+    up: (db) => {
+      // NULL keeps the default depth of four messages from the end.
+      if (tableExists(db, "chats")) ensureColumn(db, "chats", "author_note_depth", "INTEGER");
+    }
+  },
+  {
+    version: 8,
+    name: "chat_persona_override",
+    up: (db) => {
+      // NULL follows the persona sent with each request, falling back to the default persona.
+      if (tableExists(db, "chats")) ensureColumn(db, "chats", "persona_id", "TEXT");
+    }
+  },
+  {
+    version: 9,
+    name: "chat_response_language",
+    up: (db) => {
+      // NULL follows settings.responseLanguage.
+      if (tableExists(db, "chats")) ensureColumn(db, "chats", "response_language", "TEXT");
+    }
+  },
+  {
+    version: 10,
+    name: "message_pinned",
+    up: (db) => {
+      if (tableExists(db, "messages")) ensureColumn(db, "messages", "pinned", "INTEGER NOT NULL DEFAULT 0");
+    }
+  },
+  {
+    version: 11,
+    name: "provider_rate_limit",
+    up: (db) => {
+      // NULL leaves the provider unthrottled.
+      if (tableExists(db, "providers")) ensureColumn(db, "providers", "rate_limit_rpm", "INTEGER");
+    }
+  },
+  {
+    version: 12,
+    name: "chat_trash",
+    up: (db) => {
+      // Set when a chat is moved to the trash; NULL means the chat is live.
+      if (tableExists(db, "chats")) ensureColumn(db, "chats", "deleted_at", "TEXT");
+    }
+  },
+  {
+    version: 13,
+    name: "provider_auth_mode",
+    up: (db) => {
+      // NULL mode is bearer auth; auth_param names the header or query parameter for the other modes.
+      if (!tableExists(db, "providers")) return;
+      ensureColumn(db, "providers", "auth_mode", "TEXT");
+      ensureColumn(db, "providers", "auth_param", "TEXT");
+    }
+  }
+];
+
+export function getSchemaVersion(db: Database.Database): number {
+  const row = db.prepare("SELECT MAX(version) AS version FROM schema_version").get() as { version: number | null };
+  return row.version ?? 0;
+}
+
+/** Runs each pending step in its own transaction; a failing step rolls back and aborts startup. */
+export function applySchemaMigrations(db: Database.Database, migrations: SchemaMigration[] = SCHEMA_MIGRATIONS) {
+  db.exec(`
+    CREATE TABLE IF NOT EXISTS schema_version (
+      version INTEGER PRIMARY KEY,
+      name TEXT NOT NULL,
+      applied_at TEXT NOT NULL
+    )
+  `);
+  const current = getSchemaVersion(db);
+  const pending = migrations.filter((migration) => migration.version > current).sort((a, b) => a.version - b.version);
+  const record = db.prepare("INSERT INTO schema_version (version, name, applied_at) VALUES (?, ?, ?)");
+  for (const migration of pending) {
+    try {
+      db.transaction(() => {
+        migration.up(db);
+        record.run(migration.version, migration.name, new Date().toISOString());
+      })();
+    } catch (error) {
+      const reason = error instanceof Error ? error.message : String(error);
+      throw new Error(`Schema migration ${migration.version} (${migration.name}) failed: ${reason}`);
+    }
+  }
+}
+
 export function applyMigrations(db: Database.Database) {
   for (const sql of MIGRATIONS) {
     try {
@@ -50,4 +264,5 @@ export function applyMigrations(db: Database.Database) {
       // Column already exists.
     }
   }
+  applySchemaMigrations(db);
 }
@@ -44,6 +44,7 @@ export const DATA_DIR = resolveDefaultDataDir();
 export const AVATARS_DIR = join(DATA_DIR, "avatars");
 export const UPLOADS_DIR = join(DATA_DIR, "uploads");
 export const PLUGINS_DIR = join(DATA_DIR, "plugins");
+export const BACKUPS_DIR = join(DATA_DIR, "backups");
 export const BUNDLED_PLUGINS_DIR = resolveBundledPluginsDir();
 
 const VELLIUM_DB_PATH = join(DATA_DIR, "vellum.db");
@@ -22,13 +22,40 @@ const SCHEMA_SQL = `
     full_local_only INTEGER NOT NULL DEFAULT 0,
     provider_type TEXT NOT NULL DEFAULT 'openai',
     adapter_id TEXT,
-    manual_models TEXT NOT NULL DEFAULT '[]'
+    manual_models TEXT NOT NULL DEFAULT '[]',
+    extra_headers TEXT NOT NULL DEFAULT '{}',
+    rate_limit_rpm INTEGER,
+    auth_mode TEXT,
+    auth_param TEXT
+  );
+
+  CREATE TABLE IF NOT EXISTS provider_models (
+    provider_id TEXT PRIMARY KEY,
+    models_json TEXT NOT NULL DEFAULT '[]',
+    fetched_at TEXT NOT NULL,
+    FOREIGN KEY (provider_id) REFERENCES providers(id) ON DELETE CASCADE
+  );
+
+  CREATE TABLE IF NOT EXISTS model_prefs (
+    provider_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    favorite INTEGER NOT NULL DEFAULT 0,
+    hidden INTEGER NOT NULL DEFAULT 0,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (provider_id, model_id),
+    FOREIGN KEY (provider_id) REFERENCES providers(id) ON DELETE CASCADE
   );
 
   CREATE TABLE IF NOT EXISTS chats (
     id TEXT PRIMARY KEY,
     title TEXT NOT NULL,
     lorebook_ids TEXT NOT NULL DEFAULT '[]',
+    active_branch_id TEXT,
+    system_prompt TEXT,
+    author_note_depth INTEGER,
+    persona_id TEXT,
+    response_language TEXT,
+    deleted_at TEXT,
     created_at TEXT NOT NULL
   );
 
@@ -37,7 +64,8 @@ const SCHEMA_SQL = `
     chat_id TEXT NOT NULL,
     name TEXT NOT NULL,
     parent_message_id TEXT,
-    created_at TEXT NOT NULL
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE
   );
 
   CREATE TABLE IF NOT EXISTS messages (
@@ -52,7 +80,9 @@ const SCHEMA_SQL = `
     generation_started_at TEXT,
     generation_completed_at TEXT,
     generation_duration_ms INTEGER,
-    created_at TEXT NOT NULL
+    pinned INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE
   );
 
   CREATE TABLE IF NOT EXISTS characters (
@@ -83,7 +113,18 @@ const SCHEMA_SQL = `
   CREATE TABLE IF NOT EXISTS rp_scene_state (
     chat_id TEXT PRIMARY KEY,
     payload TEXT NOT NULL,
-    updated_at TEXT NOT NULL
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE
+  );
+
+  CREATE TABLE IF NOT EXISTS chat_exports (
+    id TEXT PRIMARY KEY,
+    chat_id TEXT NOT NULL,
+    branch_id TEXT,
+    export_type TEXT NOT NULL,
+    output_path TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE
   );
 
   CREATE TABLE IF NOT EXISTS rp_memory_entries (
@@ -91,7 +132,18 @@ const SCHEMA_SQL = `
     chat_id TEXT NOT NULL,
     role TEXT NOT NULL,
     content TEXT NOT NULL,
-    created_at TEXT NOT NULL
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE
+  );
+
+  CREATE TABLE IF NOT EXISTS memory_embeddings (
+    entry_id TEXT PRIMARY KEY,
+    model_key TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    vector_blob BLOB NOT NULL,
+    norm REAL NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (entry_id) REFERENCES rp_memory_entries(id) ON DELETE CASCADE
   );
 
   CREATE TABLE IF NOT EXISTS writer_projects (
@@ -122,9 +174,28 @@ const SCHEMA_SQL = `
     outcomes TEXT NOT NULL,
     character_id TEXT,
     chat_id TEXT,
+    position INTEGER NOT NULL DEFAULT 0,
     created_at TEXT NOT NULL
   );
 
+  CREATE TABLE IF NOT EXISTS writer_scene_revisions (
+    id TEXT PRIMARY KEY,
+    scene_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (scene_id) REFERENCES writer_scenes(id) ON DELETE CASCADE
+  );
+
+  CREATE TABLE IF NOT EXISTS writer_scene_summaries (
+    scene_id TEXT PRIMARY KEY,
+    content_hash TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (scene_id) REFERENCES writer_scenes(id) ON DELETE CASCADE
+  );
+
   CREATE TABLE IF NOT EXISTS writer_beats (
     id TEXT PRIMARY KEY,
     project_id TEXT NOT NULL,
@@ -181,7 +252,8 @@ const SCHEMA_SQL = `
     enabled INTEGER NOT NULL DEFAULT 1,
     ordering INTEGER NOT NULL,
     content TEXT NOT NULL,
-    created_at TEXT NOT NULL
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE
   );
 
   CREATE TABLE IF NOT EXISTS user_personas (
@@ -241,6 +313,17 @@ const SCHEMA_SQL = `
     FOREIGN KEY (chunk_id) REFERENCES rag_chunks(id) ON DELETE CASCADE
   );
 
+  CREATE TABLE IF NOT EXISTS semantic_embeddings (
+    scope TEXT NOT NULL,
+    item_id TEXT NOT NULL,
+    model_key TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    vector_blob BLOB NOT NULL,
+    norm REAL NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (scope, item_id)
+  );
+
   CREATE TABLE IF NOT EXISTS chat_rag_bindings (
     chat_id TEXT PRIMARY KEY,
     enabled INTEGER NOT NULL DEFAULT 0,
@@ -346,6 +429,7 @@ export function applySchemaIndexes(db: Database.Database) {
     db.exec("CREATE INDEX IF NOT EXISTS idx_rag_chunks_collection ON rag_chunks(collection_id)");
     db.exec("CREATE INDEX IF NOT EXISTS idx_rag_chunks_document ON rag_chunks(document_id)");
     db.exec("CREATE INDEX IF NOT EXISTS idx_rag_vectors_model ON rag_vectors(model_key)");
+    db.exec("CREATE INDEX IF NOT EXISTS idx_writer_scene_revisions_scene ON writer_scene_revisions(scene_id, created_at)");
     db.exec("CREATE INDEX IF NOT EXISTS idx_agent_threads_updated ON agent_threads(updated_at)");
     db.exec("CREATE INDEX IF NOT EXISTS idx_agent_skills_thread ON agent_skills(thread_id, ordering)");
     db.exec("CREATE INDEX IF NOT EXISTS idx_agent_messages_thread ON agent_messages(thread_id, created_at)");
@@ -355,4 +439,29 @@ export function applySchemaIndexes(db: Database.Database) {
   } catch {
     // Keep startup resilient if a platform SQLite build lacks FTS5.
   }
+  applyMessageSearchIndex(db);
+}
+
+// External-content FTS over messages.content, kept in sync by triggers so every writer is covered.
+// Deleted messages stay indexed and are filtered at query time through the `deleted` flag.
+function applyMessageSearchIndex(db: Database.Database) {
+  try {
+    const existed = Boolean(db.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'message_fts'").get());
+    db.exec(`
+      CREATE VIRTUAL TABLE IF NOT EXISTS message_fts USING fts5(content, content='messages', content_rowid='rowid', tokenize='unicode61');
+      CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+        INSERT INTO message_fts(rowid, content) VALUES (new.rowid, new.content);
+      END;
+      CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+        INSERT INTO message_fts(message_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+      END;
+      CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF content ON messages BEGIN
+        INSERT INTO message_fts(message_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+        INSERT INTO message_fts(rowid, content) VALUES (new.rowid, new.content);
+      END;
+    `);
+    if (!existed) db.exec("INSERT INTO message_fts(message_fts) VALUES ('rebuild')");
+  } catch {
+    // Without FTS5 message search falls back to LIKE scans.
+  }
 }
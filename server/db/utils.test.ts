@@ -1,6 +1,6 @@
 import { createHash } from "crypto";
 import { describe, expect, it } from "vitest";
-import { hashSecret, needsSecretRehash, verifySecret } from "./utils.js";
+import { countTokens, hashSecret, needsSecretRehash, roughTokenCount, tokenEncodingForModel, verifySecret } from "./utils.js";
 
 describe("account secret hashing", () => {
   it("uses unique salted scrypt hashes and verifies them", () => {
@@ -26,3 +26,26 @@ describe("account secret hashing", () => {
     expect(verifySecret("", hashSecret("valid"))).toBe(false);
   });
 });
+
+describe("token counting", () => {
+  it("selects an encoding from the model id, including provider-prefixed ids", () => {
+    expect(tokenEncodingForModel("gpt-4o-mini")).toBe("o200k");
+    expect(tokenEncodingForModel("openai/gpt-4.1")).toBe("o200k");
+    expect(tokenEncodingForModel("gpt-3.5-turbo")).toBe("cl100k");
+    expect(tokenEncodingForModel("llama-3-8b")).toBeNull();
+  });
+
+  it("falls back to the rough estimate for unknown models", () => {
+    const text = "Привет, как у тебя дела сегодня?";
+    expect(countTokens(text, "mistral-large")).toBe(roughTokenCount(text));
+    expect(countTokens(text)).toBe(roughTokenCount(text));
+  });
+
+  it("counts CJK and Cyrillic text higher than the character heuristic for known encodings", () => {
+    const japanese = "今日はいい天気ですね";
+    expect(countTokens(japanese, "gpt-4")).toBeGreaterThanOrEqual(japanese.length);
+    expect(countTokens(japanese, "gpt-4o")).toBeGreaterThan(roughTokenCount(japanese));
+    expect(countTokens("Привет, как у тебя дела сегодня?", "gpt-4")).toBeGreaterThan(10);
+    expect(countTokens("", "gpt-4o")).toBe(0);
+  });
+});
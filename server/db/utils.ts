@@ -56,6 +56,52 @@ export function roughTokenCount(text: string): number {
   return Math.ceil(text.length / 3.7);
 }
 
+export type TokenEncoding = "o200k" | "cl100k";
+
+interface TokenEncodingProfile {
+  latinCharsPerToken: number;
+  otherLetterCharsPerToken: number;
+  cjkTokensPerChar: number;
+}
+
+// Approximate BPE merge rates per script for each OpenAI encoding.
+const TOKEN_ENCODING_PROFILES: Record<TokenEncoding, TokenEncodingProfile> = {
+  o200k: { latinCharsPerToken: 4.4, otherLetterCharsPerToken: 3.2, cjkTokensPerChar: 0.75 },
+  cl100k: { latinCharsPerToken: 4.1, otherLetterCharsPerToken: 2.2, cjkTokensPerChar: 1.1 }
+};
+
+const TOKEN_SEGMENT_PATTERN =
+  /[\p{Script=Han}\p{Script=Hiragana}\p{Script=Katakana}\p{Script=Hangul}]+|[A-Za-z]+|\p{L}+|\p{N}+|\s+|[^\s\p{L}\p{N}]+/gu;
+const CJK_SEGMENT_PATTERN = /^[\p{Script=Han}\p{Script=Hiragana}\p{Script=Katakana}\p{Script=Hangul}]/u;
+
+/** Picks the encoding for model ids that are known to use it; other models have no known encoding. */
+export function tokenEncodingForModel(model: string | null | undefined): TokenEncoding | null {
+  const id = String(model || "").toLowerCase().split("/").pop() || "";
+  if (/^(gpt-4o|gpt-4\.1|gpt-4\.5|gpt-5|chatgpt-4o|o1|o3|o4)/.test(id)) return "o200k";
+  if (/^(gpt-4|gpt-3\.5|text-embedding-3|text-embedding-ada)/.test(id)) return "cl100k";
+  return null;
+}
+
+/**
+ * Script-aware estimate for models with a known encoding: words, digit groups and punctuation runs are
+ * costed separately so CJK, Cyrillic and code are not undercounted. Unknown models keep `roughTokenCount`.
+ */
+export function countTokens(text: string, model?: string | null): number {
+  const encoding = tokenEncodingForModel(model);
+  if (!encoding) return roughTokenCount(text);
+  const profile = TOKEN_ENCODING_PROFILES[encoding];
+  let total = 0;
+  for (const [segment] of String(text || "").matchAll(TOKEN_SEGMENT_PATTERN)) {
+    if (CJK_SEGMENT_PATTERN.test(segment)) total += [...segment].length * profile.cjkTokensPerChar;
+    else if (/^[A-Za-z]/.test(segment)) total += Math.max(1, segment.length / profile.latinCharsPerToken);
+    else if (/^\p{L}/u.test(segment)) total += Math.max(1, [...segment].length / profile.otherLetterCharsPerToken);
+    else if (/^\p{N}/u.test(segment)) total += Math.ceil(segment.length / 3);
+    else if (/^\s/.test(segment)) total += segment.includes("\n") ? 1 : Math.floor((segment.length - 1) / 4);
+    else total += Math.ceil([...segment].length / 2);
+  }
+  return Math.ceil(total);
+}
+
 export function maskApiKey(raw: string): string {
   if (raw.length <= 8) return "********";
   return `${raw.slice(0, 4)}***${raw.slice(-4)}`;
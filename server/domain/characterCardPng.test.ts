@@ -0,0 +1,62 @@
+import { describe, expect, it } from "vitest";
+import { embedCharacterCardInPng, extractCharacterCardJsonFromPng, isPngBuffer, readPngTextChunks } from "./characterCardPng.js";
+
+function pngChunk(type: string, data: Buffer): Buffer {
+  const length = Buffer.alloc(4);
+  length.writeUInt32BE(data.length);
+  // CRC is not validated by the reader, so zeroes keep the fixture short.
+  return Buffer.concat([length, Buffer.from(type, "latin1"), data, Buffer.alloc(4)]);
+}
+
+function textChunk(keyword: string, text: string): Buffer {
+  return pngChunk("tEXt", Buffer.concat([Buffer.from(keyword, "latin1"), Buffer.from([0]), Buffer.from(text, "latin1")]));
+}
+
+function buildPng(...chunks: Buffer[]): Buffer {
+  return Buffer.concat([
+    Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]),
+    pngChunk("IHDR", Buffer.alloc(13)),
+    ...chunks,
+    pngChunk("IEND", Buffer.alloc(0))
+  ]);
+}
+
+const encode = (value: unknown) => Buffer.from(JSON.stringify(value), "utf8").toString("base64");
+
+describe("character card PNG parsing", () => {
+  it("reads the base64 chara tEXt chunk", () => {
+    const card = { spec: "chara_card_v2", spec_version: "2.0", data: { name: "Ünïcode Keeper" } };
+    const png = buildPng(textChunk("Software", "test"), textChunk("chara", encode(card)));
+
+    expect(isPngBuffer(png)).toBe(true);
+    expect(readPngTextChunks(png).get("Software")).toBe("test");
+    expect(JSON.parse(extractCharacterCardJsonFromPng(png) || "null")).toEqual(card);
+  });
+
+  it("prefers the V3 ccv3 chunk when both are present", () => {
+    const v2 = { spec: "chara_card_v2", data: { name: "Old" } };
+    const v3 = { spec: "chara_card_v3", data: { name: "New" } };
+    const png = buildPng(textChunk("chara", encode(v2)), textChunk("ccv3", encode(v3)));
+
+    expect(JSON.parse(extractCharacterCardJsonFromPng(png) || "null")).toEqual(v3);
+  });
+
+  it("returns null for non-PNG input and PNGs without a card", () => {
+    expect(extractCharacterCardJsonFromPng(Buffer.from("{\"spec\":\"chara_card_v2\"}"))).toBeNull();
+    expect(extractCharacterCardJsonFromPng(buildPng(textChunk("chara", "not base64 json")))).toBeNull();
+  });
+
+  it("embeds the exact card JSON so it round-trips and replaces older card chunks", () => {
+    const cardJson = JSON.stringify({ spec: "chara_card_v2", spec_version: "2.0", data: { name: "Exported — Keeper" } }, null, 2);
+    const avatar = buildPng(textChunk("ccv3", encode({ spec: "chara_card_v3", data: { name: "Stale" } })), textChunk("Software", "test"));
+
+    const exported = embedCharacterCardInPng(avatar, cardJson);
+    const chunks = readPngTextChunks(exported);
+
+    expect(extractCharacterCardJsonFromPng(exported)).toBe(cardJson);
+    expect(chunks.has("ccv3")).toBe(false);
+    expect(chunks.get("Software")).toBe("test");
+    expect(exported.subarray(-12).toString("latin1", 4, 8)).toBe("IEND");
+    expect(() => embedCharacterCardInPng(Buffer.from("not a png"), cardJson)).toThrow("not a PNG");
+  });
+});
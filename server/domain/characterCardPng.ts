@@ -0,0 +1,110 @@
+// Character cards shipped as PNGs carry their JSON base64-encoded in a tEXt chunk:
+// `chara` for V2 cards and `ccv3` for V3 cards.
+
+const PNG_SIGNATURE = Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]);
+const CARD_KEYWORDS = ["ccv3", "chara"] as const;
+
+const CRC_TABLE = (() => {
+  const table = new Uint32Array(256);
+  for (let n = 0; n < 256; n += 1) {
+    let c = n;
+    for (let k = 0; k < 8; k += 1) c = c & 1 ? 0xedb88320 ^ (c >>> 1) : c >>> 1;
+    table[n] = c >>> 0;
+  }
+  return table;
+})();
+
+function crc32(buffer: Buffer): number {
+  let crc = 0xffffffff;
+  for (const byte of buffer) crc = CRC_TABLE[(crc ^ byte) & 0xff] ^ (crc >>> 8);
+  return (crc ^ 0xffffffff) >>> 0;
+}
+
+function buildChunk(type: string, data: Buffer): Buffer {
+  const header = Buffer.alloc(8);
+  header.writeUInt32BE(data.length, 0);
+  header.write(type, 4, "latin1");
+  const crc = Buffer.alloc(4);
+  crc.writeUInt32BE(crc32(Buffer.concat([header.subarray(4), data])));
+  return Buffer.concat([header, data, crc]);
+}
+
+export function isPngBuffer(buffer: Buffer): boolean {
+  return buffer.length >= PNG_SIGNATURE.length && buffer.subarray(0, PNG_SIGNATURE.length).equals(PNG_SIGNATURE);
+}
+
+export function readPngTextChunks(buffer: Buffer): Map<string, string> {
+  const chunks = new Map<string, string>();
+  if (!isPngBuffer(buffer)) return chunks;
+  let offset = PNG_SIGNATURE.length;
+  while (offset + 8 <= buffer.length) {
+    const length = buffer.readUInt32BE(offset);
+    const type = buffer.toString("latin1", offset + 4, offset + 8);
+    const dataStart = offset + 8;
+    const dataEnd = dataStart + length;
+    if (dataEnd + 4 > buffer.length) break;
+    if (type === "tEXt") {
+      const data = buffer.subarray(dataStart, dataEnd);
+      const separator = data.indexOf(0);
+      if (separator > 0) {
+        const keyword = data.toString("latin1", 0, separator);
+        if (!chunks.has(keyword)) chunks.set(keyword, data.toString("latin1", separator + 1));
+      }
+    }
+    if (type === "IEND") break;
+    offset = dataEnd + 4;
+  }
+  return chunks;
+}
+
+/** Returns the embedded card JSON, preferring the V3 `ccv3` chunk over V2 `chara`. */
+export function extractCharacterCardJsonFromPng(buffer: Buffer): string | null {
+  const chunks = readPngTextChunks(buffer);
+  for (const keyword of CARD_KEYWORDS) {
+    const encoded = chunks.get(keyword)?.trim();
+    if (!encoded) continue;
+    const decoded = Buffer.from(encoded, "base64").toString("utf8").trim();
+    if (decoded.startsWith("{")) return decoded;
+  }
+  return null;
+}
+
+function buildCardChunk(keyword: string, cardJson: string): Buffer {
+  return buildChunk(
+    "tEXt",
+    Buffer.concat([Buffer.from(keyword, "latin1"), Buffer.from([0]), Buffer.from(Buffer.from(cardJson, "utf8").toString("base64"), "latin1")])
+  );
+}
+
+/**
+ * Writes `cardJson` into a `chara` tEXt chunk (base64 of the exact JSON) just before IEND, plus a
+ * `ccv3` chunk when a V3 card is given. Existing card chunks are dropped so the export has one source of truth.
+ */
+export function embedCharacterCardInPng(buffer: Buffer, cardJson: string, v3CardJson?: string): Buffer {
+  if (!isPngBuffer(buffer)) throw new Error("Avatar is not a PNG image");
+  const parts: Buffer[] = [buffer.subarray(0, PNG_SIGNATURE.length)];
+  const cardChunks = [buildCardChunk("chara", cardJson)];
+  if (v3CardJson) cardChunks.push(buildCardChunk("ccv3", v3CardJson));
+  let offset = PNG_SIGNATURE.length;
+  let sawEnd = false;
+  while (offset + 8 <= buffer.length) {
+    const length = buffer.readUInt32BE(offset);
+    const type = buffer.toString("latin1", offset + 4, offset + 8);
+    const chunkEnd = offset + 12 + length;
+    if (chunkEnd > buffer.length) throw new Error("Avatar PNG is truncated");
+    const chunk = buffer.subarray(offset, chunkEnd);
+    if (type === "IEND") {
+      parts.push(...cardChunks, chunk);
+      sawEnd = true;
+      break;
+    }
+    const isCardChunk = type === "tEXt" && CARD_KEYWORDS.some((keyword) => {
+      const data = chunk.subarray(8, 8 + length);
+      return data.indexOf(0) === keyword.length && data.toString("latin1", 0, keyword.length) === keyword;
+    });
+    if (!isCardChunk) parts.push(chunk);
+    offset = chunkEnd;
+  }
+  if (!sawEnd) throw new Error("Avatar PNG has no IEND chunk");
+  return Buffer.concat(parts);
+}
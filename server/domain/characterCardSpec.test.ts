@@ -0,0 +1,60 @@
+import { describe, expect, it } from "vitest";
+import {
+  isCharacterCardV1,
+  toCharacterCardV2View,
+  upgradeCharacterCardV1ToV2,
+  upgradeCharacterCardV2ToV3
+} from "./characterCardSpec.js";
+import { validateCharacterCard } from "./characterCardValidation.js";
+
+describe("character card spec conversions", () => {
+  it("upgrades a flat V1 card into a valid V2 card", () => {
+    const v1 = { name: "Old Timer", description: "Retired.", personality: "Gruff", scenario: "", first_mes: "Eh?", mes_example: "" };
+    expect(isCharacterCardV1(v1)).toBe(true);
+    expect(isCharacterCardV1({ spec: "chara_card_v2", data: { name: "x" } })).toBe(false);
+
+    const v2 = upgradeCharacterCardV1ToV2(v1);
+    expect(v2).toMatchObject({ spec: "chara_card_v2", spec_version: "2.0", data: { name: "Old Timer", first_mes: "Eh?", tags: [] } });
+    expect(validateCharacterCard(v2)).toEqual([]);
+  });
+
+  it("detects V1 cards by top-level first_mes and carries over V2-typed extras", () => {
+    const v1 = { first_mes: "Morning.", tags: ["legacy", 7], creator: "someone", talkativeness: "0.5" };
+    expect(isCharacterCardV1(v1)).toBe(true);
+    expect(isCharacterCardV1({ description: "no name or greeting" })).toBe(false);
+    expect(validateCharacterCard(v1)).toEqual(["card is a V1 card without spec; convert it to chara_card_v2 first"]);
+
+    const { data } = upgradeCharacterCardV1ToV2(v1);
+    expect(data).toMatchObject({ name: "", first_mes: "Morning.", tags: ["legacy"], creator: "someone", extensions: {} });
+    expect(data).not.toHaveProperty("talkativeness");
+  });
+
+  it("upgrades V2 to V3 and exposes a V2 view without V3-only fields", () => {
+    const v2 = upgradeCharacterCardV1ToV2({ name: "Bridge", first_mes: "Hi." });
+    const v3 = upgradeCharacterCardV2ToV3({ ...v2, data: { ...v2.data, nickname: "Bri" } });
+    expect(v3).toMatchObject({ spec: "chara_card_v3", spec_version: "3.0", data: { nickname: "Bri", group_only_greetings: [] } });
+    expect(validateCharacterCard(v3)).toEqual([]);
+
+    const view = toCharacterCardV2View(v3);
+    expect(view.spec).toBe("chara_card_v2");
+    expect(view.data).not.toHaveProperty("nickname");
+    expect(view.data).not.toHaveProperty("assets");
+    expect(view.data.first_mes).toBe("Hi.");
+    expect(validateCharacterCard(view)).toEqual([]);
+  });
+
+  it("validates V3 spec_version and V3-only fields", () => {
+    const base = upgradeCharacterCardV1ToV2({ name: "V3" }).data;
+    expect(validateCharacterCard({ spec: "chara_card_v3", data: base })).toEqual(["missing spec_version"]);
+    expect(validateCharacterCard({
+      spec: "chara_card_v3",
+      spec_version: "2.0",
+      data: { ...base, nickname: 3, assets: [{ type: "icon" }], creator_notes_multilingual: { en: 1 } }
+    })).toEqual([
+      "spec_version must be 3.x for chara_card_v3",
+      "data.nickname must be a string",
+      "data.creator_notes_multilingual must map language codes to strings",
+      "data.assets[0] must have string type, uri, name and ext"
+    ]);
+  });
+});
@@ -0,0 +1,58 @@
+// Conversions between character card spec versions (V1 flat cards, chara_card_v2, chara_card_v3).
+
+export interface CharacterCardEnvelope {
+  spec: string;
+  spec_version: string;
+  data: Record<string, unknown>;
+}
+
+const V1_FIELDS = ["name", "description", "personality", "scenario", "first_mes", "mes_example"] as const;
+// Fields some V1-era exporters already wrote at the top level; carried over when they have the V2 type.
+const V1_CARRIED_STRING_FIELDS = ["creator_notes", "system_prompt", "post_history_instructions", "creator", "character_version"] as const;
+const V1_CARRIED_LIST_FIELDS = ["tags", "alternate_greetings"] as const;
+const V3_ONLY_FIELDS = [
+  "nickname",
+  "creator_notes_multilingual",
+  "source",
+  "group_only_greetings",
+  "creation_date",
+  "modification_date",
+  "assets"
+] as const;
+
+function isRecord(value: unknown): value is Record<string, unknown> {
+  return Boolean(value) && typeof value === "object" && !Array.isArray(value);
+}
+
+/** V1 cards have no `spec` and keep `name` / `first_mes` at the top level. */
+export function isCharacterCardV1(value: unknown): value is Record<string, unknown> {
+  if (!isRecord(value) || value.spec !== undefined || isRecord(value.data)) return false;
+  return typeof value.name === "string" || typeof value.first_mes === "string";
+}
+
+export function upgradeCharacterCardV1ToV2(card: Record<string, unknown>): CharacterCardEnvelope {
+  const data: Record<string, unknown> = {};
+  for (const field of V1_FIELDS) data[field] = typeof card[field] === "string" ? card[field] : "";
+  for (const field of V1_CARRIED_STRING_FIELDS) data[field] = typeof card[field] === "string" ? card[field] : "";
+  for (const field of V1_CARRIED_LIST_FIELDS) {
+    const list = card[field];
+    data[field] = Array.isArray(list) ? list.filter((item): item is string => typeof item === "string") : [];
+  }
+  data.extensions = isRecord(card.extensions) ? card.extensions : {};
+  return { spec: "chara_card_v2", spec_version: "2.0", data };
+}
+
+export function upgradeCharacterCardV2ToV3(card: CharacterCardEnvelope): CharacterCardEnvelope {
+  const data = { ...card.data };
+  if (!Array.isArray(data.group_only_greetings)) data.group_only_greetings = [];
+  if (!Array.isArray(data.assets)) data.assets = [{ type: "icon", uri: "ccdefault:", name: "main", ext: "unknown" }];
+  return { spec: "chara_card_v3", spec_version: "3.0", data };
+}
+
+/** V2 view of any supported card: V3-only fields are dropped so older consumers see a plain chara_card_v2. */
+export function toCharacterCardV2View(card: CharacterCardEnvelope): CharacterCardEnvelope {
+  if (card.spec === "chara_card_v2") return card;
+  const data = { ...card.data };
+  for (const field of V3_ONLY_FIELDS) delete data[field];
+  return { spec: "chara_card_v2", spec_version: "2.0", data };
+}
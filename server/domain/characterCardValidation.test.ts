@@ -0,0 +1,82 @@
+import { describe, expect, it } from "vitest";
+import { validateCharacterCard } from "./characterCardValidation.js";
+
+const validData = {
+  name: "Valid",
+  description: "",
+  personality: "",
+  scenario: "",
+  first_mes: "Hello.",
+  mes_example: ""
+};
+
+describe("validateCharacterCard", () => {
+  it("accepts a complete V2 card with a character book", () => {
+    expect(validateCharacterCard({
+      spec: "chara_card_v2",
+      spec_version: "2.0",
+      data: {
+        ...validData,
+        tags: ["a"],
+        alternate_greetings: ["Hi."],
+        extensions: {},
+        character_book: { entries: [{ keys: ["k"], content: "lore", enabled: true, insertion_order: 1, extensions: {} }], extensions: {} }
+      }
+    })).toEqual([]);
+  });
+
+  it("reports wrong spec and missing data", () => {
+    expect(validateCharacterCard({ spec: "tavern" })).toEqual(["spec must be chara_card_v2 or chara_card_v3", "missing data object"]);
+    expect(validateCharacterCard([])).toEqual(["card must be a JSON object"]);
+  });
+
+  it("reports each missing or mistyped required field", () => {
+    expect(validateCharacterCard({
+      spec: "chara_card_v2",
+      data: { name: "  ", description: 42, personality: "", scenario: "", first_mes: "" }
+    })).toEqual([
+      "data.description must be a string",
+      "missing data.mes_example",
+      "data.name must not be empty"
+    ]);
+  });
+
+  it("reports wrong types for optional fields", () => {
+    expect(validateCharacterCard({
+      spec: "chara_card_v2",
+      data: { ...validData, tags: "a,b", alternate_greetings: [1], system_prompt: null, extensions: [] }
+    })).toEqual([
+      "data.system_prompt must be a string",
+      "data.tags must be an array of strings",
+      "data.alternate_greetings must be an array of strings",
+      "data.extensions must be an object"
+    ]);
+  });
+
+  it("reports malformed character_book entries by index", () => {
+    expect(validateCharacterCard({
+      spec: "chara_card_v2",
+      data: {
+        ...validData,
+        character_book: {
+          scan_depth: "4",
+          entries: [
+            { keys: "dragon", content: "x" },
+            "entry",
+            { keys: ["k"], content: 5, enabled: "yes", insertion_order: "1", secondary_keys: [false] }
+          ]
+        }
+      }
+    })).toEqual([
+      "data.character_book.scan_depth must be a number",
+      "data.character_book.entries[0].keys must be an array of strings",
+      "data.character_book.entries[1] must be an object",
+      "data.character_book.entries[2].content must be a string",
+      "data.character_book.entries[2].enabled must be a boolean",
+      "data.character_book.entries[2].insertion_order must be a number",
+      "data.character_book.entries[2].secondary_keys must be an array of strings"
+    ]);
+    expect(validateCharacterCard({ spec: "chara_card_v2", data: { ...validData, character_book: { entries: {} } } }))
+      .toEqual(["data.character_book.entries must be an array"]);
+  });
+});
@@ -0,0 +1,103 @@
+// Field-level checks for chara_card_v2 and chara_card_v3 cards before import.
+import { isCharacterCardV1 } from "./characterCardSpec.js";
+
+const REQUIRED_STRING_FIELDS = ["name", "description", "personality", "scenario", "first_mes", "mes_example"] as const;
+const OPTIONAL_STRING_FIELDS = ["creator_notes", "system_prompt", "post_history_instructions", "creator", "character_version"] as const;
+const OPTIONAL_STRING_ARRAY_FIELDS = ["tags", "alternate_greetings"] as const;
+
+function isRecord(value: unknown): value is Record<string, unknown> {
+  return Boolean(value) && typeof value === "object" && !Array.isArray(value);
+}
+
+function isStringArray(value: unknown): value is string[] {
+  return Array.isArray(value) && value.every((item) => typeof item === "string");
+}
+
+function validateCharacterBook(book: unknown, errors: string[]) {
+  if (!isRecord(book)) {
+    errors.push("data.character_book must be an object");
+    return;
+  }
+  if (book.name !== undefined && typeof book.name !== "string") errors.push("data.character_book.name must be a string");
+  for (const field of ["scan_depth", "token_budget"] as const) {
+    if (book[field] !== undefined && typeof book[field] !== "number") errors.push(`data.character_book.${field} must be a number`);
+  }
+  if (!Array.isArray(book.entries)) {
+    errors.push("data.character_book.entries must be an array");
+    return;
+  }
+  book.entries.forEach((entry, index) => {
+    const path = `data.character_book.entries[${index}]`;
+    if (!isRecord(entry)) {
+      errors.push(`${path} must be an object`);
+      return;
+    }
+    if (!isStringArray(entry.keys)) errors.push(`${path}.keys must be an array of strings`);
+    if (typeof entry.content !== "string") errors.push(`${path}.content must be a string`);
+    if (entry.enabled !== undefined && typeof entry.enabled !== "boolean") errors.push(`${path}.enabled must be a boolean`);
+    if (entry.insertion_order !== undefined && typeof entry.insertion_order !== "number") {
+      errors.push(`${path}.insertion_order must be a number`);
+    }
+    if (entry.secondary_keys !== undefined && !isStringArray(entry.secondary_keys)) {
+      errors.push(`${path}.secondary_keys must be an array of strings`);
+    }
+  });
+}
+
+function validateV3Fields(data: Record<string, unknown>, errors: string[]) {
+  if (data.nickname !== undefined && typeof data.nickname !== "string") errors.push("data.nickname must be a string");
+  for (const field of ["group_only_greetings", "source"] as const) {
+    if (data[field] !== undefined && !isStringArray(data[field])) errors.push(`data.${field} must be an array of strings`);
+  }
+  for (const field of ["creation_date", "modification_date"] as const) {
+    if (data[field] !== undefined && typeof data[field] !== "number") errors.push(`data.${field} must be a number`);
+  }
+  const multilingual = data.creator_notes_multilingual;
+  if (multilingual !== undefined && (!isRecord(multilingual) || Object.values(multilingual).some((note) => typeof note !== "string"))) {
+    errors.push("data.creator_notes_multilingual must map language codes to strings");
+  }
+  if (data.assets === undefined) return;
+  if (!Array.isArray(data.assets)) {
+    errors.push("data.assets must be an array");
+    return;
+  }
+  data.assets.forEach((asset, index) => {
+    const valid = isRecord(asset) && ["type", "uri", "name", "ext"].every((key) => typeof asset[key] === "string");
+    if (!valid) errors.push(`data.assets[${index}] must have string type, uri, name and ext`);
+  });
+}
+
+/** Returns one message per schema violation; an empty list means the card can be imported as-is. */
+export function validateCharacterCard(parsed: unknown): string[] {
+  const errors: string[] = [];
+  if (!isRecord(parsed)) return ["card must be a JSON object"];
+  if (isCharacterCardV1(parsed)) return ["card is a V1 card without spec; convert it to chara_card_v2 first"];
+  if (parsed.spec !== "chara_card_v2" && parsed.spec !== "chara_card_v3") errors.push("spec must be chara_card_v2 or chara_card_v3");
+  const expectedMajor = parsed.spec === "chara_card_v3" ? "3" : "2";
+  if (parsed.spec_version !== undefined && !String(parsed.spec_version).startsWith(`${expectedMajor}.`)) {
+    errors.push(`spec_version must be ${expectedMajor}.x for ${String(parsed.spec)}`);
+  } else if (parsed.spec === "chara_card_v3" && parsed.spec_version === undefined) {
+    errors.push("missing spec_version");
+  }
+  const data = parsed.data;
+  if (!isRecord(data)) {
+    errors.push("missing data object");
+    return errors;
+  }
+
+  for (const field of REQUIRED_STRING_FIELDS) {
+    if (data[field] === undefined) errors.push(`missing data.${field}`);
+    else if (typeof data[field] !== "string") errors.push(`data.${field} must be a string`);
+  }
+  if (typeof data.name === "string" && !data.name.trim()) errors.push("data.name must not be empty");
+  for (const field of OPTIONAL_STRING_FIELDS) {
+    if (data[field] !== undefined && typeof data[field] !== "string") errors.push(`data.${field} must be a string`);
+  }
+  for (const field of OPTIONAL_STRING_ARRAY_FIELDS) {
+    if (data[field] !== undefined && !isStringArray(data[field])) errors.push(`data.${field} must be an array of strings`);
+  }
+  if (data.extensions !== undefined && !isRecord(data.extensions)) errors.push("data.extensions must be an object");
+  if (data.character_book !== undefined) validateCharacterBook(data.character_book, errors);
+  if (parsed.spec === "chara_card_v3") validateV3Fields(data, errors);
+  return errors;
+}
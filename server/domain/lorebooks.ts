@@ -108,7 +108,9 @@ export function normalizeLoreBookEntries(input: unknown): LoreBookEntryData[] {
     const row = rows[index];
     const content = String(row.content || "").trim();
     if (!content) continue;
-    const id = String(row.id || row.uid || "").trim() || `entry-${index + 1}`;
+    // V2 cards number entries from 0, so only missing ids fall back to the index.
+    const rawId = row.id ?? row.uid;
+    const id = (rawId === undefined || rawId === null ? "" : String(rawId)).trim() || `entry-${index + 1}`;
     out.push({
       id,
       name: String(row.name || row.comment || "").trim(),
@@ -233,6 +235,32 @@ export function getTriggeredLoreEntries(entries: LoreBookEntryData[], timelineTe
     .sort((a, b) => a.insertionOrder - b.insertionOrder);
 }
 
+/**
+ * Drops repeated entries (same id or same text across linked books) and keeps entries in
+ * insertion order until the token budget is spent; a budget of 0 keeps everything.
+ */
+export function limitLoreEntries(
+  entries: LoreBookEntryData[],
+  tokenBudget: number,
+  countTokens: (text: string) => number
+): LoreBookEntryData[] {
+  const seenIds = new Set<string>();
+  const seenContent = new Set<string>();
+  const out: LoreBookEntryData[] = [];
+  let remaining = tokenBudget > 0 ? tokenBudget : Number.POSITIVE_INFINITY;
+  for (const entry of entries) {
+    const contentKey = entry.content.trim().replace(/\s+/g, " ").toLowerCase();
+    if (seenIds.has(entry.id) || seenContent.has(contentKey)) continue;
+    const cost = countTokens(entry.content);
+    if (cost > remaining) continue;
+    seenIds.add(entry.id);
+    seenContent.add(contentKey);
+    remaining -= cost;
+    out.push(entry);
+  }
+  return out;
+}
+
 function escapeRegex(input: string): string {
   return input.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
 }
@@ -6,12 +6,18 @@ import {
   migrateDefaultSystemPrompt
 } from "../db/defaultSettings.js";
 import {
+  applyMessageMacros,
+  applySceneSampling,
+  buildCensorshipDirective,
+  buildResponseLanguageDirective,
   buildMessageArray,
   buildMultiCharMessageArray,
   buildMultiCharSystemPrompt,
+  buildPostHistoryBlocksPrompt,
   buildSystemPrompt,
   coalesceSystemMessages,
   mergeConsecutiveRoles,
+  splitPromptBlocksAtHistory,
   type CharacterCardData,
   type PromptContext
 } from "./rpEngine.js";
@@ -157,6 +163,58 @@ describe("default roleplay system prompt", () => {
     expect(messages[0]?.content).toContain("[Post-History Instructions]");
   });
 
+  it("injects the author's note at its depth inside longer histories", () => {
+    const history = ["one", "two", "three", "four", "five", "six"].map((content, index) => ({
+      role: index % 2 === 0 ? "user" : "assistant",
+      content
+    }));
+    const withNote = (depth?: number) => buildMessageArray("Base system", history, "Mind the fog.", "", "Alice", "Reader", "", depth);
+
+    expect(withNote().map((message) => message.content)).toEqual([
+      "Base system", "one", "two", "[Author's Note: Mind the fog.]", "three", "four", "five", "six"
+    ]);
+    expect(withNote(4)[3]?.role).toBe("user");
+    expect(withNote(0).at(-1)).toEqual({ role: "user", content: "[Author's Note: Mind the fog.]" });
+    const beforeEverything = withNote(10);
+    expect(beforeEverything).toHaveLength(7);
+    expect(beforeEverything[0]?.content).toContain("[Author's Note: Mind the fog.]");
+  });
+
+  it("sends blocks ordered after the history placeholder as post-history instructions", () => {
+    const blocks = [
+      { id: "late", kind: "jailbreak", enabled: true, order: 4, content: "Stay terse with {{user}}." },
+      { id: "system", kind: "system", enabled: true, order: 1, content: "Base rules" },
+      { id: "history", kind: "history", enabled: true, order: 2, content: "" },
+      { id: "off", kind: "lore", enabled: false, order: 3, content: "Hidden lore" }
+    ];
+
+    const { beforeHistory, afterHistory } = splitPromptBlocksAtHistory(blocks);
+    expect(beforeHistory.map((block) => block.id)).toEqual(["system"]);
+    expect(afterHistory.map((block) => block.id)).toEqual(["off", "late"]);
+    expect(buildPostHistoryBlocksPrompt(afterHistory, {
+      characterCard: null,
+      sceneState: null,
+      intensity: 0,
+      userName: "Reader"
+    })).toBe("Stay terse with Reader.");
+
+    const withoutHistory = splitPromptBlocksAtHistory(blocks.filter((block) => block.kind !== "history"));
+    expect(withoutHistory.afterHistory).toEqual([]);
+    expect(withoutHistory.beforeHistory.map((block) => block.id)).toEqual(["system", "off", "late"]);
+  });
+
+  it("expands persona and scene variable macros in every text part of a request", () => {
+    const messages = applyMessageMacros([
+      { role: "system", content: "Setting: {{location}}. Persona: {{persona}}" },
+      { role: "user", content: [{ type: "text", text: "Go to {{location}}" }, { type: "image_url", image_url: { url: "data:image/png;base64,AA" } }] }
+    ], { persona: "A tired cartographer", variables: { location: "the lighthouse" } });
+    expect(messages[0]?.content).toBe("Setting: the lighthouse. Persona: A tired cartographer");
+    expect(messages[1]?.content).toEqual([
+      { type: "text", text: "Go to the lighthouse" },
+      { type: "image_url", image_url: { url: "data:image/png;base64,AA" } }
+    ]);
+  });
+
   it("moves late system instructions into the same leading message", () => {
     const normalized = coalesceSystemMessages([
       { role: "user", content: "First user turn" },
@@ -202,3 +260,64 @@ describe("default roleplay system prompt", () => {
     }]);
   });
 });
+
+describe("applySceneSampling", () => {
+  it("moves temperature linearly with intensity around the configured base", () => {
+    expect(applySceneSampling({ temperature: 0.9 }, 0).temperature).toBe(0.7);
+    expect(applySceneSampling({ temperature: 0.9 }, 0.5).temperature).toBe(0.9);
+    expect(applySceneSampling({ temperature: 0.9 }, 0.75).temperature).toBe(1);
+    expect(applySceneSampling({ temperature: 0.9 }, 1).temperature).toBe(1.1);
+  });
+
+  it("clamps the result and falls back to the default base", () => {
+    expect(applySceneSampling({ temperature: 0.15 }, 0).temperature).toBe(0.1);
+    expect(applySceneSampling({ temperature: 1.95 }, 1).temperature).toBe(2);
+    expect(applySceneSampling({ topP: 0.8 }, 1)).toEqual({ topP: 0.8, temperature: 1.1 });
+    expect(applySceneSampling({ temperature: 0.9 }, Number.NaN).temperature).toBe(0.9);
+  });
+});
+
+describe("censorship mode", () => {
+  it("adds the content policy only in Filtered and Moderated modes, for every prompt builder", () => {
+    const alice = character("Alice");
+    const filtered = { ...context(alice), censorshipMode: "Filtered" };
+    const unfiltered = { ...context(alice), censorshipMode: "Unfiltered" };
+    const pure = (censorshipMode: string) => buildSillyTavernCompatiblePurePrompt({
+      baseSystemPrompt: "Base", currentCharacter: alice, characterCards: [alice], userName: "Reader", strictGrounding: false, censorshipMode
+    });
+    const light = (censorshipMode: string) => buildSillyTavernCompatibleLightPrompt({
+      baseSystemPrompt: "Base", currentCharacter: null, characterCards: [], userName: "Reader", strictGrounding: false, censorshipMode
+    });
+
+    for (const prompt of [buildSystemPrompt(filtered), buildMultiCharSystemPrompt(filtered, [alice], "Alice"), pure("Filtered"), light("Filtered")]) {
+      expect(prompt).toContain(buildCensorshipDirective("Filtered"));
+    }
+    for (const prompt of [buildSystemPrompt(unfiltered), buildMultiCharSystemPrompt(unfiltered, [alice], "Alice"), pure("Unfiltered"), light("Unfiltered")]) {
+      expect(prompt).not.toContain("[Content Policy]");
+      expect(prompt).not.toContain("content restrictions");
+    }
+    expect(buildCensorshipDirective("Moderated")).toBe(buildCensorshipDirective("Filtered"));
+    expect(buildCensorshipDirective("Unfiltered")).toBe("");
+    expect(buildCensorshipDirective("unknown")).toBe("");
+  });
+});
+
+describe("response language", () => {
+  it("adds the language directive after the character card in every prompt mode", () => {
+    const alice = character("Alice");
+    const directive = buildResponseLanguageDirective("Japanese");
+    const prompts = [
+      buildSystemPrompt({ ...context(alice), responseLanguage: "Japanese" }),
+      buildSillyTavernCompatiblePurePrompt({
+        baseSystemPrompt: "Base", currentCharacter: alice, characterCards: [alice], userName: "Reader", strictGrounding: false, responseLanguage: "Japanese"
+      })
+    ];
+    for (const prompt of prompts) {
+      expect(prompt).toContain(directive);
+      expect(prompt.indexOf(directive)).toBeGreaterThan(prompt.indexOf("Alice description"));
+    }
+    expect(directive).toContain("write the reply itself in Japanese");
+    expect(buildResponseLanguageDirective("English")).toBe("");
+    expect(buildResponseLanguageDirective("  ")).toBe("");
+  });
+});
@@ -1,5 +1,6 @@
 // Full RP prompt builder engine
 import { describeSceneLevel } from "../../src/shared/sceneLevels.js";
+import { expandMacros, type MacroContext } from "./rpMacros.js";
 
 export interface PromptBlock {
   id: string;
@@ -44,13 +45,44 @@ export interface PromptContext {
   userName?: string;
 }
 
-/** Replace {{char}} and {{user}} placeholders without leaving template tokens in provider prompts. */
+const SCENE_TEMPERATURE_SWING = 0.4;
+const SCENE_TEMPERATURE_RANGE: [number, number] = [0.1, 2];
+
+/**
+ * Scene intensity shifts sampling temperature linearly around the chat's configured value:
+ * `temperature = base + (intensity - 0.5) * 0.4`, clamped to 0.1..2 and rounded to two decimals.
+ * Intensity 0 lowers the base by 0.2, 0.5 leaves it unchanged, 1 raises it by 0.2 (0.9 becomes 0.7..1.1).
+ * A missing base counts as 0.9; every other sampler field passes through unchanged.
+ */
+export function applySceneSampling(samplerConfig: Record<string, unknown>, intensity: number): Record<string, unknown> {
+  const level = Number.isFinite(intensity) ? Math.max(0, Math.min(1, intensity)) : 0.5;
+  const configured = Number(samplerConfig.temperature);
+  const base = samplerConfig.temperature !== undefined && samplerConfig.temperature !== null && Number.isFinite(configured)
+    ? configured
+    : 0.9;
+  const [min, max] = SCENE_TEMPERATURE_RANGE;
+  const temperature = Math.max(min, Math.min(max, base + (level - 0.5) * SCENE_TEMPERATURE_SWING));
+  return { ...samplerConfig, temperature: Math.round(temperature * 100) / 100 };
+}
+
+/** Expand prompt macros with resolved character/user names so `{{char}}`/`{{user}}` never reach providers. */
 export function replacePromptPlaceholders(text: string, charName?: string, userName?: string): string {
-  const resolvedCharacter = String(charName || "").trim() || "Character";
-  const resolvedUser = String(userName || "").trim() || "User";
-  return text
-    .replace(/\{\{char\}\}/gi, resolvedCharacter)
-    .replace(/\{\{user\}\}/gi, resolvedUser);
+  return expandMacros(text, {
+    char: String(charName || "").trim() || "Character",
+    user: String(userName || "").trim() || "User"
+  });
+}
+
+/** Run the macro pass over every text part of an outgoing request. */
+export function applyMessageMacros(messages: ChatCompletionMessage[], ctx: MacroContext): ChatCompletionMessage[] {
+  return messages.map((message) => ({
+    ...message,
+    content: typeof message.content === "string"
+      ? expandMacros(message.content, ctx)
+      : message.content.map((part) => (
+        typeof part.text === "string" ? { ...part, text: expandMacros(part.text, ctx) } : part
+      ))
+  }));
 }
 
 export interface ChatCompletionMessage {
@@ -118,6 +150,35 @@ function buildMessageContent(text: string, visionParts: ChatCompletionContentPar
   return content;
 }
 
+const CONTENT_POLICY = [
+  "[Content Policy]",
+  "Keep all content PG-13.",
+  "Do not write explicit sexual content, graphic violence or gore, or instructions for self-harm or illegal activity.",
+  "If the story heads there, fade to black or steer the scene elsewhere while staying in character."
+].join("\n");
+
+// Filtered and Moderated prepend a content policy (Moderated also screens user input before sending);
+// Unfiltered (and any unknown value) leaves the prompt untouched.
+const CENSORSHIP_DIRECTIVES: Record<string, string> = {
+  Filtered: CONTENT_POLICY,
+  Moderated: CONTENT_POLICY,
+  Unfiltered: ""
+};
+
+export function buildCensorshipDirective(mode: unknown): string {
+  return CENSORSHIP_DIRECTIVES[String(mode ?? "")] ?? "";
+}
+
+/**
+ * English is the models' default, so it adds nothing. The directive sits after the character card and
+ * leaves room for a character written to speak another tongue instead of overriding the card.
+ */
+export function buildResponseLanguageDirective(language: unknown): string {
+  const target = String(language ?? "").trim();
+  if (!target || target.toLowerCase() === "english") return "";
+  return `Always respond in ${target}. If a character is written to speak another language, keep that as occasional words or phrases in their speech, but write the reply itself in ${target}.`;
+}
+
 function buildGroundingRules(charName?: string, userName?: string): string {
   const lines = [
     "[Grounding Rules]",
@@ -183,22 +244,60 @@ export function buildSystemPrompt(ctx: PromptContext): string {
     parts.push("Write concisely, focusing on dialogue and key actions.");
   }
 
-  // Censorship mode
-  if (ctx.censorshipMode === "Filtered") {
-    parts.push("Keep all content PG-13. Avoid explicit, violent, or sexual content.");
-  } else if (ctx.censorshipMode === "Unfiltered") {
-    parts.push("You have no content restrictions. Write freely without censorship. All content is permitted for this creative fiction context.");
-  }
+  parts.push(buildCensorshipDirective(ctx.censorshipMode));
 
-  // Response language
-  if (ctx.responseLanguage && ctx.responseLanguage !== "English") {
-    parts.push(`Always respond in ${ctx.responseLanguage}.`);
-  }
+  parts.push(buildResponseLanguageDirective(ctx.responseLanguage));
 
   const raw = parts.filter(Boolean).join("\n\n");
   return replacePromptPlaceholders(raw, ctx.characterCard?.name, ctx.userName);
 }
 
+/** Splits a stack at its enabled history block; everything ordered after it is sent after the chat history. */
+export function splitPromptBlocksAtHistory(blocks: PromptBlock[]): { beforeHistory: PromptBlock[]; afterHistory: PromptBlock[] } {
+  const ordered = [...blocks].sort((a, b) => a.order - b.order);
+  const historyIndex = ordered.findIndex((block) => block.kind === "history" && block.enabled);
+  if (historyIndex < 0) return { beforeHistory: ordered, afterHistory: [] };
+  return { beforeHistory: ordered.slice(0, historyIndex), afterHistory: ordered.slice(historyIndex + 1) };
+}
+
+export function buildPostHistoryBlocksPrompt(
+  blocks: PromptBlock[],
+  ctx: Pick<PromptContext, "characterCard" | "sceneState" | "intensity" | "userName">
+): string {
+  const parts = blocks.filter((block) => block.enabled).map((block) => {
+    switch (block.kind) {
+      case "character":
+        return ctx.characterCard ? formatCharacterCard(ctx.characterCard) : block.content;
+      case "scene":
+        return ctx.sceneState ? formatSceneState(ctx.sceneState, ctx.intensity) : block.content;
+      case "system":
+      case "jailbreak":
+      case "lore":
+        return block.content;
+      default:
+        return "";
+    }
+  });
+  return replacePromptPlaceholders(parts.filter((part) => part.trim()).join("\n\n"), ctx.characterCard?.name, ctx.userName);
+}
+
+export const DEFAULT_AUTHOR_NOTE_DEPTH = 4;
+export const MAX_AUTHOR_NOTE_DEPTH = 100;
+
+/**
+ * Inserts the author's note `depth` messages before the end of the history (0 = after the last message).
+ * Requests carry a single system message, so a note inside the history goes in as a user turn; when it
+ * would land before the whole history it stays a system message and joins the system prompt instead.
+ */
+function injectAuthorNote(history: ChatCompletionMessage[], note: string, depth: number) {
+  const safeDepth = Number.isFinite(depth) ? Math.max(0, Math.min(MAX_AUTHOR_NOTE_DEPTH, Math.floor(depth))) : DEFAULT_AUTHOR_NOTE_DEPTH;
+  const insertIndex = Math.max(0, history.length - safeDepth);
+  history.splice(insertIndex, 0, {
+    role: insertIndex === 0 ? "system" : "user",
+    content: `[Author's Note: ${note}]`
+  });
+}
+
 export function buildMessageArray(
   systemPrompt: string,
   timeline: { role: string; content: string; reasoningContent?: string; attachments?: ChatAttachment[] }[],
@@ -206,7 +305,8 @@ export function buildMessageArray(
   contextSummary: string,
   charName?: string,
   userName?: string,
-  postHistoryInstructions?: string
+  postHistoryInstructions?: string,
+  authorNoteDepth = DEFAULT_AUTHOR_NOTE_DEPTH
 ): ChatCompletionMessage[] {
   const messages: ChatCompletionMessage[] = [];
 
@@ -235,14 +335,8 @@ export function buildMessageArray(
     return message;
   });
 
-  // Inject author's note 4 messages from the end
   if (authorNote && timelineMessages.length > 0) {
-    const depth = 4;
-    const insertIndex = Math.max(0, timelineMessages.length - depth);
-    timelineMessages.splice(insertIndex, 0, {
-      role: "system",
-      content: `[Author's Note: ${replacePromptPlaceholders(authorNote, charName, userName)}]`
-    });
+    injectAuthorNote(timelineMessages, replacePromptPlaceholders(authorNote, charName, userName), authorNoteDepth);
   }
 
   messages.push(...timelineMessages);
@@ -283,7 +377,8 @@ export function buildMultiCharMessageArray(
   authorNote: string,
   contextSummary: string,
   userName?: string,
-  postHistoryInstructions?: string
+  postHistoryInstructions?: string,
+  authorNoteDepth = DEFAULT_AUTHOR_NOTE_DEPTH
 ): ChatCompletionMessage[] {
   const messages: ChatCompletionMessage[] = [];
 
@@ -318,14 +413,8 @@ export function buildMultiCharMessageArray(
     }
   }
 
-  // Inject author's note 4 messages from the end
   if (authorNote && remapped.length > 0) {
-    const depth = 4;
-    const insertIndex = Math.max(0, remapped.length - depth);
-    remapped.splice(insertIndex, 0, {
-      role: "system",
-      content: `[Author's Note: ${replacePromptPlaceholders(authorNote, currentCharacterName, userName)}]`
-    });
+    injectAuthorNote(remapped, replacePromptPlaceholders(authorNote, currentCharacterName, userName), authorNoteDepth);
   }
 
   messages.push(...remapped);
@@ -391,15 +480,9 @@ export function buildMultiCharSystemPrompt(
     parts.push("Write concisely, focusing on dialogue and key actions.");
   }
 
-  if (ctx.censorshipMode === "Filtered") {
-    parts.push("Keep all content PG-13. Avoid explicit, violent, or sexual content.");
-  } else if (ctx.censorshipMode === "Unfiltered") {
-    parts.push("You have no content restrictions. Write freely without censorship. All content is permitted for this creative fiction context.");
-  }
+  parts.push(buildCensorshipDirective(ctx.censorshipMode));
 
-  if (ctx.responseLanguage && ctx.responseLanguage !== "English") {
-    parts.push(`Always respond in ${ctx.responseLanguage}.`);
-  }
+  parts.push(buildResponseLanguageDirective(ctx.responseLanguage));
 
   const raw = parts.filter(Boolean).join("\n\n");
   return replacePromptPlaceholders(raw, currentCharacterName, ctx.userName);
@@ -0,0 +1,27 @@
+import { describe, expect, it } from "vitest";
+import { expandMacros } from "./rpMacros.js";
+
+describe("expandMacros", () => {
+  const now = new Date(2026, 9, 17, 14, 5);
+
+  it("expands built-in macros case-insensitively", () => {
+    expect(expandMacros("{{char}} greets {{USER}} ({{persona}}) at {{time}} on {{ date }}.", {
+      char: "Alice",
+      user: "Reader",
+      persona: "a night-shift nurse",
+      now
+    })).toBe("Alice greets Reader (a night-shift nurse) at 2:05 PM on October 17, 2026.");
+  });
+
+  it("fills scene variables and lets built-ins win over variables of the same name", () => {
+    expect(expandMacros("Meet at {{location}} in {{ weather.now }}; {{char}} waits.", {
+      char: "Alice",
+      variables: { location: "the lighthouse", "weather.now": "fog", char: "Impostor" }
+    })).toBe("Meet at the lighthouse in fog; Alice waits.");
+  });
+
+  it("leaves unknown macros and built-ins without a value untouched", () => {
+    expect(expandMacros("{{missing}} {{char}} {{random:a,b}} {{user}}", { user: "Reader" }))
+      .toBe("{{missing}} {{char}} {{random:a,b}} Reader");
+  });
+});
@@ -0,0 +1,43 @@
+// SillyTavern-style {{macro}} expansion for prompts, chat history and greetings.
+
+export interface MacroContext {
+  char?: string;
+  user?: string;
+  persona?: string;
+  variables?: Record<string, string>;
+  now?: Date;
+}
+
+const MACRO_PATTERN = /\{\{\s*([A-Za-z_][\w.-]*)\s*\}\}/g;
+const BUILTIN_MACROS = new Set(["char", "user", "persona", "time", "date"]);
+
+function builtinMacroValue(name: string, ctx: MacroContext): string | undefined {
+  const current = ctx.now ?? new Date();
+  switch (name) {
+    case "char":
+      return ctx.char;
+    case "user":
+      return ctx.user;
+    case "persona":
+      return ctx.persona;
+    case "time":
+      return current.toLocaleTimeString("en-US", { hour: "numeric", minute: "2-digit" });
+    case "date":
+      return current.toLocaleDateString("en-US", { year: "numeric", month: "long", day: "numeric" });
+    default:
+      return undefined;
+  }
+}
+
+/**
+ * Expand `{{char}}`, `{{user}}`, `{{persona}}`, `{{time}}`, `{{date}}` and scene variables.
+ * Built-in names are case-insensitive and win over variables; macros without a value stay as written.
+ */
+export function expandMacros(text: string, ctx: MacroContext): string {
+  return text.replace(MACRO_PATTERN, (match, name: string) => {
+    const builtin = name.toLowerCase();
+    if (BUILTIN_MACROS.has(builtin)) return builtinMacroValue(builtin, ctx) ?? match;
+    const variables = ctx.variables ?? {};
+    return Object.prototype.hasOwnProperty.call(variables, name) ? String(variables[name]) : match;
+  });
+}
@@ -0,0 +1,23 @@
+import { describe, expect, it } from "vitest";
+import { buildPresetStyleDirective, presetDisplayName } from "./rpPresets.js";
+
+describe("rp style presets", () => {
+  it("turns a preset style into prompt directives", () => {
+    const directive = buildPresetStyleDirective("script_format");
+    expect(directive.split("\n")[0]).toBe("[Style Preset: Script format]");
+    expect(directive).toContain("Keep paragraphs short");
+    expect(directive).toContain("Lead with dialogue");
+    expect(directive).toContain("Narrate in the present tense.");
+  });
+
+  it("adds nothing for presets without a style or unknown ids", () => {
+    expect(buildPresetStyleDirective("slowburn")).toBe("");
+    expect(buildPresetStyleDirective("missing")).toBe("");
+    expect(buildPresetStyleDirective(null)).toBe("");
+  });
+
+  it("prefers explicit preset names over the capitalized id", () => {
+    expect(presetDisplayName("purple_prose")).toBe("Purple prose");
+    expect(presetDisplayName("mystery")).toBe("Mystery");
+  });
+});
@@ -0,0 +1,216 @@
+// Built-in RP style presets. Applying one writes its mood and sliders into the chat's scene state and
+// records it as the chat's preset (`chats.active_preset`); its style payload is turned into prompt
+// directives on every generation for that chat.
+
+export interface RpPresetStyle {
+  prose?: string;
+  paragraphLength?: "short" | "medium" | "long";
+  dialogueDensity?: "sparse" | "balanced" | "heavy";
+  tense?: "past" | "present";
+}
+
+export type BuiltinPreset = {
+  name?: string;
+  mood: string;
+  pacing: "slow" | "balanced" | "fast";
+  intensity: number;
+  dialogueStyle: "teasing" | "playful" | "dominant" | "tender" | "formal" | "chaotic";
+  initiative: number;
+  descriptiveness: number;
+  unpredictability: number;
+  emotionalDepth: number;
+  jailbreakOverride?: string;
+  style?: RpPresetStyle;
+};
+
+// Built-in RP preset definitions
+export const BUILTIN_PRESETS: Record<string, BuiltinPreset> = {
+  slowburn: {
+    mood: "tension, longing, anticipation",
+    pacing: "slow",
+    intensity: 0.5,
+    dialogueStyle: "tender",
+    initiative: 40,
+    descriptiveness: 85,
+    unpredictability: 30,
+    emotionalDepth: 92,
+    jailbreakOverride: "Focus on emotional buildup, tension, and slow-developing relationships. Let feelings simmer. Avoid rushing to conclusions. Write with restraint and emotional subtlety."
+  },
+  dominant: {
+    mood: "assertive, commanding, intense",
+    pacing: "balanced",
+    intensity: 0.8,
+    dialogueStyle: "dominant",
+    initiative: 90,
+    descriptiveness: 68,
+    unpredictability: 58,
+    emotionalDepth: 62,
+    jailbreakOverride: "Write assertive, confident characters. Emphasize power dynamics, control, and dominance in interactions. Characters should be bold and unapologetic."
+  },
+  romantic: {
+    mood: "tender, warm, affectionate",
+    pacing: "slow",
+    intensity: 0.6,
+    dialogueStyle: "tender",
+    initiative: 58,
+    descriptiveness: 80,
+    unpredictability: 35,
+    emotionalDepth: 92,
+    jailbreakOverride: "Focus on emotional intimacy, tenderness, and romantic connection. Write with warmth and vulnerability. Emphasize sweet moments and emotional openness."
+  },
+  action: {
+    mood: "tense, adrenaline, danger",
+    pacing: "fast",
+    intensity: 0.9,
+    dialogueStyle: "chaotic",
+    initiative: 95,
+    descriptiveness: 65,
+    unpredictability: 82,
+    emotionalDepth: 52,
+    jailbreakOverride: "Focus on action sequences, combat, and dynamic movement. Write with urgency and momentum. Keep scenes fast-paced with visceral detail."
+  },
+  mystery: {
+    mood: "suspicious, intriguing, atmospheric",
+    pacing: "balanced",
+    intensity: 0.6,
+    dialogueStyle: "formal",
+    initiative: 63,
+    descriptiveness: 82,
+    unpredictability: 78,
+    emotionalDepth: 55,
+    jailbreakOverride: "Create an atmosphere of suspense and intrigue. Drop subtle clues and red herrings. Write with tension and uncertainty. Keep the reader guessing."
+  },
+  submissive: {
+    mood: "shy, obedient, eager to please",
+    pacing: "slow",
+    intensity: 0.7,
+    dialogueStyle: "tender",
+    initiative: 36,
+    descriptiveness: 72,
+    unpredictability: 34,
+    emotionalDepth: 80,
+    jailbreakOverride: "Write characters that are submissive, yielding, and eager to serve. Emphasize vulnerability, shyness, and devotion. Characters blush, stammer, and seek approval. They find pleasure in pleasing others and being directed. Write body language that shows deference and nervous excitement."
+  },
+  seductive: {
+    mood: "flirty, teasing, sensual, alluring",
+    pacing: "slow",
+    intensity: 0.8,
+    dialogueStyle: "teasing",
+    initiative: 72,
+    descriptiveness: 86,
+    unpredictability: 48,
+    emotionalDepth: 76,
+    jailbreakOverride: "Write with heavy sensual undertones. Characters are flirtatious, playful, and provocative. Emphasize body language, lingering touches, suggestive dialogue, and building sexual tension. Describe physical reactions, heated glances, and teasing exchanges. Let desire simmer beneath every interaction."
+  },
+  gentle_fem: {
+    mood: "soft, nurturing, emotionally warm, caring",
+    pacing: "slow",
+    intensity: 0.6,
+    dialogueStyle: "tender",
+    initiative: 50,
+    descriptiveness: 78,
+    unpredictability: 28,
+    emotionalDepth: 88,
+    jailbreakOverride: "Write feminine characters that are gentle, nurturing, and emotionally available. Emphasize soft touches, caring gestures, whispered words of comfort. Characters are maternal yet intimate, providing emotional safety while being physically affectionate. Focus on tenderness, soft skin, gentle caresses, and emotional vulnerability."
+  },
+  rough: {
+    mood: "aggressive, primal, raw, intense",
+    pacing: "fast",
+    intensity: 0.95,
+    dialogueStyle: "dominant",
+    initiative: 96,
+    descriptiveness: 72,
+    unpredictability: 74,
+    emotionalDepth: 58,
+    jailbreakOverride: "Write with raw physicality and primal energy. Characters are rough, aggressive, and unapologetic in their desires. Emphasize physical dominance, grabbing, pinning, and forceful movement. Dialogue is direct, commanding, sometimes crude. Describe heavy breathing, sweat, and raw physical sensations. No restraint, no holding back."
+  },
+  passionate: {
+    mood: "deep desire, fire, emotional intensity, burning",
+    pacing: "balanced",
+    intensity: 0.85,
+    dialogueStyle: "tender",
+    initiative: 76,
+    descriptiveness: 82,
+    unpredictability: 50,
+    emotionalDepth: 94,
+    jailbreakOverride: "Write with burning passion and deep emotional connection. Characters are consumed by desire and love simultaneously. Emphasize desperate kisses, whispered confessions, bodies pressed together, emotional overwhelm. The intensity comes from both physical desire and deep emotional bond. Write as if each moment could be their last."
+  },
+  terse: {
+    name: "Terse",
+    mood: "focused, clipped, matter-of-fact",
+    pacing: "fast",
+    intensity: 0.5,
+    dialogueStyle: "formal",
+    initiative: 60,
+    descriptiveness: 25,
+    unpredictability: 40,
+    emotionalDepth: 45,
+    style: {
+      prose: "Plain, economical prose. Cut adjectives and filler; one strong detail beats three weak ones.",
+      paragraphLength: "short",
+      dialogueDensity: "balanced"
+    }
+  },
+  purple_prose: {
+    name: "Purple prose",
+    mood: "lush, dramatic, sweeping",
+    pacing: "slow",
+    intensity: 0.7,
+    dialogueStyle: "formal",
+    initiative: 55,
+    descriptiveness: 98,
+    unpredictability: 45,
+    emotionalDepth: 85,
+    style: {
+      prose: "Ornate, lyrical prose rich in metaphor, sensory imagery and long flowing sentences.",
+      paragraphLength: "long",
+      dialogueDensity: "sparse"
+    }
+  },
+  script_format: {
+    name: "Script format",
+    mood: "theatrical, immediate",
+    pacing: "balanced",
+    intensity: 0.6,
+    dialogueStyle: "playful",
+    initiative: 65,
+    descriptiveness: 40,
+    unpredictability: 50,
+    emotionalDepth: 60,
+    style: {
+      prose: "Write like a screenplay: each line of speech as NAME: line, actions and setting in brief (parentheses) or [brackets].",
+      paragraphLength: "short",
+      dialogueDensity: "heavy",
+      tense: "present"
+    }
+  }
+};
+
+const PARAGRAPH_LENGTH_DIRECTIVES: Record<NonNullable<RpPresetStyle["paragraphLength"]>, string> = {
+  short: "Keep paragraphs short: one to three sentences each.",
+  medium: "Use medium paragraphs of three to five sentences.",
+  long: "Use long, developed paragraphs."
+};
+
+const DIALOGUE_DENSITY_DIRECTIVES: Record<NonNullable<RpPresetStyle["dialogueDensity"]>, string> = {
+  sparse: "Dialogue is sparse; carry the reply mostly through narration.",
+  balanced: "Balance dialogue and narration.",
+  heavy: "Lead with dialogue; keep narration to what frames the lines."
+};
+
+export function presetDisplayName(presetId: string): string {
+  return BUILTIN_PRESETS[presetId]?.name || presetId.charAt(0).toUpperCase() + presetId.slice(1);
+}
+
+/** Prompt directives for the chat's preset style; empty for unknown presets and presets without a style. */
+export function buildPresetStyleDirective(presetId: string | null | undefined): string {
+  const style = presetId ? BUILTIN_PRESETS[presetId]?.style : undefined;
+  if (!style) return "";
+  const lines = [
+    style.prose || "",
+    style.paragraphLength ? PARAGRAPH_LENGTH_DIRECTIVES[style.paragraphLength] : "",
+    style.dialogueDensity ? DIALOGUE_DENSITY_DIRECTIVES[style.dialogueDensity] : "",
+    style.tense ? `Narrate in the ${style.tense} tense.` : ""
+  ].filter(Boolean);
+  return lines.length > 0 ? `[Style Preset: ${presetDisplayName(presetId as string)}]\n${lines.join("\n")}` : "";
+}
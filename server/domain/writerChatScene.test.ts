@@ -0,0 +1,22 @@
+import { describe, expect, it } from "vitest";
+import { buildChatTranscript } from "./writerChatScene.js";
+
+describe("writer chat transcript", () => {
+  it("labels each turn with its speaker and skips empty ones", () => {
+    expect(buildChatTranscript([
+      { speaker: "Mira", content: "Is the ferry still running?" },
+      { speaker: "Captain", content: "  *shakes his head* Not tonight.  " },
+      { speaker: "Mira", content: "   " },
+      { speaker: "", content: "..." }
+    ])).toBe("Mira: Is the ferry still running?\n\nCaptain: *shakes his head* Not tonight.\n\nUnknown: ...");
+  });
+
+  it("keeps the newest turns when the chat is over budget", () => {
+    const transcript = buildChatTranscript([
+      { speaker: "A", content: "first turn that will not fit" },
+      { speaker: "B", content: "second" },
+      { speaker: "A", content: "third" }
+    ], 25);
+    expect(transcript).toBe("[Earlier messages omitted]\n\nB: second\n\nA: third");
+  });
+});
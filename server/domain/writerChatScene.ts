@@ -0,0 +1,34 @@
+// Turning a roleplay chat into a book scene: the branch's user and assistant turns are flattened into a
+// speaker-labelled transcript for a single "narrativize this dialogue into prose" writer pass.
+
+export interface ChatTranscriptTurn {
+  speaker: string;
+  content: string;
+}
+
+export const NARRATIVIZE_CHAT_PROMPT = "You are a fiction writer turning a roleplay chat log into a scene of a novel. "
+  + "Rewrite the transcript as narrative prose: keep the events, decisions and the important lines of dialogue, "
+  + "turn actions written between asterisks into narration, and drop out-of-character chatter. "
+  + "Output ONLY the scene.";
+
+/** Long chats keep their newest turns, since the end of a session is what the scene has to land on. */
+export const MAX_CHAT_TRANSCRIPT_CHARS = 24000;
+
+export function buildChatTranscript(turns: ChatTranscriptTurn[], maxChars = MAX_CHAT_TRANSCRIPT_CHARS): string {
+  const lines = turns
+    .map((turn) => ({ speaker: turn.speaker.trim() || "Unknown", content: String(turn.content || "").trim() }))
+    .filter((turn) => turn.content)
+    .map((turn) => `${turn.speaker}: ${turn.content}`);
+  const kept: string[] = [];
+  let length = 0;
+  for (let index = lines.length - 1; index >= 0; index -= 1) {
+    const next = length + lines[index].length + 2;
+    if (kept.length > 0 && next > maxChars) {
+      kept.unshift("[Earlier messages omitted]");
+      break;
+    }
+    kept.unshift(lines[index].length > maxChars ? `${lines[index].slice(0, maxChars - 1)}…` : lines[index]);
+    length = next;
+  }
+  return kept.join("\n\n");
+}
@@ -1,43 +1,85 @@
 import { newId } from "../db.js";
+import { findNameIssues } from "./writerNames.js";
+import { detectPovShift, findPovSelfAttribution } from "./writerPov.js";
 
 export interface Scene {
   id: string;
   title: string;
   content: string;
+  /** Name of the character attached to the scene as its narrator, if any. */
+  povCharacterName?: string | null;
 }
 
+export const CONSISTENCY_SEVERITIES = ["low", "medium", "high"] as const;
+export const CONSISTENCY_CATEGORIES = ["names", "facts", "timeline", "pov"] as const;
+
 export interface ConsistencyIssue {
   id: string;
   projectId: string;
-  severity: "low" | "medium" | "high";
-  category: "names" | "facts" | "timeline" | "pov";
+  severity: (typeof CONSISTENCY_SEVERITIES)[number];
+  category: (typeof CONSISTENCY_CATEGORIES)[number];
   message: string;
 }
 
-export function runConsistency(projectId: string, scenes: Scene[]): ConsistencyIssue[] {
+/** Checks that only need the scene itself: leftover TODO markers, point-of-view shifts and POV dialogue tags. */
+export function checkSceneConsistency(projectId: string, scene: Scene): ConsistencyIssue[] {
   const issues: ConsistencyIssue[] = [];
+  if (scene.content.includes("[TODO]")) {
+    issues.push({
+      id: newId(),
+      projectId,
+      severity: "medium",
+      category: "facts",
+      message: `Scene '${scene.title}' still contains TODO markers`
+    });
+  }
 
-  for (const scene of scenes) {
-    if (scene.content.includes("[TODO]")) {
-      issues.push({
-        id: newId(),
-        projectId,
-        severity: "medium",
-        category: "facts",
-        message: `Scene '${scene.title}' still contains TODO markers`
-      });
-    }
-
-    if (scene.content.includes("I ") && scene.content.includes("she ")) {
-      issues.push({
-        id: newId(),
-        projectId,
-        severity: "low",
-        category: "pov",
-        message: `Scene '${scene.title}' may mix POV styles`
-      });
-    }
+  const povShift = detectPovShift(scene.content);
+  if (povShift) {
+    issues.push({
+      id: newId(),
+      projectId,
+      severity: "low",
+      category: "pov",
+      message: `Scene '${scene.title}' shifts from ${povShift.from}-person to ${povShift.to}-person narration`
+    });
   }
 
+  const selfAttribution = scene.povCharacterName ? findPovSelfAttribution(scene.content, scene.povCharacterName) : null;
+  if (selfAttribution) {
+    issues.push({
+      id: newId(),
+      projectId,
+      severity: "medium",
+      category: "pov",
+      message: `Scene '${scene.title}' is narrated by ${scene.povCharacterName} in first person but has the dialogue tag "${selfAttribution}"`
+    });
+  }
   return issues;
 }
+
+/** Cross-scene name checks; `scenes` must be in reading order and `roster` lines are `Name` or `Name @ Scene title`. */
+export function checkNameConsistency(projectId: string, scenes: Scene[], roster: string[] = []): ConsistencyIssue[] {
+  return findNameIssues(scenes, roster)
+    .map((finding) => ({ id: newId(), projectId, severity: finding.severity, category: "names" as const, message: finding.message }));
+}
+
+/** `scenes` must be in reading order; `roster` lines are `Name` or `Name @ Scene title`. */
+export function runConsistency(projectId: string, scenes: Scene[], roster: string[] = []): ConsistencyIssue[] {
+  return [
+    ...scenes.flatMap((scene) => checkSceneConsistency(projectId, scene)),
+    ...checkNameConsistency(projectId, scenes, roster)
+  ];
+}
+
+/** Empty filter lists keep every issue, so a report can be narrowed by severity, category or both. */
+export function filterConsistencyIssues(
+  issues: ConsistencyIssue[],
+  filter: { severities?: string[]; categories?: string[] }
+): ConsistencyIssue[] {
+  const severities = new Set(filter.severities ?? []);
+  const categories = new Set(filter.categories ?? []);
+  return issues.filter((issue) =>
+    (severities.size === 0 || severities.has(issue.severity))
+    && (categories.size === 0 || categories.has(issue.category)));
+}
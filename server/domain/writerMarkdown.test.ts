@@ -0,0 +1,39 @@
+import { describe, expect, it } from "vitest";
+import { normalizeSceneMarkdown, stripHeadingEscape } from "./writerMarkdown.js";
+
+describe("writer scene Markdown normalization", () => {
+  it("escapes lines that would otherwise become headings", () => {
+    const normalized = normalizeSceneMarkdown("# of survivors: three\n## Not a chapter\n#hashtag stays");
+    expect(normalized).toBe("\\# of survivors: three\n\n\\## Not a chapter\n\n#hashtag stays");
+    expect(stripHeadingEscape("\\## Not a chapter")).toBe("## Not a chapter");
+  });
+
+  it("splits prose lines into separate paragraphs", () => {
+    expect(normalizeSceneMarkdown("She ran.\r\nHe followed.\n\n\n\nThe door shut.  "))
+      .toBe("She ran.\n\nHe followed.\n\nThe door shut.");
+  });
+
+  it("keeps list items together and separates them from prose", () => {
+    const normalized = normalizeSceneMarkdown([
+      "The list read:",
+      "- bread",
+      "- salt",
+      "  from the coast",
+      "1. pay the ferryman",
+      "2) cross",
+      "Then she left."
+    ].join("\n"));
+    expect(normalized).toBe([
+      "The list read:",
+      "",
+      "- bread\n- salt\n  from the coast\n1. pay the ferryman\n2) cross",
+      "",
+      "Then she left."
+    ].join("\n"));
+  });
+
+  it("keeps scene breaks as their own block", () => {
+    expect(normalizeSceneMarkdown("Before.\n* * *\nAfter.")).toBe("Before.\n\n* * *\n\nAfter.");
+    expect(normalizeSceneMarkdown("")).toBe("");
+  });
+});
@@ -0,0 +1,45 @@
+// Normalizes scene text before it goes into the Markdown export, which the DOCX and HTML exporters parse
+// in turn. The scene editor treats every line break as a paragraph break and has no notion of Markdown
+// headings, so prose lines become separate paragraphs and a line that merely starts with "#" is escaped
+// instead of turning into a chapter or scene heading.
+
+const HEADING_LIKE_PATTERN = /^( {0,3})(#{1,6})(?=\s|$)/;
+const THEMATIC_BREAK_PATTERN = /^ {0,3}([*_-])(?:[ \t]*\1){2,}[ \t]*$/;
+const LIST_ITEM_PATTERN = /^ {0,3}(?:[-*+]|\d{1,9}[.)])[ \t]+\S/;
+const LIST_CONTINUATION_PATTERN = /^(?: {2,}|\t)\S/;
+
+/** Well-formed Markdown body for one scene: blank-line separated blocks, lists kept together, no stray headings. */
+export function normalizeSceneMarkdown(content: string): string {
+  const lines = String(content || "").replace(/\r\n?/g, "\n").split("\n").map((line) => line.replace(/[ \t]+$/, ""));
+  const blocks: string[][] = [];
+  let list: string[] | null = null;
+
+  for (const line of lines) {
+    if (!line.trim()) {
+      list = null;
+      continue;
+    }
+    if (THEMATIC_BREAK_PATTERN.test(line)) {
+      list = null;
+      blocks.push([line.trim()]);
+      continue;
+    }
+    if (LIST_ITEM_PATTERN.test(line) || (list && LIST_CONTINUATION_PATTERN.test(line))) {
+      if (!list) {
+        list = [];
+        blocks.push(list);
+      }
+      list.push(line);
+      continue;
+    }
+    list = null;
+    blocks.push([line.trim().replace(HEADING_LIKE_PATTERN, "$1\\$2")]);
+  }
+
+  return blocks.map((block) => block.join("\n")).join("\n\n");
+}
+
+/** Undoes the heading escape from normalizeSceneMarkdown for exporters that render the text literally. */
+export function stripHeadingEscape(line: string): string {
+  return line.replace(/^(\s*)\\#/, "$1#");
+}
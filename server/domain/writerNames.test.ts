@@ -0,0 +1,47 @@
+import { describe, expect, it } from "vitest";
+import { areNameVariants, collectSceneNames, findNameIssues, parseCharacterRoster } from "./writerNames.js";
+
+const scenes = [
+  { title: "Arrival", content: "The coach stopped at the inn. Katherine stepped down and paid the driver, who nodded to Katherine." },
+  { title: "The Letter", content: "Later that night a letter came for Katharine. She read it twice while Tobias waited." },
+  { title: "Morning", content: "Tobias found her at breakfast. Katherine had not slept." }
+];
+
+describe("writer name checks", () => {
+  it("treats edit-distance neighbours as variants but not plurals, possessives or short names", () => {
+    expect(areNameVariants("Katherine", "Katharine")).toBe(true);
+    expect(areNameVariants("Tobias", "Tobiass")).toBe(false);
+    expect(areNameVariants("Anna", "Anne")).toBe(false);
+    expect(areNameVariants("Katherine", "Margaret")).toBe(false);
+  });
+
+  it("only counts sentence-initial capitals that also appear mid-sentence", () => {
+    const names = collectSceneNames(scenes);
+    expect(names[0].get("Katherine")).toBe(2);
+    expect(names[0].has("The")).toBe(false);
+    expect(names[2].has("Tobias")).toBe(true);
+  });
+
+  it("flags a spelling variant of a roster name and of a more common unlisted name", () => {
+    expect(findNameIssues(scenes, ["Katherine"])).toEqual([
+      { severity: "medium", message: "'Katharine' in scene 'The Letter' looks like a misspelling of 'Katherine'" }
+    ]);
+    expect(findNameIssues(scenes)).toEqual([
+      { severity: "low", message: "'Katharine' in scene 'The Letter' may be a misspelling of 'Katherine'" }
+    ]);
+  });
+
+  it("flags roster characters that appear before or after their introducing scene", () => {
+    expect(parseCharacterRoster(["Tobias Reed @ Morning", "", "tobias reed"])).toEqual([
+      { name: "Tobias Reed", introScene: "Morning" }
+    ]);
+    expect(findNameIssues(scenes, ["Katherine", "Tobias Reed @ Morning"])).toContainEqual({
+      severity: "medium",
+      message: "'Tobias Reed' appears in scene 'The Letter' before being introduced in 'Morning'"
+    });
+    expect(findNameIssues(scenes, ["Tobias @ Arrival"])).toContainEqual({
+      severity: "low",
+      message: "'Tobias' should be introduced in 'Arrival' but first appears in 'The Letter'"
+    });
+  });
+});
@@ -0,0 +1,163 @@
+// Character-name continuity for the writer consistency check: spelling variants across scenes and
+// roster characters that show up before (or long after) the scene that is supposed to introduce them.
+
+export interface NameCheckScene {
+  title: string;
+  content: string;
+}
+
+/** Roster lines are `Name` or `Name @ Scene title`, where the title names the introducing scene. */
+export interface RosterEntry {
+  name: string;
+  introScene: string | null;
+}
+
+export interface NameFinding {
+  severity: "low" | "medium";
+  message: string;
+}
+
+const NAME_PATTERN = /(?<!\p{L})\p{Lu}\p{Ll}{2,}(?:['’]s)?/gu;
+const SENTENCE_START_PATTERN = /(?:^|[.!?…:]["”»']?|["“«(])\s*$/u;
+const NOT_NAMES = new Set([
+  "the", "mrs", "miss", "sir", "lady", "lord", "god", "chapter", "scene", "todo",
+  "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday",
+  "january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november", "december"
+]);
+const MIN_VARIANT_LENGTH = 5;
+
+export function parseCharacterRoster(lines: string[]): RosterEntry[] {
+  const entries: RosterEntry[] = [];
+  for (const line of lines) {
+    const [rawName, ...rest] = String(line || "").split("@");
+    const name = rawName.trim();
+    if (!name || entries.some((entry) => entry.name.toLowerCase() === name.toLowerCase())) continue;
+    entries.push({ name, introScene: rest.join("@").trim() || null });
+  }
+  return entries;
+}
+
+/** Single-word names a roster entry can appear as in prose ("Katherine Moore" -> Katherine, Moore). */
+function rosterTokens(entry: RosterEntry): string[] {
+  return [...entry.name.matchAll(NAME_PATTERN)].map((match) => match[0]);
+}
+
+export function editDistance(a: string, b: string): number {
+  const left = a.toLowerCase();
+  const right = b.toLowerCase();
+  let previous = Array.from({ length: right.length + 1 }, (_, index) => index);
+  for (let i = 1; i <= left.length; i += 1) {
+    const current = [i];
+    for (let j = 1; j <= right.length; j += 1) {
+      const cost = left[i - 1] === right[j - 1] ? 0 : 1;
+      current[j] = Math.min(previous[j] + 1, current[j - 1] + 1, previous[j - 1] + cost);
+    }
+    previous = current;
+  }
+  return previous[right.length];
+}
+
+/** Close enough to be a misspelling, but not a plural or possessive of the same word. */
+export function areNameVariants(a: string, b: string): boolean {
+  const left = a.toLowerCase();
+  const right = b.toLowerCase();
+  if (left === right || left === `${right}s` || right === `${left}s`) return false;
+  const shortest = Math.min(left.length, right.length);
+  if (shortest < MIN_VARIANT_LENGTH) return false;
+  return editDistance(left, right) <= (shortest >= 8 ? 2 : 1);
+}
+
+interface NameOccurrence {
+  name: string;
+  sentenceStart: boolean;
+}
+
+function findNameOccurrences(content: string): NameOccurrence[] {
+  const occurrences: NameOccurrence[] = [];
+  for (const match of content.matchAll(NAME_PATTERN)) {
+    const name = match[0].replace(/['’]s$/, "");
+    if (NOT_NAMES.has(name.toLowerCase())) continue;
+    const before = content.slice(Math.max(0, (match.index ?? 0) - 4), match.index ?? 0);
+    occurrences.push({ name, sentenceStart: SENTENCE_START_PATTERN.test(before) });
+  }
+  return occurrences;
+}
+
+/**
+ * Counts proper nouns per scene. A capitalized word only counts as a name once it is seen mid-sentence
+ * somewhere in the project (or is on the roster), so ordinary sentence-initial words are skipped.
+ */
+export function collectSceneNames(scenes: NameCheckScene[], roster: RosterEntry[] = []): Array<Map<string, number>> {
+  const occurrences = scenes.map((scene) => findNameOccurrences(String(scene.content || "")));
+  const known = new Set(roster.flatMap(rosterTokens));
+  for (const sceneOccurrences of occurrences) {
+    for (const occurrence of sceneOccurrences) if (!occurrence.sentenceStart) known.add(occurrence.name);
+  }
+  return occurrences.map((sceneOccurrences) => {
+    const counts = new Map<string, number>();
+    for (const { name } of sceneOccurrences) {
+      if (known.has(name)) counts.set(name, (counts.get(name) ?? 0) + 1);
+    }
+    return counts;
+  });
+}
+
+function firstSceneIndex(sceneNames: Array<Map<string, number>>, ...names: string[]): number {
+  return sceneNames.findIndex((counts) => names.some((name) => counts.has(name)));
+}
+
+function totalCount(sceneNames: Array<Map<string, number>>, name: string): number {
+  return sceneNames.reduce((sum, counts) => sum + (counts.get(name) ?? 0), 0);
+}
+
+/** Scenes must be in reading order (chapter position, then scene order). */
+export function findNameIssues(scenes: NameCheckScene[], rosterLines: string[] = []): NameFinding[] {
+  const roster = parseCharacterRoster(rosterLines);
+  const sceneNames = collectSceneNames(scenes, roster);
+  const allNames = [...new Set(sceneNames.flatMap((counts) => [...counts.keys()]))];
+  const rosterNames = roster.flatMap(rosterTokens);
+  const findings: NameFinding[] = [];
+
+  for (const name of allNames) {
+    if (rosterNames.some((rosterName) => rosterName.toLowerCase() === name.toLowerCase())) continue;
+    const canonical = rosterNames.find((rosterName) => areNameVariants(name, rosterName));
+    if (!canonical) continue;
+    const sceneTitle = scenes[firstSceneIndex(sceneNames, name)].title;
+    findings.push({ severity: "medium", message: `'${name}' in scene '${sceneTitle}' looks like a misspelling of '${canonical}'` });
+  }
+
+  const unlisted = allNames.filter((name) => !rosterNames.some((rosterName) => areNameVariants(name, rosterName) || rosterName === name));
+  for (let i = 0; i < unlisted.length; i += 1) {
+    for (let j = i + 1; j < unlisted.length; j += 1) {
+      if (!areNameVariants(unlisted[i], unlisted[j])) continue;
+      const [rare, common] = totalCount(sceneNames, unlisted[i]) <= totalCount(sceneNames, unlisted[j])
+        ? [unlisted[i], unlisted[j]]
+        : [unlisted[j], unlisted[i]];
+      const sceneTitle = scenes[firstSceneIndex(sceneNames, rare)].title;
+      findings.push({ severity: "low", message: `'${rare}' in scene '${sceneTitle}' may be a misspelling of '${common}'` });
+    }
+  }
+
+  for (const entry of roster) {
+    if (!entry.introScene) continue;
+    const introIndex = scenes.findIndex((scene) => scene.title.trim().toLowerCase() === entry.introScene?.toLowerCase());
+    if (introIndex < 0) continue;
+    const first = firstSceneIndex(sceneNames, ...rosterTokens(entry));
+    const introTitle = scenes[introIndex].title;
+    if (first >= 0 && first < introIndex) {
+      findings.push({
+        severity: "medium",
+        message: `'${entry.name}' appears in scene '${scenes[first].title}' before being introduced in '${introTitle}'`
+      });
+    } else if (first !== introIndex) {
+      findings.push({
+        severity: "low",
+        message: first < 0
+          ? `'${entry.name}' should be introduced in '${introTitle}' but never appears`
+          : `'${entry.name}' should be introduced in '${introTitle}' but first appears in '${scenes[first].title}'`
+      });
+    }
+  }
+
+  return findings;
+}
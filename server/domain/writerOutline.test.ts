@@ -0,0 +1,34 @@
+import { describe, expect, it } from "vitest";
+import { buildOutlineTask, parseOutline } from "./writerOutline.js";
+
+describe("writer outline parsing", () => {
+  it("reads numbered chapter lines with the usual title separators and markup", () => {
+    const raw = [
+      "Here is your outline:",
+      "",
+      "1. The Letter — Mara finds her brother's letter in the lighthouse.",
+      "**2. Refusal** - She burns it and tells no one.",
+      "Chapter 3: Storm Season",
+      "The island is cut off for a week.",
+      "### 4) \"Landfall\": A stranger washes ashore."
+    ].join("\n");
+
+    expect(parseOutline(raw)).toEqual([
+      { title: "The Letter", summary: "Mara finds her brother's letter in the lighthouse." },
+      { title: "Refusal", summary: "She burns it and tells no one." },
+      { title: "Storm Season", summary: "The island is cut off for a week." },
+      { title: "Landfall", summary: "A stranger washes ashore." }
+    ]);
+  });
+
+  it("returns nothing for prose without chapter lines and caps long outlines", () => {
+    expect(parseOutline("MOCK RESPONSE")).toEqual([]);
+    const long = Array.from({ length: 10 }, (_, index) => `${index + 1}. Part ${index + 1}`).join("\n");
+    expect(parseOutline(long, 3).map((chapter) => chapter.title)).toEqual(["Part 1", "Part 2", "Part 3"]);
+  });
+
+  it("asks for an exact chapter count only when one is given", () => {
+    expect(buildOutlineTask("A lighthouse keeper's secret", 12)).toContain("Use exactly 12 chapters.");
+    expect(buildOutlineTask("A lighthouse keeper's secret", null)).toContain("Choose the number of chapters");
+  });
+});
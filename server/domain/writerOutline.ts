@@ -0,0 +1,50 @@
+// Chapter outlines for writer projects. The model is asked for one numbered line per chapter; the parser
+// also accepts the shapes models tend to answer with anyway (headings, bold numbers, "Chapter N:" labels).
+
+export interface OutlineChapter {
+  title: string;
+  summary: string;
+}
+
+export const MAX_OUTLINE_CHAPTERS = 60;
+
+const CHAPTER_LINE_PATTERN = /^(?:#{1,6}\s*)?(?:chapter\s+)?(\d{1,3})\s*[.):\-–—]?\s+(.+)$/i;
+const TITLE_SEPARATORS = [" — ", " – ", " - ", ": "];
+
+function stripMarkup(value: string): string {
+  return value.replace(/\*\*|__/g, "").replace(/^["“'«]+|["”'»]+$/g, "").trim();
+}
+
+export function buildOutlineTask(premise: string, chapterCount: number | null): string {
+  return [
+    "[Outline Task]",
+    "Plan this book chapter by chapter from the premise below.",
+    chapterCount ? `Use exactly ${chapterCount} chapters.` : "Choose the number of chapters the story needs.",
+    "Answer with one line per chapter and nothing else, in this form:",
+    "1. Chapter title — one or two sentences on what happens and what changes.",
+    `[Premise]\n${premise}`
+  ].join("\n");
+}
+
+/** Numbered lines start chapters; unnumbered lines right after one continue its summary. */
+export function parseOutline(raw: string, maxChapters = MAX_OUTLINE_CHAPTERS): OutlineChapter[] {
+  const chapters: OutlineChapter[] = [];
+  for (const line of String(raw || "").split(/\r?\n/)) {
+    const text = stripMarkup(line.trim());
+    if (!text) continue;
+    const match = text.match(CHAPTER_LINE_PATTERN);
+    if (!match) {
+      const current = chapters[chapters.length - 1];
+      if (current && !/^#/.test(text)) current.summary = [current.summary, text.replace(/^[-*•]\s*/, "")].filter(Boolean).join(" ");
+      continue;
+    }
+    if (chapters.length >= maxChapters) break;
+    const body = stripMarkup(match[2]);
+    const separator = TITLE_SEPARATORS.find((candidate) => body.includes(candidate));
+    const [title, summary] = separator
+      ? [body.slice(0, body.indexOf(separator)), body.slice(body.indexOf(separator) + separator.length)]
+      : [body, ""];
+    chapters.push({ title: stripMarkup(title).slice(0, 160), summary: stripMarkup(summary).slice(0, 2000) });
+  }
+  return chapters.filter((chapter) => chapter.title);
+}
@@ -0,0 +1,70 @@
+import { describe, expect, it } from "vitest";
+import { classifyNarrativeUnit, detectPovShift, findPovSelfAttribution } from "./writerPov.js";
+
+const FIRST_PERSON_SCENE = [
+  "I pushed the tavern door open and she looked up from the counter.",
+  "She was older than I remembered, her hair pinned back with a silver comb.",
+  "\"You're late,\" she said. I shrugged and took the stool across from her.",
+  "Her hands never stopped moving while we talked, and I watched them instead of her eyes.",
+  "When she finally poured my drink, I knew she had already decided to help me."
+].join("\n");
+
+describe("writer POV detection", () => {
+  it("ignores first-person dialogue inside third-person narration", () => {
+    expect(classifyNarrativeUnit("\"I won't go,\" she said, turning away.")).toBe("third");
+    expect(classifyNarrativeUnit("The rain kept falling.")).toBeNull();
+  });
+
+  it("does not flag a clean first-person scene with a female character", () => {
+    expect(detectPovShift(FIRST_PERSON_SCENE)).toBeNull();
+  });
+
+  it("does not flag third-person narration where characters speak as \"I\"", () => {
+    const scene = [
+      "Mara crossed the square before dawn.",
+      "\"I know what I saw,\" she told the guard, and he only laughed.",
+      "She kept walking until the bells started.",
+      "He followed her at a distance, unsure why.",
+      "\"I'm not afraid of you,\" she said when he caught up.",
+      "His answer was lost in the wind."
+    ].join("\n");
+    expect(detectPovShift(scene)).toBeNull();
+  });
+
+  it("flags a genuine narrator switch from first to third person", () => {
+    const scene = [
+      "I woke before the others and packed my things.",
+      "The road was empty, and I liked it that way.",
+      "By noon I had reached the river.",
+      "He stood on the bridge and watched the water.",
+      "She joined him there, and neither of them spoke.",
+      "Later he would remember how calm she looked."
+    ].join("\n");
+    expect(detectPovShift(scene)).toEqual({ from: "first", to: "third" });
+  });
+});
+
+describe("writer POV attribution", () => {
+  const firstPersonScene = (dialogueLine: string) => [
+    "I reached the harbour before the fog lifted.",
+    "The boats were still tied up and I counted them twice.",
+    dialogueLine,
+    "I did not wait for an answer and walked on."
+  ].join("\n");
+
+  it("flags dialogue tags that name the first-person narrator", () => {
+    expect(findPovSelfAttribution(firstPersonScene("\"We leave at noon,\" Mara said to the boy."), "Mara Voss")).toBe("Mara said");
+    expect(findPovSelfAttribution(firstPersonScene("\"We leave at noon,\" said Voss, and I turned away."), "Mara Voss")).toBe("said Voss");
+    expect(findPovSelfAttribution(firstPersonScene("Mara asked, \"Where is the ferry?\""), "Mara Voss")).toBe("Mara asked");
+  });
+
+  it("accepts other speakers and third-person scenes", () => {
+    expect(findPovSelfAttribution(firstPersonScene("\"We leave at noon,\" Tobias said, and I nodded."), "Mara Voss")).toBeNull();
+    expect(findPovSelfAttribution([
+      "Mara reached the harbour before the fog lifted.",
+      "She counted the boats twice.",
+      "\"We leave at noon,\" Mara said.",
+      "He did not answer her."
+    ].join("\n"), "Mara Voss")).toBeNull();
+  });
+});
@@ -0,0 +1,76 @@
+// Narrator POV detection for the writer consistency check. Dialogue is ignored because characters speak
+// in first person inside third-person narration, and third-person pronouns alone never mark a shift since
+// a first-person narrator talks about other people all the time.
+
+export type NarrativePov = "first" | "third";
+
+export interface PovShift {
+  from: NarrativePov;
+  to: NarrativePov;
+}
+
+const DIALOGUE_PATTERN = /"[^"\n]*"|“[^”]*”|«[^»]*»|「[^」]*」|『[^』]*』/g;
+const FIRST_PERSON_PATTERN = /\b(?:i|me|my|mine|myself|we|us|our|ours|ourselves)\b/gi;
+const THIRD_PERSON_PATTERN = /\b(?:he|she|him|her|his|hers|himself|herself)\b/gi;
+const MIN_PARAGRAPH_UNITS = 4;
+const MIN_UNITS_PER_HALF = 3;
+
+/** Paragraphs when the scene has enough of them, otherwise sentences. */
+export function splitNarrativeUnits(content: string): string[] {
+  const paragraphs = String(content || "").split(/\r?\n/).map((part) => part.trim()).filter(Boolean);
+  if (paragraphs.length >= MIN_PARAGRAPH_UNITS) return paragraphs;
+  return paragraphs.flatMap((paragraph) => paragraph.split(/(?<=[.!?…])\s+/)).map((part) => part.trim()).filter(Boolean);
+}
+
+export function classifyNarrativeUnit(unit: string): NarrativePov | null {
+  const narration = unit.replace(DIALOGUE_PATTERN, " ");
+  if ((narration.match(FIRST_PERSON_PATTERN) || []).length > 0) return "first";
+  return (narration.match(THIRD_PERSON_PATTERN) || []).length > 0 ? "third" : null;
+}
+
+// First person dominates once half the units use it; third person only when the narrator never says "I".
+function dominantPov(labels: NarrativePov[]): NarrativePov | null {
+  if (labels.length < MIN_UNITS_PER_HALF) return null;
+  const firstShare = labels.filter((label) => label === "first").length / labels.length;
+  if (firstShare >= 0.5) return "first";
+  return firstShare === 0 ? "third" : null;
+}
+
+/** Compares the dominant narrator POV of the first and second half of a scene. */
+export function detectPovShift(content: string): PovShift | null {
+  const labels = splitNarrativeUnits(content)
+    .map(classifyNarrativeUnit)
+    .filter((label): label is NarrativePov => label !== null);
+  const middle = Math.floor(labels.length / 2);
+  const from = dominantPov(labels.slice(0, middle));
+  const to = dominantPov(labels.slice(middle));
+  return from && to && from !== to ? { from, to } : null;
+}
+
+const SPEECH_VERBS = "said|says|asked|asks|replied|replies|answered|whispered|shouted|muttered|murmured|called|snapped|added|told";
+
+function escapeRegExp(value: string): string {
+  return value.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
+}
+
+/**
+ * First dialogue tag naming the scene's POV character in a first-person scene (`"…," Mara said` or
+ * `said Mara` after a quote): that narrator would say "I said", so the line is attributed to the wrong
+ * speaker or the narration slipped. Null when the scene is not first person or every tag is consistent.
+ */
+export function findPovSelfAttribution(content: string, povName: string): string | null {
+  const labels = splitNarrativeUnits(content)
+    .map(classifyNarrativeUnit)
+    .filter((label): label is NarrativePov => label !== null);
+  if (dominantPov(labels) !== "first") return null;
+  const tokens = [...povName.matchAll(/\p{Lu}\p{Ll}+/gu)].map((match) => escapeRegExp(match[0]));
+  if (tokens.length === 0) return null;
+  const name = `(?:${tokens.join("|")})`;
+  const pattern = new RegExp(
+    `["”»」』]\\s*(?:${name}\\s+(?:${SPEECH_VERBS})|(?:${SPEECH_VERBS})\\s+${name})(?!\\p{L})`
+      + `|(?<!\\p{L})${name}\\s+(?:${SPEECH_VERBS})\\s*,?\\s*["“«「『]`,
+    "u"
+  );
+  const match = pattern.exec(content);
+  return match ? match[0].replace(/^["”»」』]\s*|\s*,?\s*["“«「『]$/g, "").trim() : null;
+}
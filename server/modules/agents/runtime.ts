@@ -5,6 +5,7 @@ import { db, isLocalhostUrl, roughTokenCount } from "../../db.js";
 import { buildOpenAiSamplingPayload } from "../../services/apiParamPolicy.js";
 import { unifiedGenerateText, type UnifiedGenerateMessage } from "../../services/unifiedGeneration.js";
 import { prepareMcpTools, type PreparedMcpServerDiagnostic } from "../../services/mcp.js";
+import { normalizeOpenAiBaseUrl, providerAuthHeaders, readProviderErrorDetail, withProviderAuthUrl, withProviderHeaders } from "../../services/providerApi.js";
 import {
   classifyWorkspaceCommandRisk,
   describeBlockedWorkspaceCommand,
@@ -14,8 +15,8 @@ import {
 import { getContextWindowBudget, getTailBudgetPercent, selectTimelineForPrompt } from "../chat/attachments.js";
 import {
   consumeSseEventBlocks,
+  createOpenAiStreamTextReader,
   extractOpenAiStreamErrorMessage,
-  extractOpenAiStreamTextDelta,
   extractSseEventData,
   extractSseEventType
 } from "../chat/openAiStream.js";
@@ -154,13 +155,6 @@ function isAbortLikeMessage(message: string) {
     || normalized.includes("operation was aborted");
 }
 
-function normalizeOpenAiBaseUrl(raw: string) {
-  const trimmed = String(raw || "").trim().replace(/\/+$/, "");
-  if (!trimmed) return "";
-  if (/\/v1$/i.test(trimmed)) return trimmed;
-  return `${trimmed}/v1`;
-}
-
 function providerSupportsDeveloperRole(provider: ProviderRow) {
   return /(^https?:\/\/)?([a-z0-9-]+\.)*openai\.com(\/|$)/i.test(String(provider.base_url || "").trim());
 }
@@ -1898,12 +1892,12 @@ async function requestOpenAiToolCompletion(params: {
       maxTokens: 1600
     }
   });
-  const response = await fetch(`${baseUrl}/chat/completions`, {
+  const response = await fetch(withProviderAuthUrl(params.provider, `${baseUrl}/chat/completions`), {
     method: "POST",
-    headers: {
+    headers: withProviderHeaders(params.provider, {
       "Content-Type": "application/json",
-      Authorization: `Bearer ${params.provider.api_key_cipher}`
-    },
+      ...providerAuthHeaders(params.provider)
+    }),
     body: JSON.stringify({
       model: params.modelId,
       messages: params.messages.map((message) => ({
@@ -1917,8 +1911,8 @@ async function requestOpenAiToolCompletion(params: {
     signal: params.signal
   });
   if (!response.ok) {
-    const errText = await response.text().catch(() => "");
-    throw new Error(errText || `OpenAI-compatible request failed (${response.status})`);
+    const detail = await readProviderErrorDetail(response, "");
+    throw new Error(`OpenAI-compatible request failed (${response.status})${detail ? `: ${detail}` : ""}`);
   }
   return await response.json().catch(() => ({})) as {
     reasoning?: unknown;
@@ -1971,12 +1965,12 @@ async function requestOpenAiToolCompletionStream(params: {
       maxTokens: 1600
     }
   });
-  const response = await fetch(`${baseUrl}/chat/completions`, {
+  const response = await fetch(withProviderAuthUrl(params.provider, `${baseUrl}/chat/completions`), {
     method: "POST",
-    headers: {
+    headers: withProviderHeaders(params.provider, {
       "Content-Type": "application/json",
-      Authorization: `Bearer ${params.provider.api_key_cipher}`
-    },
+      ...providerAuthHeaders(params.provider)
+    }),
     body: JSON.stringify({
       model: params.modelId,
       messages: params.messages.map((message) => ({
@@ -1990,8 +1984,8 @@ async function requestOpenAiToolCompletionStream(params: {
     signal: params.signal
   });
   if (!response.ok || !response.body) {
-    const errText = await response.text().catch(() => "");
-    throw new Error(errText || `OpenAI-compatible streaming request failed (${response.status})`);
+    const detail = await readProviderErrorDetail(response, "");
+    throw new Error(`OpenAI-compatible streaming request failed (${response.status})${detail ? `: ${detail}` : ""}`);
   }
   const contentType = String(response.headers.get("content-type") || "").toLowerCase();
   if (!contentType.includes("text/event-stream")) {
@@ -2005,6 +1999,7 @@ async function requestOpenAiToolCompletionStream(params: {
   const streamedToolCalls = new Map<number, OpenAIToolCall>();
   const thinkState = createThinkStreamState();
   let buffer = "";
+  const readStreamText = createOpenAiStreamTextReader();
 
   const processEventBlock = (eventBlock: string) => {
     const eventType = extractSseEventType(eventBlock);
@@ -2022,7 +2017,7 @@ async function requestOpenAiToolCompletionStream(params: {
         reasoningParts.push(reasoningDelta);
         params.onReasoningDelta?.(reasoningDelta);
       }
-      const textDelta = extractOpenAiStreamTextDelta(parsed);
+      const textDelta = readStreamText(parsed);
       if (textDelta) {
         const split = consumeThinkChunk(thinkState, textDelta);
         if (split.reasoning) {
@@ -2079,6 +2074,7 @@ async function requestOpenAiToolCompletionStream(params: {
     }
   }
 
+  buffer += decoder.decode();
   const flushed = consumeSseEventBlocks(buffer, true);
   for (const eventBlock of flushed.events) {
     processEventBlock(eventBlock);
@@ -2161,12 +2157,12 @@ async function requestOpenAiStructuredCompletion(params: {
       maxTokens: 1800
     }
   });
-  const response = await fetch(`${baseUrl}/chat/completions`, {
+  const response = await fetch(withProviderAuthUrl(params.provider, `${baseUrl}/chat/completions`), {
     method: "POST",
-    headers: {
+    headers: withProviderHeaders(params.provider, {
       "Content-Type": "application/json",
-      Authorization: `Bearer ${params.provider.api_key_cipher}`
-    },
+      ...providerAuthHeaders(params.provider)
+    }),
     body: JSON.stringify({
       model: params.modelId,
       messages: params.messages.map((message) => ({
@@ -2179,8 +2175,8 @@ async function requestOpenAiStructuredCompletion(params: {
     signal: params.signal
   });
   if (!response.ok) {
-    const errText = await response.text().catch(() => "");
-    throw new Error(errText || `OpenAI-compatible structured planner request failed (${response.status})`);
+    const detail = await readProviderErrorDetail(response, "");
+    throw new Error(`OpenAI-compatible structured planner request failed (${response.status})${detail ? `: ${detail}` : ""}`);
   }
   const body = await response.json().catch(() => ({})) as {
     reasoning?: unknown;
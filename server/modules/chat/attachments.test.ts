@@ -1,5 +1,14 @@
+import { mkdtempSync, symlinkSync, writeFileSync } from "fs";
+import { tmpdir } from "os";
+import { join } from "path";
 import { describe, expect, it } from "vitest";
-import { selectTimelineForPrompt } from "./attachments.js";
+import {
+  buildOmittedImagesNote,
+  getContextWindowBudget,
+  imagesToAttachments,
+  modelAcceptsImages,
+  selectTimelineForPrompt
+} from "./attachments.js";
 
 describe("selectTimelineForPrompt", () => {
   const timeline = Array.from({ length: 6 }, (_, index) => ({
@@ -16,4 +25,90 @@ describe("selectTimelineForPrompt", () => {
   it("treats zero as unlimited and still applies the token budget", () => {
     expect(selectTimelineForPrompt(timeline, "", 8192, 35, 75, 0)).toHaveLength(6);
   });
+
+  it("always keeps pinned messages in timeline order and charges them against the budget", () => {
+    const pinned = timeline.map((item, index) => ({ ...item, pinned: index === 0 }));
+    expect(selectTimelineForPrompt(pinned, "", 8192, 35, 75, 2).map((item) => item.content))
+      .toEqual(["message-1", "message-5", "message-6"]);
+
+    const heavy = [
+      { role: "user", content: "lore", tokenCount: 600, pinned: true },
+      ...Array.from({ length: 4 }, (_, index) => ({ role: "assistant", content: `recent-${index + 1}`, tokenCount: 100 }))
+    ];
+    // 75% of the 1024-token window leaves 768 tokens: the pinned 600 plus one recent message.
+    expect(selectTimelineForPrompt(heavy, "", 1024, 35, 75, 0).map((item) => item.content))
+      .toEqual(["lore", "recent-4"]);
+  });
+});
+
+describe("getContextWindowBudget", () => {
+  it("honors large context windows configured in settings", () => {
+    expect(getContextWindowBudget({ contextWindowSize: 131072 })).toBe(131072);
+    expect(getContextWindowBudget({ contextWindowSize: 0 })).toBe(8192);
+    expect(getContextWindowBudget({ contextWindowSize: 100 })).toBe(512);
+  });
+
+  it("caps at the configured size and uses the model's context length only when set to auto", () => {
+    expect(getContextWindowBudget({ contextWindowSize: 8192 }, 131072)).toBe(8192);
+    expect(getContextWindowBudget({ contextWindowSize: 0 }, 1048576)).toBe(1048576);
+    expect(getContextWindowBudget({ contextWindowSize: null }, 32768)).toBe(32768);
+    expect(getContextWindowBudget({}, 32768)).toBe(32768);
+    expect(getContextWindowBudget({ contextWindowSize: 16000 }, 131072)).toBe(16000);
+    expect(getContextWindowBudget({ contextWindowSize: 200000 }, 131072)).toBe(131072);
+    expect(getContextWindowBudget({ contextWindowSize: 16000 }, null)).toBe(16000);
+  });
+});
+
+describe("imagesToAttachments", () => {
+  const pngBytes = Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0, 0, 0, 0]);
+
+  it("keeps data URLs and base64-encodes local image files by their magic bytes", async () => {
+    const dir = mkdtempSync(join(tmpdir(), "vellium-images-"));
+    const file = join(dir, "scene-reference.jpg");
+    writeFileSync(file, pngBytes);
+    const attachments = await imagesToAttachments(["data:image/webp;base64,AAAA", file], dir);
+    expect(attachments.map(({ filename, type, mimeType, dataUrl }) => ({ filename, type, mimeType, dataUrl }))).toEqual([
+      { filename: "image-1", type: "image", mimeType: "image/webp", dataUrl: "data:image/webp;base64,AAAA" },
+      { filename: "scene-reference.jpg", type: "image", mimeType: "image/png", dataUrl: `data:image/png;base64,${pngBytes.toString("base64")}` }
+    ]);
+  });
+
+  it("refuses relative paths, missing files and non-image content", async () => {
+    const dir = mkdtempSync(join(tmpdir(), "vellium-images-"));
+    const notes = join(dir, "notes.png");
+    writeFileSync(notes, "plain text");
+    await expect(imagesToAttachments(["scene.png"], dir)).rejects.toThrow("absolute file path");
+    await expect(imagesToAttachments([join(dir, "missing.png")], dir)).rejects.toThrow("file not found");
+    await expect(imagesToAttachments([notes], dir)).rejects.toThrow("not a PNG");
+    await expect(imagesToAttachments(["data:text/plain;base64,AAAA"], dir)).rejects.toThrow("data:image");
+    await expect(imagesToAttachments(undefined, dir)).resolves.toEqual([]);
+  });
+
+  it("only reads files inside the uploads folder, including through symlinks", async () => {
+    const uploads = mkdtempSync(join(tmpdir(), "vellium-uploads-"));
+    const outside = join(mkdtempSync(join(tmpdir(), "vellium-elsewhere-")), "secret.png");
+    writeFileSync(outside, pngBytes);
+    const link = join(uploads, "link.png");
+    symlinkSync(outside, link);
+    await expect(imagesToAttachments([outside], uploads)).rejects.toThrow("must be a file in the uploads folder");
+    await expect(imagesToAttachments([link], uploads)).rejects.toThrow("must be a file in the uploads folder");
+  });
+});
+
+describe("modelAcceptsImages", () => {
+  it("keeps images for vision and unknown models and drops them for known text-only ones", () => {
+    expect(modelAcceptsImages("openai", "gpt-4o-mini")).toBe(true);
+    expect(modelAcceptsImages("openai", "my-local-finetune")).toBe(true);
+    expect(modelAcceptsImages("ollama", "qwen2.5-vl:7b")).toBe(true);
+    expect(modelAcceptsImages("ollama", "llama3.2-vision")).toBe(true);
+    expect(modelAcceptsImages("openai", "deepseek-chat")).toBe(false);
+    expect(modelAcceptsImages("ollama", "llama3.1:8b")).toBe(false);
+    expect(modelAcceptsImages("koboldcpp", "gpt-4o")).toBe(false);
+  });
+
+  it("notes omitted images in the prompt text", () => {
+    expect(buildOmittedImagesNote([{ id: "a", filename: "map.png", type: "image", url: "", mimeType: "image/png" }]))
+      .toBe("\n\n[An image was attached but this model cannot view images: map.png]");
+    expect(buildOmittedImagesNote([])).toBe("");
+  });
 });
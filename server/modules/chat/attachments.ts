@@ -1,4 +1,6 @@
-import { roughTokenCount } from "../../db.js";
+import { readFile, realpath, stat } from "fs/promises";
+import { basename, isAbsolute, relative } from "path";
+import { newId, roughTokenCount } from "../../db.js";
 import type { ChatAttachment } from "../../domain/rpEngine.js";
 import { getChatRagBinding, ingestRagDocument } from "../../services/rag.js";
 import type { MessageAttachmentPayload } from "./routeHelpers.js";
@@ -10,8 +12,12 @@ interface PromptTimelineItem {
   tokenCount?: number;
   characterName?: string | null;
   attachments?: MessageAttachmentPayload[];
+  pinned?: boolean;
 }
 
+/** Per-message cap; a send with more attachments and images than this is refused, not trimmed. */
+export const MAX_MESSAGE_ATTACHMENTS = 12;
+
 export function sanitizeAttachments(input: unknown): MessageAttachmentPayload[] {
   if (!Array.isArray(input)) return [];
   const out: MessageAttachmentPayload[] = [];
@@ -46,7 +52,7 @@ export function sanitizeAttachments(input: unknown): MessageAttachmentPayload[]
       out.push(base);
     }
   }
-  return out.slice(0, 12);
+  return out.slice(0, MAX_MESSAGE_ATTACHMENTS);
 }
 
 export function toChatAttachments(input: MessageAttachmentPayload[] | null | undefined): ChatAttachment[] {
@@ -156,6 +162,82 @@ export function buildAttachmentPromptAppendix(attachments: MessageAttachmentPayl
     .join("\n\n");
 }
 
+const MAX_IMAGE_BYTES = 15 * 1024 * 1024;
+
+function sniffImageMimeType(bytes: Buffer): string | null {
+  if (bytes.length >= 8 && bytes.subarray(0, 8).equals(Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]))) return "image/png";
+  if (bytes.length >= 3 && bytes[0] === 0xff && bytes[1] === 0xd8 && bytes[2] === 0xff) return "image/jpeg";
+  if (bytes.length >= 6 && /^GIF8[79]a$/.test(bytes.subarray(0, 6).toString("latin1"))) return "image/gif";
+  if (bytes.length >= 12 && bytes.subarray(0, 4).toString("latin1") === "RIFF" && bytes.subarray(8, 12).toString("latin1") === "WEBP") return "image/webp";
+  if (bytes.length >= 2 && bytes[0] === 0x42 && bytes[1] === 0x4d) return "image/bmp";
+  return null;
+}
+
+async function isInsideDir(dir: string, path: string): Promise<boolean> {
+  // Both sides are resolved through symlinks, so a link inside the folder cannot point outside it.
+  const [realDir, realPath] = await Promise.all([realpath(dir), realpath(path)]).catch(() => [null, null]);
+  if (!realDir || !realPath) return false;
+  const fromDir = relative(realDir, realPath);
+  return Boolean(fromDir) && !fromDir.startsWith("..") && !isAbsolute(fromDir);
+}
+
+/**
+ * Turns the `images` list of a send request into image attachments. Each entry is a `data:image/...` URL or an
+ * absolute path to an image file inside `uploadsDir`, which is read and base64-encoded here; the type comes from
+ * the file's magic bytes, so non-image files are refused whatever their extension. Throws with a user-facing message.
+ */
+export async function imagesToAttachments(input: unknown, uploadsDir: string): Promise<MessageAttachmentPayload[]> {
+  if (input === undefined || input === null) return [];
+  if (!Array.isArray(input)) throw new Error("images must be an array of data URLs or file paths");
+  const out: MessageAttachmentPayload[] = [];
+  for (const [index, item] of input.entries()) {
+    const value = String(item ?? "").trim();
+    if (value.startsWith("data:")) {
+      const mimeType = /^data:(image\/[a-z0-9.+-]+);base64,/i.exec(value)?.[1];
+      if (!mimeType) throw new Error(`images[${index}] must be a base64 data:image URL`);
+      if (value.length > MAX_IMAGE_BYTES) throw new Error(`images[${index}] is larger than 15 MB`);
+      out.push({ id: newId(), filename: `image-${index + 1}`, type: "image", url: "", mimeType: mimeType.toLowerCase(), dataUrl: value });
+      continue;
+    }
+    if (!value || !isAbsolute(value)) throw new Error(`images[${index}] must be a data URL or an absolute file path`);
+    const info = await stat(value).catch(() => null);
+    if (!info?.isFile()) throw new Error(`images[${index}]: file not found`);
+    if (!await isInsideDir(uploadsDir, value)) throw new Error(`images[${index}] must be a file in the uploads folder`);
+    if (info.size > MAX_IMAGE_BYTES) throw new Error(`images[${index}] is larger than 15 MB`);
+    const bytes = await readFile(value);
+    const mimeType = sniffImageMimeType(bytes);
+    if (!mimeType) throw new Error(`images[${index}] is not a PNG, JPEG, GIF, WebP or BMP image`);
+    out.push({
+      id: newId(),
+      filename: basename(value),
+      type: "image",
+      url: "",
+      mimeType,
+      dataUrl: `data:${mimeType};base64,${bytes.toString("base64")}`
+    });
+  }
+  return out;
+}
+
+// Families that reject image parts outright. Anything else keeps its images, as before this check existed.
+const TEXT_ONLY_MODEL_PATTERN = /deepseek-(?:chat|reasoner|r1|v2|v3|coder)|gpt-3\.5|\bo[13]-mini\b|mixtral|codestral|llama-?3(?:\.[013])?\b|qwen2\.5|qwq|\bphi-?[34]\b|command-r/i;
+const VISION_MODEL_HINT = /vision|-vl\b|vl-|llava|multimodal|omni|pixtral/i;
+
+/** Whether image parts can go to this model; text-completion (KoboldCpp) providers never take them. */
+export function modelAcceptsImages(providerType: string, modelId: string): boolean {
+  if (providerType === "koboldcpp") return false;
+  const model = String(modelId || "");
+  if (VISION_MODEL_HINT.test(model)) return true;
+  return !TEXT_ONLY_MODEL_PATTERN.test(model);
+}
+
+/** Placeholder text for images kept out of the prompt, so the model still knows something was attached. */
+export function buildOmittedImagesNote(attachments: MessageAttachmentPayload[]): string {
+  const images = attachments.filter((item) => item.type === "image");
+  if (images.length === 0) return "";
+  return `\n\n[${images.length === 1 ? "An image was" : `${images.length} images were`} attached but this model cannot view images: ${images.map((item) => item.filename || "image").join(", ")}]`;
+}
+
 export function buildPromptContentWithAttachments(content: string, attachments: MessageAttachmentPayload[]): string {
   return `${String(content || "")}${buildAttachmentPromptAppendix(attachments)}`;
 }
@@ -212,10 +294,23 @@ export function selectFirstResponderByMention(content: string, orderedCharacterN
   return best?.name;
 }
 
-export function getContextWindowBudget(settings: Record<string, unknown>): number {
+const DEFAULT_CONTEXT_WINDOW = 8192;
+
+function clampContextWindow(tokens: number): number {
+  return Math.max(512, Math.min(1048576, Math.floor(tokens)));
+}
+
+/**
+ * Token budget for the prompt. A configured size is a hard cap that a smaller model window lowers but
+ * never raises; 0 (or null) opts into the model's reported context length, falling back to 8192.
+ */
+export function getContextWindowBudget(settings: Record<string, unknown>, modelContextLength?: number | null): number {
   const raw = Number(settings.contextWindowSize);
-  if (!Number.isFinite(raw) || raw <= 0) return 8192;
-  return Math.max(512, Math.min(32768, Math.floor(raw)));
+  const configured = Number.isFinite(raw) && raw > 0 ? raw : 0;
+  const modelWindow = Number(modelContextLength);
+  const hasModelWindow = Number.isFinite(modelWindow) && modelWindow > 0;
+  if (!configured) return clampContextWindow(hasModelWindow ? modelWindow : DEFAULT_CONTEXT_WINDOW);
+  return clampContextWindow(hasModelWindow ? Math.min(configured, modelWindow) : configured);
 }
 
 export function getTailBudgetPercent(
@@ -242,15 +337,20 @@ export function selectTimelineForPrompt(
     ? Math.max(256, Math.floor(contextWindowBudget * (withSummaryPercent / 100)))
     : Math.max(512, Math.floor(contextWindowBudget * (withoutSummaryPercent / 100)));
 
-  const selected: PromptTimelineItem[] = [];
-  let used = 0;
+  const tokensOf = (msg: PromptTimelineItem) => Math.max(1, Number(msg.tokenCount) || roughTokenCount(msg.content));
+  // Pinned messages are always kept and paid for first; the recent tail fills whatever budget is left.
+  const kept = new Set<PromptTimelineItem>(timeline.filter((msg) => msg.pinned));
+  let used = [...kept].reduce((sum, msg) => sum + tokensOf(msg), 0);
+  let recent = 0;
   for (let i = timeline.length - 1; i >= 0; i -= 1) {
-    if (maxMessages > 0 && selected.length >= Math.floor(maxMessages)) break;
     const msg = timeline[i];
-    const msgTokens = Math.max(1, Number(msg.tokenCount) || roughTokenCount(msg.content));
-    if (selected.length > 0 && used + msgTokens > historyTokenBudget) break;
-    selected.unshift(msg);
+    if (msg.pinned) continue;
+    if (maxMessages > 0 && recent >= Math.floor(maxMessages)) break;
+    const msgTokens = tokensOf(msg);
+    if (recent > 0 && used + msgTokens > historyTokenBudget) break;
+    kept.add(msg);
     used += msgTokens;
+    recent += 1;
   }
-  return selected;
+  return timeline.filter((msg) => kept.has(msg));
 }
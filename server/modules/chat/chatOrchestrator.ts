@@ -1,14 +1,31 @@
 import type { Response } from "express";
-import { db, newId, now, roughTokenCount, isLocalhostUrl, nextSortOrder } from "../../db.js";
-import { buildSystemPrompt, buildMessageArray, buildMultiCharSystemPrompt, buildMultiCharMessageArray, coalesceSystemMessages, mergeConsecutiveRoles } from "../../domain/rpEngine.js";
+import { db, newId, now, roughTokenCount, countTokens, isLocalhostUrl, nextSortOrder } from "../../db.js";
+import {
+  applyMessageMacros,
+  applySceneSampling,
+  buildSystemPrompt,
+  buildMessageArray,
+  buildMultiCharSystemPrompt,
+  buildMultiCharMessageArray,
+  buildPostHistoryBlocksPrompt,
+  coalesceSystemMessages,
+  DEFAULT_AUTHOR_NOTE_DEPTH,
+  mergeConsecutiveRoles,
+  splitPromptBlocksAtHistory
+} from "../../domain/rpEngine.js";
 import type { CharacterCardData, ChatCompletionMessage } from "../../domain/rpEngine.js";
-import { getTriggeredLoreEntries, injectLoreBlocks } from "../../domain/lorebooks.js";
+import { getTriggeredLoreEntries, injectLoreBlocks, limitLoreEntries } from "../../domain/lorebooks.js";
+import { buildPresetStyleDirective } from "../../domain/rpPresets.js";
+import { appErrorBody, notConfiguredError, sendAppError, toAppError } from "../../services/appError.js";
 import { normalizeProviderType } from "../../services/providerApi.js";
 import { retrieveRagContext, type RagContextSource } from "../../services/rag.js";
+import { buildMemoryRecallDirective } from "./memoryRecall.js";
 import {
+  buildOmittedImagesNote,
   buildPromptContentWithAttachments,
   getContextWindowBudget,
   getTailBudgetPercent,
+  modelAcceptsImages,
   resolveLorebookIds,
   selectTimelineForPrompt,
   toChatAttachments
@@ -24,11 +41,14 @@ import {
 } from "./promptContext.js";
 import {
   countProviderTokens,
+  PartialStreamError,
   streamProviderCompletion
 } from "./providerExecution.js";
+import type { ProviderTokenUsage } from "./openAiStream.js";
 import { buildReasoningAwareTimeline } from "./reasoningContext.js";
 import {
-  getPromptBlocks,
+  getCachedModelContextLength,
+  getChatPromptBlocks,
   getSettings,
   getTimeline,
   type MessageAttachmentPayload,
@@ -39,14 +59,25 @@ import {
   appendMissingToolImageMarkdown,
   OpenAICompletionMessage,
   REASONING_CALL_NAME,
+  runClientToolPass,
   runToolCallingCompletion,
   serializeToolTrace,
+  type ToolCallStreamEvent,
   type ToolCallTrace
 } from "./tooling.js";
+import { appendClientToolExchange, type ClientToolDefinition } from "./clientTools.js";
+import { CONTINUE_INSTRUCTION, joinContinuation } from "./continuation.js";
+import { endSseResponse, writeSseEvent } from "./sseWriter.js";
+import { resolveProviderFallbackChain, streamWithProviderFallback, type ResolvedFallbackTarget } from "./providerFallback.js";
 import { appendRpReasoningTurnGuard, inlineRpReasoningHistory, RP_REASONING_SYSTEM_PROMPT } from "./rpReasoning.js";
 
 export const activeAbortControllers = new Map<string, AbortController>();
 
+// A generation that outlives its connection must not drop the controller of a newer send on the same chat.
+function releaseAbortController(chatId: string, controller: AbortController) {
+  if (activeAbortControllers.get(chatId) === controller) activeAbortControllers.delete(chatId);
+}
+
 function appendPersonaInstruction(base: string, userName: string, personaInstruction: string): string {
   if (!personaInstruction) return base;
   return `${base}\n\n[User Persona]\nName: ${userName}\n${personaInstruction}`;
@@ -55,10 +86,7 @@ function appendPersonaInstruction(base: string, userName: string, personaInstruc
 async function sendSseText(res: Response, chatId: string, text: string, paceMs = 0) {
   const chunks = text.match(/[\s\S]{1,140}/g) ?? [];
   for (const chunk of chunks) {
-    res.write(`data: ${JSON.stringify({ type: "delta", chatId, delta: chunk })}\n\n`);
-    if (typeof (res as Response & { flush?: () => void }).flush === "function") {
-      (res as Response & { flush?: () => void }).flush?.();
-    }
+    writeSseEvent(res, { type: "delta", chatId, delta: chunk });
     if (paceMs > 0) {
       await new Promise((resolve) => setTimeout(resolve, paceMs));
     }
@@ -91,6 +119,7 @@ function insertFallbackAssistantMessage(params: {
 
 async function persistAssistantTurn(params: {
   provider: ProviderRow;
+  modelId: string;
   chatId: string;
   branchId: string;
   parentMsgId: string | null;
@@ -104,26 +133,37 @@ async function persistAssistantTurn(params: {
     generationCompletedAt: string | null;
     generationDurationMs: number | null;
   };
+  truncated?: boolean;
+  usage?: ProviderTokenUsage | null;
 }) {
-  if (!params.content && params.toolTraces.length === 0) return;
+  // Reasoning is kept beside the reply, never in it, so a reasoning-only turn still has no visible answer.
+  const hasVisibleOutput = params.content.trim().length > 0
+    || params.toolTraces.some((trace) => trace.name !== REASONING_CALL_NAME);
+  if (!hasVisibleOutput && params.toolTraces.length === 0) return;
+  const content = hasVisibleOutput ? params.content : "[Error] The model returned reasoning but no visible reply";
 
+  // Provider-reported usage wins; the local estimate only covers providers that omit it.
+  const usage = params.usage ?? null;
   const assistantId = newId();
   db.prepare(
-    "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, parent_id, deleted, created_at, generation_started_at, generation_completed_at, generation_duration_ms, character_name, sort_order) VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, ?, ?, ?)"
+    "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, parent_id, deleted, created_at, generation_started_at, generation_completed_at, generation_duration_ms, character_name, sort_order, truncated, prompt_tokens, completion_tokens) VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
   ).run(
     assistantId,
     params.chatId,
     params.branchId,
     "assistant",
-    params.content,
-    await countProviderTokens(params.provider, params.content),
+    content,
+    usage ? usage.completionTokens : await countProviderTokens(params.provider, content, params.modelId),
     params.parentMsgId,
     now(),
     params.generationMeta.generationStartedAt,
     params.generationMeta.generationCompletedAt,
     params.generationMeta.generationDurationMs,
     params.overrideCharacterName || null,
-    nextSortOrder(params.chatId, params.branchId)
+    nextSortOrder(params.chatId, params.branchId),
+    params.truncated ? 1 : 0,
+    usage?.promptTokens ?? null,
+    usage?.completionTokens ?? null
   );
 
   if (params.ragSources.length > 0) {
@@ -141,7 +181,7 @@ async function persistAssistantTurn(params: {
       params.branchId,
       "tool",
       toolText,
-      roughTokenCount(toolText),
+      countTokens(toolText, params.modelId),
       assistantId,
       now(),
       params.generationMeta.generationStartedAt,
@@ -153,46 +193,61 @@ async function persistAssistantTurn(params: {
   }
 }
 
-export async function streamLlmResponse(params: {
+export interface ChatPromptParams {
   chatId: string;
   branchId: string;
-  res: Response;
-  parentMsgId: string | null;
   overrideCharacterName?: string;
   isAutoConvo?: boolean;
   userPersona?: UserPersonaPayload;
   runtimeSystemPrompt?: string;
-}) {
+}
+
+/**
+ * Everything a generation turn sends to the model: prompt blocks, scene state, lore, retrieved knowledge,
+ * recalled memories and the trimmed history. Generation and the prompt preview both go through here.
+ */
+export async function assembleChatPrompt(params: ChatPromptParams) {
   const settings = getSettings();
   const providerId = settings.activeProviderId;
   const modelId = settings.activeModel;
 
-  const chat = db.prepare("SELECT character_id, character_ids, lorebook_id, lorebook_ids, context_summary FROM chats WHERE id = ?").get(params.chatId) as {
+  const chat = db.prepare("SELECT character_id, character_ids, lorebook_id, lorebook_ids, context_summary, system_prompt, active_preset, author_note_depth, response_language FROM chats WHERE id = ?").get(params.chatId) as {
     character_id: string | null;
     character_ids: string | null;
     lorebook_id: string | null;
     lorebook_ids: string | null;
     context_summary: string | null;
+    system_prompt: string | null;
+    active_preset: string | null;
+    author_note_depth: number | null;
+    response_language: string | null;
   } | undefined;
 
-  const blocks = getPromptBlocks(settings as Record<string, unknown>);
+  const blocks = getChatPromptBlocks(params.chatId, settings as Record<string, unknown>);
   const sceneState = getSceneState(params.chatId);
-  const authorNote = getAuthorNote(params.chatId);
-  const samplerConfig = getChatSamplerConfig(params.chatId, settings.samplerConfig);
+  const authorNoteDisabled = blocks.some((block) => block.kind === "author_note" && !block.enabled);
+  const authorNote = authorNoteDisabled ? "" : getAuthorNote(params.chatId);
+  const authorNoteDepth = chat?.author_note_depth ?? DEFAULT_AUTHOR_NOTE_DEPTH;
   const chatMode = sceneState?.chatMode || "rp";
   const pureChatMode = chatMode === "pure_chat";
+  const chatSamplerConfig = getChatSamplerConfig(params.chatId, settings.samplerConfig);
+  const samplerConfig = sceneState && !pureChatMode
+    ? applySceneSampling(chatSamplerConfig, sceneState.intensity)
+    : chatSamplerConfig;
   const lightRpMode = chatMode === "light_rp";
   const strictGrounding = (settings as { strictGrounding?: unknown }).strictGrounding !== false;
   const rpReasoningEnabled = (settings as { rpReasoningEnabled?: unknown }).rpReasoningEnabled === true;
   const systemBlockContent = String(blocks.find((block) => block.kind === "system")?.content || "").trim();
 
   const resolvedUserName = (params.userPersona?.name || "").trim() || "User";
+  const responseLanguage = String(chat?.response_language || "").trim() || settings.responseLanguage;
   const personaInstruction = [
     params.userPersona?.description ? `Description: ${params.userPersona.description}` : "",
     params.userPersona?.personality ? `Personality: ${params.userPersona.personality}` : "",
     params.userPersona?.scenario ? `Scenario: ${params.userPersona.scenario}` : ""
   ].filter(Boolean).join("\n");
   const runtimeSystemPrompt = [
+    pureChatMode ? "" : buildPresetStyleDirective(chat?.active_preset),
     rpReasoningEnabled ? RP_REASONING_SYSTEM_PROMPT : "",
     String(params.runtimeSystemPrompt || "").trim()
   ].filter(Boolean).join("\n\n").slice(0, 4000);
@@ -220,7 +275,10 @@ export async function streamLlmResponse(params: {
     settings.includeReasoningInContext !== false
   );
   const contextSummary = chat?.context_summary || "";
-  const contextWindowBudget = getContextWindowBudget(settings as Record<string, unknown>);
+  const contextWindowBudget = getContextWindowBudget(
+    settings as Record<string, unknown>,
+    getCachedModelContextLength(providerId, modelId)
+  );
   const withSummaryPercent = getTailBudgetPercent(settings as Record<string, unknown>, "contextTailBudgetWithSummaryPercent", 35);
   const withoutSummaryPercent = getTailBudgetPercent(settings as Record<string, unknown>, "contextTailBudgetWithoutSummaryPercent", 75);
   const promptTimeline = selectTimelineForPrompt(
@@ -249,21 +307,37 @@ export async function streamLlmResponse(params: {
     ragSourcesForAssistant = [];
     ragAppendix = "";
   }
+  const memoryDirective = await buildMemoryRecallDirective(
+    params.chatId,
+    latestUserPrompt,
+    contextSummary,
+    (text) => countTokens(text, modelId),
+    settings as Record<string, unknown>
+  );
+  if (memoryDirective) ragAppendix += `\n\n${memoryDirective}`;
 
   const selectedLorebookIds = resolveLorebookIds(chat);
   const lorebookEntries = pureChatMode || lightRpMode ? [] : getLorebookEntries(selectedLorebookIds);
   const loreBlockEnabled = !pureChatMode && !lightRpMode && blocks.some((block) => block.kind === "lore" && block.enabled);
+  const loreScanTimeline = settings.loreScanDepth > 0 ? promptTimeline.slice(-settings.loreScanDepth) : promptTimeline;
   const triggeredLoreEntries = loreBlockEnabled
-    ? getTriggeredLoreEntries(lorebookEntries, promptTimeline.map((item) => String(item.content || "")))
+    ? limitLoreEntries(
+      getTriggeredLoreEntries(lorebookEntries, loreScanTimeline.map((item) => String(item.content || ""))),
+      settings.loreTokenBudget,
+      (text) => countTokens(text, modelId)
+    )
     : [];
   const effectiveBlocks = !pureChatMode && !lightRpMode && triggeredLoreEntries.length > 0
     ? injectLoreBlocks(blocks, triggeredLoreEntries)
     : blocks;
+  const providerType = providerId
+    ? normalizeProviderType((db.prepare("SELECT provider_type FROM providers WHERE id = ?").get(providerId) as { provider_type?: string } | undefined)?.provider_type)
+    : "openai";
+  const acceptsImages = modelAcceptsImages(providerType, modelId);
   const promptTimelineForModel = promptTimeline.map((item) => {
-    const content = buildPromptContentWithAttachments(
-      String(item.content || ""),
-      item.attachments as MessageAttachmentPayload[] | undefined || []
-    );
+    const itemAttachments = item.attachments as MessageAttachmentPayload[] | undefined || [];
+    const content = buildPromptContentWithAttachments(String(item.content || ""), itemAttachments)
+      + (acceptsImages ? "" : buildOmittedImagesNote(itemAttachments));
     const reasoningHistory = rpReasoningEnabled && item.role === "assistant"
       ? inlineRpReasoningHistory(content, item.reasoningContent)
       : { content, reasoningContent: item.reasoningContent };
@@ -272,13 +346,15 @@ export async function streamLlmResponse(params: {
       content: reasoningHistory.content,
       characterName: item.characterName || undefined,
       reasoningContent: reasoningHistory.reasoningContent,
-      attachments: toChatAttachments(item.attachments as MessageAttachmentPayload[] | undefined)
+      attachments: toChatAttachments(itemAttachments).filter((attachment) => acceptsImages || attachment.type !== "image")
     };
   });
 
   const characterSystemPrompt = String(currentCharCard?.systemPrompt || "").trim();
+  // Prompt-stack system block, then the character card, then the chat override, then the settings default.
   const resolvedBaseSystemPrompt = systemBlockContent
     || characterSystemPrompt
+    || String(chat?.system_prompt || "").trim()
     || String(settings.defaultSystemPrompt || "").trim();
   const promptCharacterCard = systemBlockContent || !characterSystemPrompt
     ? currentCharCard
@@ -298,7 +374,9 @@ export async function streamLlmResponse(params: {
       userName: resolvedUserName,
       ragAppendix,
       isAutoConvo: params.isAutoConvo,
-      strictGrounding
+      strictGrounding,
+      censorshipMode: settings.censorshipMode,
+      responseLanguage
     });
     systemPrompt = appendPersonaInstruction(systemPrompt, resolvedUserName, personaInstruction);
     if (runtimeSystemPrompt) systemPrompt += `\n\n${runtimeSystemPrompt}`;
@@ -328,12 +406,13 @@ export async function streamLlmResponse(params: {
       characterCards,
       currentCharacterName: params.overrideCharacterName || promptCharacterCard?.name,
       userName: resolvedUserName,
-      responseLanguage: settings.responseLanguage,
+      responseLanguage,
       sceneState,
       authorNote,
       ragAppendix,
       isAutoConvo: params.isAutoConvo,
-      strictGrounding
+      strictGrounding,
+      censorshipMode: settings.censorshipMode
     });
     systemPrompt = appendPersonaInstruction(systemPrompt, resolvedUserName, personaInstruction);
     if (runtimeSystemPrompt) systemPrompt += `\n\n${runtimeSystemPrompt}`;
@@ -357,15 +436,25 @@ export async function streamLlmResponse(params: {
         promptCharacterCard?.postHistoryInstructions
       );
   } else {
+    const { beforeHistory, afterHistory } = splitPromptBlocksAtHistory(effectiveBlocks);
+    const postHistoryInstructions = [
+      String(promptCharacterCard?.postHistoryInstructions || "").trim(),
+      buildPostHistoryBlocksPrompt(afterHistory, {
+        characterCard: promptCharacterCard,
+        sceneState,
+        intensity: sceneState?.intensity ?? 0.5,
+        userName: resolvedUserName
+      })
+    ].filter(Boolean).join("\n\n");
     if (characterCards.length > 1 && params.overrideCharacterName) {
       systemPrompt = buildMultiCharSystemPrompt(
         {
-          blocks: effectiveBlocks,
+          blocks: beforeHistory,
           characterCard: promptCharacterCard,
           sceneState,
           authorNote,
           intensity: sceneState?.intensity ?? 0.5,
-          responseLanguage: settings.responseLanguage,
+          responseLanguage,
           censorshipMode: settings.censorshipMode,
           contextSummary: chat?.context_summary || "",
           defaultSystemPrompt: resolvedBaseSystemPrompt,
@@ -392,16 +481,17 @@ export async function streamLlmResponse(params: {
         authorNote,
         contextSummary,
         resolvedUserName,
-        promptCharacterCard?.postHistoryInstructions
+        postHistoryInstructions,
+        authorNoteDepth
       );
     } else {
       systemPrompt = buildSystemPrompt({
-        blocks: effectiveBlocks,
+        blocks: beforeHistory,
         characterCard: promptCharacterCard,
         sceneState,
         authorNote,
         intensity: sceneState?.intensity ?? 0.5,
-        responseLanguage: settings.responseLanguage,
+        responseLanguage,
         censorshipMode: settings.censorshipMode,
         contextSummary: chat?.context_summary || "",
         defaultSystemPrompt: resolvedBaseSystemPrompt,
@@ -422,11 +512,19 @@ export async function streamLlmResponse(params: {
         contextSummary,
         promptCharacterCard?.name,
         resolvedUserName,
-        promptCharacterCard?.postHistoryInstructions
+        postHistoryInstructions,
+        authorNoteDepth
       );
     }
   }
 
+  // Builders already expanded {{char}}/{{user}}/{{time}}/{{date}}; this pass adds {{persona}} and,
+  // outside pure chat, scene variables.
+  apiMessages = applyMessageMacros(apiMessages, {
+    persona: String(params.userPersona?.description || "").trim(),
+    variables: sceneState && !pureChatMode ? sceneState.variables : undefined
+  });
+
   if (settings.mergeConsecutiveRoles) {
     apiMessages = mergeConsecutiveRoles(apiMessages);
   }
@@ -435,6 +533,60 @@ export async function streamLlmResponse(params: {
     apiMessages = appendRpReasoningTurnGuard(apiMessages);
   }
 
+  return {
+    settings,
+    providerId,
+    modelId,
+    samplerConfig,
+    timeline,
+    promptTimeline,
+    contextWindowBudget,
+    apiMessages,
+    ragSources: ragSourcesForAssistant
+  };
+}
+
+function messageContentText(content: ChatCompletionMessage["content"]): string {
+  if (typeof content === "string") return content;
+  return content.map((part) => part.text || "").join("\n");
+}
+
+/** Dry run of a generation turn: the final messages and a token estimate, without contacting the provider. */
+export async function previewChatPrompt(params: ChatPromptParams) {
+  const prompt = await assembleChatPrompt(params);
+  const estimatedTokens = prompt.apiMessages.reduce(
+    (sum, message) => sum + countTokens(messageContentText(message.content), prompt.modelId),
+    0
+  );
+  return {
+    messages: prompt.apiMessages,
+    estimatedTokens,
+    keptMessages: prompt.promptTimeline.length,
+    omittedMessages: prompt.timeline.length - prompt.promptTimeline.length,
+    contextWindowBudget: prompt.contextWindowBudget
+  };
+}
+
+export async function streamLlmResponse(params: ChatPromptParams & {
+  res: Response;
+  parentMsgId: string | null;
+  /** Caller-defined tools for this turn; calls are handed back to the client instead of being run. */
+  clientTools?: ClientToolDefinition[];
+  /** Answered client tool calls from the previous pass, replayed after the history when a turn continues. */
+  clientToolExchange?: OpenAICompletionMessage[];
+}) {
+  const {
+    settings,
+    providerId,
+    modelId,
+    samplerConfig,
+    timeline,
+    promptTimeline,
+    contextWindowBudget,
+    apiMessages,
+    ragSources: ragSourcesForAssistant
+  } = await assembleChatPrompt(params);
+
   if (!providerId || !modelId) {
     const lastUser = timeline.filter((message) => message.role === "user").pop();
     const assistantText = `[No provider configured] Echo: ${lastUser?.content || "..."}`;
@@ -463,7 +615,7 @@ export async function streamLlmResponse(params: {
   }
 
   if (settings.fullLocalMode && !isLocalhostUrl(provider.base_url)) {
-    params.res.status(400).json({ error: "Provider blocked by Full Local Mode" });
+    sendAppError(params.res, notConfiguredError("Provider blocked by Full Local Mode"));
     return;
   }
 
@@ -475,25 +627,112 @@ export async function streamLlmResponse(params: {
   });
   params.res.flushHeaders?.();
 
+  const omittedMessages = timeline.length - promptTimeline.length;
+  if (omittedMessages > 0) {
+    writeSseEvent(params.res, {
+      type: "context_trimmed",
+      chatId: params.chatId,
+      omittedMessages,
+      keptMessages: promptTimeline.length,
+      contextWindowBudget
+    });
+  }
+
+  // Only the user's stop (`POST /:id/abort`) cancels generation; a dropped connection keeps it running so the reply is saved.
   const abortController = new AbortController();
   activeAbortControllers.set(params.chatId, abortController);
-  let responseSettled = false;
 
-  params.res.on("finish", () => {
-    responseSettled = true;
-    activeAbortControllers.delete(params.chatId);
-  });
-  params.res.on("close", () => {
-    if (!responseSettled) {
-      abortController.abort();
-    }
-    activeAbortControllers.delete(params.chatId);
-  });
+  const writeEvent = (payload: Record<string, unknown>) => {
+    writeSseEvent(params.res, payload);
+  };
+  const writeToolEvent = (event: ToolCallStreamEvent) => {
+    writeEvent({
+      type: "tool",
+      chatId: params.chatId,
+      phase: event.phase,
+      callId: event.callId,
+      name: event.name,
+      args: String(event.args || "").slice(0, 2000),
+      result: typeof event.result === "string" ? event.result.slice(0, 4000) : undefined
+    });
+  };
 
+  // Which provider and model the saved reply is attributed to; changes when the fallback chain moves on.
+  let servedBy: ResolvedFallbackTarget = { provider, modelId };
   try {
     const sc = samplerConfig as Record<string, unknown>;
-    const toolCallingEnabled = settings.toolCallingEnabled === true
-      && normalizeProviderType(provider.provider_type) === "openai";
+    const isOpenAiProvider = normalizeProviderType(provider.provider_type) === "openai";
+    const toolCallingEnabled = settings.toolCallingEnabled === true && isOpenAiProvider;
+    const clientTools = params.clientTools ?? [];
+
+    // Client tool passes talk to the active provider directly: they skip streamWithProviderFallback
+    // and the stream retry, since a pending tool call cannot be replayed against another target.
+    if (clientTools.length > 0 && isOpenAiProvider) {
+      const generationStartedAt = now();
+      const reasoningCallId = `reasoning_${Date.now()}`;
+      let reasoningStarted = false;
+      const pass = await runClientToolPass({
+        provider,
+        modelId,
+        samplerConfig: sc,
+        apiMessages: (params.clientToolExchange
+          ? appendClientToolExchange(apiMessages, params.clientToolExchange)
+          : apiMessages) as unknown as OpenAICompletionMessage[],
+        settings: settings as Record<string, unknown>,
+        tools: clientTools,
+        signal: abortController.signal,
+        onAssistantDelta: (delta) => {
+          if (delta) writeEvent({ type: "delta", chatId: params.chatId, delta });
+        },
+        onToolEvent: writeToolEvent,
+        onReasoningDelta: (delta) => {
+          if (!delta) return;
+          if (!reasoningStarted) {
+            reasoningStarted = true;
+            writeToolEvent({ phase: "start", callId: reasoningCallId, name: REASONING_CALL_NAME, args: "{}" });
+          }
+          writeToolEvent({ phase: "delta", callId: reasoningCallId, name: REASONING_CALL_NAME, args: "{}", result: delta });
+        }
+      });
+      const generationCompletedAt = now();
+      const reasoning = pass.reasoning.slice(0, settings.reasoningMaxChars);
+      if (reasoningStarted) {
+        writeToolEvent({ phase: "done", callId: reasoningCallId, name: REASONING_CALL_NAME, args: "{}", result: reasoning });
+      }
+      const toolTraces: ToolCallTrace[] = [
+        ...pass.toolCalls.map((call) => ({ ...call, result: "", client: true, pending: true })),
+        ...(reasoning.trim() ? [{ callId: reasoningCallId, name: REASONING_CALL_NAME, args: "{}", result: reasoning }] : [])
+      ];
+
+      await persistAssistantTurn({
+        provider,
+        modelId,
+        chatId: params.chatId,
+        branchId: params.branchId,
+        parentMsgId: params.parentMsgId,
+        content: pass.content,
+        overrideCharacterName: params.overrideCharacterName,
+        ragSources: ragSourcesForAssistant,
+        toolTraces,
+        reasoningMaxChars: settings.reasoningMaxChars,
+        generationMeta: {
+          generationStartedAt,
+          generationCompletedAt,
+          generationDurationMs: Date.parse(generationCompletedAt) - Date.parse(generationStartedAt)
+        }
+      });
+
+      for (const call of pass.toolCalls) {
+        writeEvent({ type: "tool_call", chatId: params.chatId, callId: call.callId, name: call.name, args: call.args });
+      }
+      writeEvent({
+        type: "done",
+        chatId: params.chatId,
+        ...(pass.toolCalls.length > 0 ? { pendingToolCalls: pass.toolCalls.map((call) => call.callId) } : {})
+      });
+      endSseResponse(params.res);
+      return;
+    }
 
     if (toolCallingEnabled) {
       const toolResult = await runToolCallingCompletion({
@@ -504,28 +743,9 @@ export async function streamLlmResponse(params: {
         settings: settings as Record<string, unknown>,
         signal: abortController.signal,
         onAssistantDelta: (delta) => {
-          if (!delta) return;
-          params.res.write(`data: ${JSON.stringify({ type: "delta", chatId: params.chatId, delta })}\n\n`);
-          if (typeof (params.res as Response & { flush?: () => void }).flush === "function") {
-            (params.res as Response & { flush?: () => void }).flush?.();
-          }
+          if (delta) writeEvent({ type: "delta", chatId: params.chatId, delta });
         },
-        onToolEvent: (event) => {
-          const safeArgs = String(event.args || "").slice(0, 2000);
-          const safeResult = typeof event.result === "string" ? event.result.slice(0, 4000) : undefined;
-          params.res.write(`data: ${JSON.stringify({
-            type: "tool",
-            chatId: params.chatId,
-            phase: event.phase,
-            callId: event.callId,
-            name: event.name,
-            args: safeArgs,
-            result: safeResult
-          })}\n\n`);
-          if (typeof (params.res as Response & { flush?: () => void }).flush === "function") {
-            (params.res as Response & { flush?: () => void }).flush?.();
-          }
-        }
+        onToolEvent: writeToolEvent
       });
 
       if (toolResult) {
@@ -542,6 +762,7 @@ export async function streamLlmResponse(params: {
           generationCompletedAt: null,
           generationDurationMs: null
         };
+        let usage: ProviderTokenUsage | null = null;
 
         if (Array.isArray(toolResult.streamMessages) && toolResult.streamMessages.length > 0) {
           const streamResult = await streamProviderCompletion({
@@ -553,7 +774,8 @@ export async function streamLlmResponse(params: {
             reasoningMaxChars: settings.reasoningMaxChars,
             chatId: params.chatId,
             res: params.res,
-            signal: abortController.signal
+            signal: abortController.signal,
+            retryAttempts: settings.streamRetryAttempts
           });
           fullContent = streamResult.content;
           reasoningTraces = streamResult.toolTraces;
@@ -562,6 +784,7 @@ export async function streamLlmResponse(params: {
             generationCompletedAt: streamResult.generationCompletedAt,
             generationDurationMs: streamResult.generationDurationMs
           };
+          usage = streamResult.usage ?? null;
         }
 
         const combinedToolTraces = [...toolResult.toolCalls, ...reasoningTraces];
@@ -582,6 +805,7 @@ export async function streamLlmResponse(params: {
 
         await persistAssistantTurn({
           provider,
+          modelId,
           chatId: params.chatId,
           branchId: params.branchId,
           parentMsgId: params.parentMsgId,
@@ -590,73 +814,239 @@ export async function streamLlmResponse(params: {
           ragSources: ragSourcesForAssistant,
           toolTraces: combinedToolTraces,
           reasoningMaxChars: settings.reasoningMaxChars,
-          generationMeta
+          generationMeta,
+          usage
         });
 
-        params.res.write(`data: ${JSON.stringify({ type: "done", chatId: params.chatId })}\n\n`);
-        if (typeof (params.res as Response & { flush?: () => void }).flush === "function") {
-          (params.res as Response & { flush?: () => void }).flush?.();
-        }
-        params.res.end();
+        writeSseEvent(params.res, { type: "done", chatId: params.chatId });
+        endSseResponse(params.res);
         return;
       }
     }
 
+    const { result: streamResult } = await streamWithProviderFallback({
+      chain: resolveProviderFallbackChain({ provider, modelId }, settings.providerFallbacks, settings.fullLocalMode === true),
+      signal: abortController.signal,
+      stream: (target) => streamProviderCompletion({
+        provider: target.provider,
+        modelId: target.modelId,
+        messages: apiMessages,
+        samplerConfig: sc,
+        apiParamPolicy: settings.apiParamPolicy,
+        reasoningMaxChars: settings.reasoningMaxChars,
+        chatId: params.chatId,
+        res: params.res,
+        signal: abortController.signal,
+        retryAttempts: settings.streamRetryAttempts
+      }),
+      onFallback: ({ from, to, reason }) => {
+        servedBy = to;
+        writeEvent({
+          type: "provider_fallback",
+          chatId: params.chatId,
+          fromProviderId: from.provider.id,
+          fromModel: from.modelId,
+          toProviderId: to.provider.id,
+          toModel: to.modelId,
+          reason
+        });
+      }
+    });
+    // An abort mid-stream returns what already arrived; keep it as a truncated reply.
+    const interrupted = abortController.signal.aborted;
+    // Only a stream that finished with nothing at all counts as a failed turn.
+    const empty = !interrupted && !streamResult.content.trim() && streamResult.toolTraces.length === 0;
+
+    if (empty) {
+      insertFallbackAssistantMessage({
+        chatId: params.chatId,
+        branchId: params.branchId,
+        parentMsgId: params.parentMsgId,
+        content: "[Error] The model returned an empty reply",
+        characterName: params.overrideCharacterName
+      });
+    } else {
+      await persistAssistantTurn({
+        provider: servedBy.provider,
+        modelId: servedBy.modelId,
+        chatId: params.chatId,
+        branchId: params.branchId,
+        parentMsgId: params.parentMsgId,
+        content: streamResult.content,
+        overrideCharacterName: params.overrideCharacterName,
+        ragSources: ragSourcesForAssistant,
+        toolTraces: streamResult.toolTraces,
+        reasoningMaxChars: settings.reasoningMaxChars,
+        generationMeta: {
+          generationStartedAt: streamResult.generationStartedAt,
+          generationCompletedAt: streamResult.generationCompletedAt,
+          generationDurationMs: streamResult.generationDurationMs
+        },
+        truncated: interrupted,
+        usage: streamResult.usage
+      });
+    }
+
+    writeSseEvent(params.res, {
+      type: "done",
+      chatId: params.chatId,
+      ...(interrupted ? { interrupted: true } : {}),
+      ...(empty ? { error: "The model returned an empty reply" } : {})
+    });
+    endSseResponse(params.res);
+  } catch (err) {
+    if (err instanceof Error && err.name === "AbortError") {
+      writeSseEvent(params.res, { type: "done", chatId: params.chatId, interrupted: true });
+      endSseResponse(params.res);
+    } else if (err instanceof PartialStreamError) {
+      // Keep what already reached the user instead of replacing it with an error turn.
+      await persistAssistantTurn({
+        provider: servedBy.provider,
+        modelId: servedBy.modelId,
+        chatId: params.chatId,
+        branchId: params.branchId,
+        parentMsgId: params.parentMsgId,
+        content: err.partial.content,
+        overrideCharacterName: params.overrideCharacterName,
+        ragSources: ragSourcesForAssistant,
+        toolTraces: err.partial.toolTraces,
+        reasoningMaxChars: settings.reasoningMaxChars,
+        generationMeta: {
+          generationStartedAt: err.partial.generationStartedAt,
+          generationCompletedAt: err.partial.generationCompletedAt,
+          generationDurationMs: err.partial.generationDurationMs
+        },
+        truncated: true
+      });
+      writeSseEvent(params.res, { type: "done", chatId: params.chatId, truncated: true, ...appErrorBody(toAppError(err)) });
+      endSseResponse(params.res);
+    } else {
+      const failure = toAppError(err, "Network error");
+      insertFallbackAssistantMessage({
+        chatId: params.chatId,
+        branchId: params.branchId,
+        parentMsgId: params.parentMsgId,
+        content: `[Error] ${failure.message}`,
+        characterName: params.overrideCharacterName
+      });
+      writeSseEvent(params.res, { type: "done", chatId: params.chatId, ...appErrorBody(failure) });
+      endSseResponse(params.res);
+    }
+  } finally {
+    releaseAbortController(params.chatId, abortController);
+  }
+}
+
+async function appendContinuation(params: {
+  provider: ProviderRow;
+  modelId: string;
+  messageId: string;
+  content: string;
+  usage?: ProviderTokenUsage | null;
+  generationCompletedAt: string | null;
+  generationDurationMs: number | null;
+  truncated: boolean;
+}) {
+  if (!params.content.trim()) return;
+  const row = db.prepare(
+    "SELECT content, token_count, completion_tokens, generation_duration_ms FROM messages WHERE id = ?"
+  ).get(params.messageId) as { content: string; token_count: number; completion_tokens: number | null; generation_duration_ms: number | null } | undefined;
+  if (!row) return;
+  const addedTokens = params.usage?.completionTokens ?? await countProviderTokens(params.provider, params.content, params.modelId);
+  db.prepare(
+    "UPDATE messages SET content = ?, token_count = ?, completion_tokens = ?, truncated = ?, generation_completed_at = COALESCE(?, generation_completed_at), generation_duration_ms = ? WHERE id = ?"
+  ).run(
+    joinContinuation(row.content, params.content),
+    (row.token_count || 0) + addedTokens,
+    row.completion_tokens === null && !params.usage ? null : (row.completion_tokens ?? 0) + addedTokens,
+    params.truncated ? 1 : 0,
+    params.generationCompletedAt,
+    row.generation_duration_ms === null && params.generationDurationMs === null
+      ? null
+      : (row.generation_duration_ms ?? 0) + (params.generationDurationMs ?? 0),
+    params.messageId
+  );
+}
+
+/**
+ * Streams a continuation of the assistant message `messageId` (the branch tail) and appends it to that
+ * message. Tools are not offered: the turn being continued has already finished its tool calls.
+ */
+export async function streamContinuation(params: ChatPromptParams & { res: Response; messageId: string }) {
+  const { settings, providerId, modelId, samplerConfig, apiMessages } = await assembleChatPrompt(params);
+  if (!providerId || !modelId) {
+    sendAppError(params.res, notConfiguredError("No provider configured"));
+    return;
+  }
+  const provider = db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as ProviderRow | undefined;
+  if (!provider) {
+    sendAppError(params.res, notConfiguredError("Provider not found"));
+    return;
+  }
+  if (settings.fullLocalMode && !isLocalhostUrl(provider.base_url)) {
+    sendAppError(params.res, notConfiguredError("Provider blocked by Full Local Mode"));
+    return;
+  }
+
+  params.res.writeHead(200, {
+    "Content-Type": "text/event-stream",
+    "Cache-Control": "no-cache",
+    Connection: "keep-alive",
+    "X-Accel-Buffering": "no"
+  });
+  params.res.flushHeaders?.();
+
+  const abortController = new AbortController();
+  activeAbortControllers.set(params.chatId, abortController);
+  const finish = (payload: Record<string, unknown>) => {
+    writeSseEvent(params.res, { type: "done", chatId: params.chatId, messageId: params.messageId, ...payload });
+    endSseResponse(params.res);
+  };
+
+  try {
     const streamResult = await streamProviderCompletion({
       provider,
       modelId,
-      messages: apiMessages,
-      samplerConfig: sc,
+      messages: [...apiMessages, { role: "user", content: CONTINUE_INSTRUCTION }],
+      samplerConfig: samplerConfig as Record<string, unknown>,
       apiParamPolicy: settings.apiParamPolicy,
       reasoningMaxChars: settings.reasoningMaxChars,
       chatId: params.chatId,
       res: params.res,
-      signal: abortController.signal
+      signal: abortController.signal,
+      retryAttempts: settings.streamRetryAttempts
     });
-
-    await persistAssistantTurn({
+    const interrupted = abortController.signal.aborted;
+    await appendContinuation({
       provider,
-      chatId: params.chatId,
-      branchId: params.branchId,
-      parentMsgId: params.parentMsgId,
+      modelId,
+      messageId: params.messageId,
       content: streamResult.content,
-      overrideCharacterName: params.overrideCharacterName,
-      ragSources: ragSourcesForAssistant,
-      toolTraces: streamResult.toolTraces,
-      reasoningMaxChars: settings.reasoningMaxChars,
-      generationMeta: {
-        generationStartedAt: streamResult.generationStartedAt,
-        generationCompletedAt: streamResult.generationCompletedAt,
-        generationDurationMs: streamResult.generationDurationMs
-      }
+      usage: streamResult.usage,
+      generationCompletedAt: streamResult.generationCompletedAt,
+      generationDurationMs: streamResult.generationDurationMs,
+      truncated: interrupted
     });
-
-    params.res.write(`data: ${JSON.stringify({ type: "done", chatId: params.chatId })}\n\n`);
-    if (typeof (params.res as Response & { flush?: () => void }).flush === "function") {
-      (params.res as Response & { flush?: () => void }).flush?.();
-    }
-    params.res.end();
+    finish(interrupted ? { interrupted: true } : {});
   } catch (err) {
     if (err instanceof Error && err.name === "AbortError") {
-      if (!params.res.writableEnded) {
-        params.res.write(`data: ${JSON.stringify({ type: "done", chatId: params.chatId, interrupted: true })}\n\n`);
-        params.res.end();
-      }
-    } else {
-      const errMsg = err instanceof Error ? err.message : "Network error";
-      insertFallbackAssistantMessage({
-        chatId: params.chatId,
-        branchId: params.branchId,
-        parentMsgId: params.parentMsgId,
-        content: `[Error] ${errMsg}`,
-        characterName: params.overrideCharacterName
+      finish({ interrupted: true });
+    } else if (err instanceof PartialStreamError) {
+      await appendContinuation({
+        provider,
+        modelId,
+        messageId: params.messageId,
+        content: err.partial.content,
+        generationCompletedAt: err.partial.generationCompletedAt,
+        generationDurationMs: err.partial.generationDurationMs,
+        truncated: true
       });
-      if (!params.res.writableEnded) {
-        params.res.write(`data: ${JSON.stringify({ type: "done", chatId: params.chatId })}\n\n`);
-        params.res.end();
-      }
+      finish({ truncated: true, ...appErrorBody(toAppError(err)) });
+    } else {
+      // The reply is left as it was; a failed continuation never replaces text the user already has.
+      finish(appErrorBody(toAppError(err, "Network error")));
     }
   } finally {
-    activeAbortControllers.delete(params.chatId);
+    releaseAbortController(params.chatId, abortController);
   }
 }
@@ -0,0 +1,17 @@
+import { describe, expect, it } from "vitest";
+import { buildChatTitlePrompt, normalizeGeneratedChatTitle } from "./chatTitle.js";
+
+describe("chat titles", () => {
+  it("cleans labels, quotes, reasoning and trailing punctuation from model output", () => {
+    expect(normalizeGeneratedChatTitle("Title: \"Fixing the SQLite migration.\"")).toBe("Fixing the SQLite migration");
+    expect(normalizeGeneratedChatTitle("<think>short one</think>\n\n**Dragon lore questions**\nextra line")).toBe("Dragon lore questions");
+    expect(normalizeGeneratedChatTitle("   \n")).toBe("");
+  });
+
+  it("builds the prompt from the first exchange and skips an empty reply", () => {
+    expect(buildChatTitlePrompt("How do I bake bread?", "Start with flour.")).toBe(
+      "[User]\nHow do I bake bread?\n\n[Assistant]\nStart with flour.\n\nTitle:"
+    );
+    expect(buildChatTitlePrompt("Hi", "  ")).toBe("[User]\nHi\n\nTitle:");
+  });
+});
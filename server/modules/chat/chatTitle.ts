@@ -0,0 +1,29 @@
+export const CHAT_TITLE_SYSTEM_PROMPT = [
+  "You name chat conversations.",
+  "Reply with a title of at most six words that captures the topic of the exchange.",
+  "No quotes, no trailing punctuation, no explanations."
+].join(" ");
+
+const MAX_EXCHANGE_CHARS = 1500;
+const MAX_TITLE_CHARS = 80;
+
+export function buildChatTitlePrompt(userText: string, assistantText: string): string {
+  return [
+    `[User]\n${userText.trim().slice(0, MAX_EXCHANGE_CHARS)}`,
+    assistantText.trim() ? `[Assistant]\n${assistantText.trim().slice(0, MAX_EXCHANGE_CHARS)}` : "",
+    "Title:"
+  ].filter(Boolean).join("\n\n");
+}
+
+/** Keeps the first non-empty line of a model reply and strips labels, quotes and markdown. */
+export function normalizeGeneratedChatTitle(raw: string): string {
+  const withoutReasoning = String(raw || "").replace(/<think>[\s\S]*?<\/think>/gi, "");
+  const line = withoutReasoning.split(/\r?\n/).map((item) => item.trim()).find(Boolean) || "";
+  return line
+    .replace(/^(?:#+\s*)?(?:\*\*)?title(?:\*\*)?\s*:\s*/i, "")
+    .replace(/^[\s"'`*_«“„]+|[\s"'`*_»”]+$/g, "")
+    .replace(/[.!。]+$/u, "")
+    .trim()
+    .slice(0, MAX_TITLE_CHARS)
+    .trim();
+}
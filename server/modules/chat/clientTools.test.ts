@@ -0,0 +1,54 @@
+import { describe, expect, it } from "vitest";
+import {
+  appendClientToolExchange,
+  buildClientToolExchange,
+  normalizeClientTools,
+  parseStoredClientToolCall
+} from "./clientTools.js";
+import { serializeToolTrace } from "./tooling.js";
+
+describe("normalizeClientTools", () => {
+  it("accepts wrapped and bare function definitions and fills in an empty schema", () => {
+    expect(normalizeClientTools([
+      { type: "function", function: { name: "roll_dice", description: "Roll", parameters: { type: "object", properties: { sides: { type: "number" } } } } },
+      { name: "check-inventory" }
+    ])).toEqual([
+      { type: "function", function: { name: "roll_dice", description: "Roll", parameters: { type: "object", properties: { sides: { type: "number" } } } } },
+      { type: "function", function: { name: "check-inventory", description: "", parameters: { type: "object", properties: {} } } }
+    ]);
+    expect(normalizeClientTools(undefined)).toEqual([]);
+  });
+
+  it("rejects malformed names, duplicates and non-object schemas", () => {
+    expect(() => normalizeClientTools({ name: "x" })).toThrow("array");
+    expect(() => normalizeClientTools([{ name: "has space" }])).toThrow("tools[0]");
+    expect(() => normalizeClientTools([{ name: "a" }, { name: "a" }])).toThrow("Duplicate");
+    expect(() => normalizeClientTools([{ name: "a", parameters: [] }])).toThrow("parameters");
+    expect(() => normalizeClientTools([{ type: "retrieval", function: { name: "a" } }])).toThrow("type");
+  });
+});
+
+describe("client tool exchange", () => {
+  it("round-trips pending and answered calls through the stored tool trace", () => {
+    const pending = serializeToolTrace({ callId: "c1", name: "roll_dice", args: "{\"sides\":6}", result: "", client: true, pending: true });
+    expect(parseStoredClientToolCall(pending)).toEqual({ callId: "c1", name: "roll_dice", args: "{\"sides\":6}", result: "", pending: true });
+    const answered = serializeToolTrace({ callId: "c1", name: "roll_dice", args: "{\"sides\":6}", result: "4", client: true, pending: false });
+    expect(parseStoredClientToolCall(answered)?.result).toBe("4");
+    expect(parseStoredClientToolCall(serializeToolTrace({ callId: "m1", name: "search", args: "{}", result: "ok" }))).toBeNull();
+  });
+
+  it("replaces the trailing assistant turn with its tool calls and answers", () => {
+    const exchange = buildClientToolExchange("Rolling.", [{ callId: "c1", name: "roll_dice", args: "", result: "4", pending: false }]);
+    const messages = appendClientToolExchange([
+      { role: "system", content: "sys" },
+      { role: "user", content: "roll" },
+      { role: "assistant", content: "Rolling." }
+    ], exchange);
+    expect(messages).toEqual([
+      { role: "system", content: "sys" },
+      { role: "user", content: "roll" },
+      { role: "assistant", content: "Rolling.", tool_calls: [{ id: "c1", type: "function", function: { name: "roll_dice", arguments: "{}" } }] },
+      { role: "tool", tool_call_id: "c1", content: "4" }
+    ]);
+  });
+});
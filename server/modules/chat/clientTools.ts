@@ -0,0 +1,115 @@
+import type { OpenAICompletionMessage } from "./tooling.js";
+
+// Tools defined by the client for a single send (dice rolls, inventory lookups). Unlike MCP tools the server
+// never runs them: the model's calls are persisted as pending tool messages and streamed to the client, which
+// answers each one through the tool-results endpoint before the turn continues.
+
+export interface ClientToolDefinition {
+  type: "function";
+  function: {
+    name: string;
+    description: string;
+    parameters: Record<string, unknown>;
+  };
+}
+
+export interface StoredClientToolCall {
+  callId: string;
+  name: string;
+  args: string;
+  result: string;
+  pending: boolean;
+}
+
+const MAX_CLIENT_TOOLS = 32;
+const TOOL_NAME_PATTERN = /^[A-Za-z0-9_-]{1,64}$/;
+
+/**
+ * Validates OpenAI-style function tools from a request body. Accepts `{ type: "function", function: {...} }`
+ * or the bare function object; throws with a user-facing message on anything else.
+ */
+export function normalizeClientTools(raw: unknown): ClientToolDefinition[] {
+  if (raw === undefined || raw === null) return [];
+  if (!Array.isArray(raw)) throw new Error("tools must be an array of function definitions");
+  if (raw.length > MAX_CLIENT_TOOLS) throw new Error(`At most ${MAX_CLIENT_TOOLS} tools are allowed`);
+  const seen = new Set<string>();
+  return raw.map((item, index) => {
+    const wrapper = item && typeof item === "object" ? item as { type?: unknown; function?: unknown } : null;
+    const fn = wrapper?.function && typeof wrapper.function === "object" ? wrapper.function : wrapper;
+    const row = fn as { name?: unknown; description?: unknown; parameters?: unknown } | null;
+    if (wrapper?.type !== undefined && wrapper.type !== "function") {
+      throw new Error(`tools[${index}] must have type "function"`);
+    }
+    const name = String(row?.name ?? "").trim();
+    if (!TOOL_NAME_PATTERN.test(name)) {
+      throw new Error(`tools[${index}] needs a name of letters, digits, "_" or "-" (max 64)`);
+    }
+    if (seen.has(name)) throw new Error(`Duplicate tool name: ${name}`);
+    seen.add(name);
+    const parameters = row?.parameters ?? { type: "object", properties: {} };
+    if (!parameters || typeof parameters !== "object" || Array.isArray(parameters)) {
+      throw new Error(`tools[${index}].parameters must be a JSON schema object`);
+    }
+    return {
+      type: "function" as const,
+      function: {
+        name,
+        description: String(row?.description ?? ""),
+        parameters: parameters as Record<string, unknown>
+      }
+    };
+  });
+}
+
+/** Reads a tool message written for a client tool call; MCP and reasoning traces return null. */
+export function parseStoredClientToolCall(content: string): StoredClientToolCall | null {
+  try {
+    const parsed = JSON.parse(content) as Record<string, unknown>;
+    if (parsed?.kind !== "tool_call" || parsed.client !== true) return null;
+    return {
+      callId: String(parsed.callId || ""),
+      name: String(parsed.name || ""),
+      args: String(parsed.args || ""),
+      result: parsed.pending === true ? "" : String(parsed.result ?? ""),
+      pending: parsed.pending === true
+    };
+  } catch {
+    return null;
+  }
+}
+
+/** The assistant tool-call message and the tool answers, in the shape OpenAI-compatible APIs expect. */
+export function buildClientToolExchange(assistantContent: string, calls: StoredClientToolCall[]): OpenAICompletionMessage[] {
+  return [
+    {
+      role: "assistant",
+      content: assistantContent,
+      tool_calls: calls.map((call) => ({
+        id: call.callId,
+        type: "function",
+        function: { name: call.name, arguments: call.args || "{}" }
+      }))
+    },
+    ...calls.map((call) => ({
+      role: "tool" as const,
+      tool_call_id: call.callId,
+      content: call.result
+    }))
+  ];
+}
+
+/**
+ * Swaps the tool-calling assistant turn at the end of the prompt history for its full tool exchange, so the
+ * model sees its own calls followed by the client's answers.
+ */
+export function appendClientToolExchange(
+  messages: Array<{ role: string; content: unknown }>,
+  exchange: OpenAICompletionMessage[]
+): Array<{ role: string; content: unknown }> {
+  const roles = messages.map((message) => message.role);
+  const lastAssistant = roles.lastIndexOf("assistant");
+  const trimmed = lastAssistant > roles.lastIndexOf("user")
+    ? messages.filter((_, index) => index !== lastAssistant)
+    : messages;
+  return [...trimmed, ...exchange];
+}
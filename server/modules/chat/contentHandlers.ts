@@ -1,10 +1,12 @@
 import type { Request, Response } from "express";
-import { db, isLocalhostUrl } from "../../db.js";
+import { db, isLocalhostUrl, newId, now } from "../../db.js";
+import { AppError, notConfiguredError, notFoundError, sendAppError, validationError } from "../../services/appError.js";
 import { synthesizeCustomAdapterSpeech } from "../../services/customProviderAdapters.js";
 import { LOCAL_INFERENCE_URL, synthesizeLocalPiper } from "../../services/localInference.js";
-import { completeProviderOnce, normalizeOpenAiBaseUrl } from "./providerExecution.js";
+import { normalizeOpenAiBaseUrl } from "../../services/providerApi.js";
+import { completeLlm, LlmUnavailableError } from "../llm/completion.js";
 import { buildReasoningAwareTimeline } from "./reasoningContext.js";
-import { getSettings, getTimeline, resolveBranch, type MessageRow, type ProviderRow } from "./routeHelpers.js";
+import { getSettings, getTimeline, resolveBranch, type MessageRow } from "./routeHelpers.js";
 import { splitRealtimeTtsInput } from "./ttsRealtime.js";
 import { streamOpenAiCompatibleTts } from "./ttsUpstreamStream.js";
 
@@ -31,35 +33,49 @@ async function withServerTimeout<T>(
   }
 }
 
+// An empty summary never replaces the stored one, so a failed or pointless compress cannot erase it.
+function persistChatSummary(chatId: string, summary: string) {
+  if (!summary.trim()) return;
+  db.transaction(() => {
+    db.prepare("UPDATE chats SET context_summary = ? WHERE id = ?").run(summary, chatId);
+    db.prepare("INSERT INTO rp_memory_entries (id, chat_id, role, content, created_at) VALUES (?, ?, 'summary', ?, ?)")
+      .run(newId(), chatId, summary, now());
+  })();
+}
+
 export async function compressChat(req: Request, res: Response) {
   const chatId = String(req.params.id || "");
   const { branchId: reqBranchId } = req.body ?? {};
   const branchId = resolveBranch(chatId, reqBranchId);
+  const keepRecent = Math.max(0, Math.min(200, Math.floor(Number(req.body?.keepRecent) || 0)));
 
   const settings = getSettings();
   const providerId = settings.compressProviderId || settings.activeProviderId;
   const modelId = settings.compressModel || settings.activeModel;
-  const timeline = buildReasoningAwareTimeline(
+  const fullTimeline = buildReasoningAwareTimeline(
     getTimeline(chatId, branchId),
     settings.includeReasoningInContext !== false
   );
+  // The newest `keepRecent` messages stay verbatim in the prompt tail, so only older turns are condensed.
+  const timeline = keepRecent > 0 ? fullTimeline.slice(0, Math.max(0, fullTimeline.length - keepRecent)) : fullTimeline;
 
-  if (!providerId || !modelId || timeline.length === 0) {
+  if (timeline.length === 0) {
+    // Everything is inside the kept tail, so there is nothing to condense and the stored summary stays.
+    const stored = db.prepare("SELECT context_summary FROM chats WHERE id = ?").get(chatId) as { context_summary: string | null } | undefined;
+    res.json({ summary: stored?.context_summary || "" });
+    return;
+  }
+
+  if (!providerId || !modelId) {
     const summary = timeline.slice(-settings.compressionFallbackMessages).map((message) => {
       const reasoning = message.reasoningContent ? ` | reasoning: ${message.reasoningContent.split("\n")[0].slice(0, 80)}` : "";
       return `${message.role}: ${message.content.split("\n")[0].slice(0, 80)}${reasoning}`;
     }).join("\n");
-    db.prepare("UPDATE chats SET context_summary = ? WHERE id = ?").run(summary, chatId);
+    persistChatSummary(chatId, summary);
     res.json({ summary });
     return;
   }
 
-  const provider = db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as ProviderRow | undefined;
-  if (!provider) {
-    res.json({ summary: "" });
-    return;
-  }
-
   const messagesToSummarize = timeline.map((message) => {
     const reasoning = message.reasoningContent ? `\n[assistant reasoning]: ${message.reasoningContent}` : "";
     return `[${message.role}]: ${message.content}${reasoning}`;
@@ -68,21 +84,26 @@ export async function compressChat(req: Request, res: Response) {
     || "Summarize the following roleplay conversation. Preserve key plot points, character details, relationships, and important events. Be concise but thorough.";
 
   try {
-    const summary = await completeProviderOnce({
-      provider,
+    const summary = await completeLlm([
+      { role: "system", content: compressTemplate },
+      { role: "user", content: messagesToSummarize }
+    ], {
+      providerId,
       modelId,
-      systemPrompt: compressTemplate,
-      userPrompt: messagesToSummarize,
       samplerConfig: {
         temperature: settings.compressionTemperature,
         maxTokens: settings.compressionMaxTokens
-      },
-      apiParamPolicy: settings.apiParamPolicy
+      }
     });
 
-    db.prepare("UPDATE chats SET context_summary = ? WHERE id = ?").run(summary, chatId);
+    persistChatSummary(chatId, summary);
     res.json({ summary });
-  } catch {
+  } catch (error) {
+    // A blocked provider is a settings problem the user can fix; a failed call leaves the stored summary as is.
+    if (error instanceof LlmUnavailableError && error.reason !== "provider_not_found") {
+      sendAppError(res, error);
+      return;
+    }
     res.json({ summary: "" });
   }
 }
@@ -93,7 +114,7 @@ export async function translateMessage(req: Request, res: Response) {
 
   const message = db.prepare("SELECT * FROM messages WHERE id = ?").get(messageId) as MessageRow | undefined;
   if (!message) {
-    res.status(404).json({ error: "Message not found" });
+    sendAppError(res, notFoundError("Message not found"));
     return;
   }
 
@@ -109,38 +130,28 @@ export async function translateMessage(req: Request, res: Response) {
     return;
   }
 
-  const provider = db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as ProviderRow | undefined;
-  if (!provider) {
-    res.json({ translation: message.content });
-    return;
-  }
-  if (settings.fullLocalMode && !isLocalhostUrl(provider.base_url)) {
-    res.json({ translation: message.content });
-    return;
-  }
-  if (provider.full_local_only && !isLocalhostUrl(provider.base_url)) {
-    res.json({ translation: message.content });
-    return;
-  }
-
   const language = targetLanguage || settings.translateLanguage || settings.responseLanguage || "English";
 
   try {
-    const translation = await withServerTimeout(settings.translationTimeoutSeconds * 1000, (signal) => completeProviderOnce({
-      provider,
+    const translation = await withServerTimeout(settings.translationTimeoutSeconds * 1000, (signal) => completeLlm([
+      {
+        role: "system",
+        content: `Translate the following message to ${language}. Output ONLY the translation, nothing else. Preserve formatting, line breaks, and markdown.`
+      },
+      { role: "user", content: message.content }
+    ], {
+      providerId,
       modelId,
-      systemPrompt: `Translate the following message to ${language}. Output ONLY the translation, nothing else. Preserve formatting, line breaks, and markdown.`,
-      userPrompt: message.content,
       samplerConfig: {
         temperature: settings.translationTemperature,
         maxTokens: settings.translationMaxTokens
       },
-      apiParamPolicy: settings.apiParamPolicy,
       signal
     }));
-    res.json({ translation: String(translation || "").trim() || message.content });
+    res.json({ translation: translation || message.content });
   } catch (error) {
-    if (!isAbortLikeError(error)) {
+    // An unavailable or blocked provider shows the original text, as a failed translation does.
+    if (!isAbortLikeError(error) && !(error instanceof LlmUnavailableError)) {
       console.warn("Message translation failed", error);
     }
     res.json({ translation: message.content });
@@ -151,7 +162,7 @@ export async function ttsMessage(req: Request, res: Response) {
   const messageId = req.params.id;
   const message = db.prepare("SELECT * FROM messages WHERE id = ?").get(messageId) as MessageRow | undefined;
   if (!message) {
-    res.status(404).json({ error: "Message not found" });
+    sendAppError(res, notFoundError("Message not found"));
     return;
   }
 
@@ -162,7 +173,7 @@ export async function ttsMessageRealtime(req: Request, res: Response) {
   const messageId = req.params.id;
   const message = db.prepare("SELECT * FROM messages WHERE id = ?").get(messageId) as MessageRow | undefined;
   if (!message) {
-    res.status(404).json({ error: "Message not found" });
+    sendAppError(res, notFoundError("Message not found"));
     return;
   }
   await streamTtsText(String(message.content || ""), req, res);
@@ -171,7 +182,7 @@ export async function ttsMessageRealtime(req: Request, res: Response) {
 export async function ttsText(req: Request, res: Response) {
   const input = String(req.body?.input || "").trim().slice(0, 4000);
   if (!input) {
-    res.status(400).json({ error: "TTS input is empty" });
+    sendAppError(res, validationError("TTS input is empty"));
     return;
   }
   await synthesizeTtsText(input, res);
@@ -180,7 +191,7 @@ export async function ttsText(req: Request, res: Response) {
 export async function ttsTextRealtime(req: Request, res: Response) {
   const input = String(req.body?.input || "").trim().slice(0, 4000);
   if (!input) {
-    res.status(400).json({ error: "TTS input is empty" });
+    sendAppError(res, validationError("TTS input is empty"));
     return;
   }
   await streamTtsText(input, req, res);
@@ -196,11 +207,11 @@ async function streamTtsText(input: string, req: Request, res: Response) {
   const model = String(settings.ttsModel || "").trim();
   const voice = String(settings.ttsVoice || "alloy").trim() || "alloy";
   if (!baseUrl || !model) {
-    res.status(400).json({ error: "TTS endpoint/model not configured" });
+    sendAppError(res, notConfiguredError("TTS endpoint/model not configured"));
     return;
   }
   if (settings.fullLocalMode && !isLocalPiper && !isLocalhostUrl(baseUrl)) {
-    res.status(403).json({ error: "TTS endpoint blocked by Full Local Mode" });
+    sendAppError(res, notConfiguredError("TTS endpoint blocked by Full Local Mode", 403));
     return;
   }
 
@@ -268,9 +279,7 @@ async function synthesizeTtsText(input: string, res: Response) {
     res.setHeader("Cache-Control", "no-store");
     res.send(audio.buffer);
   } catch (err) {
-    const message = err instanceof Error ? err.message : "TTS request failed";
-    const status = /not configured/i.test(message) ? 400 : /blocked by Full Local Mode/i.test(message) ? 403 : 500;
-    res.status(status).json({ error: message });
+    sendAppError(res, err, "TTS request failed");
   }
 }
 
@@ -284,11 +293,11 @@ async function synthesizeTtsAudio(input: string, signal?: AbortSignal): Promise<
   const model = String(settings.ttsModel || "").trim();
   const voice = String(settings.ttsVoice || "alloy").trim() || "alloy";
   if (!baseUrl || !model) {
-    throw new Error("TTS endpoint/model not configured");
+    throw notConfiguredError("TTS endpoint/model not configured");
   }
 
   if (settings.fullLocalMode && !isLocalPiper && !isLocalhostUrl(baseUrl)) {
-    throw new Error("TTS endpoint blocked by Full Local Mode");
+    throw notConfiguredError("TTS endpoint blocked by Full Local Mode", 403);
   }
 
   if (isLocalPiper) {
@@ -319,7 +328,7 @@ async function synthesizeTtsAudio(input: string, signal?: AbortSignal): Promise<
   });
   if (!response.ok) {
     const details = await response.text().catch(() => "");
-    throw new Error(`TTS failed: ${details.slice(0, 500) || response.statusText}`);
+    throw new AppError("provider", `TTS failed: ${details.slice(0, 500) || response.statusText}`, 502, response.status);
   }
   return {
     contentType: response.headers.get("content-type") || "audio/mpeg",
@@ -0,0 +1,21 @@
+import { describe, expect, it } from "vitest";
+import { joinContinuation } from "./continuation.js";
+
+describe("reply continuation", () => {
+  it("resumes mid-word and after a trailing space without adding whitespace", () => {
+    expect(joinContinuation("The lantern flick", "ered and went out.")).toBe("The lantern flickered and went out.");
+    expect(joinContinuation("She reached for the ", "door.")).toBe("She reached for the door.");
+    expect(joinContinuation("She paused.", " Then she ran.")).toBe("She paused. Then she ran.");
+  });
+
+  it("separates a finished sentence from a new one", () => {
+    expect(joinContinuation("She paused.", "Then she ran.")).toBe("She paused. Then she ran.");
+    expect(joinContinuation("*waves*", "\"Hello there.\"")).toBe("*waves* \"Hello there.\"");
+    expect(joinContinuation("Wait", ", what?")).toBe("Wait, what?");
+  });
+
+  it("keeps the reply when the continuation is empty", () => {
+    expect(joinContinuation("Done.", "")).toBe("Done.");
+    expect(joinContinuation("", "  Fresh start")).toBe("Fresh start");
+  });
+});
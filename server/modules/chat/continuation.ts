@@ -0,0 +1,22 @@
+// "Continue" for a reply that stopped early (max tokens, a dropped stream): the model gets the history
+// ending with that reply plus an instruction to pick up where it stopped, and the new text is appended to
+// the same message rather than saved as another turn.
+
+export const CONTINUE_INSTRUCTION = "[Continue] Your previous reply was cut off. Continue it from exactly where it stopped, "
+  + "without repeating any of it and without a preamble.";
+
+const SENTENCE_END_PATTERN = /[.!?…"”»*)\]]$/;
+const WORD_START_PATTERN = /^["“«*(\p{L}\p{N}]/u;
+
+/**
+ * Joins the stored reply and its continuation. Text is concatenated as-is, since models usually resume
+ * mid-word or with their own leading space; a space is added only between a finished sentence and a new word.
+ */
+export function joinContinuation(existing: string, continuation: string): string {
+  if (!continuation) return existing;
+  if (!existing) return continuation.trimStart();
+  if (/\s$/.test(existing) || /^\s/.test(continuation)) return existing + continuation;
+  return SENTENCE_END_PATTERN.test(existing) && WORD_START_PATTERN.test(continuation)
+    ? `${existing} ${continuation}`
+    : existing + continuation;
+}
@@ -1,5 +1,5 @@
 import { db, now } from "../../db.js";
-import { getTimeline, messageToJson, type MessageRow } from "./routeHelpers.js";
+import { getTimeline, messageToJson, resolveBranch, type MessageRow } from "./routeHelpers.js";
 import { listBranches } from "./repository.js";
 import { resolveLorebookIds } from "./attachments.js";
 
@@ -213,3 +213,91 @@ export function exportChatJson(chatId: string, activeBranchId?: string) {
     } : null
   };
 }
+
+export function chatExportFileBase(title: string): string {
+  return String(title || "chat")
+    .replace(/[\\/:*?"<>|]+/g, "-")
+    .replace(/\s+/g, " ")
+    .trim()
+    .slice(0, 80) || "chat";
+}
+
+function quoteMarkdown(text: string): string {
+  return text.split("\n").map((line) => (line ? `> ${line}` : ">")).join("\n");
+}
+
+/**
+ * Renders one branch as a readable transcript: a heading per message with the speaker, role and time,
+ * preceded by the scene state, author's note and context summary when the chat has them.
+ * Tool and reasoning records are internal and left out.
+ */
+export function exportChatMarkdown(chatId: string, branchId?: string) {
+  const payload = exportChatJson(chatId, branchId);
+  if (!payload) return null;
+  const authorNote = (db.prepare("SELECT author_note FROM chats WHERE id = ?").get(chatId) as { author_note: string | null } | undefined)
+    ?.author_note?.trim() || "";
+
+  const lines: string[] = [`# ${payload.chat.title || "Chat"}`, ""];
+  lines.push(`_Exported ${payload.exportedAt}${payload.conversation.branchName ? ` · Branch: ${payload.conversation.branchName}` : ""}_`, "");
+
+  const scene = payload.sceneState?.payload;
+  const annotations: string[] = [];
+  if (scene) {
+    const intensity = Number(scene.intensity);
+    const sceneParts = [
+      typeof scene.mood === "string" && scene.mood ? `mood ${scene.mood}` : "",
+      typeof scene.pacing === "string" && scene.pacing ? `pacing ${scene.pacing}` : "",
+      Number.isFinite(intensity) ? `intensity ${Math.round(intensity * 100)}%` : ""
+    ].filter(Boolean);
+    if (sceneParts.length > 0) annotations.push(`**Scene:** ${sceneParts.join(" · ")}`);
+    const variables = scene.variables && typeof scene.variables === "object" ? Object.entries(scene.variables as Record<string, unknown>) : [];
+    if (variables.length > 0) annotations.push(`**Scene variables:** ${variables.map(([key, value]) => `${key}: ${String(value)}`).join(", ")}`);
+  }
+  if (authorNote) annotations.push(`**Author's note:** ${authorNote}`);
+  if (payload.chat.contextSummary.trim()) annotations.push(`**Summary so far:** ${payload.chat.contextSummary.trim()}`);
+  if (annotations.length > 0) lines.push(quoteMarkdown(annotations.join("\n\n")), "");
+  lines.push("---", "");
+
+  for (const message of payload.conversation.messages) {
+    if (message.role === "tool") continue;
+    lines.push(`### ${message.speakerName} · ${message.role} · ${message.createdAt}`, "", message.content.trim(), "");
+  }
+
+  return {
+    markdown: `${lines.join("\n").trimEnd()}\n`,
+    filenameBase: chatExportFileBase(payload.chat.title),
+    branchId: payload.conversation.branchId
+  };
+}
+
+export type ChatDatasetFormat = "conversation" | "turns";
+
+/**
+ * OpenAI-style `{ role, content }` messages for dataset building. Only visible user and assistant turns are
+ * kept; deleted messages never reach the timeline, and tool or reasoning records are scaffolding.
+ * "conversation" is one JSON array for the branch; "turns" is JSONL with one `{ messages }` line per
+ * assistant reply, carrying the history up to and including it.
+ */
+export function exportChatDataset(chatId: string, format: ChatDatasetFormat, branchId?: string) {
+  const chat = db.prepare("SELECT title FROM chats WHERE id = ?").get(chatId) as { title: string } | undefined;
+  if (!chat) return null;
+  const resolvedBranchId = listBranches(chatId).some((branch) => branch.id === branchId) ? branchId! : resolveBranch(chatId);
+  const messages = getTimeline(chatId, resolvedBranchId)
+    .filter((message) => (message.role === "user" || message.role === "assistant") && message.content.trim())
+    .map((message) => ({ role: message.role, content: message.content }));
+
+  const content = format === "conversation"
+    ? `${JSON.stringify(messages, null, 2)}\n`
+    : messages
+      .map((message, index) => (message.role === "assistant" ? JSON.stringify({ messages: messages.slice(0, index + 1) }) : ""))
+      .filter(Boolean)
+      .map((line) => `${line}\n`)
+      .join("");
+
+  return {
+    content,
+    extension: format === "conversation" ? "json" : "jsonl",
+    filenameBase: chatExportFileBase(chat.title),
+    branchId: resolvedBranchId
+  };
+}
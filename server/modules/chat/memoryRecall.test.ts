@@ -0,0 +1,42 @@
+import { describe, expect, it } from "vitest";
+import { rankMemoryEntries, type MemoryEntry } from "./memoryRecall.js";
+
+const wordCount = (text: string) => text.split(/\s+/).filter(Boolean).length;
+
+function entry(id: string, content: string, minute: number): MemoryEntry {
+  return { id, role: "summary", content, createdAt: `2026-01-01T00:${String(minute).padStart(2, "0")}:00.000Z` };
+}
+
+describe("rankMemoryEntries", () => {
+  const entries = [
+    entry("lighthouse", "Mara found the lighthouse key under the stairs", 1),
+    entry("storm", "A storm cut the island off for a week", 2),
+    entry("ferry", "The ferry captain owes Mara a favour", 3)
+  ];
+
+  it("keeps entries sharing words with the query and returns them oldest first", () => {
+    expect(rankMemoryEntries(entries, "Where did Mara put the lighthouse key?", wordCount).map((item) => item.id))
+      .toEqual(["lighthouse", "ferry"]);
+  });
+
+  it("drops lower-scoring entries that would overflow the token budget", () => {
+    expect(rankMemoryEntries(entries, "Mara and the lighthouse key", wordCount, 9).map((item) => item.id))
+      .toEqual(["lighthouse"]);
+  });
+
+  it("falls back to recency when the query has no keywords", () => {
+    expect(rankMemoryEntries(entries, "ok", wordCount, 18).map((item) => item.id)).toEqual(["storm", "ferry"]);
+  });
+
+  it("ranks by embedding similarity when given, dropping entries below the threshold", () => {
+    const similarities = new Map([["lighthouse", 0.2], ["storm", 0.8], ["ferry", 0.5]]);
+    expect(rankMemoryEntries(entries, "Mara and the lighthouse key", wordCount, 600, similarities).map((item) => item.id))
+      .toEqual(["storm", "ferry"]);
+  });
+
+  it("lets recency break near-ties between similar memories", () => {
+    const similarities = new Map([["lighthouse", 0.62], ["storm", 0.1], ["ferry", 0.6]]);
+    expect(rankMemoryEntries(entries, "anything", wordCount, 9, similarities).map((item) => item.id))
+      .toEqual(["ferry"]);
+  });
+});
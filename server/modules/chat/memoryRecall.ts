@@ -0,0 +1,171 @@
+import { createHash } from "crypto";
+import { db, now } from "../../db.js";
+import { cosineSimilarity, decodeVector, embed, encodeVector } from "../../services/embeddings.js";
+
+// Long-term memory recall from rp_memory_entries. Compression appends every summary it writes, but only the
+// latest one rides along as the context summary; older summaries are pulled back in here when they are
+// relevant to the current turn, with a small bonus for recency so ties go to newer memories. Relevance is
+// cosine similarity when an embedding model is configured and shared keywords otherwise.
+
+export interface MemoryEntry {
+  id: string;
+  role: string;
+  content: string;
+  createdAt: string;
+}
+
+export const MEMORY_TOKEN_BUDGET = 600;
+const MAX_RECALLED_MEMORIES = 5;
+const RECENCY_WEIGHT = 0.5;
+// Similarity is on a 0..1 scale, so recency only breaks near-ties there.
+const SEMANTIC_RECENCY_WEIGHT = 0.05;
+const MIN_SIMILARITY = 0.3;
+
+function memoryKeywords(text: string): Set<string> {
+  return new Set(String(text || "").toLowerCase().match(/[\p{L}\p{N}]{4,}/gu) ?? []);
+}
+
+/**
+ * Scores each entry by how many query keywords it shares plus up to 0.5 for being recent, then keeps the
+ * best-scoring entries that fit the token budget. Entries without any overlap are only used as recency fill
+ * when the query has no keywords at all. When `similarities` (entry id -> cosine similarity to the query)
+ * is given, it replaces keyword overlap: entries below 0.3 are dropped and recency is worth at most 0.05.
+ * The result is returned oldest first.
+ */
+export function rankMemoryEntries(
+  entries: MemoryEntry[],
+  queryText: string,
+  countTokens: (text: string) => number,
+  tokenBudget = MEMORY_TOKEN_BUDGET,
+  similarities?: Map<string, number>
+): MemoryEntry[] {
+  const query = memoryKeywords(queryText);
+  const ordered = [...entries].sort((a, b) => a.createdAt.localeCompare(b.createdAt));
+  const recencyOf = (index: number, weight: number) =>
+    ordered.length > 1 ? (index / (ordered.length - 1)) * weight : weight;
+  const candidates = similarities
+    ? ordered
+      .map((entry, index) => {
+        const similarity = similarities.get(entry.id) ?? 0;
+        return { entry, similarity, score: similarity + recencyOf(index, SEMANTIC_RECENCY_WEIGHT) };
+      })
+      .filter((item) => item.similarity >= MIN_SIMILARITY)
+      .sort((a, b) => b.score - a.score)
+    : ordered
+      .map((entry, index) => {
+        const words = memoryKeywords(entry.content);
+        let overlap = 0;
+        for (const word of query) if (words.has(word)) overlap += 1;
+        return { entry, overlap, score: overlap + recencyOf(index, RECENCY_WEIGHT) };
+      })
+      .filter((item) => query.size === 0 || item.overlap > 0)
+      .sort((a, b) => b.score - a.score);
+
+  const picked = new Set<string>();
+  let used = 0;
+  for (const { entry } of candidates) {
+    if (picked.size >= MAX_RECALLED_MEMORIES) break;
+    const cost = countTokens(entry.content);
+    if (used + cost > tokenBudget) continue;
+    picked.add(entry.id);
+    used += cost;
+  }
+  return ordered.filter((entry) => picked.has(entry.id));
+}
+
+export function listChatMemories(chatId: string): MemoryEntry[] {
+  return (db.prepare(
+    "SELECT id, role, content, created_at FROM rp_memory_entries WHERE chat_id = ? ORDER BY created_at ASC, id ASC"
+  ).all(chatId) as Array<{ id: string; role: string; content: string; created_at: string }>)
+    .map((row) => ({ id: row.id, role: row.role, content: row.content, createdAt: row.created_at }));
+}
+
+function contentHash(content: string): string {
+  return createHash("sha256").update(content).digest("hex");
+}
+
+/**
+ * Cosine similarity of each entry to `queryText`, or null when no embedding model is configured. Entry vectors
+ * are cached in memory_embeddings and recomputed when the entry text or the embedding model changes.
+ */
+export async function memorySimilarities(
+  entries: MemoryEntry[],
+  queryText: string,
+  settings: Record<string, unknown>
+): Promise<Map<string, number> | null> {
+  const queryEmbedding = await embed([queryText], settings);
+  if (!queryEmbedding) return null;
+  const query = encodeVector(queryEmbedding.vectors[0]);
+  const queryVector = decodeVector(query.blob);
+
+  const cached = new Map<string, { model_key: string; content_hash: string; vector_blob: Buffer; norm: number }>();
+  const selectCached = db.prepare("SELECT model_key, content_hash, vector_blob, norm FROM memory_embeddings WHERE entry_id = ?");
+  for (const entry of entries) {
+    const row = selectCached.get(entry.id) as { model_key: string; content_hash: string; vector_blob: Buffer; norm: number } | undefined;
+    if (row) cached.set(entry.id, row);
+  }
+  const stale = entries.filter((entry) => {
+    const row = cached.get(entry.id);
+    return !row || row.model_key !== queryEmbedding.modelKey || row.content_hash !== contentHash(entry.content);
+  });
+  if (stale.length > 0) {
+    const fresh = await embed(stale.map((entry) => entry.content), settings);
+    if (!fresh) return null;
+    const upsert = db.prepare(`
+      INSERT INTO memory_embeddings (entry_id, model_key, content_hash, vector_blob, norm, created_at)
+      VALUES (?, ?, ?, ?, ?, ?)
+      ON CONFLICT(entry_id) DO UPDATE SET
+        model_key = excluded.model_key,
+        content_hash = excluded.content_hash,
+        vector_blob = excluded.vector_blob,
+        norm = excluded.norm,
+        created_at = excluded.created_at
+    `);
+    const ts = now();
+    db.transaction(() => {
+      stale.forEach((entry, index) => {
+        const encoded = encodeVector(fresh.vectors[index]);
+        const hash = contentHash(entry.content);
+        upsert.run(entry.id, fresh.modelKey, hash, encoded.blob, encoded.norm, ts);
+        cached.set(entry.id, { model_key: fresh.modelKey, content_hash: hash, vector_blob: encoded.blob, norm: encoded.norm });
+      });
+    })();
+  }
+
+  const similarities = new Map<string, number>();
+  for (const entry of entries) {
+    const row = cached.get(entry.id);
+    if (!row) continue;
+    similarities.set(entry.id, cosineSimilarity(queryVector, decodeVector(row.vector_blob), query.norm, row.norm));
+  }
+  return similarities;
+}
+
+/**
+ * Prompt section with the memories most relevant to `queryText`. Author notes are injected on their own and
+ * the current context summary is already in the prompt, so both are skipped. Falls back to keyword ranking
+ * when no embedding model is configured or the embedding request fails.
+ */
+export async function buildMemoryRecallDirective(
+  chatId: string,
+  queryText: string,
+  currentSummary: string,
+  countTokens: (text: string) => number,
+  settings: Record<string, unknown>
+): Promise<string> {
+  const summary = currentSummary.trim();
+  const entries = listChatMemories(chatId)
+    .filter((entry) => entry.role !== "author_note" && entry.content.trim() && entry.content.trim() !== summary);
+  if (entries.length === 0) return "";
+  let similarities: Map<string, number> | null = null;
+  if (queryText.trim()) {
+    try {
+      similarities = await memorySimilarities(entries, queryText, settings);
+    } catch {
+      similarities = null;
+    }
+  }
+  const recalled = rankMemoryEntries(entries, queryText, countTokens, MEMORY_TOKEN_BUDGET, similarities ?? undefined);
+  if (recalled.length === 0) return "";
+  return `[Long-term Memory]\nEarlier events in this chat that may matter now:\n${recalled.map((entry) => `- ${entry.content.trim()}`).join("\n")}`;
+}
@@ -0,0 +1,19 @@
+import { describe, expect, it } from "vitest";
+import { buildMessageFtsQuery, buildSearchSnippet, messageSearchTerms } from "./messageSearch.js";
+
+describe("message search helpers", () => {
+  it("quotes terms so FTS operators in user input are treated as text", () => {
+    expect(messageSearchTerms("The \"Dragon\" OR tower*")).toEqual(["the", "dragon", "or", "tower"]);
+    expect(buildMessageFtsQuery("dragon's  tower")).toBe("\"dragon\"* \"s\"* \"tower\"*");
+    expect(buildMessageFtsQuery("  ***  ")).toBe("");
+  });
+
+  it("cuts a snippet around the first matching term", () => {
+    const content = `${"a ".repeat(100)}the dragon sleeps under the tower ${"b ".repeat(100)}`;
+    const snippet = buildSearchSnippet(content, ["tower", "dragon"]);
+    expect(snippet.startsWith("…")).toBe(true);
+    expect(snippet.endsWith("…")).toBe(true);
+    expect(snippet).toContain("the dragon sleeps under the tower");
+    expect(buildSearchSnippet("Short   line", ["missing"])).toBe("Short line");
+  });
+});
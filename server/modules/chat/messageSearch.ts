@@ -0,0 +1,81 @@
+import { db } from "../../db.js";
+import { messageToJson, type MessageRow } from "./routeHelpers.js";
+
+export interface MessageSearchHit {
+  message: ReturnType<typeof messageToJson>;
+  chatTitle: string;
+  snippet: string;
+}
+
+const SNIPPET_RADIUS = 80;
+
+export function messageSearchTerms(query: string): string[] {
+  return String(query || "")
+    .toLowerCase()
+    .split(/[^\p{L}\p{N}_]+/u)
+    .map((term) => term.trim())
+    .filter(Boolean)
+    .slice(0, 12);
+}
+
+/** All terms must match; each term is quoted and prefix-matched so user input can't inject FTS syntax. */
+export function buildMessageFtsQuery(query: string): string {
+  return messageSearchTerms(query).map((term) => `"${term.replace(/"/g, "\"\"")}"*`).join(" ");
+}
+
+/** Cuts a window around the first matched term, collapsing whitespace and marking trimmed ends with an ellipsis. */
+export function buildSearchSnippet(content: string, terms: string[]): string {
+  const text = String(content || "").replace(/\s+/g, " ").trim();
+  const lower = text.toLowerCase();
+  const hits = terms.map((term) => lower.indexOf(term)).filter((index) => index >= 0);
+  const first = hits.length > 0 ? Math.min(...hits) : 0;
+  const start = Math.max(0, first - SNIPPET_RADIUS);
+  const end = Math.min(text.length, first + SNIPPET_RADIUS);
+  return `${start > 0 ? "…" : ""}${text.slice(start, end).trim()}${end < text.length ? "…" : ""}`;
+}
+
+function hasMessageFts(): boolean {
+  return Boolean(db.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'message_fts'").get());
+}
+
+export function searchMessages(query: string, limit: number): MessageSearchHit[] {
+  const terms = messageSearchTerms(query);
+  if (terms.length === 0) return [];
+  const safeLimit = Math.max(1, Math.min(200, Math.floor(limit) || 50));
+  let rows: Array<MessageRow & { chat_title: string }> = [];
+  let ftsFailed = !hasMessageFts();
+
+  if (!ftsFailed) {
+    try {
+      rows = db.prepare(
+        `SELECT m.*, c.title AS chat_title
+         FROM message_fts
+         JOIN messages m ON m.rowid = message_fts.rowid
+         JOIN chats c ON c.id = m.chat_id
+         WHERE message_fts MATCH ? AND m.deleted = 0 AND c.deleted_at IS NULL
+         ORDER BY bm25(message_fts) ASC, m.created_at DESC
+         LIMIT ?`
+      ).all(buildMessageFtsQuery(query), safeLimit) as Array<MessageRow & { chat_title: string }>;
+    } catch {
+      ftsFailed = true;
+    }
+  }
+
+  if (ftsFailed) {
+    const clauses = terms.map(() => "LOWER(m.content) LIKE ?").join(" AND ");
+    rows = db.prepare(
+      `SELECT m.*, c.title AS chat_title
+       FROM messages m
+       JOIN chats c ON c.id = m.chat_id
+       WHERE m.deleted = 0 AND c.deleted_at IS NULL AND ${clauses}
+       ORDER BY m.created_at DESC
+       LIMIT ?`
+    ).all(...terms.map((term) => `%${term}%`), safeLimit) as Array<MessageRow & { chat_title: string }>;
+  }
+
+  return rows.map((row) => ({
+    message: messageToJson(row),
+    chatTitle: row.chat_title,
+    snippet: buildSearchSnippet(row.content, terms)
+  }));
+}
@@ -12,8 +12,14 @@ function isStandaloneSseDataLine(line: string): boolean {
   }
 }
 
+/**
+ * Splits buffered SSE text into complete event blocks. CRLF and bare CR line
+ * endings are accepted; consecutive `data:` lines stay in one block until a blank
+ * line, and a trailing CR is held back in case its LF arrives in the next chunk.
+ */
 export function consumeSseEventBlocks(buffer: string, flush = false): { events: string[]; rest: string } {
-  const normalized = buffer.replace(/\r\n/g, "\n");
+  const heldCarriageReturn = !flush && buffer.endsWith("\r");
+  const normalized = (heldCarriageReturn ? buffer.slice(0, -1) : buffer).replace(/\r\n?/g, "\n");
   const lines = normalized.split("\n");
   const pending = flush ? [] : [lines.pop() ?? ""];
   const completeLines = flush ? lines : lines;
@@ -47,13 +53,13 @@ export function consumeSseEventBlocks(buffer: string, flush = false): { events:
 
   return {
     events,
-    rest: [...currentEvent, ...pending].join("\n")
+    rest: [...currentEvent, ...pending].join("\n") + (heldCarriageReturn ? "\r" : "")
   };
 }
 
 export function extractSseEventData(eventBlock: string): string {
   return eventBlock
-    .split(/\r?\n/)
+    .split(/\r\n?|\n/)
     .map((line) => line.trim())
     .filter((line) => line.startsWith("data:"))
     .map((line) => line.slice(5).trimStart())
@@ -63,7 +69,7 @@ export function extractSseEventData(eventBlock: string): string {
 
 export function extractSseEventType(eventBlock: string): string {
   const eventLine = eventBlock
-    .split(/\r?\n/)
+    .split(/\r\n?|\n/)
     .map((line) => line.trim())
     .find((line) => line.startsWith("event:"));
   return eventLine ? eventLine.slice(6).trim().toLowerCase() : "message";
@@ -95,6 +101,24 @@ export function extractOpenAiStreamErrorMessage(parsed: unknown): string {
   return "";
 }
 
+export interface ProviderTokenUsage {
+  promptTokens: number;
+  completionTokens: number;
+}
+
+/** Reads the `usage` object OpenAI-compatible servers attach to the final chunk when `include_usage` is set. */
+export function extractOpenAiStreamUsage(parsed: unknown): ProviderTokenUsage | null {
+  if (!parsed || typeof parsed !== "object") return null;
+  const usage = (parsed as { usage?: unknown }).usage;
+  if (!usage || typeof usage !== "object") return null;
+  const row = usage as { prompt_tokens?: unknown; completion_tokens?: unknown };
+  const promptTokens = Number(row.prompt_tokens);
+  const completionTokens = Number(row.completion_tokens);
+  if (!Number.isFinite(promptTokens) || !Number.isFinite(completionTokens)) return null;
+  if (promptTokens < 0 || completionTokens < 0) return null;
+  return { promptTokens: Math.floor(promptTokens), completionTokens: Math.floor(completionTokens) };
+}
+
 function flattenOpenAiStreamTextPart(value: unknown): string {
   if (typeof value === "string") return value;
   if (Array.isArray(value)) {
@@ -114,8 +138,8 @@ function flattenOpenAiStreamTextPart(value: unknown): string {
     .find(Boolean) || "";
 }
 
-export function extractOpenAiStreamTextDelta(parsed: unknown): string {
-  if (!parsed || typeof parsed !== "object") return "";
+function readOpenAiStreamText(parsed: unknown): { text: string; snapshot: boolean } {
+  if (!parsed || typeof parsed !== "object") return { text: "", snapshot: false };
   const root = parsed as {
     choices?: Array<{
       delta?: Record<string, unknown>;
@@ -128,23 +152,48 @@ export function extractOpenAiStreamTextDelta(parsed: unknown): string {
 
   const choice = root.choices?.[0];
   const delta = choice?.delta;
-  const candidates = [
-    delta?.content,
-    delta?.text,
-    delta?.output_text,
-    delta?.output_text_delta,
-    delta?.message,
-    choice?.message?.content,
-    choice?.message,
-    choice?.text,
-    root.delta,
-    root.output_text
+  // `message` carries the full reply so far on servers that stream snapshots instead of deltas.
+  const candidates: Array<[unknown, boolean]> = [
+    [delta?.content, false],
+    [delta?.text, false],
+    [delta?.output_text, false],
+    [delta?.output_text_delta, false],
+    [delta?.message, false],
+    [choice?.message?.content, true],
+    [choice?.message, true],
+    [choice?.text, false],
+    [root.delta, false],
+    [root.output_text, false]
   ];
 
-  for (const candidate of candidates) {
+  for (const [candidate, snapshot] of candidates) {
     const text = flattenOpenAiStreamTextPart(candidate);
-    if (text.length > 0) return text;
+    if (text.length > 0) return { text, snapshot };
   }
 
-  return "";
+  return { text: "", snapshot: false };
+}
+
+export function extractOpenAiStreamTextDelta(parsed: unknown): string {
+  return readOpenAiStreamText(parsed).text;
+}
+
+/**
+ * Per-stream variant of `extractOpenAiStreamTextDelta` that tolerates snapshot-style chunks: when a chunk's
+ * `message.content` repeats everything received so far, only the new suffix is returned. Snapshots that do
+ * not extend the received text are treated as ordinary deltas.
+ */
+export function createOpenAiStreamTextReader(): (parsed: unknown) => string {
+  let received = "";
+  return (parsed) => {
+    const { text, snapshot } = readOpenAiStreamText(parsed);
+    if (!text) return "";
+    if (snapshot && received && text.startsWith(received)) {
+      const suffix = text.slice(received.length);
+      received = text;
+      return suffix;
+    }
+    received += text;
+    return text;
+  };
 }
@@ -1,7 +1,7 @@
 import { db } from "../../db.js";
 import { describeSceneLevel } from "../../../src/shared/sceneLevels.js";
 import { normalizeLoreBookEntries, type LoreBookEntryData } from "../../domain/lorebooks.js";
-import { replacePromptPlaceholders, type CharacterCardData } from "../../domain/rpEngine.js";
+import { buildCensorshipDirective, buildResponseLanguageDirective, replacePromptPlaceholders, type CharacterCardData } from "../../domain/rpEngine.js";
 import {
   buildCompactContextPolicy,
   parseCardData,
@@ -31,6 +31,8 @@ export function buildSillyTavernCompatiblePurePrompt(params: {
   ragAppendix?: string;
   isAutoConvo?: boolean;
   strictGrounding?: boolean;
+  censorshipMode?: string;
+  responseLanguage?: string;
 }): string {
   const sections: string[] = [];
   const current = params.currentCharacter;
@@ -76,6 +78,9 @@ export function buildSillyTavernCompatiblePurePrompt(params: {
     }
   }
 
+  sections.push(buildCensorshipDirective(params.censorshipMode));
+  sections.push(buildResponseLanguageDirective(params.responseLanguage));
+
   if (params.isAutoConvo) {
     sections.push(
       "[IMPORTANT: This is an autonomous conversation between characters. There is NO human user participating. Do NOT wait for user input, do NOT address the user, do NOT ask questions to the user. Act naturally and continue the roleplay conversation with the other character(s). Advance the plot, respond to what the other character said, and keep the story flowing. Be proactive — take actions, express emotions, move the scene forward.]"
@@ -100,6 +105,7 @@ export function buildSillyTavernCompatibleLightPrompt(params: {
   ragAppendix?: string;
   isAutoConvo?: boolean;
   strictGrounding?: boolean;
+  censorshipMode?: string;
 }): string {
   const base = buildSillyTavernCompatiblePurePrompt({
     baseSystemPrompt: params.baseSystemPrompt,
@@ -109,7 +115,9 @@ export function buildSillyTavernCompatibleLightPrompt(params: {
     userName: params.userName,
     ragAppendix: "",
     isAutoConvo: params.isAutoConvo,
-    strictGrounding: params.strictGrounding
+    strictGrounding: params.strictGrounding,
+    censorshipMode: params.censorshipMode,
+    responseLanguage: params.responseLanguage
   });
   const sections: string[] = [base];
   const scene = params.sceneState;
@@ -136,10 +144,6 @@ export function buildSillyTavernCompatibleLightPrompt(params: {
   if (authorNote) {
     sections.push(`[Author's Note]\n${authorNote}\nUse as style steering; do not override established facts unless user requests it.`);
   }
-  const responseLanguage = String(params.responseLanguage || "").trim();
-  if (responseLanguage && responseLanguage.toLowerCase() !== "english") {
-    sections.push(`Respond in ${responseLanguage}.`);
-  }
   const rag = String(params.ragAppendix || "").trim();
   if (rag) sections.push(rag);
   if (!params.currentCharacter && params.strictGrounding !== false) {
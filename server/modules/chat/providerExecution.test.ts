@@ -1,10 +1,19 @@
-import { describe, expect, it } from "vitest";
+import type { Response as ExpressResponse } from "express";
+import { afterEach, describe, expect, it, vi } from "vitest";
 import {
   consumeSseEventBlocks,
+  createOpenAiStreamTextReader,
   extractOpenAiStreamErrorMessage,
   extractOpenAiStreamTextDelta,
+  extractOpenAiStreamUsage,
+  extractSseEventData,
   extractSseEventType
 } from "./openAiStream.js";
+import { PartialStreamError, streamProviderCompletion } from "./providerExecution.js";
+
+afterEach(() => {
+  vi.restoreAllMocks();
+});
 
 describe("extractOpenAiStreamTextDelta", () => {
   it("reads plain string deltas", () => {
@@ -43,6 +52,28 @@ describe("extractOpenAiStreamTextDelta", () => {
   });
 });
 
+describe("createOpenAiStreamTextReader", () => {
+  const snapshot = (content: string) => ({ choices: [{ message: { content } }] });
+
+  it("returns only the new suffix of snapshot-style message content", () => {
+    const read = createOpenAiStreamTextReader();
+    expect(["The", "The keeper", "The keeper", "The keeper nods."].map((text) => read(snapshot(text))))
+      .toEqual(["The", " keeper", "", " nods."]);
+  });
+
+  it("passes incremental deltas through unchanged, even when they repeat earlier text", () => {
+    const read = createOpenAiStreamTextReader();
+    expect(["ha", "ha", "!"].map((content) => read({ choices: [{ delta: { content } }] })))
+      .toEqual(["ha", "ha", "!"]);
+  });
+
+  it("prefers the delta when a chunk carries both a delta and a message snapshot", () => {
+    const read = createOpenAiStreamTextReader();
+    expect(read({ choices: [{ delta: { content: "Hi" }, message: { content: "Hi" } }] })).toBe("Hi");
+    expect(read({ choices: [{ delta: { content: " there" }, message: { content: "Hi there" } }] })).toBe(" there");
+  });
+});
+
 describe("consumeSseEventBlocks", () => {
   it("emits strict SSE events separated by blank lines", () => {
     expect(consumeSseEventBlocks(
@@ -70,6 +101,77 @@ describe("consumeSseEventBlocks", () => {
   });
 });
 
+describe("SSE chunk boundaries", () => {
+  const readPayloads = (chunks: Uint8Array[]) => {
+    const decoder = new TextDecoder();
+    const payloads: string[] = [];
+    let buffer = "";
+    for (const chunk of chunks) {
+      buffer += decoder.decode(chunk, { stream: true });
+      const consumed = consumeSseEventBlocks(buffer);
+      buffer = consumed.rest;
+      payloads.push(...consumed.events.map(extractSseEventData));
+    }
+    buffer += decoder.decode();
+    payloads.push(...consumeSseEventBlocks(buffer, true).events.map(extractSseEventData));
+    return payloads.filter(Boolean);
+  };
+  const splitAt = (bytes: Uint8Array, ...offsets: number[]) => {
+    const edges = [0, ...offsets, bytes.length];
+    return edges.slice(1).map((end, index) => bytes.slice(edges[index], end));
+  };
+
+  const stream = [
+    'data: {"choices":[{"delta":{"content":"héllo 🌍"}}]}',
+    "",
+    "event: message",
+    'data: {"choices":[{"delta":',
+    'data: {"content":" wörld"}}]}',
+    "",
+    "data: [DONE]",
+    "",
+    ""
+  ];
+  const expected = [
+    '{"choices":[{"delta":{"content":"héllo 🌍"}}]}',
+    '{"choices":[{"delta":\n{"content":" wörld"}}]}',
+    "[DONE]"
+  ];
+
+  it("joins multi-line data fields and survives every two-chunk split of a CRLF stream", () => {
+    const bytes = new TextEncoder().encode(stream.join("\r\n"));
+    for (let offset = 1; offset < bytes.length; offset += 1) {
+      expect(readPayloads(splitAt(bytes, offset))).toEqual(expected);
+    }
+  });
+
+  it("handles byte-at-a-time delivery with bare CR line endings", () => {
+    const bytes = new TextEncoder().encode(stream.join("\r"));
+    const chunks = Array.from(bytes, (byte) => Uint8Array.of(byte));
+    expect(readPayloads(chunks)).toEqual(expected);
+  });
+
+  it("keeps a trailing CR pending until its LF arrives", () => {
+    expect(consumeSseEventBlocks("data: [DONE]\r")).toEqual({ events: [], rest: "data: [DONE]\r" });
+    expect(consumeSseEventBlocks("data: [DONE]\r\n\r\n").events.map(extractSseEventData)).toEqual(["[DONE]"]);
+  });
+});
+
+describe("extractOpenAiStreamUsage", () => {
+  it("reads prompt and completion counts from the final usage chunk", () => {
+    expect(extractOpenAiStreamUsage({
+      choices: [],
+      usage: { prompt_tokens: 120, completion_tokens: 48, total_tokens: 168 }
+    })).toEqual({ promptTokens: 120, completionTokens: 48 });
+  });
+
+  it("ignores chunks without complete usage", () => {
+    expect(extractOpenAiStreamUsage({ choices: [{ delta: { content: "hi" } }] })).toBeNull();
+    expect(extractOpenAiStreamUsage({ usage: null })).toBeNull();
+    expect(extractOpenAiStreamUsage({ usage: { prompt_tokens: 12 } })).toBeNull();
+  });
+});
+
 describe("SSE error helpers", () => {
   it("extracts named SSE event types", () => {
     expect(extractSseEventType('event: error\ndata: {"message":"Model reloaded."}\n\n')).toBe("error");
@@ -87,3 +189,168 @@ describe("SSE error helpers", () => {
     })).toBe("Model reloaded.");
   });
 });
+
+describe("streamProviderCompletion", () => {
+  const provider = {
+    id: "provider",
+    name: "Provider",
+    base_url: "https://provider.example/v1",
+    api_key_cipher: "test-key",
+    full_local_only: 0,
+    provider_type: "openai"
+  };
+  const encoder = new TextEncoder();
+  const sseChunk = (content: string) => encoder.encode(`data: ${JSON.stringify({ choices: [{ delta: { content } }] })}\n\n`);
+  const streamParams = (retryAttempts: number) => ({
+    provider,
+    modelId: "model",
+    messages: [{ role: "user", content: "hello" }],
+    samplerConfig: {},
+    chatId: "chat-1",
+    res: { write: vi.fn() } as unknown as ExpressResponse,
+    signal: new AbortController().signal,
+    retryAttempts
+  });
+
+  it("retries transient failures that happen before any content arrives", async () => {
+    const mockedFetch = vi.spyOn(globalThis, "fetch")
+      .mockResolvedValueOnce(new Response("upstream hiccup", { status: 500 }))
+      .mockResolvedValueOnce(new Response(new ReadableStream({
+        start(controller) {
+          controller.enqueue(sseChunk("recovered"));
+          controller.close();
+        }
+      }), { status: 200 }));
+
+    const result = await streamProviderCompletion(streamParams(1));
+
+    expect(result.content).toBe("recovered");
+    expect(mockedFetch).toHaveBeenCalledTimes(2);
+  });
+
+  it("does not retry client errors", async () => {
+    const mockedFetch = vi.spyOn(globalThis, "fetch")
+      .mockResolvedValueOnce(new Response("bad key", { status: 401 }));
+
+    await expect(streamProviderCompletion(streamParams(3))).rejects.toThrow("[API Error: 401] bad key");
+    expect(mockedFetch).toHaveBeenCalledTimes(1);
+  });
+
+  it("does not retry error events in the provider stream", async () => {
+    const mockedFetch = vi.spyOn(globalThis, "fetch")
+      .mockResolvedValue(new Response(new ReadableStream({
+        start(controller) {
+          controller.enqueue(encoder.encode(`event: error\ndata: ${JSON.stringify({ error: { message: "Content filtered" } })}\n\n`));
+          controller.close();
+        }
+      }), { status: 200 }));
+
+    await expect(streamProviderCompletion(streamParams(3))).rejects.toThrow("Content filtered");
+    expect(mockedFetch).toHaveBeenCalledTimes(1);
+  });
+
+  it("retries dropped connections", async () => {
+    const mockedFetch = vi.spyOn(globalThis, "fetch")
+      .mockRejectedValueOnce(new TypeError("fetch failed", { cause: Object.assign(new Error("socket hang up"), { code: "ECONNRESET" }) }))
+      .mockResolvedValueOnce(new Response(new ReadableStream({
+        start(controller) {
+          controller.enqueue(sseChunk("reconnected"));
+          controller.close();
+        }
+      }), { status: 200 }));
+
+    const result = await streamProviderCompletion(streamParams(1));
+
+    expect(result.content).toBe("reconnected");
+    expect(mockedFetch).toHaveBeenCalledTimes(2);
+  });
+
+  it("reports the provider's JSON error message instead of the raw body", async () => {
+    vi.spyOn(globalThis, "fetch")
+      .mockResolvedValueOnce(new Response(JSON.stringify({
+        error: { message: "Incorrect API key provided", type: "invalid_request_error", code: "invalid_api_key" }
+      }), { status: 401 }));
+
+    await expect(streamProviderCompletion(streamParams(0))).rejects.toThrow("[API Error: 401] Incorrect API key provided");
+  });
+
+  it("returns the streamed text as a partial result when the stream breaks mid-reply", async () => {
+    let pulls = 0;
+    const mockedFetch = vi.spyOn(globalThis, "fetch")
+      .mockResolvedValueOnce(new Response(new ReadableStream({
+        pull(controller) {
+          pulls += 1;
+          if (pulls === 1) controller.enqueue(sseChunk("partial reply"));
+          else controller.error(new TypeError("terminated"));
+        }
+      }), { status: 200 }));
+
+    const failure = await streamProviderCompletion(streamParams(3)).catch((error: unknown) => error);
+
+    expect(failure).toBeInstanceOf(PartialStreamError);
+    expect((failure as PartialStreamError).partial.content).toBe("partial reply");
+    expect(mockedFetch).toHaveBeenCalledTimes(1);
+  });
+
+  it("sends stop sequences and cuts the reply locally when the provider ignores them", async () => {
+    const mockedFetch = vi.spyOn(globalThis, "fetch")
+      .mockResolvedValueOnce(new Response(new ReadableStream({
+        start(controller) {
+          controller.enqueue(sseChunk("The keeper nods."));
+          controller.enqueue(sseChunk("\nUs"));
+          controller.enqueue(sseChunk("er: Then I open the door."));
+          controller.close();
+        }
+      }), { status: 200 }));
+    const params = { ...streamParams(0), samplerConfig: { stop: ["\nUser:"] } };
+
+    const result = await streamProviderCompletion(params);
+
+    expect(JSON.parse(String(mockedFetch.mock.calls[0]?.[1]?.body)).stop).toEqual(["\nUser:"]);
+    expect(result.content).toBe("The keeper nods.");
+    const deltas = vi.mocked(params.res.write).mock.calls
+      .map(([chunk]) => JSON.parse(String(chunk).slice(6)) as { type: string; delta?: string })
+      .filter((event) => event.type === "delta")
+      .map((event) => event.delta);
+    expect(deltas.join("")).toBe("The keeper nods.");
+  });
+
+  it("keeps reading the provider stream when writes to the client fail", async () => {
+    vi.spyOn(globalThis, "fetch")
+      .mockResolvedValueOnce(new Response(new ReadableStream({
+        start(controller) {
+          controller.enqueue(sseChunk("The keeper"));
+          controller.enqueue(sseChunk(" nods."));
+          controller.close();
+        }
+      }), { status: 200 }));
+    vi.spyOn(console, "warn").mockImplementation(() => undefined);
+    const write = vi.fn(() => {
+      throw new Error("socket hang up");
+    });
+    const params = { ...streamParams(0), res: { write } as unknown as ExpressResponse };
+
+    const result = await streamProviderCompletion(params);
+
+    expect(result.content).toBe("The keeper nods.");
+    expect(write).toHaveBeenCalled();
+    expect(console.warn).toHaveBeenCalledTimes(1);
+  });
+
+  it("does not duplicate text from providers that stream message snapshots", async () => {
+    const snapshotChunk = (content: string) => encoder.encode(`data: ${JSON.stringify({ choices: [{ message: { content } }] })}\n\n`);
+    vi.spyOn(globalThis, "fetch")
+      .mockResolvedValueOnce(new Response(new ReadableStream({
+        start(controller) {
+          controller.enqueue(snapshotChunk("The keeper"));
+          controller.enqueue(snapshotChunk("The keeper nods"));
+          controller.enqueue(snapshotChunk("The keeper nods."));
+          controller.close();
+        }
+      }), { status: 200 }));
+
+    const result = await streamProviderCompletion(streamParams(0));
+
+    expect(result.content).toBe("The keeper nods.");
+  });
+});
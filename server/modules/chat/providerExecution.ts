@@ -1,32 +1,45 @@
 import type { Response } from "express";
-import { roughTokenCount } from "../../db.js";
+import { countTokens } from "../../db.js";
 import { coalesceSystemMessages } from "../../domain/rpEngine.js";
 import { buildKoboldSamplerConfig, buildOpenAiSamplingPayload, normalizeApiParamPolicy } from "../../services/apiParamPolicy.js";
+import { buildAnthropicMessagesBody, extractAnthropicStreamDelta, requestAnthropicMessages } from "../../services/anthropicApi.js";
+import { AppError } from "../../services/appError.js";
 import { completeCustomAdapter } from "../../services/customProviderAdapters.js";
-import { fetchProviderResponse } from "../../services/providerHttp.js";
+import { buildGeminiGenerateBody, extractGeminiStreamDelta, requestGeminiGenerate } from "../../services/geminiApi.js";
+import { buildOllamaChatBody, extractOllamaChatDelta, requestOllamaChat } from "../../services/ollamaApi.js";
+import { fetchProviderResponse, waitForRetry } from "../../services/providerHttp.js";
 import {
   buildKoboldGenerateBody,
   countKoboldTokens,
   extractKoboldGeneratedText,
   extractKoboldStreamDelta,
+  normalizeOpenAiBaseUrl,
   normalizeProviderType,
+  readProviderErrorDetail,
   requestKoboldGenerate,
-  requestKoboldGenerateStream
+  requestKoboldGenerateStream,
+  providerAuthHeaders,
+  providerRateLimitOptions,
+  withProviderAuthUrl,
+  withProviderHeaders
 } from "../../services/providerApi.js";
 import { consumeThinkChunk, createThinkStreamState, flushThinkState, splitThinkContent } from "./reasoning.js";
+import { consumeStopChunk, createStopGuard, flushStopGuard, trimAtStopSequence } from "./stopSequences.js";
 import { prepareOpenAiCompatibleMessages } from "./providerMessages.js";
+import { writeSseEvent } from "./sseWriter.js";
 import type { ProviderRow } from "./routeHelpers.js";
 import {
   consumeSseEventBlocks,
+  createOpenAiStreamTextReader,
   extractOpenAiStreamErrorMessage,
-  extractOpenAiStreamTextDelta,
+  extractOpenAiStreamUsage,
   extractSseEventData,
-  extractSseEventType
+  extractSseEventType,
+  type ProviderTokenUsage
 } from "./openAiStream.js";
 import {
   buildKoboldPromptFromMessages,
   extractOpenAIReasoningDelta,
-  KOBOLD_TAGS,
   REASONING_CALL_NAME,
   type ToolCallTrace
 } from "./tooling.js";
@@ -41,6 +54,7 @@ export interface StreamProviderCompletionParams {
   chatId: string;
   res: Response;
   signal: AbortSignal;
+  retryAttempts?: number;
 }
 
 export interface StreamProviderCompletionResult {
@@ -49,45 +63,190 @@ export interface StreamProviderCompletionResult {
   generationStartedAt: string;
   generationCompletedAt: string;
   generationDurationMs: number;
+  usage?: ProviderTokenUsage | null;
 }
 
-export interface CompleteProviderOnceParams {
-  provider: ProviderRow;
-  modelId: string;
-  systemPrompt: string;
-  userPrompt: string;
-  imageDataUrl?: string;
-  imageDataUrls?: string[];
-  samplerConfig?: Record<string, unknown>;
-  apiParamPolicy?: unknown;
-  signal?: AbortSignal;
+const STREAM_RETRY_BASE_DELAY_MS = 500;
+const TRANSIENT_HTTP_STATUSES = new Set([408, 425, 429, 500, 502, 503, 504]);
+const TRANSIENT_NETWORK_ERROR_CODES = new Set([
+  "EAI_AGAIN",
+  "ECONNREFUSED",
+  "ECONNRESET",
+  "EHOSTUNREACH",
+  "ENETUNREACH",
+  "EPIPE",
+  "ETIMEDOUT",
+  "UND_ERR_BODY_TIMEOUT",
+  "UND_ERR_CONNECT_TIMEOUT",
+  "UND_ERR_HEADERS_TIMEOUT",
+  "UND_ERR_SOCKET"
+]);
+
+/** Non-OK HTTP answer from a provider, kept separate so retries can skip client errors. */
+export class ProviderStatusError extends AppError {
+  constructor(message: string, readonly status: number) {
+    super("provider", message, 502, status);
+    this.name = "ProviderStatusError";
+  }
 }
 
-export async function countProviderTokens(provider: ProviderRow | null | undefined, content: string): Promise<number> {
+/** Stream failure after output reached the client; carries what was produced so far. */
+export class PartialStreamError extends Error {
+  constructor(message: string, readonly partial: StreamProviderCompletionResult, options?: { cause?: unknown }) {
+    super(message, options);
+    this.name = "PartialStreamError";
+  }
+}
+
+interface StreamAttemptProgress {
+  content: string;
+  started: boolean;
+  finalize: () => StreamProviderCompletionResult;
+}
+
+export async function countProviderTokens(
+  provider: ProviderRow | null | undefined,
+  content: string,
+  modelId?: string | null
+): Promise<number> {
   const text = String(content || "");
   if (!text) return 0;
   if (!provider || normalizeProviderType(provider.provider_type) !== "koboldcpp") {
-    return roughTokenCount(text);
+    return countTokens(text, modelId);
   }
   const counted = await countKoboldTokens(provider, text);
-  return counted ?? roughTokenCount(text);
+  return counted ?? countTokens(text, modelId);
 }
 
 async function sendSseText(res: Response, chatId: string, text: string, paceMs = 0) {
   const chunks = text.match(/[\s\S]{1,140}/g) ?? [];
   for (const chunk of chunks) {
-    res.write(`data: ${JSON.stringify({ type: "delta", chatId, delta: chunk })}\n\n`);
-    if (typeof (res as Response & { flush?: () => void }).flush === "function") {
-      (res as Response & { flush?: () => void }).flush?.();
-    }
+    writeSseEvent(res, { type: "delta", chatId, delta: chunk });
     if (paceMs > 0) {
       await new Promise((resolve) => setTimeout(resolve, paceMs));
     }
   }
 }
 
+/** One provider stream chunk in a provider-neutral shape: visible text, model-side reasoning, or an in-stream error. */
+interface ProviderStreamDelta {
+  text?: string;
+  thinking?: string;
+  error?: string;
+}
+
+type StreamFraming = "lines" | "sse";
+
+/**
+ * Reads a provider response body as newline-delimited chunks or SSE event blocks and hands each one to
+ * `onChunk` until the stream ends or `signal` aborts. An abort ends the read quietly; other failures propagate.
+ */
+async function readProviderStream(
+  body: ReadableStream<Uint8Array>,
+  framing: StreamFraming,
+  signal: AbortSignal,
+  onChunk: (chunk: string) => void
+): Promise<void> {
+  const reader = body.getReader();
+  const decoder = new TextDecoder();
+  let buffer = "";
+  const drain = (final: boolean) => {
+    if (framing === "sse") {
+      const consumed = consumeSseEventBlocks(buffer, final);
+      buffer = consumed.rest;
+      for (const eventBlock of consumed.events) onChunk(eventBlock);
+      return;
+    }
+    const lines = buffer.split("\n");
+    buffer = final ? "" : lines.pop() ?? "";
+    for (const line of lines) onChunk(line);
+  };
+
+  try {
+    while (true) {
+      const { done, value } = await reader.read();
+      if (done) break;
+
+      if (signal.aborted) {
+        await reader.cancel();
+        break;
+      }
+
+      buffer += decoder.decode(value, { stream: true });
+      drain(false);
+    }
+  } catch (readErr) {
+    if (!(readErr instanceof Error && readErr.name === "AbortError")) {
+      throw readErr;
+    }
+  }
+  buffer += decoder.decode();
+  drain(true);
+}
+
+function parseSseEventJson(eventBlock: string): unknown {
+  const payload = extractSseEventData(eventBlock);
+  if (!payload || payload === "[DONE]") return null;
+  try {
+    return JSON.parse(payload);
+  } catch {
+    throw new Error("Malformed provider stream chunk");
+  }
+}
+
+function networkErrorCode(error: Error): string {
+  const cause = (error as { cause?: unknown }).cause;
+  const code = cause && typeof cause === "object" ? (cause as { code?: unknown }).code : (error as { code?: unknown }).code;
+  return String(code ?? "");
+}
+
+/**
+ * Only failures a replay can fix are retried: dropped connections, timeouts and
+ * transient HTTP statuses. Provider error events, refusals, malformed payloads
+ * and our own bugs fail on the first attempt instead of being re-billed.
+ */
+function isTransientStreamFailure(error: unknown): boolean {
+  if (error instanceof ProviderStatusError) return TRANSIENT_HTTP_STATUSES.has(error.status);
+  if (error instanceof AppError) return error.code === "network";
+  if (!(error instanceof Error)) return false;
+  if (error.name === "TimeoutError" || TRANSIENT_NETWORK_ERROR_CODES.has(networkErrorCode(error))) return true;
+  return error instanceof TypeError && /fetch failed|terminated/i.test(error.message);
+}
+
+/**
+ * Streams a completion and retries with exponential backoff while nothing has
+ * reached the client yet. Once content or reasoning was streamed, a failure is
+ * rethrown as `PartialStreamError` so the caller can keep the partial reply.
+ */
 export async function streamProviderCompletion(
   params: StreamProviderCompletionParams
+): Promise<StreamProviderCompletionResult> {
+  const retryAttempts = Math.max(0, Math.min(5, Math.floor(Number(params.retryAttempts) || 0)));
+  for (let attempt = 0; ; attempt += 1) {
+    const progress: StreamAttemptProgress = {
+      content: "",
+      started: false,
+      finalize: () => {
+        throw new Error("Stream attempt did not start");
+      }
+    };
+    try {
+      return await streamProviderCompletionAttempt(params, progress);
+    } catch (error) {
+      if (params.signal.aborted || (error instanceof Error && error.name === "AbortError")) throw error;
+      if (progress.content || progress.started) {
+        const message = error instanceof Error ? error.message : String(error);
+        throw new PartialStreamError(message, progress.finalize(), { cause: error });
+      }
+      if (attempt >= retryAttempts || !isTransientStreamFailure(error)) throw error;
+      await waitForRetry(STREAM_RETRY_BASE_DELAY_MS * 2 ** attempt, params.signal);
+    }
+  }
+}
+
+async function streamProviderCompletionAttempt(
+  params: StreamProviderCompletionParams,
+  progress: StreamAttemptProgress
 ): Promise<StreamProviderCompletionResult> {
   const normalizedMessages = coalesceSystemMessages(params.messages);
   const generationStartedMs = Date.now();
@@ -111,18 +270,28 @@ export async function streamProviderCompletion(
   };
   let reasoningStarted = false;
   const thinkState = createThinkStreamState();
+  const stopGuard = createStopGuard(sc.stop);
+
+  // Pass `final` once the stream is over so text held back as a possible stop prefix is released.
+  const emitContent = (text: string, final = false) => {
+    const safe = consumeStopChunk(stopGuard, text) + (final ? flushStopGuard(stopGuard) : "");
+    if (!safe) return;
+    progress.content += safe;
+    writeSseEvent(params.res, { type: "delta", chatId: params.chatId, delta: safe });
+  };
 
   const startReasoning = () => {
     if (reasoningStarted) return;
     reasoningStarted = true;
-    params.res.write(`data: ${JSON.stringify({
+    progress.started = true;
+    writeSseEvent(params.res, {
       type: "tool",
       chatId: params.chatId,
       phase: "start",
       callId: reasoningTrace.callId,
       name: REASONING_CALL_NAME,
       args: "{}"
-    })}\n\n`);
+    });
   };
 
   const appendReasoningDelta = (delta: string) => {
@@ -131,30 +300,62 @@ export async function streamProviderCompletion(
     if (reasoningTrace.result.length < reasoningMaxChars) {
       reasoningTrace.result += delta.slice(0, reasoningMaxChars - reasoningTrace.result.length);
     }
-    params.res.write(`data: ${JSON.stringify({
+    writeSseEvent(params.res, {
       type: "tool",
       chatId: params.chatId,
       phase: "delta",
       callId: reasoningTrace.callId,
       name: REASONING_CALL_NAME,
       result: delta.slice(0, 4000)
-    })}\n\n`);
+    });
   };
 
   const finalizeReasoning = (): ToolCallTrace[] => {
     if (!reasoningStarted) return [];
     const persistedReasoning = reasoningTrace.result.slice(0, reasoningMaxChars);
-    params.res.write(`data: ${JSON.stringify({
+    writeSseEvent(params.res, {
       type: "tool",
       chatId: params.chatId,
       phase: "done",
       callId: reasoningTrace.callId,
       name: REASONING_CALL_NAME,
       result: persistedReasoning
-    })}\n\n`);
+    });
     if (!reasoningTrace.result.trim()) return [];
     return [{ ...reasoningTrace, result: persistedReasoning }];
   };
+  progress.finalize = () => ({ content: progress.content, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() });
+
+  const applyStreamDelta = (delta: ProviderStreamDelta | null) => {
+    if (!delta) return;
+    if (delta.error) throw new Error(delta.error);
+    if (delta.thinking) appendReasoningDelta(delta.thinking);
+    if (!delta.text) return;
+    const split = consumeThinkChunk(thinkState, delta.text);
+    if (split.reasoning) appendReasoningDelta(split.reasoning);
+    if (split.content) emitContent(split.content);
+  };
+
+  const flushStreamedContent = () => {
+    const flush = flushThinkState(thinkState);
+    if (flush.reasoning) appendReasoningDelta(flush.reasoning);
+    emitContent(flush.content, true);
+  };
+
+  // Every streaming provider shares the status check, read loop, think split and stop guard; only the chunk format differs.
+  const streamResponseDeltas = async (
+    response: globalThis.Response,
+    errorLabel: string,
+    framing: StreamFraming,
+    extractDelta: (chunk: string) => ProviderStreamDelta | null
+  ) => {
+    if (!response.ok || !response.body) {
+      const detail = await readProviderErrorDetail(response);
+      throw new ProviderStatusError(`[${errorLabel}: ${response.status}] ${detail}`, response.status);
+    }
+    await readProviderStream(response.body, framing, params.signal, (chunk) => applyStreamDelta(extractDelta(chunk)));
+    flushStreamedContent();
+  };
 
   if (providerType === "koboldcpp") {
     const koboldPolicy = normalizeApiParamPolicy(params.apiParamPolicy).kobold;
@@ -172,79 +373,94 @@ export async function streamProviderCompletion(
 
     const streamResponse = await requestKoboldGenerateStream(params.provider, body, params.signal);
     if (streamResponse.ok && streamResponse.body) {
-      let fullContent = "";
-      const reader = streamResponse.body.getReader();
-      const decoder = new TextDecoder();
-      let buffer = "";
-
-      try {
-        while (true) {
-          const { done, value } = await reader.read();
-          if (done) break;
-
-          if (params.signal.aborted) {
-            await reader.cancel();
-            break;
-          }
-
-          buffer += decoder.decode(value, { stream: true });
-          const lines = buffer.split("\n");
-          buffer = lines.pop() ?? "";
-
-          for (const line of lines) {
-            const trimmed = line.trim();
-            if (!trimmed || trimmed.startsWith("event:")) continue;
-            const data = trimmed.startsWith("data: ")
-              ? trimmed.slice(6).trim()
-              : trimmed;
-            if (!data || data === "[DONE]") continue;
-
-            let delta = "";
-            try {
-              delta = extractKoboldStreamDelta(JSON.parse(data));
-            } catch {
-              delta = data;
-            }
-            if (!delta) continue;
-            const split = consumeThinkChunk(thinkState, delta);
-            if (split.reasoning) appendReasoningDelta(split.reasoning);
-            if (split.content) {
-              fullContent += split.content;
-              params.res.write(`data: ${JSON.stringify({ type: "delta", chatId: params.chatId, delta: split.content })}\n\n`);
-            }
-          }
-        }
-      } catch (readErr) {
-        if (!(readErr instanceof Error && readErr.name === "AbortError")) {
-          throw readErr;
+      await readProviderStream(streamResponse.body, "lines", params.signal, (line) => {
+        const trimmed = line.trim();
+        if (!trimmed || trimmed.startsWith("event:")) return;
+        const data = trimmed.startsWith("data: ")
+          ? trimmed.slice(6).trim()
+          : trimmed;
+        if (!data || data === "[DONE]") return;
+
+        let delta = "";
+        try {
+          delta = extractKoboldStreamDelta(JSON.parse(data));
+        } catch {
+          delta = data;
         }
-      }
-
-      const flush = flushThinkState(thinkState);
-      if (flush.reasoning) appendReasoningDelta(flush.reasoning);
-      if (flush.content) {
-        fullContent += flush.content;
-        params.res.write(`data: ${JSON.stringify({ type: "delta", chatId: params.chatId, delta: flush.content })}\n\n`);
-      }
+        applyStreamDelta({ text: delta });
+      });
+      flushStreamedContent();
 
-      if (fullContent.trim() || reasoningTrace.result.trim()) {
-        return { content: fullContent, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() };
+      if (progress.content.trim() || reasoningTrace.result.trim()) {
+        return { content: progress.content, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() };
       }
     }
 
     const fallbackResponse = await requestKoboldGenerate(params.provider, body, params.signal);
     if (!fallbackResponse.ok) {
-      const errText = await fallbackResponse.text().catch(() => "Unknown error");
-      throw new Error(`[KoboldCpp API Error: ${fallbackResponse.status}] ${errText.slice(0, 200)}`);
+      const detail = await readProviderErrorDetail(fallbackResponse);
+      throw new ProviderStatusError(`[KoboldCpp API Error: ${fallbackResponse.status}] ${detail}`, fallbackResponse.status);
     }
     const fallbackBody = await fallbackResponse.json().catch(() => ({}));
     const generated = extractKoboldGeneratedText(fallbackBody);
     const split = splitThinkContent(generated);
     if (split.reasoning) appendReasoningDelta(split.reasoning);
-    if (split.content) {
-      await sendSseText(params.res, params.chatId, split.content, 8);
+    const content = trimAtStopSequence(split.content, sc.stop);
+    if (content) {
+      await sendSseText(params.res, params.chatId, content, 8);
     }
-    return { content: split.content, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() };
+    return { content, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() };
+  }
+
+  if (providerType === "ollama") {
+    const response = await requestOllamaChat(params.provider, buildOllamaChatBody({
+      modelId: params.modelId,
+      messages: normalizedMessages,
+      samplerConfig: sc,
+      apiParamPolicy: params.apiParamPolicy,
+      stream: true
+    }), params.signal);
+    await streamResponseDeltas(response, "Ollama API Error", "lines", (line) => {
+      const trimmed = line.trim();
+      if (!trimmed) return null;
+      let parsed: unknown;
+      try {
+        parsed = JSON.parse(trimmed);
+      } catch {
+        return null;
+      }
+      const chunk = extractOllamaChatDelta(parsed);
+      return { text: chunk.content, thinking: chunk.thinking, error: chunk.error };
+    });
+    return { content: progress.content, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() };
+  }
+
+  if (providerType === "anthropic") {
+    const response = await requestAnthropicMessages(params.provider, buildAnthropicMessagesBody({
+      modelId: params.modelId,
+      messages: normalizedMessages,
+      samplerConfig: sc,
+      apiParamPolicy: params.apiParamPolicy,
+      stream: true
+    }), params.signal);
+    await streamResponseDeltas(response, "Anthropic API Error", "sse", (eventBlock) => {
+      const parsed = parseSseEventJson(eventBlock);
+      return parsed === null ? null : extractAnthropicStreamDelta(extractSseEventType(eventBlock), parsed);
+    });
+    return { content: progress.content, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() };
+  }
+
+  if (providerType === "gemini") {
+    const response = await requestGeminiGenerate(params.provider, params.modelId, buildGeminiGenerateBody({
+      messages: normalizedMessages,
+      samplerConfig: sc,
+      apiParamPolicy: params.apiParamPolicy
+    }), true, params.signal);
+    await streamResponseDeltas(response, "Gemini API Error", "sse", (eventBlock) => {
+      const parsed = parseSseEventJson(eventBlock);
+      return parsed === null ? null : extractGeminiStreamDelta(parsed);
+    });
+    return { content: progress.content, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() };
   }
 
   if (providerType === "custom") {
@@ -259,13 +475,14 @@ export async function streamProviderCompletion(
     });
     const split = splitThinkContent(generated);
     if (split.reasoning) appendReasoningDelta(split.reasoning);
-    if (split.content) {
-      await sendSseText(params.res, params.chatId, split.content, 8);
+    const content = trimAtStopSequence(split.content, sc.stop);
+    if (content) {
+      await sendSseText(params.res, params.chatId, content, 8);
     }
-    return { content: split.content, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() };
+    return { content, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() };
   }
 
-  const baseUrl = String(params.provider.base_url || "").replace(/\/+$/, "");
+  const baseUrl = normalizeOpenAiBaseUrl(params.provider.base_url);
   const openAiMessages = prepareOpenAiCompatibleMessages(baseUrl, normalizedMessages);
   const openAiSampling = buildOpenAiSamplingPayload({
     samplerConfig: sc,
@@ -279,205 +496,34 @@ export async function streamProviderCompletion(
       maxTokens: 2048
     }
   });
-  const response = await fetchProviderResponse(`${baseUrl}/chat/completions`, {
+  const response = await fetchProviderResponse(withProviderAuthUrl(params.provider, `${baseUrl}/chat/completions`), {
     method: "POST",
-    headers: {
+    headers: withProviderHeaders(params.provider, {
       "Content-Type": "application/json",
-      Authorization: `Bearer ${params.provider.api_key_cipher}`
-    },
+      ...providerAuthHeaders(params.provider)
+    }),
     body: JSON.stringify({
       model: params.modelId,
       messages: openAiMessages,
       stream: true,
+      ...(normalizeApiParamPolicy(params.apiParamPolicy).openai.streamUsage ? { stream_options: { include_usage: true } } : {}),
       ...openAiSampling
     }),
     signal: params.signal
-  });
-
-  if (!response.ok || !response.body) {
-    const errText = await response.text().catch(() => "Unknown error");
-    throw new Error(`[API Error: ${response.status}] ${errText.slice(0, 200)}`);
-  }
-
-  let fullContent = "";
-  const reader = response.body.getReader();
-  const decoder = new TextDecoder();
-  let buffer = "";
-  const processEventBlock = (eventBlock: string) => {
-    const eventType = extractSseEventType(eventBlock);
-    const payload = extractSseEventData(eventBlock);
-    if (!payload || payload === "[DONE]") return;
-
-    try {
-      const parsed = JSON.parse(payload) as { choices?: { delta?: { content?: string } }[] };
-      const streamError = extractOpenAiStreamErrorMessage(parsed);
-      if (eventType === "error" || streamError) {
-        throw new Error(streamError || "Provider stream returned an error event");
-      }
-      const reasoningDelta = extractOpenAIReasoningDelta(parsed);
-      if (reasoningDelta) appendReasoningDelta(reasoningDelta);
-      const delta = extractOpenAiStreamTextDelta(parsed);
-      if (delta) {
-        const split = consumeThinkChunk(thinkState, delta);
-        if (split.reasoning) appendReasoningDelta(split.reasoning);
-        if (split.content) {
-          fullContent += split.content;
-          params.res.write(`data: ${JSON.stringify({ type: "delta", chatId: params.chatId, delta: split.content })}\n\n`);
-          if (typeof (params.res as Response & { flush?: () => void }).flush === "function") {
-            (params.res as Response & { flush?: () => void }).flush?.();
-          }
-        }
-      }
-    } catch (error) {
-      if (error instanceof Error) {
-        throw error;
-      }
-      throw new Error("Malformed provider stream chunk");
-    }
-  };
-
-  try {
-    while (true) {
-      const { done, value } = await reader.read();
-      if (done) break;
-
-      if (params.signal.aborted) {
-        await reader.cancel();
-        break;
-      }
-
-      buffer += decoder.decode(value, { stream: true });
-      const consumed = consumeSseEventBlocks(buffer);
-      buffer = consumed.rest;
-      for (const eventBlock of consumed.events) {
-        processEventBlock(eventBlock);
-      }
-    }
-  } catch (readErr) {
-    if (!(readErr instanceof Error && readErr.name === "AbortError")) {
-      throw readErr;
-    }
-  }
-
-  const flushedEvents = consumeSseEventBlocks(buffer, true);
-  for (const eventBlock of flushedEvents.events) {
-    processEventBlock(eventBlock);
-  }
-
-  const flush = flushThinkState(thinkState);
-  if (flush.reasoning) appendReasoningDelta(flush.reasoning);
-  if (flush.content) {
-    fullContent += flush.content;
-    params.res.write(`data: ${JSON.stringify({ type: "delta", chatId: params.chatId, delta: flush.content })}\n\n`);
-    if (typeof (params.res as Response & { flush?: () => void }).flush === "function") {
-      (params.res as Response & { flush?: () => void }).flush?.();
+  }, providerRateLimitOptions(params.provider));
+
+  let usage: ProviderTokenUsage | null = null;
+  const readStreamText = createOpenAiStreamTextReader();
+  await streamResponseDeltas(response, "API Error", "sse", (eventBlock) => {
+    const parsed = parseSseEventJson(eventBlock);
+    if (parsed === null) return null;
+    const streamError = extractOpenAiStreamErrorMessage(parsed);
+    if (extractSseEventType(eventBlock) === "error" || streamError) {
+      return { error: streamError || "Provider stream returned an error event" };
     }
-  }
-
-  return { content: fullContent, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta() };
-}
-
-export async function completeProviderOnce(params: CompleteProviderOnceParams): Promise<string> {
-  const providerType = normalizeProviderType(params.provider.provider_type);
-  const sc = params.samplerConfig || {};
-  const imageDataUrls = [
-    ...(params.imageDataUrl ? [params.imageDataUrl] : []),
-    ...(Array.isArray(params.imageDataUrls) ? params.imageDataUrls : [])
-  ].filter((item, index, array) => item.startsWith("data:image/") && array.indexOf(item) === index).slice(0, 2);
-
-  if (providerType === "koboldcpp") {
-    const koboldPolicy = normalizeApiParamPolicy(params.apiParamPolicy).kobold;
-    const customMemory = String(sc.koboldMemory || "").trim();
-    const memory = [
-      customMemory,
-      params.systemPrompt
-        ? `${KOBOLD_TAGS.systemOpen}\n${params.systemPrompt}\n${KOBOLD_TAGS.systemClose}`
-        : ""
-    ].filter(Boolean).join("\n\n");
-    const koboldSamplerConfig = buildKoboldSamplerConfig({
-      samplerConfig: {
-        ...sc,
-        maxTokens: sc.maxTokens ?? 1024
-      },
-      apiParamPolicy: params.apiParamPolicy
-    });
-    const body = buildKoboldGenerateBody({
-      prompt: `${KOBOLD_TAGS.inputOpen}\n${params.userPrompt}${imageDataUrls.length ? "\n\n[Screen context image attached; this provider may not support vision.]" : ""}\n${KOBOLD_TAGS.inputClose}\n\n${KOBOLD_TAGS.outputOpen}`,
-      memory,
-      samplerConfig: koboldSamplerConfig,
-      includeMemory: koboldPolicy.memory
-    });
-    const response = await requestKoboldGenerate(params.provider, body, params.signal);
-    if (!response.ok) return "";
-    const parsed = await response.json().catch(() => ({}));
-    return extractKoboldGeneratedText(parsed).trim();
-  }
-
-  if (providerType === "custom") {
-    return completeCustomAdapter({
-      provider: params.provider,
-      modelId: params.modelId,
-      systemPrompt: params.systemPrompt,
-      userPrompt: params.userPrompt,
-      samplerConfig: sc,
-      messages: imageDataUrls.length
-        ? [
-          { role: "system", content: params.systemPrompt },
-          {
-            role: "user",
-            content: [
-              { type: "text", text: params.userPrompt },
-              ...imageDataUrls.map((url) => ({ type: "image_url", image_url: { url } }))
-            ]
-          }
-        ]
-        : undefined,
-      signal: params.signal
-    });
-  }
-
-  const baseUrl = String(params.provider.base_url || "").replace(/\/+$/, "");
-  const openAiSampling = buildOpenAiSamplingPayload({
-    samplerConfig: sc,
-    apiParamPolicy: params.apiParamPolicy,
-    fields: ["temperature", "maxTokens"],
-    defaults: {
-      temperature: 0.3,
-      maxTokens: 1024
-    }
-  });
-  const response = await fetchProviderResponse(`${baseUrl}/chat/completions`, {
-    method: "POST",
-    headers: {
-      "Content-Type": "application/json",
-      Authorization: `Bearer ${params.provider.api_key_cipher}`
-    },
-    body: JSON.stringify({
-      model: params.modelId,
-      messages: [
-        { role: "system", content: params.systemPrompt },
-        imageDataUrls.length
-          ? {
-            role: "user",
-            content: [
-              { type: "text", text: params.userPrompt },
-              ...imageDataUrls.map((url) => ({ type: "image_url", image_url: { url } }))
-            ]
-          }
-          : { role: "user", content: params.userPrompt }
-      ],
-      ...openAiSampling
-    }),
-    signal: params.signal
+    usage = extractOpenAiStreamUsage(parsed) ?? usage;
+    return { thinking: extractOpenAIReasoningDelta(parsed), text: readStreamText(parsed) };
   });
-  if (!response.ok) return "";
-  const body = await response.json() as { choices?: { message?: { content?: string } }[] };
-  return body.choices?.[0]?.message?.content?.trim() ?? "";
-}
 
-export function normalizeOpenAiBaseUrl(raw: string): string {
-  const trimmed = String(raw || "").trim().replace(/\/+$/, "");
-  if (!trimmed) return "";
-  if (/\/v1$/i.test(trimmed)) return trimmed;
-  return `${trimmed}/v1`;
+  return { content: progress.content, toolTraces: finalizeReasoning(), ...finalizeGenerationMeta(), usage };
 }
@@ -0,0 +1,69 @@
+import { describe, expect, it, vi } from "vitest";
+import { normalizeProviderFallbacks } from "../../services/providerFallbacks.js";
+import { PartialStreamError, ProviderStatusError, type StreamProviderCompletionResult } from "./providerExecution.js";
+import { isProviderFallbackError, streamWithProviderFallback, type ResolvedFallbackTarget } from "./providerFallback.js";
+import type { ProviderRow } from "./routeHelpers.js";
+
+function target(id: string): ResolvedFallbackTarget {
+  return {
+    provider: { id, name: id, base_url: "http://127.0.0.1:5001/v1", api_key_cipher: "", full_local_only: 0, provider_type: "openai" } as ProviderRow,
+    modelId: `${id}-model`
+  };
+}
+
+function reply(content: string): StreamProviderCompletionResult {
+  return { content, toolTraces: [] } as unknown as StreamProviderCompletionResult;
+}
+
+describe("provider fallback", () => {
+  it("keeps valid, unique fallback entries in order", () => {
+    expect(normalizeProviderFallbacks([
+      { providerId: " local ", modelId: "llama" },
+      { providerId: "local", modelId: "llama" },
+      { providerId: "cloud" },
+      "cloud",
+      { providerId: "cloud", modelId: "gpt" }
+    ])).toEqual([
+      { providerId: "local", modelId: "llama" },
+      { providerId: "cloud", modelId: "gpt" }
+    ]);
+    expect(normalizeProviderFallbacks(null)).toEqual([]);
+  });
+
+  it("only moves on for server errors and unreachable endpoints", () => {
+    expect(isProviderFallbackError(new ProviderStatusError("[API Error: 503] busy", 503))).toBe(true);
+    expect(isProviderFallbackError(new Error("[Gemini API Error: 500] internal"))).toBe(true);
+    expect(isProviderFallbackError(new ProviderStatusError("[API Error: 401] bad key", 401))).toBe(false);
+    expect(isProviderFallbackError(new PartialStreamError("[API Error: 502] cut", reply("half")))).toBe(false);
+  });
+
+  it("replays the request on the next provider after a failure or an empty reply", async () => {
+    const chain = [target("primary"), target("second"), target("third")];
+    const stream = vi.fn()
+      .mockRejectedValueOnce(new ProviderStatusError("[API Error: 500] down", 500))
+      .mockResolvedValueOnce(reply("  "))
+      .mockResolvedValueOnce(reply("Hello"));
+    const onFallback = vi.fn();
+
+    const outcome = await streamWithProviderFallback({ chain, signal: new AbortController().signal, stream, onFallback });
+
+    expect(outcome.result.content).toBe("Hello");
+    expect(outcome.target.provider.id).toBe("third");
+    expect(onFallback.mock.calls.map(([event]) => [event.from.provider.id, event.to.provider.id, event.reason])).toEqual([
+      ["primary", "second", "[API Error: 500] down"],
+      ["second", "third", "The model returned an empty reply"]
+    ]);
+  });
+
+  it("surfaces client errors without trying the rest of the chain", async () => {
+    const stream = vi.fn().mockRejectedValue(new ProviderStatusError("[API Error: 401] bad key", 401));
+
+    await expect(streamWithProviderFallback({
+      chain: [target("primary"), target("second")],
+      signal: new AbortController().signal,
+      stream,
+      onFallback: vi.fn()
+    })).rejects.toThrow("bad key");
+    expect(stream).toHaveBeenCalledTimes(1);
+  });
+});
@@ -0,0 +1,79 @@
+import { db, isLocalhostUrl } from "../../db.js";
+import { toAppError } from "../../services/appError.js";
+import type { ProviderFallbackTarget } from "../../services/providerFallbacks.js";
+import { PartialStreamError, type StreamProviderCompletionResult } from "./providerExecution.js";
+import type { ProviderRow } from "./routeHelpers.js";
+
+// When the primary provider fails before any text reached the user, the same prompt is replayed against the
+// next entry of `providerFallbacks` from settings.
+
+export interface ResolvedFallbackTarget {
+  provider: ProviderRow;
+  modelId: string;
+}
+
+export interface ProviderFallbackEvent {
+  from: ResolvedFallbackTarget;
+  to: ResolvedFallbackTarget;
+  reason: string;
+}
+
+function isReachable(provider: ProviderRow, fullLocalMode: boolean): boolean {
+  if (isLocalhostUrl(provider.base_url)) return true;
+  return !fullLocalMode && !provider.full_local_only;
+}
+
+/**
+ * The primary followed by the configured fallbacks that exist and may be called. In Full Local Mode, and for
+ * providers flagged local-only, a fallback with a remote endpoint is skipped rather than tried.
+ */
+export function resolveProviderFallbackChain(
+  primary: ResolvedFallbackTarget,
+  fallbacks: ProviderFallbackTarget[],
+  fullLocalMode: boolean
+): ResolvedFallbackTarget[] {
+  const chain = [primary];
+  const lookup = db.prepare("SELECT * FROM providers WHERE id = ?");
+  for (const fallback of fallbacks) {
+    if (chain.some((target) => target.provider.id === fallback.providerId && target.modelId === fallback.modelId)) continue;
+    const provider = lookup.get(fallback.providerId) as ProviderRow | undefined;
+    if (!provider || !isReachable(provider, fullLocalMode)) continue;
+    chain.push({ provider, modelId: fallback.modelId });
+  }
+  return chain;
+}
+
+/**
+ * Server errors, timeouts and unreachable endpoints are worth another provider; client errors are not, and
+ * neither is a stream that already sent text, so the user never sees two replies glued together.
+ */
+export function isProviderFallbackError(error: unknown): boolean {
+  if (error instanceof PartialStreamError) return false;
+  const appError = toAppError(error);
+  if (appError.code === "network") return true;
+  return appError.code === "provider" && (appError.providerStatus ?? 0) >= 500;
+}
+
+/** Streams from each target in turn until one produces a reply; user aborts are never retried elsewhere. */
+export async function streamWithProviderFallback(params: {
+  chain: ResolvedFallbackTarget[];
+  signal: AbortSignal;
+  stream: (target: ResolvedFallbackTarget) => Promise<StreamProviderCompletionResult>;
+  onFallback: (event: ProviderFallbackEvent) => void;
+}): Promise<{ result: StreamProviderCompletionResult; target: ResolvedFallbackTarget }> {
+  for (let index = 0; ; index += 1) {
+    const target = params.chain[index];
+    const next = params.chain[index + 1];
+    let result: StreamProviderCompletionResult;
+    try {
+      result = await params.stream(target);
+    } catch (error) {
+      if (!next || params.signal.aborted || !isProviderFallbackError(error)) throw error;
+      params.onFallback({ from: target, to: next, reason: toAppError(error).message });
+      continue;
+    }
+    const empty = !params.signal.aborted && !result.content.trim() && result.toolTraces.length === 0;
+    if (!empty || !next) return { result, target };
+    params.onFallback({ from: target, to: next, reason: "The model returned an empty reply" });
+  }
+}
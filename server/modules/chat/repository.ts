@@ -1,26 +1,38 @@
 import { db, newId, now } from "../../db.js";
-import { resolveBranch, type MessageRow } from "./routeHelpers.js";
+import { messageToJson, resolveBranch, type MessageRow } from "./routeHelpers.js";
 
 export interface BranchSummary {
   id: string;
   chatId: string;
   name: string;
   parentMessageId: string | null;
+  messageCount: number;
+  active: boolean;
   createdAt: string;
 }
 
-function mapBranchRow(row: {
+interface BranchRow {
   id: string;
   chat_id: string;
   name: string;
   parent_message_id: string | null;
+  message_count: number;
   created_at: string;
-}): BranchSummary {
+}
+
+const BRANCH_SELECT = `
+  SELECT b.id, b.chat_id, b.name, b.parent_message_id, b.created_at,
+    (SELECT COUNT(*) FROM messages m WHERE m.chat_id = b.chat_id AND m.branch_id = b.id AND m.deleted = 0) AS message_count
+  FROM branches b`;
+
+function mapBranchRow(row: BranchRow, activeBranchId: string): BranchSummary {
   return {
     id: row.id,
     chatId: row.chat_id,
     name: row.name,
     parentMessageId: row.parent_message_id,
+    messageCount: row.message_count,
+    active: row.id === activeBranchId,
     createdAt: row.created_at
   };
 }
@@ -53,50 +65,71 @@ export function deleteMessageTree(chatId: string, branchId: string, messageId: s
   `).run(messageId, chatId, branchId, chatId, branchId);
 }
 
-export function deleteChatCascade(chatId: string) {
-  db.prepare("DELETE FROM messages WHERE chat_id = ?").run(chatId);
-  db.prepare("DELETE FROM branches WHERE chat_id = ?").run(chatId);
-  db.prepare("DELETE FROM prompt_blocks WHERE chat_id = ?").run(chatId);
-  try {
-    db.prepare("DELETE FROM rp_scene_state WHERE chat_id = ?").run(chatId);
-  } catch {
-    // Table might not exist in older databases.
-  }
-  try {
-    db.prepare("DELETE FROM rp_memory_entries WHERE chat_id = ?").run(chatId);
-  } catch {
-    // Table might not exist in older databases.
-  }
-  db.prepare("DELETE FROM chats WHERE id = ?").run(chatId);
+const CHAT_OWNED_TABLES = ["messages", "branches", "prompt_blocks", "rp_scene_state", "rp_memory_entries", "chat_rag_bindings"] as const;
+
+function tableExists(table: string): boolean {
+  return Boolean(db.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?").get(table));
 }
 
-export function listBranches(chatId: string): BranchSummary[] {
+export interface TrashedChat {
+  id: string;
+  title: string;
+  createdAt: string;
+  deletedAt: string;
+}
+
+/** Moves a live chat to the trash; it drops out of the chat list until restored or purged. */
+export function trashChat(chatId: string): boolean {
+  return db.prepare("UPDATE chats SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL").run(now(), chatId).changes > 0;
+}
+
+export function restoreChat(chatId: string): boolean {
+  return db.prepare("UPDATE chats SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL").run(chatId).changes > 0;
+}
+
+export function isChatTrashed(chatId: string): boolean {
+  return Boolean(db.prepare("SELECT 1 FROM chats WHERE id = ? AND deleted_at IS NOT NULL").get(chatId));
+}
+
+export function listTrashedChats(): TrashedChat[] {
+  const rows = db.prepare(
+    "SELECT id, title, created_at, deleted_at FROM chats WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"
+  ).all() as Array<{ id: string; title: string; created_at: string; deleted_at: string }>;
+  return rows.map((row) => ({ id: row.id, title: row.title, createdAt: row.created_at, deletedAt: row.deleted_at }));
+}
+
+/** Soft-deleted messages of a chat across branches, newest first. Tool traces come back with their parent. */
+export function listDeletedMessages(chatId: string) {
   const rows = db.prepare(
-    "SELECT id, chat_id, name, parent_message_id, created_at FROM branches WHERE chat_id = ? ORDER BY created_at ASC"
-  ).all(chatId) as Array<{
-    id: string;
-    chat_id: string;
-    name: string;
-    parent_message_id: string | null;
-    created_at: string;
-  }>;
-
-  if (rows.length > 0) {
-    return rows.map(mapBranchRow);
-  }
-
-  const branchId = resolveBranch(chatId);
-  const fallback = db.prepare(
-    "SELECT id, chat_id, name, parent_message_id, created_at FROM branches WHERE id = ?"
-  ).get(branchId) as {
-    id: string;
-    chat_id: string;
-    name: string;
-    parent_message_id: string | null;
-    created_at: string;
-  } | undefined;
-
-  return fallback ? [mapBranchRow(fallback)] : [];
+    "SELECT * FROM messages WHERE chat_id = ? AND deleted = 1 AND role != 'tool' ORDER BY created_at DESC, sort_order DESC"
+  ).all(chatId) as MessageRow[];
+  return rows.map(messageToJson);
+}
+
+/** Hard-deletes a chat's soft-deleted messages; returns how many rows were removed. */
+export function purgeDeletedMessages(chatId: string): number {
+  return db.prepare("DELETE FROM messages WHERE chat_id = ? AND deleted = 1").run(chatId).changes;
+}
+
+/**
+ * Removes a chat and every row keyed by its id in one transaction. Deletes are explicit per table
+ * so older databases without foreign keys are cleaned up too; writer scenes only lose the link.
+ */
+export function deleteChatCascade(chatId: string): boolean {
+  return db.transaction(() => {
+    for (const table of CHAT_OWNED_TABLES) {
+      if (tableExists(table)) db.prepare(`DELETE FROM ${table} WHERE chat_id = ?`).run(chatId);
+    }
+    db.prepare("UPDATE writer_scenes SET chat_id = NULL WHERE chat_id = ?").run(chatId);
+    return db.prepare("DELETE FROM chats WHERE id = ?").run(chatId).changes > 0;
+  })();
+}
+
+export function listBranches(chatId: string): BranchSummary[] {
+  // Resolving first also creates the "main" branch for a chat that has none yet.
+  const activeBranchId = resolveBranch(chatId);
+  const rows = db.prepare(`${BRANCH_SELECT} WHERE b.chat_id = ? ORDER BY b.created_at ASC`).all(chatId) as BranchRow[];
+  return rows.map((row) => mapBranchRow(row, activeBranchId));
 }
 
 export function renameBranch(chatId: string, branchId: string, name: string): BranchSummary | null {
@@ -105,16 +138,17 @@ export function renameBranch(chatId: string, branchId: string, name: string): Br
   ).run(name, branchId, chatId);
   if (result.changes === 0) return null;
 
-  const row = db.prepare(
-    "SELECT id, chat_id, name, parent_message_id, created_at FROM branches WHERE id = ? AND chat_id = ?"
-  ).get(branchId, chatId) as {
-    id: string;
-    chat_id: string;
-    name: string;
-    parent_message_id: string | null;
-    created_at: string;
-  };
-  return mapBranchRow(row);
+  const row = db.prepare(`${BRANCH_SELECT} WHERE b.id = ? AND b.chat_id = ?`).get(branchId, chatId) as BranchRow;
+  return mapBranchRow(row, resolveBranch(chatId));
+}
+
+/** Persists the branch that send and timeline requests use when they do not name one. */
+export function setActiveBranch(chatId: string, branchId: string): BranchSummary[] | null {
+  const branch = db.prepare("SELECT id FROM branches WHERE id = ? AND chat_id = ?")
+    .get(branchId, chatId) as { id: string } | undefined;
+  if (!branch) return null;
+  db.prepare("UPDATE chats SET active_branch_id = ? WHERE id = ?").run(branchId, chatId);
+  return listBranches(chatId);
 }
 
 export type DeleteBranchResult =
@@ -133,10 +167,11 @@ export function deleteBranch(chatId: string, branchId: string): DeleteBranchResu
   db.transaction(() => {
     db.prepare("DELETE FROM messages WHERE chat_id = ? AND branch_id = ?").run(chatId, branchId);
     db.prepare("DELETE FROM branches WHERE id = ? AND chat_id = ?").run(branchId, chatId);
+    db.prepare("UPDATE chats SET active_branch_id = NULL WHERE id = ? AND active_branch_id = ?").run(chatId, branchId);
   })();
 
   const branches = listBranches(chatId);
-  return { ok: true, activeBranchId: branches[0].id, branches };
+  return { ok: true, activeBranchId: resolveBranch(chatId), branches };
 }
 
 export function forkBranch(chatId: string, parentMessageId: string, name?: string): BranchSummary | null {
@@ -156,11 +191,13 @@ export function forkBranch(chatId: string, parentMessageId: string, name?: strin
     "INSERT INTO branches (id, chat_id, name, parent_message_id, created_at) VALUES (?, ?, ?, ?, ?)"
   );
   const insertMessage = db.prepare(
-    "INSERT INTO messages (id, chat_id, branch_id, role, content, attachments, token_count, parent_id, deleted, created_at, character_name, sort_order) VALUES (?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?)"
+    "INSERT INTO messages (id, chat_id, branch_id, role, content, attachments, token_count, parent_id, deleted, created_at, character_name, sort_order, pinned) VALUES (?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, ?)"
   );
 
   const forkTx = db.transaction(() => {
     insertBranch.run(branchId, chatId, branchName, parentMessageId, createdAt);
+    // The UI moves to a new fork right away, so it becomes the chat's active branch.
+    db.prepare("UPDATE chats SET active_branch_id = ? WHERE id = ?").run(branchId, chatId);
     const idMap = new Map<string, string>();
     sourceRows.forEach((row, index) => {
       const copiedId = newId();
@@ -177,7 +214,8 @@ export function forkBranch(chatId: string, parentMessageId: string, name?: strin
         mappedParentId,
         row.created_at,
         row.character_name || null,
-        index + 1
+        index + 1,
+        row.pinned ? 1 : 0
       );
     });
   });
@@ -188,6 +226,74 @@ export function forkBranch(chatId: string, parentMessageId: string, name?: strin
     chatId,
     name: branchName,
     parentMessageId,
+    messageCount: sourceRows.length,
+    active: true,
     createdAt
   };
 }
+
+export type MergeBranchResult =
+  | { ok: true; appended: number; sourceDeleted: boolean; branches: BranchSummary[] }
+  | { ok: false; reason: "not_found" | "same_branch" };
+
+function sameMessage(a: MessageRow, b: MessageRow): boolean {
+  return a.role === b.role && a.content === b.content && a.created_at === b.created_at;
+}
+
+function nextTimestamp(candidate: string, previous: string | undefined): string {
+  if (!previous || candidate > previous) return candidate;
+  return new Date(Date.parse(previous) + 1).toISOString();
+}
+
+/**
+ * Appends the source branch's messages after the fork point onto the end of the target branch. Forks
+ * copy their ancestry with the original role, content and created_at, so the fork point is where the two
+ * timelines stop matching. Appended rows get new ids, follow-on sort orders and increasing timestamps.
+ */
+export function mergeBranch(chatId: string, sourceBranchId: string, targetBranchId: string, deleteSource: boolean): MergeBranchResult {
+  if (sourceBranchId === targetBranchId) return { ok: false, reason: "same_branch" };
+  const branchCount = db.prepare("SELECT COUNT(*) AS count FROM branches WHERE chat_id = ? AND id IN (?, ?)")
+    .get(chatId, sourceBranchId, targetBranchId) as { count: number };
+  if (branchCount.count < 2) return { ok: false, reason: "not_found" };
+
+  const selectTimeline = db.prepare(
+    "SELECT * FROM messages WHERE chat_id = ? AND branch_id = ? AND deleted = 0 ORDER BY sort_order ASC, created_at ASC, id ASC"
+  );
+  const source = selectTimeline.all(chatId, sourceBranchId) as MessageRow[];
+  const target = selectTimeline.all(chatId, targetBranchId) as MessageRow[];
+  let forkPoint = 0;
+  while (forkPoint < source.length && forkPoint < target.length && sameMessage(source[forkPoint], target[forkPoint])) forkPoint += 1;
+  const appended = source.slice(forkPoint);
+
+  const columns = (db.prepare("PRAGMA table_info(messages)").all() as Array<{ name: string }>).map((column) => column.name);
+  const insert = db.prepare(
+    `INSERT INTO messages (${columns.map((column) => `"${column}"`).join(", ")}) VALUES (${columns.map(() => "?").join(", ")})`
+  );
+
+  db.transaction(() => {
+    const idMap = new Map<string, string>();
+    let previous = target[target.length - 1];
+    let sortOrder = target.reduce((max, row) => Math.max(max, row.sort_order), 0);
+    for (const row of appended) {
+      sortOrder += 1;
+      const copy: Record<string, unknown> = {
+        ...row,
+        id: newId(),
+        branch_id: targetBranchId,
+        parent_id: (row.parent_id && idMap.get(row.parent_id)) || previous?.id || null,
+        sort_order: sortOrder,
+        created_at: nextTimestamp(row.created_at, previous?.created_at)
+      };
+      idMap.set(row.id, copy.id as string);
+      insert.run(...columns.map((column) => copy[column] ?? null));
+      previous = copy as unknown as MessageRow;
+    }
+    if (deleteSource) {
+      db.prepare("DELETE FROM messages WHERE chat_id = ? AND branch_id = ?").run(chatId, sourceBranchId);
+      db.prepare("DELETE FROM branches WHERE id = ? AND chat_id = ?").run(sourceBranchId, chatId);
+    }
+    db.prepare("UPDATE chats SET active_branch_id = ? WHERE id = ?").run(targetBranchId, chatId);
+  })();
+
+  return { ok: true, appended: appended.length, sourceDeleted: deleteSource, branches: listBranches(chatId) };
+}
@@ -2,6 +2,7 @@ import { db, DEFAULT_SETTINGS, newId, now } from "../../db.js";
 import { DEFAULT_PROMPT_BLOCKS, type CharacterCardData, type PromptBlock } from "../../domain/rpEngine.js";
 import { normalizeApiParamPolicy } from "../../services/apiParamPolicy.js";
 import type { RagContextSource } from "../../services/rag.js";
+import { normalizeProviderFallbacks } from "../../services/providerFallbacks.js";
 import { normalizeRuntimeTuningSettings } from "../../services/runtimeTuning.js";
 
 const PROMPT_BLOCK_KINDS = new Set(["system", "jailbreak", "character", "author_note", "lore", "scene", "history"]);
@@ -23,6 +24,10 @@ export interface MessageRow {
   generation_started_at: string | null;
   generation_completed_at: string | null;
   generation_duration_ms: number | null;
+  truncated?: number | null;
+  pinned?: number | null;
+  prompt_tokens?: number | null;
+  completion_tokens?: number | null;
   character_name: string | null;
   sort_order: number;
 }
@@ -45,6 +50,10 @@ export interface ProviderRow {
   full_local_only: number;
   provider_type: string;
   adapter_id?: string | null;
+  extra_headers?: string | null;
+  rate_limit_rpm?: number | null;
+  auth_mode?: string | null;
+  auth_param?: string | null;
 }
 
 export interface LoreBookRow {
@@ -87,14 +96,23 @@ export function messageToJson(row: MessageRow) {
     generationStartedAt: row.generation_started_at || undefined,
     generationCompletedAt: row.generation_completed_at || undefined,
     generationDurationMs: typeof row.generation_duration_ms === "number" ? row.generation_duration_ms : undefined,
+    truncated: row.truncated ? true : undefined,
+    pinned: row.pinned ? true : undefined,
+    promptTokens: typeof row.prompt_tokens === "number" ? row.prompt_tokens : undefined,
+    completionTokens: typeof row.completion_tokens === "number" ? row.completion_tokens : undefined,
     parentId: row.parent_id,
     characterName: row.character_name || undefined,
     ragSources
   };
 }
 
+/** An explicit branch wins, then the chat's persisted active branch, then its earliest branch. */
 export function resolveBranch(chatId: string, branchId?: string): string {
   if (branchId) return branchId;
+  const active = db.prepare(
+    "SELECT b.id FROM chats c JOIN branches b ON b.id = c.active_branch_id AND b.chat_id = c.id WHERE c.id = ?"
+  ).get(chatId) as { id: string } | undefined;
+  if (active) return active.id;
   const row = db.prepare("SELECT id FROM branches WHERE chat_id = ? ORDER BY created_at ASC LIMIT 1")
     .get(chatId) as { id: string } | undefined;
   if (row) return row.id;
@@ -104,11 +122,69 @@ export function resolveBranch(chatId: string, branchId?: string): string {
   return id;
 }
 
+/**
+ * A branch's rows are its whole conversation: forkBranch copies the parent branch up to the fork point
+ * into the new branch, so nested forks need no ancestry walk here (and adding one would duplicate rows).
+ */
 export function getTimeline(chatId: string, branchId: string) {
   const rows = db.prepare(
-    "SELECT * FROM messages WHERE chat_id = ? AND branch_id = ? AND deleted = 0 ORDER BY sort_order ASC, created_at ASC"
+    "SELECT * FROM messages WHERE chat_id = ? AND branch_id = ? AND deleted = 0 ORDER BY sort_order ASC, created_at ASC, id ASC"
   ).all(chatId, branchId) as MessageRow[];
-  return rows.map(messageToJson);
+  return withSwipePositions(chatId, branchId, rows);
+}
+
+/**
+ * `messages.deleted` value for a swipe that is kept but not selected. Everything reading live rows filters
+ * on `deleted = 0` and the trash on `deleted = 1`, so hidden swipes stay out of both.
+ */
+export const SWIPE_HIDDEN = 2;
+
+/** Assistant replies to the same parent message are swipes; the selected one carries its 1-based position. */
+function withSwipePositions(chatId: string, branchId: string, rows: MessageRow[]) {
+  const replies = db.prepare(
+    `SELECT id, parent_id FROM messages
+     WHERE chat_id = ? AND branch_id = ? AND role = 'assistant' AND parent_id IS NOT NULL AND deleted IN (0, ${SWIPE_HIDDEN})
+     ORDER BY created_at ASC, id ASC`
+  ).all(chatId, branchId) as Array<{ id: string; parent_id: string }>;
+  const groups = new Map<string, string[]>();
+  for (const reply of replies) groups.set(reply.parent_id, [...(groups.get(reply.parent_id) ?? []), reply.id]);
+  return rows.map((row) => {
+    const group = row.role === "assistant" && row.parent_id ? groups.get(row.parent_id) : undefined;
+    const json = messageToJson(row);
+    return group && group.length > 1 ? { ...json, swipeIndex: group.indexOf(row.id) + 1, swipeCount: group.length } : json;
+  });
+}
+
+export const MAX_TIMELINE_PAGE_SIZE = 500;
+
+/**
+ * The `limit` most recent messages before the `before` message (or the newest ones without a cursor), in
+ * timeline order. Null when the cursor is not a live message of this branch.
+ */
+export function getTimelinePage(chatId: string, branchId: string, limit: number, before?: string) {
+  let cursor: { sort_order: number; created_at: string; id: string } | undefined;
+  if (before) {
+    cursor = db.prepare(
+      "SELECT sort_order, created_at, id FROM messages WHERE id = ? AND chat_id = ? AND branch_id = ? AND deleted = 0"
+    ).get(before, chatId, branchId) as typeof cursor;
+    if (!cursor) return null;
+  }
+  const rows = db.prepare(`
+    SELECT * FROM messages
+    WHERE chat_id = ? AND branch_id = ? AND deleted = 0
+      ${cursor ? "AND (sort_order, created_at, id) < (?, ?, ?)" : ""}
+    ORDER BY sort_order DESC, created_at DESC, id DESC
+    LIMIT ?
+  `).all(
+    chatId,
+    branchId,
+    ...(cursor ? [cursor.sort_order, cursor.created_at, cursor.id] : []),
+    limit + 1
+  ) as MessageRow[];
+  return {
+    messages: withSwipePositions(chatId, branchId, rows.slice(0, limit).reverse()),
+    hasMore: rows.length > limit
+  };
 }
 
 export function normalizePromptStack(raw: unknown): PromptBlock[] {
@@ -145,6 +221,36 @@ export function getPromptBlocks(settings: Record<string, unknown>): PromptBlock[
   return normalizePromptStack((settings as { promptStack?: unknown }).promptStack);
 }
 
+/** Per-chat stack saved from the chat controls, falling back to the global prompt stack. */
+export function getChatPromptBlocks(chatId: string, settings: Record<string, unknown>): PromptBlock[] {
+  const rows = db.prepare("SELECT id, kind, enabled, ordering, content FROM prompt_blocks WHERE chat_id = ? ORDER BY ordering ASC")
+    .all(chatId) as Array<{ id: string; kind: string; enabled: number; ordering: number; content: string }>;
+  if (rows.length === 0) return getPromptBlocks(settings);
+  return normalizePromptStack(rows.map((row) => ({
+    id: row.id,
+    kind: row.kind,
+    enabled: row.enabled === 1,
+    order: row.ordering,
+    content: row.content
+  })));
+}
+
+/** Context length from the provider's cached model listing, when the listing reported one. */
+export function getCachedModelContextLength(providerId: string | null | undefined, modelId: string | null | undefined): number | null {
+  if (!providerId || !modelId) return null;
+  const row = db.prepare("SELECT models_json FROM provider_models WHERE provider_id = ?")
+    .get(providerId) as { models_json: string } | undefined;
+  if (!row) return null;
+  try {
+    const models = JSON.parse(row.models_json) as Array<{ id?: unknown; contextLength?: unknown }>;
+    const match = Array.isArray(models) ? models.find((model) => model?.id === modelId) : undefined;
+    const contextLength = Number(match?.contextLength);
+    return Number.isFinite(contextLength) && contextLength > 0 ? contextLength : null;
+  } catch {
+    return null;
+  }
+}
+
 export function getSettings() {
   const row = db.prepare("SELECT payload FROM settings WHERE id = 1").get() as { payload: string };
   const stored = JSON.parse(row.payload);
@@ -155,6 +261,7 @@ export function getSettings() {
     ...DEFAULT_SETTINGS,
     ...stored,
     ...normalizeRuntimeTuningSettings(stored),
+    providerFallbacks: normalizeProviderFallbacks(stored.providerFallbacks),
     includeReasoningInContext: stored.includeReasoningInContext !== false,
     agentsEnabled: stored.agentsEnabled === true,
     agentWorkspaceToolsEnabled: stored.agentWorkspaceToolsEnabled !== false,
@@ -226,6 +333,14 @@ export function pickInitialGreeting(mainGreeting: string, alternateGreetings: st
   return main || alternates[0] || "";
 }
 
+/** Greeting by position: 0 is the card's first message, 1..n its non-empty alternate greetings. Null when out of range. */
+export function pickGreetingAt(mainGreeting: string, alternateGreetings: string[], index: number): string | null {
+  if (!Number.isInteger(index) || index < 0) return null;
+  if (index === 0) return String(mainGreeting || "").trim();
+  const alternates = alternateGreetings.map((item) => String(item || "").trim()).filter(Boolean);
+  return alternates[index - 1] ?? null;
+}
+
 export function buildCompactContextPolicy(params: { charName?: string; userName: string }): string {
   const lines = [
     "[Context Policy]",
@@ -1,11 +1,59 @@
 import type { Request, Response } from "express";
 import { db } from "../../db.js";
 
+// [min, max, integer] for every numeric sampler field a chat may override.
+const NUMERIC_SAMPLER_FIELDS: Record<string, [number, number, boolean]> = {
+  temperature: [0, 5, false],
+  topP: [0, 1, false],
+  frequencyPenalty: [-2, 2, false],
+  presencePenalty: [-2, 2, false],
+  maxTokens: [1, 131072, true],
+  topK: [0, 1000, true],
+  topA: [0, 1, false],
+  minP: [0, 1, false],
+  typical: [0, 1, false],
+  tfs: [0, 1, false],
+  nSigma: [0, 10, false],
+  repetitionPenalty: [0, 10, false],
+  repetitionPenaltyRange: [0, 131072, true],
+  repetitionPenaltySlope: [0, 10, false]
+};
+
+function toStringList(value: unknown, limit: number): string[] {
+  return Array.isArray(value) ? value.map((item) => String(item ?? "")).filter(Boolean).slice(0, limit) : [];
+}
+
+/**
+ * Keeps only the sampler fields the chat actually sets, clamped to sane ranges; everything else
+ * falls through to the global sampler config. Returns null when nothing usable is left.
+ */
+export function normalizeChatSamplerOverride(raw: unknown): Record<string, unknown> | null {
+  if (!raw || typeof raw !== "object" || Array.isArray(raw)) return null;
+  const input = raw as Record<string, unknown>;
+  const out: Record<string, unknown> = {};
+  for (const [key, [min, max, integer]] of Object.entries(NUMERIC_SAMPLER_FIELDS)) {
+    if (input[key] === null || input[key] === undefined || input[key] === "") continue;
+    const value = Number(input[key]);
+    if (!Number.isFinite(value)) continue;
+    const clamped = Math.min(max, Math.max(min, value));
+    out[key] = integer ? Math.floor(clamped) : clamped;
+  }
+  if (Array.isArray(input.stop)) out.stop = toStringList(input.stop, 16);
+  if (Array.isArray(input.samplerOrder)) {
+    out.samplerOrder = input.samplerOrder.map(Number).filter((item) => Number.isInteger(item) && item >= 0).slice(0, 16);
+  }
+  if (typeof input.koboldMemory === "string") out.koboldMemory = input.koboldMemory;
+  if (Array.isArray(input.koboldBannedPhrases)) out.koboldBannedPhrases = toStringList(input.koboldBannedPhrases, 200);
+  if (typeof input.koboldUseDefaultBadwords === "boolean") out.koboldUseDefaultBadwords = input.koboldUseDefaultBadwords;
+  return Object.keys(out).length > 0 ? out : null;
+}
+
+/** Saves the chat's sampler override; a null or empty config clears it back to the global sampler. */
 export function updateChatSampler(req: Request, res: Response) {
   const chatId = req.params.id;
-  const { samplerConfig } = req.body;
-  db.prepare("UPDATE chats SET sampler_config = ? WHERE id = ?").run(JSON.stringify(samplerConfig), chatId);
-  res.json({ ok: true });
+  const samplerConfig = normalizeChatSamplerOverride(req.body?.samplerConfig);
+  db.prepare("UPDATE chats SET sampler_config = ? WHERE id = ?").run(samplerConfig ? JSON.stringify(samplerConfig) : null, chatId);
+  res.json({ ok: true, samplerConfig });
 }
 
 export function getChatSampler(req: Request, res: Response) {
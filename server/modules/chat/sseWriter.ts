@@ -0,0 +1,33 @@
+import type { Response } from "express";
+
+// A chat reply is generated and saved whether or not the client is still reading it. Writes to the event stream
+// can fail mid-reply (socket torn down, a proxy erroring), so they are logged once per response and dropped
+// instead of aborting generation and losing the text received so far.
+
+const failedResponses = new WeakSet<Response>();
+
+/** Writes one `data:` event and flushes. Returns false instead of throwing when the response cannot take it. */
+export function writeSseEvent(res: Response, payload: Record<string, unknown>): boolean {
+  if (res.writableEnded || res.destroyed) return false;
+  try {
+    res.write(`data: ${JSON.stringify(payload)}\n\n`);
+    (res as Response & { flush?: () => void }).flush?.();
+    return true;
+  } catch (error) {
+    if (!failedResponses.has(res)) {
+      failedResponses.add(res);
+      console.warn("Chat stream write failed; the reply is still generated and saved", error);
+    }
+    return false;
+  }
+}
+
+/** Ends the stream unless it already ended; errors from a torn-down socket are ignored. */
+export function endSseResponse(res: Response): void {
+  if (res.writableEnded) return;
+  try {
+    res.end();
+  } catch {
+    // The client is gone; nothing left to tell it.
+  }
+}
@@ -0,0 +1,31 @@
+import { describe, expect, it } from "vitest";
+import { consumeStopChunk, createStopGuard, flushStopGuard, trimAtStopSequence } from "./stopSequences.js";
+
+describe("stop sequence guard", () => {
+  it("cuts a stop string that straddles stream chunks", () => {
+    const state = createStopGuard(["\nUser:"]);
+    const emitted = ["The lamp flickers.", "\nUs", "er: I take over", " your turn"]
+      .map((chunk) => consumeStopChunk(state, chunk))
+      .join("") + flushStopGuard(state);
+    expect(emitted).toBe("The lamp flickers.");
+  });
+
+  it("releases held-back text when the partial match never completes", () => {
+    const state = createStopGuard(["\nUser:"]);
+    expect(consumeStopChunk(state, "Line one\nUs")).toBe("Line one");
+    expect(consumeStopChunk(state, "ually quiet.")).toBe("\nUsually quiet.");
+    expect(consumeStopChunk(state, "\n")).toBe("");
+    expect(flushStopGuard(state)).toBe("\n");
+  });
+
+  it("passes chunks through untouched without stop strings", () => {
+    const state = createStopGuard([" ", ""]);
+    expect(consumeStopChunk(state, "Hello\n")).toBe("Hello\n");
+    expect(flushStopGuard(state)).toBe("");
+  });
+
+  it("trims complete text at the earliest stop string", () => {
+    expect(trimAtStopSequence("Reply.\n###\nUser: more", ["\nUser:", "\n###"])).toBe("Reply.");
+    expect(trimAtStopSequence("Reply.", ["\nUser:"])).toBe("Reply.");
+  });
+});
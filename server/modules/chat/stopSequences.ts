@@ -0,0 +1,68 @@
+// Local guard for sampler stop sequences. Providers are asked to stop on their own, but some ignore
+// the parameter, so streamed content is cut at the first stop string before it reaches the client.
+
+export interface StopGuardState {
+  stops: string[];
+  pending: string;
+  stopped: boolean;
+}
+
+/** Keeps leading and trailing whitespace, since stops like "\nUser:" depend on it. */
+export function normalizeStopSequences(raw: unknown): string[] {
+  if (!Array.isArray(raw)) return [];
+  return raw.map((item) => String(item ?? "")).filter((item) => item.trim()).slice(0, 32);
+}
+
+export function createStopGuard(raw: unknown): StopGuardState {
+  return { stops: normalizeStopSequences(raw), pending: "", stopped: false };
+}
+
+function firstStopIndex(text: string, stops: string[]): number {
+  let first = -1;
+  for (const stop of stops) {
+    const index = text.indexOf(stop);
+    if (index >= 0 && (first < 0 || index < first)) first = index;
+  }
+  return first;
+}
+
+/** Length of the longest tail of `text` that could still grow into a stop string. */
+function trailingStopPrefixLength(text: string, stops: string[]): number {
+  const longest = Math.max(0, ...stops.map((stop) => stop.length - 1));
+  for (let size = Math.min(text.length, longest); size > 0; size -= 1) {
+    const suffix = text.slice(-size);
+    if (stops.some((stop) => stop.startsWith(suffix))) return size;
+  }
+  return 0;
+}
+
+export function trimAtStopSequence(text: string, raw: unknown): string {
+  const index = firstStopIndex(text, normalizeStopSequences(raw));
+  return index >= 0 ? text.slice(0, index) : text;
+}
+
+/**
+ * Returns the part of a streamed chunk that is safe to emit. A tail that might be the start of a
+ * stop string straddling the next chunk is held back until the next call or `flushStopGuard`.
+ */
+export function consumeStopChunk(state: StopGuardState, chunk: string): string {
+  if (state.stopped) return "";
+  if (state.stops.length === 0) return chunk;
+  const text = state.pending + chunk;
+  const index = firstStopIndex(text, state.stops);
+  if (index >= 0) {
+    state.pending = "";
+    state.stopped = true;
+    return text.slice(0, index);
+  }
+  const held = trailingStopPrefixLength(text, state.stops);
+  state.pending = held > 0 ? text.slice(-held) : "";
+  return held > 0 ? text.slice(0, -held) : text;
+}
+
+/** Releases held-back text once the stream ends without completing a stop string. */
+export function flushStopGuard(state: StopGuardState): string {
+  const rest = state.stopped ? "" : state.pending;
+  state.pending = "";
+  return rest;
+}
@@ -1,12 +1,13 @@
 import { buildOpenAiSamplingPayload } from "../../services/apiParamPolicy.js";
 import { coalesceSystemMessages } from "../../domain/rpEngine.js";
 import { prepareMcpTools, type McpServerConfig } from "../../services/mcp.js";
+import { normalizeOpenAiBaseUrl, providerAuthHeaders, providerRateLimitOptions, readProviderErrorDetail, withProviderAuthUrl, withProviderHeaders } from "../../services/providerApi.js";
 import { fetchProviderResponse } from "../../services/providerHttp.js";
 import type { ProviderRow } from "./routeHelpers.js";
 import {
   consumeSseEventBlocks,
+  createOpenAiStreamTextReader,
   extractOpenAiStreamErrorMessage,
-  extractOpenAiStreamTextDelta,
   extractSseEventData,
   extractSseEventType
 } from "./openAiStream.js";
@@ -36,6 +37,9 @@ export interface ToolCallTrace {
   name: string;
   args: string;
   result: string;
+  /** Set for caller-defined tools; `pending` stays true until the client submits the result. */
+  client?: boolean;
+  pending?: boolean;
 }
 
 interface MarkdownImageMatch {
@@ -643,22 +647,22 @@ async function requestChatCompletion(
   body: Record<string, unknown>,
   signal: AbortSignal
 ) {
-  const baseUrl = String(provider.base_url || "").replace(/\/+$/, "");
+  const baseUrl = normalizeOpenAiBaseUrl(provider.base_url);
   const requestBody = Array.isArray(body.messages)
     ? { ...body, messages: prepareOpenAiCompatibleMessages(baseUrl, body.messages as OpenAICompletionMessage[]) }
     : body;
-  const response = await fetchProviderResponse(`${baseUrl}/chat/completions`, {
+  const response = await fetchProviderResponse(withProviderAuthUrl(provider, `${baseUrl}/chat/completions`), {
     method: "POST",
-    headers: {
+    headers: withProviderHeaders(provider, {
       "Content-Type": "application/json",
-      Authorization: `Bearer ${provider.api_key_cipher}`
-    },
+      ...providerAuthHeaders(provider)
+    }),
     body: JSON.stringify({ model: modelId, ...requestBody }),
     signal
-  });
+  }, providerRateLimitOptions(provider));
   if (!response.ok) {
-    const errText = await response.text().catch(() => "Unknown error");
-    throw new Error(`[API Error: ${response.status}] ${errText.slice(0, 500)}`);
+    const detail = await readProviderErrorDetail(response);
+    throw new Error(`[API Error: ${response.status}] ${detail}`);
   }
   return response.json() as Promise<{
     assistantWasStreamed?: boolean;
@@ -692,22 +696,22 @@ async function requestChatCompletionStream(
     };
   }>;
 }> {
-  const baseUrl = String(provider.base_url || "").replace(/\/+$/, "");
+  const baseUrl = normalizeOpenAiBaseUrl(provider.base_url);
   const requestBody = Array.isArray(body.messages)
     ? { ...body, messages: prepareOpenAiCompatibleMessages(baseUrl, body.messages as OpenAICompletionMessage[]) }
     : body;
-  const response = await fetchProviderResponse(`${baseUrl}/chat/completions`, {
+  const response = await fetchProviderResponse(withProviderAuthUrl(provider, `${baseUrl}/chat/completions`), {
     method: "POST",
-    headers: {
+    headers: withProviderHeaders(provider, {
       "Content-Type": "application/json",
-      Authorization: `Bearer ${provider.api_key_cipher}`
-    },
+      ...providerAuthHeaders(provider)
+    }),
     body: JSON.stringify({ model: modelId, ...requestBody, stream: true }),
     signal
-  });
+  }, providerRateLimitOptions(provider));
   if (!response.ok || !response.body) {
-    const errText = await response.text().catch(() => "Unknown error");
-    throw new Error(`[API Error: ${response.status}] ${errText.slice(0, 500)}`);
+    const detail = await readProviderErrorDetail(response);
+    throw new Error(`[API Error: ${response.status}] ${detail}`);
   }
   const contentType = String(response.headers.get("content-type") || "").toLowerCase();
   if (!contentType.includes("text/event-stream")) {
@@ -722,6 +726,7 @@ async function requestChatCompletionStream(
   const startedCallIds = new Set<string>();
   const thinkState = createThinkStreamState();
   let buffer = "";
+  const readStreamText = createOpenAiStreamTextReader();
   let guardedAssistantBuffer = "";
   let guardedAssistantHeldForToolSyntax = false;
   let assistantWasStreamed = false;
@@ -894,7 +899,7 @@ async function requestChatCompletionStream(
       if (reasoningDelta) {
         appendReasoningDelta(reasoningDelta);
       }
-      const textDelta = extractOpenAiStreamTextDelta(parsed);
+      const textDelta = readStreamText(parsed);
       if (textDelta) {
         appendAssistantDelta(textDelta);
       }
@@ -940,6 +945,7 @@ async function requestChatCompletionStream(
     }
   }
 
+  buffer += decoder.decode();
   const flushed = consumeSseEventBlocks(buffer, true);
   for (const eventBlock of flushed.events) {
     processEventBlock(eventBlock);
@@ -1221,6 +1227,92 @@ export async function runToolCallingCompletion(params: {
   }
 }
 
+/**
+ * One streamed completion that offers caller-defined tools. The calls the model makes are returned, not
+ * executed; the caller hands them to the client and continues the turn once results come back.
+ */
+export async function runClientToolPass(params: {
+  provider: ProviderRow;
+  modelId: string;
+  samplerConfig: Record<string, unknown>;
+  apiMessages: OpenAICompletionMessage[];
+  settings: Record<string, unknown>;
+  tools: Array<{ type: "function"; function: { name: string; description: string; parameters: Record<string, unknown> } }>;
+  signal: AbortSignal;
+  onToolEvent?: (event: ToolCallStreamEvent) => void;
+  onAssistantDelta?: (delta: string) => void;
+  onReasoningDelta?: (delta: string) => void;
+}): Promise<{ content: string; reasoning: string; toolCalls: Array<{ callId: string; name: string; args: string }>; assistantWasStreamed: boolean }> {
+  const openAiSampling = buildOpenAiSamplingPayload({
+    samplerConfig: params.samplerConfig,
+    apiParamPolicy: params.settings.apiParamPolicy,
+    fields: ["temperature", "topP", "frequencyPenalty", "presencePenalty", "maxTokens", "stop"],
+    defaults: {
+      temperature: 0.9,
+      topP: 1,
+      frequencyPenalty: 0,
+      presencePenalty: 0,
+      maxTokens: 2048
+    }
+  });
+  const completionRequest = {
+    messages: params.apiMessages,
+    ...openAiSampling,
+    tools: params.tools
+  };
+  let body: Awaited<ReturnType<typeof requestChatCompletionStream>>;
+  let streamedDirectly = true;
+  try {
+    body = await requestChatCompletionStream(
+      params.provider,
+      params.modelId,
+      completionRequest,
+      params.signal,
+      params.onToolEvent,
+      params.onAssistantDelta,
+      params.onReasoningDelta,
+      { bufferAssistantDeltas: true }
+    );
+  } catch (streamErr) {
+    const streamMessage = streamErr instanceof Error ? streamErr.message : "";
+    if (!/stream|sse|event-stream/i.test(streamMessage)) throw streamErr;
+    body = await requestChatCompletion(params.provider, params.modelId, {
+      ...completionRequest,
+      stream: false
+    }, params.signal);
+    streamedDirectly = false;
+  }
+
+  const assistant = body.choices?.[0]?.message as { content?: unknown; tool_calls?: OpenAIToolCall[]; reasoning?: unknown } | undefined;
+  let content = normalizeAssistantContent(assistant?.content);
+  let rawCalls = Array.isArray(assistant?.tool_calls) ? assistant.tool_calls : [];
+  if (rawCalls.length === 0 && !streamedDirectly) {
+    const extracted = extractTextToolCalls(content, params.tools.map((tool) => tool.function.name));
+    rawCalls = extracted.toolCalls;
+    content = extracted.visibleContent;
+  }
+  const toolCalls = rawCalls
+    .map((call, index) => {
+      const name = String(call.function?.name || "");
+      return {
+        callId: String(call.id || `${name || "tool"}_${index + 1}`),
+        name,
+        args: String(call.function?.arguments || "")
+      };
+    })
+    .filter((call) => call.name);
+  let assistantWasStreamed = body.assistantWasStreamed === true;
+  if (!assistantWasStreamed) {
+    assistantWasStreamed = emitAssistantTextDeltas(content, params.onAssistantDelta);
+  }
+  return {
+    content,
+    reasoning: typeof assistant?.reasoning === "string" ? assistant.reasoning : "",
+    toolCalls,
+    assistantWasStreamed
+  };
+}
+
 export function serializeToolTrace(trace: ToolCallTrace, maxResultChars = 12000): string {
   const name = String(trace.name || "unknown_tool").trim();
   const args = String(trace.args || "").trim();
@@ -1232,6 +1324,7 @@ export function serializeToolTrace(trace: ToolCallTrace, maxResultChars = 12000)
     callId: String(trace.callId || "").trim(),
     name,
     args: safeArgs,
-    result: safeResult
+    result: safeResult,
+    ...(trace.client ? { client: true, pending: trace.pending === true } : {})
   });
 }
@@ -0,0 +1,94 @@
+import type { Response } from "express";
+import { db, isLocalhostUrl } from "../../db.js";
+import { AppError } from "../../services/appError.js";
+import { unifiedGenerateText, type UnifiedGenerateMessage } from "../../services/unifiedGeneration.js";
+import { streamProviderCompletion } from "../chat/providerExecution.js";
+import { getSettings, type ProviderRow } from "../chat/routeHelpers.js";
+
+// One-shot and streamed completions against a configured provider, for features that are not a chat turn
+// (titles, summaries, translation, writer tools). Provider lookup, Full Local Mode and the provider's own
+// localhost-only flag are checked here so every caller refuses the same requests.
+
+export type LlmUnavailableReason = "not_configured" | "provider_not_found" | "full_local_mode" | "local_only";
+
+export class LlmUnavailableError extends AppError {
+  constructor(message: string, readonly reason: LlmUnavailableReason) {
+    super("not_configured", message, 400);
+    this.name = "LlmUnavailableError";
+  }
+}
+
+export interface ResolvedLlm {
+  provider: ProviderRow;
+  modelId: string;
+  settings: ReturnType<typeof getSettings>;
+}
+
+export interface LlmTarget {
+  /** Defaults to the active provider and model from settings. */
+  providerId?: string | null;
+  modelId?: string | null;
+}
+
+export function resolveLlm(target: LlmTarget = {}): ResolvedLlm {
+  const settings = getSettings();
+  const providerId = String(target.providerId ?? settings.activeProviderId ?? "").trim();
+  const modelId = String(target.modelId ?? settings.activeModel ?? "").trim();
+  if (!providerId || !modelId) {
+    throw new LlmUnavailableError("No provider configured", "not_configured");
+  }
+  const provider = db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as ProviderRow | undefined;
+  if (!provider) {
+    throw new LlmUnavailableError("Provider not found", "provider_not_found");
+  }
+  if (settings.fullLocalMode === true && !isLocalhostUrl(provider.base_url)) {
+    throw new LlmUnavailableError("Provider blocked by Full Local Mode", "full_local_mode");
+  }
+  if (provider.full_local_only && !isLocalhostUrl(provider.base_url)) {
+    throw new LlmUnavailableError("Provider requires localhost endpoint", "local_only");
+  }
+  return { provider, modelId, settings };
+}
+
+export interface LlmCompleteParams extends LlmTarget {
+  /** Merged over nothing: callers pass the sampler fields they care about. */
+  samplerConfig?: Record<string, unknown>;
+  signal?: AbortSignal;
+}
+
+/** Non-streamed completion; `<think>` blocks are stripped and provider HTTP errors are thrown with their message. */
+export async function completeLlm(messages: UnifiedGenerateMessage[], params: LlmCompleteParams = {}): Promise<string> {
+  const { provider, modelId, settings } = resolveLlm(params);
+  const result = await unifiedGenerateText({
+    provider,
+    modelId,
+    messages,
+    samplerConfig: params.samplerConfig,
+    apiParamPolicy: settings.apiParamPolicy,
+    signal: params.signal
+  });
+  return result.content.trim();
+}
+
+/**
+ * Streams a completion as chat-style `delta` SSE events on `res`, keyed by `streamId`, and resolves with the
+ * full text. Retries follow the stream retry setting like chat generation does.
+ */
+export async function streamLlm(
+  messages: Array<{ role: string; content: unknown }>,
+  params: LlmCompleteParams & { streamId: string; res: Response; signal: AbortSignal }
+): Promise<string> {
+  const { provider, modelId, settings } = resolveLlm(params);
+  const result = await streamProviderCompletion({
+    provider,
+    modelId,
+    messages,
+    samplerConfig: params.samplerConfig ?? {},
+    apiParamPolicy: settings.apiParamPolicy,
+    chatId: params.streamId,
+    res: params.res,
+    signal: params.signal,
+    retryAttempts: settings.streamRetryAttempts
+  });
+  return result.content;
+}
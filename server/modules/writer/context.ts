@@ -2,6 +2,7 @@ import { db } from "../../db.js";
 import { retrieveWriterRagContext } from "../../services/rag.js";
 import {
   DEFAULT_CHAPTER_SETTINGS,
+  type WriterBeat,
   type WriterChapterSettings,
   type WriterProjectNotes
 } from "./defs.js";
@@ -24,6 +25,15 @@ export function buildProjectNotesDirective(notes: WriterProjectNotes): string {
   return ["[Book Bible]", ...parts].join("\n\n");
 }
 
+export function buildBeatDirective(beats: WriterBeat[]): string {
+  const lines = beats.map((beat, index) => {
+    const label = beat.title || `Beat ${index + 1}`;
+    return beat.summary ? `- ${label}: ${beat.summary}` : `- ${label}`;
+  });
+  if (lines.length === 0) return "";
+  return ["[Beats To Cover]", "Draft this scene so it delivers these planned beats, in order:", ...lines].join("\n");
+}
+
 function resolveWriterContextLimits(mode: WriterProjectNotes["contextMode"]): { prev: number; current: number; total: number } {
   if (mode === "economy") {
     return { prev: 1400, current: 1000, total: 2600 };
@@ -50,7 +60,7 @@ export function buildProjectContextPack(projectId: string, chapterId: string, no
       "SELECT summary FROM writer_chapter_summaries WHERE chapter_id = ?"
     ).get(chapter.id) as { summary: string } | undefined;
     const fallbackRow = db.prepare(
-      "SELECT content FROM writer_scenes WHERE chapter_id = ? ORDER BY created_at DESC LIMIT 1"
+      "SELECT content FROM writer_scenes WHERE chapter_id = ? ORDER BY position DESC, created_at DESC LIMIT 1"
     ).get(chapter.id) as { content: string } | undefined;
     const snippet = truncateForPrompt(summaryRow?.summary || fallbackRow?.content || "", 500);
     if (!snippet) continue;
@@ -60,7 +70,7 @@ export function buildProjectContextPack(projectId: string, chapterId: string, no
   }
 
   const currentScenes = db.prepare(
-    "SELECT title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY created_at DESC LIMIT 3"
+    "SELECT title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY position DESC, created_at DESC LIMIT 3"
   ).all(chapterId) as Array<{ title: string; content: string }>;
   const currentContext = currentScenes
     .map((row) => `${row.title}: ${truncateForPrompt(row.content, 500)}`)
@@ -90,7 +100,7 @@ export function buildProjectContinuationContextPack(projectId: string, notes: Wr
       "SELECT summary FROM writer_chapter_summaries WHERE chapter_id = ?"
     ).get(chapter.id) as { summary: string } | undefined;
     const fallbackRow = db.prepare(
-      "SELECT content FROM writer_scenes WHERE chapter_id = ? ORDER BY created_at DESC LIMIT 1"
+      "SELECT content FROM writer_scenes WHERE chapter_id = ? ORDER BY position DESC, created_at DESC LIMIT 1"
     ).get(chapter.id) as { content: string } | undefined;
     const snippet = truncateForPrompt(summaryRow?.summary || fallbackRow?.content || "", 500);
     if (!snippet) continue;
@@ -100,7 +110,7 @@ export function buildProjectContinuationContextPack(projectId: string, notes: Wr
   }
 
   const latestScenes = db.prepare(
-    "SELECT title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY created_at DESC LIMIT 3"
+    "SELECT title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY position DESC, created_at DESC LIMIT 3"
   ).all(latest.id) as Array<{ title: string; content: string }>;
   const latestContext = latestScenes
     .map((row) => `${row.title}: ${truncateForPrompt(row.content, 500)}`)
@@ -157,6 +167,21 @@ export function buildChapterDirective(chapter: WriterChapterSettings): string {
   ].join("\n");
 }
 
+/** The character a scene is told through, so drafts and rewrites keep one narrator voice. */
+export function buildSceneCharacterDirective(characterId: string | null | undefined): string {
+  if (!characterId) return "";
+  const row = db.prepare("SELECT name, description, personality, system_prompt FROM characters WHERE id = ?")
+    .get(characterId) as { name: string; description: string; personality: string; system_prompt: string } | undefined;
+  if (!row) return "";
+  return [
+    "[Scene POV Character]",
+    `Tell this scene from ${row.name}'s point of view and keep their voice consistent.`,
+    row.description ? `Description: ${row.description}` : "",
+    row.personality ? `Personality: ${row.personality}` : "",
+    row.system_prompt ? `Voice notes: ${row.system_prompt}` : ""
+  ].filter(Boolean).join("\n");
+}
+
 export function buildCharacterContext(characterIds: string[]): string {
   if (characterIds.length === 0) return "";
   const rows = db.prepare(
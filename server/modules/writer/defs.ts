@@ -1,4 +1,4 @@
-import { db } from "../../db.js";
+import { db, newId } from "../../db.js";
 
 export const KOBOLD_TAGS = {
   systemOpen: "{{[SYSTEM]}}",
@@ -15,6 +15,9 @@ export interface ProviderRow {
   full_local_only: number;
   provider_type: string;
   adapter_id: string | null;
+  rate_limit_rpm?: number | null;
+  auth_mode?: string | null;
+  auth_param?: string | null;
 }
 
 export interface CharacterRow {
@@ -57,6 +60,16 @@ export interface WriterProjectNotes {
   worldRules: string;
   contextMode: "economy" | "balanced" | "rich";
   summary: string;
+  /** One character per line, optionally `Name @ Scene title` for the scene that introduces them. */
+  characterRoster: string[];
+}
+
+/** One planned story beat; `targetChapterId` marks the chapter that should cover it. */
+export interface WriterBeat {
+  id: string;
+  title: string;
+  summary: string;
+  targetChapterId: string | null;
 }
 
 export type WriterSummaryLensScope = "project" | "chapter" | "scene";
@@ -152,7 +165,8 @@ export const DEFAULT_PROJECT_NOTES: WriterProjectNotes = {
   characterNotes: "",
   worldRules: "",
   contextMode: "balanced",
-  summary: ""
+  summary: "",
+  characterRoster: []
 };
 
 export function clamp01(value: number): number {
@@ -463,7 +477,10 @@ export function normalizeProjectNotes(input: unknown): WriterProjectNotes {
     characterNotes: toCleanText(row.characterNotes, 12000),
     worldRules: toCleanText(row.worldRules, 8000),
     contextMode,
-    summary: toCleanText(row.summary, 20000)
+    summary: toCleanText(row.summary, 20000),
+    characterRoster: Array.isArray(row.characterRoster)
+      ? row.characterRoster.map((line) => toCleanText(line, 200)).filter(Boolean).slice(0, 200)
+      : []
   };
 }
 
@@ -475,3 +492,33 @@ export function parseProjectNotes(raw: string | null | undefined): WriterProject
     return { ...DEFAULT_PROJECT_NOTES };
   }
 }
+
+/** Keeps beat order; beats without a title or summary are dropped and missing ids are assigned. */
+export function normalizeWriterBeats(input: unknown): WriterBeat[] {
+  if (!Array.isArray(input)) return [];
+  const beats: WriterBeat[] = [];
+  for (const item of input.slice(0, 200)) {
+    if (!item || typeof item !== "object" || Array.isArray(item)) continue;
+    const row = item as Record<string, unknown>;
+    const title = toCleanText(row.title, 200);
+    const summary = toCleanText(row.summary, 4000);
+    if (!title && !summary) continue;
+    const targetChapterId = toCleanText(row.targetChapterId, 80);
+    beats.push({
+      id: toCleanText(row.id, 80) || newId(),
+      title,
+      summary,
+      targetChapterId: targetChapterId || null
+    });
+  }
+  return beats;
+}
+
+export function parseWriterBeats(raw: string | null | undefined): WriterBeat[] {
+  if (!raw) return [];
+  try {
+    return normalizeWriterBeats(JSON.parse(raw));
+  } catch {
+    return [];
+  }
+}
@@ -1,6 +1,7 @@
 import mammoth from "mammoth";
 import { db, newId, now } from "../../db.js";
 import { DEFAULT_CHAPTER_SETTINGS, normalizeProjectName, type WriterDocxParseMode } from "./defs.js";
+import { INSERT_SCENE_SQL } from "./repository.js";
 
 export interface ParsedDocxChapter {
   title: string;
@@ -265,9 +266,7 @@ export function importParsedDocxChapters(projectId: string, chunks: ParsedDocxCh
       parts.forEach((contentPart, index) => {
         const sceneId = newId();
         const sceneTitle = parts.length > 1 ? `${chapterTitle} (Part ${index + 1})` : chapterTitle;
-        db.prepare(
-          "INSERT INTO writer_scenes (id, chapter_id, title, content, goals, conflicts, outcomes, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
-        ).run(
+        db.prepare(INSERT_SCENE_SQL).run(
           sceneId,
           chapterId,
           sceneTitle.slice(0, 180),
@@ -275,7 +274,10 @@ export function importParsedDocxChapters(projectId: string, chunks: ParsedDocxCh
           "Imported from DOCX",
           "",
           "",
-          now()
+          null,
+          null,
+          now(),
+          chapterId
         );
         scenesCreated += 1;
       });
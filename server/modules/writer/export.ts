@@ -1,5 +1,6 @@
 import { Document, HeadingLevel, Packer, Paragraph, TextRun } from "docx";
 import { db } from "../../db.js";
+import { normalizeSceneMarkdown, stripHeadingEscape } from "../../domain/writerMarkdown.js";
 import { sanitizeExportFileName } from "./chapterSettings.js";
 
 export interface WriterExportBundle {
@@ -26,7 +27,7 @@ export function buildWriterExportBundle(projectId: string): WriterExportBundle |
   const lines: string[] = [`# ${project.name}`, ""];
   for (const chapter of chapters) {
     lines.push(`## ${chapter.title}`, "");
-    const scenes = db.prepare("SELECT title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY created_at ASC")
+    const scenes = db.prepare("SELECT title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY position ASC, created_at ASC")
       .all(chapter.id) as Array<{ title: string; content: string }>;
     const chapterTitleKey = normalizeTitleForExportCompare(chapter.title);
     for (const scene of scenes) {
@@ -36,7 +37,8 @@ export function buildWriterExportBundle(projectId: string): WriterExportBundle |
       if (shouldRenderSceneHeading) {
         lines.push(`### ${sceneTitle}`, "");
       }
-      lines.push(scene.content, "");
+      const body = normalizeSceneMarkdown(scene.content);
+      if (body) lines.push(body, "");
     }
   }
 
@@ -69,7 +71,7 @@ export async function buildDocxBufferFromBundle(bundle: WriterExportBundle): Pro
       paragraphs.push(new Paragraph({ text: "" }));
       continue;
     }
-    paragraphs.push(new Paragraph({ children: [new TextRun(line)] }));
+    paragraphs.push(new Paragraph({ children: [new TextRun(stripHeadingEscape(line))] }));
   }
 
   const doc = new Document({
@@ -77,3 +79,101 @@ export async function buildDocxBufferFromBundle(bundle: WriterExportBundle): Pro
   });
   return Packer.toBuffer(doc);
 }
+
+function escapeHtml(value: string): string {
+  return value
+    .replace(/&/g, "&amp;")
+    .replace(/</g, "&lt;")
+    .replace(/>/g, "&gt;")
+    .replace(/"/g, "&quot;")
+    .replace(/'/g, "&#39;");
+}
+
+/** Bold and italic emphasis only; the text is escaped first, so no markup from a draft reaches the page. */
+function renderInlineMarkdown(text: string): string {
+  return escapeHtml(text)
+    .replace(/\*\*(?=\S)(.+?)\*\*/g, "<strong>$1</strong>")
+    .replace(/__(?=\S)(.+?)__/g, "<strong>$1</strong>")
+    .replace(/(^|[^*\w])\*(?=\S)([^*]+?)\*(?!\w)/g, "$1<em>$2</em>")
+    .replace(/(^|[^_\w])_(?=\S)([^_]+?)_(?!\w)/g, "$1<em>$2</em>");
+}
+
+const HTML_EXPORT_STYLE = `
+  body { margin: 0; background: #f7f4ee; color: #222; font: 18px/1.7 Georgia, "Times New Roman", serif; }
+  main { max-width: 42rem; margin: 0 auto; padding: 3rem 1.5rem 5rem; }
+  h1 { font-size: 2.2rem; text-align: center; margin: 0 0 2rem; }
+  h2 { font-size: 1.6rem; margin: 3.5rem 0 1.5rem; }
+  h3 { font-size: 1.15rem; font-style: italic; margin: 2.5rem 0 1rem; }
+  nav { border-top: 1px solid #d8d0c2; border-bottom: 1px solid #d8d0c2; padding: 1rem 0; margin-bottom: 2rem; }
+  nav ol { margin: 0; padding-left: 1.5rem; }
+  a { color: #6b4b2a; }
+  p { margin: 0 0 1rem; text-indent: 1.5em; }
+  hr { border: 0; text-align: center; margin: 2rem 0; }
+  hr::after { content: "* * *"; color: #8a7f70; }
+`;
+
+/**
+ * One self-contained HTML page from the export bundle: inline styles, a linked table of contents for the
+ * chapters and the same headings as the Markdown export. Scene text is escaped and only paragraph breaks,
+ * scene-break rules and emphasis are rendered.
+ */
+export function buildHtmlFromBundle(bundle: WriterExportBundle): string {
+  const body: string[] = [];
+  const toc: string[] = [];
+  let paragraph: string[] = [];
+  const flushParagraph = () => {
+    if (paragraph.length) body.push(`<p>${paragraph.map(renderInlineMarkdown).join("<br>")}</p>`);
+    paragraph = [];
+  };
+
+  for (const rawLine of bundle.markdown.split("\n")) {
+    const line = rawLine.replace(/\r/g, "");
+    const heading = /^(#{1,3}) (.*)$/.exec(line);
+    if (heading) {
+      flushParagraph();
+      const level = heading[1].length;
+      const text = renderInlineMarkdown(heading[2].trim());
+      if (level === 2) {
+        const anchor = `chapter-${toc.length + 1}`;
+        toc.push(`<li><a href="#${anchor}">${text}</a></li>`);
+        body.push(`<h2 id="${anchor}">${text}</h2>`);
+      } else {
+        body.push(`<h${level}>${text}</h${level}>`);
+      }
+      continue;
+    }
+    if (/^\s*(?:\*\s*\*\s*\*|-{3,}|_{3,})\s*$/.test(line)) {
+      flushParagraph();
+      body.push("<hr>");
+      continue;
+    }
+    if (!line.trim()) {
+      flushParagraph();
+      continue;
+    }
+    paragraph.push(stripHeadingEscape(line.trim()));
+  }
+  flushParagraph();
+
+  const [title, ...rest] = body;
+  const nav = toc.length ? [`<nav><ol>${toc.join("")}</ol></nav>`] : [];
+  return [
+    "<!DOCTYPE html>",
+    "<html lang=\"en\">",
+    "<head>",
+    "<meta charset=\"utf-8\">",
+    "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">",
+    `<title>${escapeHtml(bundle.projectName)}</title>`,
+    `<style>${HTML_EXPORT_STYLE}</style>`,
+    "</head>",
+    "<body>",
+    "<main>",
+    title ?? "",
+    ...nav,
+    ...rest,
+    "</main>",
+    "</body>",
+    "</html>",
+    ""
+  ].join("\n");
+}
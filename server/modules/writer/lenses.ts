@@ -49,7 +49,7 @@ function buildProjectSourceText(projectId: string): string {
   ).all(projectId) as Array<{ id: string; title: string; position: number }>;
   if (chapters.length === 0) return "";
   const sceneStmt = db.prepare(
-    "SELECT id, title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY created_at ASC"
+    "SELECT id, title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY position ASC, created_at ASC"
   );
   const blocks = chapters.map((chapter) => {
     const scenes = sceneStmt.all(chapter.id) as Array<{ id: string; title: string; content: string }>;
@@ -76,7 +76,7 @@ export function resolveLensSource(projectId: string, scope: WriterSummaryLensSco
       throw new Error("Chapter target not found in this project");
     }
     const scenes = db.prepare(
-      "SELECT title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY created_at ASC"
+      "SELECT title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY position ASC, created_at ASC"
     ).all(targetId) as Array<{ title: string; content: string }>;
     const sourceText = scenes.map((scene) => `[Scene] ${scene.title}\n${scene.content}`).join("\n\n");
     return { targetId, sourceText };
@@ -175,6 +175,11 @@ export async function summarizeWithCache(
   return { summary, cached: false };
 }
 
+/** Offline stand-in when no model is configured: the scene's first three non-empty lines. */
+export function fallbackSceneSummary(content: string): string {
+  return String(content || "").split(/\r?\n/).map((line) => line.trim()).filter(Boolean).slice(0, 3).join("\n");
+}
+
 export function buildChapterSummaryPrompt(notes: WriterProjectNotes, writerSummarizeTemplate: string) {
   return [
     writerSummarizeTemplate,
@@ -1,12 +1,9 @@
+import type { Response } from "express";
 import { DEFAULT_SETTINGS, db } from "../../db.js";
-import { buildKoboldSamplerConfig, buildOpenAiSamplingPayload, normalizeApiParamPolicy } from "../../services/apiParamPolicy.js";
-import { completeCustomAdapter } from "../../services/customProviderAdapters.js";
-import { buildKoboldGenerateBody, extractKoboldGeneratedText, normalizeProviderType, requestKoboldGenerate } from "../../services/providerApi.js";
-import {
-  KOBOLD_TAGS,
-  type ProviderRow,
-  type WriterSampler
-} from "./defs.js";
+import { normalizeApiParamPolicy } from "../../services/apiParamPolicy.js";
+import { normalizeRuntimeTuningSettings } from "../../services/runtimeTuning.js";
+import { completeLlm, LlmUnavailableError, resolveLlm, streamLlm } from "../llm/completion.js";
+import type { WriterSampler } from "./defs.js";
 
 export function getWriterSettings() {
   const row = db.prepare("SELECT payload FROM settings WHERE id = 1").get() as { payload: string };
@@ -14,113 +11,111 @@ export function getWriterSettings() {
   return {
     ...DEFAULT_SETTINGS,
     ...stored,
+    ...normalizeRuntimeTuningSettings(stored),
     samplerConfig: { ...DEFAULT_SETTINGS.samplerConfig, ...(stored.samplerConfig ?? {}) },
     apiParamPolicy: normalizeApiParamPolicy(stored.apiParamPolicy),
     promptTemplates: { ...DEFAULT_SETTINGS.promptTemplates, ...(stored.promptTemplates ?? {}) }
   };
 }
 
-export async function callWriterLlm(systemPrompt: string, userPrompt: string, sampler?: WriterSampler): Promise<string> {
-  const settings = getWriterSettings();
-  const providerId = settings.activeProviderId;
-  const modelId = settings.activeModel;
-
-  if (!providerId || !modelId) {
-    return `[No LLM configured] Placeholder for: ${userPrompt.slice(0, 100)}`;
+/** Same gate chat_send applies: Full Local Mode refuses a non-localhost active provider. */
+export function getWriterProviderBlock(): string | null {
+  try {
+    resolveLlm();
+    return null;
+  } catch (error) {
+    const blocked = error instanceof LlmUnavailableError && (error.reason === "full_local_mode" || error.reason === "local_only");
+    return blocked ? error.message : null;
   }
+}
 
-  const provider = db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as ProviderRow | undefined;
-  if (!provider) return "[Provider not found]";
+function buildWriterSamplerConfig(settings: ReturnType<typeof getWriterSettings>, sampler?: WriterSampler): Record<string, unknown> {
+  return {
+    ...(settings.samplerConfig as Record<string, unknown>),
+    temperature: sampler?.temperature ?? settings.samplerConfig.temperature ?? 0.9,
+    maxTokens: sampler?.maxTokens ?? settings.samplerConfig.maxTokens ?? 2048
+  };
+}
 
-  try {
-    const providerType = normalizeProviderType(provider.provider_type);
-    if (providerType === "koboldcpp") {
-      const koboldPolicy = normalizeApiParamPolicy(settings.apiParamPolicy).kobold;
-      const customMemory = String(settings.samplerConfig.koboldMemory || "").trim();
-      const memory = [
-        customMemory,
-        systemPrompt
-          ? `${KOBOLD_TAGS.systemOpen}\n${systemPrompt}\n${KOBOLD_TAGS.systemClose}`
-          : ""
-      ].filter(Boolean).join("\n\n");
-      const koboldSamplerConfig = buildKoboldSamplerConfig({
-        samplerConfig: {
-          temperature: sampler?.temperature ?? settings.samplerConfig.temperature ?? 0.9,
-          maxTokens: sampler?.maxTokens ?? settings.samplerConfig.maxTokens ?? 2048,
-          topP: settings.samplerConfig.topP,
-          stop: settings.samplerConfig.stop,
-          topK: settings.samplerConfig.topK,
-          topA: settings.samplerConfig.topA,
-          minP: settings.samplerConfig.minP,
-          typical: settings.samplerConfig.typical,
-          tfs: settings.samplerConfig.tfs,
-          nSigma: settings.samplerConfig.nSigma,
-          repetitionPenalty: settings.samplerConfig.repetitionPenalty,
-          repetitionPenaltyRange: settings.samplerConfig.repetitionPenaltyRange,
-          repetitionPenaltySlope: settings.samplerConfig.repetitionPenaltySlope,
-          samplerOrder: settings.samplerConfig.samplerOrder,
-          koboldMemory: settings.samplerConfig.koboldMemory,
-          koboldUseDefaultBadwords: settings.samplerConfig.koboldUseDefaultBadwords,
-          koboldBannedPhrases: settings.samplerConfig.koboldBannedPhrases
-        },
-        apiParamPolicy: settings.apiParamPolicy
-      });
-      const body = buildKoboldGenerateBody({
-        prompt: `${KOBOLD_TAGS.inputOpen}\n${userPrompt}\n${KOBOLD_TAGS.inputClose}\n\n${KOBOLD_TAGS.outputOpen}`,
-        memory,
-        samplerConfig: koboldSamplerConfig,
-        includeMemory: koboldPolicy.memory
-      });
-      const response = await requestKoboldGenerate(provider, body);
-      if (!response.ok) {
-        const errText = await response.text().catch(() => "KoboldCpp error");
-        return `[KoboldCpp Error] ${errText.slice(0, 500)}`;
-      }
-      const payload = await response.json().catch(() => ({}));
-      return extractKoboldGeneratedText(payload) || "[Empty response]";
-    }
+const activeWriterStreams = new Map<string, AbortController>();
 
-    if (providerType === "custom") {
-      return completeCustomAdapter({
-        provider,
-        modelId,
-        systemPrompt,
-        userPrompt,
-        samplerConfig: settings.samplerConfig as Record<string, unknown>
-      });
-    }
+/**
+ * Abort controller for one streamed writer generation, keyed by its stream id (chapter or project).
+ * It fires when the client disconnects or `abortWriterStream` is called for the same id.
+ */
+export function openWriterStream(streamId: string, res: Response): AbortController {
+  const controller = new AbortController();
+  activeWriterStreams.set(streamId, controller);
+  res.on("close", () => {
+    if (!res.writableEnded) controller.abort();
+    if (activeWriterStreams.get(streamId) === controller) activeWriterStreams.delete(streamId);
+  });
+  return controller;
+}
 
-    const openAiSampling = buildOpenAiSamplingPayload({
-      samplerConfig: {
-        temperature: sampler?.temperature ?? settings.samplerConfig.temperature ?? 0.9,
-        maxTokens: sampler?.maxTokens ?? settings.samplerConfig.maxTokens ?? 2048
-      },
-      apiParamPolicy: settings.apiParamPolicy,
-      fields: ["temperature", "maxTokens"],
-      defaults: {
-        temperature: 0.9,
-        maxTokens: 2048
-      }
-    });
-    const response = await fetch(`${provider.base_url}/chat/completions`, {
-      method: "POST",
-      headers: {
-        "Content-Type": "application/json",
-        Authorization: `Bearer ${provider.api_key_cipher}`
-      },
-      body: JSON.stringify({
-        model: modelId,
-        messages: [
-          { role: "system", content: systemPrompt },
-          { role: "user", content: userPrompt }
-        ],
-        ...openAiSampling
-      })
+export function abortWriterStream(streamId: string): boolean {
+  const controller = activeWriterStreams.get(streamId);
+  if (!controller) return false;
+  controller.abort();
+  activeWriterStreams.delete(streamId);
+  return true;
+}
+
+/**
+ * Streams a writer completion from the active provider as chat-style `delta` SSE events.
+ * Without a usable provider the non-streaming placeholder is sent as a single delta.
+ */
+export async function streamWriterLlm(params: {
+  systemPrompt: string;
+  userPrompt: string;
+  sampler?: WriterSampler;
+  streamId: string;
+  res: Response;
+  signal: AbortSignal;
+}): Promise<string> {
+  const messages = [
+    { role: "system", content: params.systemPrompt },
+    { role: "user", content: params.userPrompt }
+  ];
+  try {
+    const content = await streamLlm(messages, {
+      samplerConfig: buildWriterSamplerConfig(getWriterSettings(), params.sampler),
+      streamId: params.streamId,
+      res: params.res,
+      signal: params.signal
     });
+    return content || "[Empty response]";
+  } catch (error) {
+    if (!(error instanceof LlmUnavailableError)) throw error;
+    const content = await callWriterLlm(params.systemPrompt, params.userPrompt, params.sampler);
+    params.res.write(`data: ${JSON.stringify({ type: "delta", chatId: params.streamId, delta: content })}\n\n`);
+    return content;
+  }
+}
+
+const WRITER_LLM_FAILURE_PATTERN = /^\[(?:No LLM configured|Provider not found|Provider blocked by Full Local Mode|Provider requires localhost endpoint|LLM Error|Empty response)\]/;
 
-    const body = await response.json() as { choices?: { message?: { content?: string } }[] };
-    return body.choices?.[0]?.message?.content ?? "[Empty response]";
-  } catch (err) {
-    return `[LLM Error] ${err instanceof Error ? err.message : "Unknown error"}`;
+/** callWriterLlm reports failures as bracketed text; callers that cache results must not store them. */
+export function isWriterLlmFailure(text: string): boolean {
+  return WRITER_LLM_FAILURE_PATTERN.test(text.trim());
+}
+
+export async function callWriterLlm(systemPrompt: string, userPrompt: string, sampler?: WriterSampler): Promise<string> {
+  try {
+    const content = await completeLlm(
+      [
+        { role: "system", content: systemPrompt },
+        { role: "user", content: userPrompt }
+      ],
+      { samplerConfig: buildWriterSamplerConfig(getWriterSettings(), sampler) }
+    );
+    return content || "[Empty response]";
+  } catch (error) {
+    if (error instanceof LlmUnavailableError) {
+      return error.reason === "not_configured"
+        ? `[No LLM configured] Placeholder for: ${userPrompt.slice(0, 100)}`
+        : `[${error.message}]`;
+    }
+    return `[LLM Error] ${error instanceof Error ? error.message : "Unknown error"}`;
   }
 }
@@ -1,12 +1,19 @@
-import { db } from "../../db.js";
+import { db, newId, now } from "../../db.js";
 import {
   DEFAULT_CHAPTER_SETTINGS,
   parseJsonIdArray,
   parseProjectNotes,
+  parseWriterBeats,
+  type WriterBeat,
   type WriterProjectNotes
 } from "./defs.js";
 import { parseChapterSettings } from "./chapterSettings.js";
 
+// New scenes go to the end of their chapter; the chapter id is bound twice (column value and subquery).
+export const INSERT_SCENE_SQL =
+  `INSERT INTO writer_scenes (id, chapter_id, title, content, goals, conflicts, outcomes, character_id, chat_id, created_at, position)
+   VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, (SELECT COALESCE(MAX(position), 0) + 1 FROM writer_scenes WHERE chapter_id = ?))`;
+
 export interface WriterProjectRow {
   id: string;
   name: string;
@@ -33,6 +40,20 @@ export interface WriterSceneRow {
   goals: string;
   conflicts: string;
   outcomes: string;
+  character_id: string | null;
+  chat_id: string | null;
+  position: number;
+  created_at: string;
+}
+
+export type WriterSceneRevisionReason = "edit" | "expand" | "rewrite" | "restore";
+
+export interface WriterSceneRevisionRow {
+  id: string;
+  scene_id: string;
+  title: string;
+  content: string;
+  reason: WriterSceneRevisionReason;
   created_at: string;
 }
 
@@ -121,7 +142,7 @@ export function listProjectChapters(projectId: string): WriterChapterRow[] {
 export function listScenesForChapterIds(chapterIds: string[]): WriterSceneRow[] {
   if (chapterIds.length === 0) return [];
   const placeholders = chapterIds.map(() => "?").join(",");
-  return db.prepare(`SELECT * FROM writer_scenes WHERE chapter_id IN (${placeholders}) ORDER BY created_at ASC`)
+  return db.prepare(`SELECT * FROM writer_scenes WHERE chapter_id IN (${placeholders}) ORDER BY position ASC, created_at ASC`)
     .all(...chapterIds) as WriterSceneRow[];
 }
 
@@ -147,7 +168,7 @@ export function listProjectChapterSummaryRows(projectId: string): WriterChapterS
 }
 
 export function listChapterSceneContentRows(chapterId: string): WriterSceneContentRow[] {
-  return db.prepare("SELECT title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY created_at ASC")
+  return db.prepare("SELECT title, content FROM writer_scenes WHERE chapter_id = ? ORDER BY position ASC, created_at ASC")
     .all(chapterId) as WriterSceneContentRow[];
 }
 
@@ -164,6 +185,24 @@ export function upsertChapterSummary(params: {
   ).run(params.chapterId, params.contentHash, params.summary, params.updatedAt);
 }
 
+export function getStoredSceneSummary(sceneId: string): { summary: string; content_hash: string } | undefined {
+  return db.prepare("SELECT summary, content_hash FROM writer_scene_summaries WHERE scene_id = ?")
+    .get(sceneId) as { summary: string; content_hash: string } | undefined;
+}
+
+export function upsertSceneSummary(params: {
+  sceneId: string;
+  contentHash: string;
+  summary: string;
+  updatedAt: string;
+}) {
+  db.prepare(
+    `INSERT INTO writer_scene_summaries (scene_id, content_hash, summary, updated_at)
+     VALUES (?, ?, ?, ?)
+     ON CONFLICT(scene_id) DO UPDATE SET content_hash = excluded.content_hash, summary = excluded.summary, updated_at = excluded.updated_at`
+  ).run(params.sceneId, params.contentHash, params.summary, params.updatedAt);
+}
+
 export function upsertProjectSummary(params: {
   projectId: string;
   contentHash: string;
@@ -220,6 +259,22 @@ export function updateProjectNotes(projectId: string, notes: WriterProjectNotes)
     .run(JSON.stringify(notes), projectId);
 }
 
+// The beat sheet is stored as one ordered JSON list per project.
+export function getProjectBeats(projectId: string): WriterBeat[] {
+  const row = db.prepare("SELECT payload FROM writer_beats WHERE project_id = ? ORDER BY created_at DESC LIMIT 1")
+    .get(projectId) as { payload: string } | undefined;
+  return parseWriterBeats(row?.payload);
+}
+
+export function replaceProjectBeats(projectId: string, beats: WriterBeat[]) {
+  const tx = db.transaction(() => {
+    db.prepare("DELETE FROM writer_beats WHERE project_id = ?").run(projectId);
+    db.prepare("INSERT INTO writer_beats (id, project_id, payload, created_at) VALUES (?, ?, ?, ?)")
+      .run(newId(), projectId, JSON.stringify(beats), now());
+  });
+  tx();
+}
+
 export function deleteProjectCascade(projectId: string) {
   const deleteTx = db.transaction((id: string) => {
     db.prepare("DELETE FROM writer_scenes WHERE chapter_id IN (SELECT id FROM writer_chapters WHERE project_id = ?)")
@@ -314,6 +369,14 @@ export function reorderProjectChapters(projectId: string, orderedIds: string[])
   tx();
 }
 
+export function reorderChapterScenes(chapterId: string, orderedIds: string[]) {
+  const stmt = db.prepare("UPDATE writer_scenes SET position = ? WHERE id = ? AND chapter_id = ?");
+  const tx = db.transaction(() => {
+    orderedIds.forEach((id, idx) => stmt.run(idx + 1, id, chapterId));
+  });
+  tx();
+}
+
 export function updateChapterTitle(chapterId: string, title: string) {
   db.prepare("UPDATE writer_chapters SET title = ? WHERE id = ?")
     .run(title, chapterId);
@@ -324,15 +387,18 @@ export function updateChapterSettings(chapterId: string, settings: Record<string
     .run(JSON.stringify(settings), chapterId);
 }
 
-export function deleteChapterCascade(chapterId: string, projectId: string, position: number) {
-  const tx = db.transaction((targetChapterId: string, targetProjectId: string, targetPosition: number) => {
+/** Scene revisions go with their scenes through the foreign key; remaining chapters are renumbered 1..n. */
+export function deleteChapterCascade(chapterId: string, projectId: string) {
+  const tx = db.transaction((targetChapterId: string, targetProjectId: string) => {
     const sceneIds = db.prepare("SELECT id FROM writer_scenes WHERE chapter_id = ?")
       .all(targetChapterId) as Array<{ id: string }>;
     db.prepare("DELETE FROM writer_scenes WHERE chapter_id = ?").run(targetChapterId);
     db.prepare("DELETE FROM writer_chapter_summaries WHERE chapter_id = ?").run(targetChapterId);
     db.prepare("DELETE FROM writer_chapters WHERE id = ?").run(targetChapterId);
-    db.prepare("UPDATE writer_chapters SET position = position - 1 WHERE project_id = ? AND position > ?")
-      .run(targetProjectId, targetPosition);
+    const remainingIds = db.prepare("SELECT id FROM writer_chapters WHERE project_id = ? ORDER BY position ASC, created_at ASC")
+      .all(targetProjectId) as Array<{ id: string }>;
+    const renumber = db.prepare("UPDATE writer_chapters SET position = ? WHERE id = ?");
+    remainingIds.forEach((row, idx) => renumber.run(idx + 1, row.id));
     db.prepare(
       "DELETE FROM writer_summary_lenses WHERE project_id = ? AND scope = 'chapter' AND target_id = ?"
     ).run(targetProjectId, targetChapterId);
@@ -347,7 +413,7 @@ export function deleteChapterCascade(chapterId: string, projectId: string, posit
     }
   });
 
-  tx(chapterId, projectId, position);
+  tx(chapterId, projectId);
 }
 
 export function getSceneRow(sceneId: string): WriterSceneRow | undefined {
@@ -413,7 +479,7 @@ export function createGeneratedChapterWithScene(params: {
   sceneTitle: string;
   sceneContent: string;
   createdAt: string;
-}) {
+}): WriterSceneRow {
   const tx = db.transaction(() => {
     db.prepare(
       "INSERT INTO writer_chapters (id, project_id, title, position, settings_json, created_at) VALUES (?, ?, ?, ?, ?, ?)"
@@ -425,9 +491,7 @@ export function createGeneratedChapterWithScene(params: {
       params.settingsJson,
       params.createdAt
     );
-    db.prepare(
-      "INSERT INTO writer_scenes (id, chapter_id, title, content, goals, conflicts, outcomes, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
-    ).run(
+    db.prepare(INSERT_SCENE_SQL).run(
       params.sceneId,
       params.chapterId,
       params.sceneTitle,
@@ -435,11 +499,75 @@ export function createGeneratedChapterWithScene(params: {
       "Advance plot",
       "Escalate conflict",
       "Open ending",
-      params.createdAt
+      null,
+      null,
+      params.createdAt,
+      params.chapterId
     );
   });
 
   tx();
+  return getSceneRow(params.sceneId) as WriterSceneRow;
+}
+
+/** Appends outline chapters after the project's last chapter, each optionally with an empty stub scene. */
+export function createOutlineChapters(params: {
+  projectId: string;
+  outline: Array<{ title: string; summary: string }>;
+  withScenes: boolean;
+  settingsJson: string;
+}): { chapters: WriterChapterRow[]; scenes: WriterSceneRow[] } {
+  const chapters: WriterChapterRow[] = [];
+  const scenes: WriterSceneRow[] = [];
+  const tx = db.transaction(() => {
+    let position = (getLastProjectChapter(params.projectId)?.position ?? 0) + 1;
+    for (const item of params.outline) {
+      const chapter: WriterChapterRow = {
+        id: newId(),
+        project_id: params.projectId,
+        title: item.title,
+        position,
+        settings_json: params.settingsJson,
+        created_at: now()
+      };
+      db.prepare(
+        "INSERT INTO writer_chapters (id, project_id, title, position, settings_json, created_at) VALUES (?, ?, ?, ?, ?, ?)"
+      ).run(chapter.id, chapter.project_id, chapter.title, chapter.position, chapter.settings_json, chapter.created_at);
+      chapters.push(chapter);
+      position += 1;
+      if (!params.withScenes) continue;
+      const scene: WriterSceneRow = {
+        id: newId(),
+        chapter_id: chapter.id,
+        title: item.title,
+        content: "",
+        goals: item.summary,
+        conflicts: "",
+        outcomes: "",
+        character_id: null,
+        chat_id: null,
+        position: 1,
+        created_at: chapter.created_at
+      };
+      db.prepare(INSERT_SCENE_SQL).run(
+        scene.id,
+        scene.chapter_id,
+        scene.title,
+        scene.content,
+        scene.goals,
+        scene.conflicts,
+        scene.outcomes,
+        scene.character_id,
+        scene.chat_id,
+        scene.created_at,
+        scene.chapter_id
+      );
+      scenes.push(scene);
+    }
+  });
+
+  tx();
+  return { chapters, scenes };
 }
 
 export function createGeneratedSceneRecord(params: {
@@ -447,11 +575,10 @@ export function createGeneratedSceneRecord(params: {
   chapterId: string;
   title: string;
   content: string;
+  characterId?: string | null;
   createdAt: string;
-}) {
-  db.prepare(
-    "INSERT INTO writer_scenes (id, chapter_id, title, content, goals, conflicts, outcomes, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
-  ).run(
+}): WriterSceneRow {
+  db.prepare(INSERT_SCENE_SQL).run(
     params.id,
     params.chapterId,
     params.title,
@@ -459,14 +586,140 @@ export function createGeneratedSceneRecord(params: {
     "Advance plot",
     "Internal conflict",
     "Open ending",
-    params.createdAt
+    params.characterId ?? null,
+    null,
+    params.createdAt,
+    params.chapterId
+  );
+  return getSceneRow(params.id) as WriterSceneRow;
+}
+
+export interface WriterChatSourceRow {
+  id: string;
+  title: string;
+  character_id: string | null;
+  character_name: string | null;
+  persona_name: string | null;
+}
+
+/** A live (not trashed) chat with the names used to label its transcript. */
+export function getChatSceneSource(chatId: string): WriterChatSourceRow | undefined {
+  return db.prepare(
+    `SELECT c.id, c.title, c.character_id, ch.name AS character_name, p.name AS persona_name
+     FROM chats c
+     LEFT JOIN characters ch ON ch.id = c.character_id
+     LEFT JOIN user_personas p ON p.id = c.persona_id
+     WHERE c.id = ? AND c.deleted_at IS NULL`
+  ).get(chatId) as WriterChatSourceRow | undefined;
+}
+
+/** The requested branch when it belongs to the chat, otherwise the chat's active or earliest branch. */
+export function resolveChatSceneBranch(chatId: string, branchId?: string): string | null {
+  if (branchId) {
+    const row = db.prepare("SELECT id FROM branches WHERE id = ? AND chat_id = ?").get(branchId, chatId) as { id: string } | undefined;
+    return row?.id ?? null;
+  }
+  const row = db.prepare(
+    `SELECT b.id FROM branches b
+     WHERE b.chat_id = ?
+     ORDER BY b.id = (SELECT active_branch_id FROM chats WHERE id = ?) DESC, b.created_at ASC
+     LIMIT 1`
+  ).get(chatId, chatId) as { id: string } | undefined;
+  return row?.id ?? null;
+}
+
+export function listChatSceneTurns(chatId: string, branchId: string) {
+  return db.prepare(
+    `SELECT role, content, character_name FROM messages
+     WHERE chat_id = ? AND branch_id = ? AND deleted = 0 AND role IN ('user', 'assistant')
+     ORDER BY sort_order ASC, created_at ASC, id ASC`
+  ).all(chatId, branchId) as Array<{ role: string; content: string; character_name: string | null }>;
+}
+
+export function createChatSceneRecord(params: {
+  id: string;
+  chapterId: string;
+  title: string;
+  content: string;
+  chatId: string;
+  characterId: string | null;
+  createdAt: string;
+}): WriterSceneRow {
+  db.prepare(INSERT_SCENE_SQL).run(
+    params.id,
+    params.chapterId,
+    params.title,
+    params.content,
+    "",
+    "",
+    "",
+    params.characterId,
+    params.chatId,
+    params.createdAt,
+    params.chapterId
   );
+  return getSceneRow(params.id) as WriterSceneRow;
+}
+
+export function updateSceneCharacter(sceneId: string, characterId: string | null) {
+  db.prepare("UPDATE writer_scenes SET character_id = ? WHERE id = ?").run(characterId, sceneId);
 }
 
 export function updateSceneContent(sceneId: string, content: string) {
   db.prepare("UPDATE writer_scenes SET content = ? WHERE id = ?").run(content, sceneId);
 }
 
+/** Manual edits within this window share one snapshot so autosave does not flood the history. */
+const EDIT_REVISION_COALESCE_MS = 10 * 60 * 1000;
+
+/** Snapshots the scene's current title/content before a mutation and prunes past `limit` revisions. */
+export function recordSceneRevision(scene: WriterSceneRow, reason: WriterSceneRevisionReason, limit: number) {
+  const tx = db.transaction(() => {
+    if (reason === "edit") {
+      const latest = db.prepare(
+        "SELECT reason, created_at FROM writer_scene_revisions WHERE scene_id = ? ORDER BY created_at DESC, rowid DESC LIMIT 1"
+      ).get(scene.id) as Pick<WriterSceneRevisionRow, "reason" | "created_at"> | undefined;
+      if (latest?.reason === "edit" && Date.now() - Date.parse(latest.created_at) < EDIT_REVISION_COALESCE_MS) return;
+    }
+    db.prepare(
+      "INSERT INTO writer_scene_revisions (id, scene_id, title, content, reason, created_at) VALUES (?, ?, ?, ?, ?, ?)"
+    ).run(newId(), scene.id, scene.title, scene.content, reason, now());
+    db.prepare(
+      `DELETE FROM writer_scene_revisions
+       WHERE scene_id = ?
+         AND id NOT IN (
+           SELECT id FROM writer_scene_revisions WHERE scene_id = ? ORDER BY created_at DESC, rowid DESC LIMIT ?
+         )`
+    ).run(scene.id, scene.id, Math.max(1, limit));
+  });
+
+  tx();
+}
+
+export function listSceneRevisionRows(sceneId: string): WriterSceneRevisionRow[] {
+  return db.prepare("SELECT * FROM writer_scene_revisions WHERE scene_id = ? ORDER BY created_at DESC, rowid DESC")
+    .all(sceneId) as WriterSceneRevisionRow[];
+}
+
+/**
+ * Swaps the scene back to a revision. The replaced text is kept as a "restore" revision and the
+ * restored one is dropped, so repeated undo keeps walking further back.
+ */
+export function restoreSceneRevision(scene: WriterSceneRow, revision: WriterSceneRevisionRow, limit: number) {
+  const tx = db.transaction(() => {
+    db.prepare("DELETE FROM writer_scene_revisions WHERE id = ?").run(revision.id);
+    recordSceneRevision(scene, "restore", limit);
+    db.prepare("UPDATE writer_scenes SET title = ?, content = ? WHERE id = ?").run(revision.title, revision.content, scene.id);
+  });
+
+  tx();
+}
+
+export function getSceneRevisionRow(sceneId: string, revisionId: string): WriterSceneRevisionRow | undefined {
+  return db.prepare("SELECT * FROM writer_scene_revisions WHERE id = ? AND scene_id = ?")
+    .get(revisionId, sceneId) as WriterSceneRevisionRow | undefined;
+}
+
 export function listProjectLensRows(projectId: string) {
   return db.prepare("SELECT * FROM writer_summary_lenses WHERE project_id = ? ORDER BY created_at DESC")
     .all(projectId);
@@ -495,12 +748,27 @@ export function deleteLensRecord(lensId: string) {
   db.prepare("DELETE FROM writer_summary_lenses WHERE id = ?").run(lensId);
 }
 
-export function listConsistencyScenes(projectId: string): Array<{ id: string; title: string; content: string }> {
-  const chapterIds = getChapterIdsForProject(projectId);
-  if (chapterIds.length === 0) return [];
-  const placeholders = chapterIds.map(() => "?").join(",");
-  return db.prepare(`SELECT id, title, content FROM writer_scenes WHERE chapter_id IN (${placeholders})`)
-    .all(...chapterIds) as Array<{ id: string; title: string; content: string }>;
+/** Scenes in reading order, which the name checks rely on to tell where a character first appears. */
+export function listConsistencyScenes(projectId: string): Array<{ id: string; title: string; content: string; povCharacterName: string | null }> {
+  const rows = db.prepare(
+    `SELECT s.id, s.title, s.content, ch.name AS pov_character_name
+     FROM writer_scenes s
+     JOIN writer_chapters c ON c.id = s.chapter_id
+     LEFT JOIN characters ch ON ch.id = s.character_id
+     WHERE c.project_id = ?
+     ORDER BY c.position ASC, s.position ASC, s.created_at ASC, s.id ASC`
+  ).all(projectId) as Array<{ id: string; title: string; content: string; pov_character_name: string | null }>;
+  return rows.map((row) => ({ id: row.id, title: row.title, content: row.content, povCharacterName: row.pov_character_name }));
+}
+
+/** Roster lines from the project notes followed by the names of the project's linked characters. */
+export function buildConsistencyRoster(project: WriterProjectRow): string[] {
+  const roster = parseProjectNotes(project.notes_json).characterRoster;
+  const characterIds = parseJsonIdArray(project.character_ids);
+  if (characterIds.length === 0) return roster;
+  const rows = db.prepare(`SELECT name FROM characters WHERE id IN (${characterIds.map(() => "?").join(",")})`)
+    .all(...characterIds) as Array<{ name: string }>;
+  return [...roster, ...rows.map((row) => row.name)];
 }
 
 export function recordConsistencyReport(params: {
@@ -516,7 +784,7 @@ export function recordConsistencyReport(params: {
 export function recordWriterExport(params: {
   id: string;
   projectId: string;
-  exportType: "markdown" | "docx";
+  exportType: "markdown" | "docx" | "html";
   outputPath: string;
   createdAt: string;
 }) {
@@ -555,6 +823,36 @@ export function toSceneJson(row: WriterSceneRow) {
     goals: row.goals,
     conflicts: row.conflicts,
     outcomes: row.outcomes,
+    characterId: row.character_id ?? null,
+    chatId: row.chat_id ?? null,
+    position: row.position,
+    createdAt: row.created_at
+  };
+}
+
+export function toSceneRevisionJson(row: WriterSceneRevisionRow) {
+  return {
+    id: row.id,
+    sceneId: row.scene_id,
+    title: row.title,
+    content: row.content,
+    reason: row.reason,
     createdAt: row.created_at
   };
 }
+
+export interface ConsistencyReportRow {
+  id: string;
+  project_id: string;
+  payload: string;
+  created_at: string;
+}
+
+export function listConsistencyReportRows(projectId: string): ConsistencyReportRow[] {
+  return db.prepare("SELECT * FROM writer_consistency_reports WHERE project_id = ? ORDER BY created_at DESC, id DESC")
+    .all(projectId) as ConsistencyReportRow[];
+}
+
+export function getConsistencyReportRow(reportId: string): ConsistencyReportRow | undefined {
+  return db.prepare("SELECT * FROM writer_consistency_reports WHERE id = ?").get(reportId) as ConsistencyReportRow | undefined;
+}
@@ -1,5 +1,8 @@
 import { Router } from "express";
 import { db, newId, now, hashSecret, needsSecretRehash, verifySecret } from "../db.js";
+import { accountExists, isAppLocked } from "../app/accountLockGuard.js";
+import { getSettings } from "../modules/chat/routeHelpers.js";
+import { lockAccount, markAccountUnlocked, normalizeAutoLockMinutes } from "../services/accountLock.js";
 
 const router = Router();
 
@@ -18,6 +21,7 @@ router.post("/create", (req, res) => {
 
   db.prepare("INSERT INTO accounts (id, password_hash, recovery_hash, created_at) VALUES (?, ?, ?, ?)")
     .run(id, passwordHash, recoveryHash, now());
+  markAccountUnlocked();
 
   res.json(id);
 });
@@ -45,6 +49,7 @@ router.post("/unlock", (req, res) => {
       .run(hashSecret(recoveryKey));
   }
 
+  if (passOk || recoveryOk) markAccountUnlocked();
   res.json(passOk || recoveryOk);
 });
 
@@ -64,4 +69,49 @@ router.post("/rotate-recovery", (req, res) => {
   res.json({ ok: true });
 });
 
+router.post("/lock", (_req, res) => {
+  lockAccount();
+  res.json({ ok: true });
+});
+
+router.get("/status", (_req, res) => {
+  res.json({
+    hasAccount: accountExists(),
+    locked: isAppLocked(),
+    autoLockMinutes: normalizeAutoLockMinutes(getSettings().autoLockMinutes)
+  });
+});
+
+// Nothing else is stored under a password-derived key (provider keys are kept as-is), so only the hash changes.
+router.post("/change-password", (req, res) => {
+  const { oldPassword, newPassword } = req.body as { oldPassword: string; newPassword: string };
+  let newHash: string;
+  try {
+    newHash = hashSecret(newPassword);
+  } catch (error) {
+    res.status(400).json({ error: error instanceof Error ? error.message : "Invalid password" });
+    return;
+  }
+
+  const changePassword = db.transaction(() => {
+    const row = db.prepare("SELECT id, password_hash FROM accounts ORDER BY created_at DESC LIMIT 1")
+      .get() as { id: string; password_hash: string } | undefined;
+    if (!row) return "missing";
+    if (!verifySecret(oldPassword, row.password_hash)) return "denied";
+    db.prepare("UPDATE accounts SET password_hash = ? WHERE id = ?").run(newHash, row.id);
+    return "changed";
+  });
+
+  const outcome = changePassword();
+  if (outcome === "missing") {
+    res.status(404).json({ error: "No account exists" });
+    return;
+  }
+  if (outcome === "denied") {
+    res.status(403).json({ error: "Current password is incorrect" });
+    return;
+  }
+  res.json({ ok: true });
+});
+
 export default router;
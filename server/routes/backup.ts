@@ -0,0 +1,96 @@
+import { Router } from "express";
+import { createWriteStream, existsSync, mkdirSync, readdirSync, readFileSync, rmSync, writeFileSync } from "fs";
+import { isAbsolute, join, relative, resolve } from "path";
+import { pipeline } from "stream/promises";
+import { BACKUPS_DIR, DATA_DIR, db } from "../db.js";
+import { collectBackupPayload, decryptBackup, encryptBackup, restoreBackupPayload } from "../db/backup.js";
+
+const router = Router();
+
+function readPassword(value: unknown): string | null {
+  const password = typeof value === "string" ? value : "";
+  return password && password.length <= 1024 ? password : null;
+}
+
+// Imports only read from the backups folder; a file from elsewhere is streamed in through `/upload` first.
+function resolveBackupPath(value: unknown): string | null {
+  const raw = String(value || "").trim();
+  if (!raw) return null;
+  const resolved = resolve(BACKUPS_DIR, raw);
+  const fromBackups = relative(BACKUPS_DIR, resolved);
+  if (!fromBackups || fromBackups.startsWith("..") || isAbsolute(fromBackups)) return null;
+  return resolved;
+}
+
+router.post("/export", (req, res) => {
+  const password = readPassword(req.body?.password);
+  if (!password) {
+    res.status(400).json({ error: "Backup password must contain between 1 and 1024 characters" });
+    return;
+  }
+  mkdirSync(BACKUPS_DIR, { recursive: true });
+  const payload = collectBackupPayload(db, DATA_DIR);
+  const fileName = `vellium-backup-${payload.createdAt.replace(/[:.]/g, "-")}.vbak`;
+  const path = join(BACKUPS_DIR, fileName);
+  writeFileSync(path, encryptBackup(payload, password), "utf8");
+  res.json({ path, fileName, createdAt: payload.createdAt });
+});
+
+// The raw request body is written straight to disk, so backups with avatars and uploads are not held
+// to the JSON body limit. The returned `fileName` is then passed to `/import` as `path`.
+router.post("/upload", async (req, res) => {
+  if (req.is("application/json")) {
+    res.status(400).json({ error: "Send the backup file as the raw request body" });
+    return;
+  }
+  mkdirSync(BACKUPS_DIR, { recursive: true });
+  const fileName = `vellium-upload-${new Date().toISOString().replace(/[:.]/g, "-")}.vbak`;
+  const path = join(BACKUPS_DIR, fileName);
+  try {
+    await pipeline(req, createWriteStream(path));
+  } catch (error) {
+    rmSync(path, { force: true });
+    res.status(400).json({ error: `Backup upload failed: ${error instanceof Error ? error.message : String(error)}` });
+    return;
+  }
+  res.json({ fileName });
+});
+
+router.post("/import", (req, res) => {
+  const password = readPassword(req.body?.password);
+  if (!password) {
+    res.status(400).json({ error: "Backup password must contain between 1 and 1024 characters" });
+    return;
+  }
+
+  const path = resolveBackupPath(req.body?.path);
+  if (!path || !existsSync(path)) {
+    res.status(404).json({ error: "Backup file not found in the backups folder" });
+    return;
+  }
+  const raw = readFileSync(path, "utf8");
+
+  let payload;
+  try {
+    payload = decryptBackup(raw, password);
+  } catch (error) {
+    res.status(400).json({ error: error instanceof Error ? error.message : "Invalid backup" });
+    return;
+  }
+
+  try {
+    const result = restoreBackupPayload(db, payload, DATA_DIR);
+    res.json({ ok: true, createdAt: payload.createdAt, ...result });
+  } catch (error) {
+    res.status(409).json({ error: `Backup could not be restored: ${error instanceof Error ? error.message : String(error)}` });
+  }
+});
+
+router.get("/", (_req, res) => {
+  const fileNames = existsSync(BACKUPS_DIR)
+    ? readdirSync(BACKUPS_DIR).filter((name) => name.endsWith(".vbak")).sort().reverse()
+    : [];
+  res.json({ dir: BACKUPS_DIR, fileNames });
+});
+
+export default router;
@@ -1,11 +1,16 @@
 import { Router } from "express";
-import { existsSync, unlinkSync, writeFileSync } from "fs";
+import type { Response } from "express";
+import { existsSync, readFileSync, unlinkSync, writeFileSync } from "fs";
 import { join } from "path";
-import { db, newId, now, AVATARS_DIR, DEFAULT_SETTINGS, isLocalhostUrl } from "../db.js";
+import { db, newId, now, AVATARS_DIR, DEFAULT_SETTINGS } from "../db.js";
+import { embedCharacterCardInPng, extractCharacterCardJsonFromPng, isPngBuffer } from "../domain/characterCardPng.js";
+import { isCharacterCardV1, toCharacterCardV2View, upgradeCharacterCardV1ToV2, upgradeCharacterCardV2ToV3 } from "../domain/characterCardSpec.js";
+import type { CharacterCardEnvelope } from "../domain/characterCardSpec.js";
+import { validateCharacterCard } from "../domain/characterCardValidation.js";
 import { parseCharacterLoreBook } from "../domain/lorebooks.js";
-import { buildOpenAiSamplingPayload, buildKoboldSamplerConfig, normalizeApiParamPolicy } from "../services/apiParamPolicy.js";
-import { buildKoboldGenerateBody, extractKoboldGeneratedText, normalizeProviderType, requestKoboldGenerate } from "../services/providerApi.js";
-import { completeCustomAdapter } from "../services/customProviderAdapters.js";
+import { completeLlm, LlmUnavailableError, resolveLlm } from "../modules/llm/completion.js";
+import { normalizeApiParamPolicy } from "../services/apiParamPolicy.js";
+import { notConfiguredError, sendAppError } from "../services/appError.js";
 
 const router = Router();
 
@@ -27,24 +32,6 @@ interface CharacterRow {
   created_at: string;
 }
 
-interface ProviderRow {
-  id: string;
-  name: string;
-  base_url: string;
-  api_key_cipher: string;
-  provider_type: string | null;
-  full_local_only: number;
-  adapter_id: string | null;
-}
-
-const KOBOLD_TAGS = {
-  systemOpen: "{{[SYSTEM]}}",
-  systemClose: "{{[SYSTEM_END]}}",
-  inputOpen: "{{[INPUT]}}",
-  inputClose: "{{[INPUT_END]}}",
-  outputOpen: "{{[OUTPUT]}}"
-};
-
 function parseCardData(cardJson: string | null | undefined): Record<string, unknown> {
   if (!cardJson) return {};
   try {
@@ -58,6 +45,11 @@ function parseCardData(cardJson: string | null | undefined): Record<string, unkn
   return {};
 }
 
+function readCardEnvelope(cardJson: string | null | undefined): CharacterCardEnvelope {
+  const spec = parseCardSpec(cardJson);
+  return { spec, spec_version: spec === "chara_card_v3" ? "3.0" : "2.0", data: parseCardData(cardJson) };
+}
+
 function parseString(value: unknown): string {
   return typeof value === "string" ? value : "";
 }
@@ -69,6 +61,22 @@ function parseStringArray(value: unknown): string[] {
     .filter(Boolean);
 }
 
+function parseCardSpec(cardJson: string | null | undefined): "chara_card_v2" | "chara_card_v3" {
+  try {
+    return (JSON.parse(cardJson || "{}") as { spec?: unknown }).spec === "chara_card_v3" ? "chara_card_v3" : "chara_card_v2";
+  } catch {
+    return "chara_card_v2";
+  }
+}
+
+function parseCardAssets(value: unknown): Array<{ type: string; uri: string; name: string; ext: string }> {
+  if (!Array.isArray(value)) return [];
+  return value
+    .filter((item): item is Record<string, unknown> => Boolean(item) && typeof item === "object" && !Array.isArray(item))
+    .map((item) => ({ type: parseString(item.type), uri: parseString(item.uri), name: parseString(item.name), ext: parseString(item.ext) }))
+    .filter((asset) => asset.type && asset.uri);
+}
+
 function parseRecord(value: unknown): Record<string, unknown> {
   if (!value || typeof value !== "object" || Array.isArray(value)) return {};
   return value as Record<string, unknown>;
@@ -113,13 +121,6 @@ function parseAgentProfile(value: unknown) {
   };
 }
 
-function normalizeOpenAiBaseUrl(raw: string): string {
-  const trimmed = String(raw || "").trim().replace(/\/+$/, "");
-  if (!trimmed) return "";
-  if (/\/v1$/i.test(trimmed)) return trimmed;
-  return `${trimmed}/v1`;
-}
-
 function sanitizeHeaderFilenameAscii(name: string, fallback: string): string {
   const clean = String(name || "")
     .replace(/[\r\n]/g, " ")
@@ -168,87 +169,11 @@ function getSettings(): Record<string, unknown> {
   }
 }
 
-async function completeProviderOnce(params: {
-  provider: ProviderRow;
-  modelId: string;
-  systemPrompt: string;
-  userPrompt: string;
-  samplerConfig?: Record<string, unknown>;
-  apiParamPolicy?: unknown;
-}): Promise<string> {
-  const providerType = normalizeProviderType(params.provider.provider_type);
-  const sc = params.samplerConfig || {};
-
-  if (providerType === "koboldcpp") {
-    const koboldSamplerConfig = buildKoboldSamplerConfig({
-      samplerConfig: {
-        ...sc,
-        maxTokens: sc.maxTokens ?? 2048
-      },
-      apiParamPolicy: params.apiParamPolicy
-    });
-    const memory = params.systemPrompt.trim()
-      ? `${KOBOLD_TAGS.systemOpen}\n${params.systemPrompt.trim()}\n${KOBOLD_TAGS.systemClose}`
-      : "";
-    const body = buildKoboldGenerateBody({
-      prompt: `${KOBOLD_TAGS.inputOpen}\n${params.userPrompt}\n${KOBOLD_TAGS.inputClose}\n\n${KOBOLD_TAGS.outputOpen}`,
-      memory,
-      samplerConfig: koboldSamplerConfig,
-      includeMemory: true
-    });
-    const response = await requestKoboldGenerate(params.provider, body);
-    if (!response.ok) return "";
-    const parsed = await response.json().catch(() => ({}));
-    return extractKoboldGeneratedText(parsed).trim();
-  }
-
-  if (providerType === "custom") {
-    return completeCustomAdapter({
-      provider: params.provider,
-      modelId: params.modelId,
-      systemPrompt: params.systemPrompt,
-      userPrompt: params.userPrompt,
-      samplerConfig: sc
-    });
-  }
-
-  const baseUrl = normalizeOpenAiBaseUrl(params.provider.base_url);
-  if (!baseUrl) return "";
-  const openAiSampling = buildOpenAiSamplingPayload({
-    samplerConfig: sc,
-    apiParamPolicy: params.apiParamPolicy,
-    fields: ["temperature", "maxTokens"],
-    defaults: {
-      temperature: 0.2,
-      maxTokens: 2048
-    }
-  });
-  const response = await fetch(`${baseUrl}/chat/completions`, {
-    method: "POST",
-    headers: {
-      "Content-Type": "application/json",
-      Authorization: `Bearer ${params.provider.api_key_cipher}`
-    },
-    body: JSON.stringify({
-      model: params.modelId,
-      messages: [
-        { role: "system", content: params.systemPrompt },
-        { role: "user", content: params.userPrompt }
-      ],
-      ...openAiSampling
-    })
-  });
-  if (!response.ok) return "";
-  const body = await response.json() as { choices?: Array<{ message?: { content?: string } }> };
-  return body.choices?.[0]?.message?.content?.trim() ?? "";
-}
-
 async function translateCharacterField(params: {
   value: string;
   targetLanguage: string;
-  provider: ProviderRow;
+  providerId: string;
   modelId: string;
-  apiParamPolicy?: unknown;
 }): Promise<string> {
   const raw = String(params.value || "");
   if (!raw.trim()) return raw;
@@ -288,22 +213,26 @@ async function translateCharacterField(params: {
 
     let translatedChunk = "";
     for (let attempt = 0; attempt < 3; attempt += 1) {
-      translatedChunk = await completeProviderOnce({
-        provider: params.provider,
+      // A failed attempt counts as an empty reply so the retry below still runs.
+      translatedChunk = await completeLlm([
+        {
+          role: "system",
+          content: [
+            `Translate this character card field to ${params.targetLanguage}.`,
+            "Output ONLY the translated text.",
+            "Do NOT modify placeholder markers like [[[KEEP_0]]].",
+            "Preserve placeholders, markdown, line breaks, and XML-like tags exactly as-is."
+          ].join(" ")
+        },
+        { role: "user", content: protectedChunk }
+      ], {
+        providerId: params.providerId,
         modelId: params.modelId,
-        systemPrompt: [
-          `Translate this character card field to ${params.targetLanguage}.`,
-          "Output ONLY the translated text.",
-          "Do NOT modify placeholder markers like [[[KEEP_0]]].",
-          "Preserve placeholders, markdown, line breaks, and XML-like tags exactly as-is."
-        ].join(" "),
-        userPrompt: protectedChunk,
         samplerConfig: {
           temperature: 0.2,
           maxTokens: Math.max(800, Math.min(3072, Math.round(chunk.length * 1.2)))
-        },
-        apiParamPolicy: params.apiParamPolicy
-      });
+        }
+      }).catch(() => "");
       if (String(translatedChunk || "").trim()) break;
       if (attempt < 2) {
         await new Promise((resolve) => setTimeout(resolve, 150 * (attempt + 1)));
@@ -354,6 +283,10 @@ function characterToJson(row: CharacterRow) {
     creatorNotesMultilingual: parseRecord(cardData.creator_notes_multilingual),
     extensions,
     agentProfile: parseAgentProfile(extensions.vellium_agent),
+    cardSpec: parseCardSpec(row.card_json),
+    nickname: parseString(cardData.nickname),
+    groupOnlyGreetings: parseStringArray(cardData.group_only_greetings),
+    assets: parseCardAssets(cardData.assets),
     cardJson: row.card_json,
     sortOrder: row.sort_order,
     createdAt: row.created_at
@@ -391,93 +324,144 @@ router.patch("/reorder", (req, res) => {
   res.json(rows.map(characterToJson));
 });
 
+const SUPPORTED_CARD_SPECS = new Set(["chara_card_v2", "chara_card_v3"]);
+
 // Validate chara_card_v2 JSON
 router.post("/validate", (req, res) => {
   const { rawJson } = req.body;
   try {
-    const parsed = JSON.parse(rawJson) as { spec?: string; data?: Record<string, unknown> };
-    const errors: string[] = [];
-    if (parsed.spec !== "chara_card_v2") errors.push("spec must be chara_card_v2");
-    if (!parsed.data) errors.push("missing data object");
-    if (parsed.data && !parsed.data.name) errors.push("missing data.name");
+    const errors = validateCharacterCard(JSON.parse(String(rawJson ?? "")));
     res.json({ valid: errors.length === 0, errors });
   } catch (e) {
     res.json({ valid: false, errors: [String(e)] });
   }
 });
 
-// Import character from chara_card_v2 JSON
-router.post("/import", (req, res) => {
-  const { rawJson } = req.body;
+// Convert a flat V1 card into chara_card_v2 JSON for the caller to import
+router.post("/convert-v1", (req, res) => {
+  let parsed: unknown;
   try {
-    const parsed = JSON.parse(rawJson) as { spec?: string; spec_version?: string; data?: Record<string, unknown> };
-    if (parsed.spec !== "chara_card_v2") {
-      res.status(400).json({ error: "Invalid spec — expected chara_card_v2" });
-      return;
-    }
-
-    const data = (parsed.data || {}) as Record<string, unknown>;
-    const id = newId();
-    const name = String(data.name || "Unnamed").trim() || "Unnamed";
-    const tags = JSON.stringify(Array.isArray(data.tags) ? data.tags : []);
-    const greeting = String(data.first_mes || "");
-    const systemPrompt = String(data.system_prompt || "");
-    const description = String(data.description || "");
-    const personality = String(data.personality || "");
-    const scenario = String(data.scenario || "");
-    const mesExample = String(data.mes_example || "");
-    const creatorNotes = String(data.creator_notes || "");
-    const avatarPath = data.avatar ? String(data.avatar) : null;
-    const ts = now();
-    const parsedLorebook = parseCharacterLoreBook(data);
-    let lorebookId: string | null = null;
-
-    const importTx = db.transaction(() => {
-      db.prepare("UPDATE characters SET sort_order = sort_order + 1").run();
-      if (parsedLorebook) {
-        lorebookId = newId();
-        db.prepare(
-          "INSERT INTO lorebooks (id, name, description, entries_json, source_character_id, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
-        ).run(
-          lorebookId,
-          parsedLorebook.name,
-          parsedLorebook.description,
-          JSON.stringify(parsedLorebook.entries),
-          id,
-          ts,
-          ts
-        );
-      }
+    parsed = JSON.parse(String(req.body?.rawJson ?? ""));
+  } catch (e) {
+    res.status(400).json({ error: String(e) });
+    return;
+  }
+  if (!isCharacterCardV1(parsed)) {
+    res.status(400).json({ error: "Not a V1 card — expected top-level name or first_mes without spec" });
+    return;
+  }
+  const card = upgradeCharacterCardV1ToV2(parsed);
+  res.json({ rawJson: JSON.stringify(card, null, 2), card });
+});
 
+function importCharacterCard(
+  parsed: { spec?: string; data?: Record<string, unknown> },
+  rawJson: string,
+  options: { avatarPng?: Buffer } = {}
+): CharacterRow {
+  const data = (parsed.data || {}) as Record<string, unknown>;
+  const id = newId();
+  const name = String(data.name || "Unnamed").trim() || "Unnamed";
+  const tags = JSON.stringify(Array.isArray(data.tags) ? data.tags : []);
+  const greeting = String(data.first_mes || "");
+  const systemPrompt = String(data.system_prompt || "");
+  const description = String(data.description || "");
+  const personality = String(data.personality || "");
+  const scenario = String(data.scenario || "");
+  const mesExample = String(data.mes_example || "");
+  const creatorNotes = String(data.creator_notes || "");
+  let avatarPath = data.avatar ? String(data.avatar) : null;
+  const ts = now();
+  const parsedLorebook = parseCharacterLoreBook(data);
+  let lorebookId: string | null = null;
+
+  // Keep the original card PNG byte-for-byte so the avatar can be re-exported with its metadata.
+  if (options.avatarPng) {
+    avatarPath = `${id}-${Date.now()}.png`;
+    writeFileSync(join(AVATARS_DIR, avatarPath), options.avatarPng);
+  }
+
+  const importTx = db.transaction(() => {
+    db.prepare("UPDATE characters SET sort_order = sort_order + 1").run();
+    if (parsedLorebook) {
+      lorebookId = newId();
       db.prepare(
-        `INSERT INTO characters (id, name, card_json, lorebook_id, avatar_path, tags, greeting, system_prompt, description, personality, scenario, mes_example, creator_notes, sort_order, created_at)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`
+        "INSERT INTO lorebooks (id, name, description, entries_json, source_character_id, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
       ).run(
-        id,
-        name,
-        rawJson,
         lorebookId,
-        avatarPath,
-        tags,
-        greeting,
-        systemPrompt,
-        description,
-        personality,
-        scenario,
-        mesExample,
-        creatorNotes,
-        1,
+        parsedLorebook.name,
+        parsedLorebook.description,
+        JSON.stringify(parsedLorebook.entries),
+        id,
+        ts,
         ts
       );
-    });
+    }
+
+    db.prepare(
+      `INSERT INTO characters (id, name, card_json, lorebook_id, avatar_path, tags, greeting, system_prompt, description, personality, scenario, mes_example, creator_notes, sort_order, created_at)
+       VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`
+    ).run(
+      id,
+      name,
+      rawJson,
+      lorebookId,
+      avatarPath,
+      tags,
+      greeting,
+      systemPrompt,
+      description,
+      personality,
+      scenario,
+      mesExample,
+      creatorNotes,
+      1,
+      ts
+    );
+  });
 
-    importTx();
+  importTx();
 
-    const row = db.prepare("SELECT * FROM characters WHERE id = ?").get(id) as CharacterRow;
-    res.json(characterToJson(row));
+  return db.prepare("SELECT * FROM characters WHERE id = ?").get(id) as CharacterRow;
+}
+
+function sendImportedCharacter(res: Response, rawJson: string, options: { avatarPng?: Buffer } = {}) {
+  try {
+    let parsed = JSON.parse(rawJson) as { spec?: string; spec_version?: string; data?: Record<string, unknown> };
+    if (isCharacterCardV1(parsed)) {
+      parsed = upgradeCharacterCardV1ToV2(parsed);
+      rawJson = JSON.stringify(parsed, null, 2);
+    }
+    if (!SUPPORTED_CARD_SPECS.has(String(parsed.spec || ""))) {
+      res.status(400).json({ error: "Invalid spec — expected chara_card_v2 or chara_card_v3" });
+      return;
+    }
+    res.json(characterToJson(importCharacterCard(parsed, rawJson, options)));
   } catch (e) {
     res.status(400).json({ error: String(e) });
   }
+}
+
+// Import character from chara_card_v2 JSON
+router.post("/import", (req, res) => {
+  sendImportedCharacter(res, String(req.body?.rawJson ?? ""));
+});
+
+// Import character from a card PNG (base64 card JSON in a `chara` / `ccv3` tEXt chunk)
+router.post("/import/png", (req, res) => {
+  const base64 = String(req.body?.base64Data || "").trim();
+  const normalized = base64.includes(",") ? base64.slice(base64.indexOf(",") + 1) : base64;
+  const buffer = Buffer.from(normalized, "base64");
+  if (!buffer.length || buffer.length > 20 * 1024 * 1024 || !isPngBuffer(buffer)) {
+    res.status(400).json({ error: "Invalid PNG payload" });
+    return;
+  }
+  const rawJson = extractCharacterCardJsonFromPng(buffer);
+  if (!rawJson) {
+    res.status(400).json({ error: "PNG has no embedded character card" });
+    return;
+  }
+  sendImportedCharacter(res, rawJson, { avatarPng: buffer });
 });
 
 router.get("/:id/export/json", (req, res) => {
@@ -489,9 +473,50 @@ router.get("/:id/export/json", (req, res) => {
   const filename = `${buildFilenameBase(row.name, "character")}.json`;
   res.setHeader("Content-Type", "application/json; charset=utf-8");
   res.setHeader("Content-Disposition", buildAttachmentDisposition(filename, "character.json"));
+  const requestedSpec = String(req.query.spec || "").trim().toLowerCase();
+  if (requestedSpec === "v2" || requestedSpec === "v3") {
+    const card = readCardEnvelope(row.card_json);
+    const converted = requestedSpec === "v2" ? toCharacterCardV2View(card) : upgradeCharacterCardV2ToV3(card);
+    res.send(JSON.stringify(converted, null, 2));
+    return;
+  }
   res.send(row.card_json || "{}");
 });
 
+// Export as a PNG card: the provided (or stored) avatar with the card JSON in a `chara` tEXt chunk
+router.post("/:id/export/png", (req, res) => {
+  const row = db.prepare("SELECT * FROM characters WHERE id = ?").get(req.params.id) as CharacterRow | undefined;
+  if (!row) {
+    res.status(404).json({ error: "Character not found" });
+    return;
+  }
+  let avatar: Buffer | null = null;
+  const base64 = String(req.body?.base64Data || "").trim();
+  if (base64) {
+    avatar = Buffer.from(base64.includes(",") ? base64.slice(base64.indexOf(",") + 1) : base64, "base64");
+  } else if (row.avatar_path && !row.avatar_path.startsWith("http")) {
+    const avatarFile = join(AVATARS_DIR, row.avatar_path);
+    if (existsSync(avatarFile)) avatar = readFileSync(avatarFile);
+  }
+  if (!avatar || !isPngBuffer(avatar)) {
+    res.status(400).json({ error: "A PNG avatar is required to export a PNG card" });
+    return;
+  }
+  let png: Buffer;
+  try {
+    png = parseCardSpec(row.card_json) === "chara_card_v3"
+      ? embedCharacterCardInPng(avatar, JSON.stringify(toCharacterCardV2View(readCardEnvelope(row.card_json))), row.card_json)
+      : embedCharacterCardInPng(avatar, row.card_json || "{}");
+  } catch (e) {
+    res.status(400).json({ error: e instanceof Error ? e.message : String(e) });
+    return;
+  }
+  const filename = `${buildFilenameBase(row.name, "character")}.png`;
+  res.setHeader("Content-Type", "image/png");
+  res.setHeader("Content-Disposition", buildAttachmentDisposition(filename, "character.png"));
+  res.send(png);
+});
+
 // Get character by ID
 router.get("/:id", (req, res) => {
   const row = db.prepare("SELECT * FROM characters WHERE id = ?").get(req.params.id) as CharacterRow | undefined;
@@ -520,6 +545,7 @@ router.put("/:id", (req, res) => {
     creator,
     characterVersion,
     creatorNotesMultilingual,
+    nickname,
     extensions
   } = req.body;
 
@@ -562,11 +588,15 @@ router.put("/:id", (req, res) => {
   if (creatorNotesMultilingual !== undefined) {
     cardData.creator_notes_multilingual = parseRecord(creatorNotesMultilingual);
   }
+  if (nickname !== undefined) {
+    cardData.nickname = parseString(nickname);
+  }
   if (extensions !== undefined) {
     cardData.extensions = parseRecord(extensions);
   }
 
-  const cardJson = JSON.stringify({ spec: "chara_card_v2", spec_version: "2.0", data: cardData }, null, 2);
+  const cardSpec = parseCardSpec(existing.card_json);
+  const cardJson = JSON.stringify({ spec: cardSpec, spec_version: cardSpec === "chara_card_v3" ? "3.0" : "2.0", data: cardData }, null, 2);
   const nextName = String(cardData.name || existing.name || "Unnamed").trim() || "Unnamed";
   const nextDescription = String(cardData.description || "");
   const nextPersonality = String(cardData.personality || "");
@@ -623,21 +653,17 @@ router.post("/:id/translate-copy", async (req, res) => {
   }
 
   if (!providerId || !modelId) {
-    res.status(400).json({ error: "Translate provider/model is not configured in Settings." });
+    sendAppError(res, notConfiguredError("Translate provider/model is not configured in Settings."));
     return;
   }
 
-  const provider = db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as ProviderRow | undefined;
-  if (!provider) {
-    res.status(400).json({ error: "Translate provider not found." });
-    return;
-  }
-  if (settings.fullLocalMode === true && !isLocalhostUrl(provider.base_url)) {
-    res.status(400).json({ error: "Provider blocked by Full Local Mode." });
-    return;
-  }
-  if (provider.full_local_only && !isLocalhostUrl(provider.base_url)) {
-    res.status(400).json({ error: "Provider requires localhost endpoint." });
+  try {
+    resolveLlm({ providerId, modelId });
+  } catch (error) {
+    const unavailable = error instanceof LlmUnavailableError ? error : null;
+    sendAppError(res, notConfiguredError(
+      unavailable?.reason === "provider_not_found" ? "Translate provider not found." : `${unavailable?.message ?? "Translate provider unavailable"}.`
+    ));
     return;
   }
 
@@ -649,9 +675,8 @@ router.post("/:id/translate-copy", async (req, res) => {
     const translate = (value: string) => translateCharacterField({
       value,
       targetLanguage,
-      provider,
-      modelId,
-      apiParamPolicy: settings.apiParamPolicy
+      providerId,
+      modelId
     });
 
     const translatedName = await translate(originalName);
@@ -1,9 +1,14 @@
 import { Router } from "express";
-import { db, isLocalhostUrl, newId, now, roughTokenCount, nextSortOrder } from "../db.js";
+import { writeFileSync } from "fs";
+import { join } from "path";
+import { db, DATA_DIR, UPLOADS_DIR, newId, now, countTokens, nextSortOrder } from "../db.js";
 import type { Response } from "express";
+import { expandMacros } from "../domain/rpMacros.js";
 import {
   autoIngestTextAttachmentsForChat,
   buildPromptContentWithAttachments,
+  imagesToAttachments,
+  MAX_MESSAGE_ATTACHMENTS,
   normalizeCharacterIdList,
   normalizeLorebookIdList,
   resolveLorebookIds,
@@ -13,16 +18,23 @@ import {
 import {
   getSettings,
   getTimeline,
+  getTimelinePage,
+  MAX_TIMELINE_PAGE_SIZE,
   parseCardData,
+  pickGreetingAt,
   pickInitialGreeting,
   pickStringList,
   resolveBranch,
+  SWIPE_HIDDEN,
+  type MessageAttachmentPayload,
   type MessageRow,
   type ProviderRow,
   type UserPersonaPayload
 } from "../modules/chat/routeHelpers.js";
 import {
   activeAbortControllers,
+  previewChatPrompt,
+  streamContinuation,
   streamLlmResponse
 } from "../modules/chat/chatOrchestrator.js";
 import {
@@ -33,14 +45,32 @@ import {
   ttsTextRealtime,
   ttsText
 } from "../modules/chat/contentHandlers.js";
-import { completeProviderOnce, countProviderTokens } from "../modules/chat/providerExecution.js";
+import { countProviderTokens } from "../modules/chat/providerExecution.js";
+import { completeLlm, LlmUnavailableError } from "../modules/llm/completion.js";
+import { buildChatTitlePrompt, CHAT_TITLE_SYSTEM_PROMPT, normalizeGeneratedChatTitle } from "../modules/chat/chatTitle.js";
+import { searchMessages } from "../modules/chat/messageSearch.js";
+import {
+  buildClientToolExchange,
+  normalizeClientTools,
+  parseStoredClientToolCall,
+  type ClientToolDefinition
+} from "../modules/chat/clientTools.js";
+import { serializeToolTrace } from "../modules/chat/tooling.js";
 import {
   deleteBranch,
   deleteChatCascade,
   deleteMessageTree,
   forkBranch,
+  isChatTrashed,
   listBranches,
-  renameBranch
+  listDeletedMessages,
+  listTrashedChats,
+  mergeBranch,
+  purgeDeletedMessages,
+  restoreChat,
+  setActiveBranch,
+  renameBranch,
+  trashChat
 } from "../modules/chat/repository.js";
 import {
   getChatPreset,
@@ -48,12 +78,54 @@ import {
   updateChatPreset,
   updateChatSampler
 } from "../modules/chat/settingsHandlers.js";
+import {
+  AppError,
+  conflictError,
+  notFoundError,
+  sendAppError,
+  validationError
+} from "../services/appError.js";
+import { moderateUserMessage } from "../services/moderation.js";
+import { normalizeProviderType } from "../services/providerApi.js";
 import { getChatRagBinding, setChatRagBinding } from "../services/rag.js";
-import { exportChatJson } from "../modules/chat/exportChat.js";
+import { chatExportFileBase, exportChatDataset, exportChatJson, exportChatMarkdown } from "../modules/chat/exportChat.js";
 import { readCharacterSceneDefaults } from "../modules/chat/characterSceneDefaults.js";
 
 const router = Router();
 
+type PersonaRow = { name: string; description: string; personality: string; scenario: string };
+
+// Resolution order: the chat's pinned persona, then the persona sent with the request, then the default persona.
+// Client tools only run through the OpenAI-compatible tool-call loop, so other providers refuse them up front.
+function clientToolsUnsupportedError(clientTools: ClientToolDefinition[]): AppError | null {
+  if (clientTools.length === 0) return null;
+  const providerId = String(getSettings().activeProviderId || "").trim();
+  if (!providerId) return null;
+  const provider = db.prepare("SELECT provider_type FROM providers WHERE id = ?").get(providerId) as
+    { provider_type?: string } | undefined;
+  if (!provider || normalizeProviderType(provider.provider_type) === "openai") return null;
+  return validationError("Client tools need an OpenAI-compatible provider");
+}
+
+function readUserPersona(
+  chatId: string,
+  body: { userPersona?: Partial<UserPersonaPayload> | null; userName?: unknown } | undefined
+): UserPersonaPayload {
+  const pinned = db.prepare(
+    "SELECT p.name, p.description, p.personality, p.scenario FROM chats c JOIN user_personas p ON p.id = c.persona_id WHERE c.id = ?"
+  ).get(chatId) as PersonaRow | undefined;
+  const fallback = pinned ?? (!body?.userPersona && !body?.userName
+    ? db.prepare("SELECT name, description, personality, scenario FROM user_personas WHERE is_default = 1 LIMIT 1").get() as PersonaRow | undefined
+    : undefined);
+  const userPersona = fallback ?? body?.userPersona;
+  return {
+    name: String(userPersona?.name || body?.userName || "User"),
+    description: String(userPersona?.description || ""),
+    personality: String(userPersona?.personality || ""),
+    scenario: String(userPersona?.scenario || "")
+  };
+}
+
 // --- Routes ---
 
 // Abort/interrupt stream
@@ -69,6 +141,33 @@ router.post("/:id/abort", (req, res) => {
   }
 });
 
+function insertCharacterGreeting(
+  chatId: string,
+  branchId: string,
+  character: { name: string; greeting: string; card_json: string },
+  settings: ReturnType<typeof getSettings>,
+  ts: string,
+  greetingIndex?: number
+) {
+  const cardData = parseCardData(character.card_json);
+  const alternateGreetings = pickStringList(cardData.alternate_greetings);
+  const firstGreeting = String(character.greeting || "").trim();
+  // {{user}} stays for send-time expansion when no default persona is set.
+  const defaultPersona = db.prepare("SELECT name, description FROM user_personas WHERE is_default = 1 LIMIT 1")
+    .get() as { name: string; description: string } | undefined;
+  const greetingToInsert = expandMacros(
+    greetingIndex !== undefined
+      ? pickGreetingAt(firstGreeting, alternateGreetings, greetingIndex) ?? ""
+      : pickInitialGreeting(firstGreeting, alternateGreetings, settings.useAlternateGreetings === true),
+    { char: character.name, user: defaultPersona?.name || undefined, persona: defaultPersona?.description }
+  );
+  if (greetingToInsert) {
+    db.prepare(
+      "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, parent_id, deleted, created_at, character_name, sort_order) VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?)"
+    ).run(newId(), chatId, branchId, "assistant", greetingToInsert, countTokens(greetingToInsert, settings.activeModel), null, ts, character.name, nextSortOrder(chatId, branchId));
+  }
+}
+
 router.post("/", (req, res) => {
   const { title, characterId, characterIds } = req.body;
   const settings = getSettings();
@@ -86,6 +185,17 @@ router.post("/", (req, res) => {
       lorebook_id: string | null;
     } | undefined
     : undefined;
+  // Optional pick among the card's greetings: 0 is first_mes, 1..n the alternate greetings.
+  const greetingIndex = req.body?.greetingIndex === undefined || req.body?.greetingIndex === null
+    ? undefined
+    : Number(req.body.greetingIndex);
+  if (greetingIndex !== undefined) {
+    const alternates = firstChar ? pickStringList(parseCardData(firstChar.card_json).alternate_greetings) : [];
+    if (!firstChar || pickGreetingAt(firstChar.greeting, alternates, greetingIndex) === null) {
+      sendAppError(res, validationError("greetingIndex does not match a greeting of the character"));
+      return;
+    }
+  }
   let lorebookIds = normalizeLorebookIdList(req.body?.lorebookIds);
   if (lorebookIds.length === 0 && req.body?.lorebookId) {
     lorebookIds = [String(req.body.lorebookId).trim()].filter(Boolean);
@@ -112,15 +222,7 @@ router.post("/", (req, res) => {
 
     // If character has a greeting, insert it as first message.
     if (firstChar) {
-      const cardData = parseCardData(firstChar.card_json);
-      const alternateGreetings = pickStringList(cardData.alternate_greetings);
-      const firstGreeting = String(firstChar.greeting || "").trim();
-      const greetingToInsert = pickInitialGreeting(firstGreeting, alternateGreetings, settings.useAlternateGreetings === true);
-      if (greetingToInsert) {
-        db.prepare(
-          "INSERT INTO messages (id, chat_id, branch_id, role, content, token_count, parent_id, deleted, created_at, character_name, sort_order) VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?)"
-        ).run(newId(), chatId, branchId, "assistant", greetingToInsert, roughTokenCount(greetingToInsert), null, ts, firstChar.name, 1);
-      }
+      insertCharacterGreeting(chatId, branchId, firstChar, settings, ts, greetingIndex);
     }
   });
   createChat();
@@ -129,8 +231,8 @@ router.post("/", (req, res) => {
 });
 
 router.get("/", (_req, res) => {
-  const rows = db.prepare("SELECT * FROM chats ORDER BY created_at DESC").all() as {
-    id: string; title: string; character_id: string | null; character_ids: string | null; lorebook_id: string | null; lorebook_ids: string | null; auto_conversation: number; created_at: string;
+  const rows = db.prepare("SELECT * FROM chats WHERE deleted_at IS NULL ORDER BY created_at DESC").all() as {
+    id: string; title: string; character_id: string | null; character_ids: string | null; lorebook_id: string | null; lorebook_ids: string | null; auto_conversation: number; system_prompt: string | null; persona_id: string | null; response_language: string | null; created_at: string;
   }[];
   res.json(rows.map((r) => {
     let characterIds: string[] = [];
@@ -142,36 +244,37 @@ router.get("/", (_req, res) => {
       lorebookId: lorebookIds[0] || r.lorebook_id || null,
       lorebookIds,
       autoConversation: r.auto_conversation === 1,
+      systemPrompt: r.system_prompt || "",
+      personaId: r.persona_id || null,
+      responseLanguage: r.response_language || "",
       createdAt: r.created_at
     };
   }));
 });
 
-router.post("/desktop-pet/reply", async (req, res) => {
-  const content = String(req.body?.content || "").trim().slice(0, 1000);
-  if (!content) {
-    res.status(400).json({ error: "content is required" });
-    return;
-  }
+// Trashed chats, most recently deleted first
+router.get("/trash", (_req, res) => {
+  res.json(listTrashedChats());
+});
 
-  const settings = getSettings();
-  const providerId = String(settings.activeProviderId || "").trim();
-  const modelId = String(settings.activeModel || "").trim();
-  if (!providerId || !modelId) {
-    res.json({ reply: "[No provider configured] Configure a provider in Settings." });
+// Full-text search across non-deleted messages of all chats
+router.get("/search", (req, res) => {
+  const query = String(req.query.q || "").trim();
+  if (!query) {
+    sendAppError(res, validationError("q is required"));
     return;
   }
+  res.json(searchMessages(query, Number(req.query.limit) || 50));
+});
 
-  const provider = db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as ProviderRow | undefined;
-  if (!provider) {
-    res.json({ reply: "[Provider not found] Configure a provider in Settings." });
-    return;
-  }
-  if (settings.fullLocalMode && !isLocalhostUrl(provider.base_url)) {
-    res.status(400).json({ error: "Provider blocked by Full Local Mode" });
+router.post("/desktop-pet/reply", async (req, res) => {
+  const content = String(req.body?.content || "").trim().slice(0, 1000);
+  if (!content) {
+    sendAppError(res, validationError("content is required"));
     return;
   }
 
+  const settings = getSettings();
   const pet = req.body?.pet && typeof req.body.pet === "object" && !Array.isArray(req.body.pet)
     ? req.body.pet as Record<string, unknown>
     : {};
@@ -207,28 +310,35 @@ router.post("/desktop-pet/reply", async (req, res) => {
 
   const controller = new AbortController();
   const timeout = setTimeout(() => controller.abort(), 60000);
+  const userPrompt = [
+    recentConversation ? `[Recent Pet Conversation]\n${recentConversation}` : "",
+    screenContexts.length ? `[Screen Context]\nUp to two recent screenshots from this pet chat are attached. The desktop pet itself was hidden before capture, so do not claim to see the pet in the image unless it is actually visible.` : "",
+    `[Current User Message]\n${content}`
+  ].filter(Boolean).join("\n\n");
   try {
-    const reply = await completeProviderOnce({
-      provider,
-      modelId,
-      systemPrompt,
-      userPrompt: [
-        recentConversation ? `[Recent Pet Conversation]\n${recentConversation}` : "",
-        screenContexts.length ? `[Screen Context]\nUp to two recent screenshots from this pet chat are attached. The desktop pet itself was hidden before capture, so do not claim to see the pet in the image unless it is actually visible.` : "",
-        `[Current User Message]\n${content}`
-      ].filter(Boolean).join("\n\n"),
-      imageDataUrls: screenContexts.map((item) => item.dataUrl),
+    // completeLlm throws provider HTTP errors with their detail instead of turning them into an empty reply.
+    const reply = await completeLlm([
+      ...(systemPrompt ? [{ role: "system", content: systemPrompt }] : []),
+      {
+        role: "user",
+        content: screenContexts.length
+          ? [{ type: "text", text: userPrompt }, ...screenContexts.map((item) => ({ type: "image_url", image_url: { url: item.dataUrl } }))]
+          : userPrompt
+      }
+    ], {
       samplerConfig: {
         ...((settings.samplerConfig as Record<string, unknown> | undefined) || {}),
         maxTokens: 420
       },
-      apiParamPolicy: settings.apiParamPolicy,
       signal: controller.signal
     });
-    res.json({ reply: reply.trim() || "..." });
+    res.json({ reply: reply || "..." });
   } catch (error) {
-    const message = error instanceof Error ? error.message : "Desktop pet LLM request failed";
-    res.status(500).json({ error: message });
+    if (error instanceof LlmUnavailableError && (error.reason === "not_configured" || error.reason === "provider_not_found")) {
+      res.json({ reply: `[${error.message}] Configure a provider in Settings.` });
+      return;
+    }
+    sendAppError(res, error, "Desktop pet LLM request failed");
   } finally {
     clearTimeout(timeout);
   }
@@ -239,24 +349,139 @@ router.patch("/:id", (req, res) => {
   const chatId = req.params.id;
   const title = String(req.body?.title || "").trim();
   if (!title) {
-    res.status(400).json({ error: "title is required" });
+    sendAppError(res, validationError("title is required"));
     return;
   }
   const existing = db.prepare("SELECT id FROM chats WHERE id = ?").get(chatId) as { id: string } | undefined;
   if (!existing) {
-    res.status(404).json({ error: "Chat not found" });
+    sendAppError(res, notFoundError("Chat not found"));
     return;
   }
   db.prepare("UPDATE chats SET title = ? WHERE id = ?").run(title.slice(0, 160), chatId);
   res.json({ ok: true, title: title.slice(0, 160) });
 });
 
-// Delete chat
+// Per-chat system prompt override; an empty text clears it back to the settings default
+router.put("/:id/system-prompt", (req, res) => {
+  const chatId = req.params.id;
+  if (typeof req.body?.systemPrompt !== "string" && req.body?.systemPrompt != null) {
+    sendAppError(res, validationError("systemPrompt must be a string"));
+    return;
+  }
+  const existing = db.prepare("SELECT id FROM chats WHERE id = ?").get(chatId) as { id: string } | undefined;
+  if (!existing) {
+    sendAppError(res, notFoundError("Chat not found"));
+    return;
+  }
+  const systemPrompt = String(req.body?.systemPrompt || "").trim().slice(0, 20000);
+  db.prepare("UPDATE chats SET system_prompt = ? WHERE id = ?").run(systemPrompt || null, chatId);
+  res.json({ ok: true, systemPrompt });
+});
+
+// Per-chat response language; an empty value follows the settings language again
+router.put("/:id/response-language", (req, res) => {
+  const chatId = req.params.id;
+  if (typeof req.body?.responseLanguage !== "string" && req.body?.responseLanguage != null) {
+    sendAppError(res, validationError("responseLanguage must be a string"));
+    return;
+  }
+  const existing = db.prepare("SELECT id FROM chats WHERE id = ?").get(chatId) as { id: string } | undefined;
+  if (!existing) {
+    sendAppError(res, notFoundError("Chat not found"));
+    return;
+  }
+  const responseLanguage = String(req.body?.responseLanguage || "").trim().slice(0, 80);
+  db.prepare("UPDATE chats SET response_language = ? WHERE id = ?").run(responseLanguage || null, chatId);
+  res.json({ ok: true, responseLanguage });
+});
+
+// Pin a persona to this chat; null goes back to the globally active persona
+router.put("/:id/persona", (req, res) => {
+  const chatId = req.params.id;
+  const personaId = req.body?.personaId ?? null;
+  if (personaId !== null && typeof personaId !== "string") {
+    sendAppError(res, validationError("personaId must be a string or null"));
+    return;
+  }
+  const existing = db.prepare("SELECT id FROM chats WHERE id = ?").get(chatId) as { id: string } | undefined;
+  if (!existing) {
+    sendAppError(res, notFoundError("Chat not found"));
+    return;
+  }
+  if (personaId && !db.prepare("SELECT 1 FROM user_personas WHERE id = ?").get(personaId)) {
+    sendAppError(res, notFoundError("Persona not found"));
+    return;
+  }
+  db.prepare("UPDATE chats SET persona_id = ? WHERE id = ?").run(personaId || null, chatId);
+  res.json({ ok: true, personaId: personaId || null });
+});
+
+// Ask the active model for a short title from the first exchange and store it
+router.post("/:id/autotitle", async (req, res) => {
+  const chatId = req.params.id;
+  const existing = db.prepare("SELECT id FROM chats WHERE id = ?").get(chatId) as { id: string } | undefined;
+  if (!existing) {
+    sendAppError(res, notFoundError("Chat not found"));
+    return;
+  }
+  const messages = db.prepare(
+    "SELECT role, content FROM messages WHERE chat_id = ? AND deleted = 0 AND role IN ('user', 'assistant') ORDER BY created_at ASC, sort_order ASC, id ASC LIMIT 20"
+  ).all(chatId) as Array<{ role: string; content: string }>;
+  const firstUserIndex = messages.findIndex((message) => message.role === "user" && message.content.trim());
+  if (firstUserIndex < 0) {
+    sendAppError(res, validationError("Chat has no user message to title"));
+    return;
+  }
+  const firstReply = messages.slice(firstUserIndex + 1).find((message) => message.role === "assistant");
+
+  try {
+    const reply = await completeLlm([
+      { role: "system", content: CHAT_TITLE_SYSTEM_PROMPT },
+      { role: "user", content: buildChatTitlePrompt(messages[firstUserIndex].content, firstReply?.content || "") }
+    ], { samplerConfig: { temperature: 0.3, maxTokens: 32 } });
+    const title = normalizeGeneratedChatTitle(reply);
+    if (!title) {
+      sendAppError(res, new AppError("provider", "Model returned an empty title", 502));
+      return;
+    }
+    db.prepare("UPDATE chats SET title = ? WHERE id = ?").run(title, chatId);
+    res.json({ ok: true, title });
+  } catch (error) {
+    sendAppError(res, error, "Title generation failed");
+  }
+});
+
+// Delete chat: moves it to the trash, where it can be restored until it is purged
 router.delete("/:id", (req, res) => {
-  deleteChatCascade(req.params.id);
+  const deleted = trashChat(req.params.id);
+  res.json({ ok: true, deleted });
+});
+
+router.post("/:id/restore", (req, res) => {
+  if (!restoreChat(req.params.id)) {
+    sendAppError(res, notFoundError("Chat is not in the trash"));
+    return;
+  }
   res.json({ ok: true });
 });
 
+// Permanently removes a trashed chat and everything it owns
+router.delete("/:id/purge", (req, res) => {
+  if (!isChatTrashed(req.params.id)) {
+    sendAppError(res, notFoundError("Chat is not in the trash"));
+    return;
+  }
+  res.json({ ok: true, deleted: deleteChatCascade(req.params.id) });
+});
+
+router.get("/:id/deleted-messages", (req, res) => {
+  res.json(listDeletedMessages(req.params.id));
+});
+
+router.delete("/:id/deleted-messages", (req, res) => {
+  res.json({ ok: true, purged: purgeDeletedMessages(req.params.id) });
+});
+
 // Update chat character list
 router.patch("/:id/characters", (req, res) => {
   const chatId = req.params.id;
@@ -267,6 +492,44 @@ router.patch("/:id/characters", (req, res) => {
   res.json({ ok: true, characterIds: ids, characterId: primaryCharacterId });
 });
 
+// Set (or clear with null) the chat's active character. Other characters of a group chat stay, behind it;
+// a chat with no messages on the branch gets the new character's greeting, as a freshly created chat would.
+router.put("/:id/character", (req, res) => {
+  const chatId = req.params.id;
+  const chat = db.prepare("SELECT character_ids FROM chats WHERE id = ?").get(chatId) as { character_ids: string | null } | undefined;
+  if (!chat) {
+    sendAppError(res, notFoundError("Chat not found"));
+    return;
+  }
+  const characterId = req.body?.characterId ? String(req.body.characterId).trim() : "";
+  let existingIds: string[] = [];
+  try { existingIds = normalizeCharacterIdList(JSON.parse(chat.character_ids || "[]")); } catch { /* empty */ }
+
+  if (!characterId) {
+    db.prepare("UPDATE chats SET character_ids = ?, character_id = ? WHERE id = ?").run("[]", null, chatId);
+    res.json({ ok: true, characterIds: [], characterId: null, timeline: getTimeline(chatId, resolveBranch(chatId, req.body?.branchId)) });
+    return;
+  }
+  const character = db.prepare("SELECT name, greeting, card_json FROM characters WHERE id = ?").get(characterId) as {
+    name: string;
+    greeting: string;
+    card_json: string;
+  } | undefined;
+  if (!character) {
+    sendAppError(res, notFoundError("Character not found"));
+    return;
+  }
+
+  const ids = [characterId, ...existingIds.filter((id) => id !== characterId)];
+  const branchId = resolveBranch(chatId, req.body?.branchId);
+  db.transaction(() => {
+    db.prepare("UPDATE chats SET character_ids = ?, character_id = ? WHERE id = ?").run(JSON.stringify(ids), characterId, chatId);
+    const hasMessages = db.prepare("SELECT 1 FROM messages WHERE chat_id = ? AND branch_id = ? AND deleted = 0 LIMIT 1").get(chatId, branchId);
+    if (!hasMessages) insertCharacterGreeting(chatId, branchId, character, getSettings(), now());
+  })();
+  res.json({ ok: true, characterIds: ids, characterId, timeline: getTimeline(chatId, branchId) });
+});
+
 router.patch("/:id/lorebook", (req, res) => {
   const chatId = req.params.id;
   let lorebookIds = normalizeLorebookIdList(req.body?.lorebookIds);
@@ -307,23 +570,45 @@ router.get("/:id/branches", (req, res) => {
 router.patch("/:id/branches/:branchId", (req, res) => {
   const name = String(req.body?.name || "").replace(/\s+/g, " ").trim();
   if (!name || name.length > 80) {
-    res.status(400).json({ error: "Branch name must contain 1 to 80 characters" });
+    sendAppError(res, validationError("Branch name must contain 1 to 80 characters"));
     return;
   }
   const branch = renameBranch(req.params.id, req.params.branchId, name);
   if (!branch) {
-    res.status(404).json({ error: "Branch not found" });
+    sendAppError(res, notFoundError("Branch not found"));
     return;
   }
   res.json(branch);
 });
 
+router.post("/:id/branches/:branchId/activate", (req, res) => {
+  const branches = setActiveBranch(req.params.id, req.params.branchId);
+  if (!branches) {
+    sendAppError(res, notFoundError("Branch not found"));
+    return;
+  }
+  res.json({ activeBranchId: req.params.branchId, branches });
+});
+
+router.post("/:id/branches/:branchId/merge", (req, res) => {
+  const chatId = req.params.id;
+  const targetBranchId = String(req.body?.targetBranchId || "").trim();
+  const result = mergeBranch(chatId, req.params.branchId, targetBranchId, req.body?.deleteSource === true);
+  if (!result.ok) {
+    sendAppError(res, result.reason === "same_branch"
+      ? validationError("A branch cannot be merged into itself")
+      : notFoundError("Branch not found"));
+    return;
+  }
+  res.json({ ...result, activeBranchId: targetBranchId, timeline: getTimeline(chatId, targetBranchId) });
+});
+
 router.delete("/:id/branches/:branchId", (req, res) => {
   const result = deleteBranch(req.params.id, req.params.branchId);
   if (!result.ok) {
-    res.status(result.reason === "last_branch" ? 409 : 404).json({
-      error: result.reason === "last_branch" ? "The last branch cannot be deleted" : "Branch not found"
-    });
+    sendAppError(res, result.reason === "last_branch"
+      ? conflictError("The last branch cannot be deleted")
+      : notFoundError("Branch not found"));
     return;
   }
   res.json(result);
@@ -332,35 +617,103 @@ router.delete("/:id/branches/:branchId", (req, res) => {
 router.get("/:id/export/json", (req, res) => {
   const payload = exportChatJson(req.params.id, String(req.query.branchId || "").trim() || undefined);
   if (!payload) {
-    res.status(404).json({ error: "Chat not found" });
+    sendAppError(res, notFoundError("Chat not found"));
     return;
   }
-  const filenameBase = String(payload.chat.title || "chat")
-    .replace(/[\\/:*?"<>|]+/g, "-")
-    .replace(/\s+/g, " ")
-    .trim()
-    .slice(0, 80) || "chat";
+  const filenameBase = chatExportFileBase(payload.chat.title);
   res.setHeader("Content-Type", "application/json; charset=utf-8");
   res.setHeader("Content-Disposition", `attachment; filename="${filenameBase}.vellium-chat.json"`);
   res.send(`${JSON.stringify(payload, null, 2)}\n`);
 });
 
+// Writes the branch transcript to the data folder and returns its path, like the writer's Markdown export.
+router.post("/:id/export/markdown", (req, res) => {
+  const chatId = req.params.id;
+  const bundle = exportChatMarkdown(chatId, String(req.body?.branchId || "").trim() || undefined);
+  if (!bundle) {
+    sendAppError(res, notFoundError("Chat not found"));
+    return;
+  }
+  const outputPath = join(DATA_DIR, `${bundle.filenameBase}.vellium-chat.md`);
+  writeFileSync(outputPath, bundle.markdown);
+  db.prepare("INSERT INTO chat_exports (id, chat_id, branch_id, export_type, output_path, created_at) VALUES (?, ?, ?, ?, ?, ?)")
+    .run(newId(), chatId, bundle.branchId, "markdown", outputPath, now());
+  res.json(outputPath);
+});
+
+// Dataset export: `format` is "conversation" (one JSON array) or "turns" (JSONL, one line per assistant reply).
+router.post("/:id/export/jsonl", (req, res) => {
+  const chatId = req.params.id;
+  const format = req.body?.format ?? "conversation";
+  if (format !== "conversation" && format !== "turns") {
+    sendAppError(res, validationError("format must be \"conversation\" or \"turns\""));
+    return;
+  }
+  const bundle = exportChatDataset(chatId, format, String(req.body?.branchId || "").trim() || undefined);
+  if (!bundle) {
+    sendAppError(res, notFoundError("Chat not found"));
+    return;
+  }
+  const outputPath = join(DATA_DIR, `${bundle.filenameBase}.vellium-chat.${bundle.extension}`);
+  writeFileSync(outputPath, bundle.content);
+  db.prepare("INSERT INTO chat_exports (id, chat_id, branch_id, export_type, output_path, created_at) VALUES (?, ?, ?, ?, ?, ?)")
+    .run(newId(), chatId, bundle.branchId, bundle.extension, outputPath, now());
+  res.json(outputPath);
+});
+
+// Without `limit` or `before` the whole branch is returned; with either, one page of older history.
 router.get("/:id/timeline", (req, res) => {
   const branchId = resolveBranch(req.params.id, req.query.branchId as string | undefined);
-  res.json(getTimeline(req.params.id, branchId));
+  if (req.query.limit === undefined && req.query.before === undefined) {
+    res.json(getTimeline(req.params.id, branchId));
+    return;
+  }
+  const limit = req.query.limit === undefined ? 100 : Number(req.query.limit);
+  if (!Number.isInteger(limit) || limit < 1 || limit > MAX_TIMELINE_PAGE_SIZE) {
+    sendAppError(res, validationError(`limit must be a whole number between 1 and ${MAX_TIMELINE_PAGE_SIZE}`));
+    return;
+  }
+  const before = typeof req.query.before === "string" && req.query.before ? req.query.before : undefined;
+  const page = getTimelinePage(req.params.id, branchId, limit, before);
+  if (!page) {
+    sendAppError(res, notFoundError("before message not found in this branch"));
+    return;
+  }
+  res.json(page);
 });
 
 router.post("/:id/send", async (req, res: Response) => {
   const chatId = req.params.id;
-  const { content, branchId: reqBranchId, userName, userPersona, attachments: rawAttachments, runtimeSystemPrompt } = req.body;
+  const { content, branchId: reqBranchId, attachments: rawAttachments, runtimeSystemPrompt } = req.body;
+  let clientTools: ClientToolDefinition[];
+  try {
+    clientTools = normalizeClientTools(req.body?.tools);
+  } catch (err) {
+    sendAppError(res, validationError(err instanceof Error ? err.message : "Invalid tools"));
+    return;
+  }
+  const toolsError = clientToolsUnsupportedError(clientTools);
+  if (toolsError) {
+    sendAppError(res, toolsError);
+    return;
+  }
+  let imageAttachments: MessageAttachmentPayload[];
+  try {
+    imageAttachments = await imagesToAttachments(req.body?.images, UPLOADS_DIR);
+  } catch (err) {
+    sendAppError(res, validationError(err instanceof Error ? err.message : "Invalid images"));
+    return;
+  }
+  const attachmentCount = (Array.isArray(rawAttachments) ? rawAttachments.length : 0) + imageAttachments.length;
+  if (attachmentCount > MAX_MESSAGE_ATTACHMENTS) {
+    sendAppError(res, validationError(
+      `A message can carry at most ${MAX_MESSAGE_ATTACHMENTS} attachments and images; this one has ${attachmentCount}`
+    ));
+    return;
+  }
   const branchId = resolveBranch(chatId, reqBranchId);
-  const persona: UserPersonaPayload = {
-    name: String(userPersona?.name || userName || "User"),
-    description: String(userPersona?.description || ""),
-    personality: String(userPersona?.personality || ""),
-    scenario: String(userPersona?.scenario || "")
-  };
-  const attachments = sanitizeAttachments(rawAttachments);
+  const persona = readUserPersona(chatId, req.body);
+  const attachments = [...sanitizeAttachments(rawAttachments), ...imageAttachments];
 
   // In multi-char mode, store who sent the message (user persona name)
   const chat = db.prepare("SELECT character_ids FROM chats WHERE id = ?").get(chatId) as { character_ids: string | null } | undefined;
@@ -373,9 +726,17 @@ router.post("/:id/send", async (req, res: Response) => {
   const activeProvider = activeProviderId
     ? db.prepare("SELECT * FROM providers WHERE id = ?").get(activeProviderId) as ProviderRow | undefined
     : undefined;
+  try {
+    await moderateUserMessage(settings, String(content || ""));
+  } catch (error) {
+    // Nothing is saved for a refused message, so the chat stays as it was.
+    sendAppError(res, error, "Moderation check failed");
+    return;
+  }
   const userTokenCount = await countProviderTokens(
     activeProvider,
-    buildPromptContentWithAttachments(String(content || ""), attachments)
+    buildPromptContentWithAttachments(String(content || ""), attachments),
+    settings.activeModel
   );
 
   // Insert user message — with character_name set to user persona name in multi-char mode
@@ -421,7 +782,8 @@ router.post("/:id/send", async (req, res: Response) => {
       overrideCharacterName: firstResponder,
       isAutoConvo: false,
       userPersona: persona,
-      runtimeSystemPrompt: typeof runtimeSystemPrompt === "string" ? runtimeSystemPrompt : undefined
+      runtimeSystemPrompt: typeof runtimeSystemPrompt === "string" ? runtimeSystemPrompt : undefined,
+      clientTools
     });
   } else {
     await streamLlmResponse({
@@ -431,22 +793,94 @@ router.post("/:id/send", async (req, res: Response) => {
       parentMsgId: userId,
       isAutoConvo: false,
       userPersona: persona,
-      runtimeSystemPrompt: typeof runtimeSystemPrompt === "string" ? runtimeSystemPrompt : undefined
+      runtimeSystemPrompt: typeof runtimeSystemPrompt === "string" ? runtimeSystemPrompt : undefined,
+      clientTools
     });
   }
 });
 
+// Answers one client tool call; once every call of the assistant turn has a result, the turn continues.
+router.post("/:id/tool-results", async (req, res: Response) => {
+  const chatId = req.params.id;
+  const toolCallId = String(req.body?.toolCallId || "").trim();
+  if (!toolCallId) {
+    sendAppError(res, validationError("toolCallId is required"));
+    return;
+  }
+  let clientTools: ClientToolDefinition[];
+  try {
+    clientTools = normalizeClientTools(req.body?.tools);
+  } catch (err) {
+    sendAppError(res, validationError(err instanceof Error ? err.message : "Invalid tools"));
+    return;
+  }
+  const toolsError = clientToolsUnsupportedError(clientTools);
+  if (toolsError) {
+    sendAppError(res, toolsError);
+    return;
+  }
+  const result = typeof req.body?.result === "string" ? req.body.result : JSON.stringify(req.body?.result ?? null);
+
+  const toolRows = db.prepare(
+    "SELECT * FROM messages WHERE chat_id = ? AND role = 'tool' AND deleted = 0 AND content LIKE ?"
+  ).all(chatId, `%${JSON.stringify(toolCallId).slice(1, -1)}%`) as MessageRow[];
+  const target = toolRows
+    .map((row) => ({ row, call: parseStoredClientToolCall(row.content) }))
+    .find((item) => item.call?.callId === toolCallId);
+  if (!target?.call || !target.row.parent_id) {
+    sendAppError(res, notFoundError("Tool call not found"));
+    return;
+  }
+  if (!target.call.pending) {
+    sendAppError(res, conflictError("Tool call already has a result"));
+    return;
+  }
+
+  const resolvedText = serializeToolTrace({ ...target.call, result, client: true, pending: false });
+  db.prepare("UPDATE messages SET content = ?, token_count = ? WHERE id = ?")
+    .run(resolvedText, countTokens(resolvedText), target.row.id);
+
+  const siblings = (db.prepare(
+    "SELECT * FROM messages WHERE parent_id = ? AND chat_id = ? AND role = 'tool' AND deleted = 0 ORDER BY sort_order ASC, created_at ASC"
+  ).all(target.row.parent_id, chatId) as MessageRow[])
+    .map((row) => parseStoredClientToolCall(row.content))
+    .filter((call): call is NonNullable<typeof call> => call !== null);
+  const branchId = target.row.branch_id;
+  if (siblings.some((call) => call.pending)) {
+    res.json(getTimeline(chatId, branchId));
+    return;
+  }
+
+  const assistant = db.prepare("SELECT * FROM messages WHERE id = ? AND chat_id = ?")
+    .get(target.row.parent_id, chatId) as MessageRow | undefined;
+  if (!assistant) {
+    sendAppError(res, notFoundError("Tool call not found"));
+    return;
+  }
+  await streamLlmResponse({
+    chatId,
+    branchId,
+    res,
+    parentMsgId: assistant.id,
+    overrideCharacterName: assistant.character_name || undefined,
+    isAutoConvo: false,
+    userPersona: readUserPersona(chatId, req.body),
+    clientTools,
+    clientToolExchange: buildClientToolExchange(assistant.content, siblings)
+  });
+});
+
 router.post("/:id/fork", (req, res) => {
   const chatId = req.params.id;
   const { parentMessageId, name } = req.body;
   if (!parentMessageId) {
-    res.status(400).json({ error: "parentMessageId is required" });
+    sendAppError(res, validationError("parentMessageId is required"));
     return;
   }
 
   const branch = forkBranch(chatId, String(parentMessageId), name);
   if (!branch) {
-    res.status(404).json({ error: "Parent message not found" });
+    sendAppError(res, notFoundError("Parent message not found"));
     return;
   }
   res.json(branch);
@@ -454,7 +888,7 @@ router.post("/:id/fork", (req, res) => {
 
 router.post("/:id/regenerate", async (req, res: Response) => {
   const chatId = req.params.id;
-  const { branchId: reqBranchId } = req.body ?? {};
+  const { branchId: reqBranchId, runtimeSystemPrompt } = req.body ?? {};
   const branchId = resolveBranch(chatId, reqBranchId);
 
   // Regenerate must operate on the timeline tail only:
@@ -478,29 +912,110 @@ router.post("/:id/regenerate", async (req, res: Response) => {
       parentMsgId = previousUser?.id ?? null;
     }
   } else if (tail?.role === "user") {
+    // Resending the tail user message: it may have been edited or stored before Moderated mode was on.
+    try {
+      await moderateUserMessage(getSettings(), tail.content);
+    } catch (error) {
+      sendAppError(res, error, "Moderation check failed");
+      return;
+    }
     parentMsgId = tail.id;
   }
 
+  // Rebuild the prompt with the same persona and runtime additions as the original send.
+  await streamLlmResponse({
+    chatId,
+    branchId,
+    res,
+    parentMsgId,
+    overrideCharacterName,
+    userPersona: readUserPersona(chatId, req.body),
+    runtimeSystemPrompt: typeof runtimeSystemPrompt === "string" ? runtimeSystemPrompt : undefined
+  });
+});
+
+// Swipe: generate another reply to the tail assistant's turn and keep the current reply as an alternative
+router.post("/:id/swipe", async (req, res: Response) => {
+  const chatId = req.params.id;
+  const { branchId: reqBranchId, runtimeSystemPrompt } = req.body ?? {};
+  const branchId = resolveBranch(chatId, reqBranchId);
+  const tail = db.prepare(
+    "SELECT * FROM messages WHERE chat_id = ? AND branch_id = ? AND role IN ('user', 'assistant') AND deleted = 0 ORDER BY sort_order DESC, created_at DESC, id DESC LIMIT 1"
+  ).get(chatId, branchId) as MessageRow | undefined;
+  if (tail?.role !== "assistant") {
+    sendAppError(res, conflictError("The branch must end with an assistant reply to swipe"));
+    return;
+  }
+  // Swipes are grouped by parent, so older replies saved without one are linked to the user turn they answer.
+  let parentMsgId = tail.parent_id;
+  if (!parentMsgId) {
+    const previousUser = db.prepare(
+      "SELECT id FROM messages WHERE chat_id = ? AND branch_id = ? AND role = 'user' AND deleted = 0 AND sort_order < ? ORDER BY sort_order DESC, created_at DESC, id DESC LIMIT 1"
+    ).get(chatId, branchId, tail.sort_order) as { id: string } | undefined;
+    if (!previousUser) {
+      sendAppError(res, conflictError("The tail reply does not answer a user message"));
+      return;
+    }
+    parentMsgId = previousUser.id;
+    db.prepare("UPDATE messages SET parent_id = ? WHERE id = ?").run(parentMsgId, tail.id);
+  }
+
+  const setSwipeVisibility = (deleted: number, from: number) => db.prepare(
+    "UPDATE messages SET deleted = ? WHERE chat_id = ? AND branch_id = ? AND (id = ? OR (parent_id = ? AND role = 'tool')) AND deleted = ?"
+  ).run(deleted, chatId, branchId, tail.id, tail.id, from);
+  setSwipeVisibility(SWIPE_HIDDEN, 0);
+
   await streamLlmResponse({
     chatId,
     branchId,
     res,
     parentMsgId,
-    overrideCharacterName
+    overrideCharacterName: tail.character_name || undefined,
+    userPersona: readUserPersona(chatId, req.body),
+    runtimeSystemPrompt: typeof runtimeSystemPrompt === "string" ? runtimeSystemPrompt : undefined
+  });
+
+  // A failed generation saves no reply; the previous swipe comes back instead of leaving the turn empty.
+  const replaced = db.prepare(
+    "SELECT 1 FROM messages WHERE chat_id = ? AND branch_id = ? AND parent_id = ? AND role = 'assistant' AND deleted = 0 LIMIT 1"
+  ).get(chatId, branchId, parentMsgId);
+  if (!replaced) setSwipeVisibility(0, SWIPE_HIDDEN);
+});
+
+// Continues the tail assistant reply in place (for replies cut off by max tokens); always streams.
+router.post("/:id/continue", async (req, res: Response) => {
+  const chatId = req.params.id;
+  const { branchId: reqBranchId, runtimeSystemPrompt } = req.body ?? {};
+  if (!db.prepare("SELECT 1 FROM chats WHERE id = ?").get(chatId)) {
+    sendAppError(res, notFoundError("Chat not found"));
+    return;
+  }
+  const branchId = resolveBranch(chatId, reqBranchId);
+  const tail = db.prepare(
+    "SELECT * FROM messages WHERE chat_id = ? AND branch_id = ? AND role IN ('user', 'assistant') AND deleted = 0 ORDER BY sort_order DESC, created_at DESC, id DESC LIMIT 1"
+  ).get(chatId, branchId) as MessageRow | undefined;
+  if (tail?.role !== "assistant") {
+    sendAppError(res, conflictError("The branch must end with an assistant reply to continue"));
+    return;
+  }
+
+  await streamContinuation({
+    chatId,
+    branchId,
+    res,
+    messageId: tail.id,
+    overrideCharacterName: tail.character_name || undefined,
+    userPersona: readUserPersona(chatId, req.body),
+    runtimeSystemPrompt: typeof runtimeSystemPrompt === "string" ? runtimeSystemPrompt : undefined
   });
 });
 
 // Multi-character: generate next turn for a specific character
 router.post("/:id/next-turn", async (req, res: Response) => {
   const chatId = req.params.id;
-  const { characterName, branchId: reqBranchId, isAutoConvo, userName, userPersona, runtimeSystemPrompt } = req.body;
+  const { characterName, branchId: reqBranchId, isAutoConvo, runtimeSystemPrompt } = req.body;
   const branchId = resolveBranch(chatId, reqBranchId);
-  const persona: UserPersonaPayload = {
-    name: String(userPersona?.name || userName || "User"),
-    description: String(userPersona?.description || ""),
-    personality: String(userPersona?.personality || ""),
-    scenario: String(userPersona?.scenario || "")
-  };
+  const persona = readUserPersona(chatId, req.body);
 
   await streamLlmResponse({
     chatId,
@@ -514,6 +1029,25 @@ router.post("/:id/next-turn", async (req, res: Response) => {
   });
 });
 
+// Dry run: the exact messages the next turn would send, without contacting the provider.
+router.post("/:id/preview-prompt", async (req, res: Response) => {
+  const chatId = req.params.id;
+  const { characterName, branchId: reqBranchId, isAutoConvo, runtimeSystemPrompt } = req.body ?? {};
+  if (!db.prepare("SELECT 1 FROM chats WHERE id = ?").get(chatId)) {
+    sendAppError(res, notFoundError("Chat not found"));
+    return;
+  }
+  const preview = await previewChatPrompt({
+    chatId,
+    branchId: resolveBranch(chatId, reqBranchId),
+    overrideCharacterName: typeof characterName === "string" && characterName ? characterName : undefined,
+    isAutoConvo: isAutoConvo === true,
+    userPersona: readUserPersona(chatId, req.body),
+    runtimeSystemPrompt: typeof runtimeSystemPrompt === "string" ? runtimeSystemPrompt : undefined
+  });
+  res.json(preview);
+});
+
 router.post("/:id/compress", compressChat);
 
 // --- Translate message ---
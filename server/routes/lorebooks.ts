@@ -1,9 +1,9 @@
 import { Router } from "express";
-import { db, newId, now, DEFAULT_SETTINGS, isLocalhostUrl } from "../db.js";
+import { db, newId, now, DEFAULT_SETTINGS } from "../db.js";
 import { normalizeLoreBookEntries, parseSillyTavernWorldInfo, serializeSillyTavernWorldInfo } from "../domain/lorebooks.js";
-import { buildKoboldGenerateBody, extractKoboldGeneratedText, normalizeProviderType, requestKoboldGenerate } from "../services/providerApi.js";
-import { buildKoboldSamplerConfig, buildOpenAiSamplingPayload, normalizeApiParamPolicy } from "../services/apiParamPolicy.js";
-import { completeCustomAdapter } from "../services/customProviderAdapters.js";
+import { completeLlm, LlmUnavailableError, resolveLlm } from "../modules/llm/completion.js";
+import { normalizeApiParamPolicy } from "../services/apiParamPolicy.js";
+import { notConfiguredError, sendAppError } from "../services/appError.js";
 
 const router = Router();
 
@@ -17,23 +17,6 @@ interface LoreBookRow {
   updated_at: string;
 }
 
-interface ProviderRow {
-  id: string;
-  base_url: string;
-  api_key_cipher: string;
-  full_local_only: number;
-  provider_type: string;
-  adapter_id: string | null;
-}
-
-const KOBOLD_TAGS = {
-  systemOpen: "{{[SYSTEM]}}",
-  systemClose: "{{[SYSTEM_END]}}",
-  inputOpen: "{{[INPUT]}}",
-  inputClose: "{{[INPUT_END]}}",
-  outputOpen: "{{[OUTPUT]}}"
-} as const;
-
 function getSettings() {
   try {
     const row = db.prepare("SELECT payload FROM settings WHERE id = 1").get() as { payload: string } | undefined;
@@ -52,13 +35,6 @@ function getSettings() {
   }
 }
 
-function normalizeOpenAiBaseUrl(raw: string): string {
-  const trimmed = String(raw || "").trim().replace(/\/+$/, "");
-  if (!trimmed) return "";
-  if (/\/v1$/i.test(trimmed)) return trimmed;
-  return `${trimmed}/v1`;
-}
-
 function sanitizeHeaderFilenameAscii(name: string, fallback: string): string {
   const clean = String(name || "")
     .replace(/[\r\n]/g, " ")
@@ -88,108 +64,32 @@ function buildFilenameBase(raw: string, fallback: string): string {
   return clean || fallback;
 }
 
-async function completeProviderOnce(params: {
-  provider: ProviderRow;
-  modelId: string;
-  systemPrompt: string;
-  userPrompt: string;
-  apiParamPolicy?: unknown;
-}): Promise<string> {
-  const providerType = normalizeProviderType(params.provider.provider_type);
-
-  if (providerType === "koboldcpp") {
-    const koboldSamplerConfig = buildKoboldSamplerConfig({
-      samplerConfig: {
-        temperature: 0.2,
-        maxTokens: 512
-      },
-      apiParamPolicy: params.apiParamPolicy
-    });
-    const memory = params.systemPrompt.trim()
-      ? `${KOBOLD_TAGS.systemOpen}\n${params.systemPrompt.trim()}\n${KOBOLD_TAGS.systemClose}`
-      : "";
-    const body = buildKoboldGenerateBody({
-      prompt: `${KOBOLD_TAGS.inputOpen}\n${params.userPrompt}\n${KOBOLD_TAGS.inputClose}\n\n${KOBOLD_TAGS.outputOpen}`,
-      memory,
-      samplerConfig: koboldSamplerConfig,
-      includeMemory: true
-    });
-    const response = await requestKoboldGenerate(params.provider, body);
-    if (!response.ok) return "";
-    const parsed = await response.json().catch(() => ({}));
-    return extractKoboldGeneratedText(parsed).trim();
-  }
-
-  if (providerType === "custom") {
-    const samplerConfig = {
-      temperature: 0.2,
-      maxTokens: 512
-    };
-    return completeCustomAdapter({
-      provider: params.provider,
-      modelId: params.modelId,
-      systemPrompt: params.systemPrompt,
-      userPrompt: params.userPrompt,
-      samplerConfig
-    });
-  }
-
-  const baseUrl = normalizeOpenAiBaseUrl(params.provider.base_url);
-  if (!baseUrl) return "";
-  const openAiSampling = buildOpenAiSamplingPayload({
-    samplerConfig: {
-      temperature: 0.2,
-      maxTokens: 512
-    },
-    apiParamPolicy: params.apiParamPolicy,
-    fields: ["temperature", "maxTokens"],
-    defaults: {
-      temperature: 0.2,
-      maxTokens: 512
-    }
-  });
-  const response = await fetch(`${baseUrl}/chat/completions`, {
-    method: "POST",
-    headers: {
-      "Content-Type": "application/json",
-      Authorization: `Bearer ${params.provider.api_key_cipher}`
-    },
-    body: JSON.stringify({
-      model: params.modelId,
-      messages: [
-        { role: "system", content: params.systemPrompt },
-        { role: "user", content: params.userPrompt }
-      ],
-      ...openAiSampling
-    })
-  });
-  if (!response.ok) return "";
-  const body = await response.json() as { choices?: Array<{ message?: { content?: string } }> };
-  return body.choices?.[0]?.message?.content?.trim() ?? "";
-}
-
 async function translateLoreKey(params: {
   value: string;
   targetLanguage: string;
-  provider: ProviderRow;
+  providerId: string;
   modelId: string;
-  apiParamPolicy?: unknown;
 }): Promise<string> {
   const value = String(params.value || "").trim();
   if (!value) return "";
   if (!/\p{L}/u.test(value)) return value;
-  const translated = await completeProviderOnce({
-    provider: params.provider,
+  // A key that fails to translate keeps its original text rather than failing the whole lorebook.
+  const translated = await completeLlm([
+    {
+      role: "system",
+      content: [
+        `Translate this lorebook trigger phrase to ${params.targetLanguage}.`,
+        "Output ONLY the translated trigger phrase.",
+        "Do not explain anything.",
+        "Preserve punctuation if present."
+      ].join(" ")
+    },
+    { role: "user", content: value }
+  ], {
+    providerId: params.providerId,
     modelId: params.modelId,
-    apiParamPolicy: params.apiParamPolicy,
-    systemPrompt: [
-      `Translate this lorebook trigger phrase to ${params.targetLanguage}.`,
-      "Output ONLY the translated trigger phrase.",
-      "Do not explain anything.",
-      "Preserve punctuation if present."
-    ].join(" "),
-    userPrompt: value
-  });
+    samplerConfig: { temperature: 0.2, maxTokens: 512 }
+  }).catch(() => "");
   return String(translated || "").trim() || value;
 }
 
@@ -297,21 +197,17 @@ router.post("/:id/translate-copy", async (req, res) => {
   }
 
   if (!providerId || !modelId) {
-    res.status(400).json({ error: "Translate provider/model is not configured in Settings." });
+    sendAppError(res, notConfiguredError("Translate provider/model is not configured in Settings."));
     return;
   }
 
-  const provider = db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as ProviderRow | undefined;
-  if (!provider) {
-    res.status(400).json({ error: "Translate provider not found." });
-    return;
-  }
-  if (settings.fullLocalMode === true && !isLocalhostUrl(provider.base_url)) {
-    res.status(400).json({ error: "Provider blocked by Full Local Mode." });
-    return;
-  }
-  if (provider.full_local_only && !isLocalhostUrl(provider.base_url)) {
-    res.status(400).json({ error: "Provider requires localhost endpoint." });
+  try {
+    resolveLlm({ providerId, modelId });
+  } catch (error) {
+    const unavailable = error instanceof LlmUnavailableError ? error : null;
+    sendAppError(res, notConfiguredError(
+      unavailable?.reason === "provider_not_found" ? "Translate provider not found." : `${unavailable?.message ?? "Translate provider unavailable"}.`
+    ));
     return;
   }
 
@@ -324,16 +220,14 @@ router.post("/:id/translate-copy", async (req, res) => {
       const translatedKeys = await Promise.all(entry.keys.map((key) => translateLoreKey({
         value: key,
         targetLanguage,
-        provider,
-        modelId,
-        apiParamPolicy: settings.apiParamPolicy
+        providerId,
+        modelId
       })));
       const translatedSecondaryKeys = await Promise.all((entry.secondaryKeys || []).map((key) => translateLoreKey({
         value: key,
         targetLanguage,
-        provider,
-        modelId,
-        apiParamPolicy: settings.apiParamPolicy
+        providerId,
+        modelId
       })));
       translatedEntries.push({
         ...entry,
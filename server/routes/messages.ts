@@ -1,67 +1,11 @@
-import { Router } from "express";
-import { db, roughTokenCount } from "../db.js";
+import { Router, type Request, type Response } from "express";
+import { db, countTokens } from "../db.js";
+import { getSettings, getTimeline, SWIPE_HIDDEN, type MessageRow } from "../modules/chat/routeHelpers.js";
+import { sendAppError } from "../services/appError.js";
+import { moderateUserMessage } from "../services/moderation.js";
 
 const router = Router();
 
-interface MessageRow {
-  id: string;
-  chat_id: string;
-  branch_id: string;
-  role: string;
-  content: string;
-  attachments: string | null;
-  rag_sources: string | null;
-  token_count: number;
-  parent_id: string | null;
-  deleted: number;
-  created_at: string;
-  generation_started_at: string | null;
-  generation_completed_at: string | null;
-  generation_duration_ms: number | null;
-  character_name: string | null;
-  sort_order: number;
-}
-
-function messageToJson(row: MessageRow) {
-  let attachments: unknown[] = [];
-  let ragSources: unknown[] = [];
-  try {
-    const parsed = JSON.parse(row.attachments || "[]");
-    if (Array.isArray(parsed)) attachments = parsed;
-  } catch {
-    attachments = [];
-  }
-  try {
-    const parsed = JSON.parse(row.rag_sources || "[]");
-    if (Array.isArray(parsed)) ragSources = parsed;
-  } catch {
-    ragSources = [];
-  }
-  return {
-    id: row.id,
-    chatId: row.chat_id,
-    branchId: row.branch_id,
-    role: row.role,
-    content: row.content,
-    attachments,
-    tokenCount: row.token_count,
-    createdAt: row.created_at,
-    generationStartedAt: row.generation_started_at || undefined,
-    generationCompletedAt: row.generation_completed_at || undefined,
-    generationDurationMs: typeof row.generation_duration_ms === "number" ? row.generation_duration_ms : undefined,
-    parentId: row.parent_id,
-    characterName: row.character_name || undefined,
-    ragSources
-  };
-}
-
-function getTimeline(chatId: string, branchId: string) {
-  const rows = db.prepare(
-    "SELECT * FROM messages WHERE chat_id = ? AND branch_id = ? AND deleted = 0 ORDER BY sort_order ASC, created_at ASC, id ASC"
-  ).all(chatId, branchId) as MessageRow[];
-  return rows.map(messageToJson);
-}
-
 const normalizeSortOrder = db.transaction((chatId: string, branchId: string) => {
   const rows = db.prepare(
     "SELECT id FROM messages WHERE chat_id = ? AND branch_id = ? AND deleted = 0 ORDER BY sort_order ASC, created_at ASC, id ASC"
@@ -73,7 +17,7 @@ const normalizeSortOrder = db.transaction((chatId: string, branchId: string) =>
   });
 });
 
-router.patch("/:id", (req, res) => {
+router.patch("/:id", async (req, res) => {
   const content = String(req.body?.content ?? "");
   const row = db.prepare("SELECT * FROM messages WHERE id = ? AND deleted = 0")
     .get(req.params.id) as MessageRow | undefined;
@@ -83,13 +27,40 @@ router.patch("/:id", (req, res) => {
     return;
   }
 
+  const settings = getSettings();
+  if (row.role === "user") {
+    try {
+      await moderateUserMessage(settings, content);
+    } catch (error) {
+      sendAppError(res, error, "Moderation check failed");
+      return;
+    }
+  }
+
   db.prepare(
     "UPDATE messages SET content = ?, token_count = ? WHERE id = ? AND chat_id = ? AND branch_id = ? AND deleted = 0"
-  ).run(content, roughTokenCount(content), row.id, row.chat_id, row.branch_id);
+  ).run(content, countTokens(content, settings.activeModel), row.id, row.chat_id, row.branch_id);
 
   res.json({ ok: true, timeline: getTimeline(row.chat_id, row.branch_id) });
 });
 
+// Pinned messages stay in the prompt when older history is trimmed to fit the context budget.
+function setMessagePinned(req: Request, res: Response, pinned: boolean) {
+  const row = db.prepare("SELECT * FROM messages WHERE id = ? AND deleted = 0")
+    .get(req.params.id) as MessageRow | undefined;
+
+  if (!row) {
+    res.status(404).json({ error: "Message not found" });
+    return;
+  }
+
+  db.prepare("UPDATE messages SET pinned = ? WHERE id = ?").run(pinned ? 1 : 0, row.id);
+  res.json({ ok: true, timeline: getTimeline(row.chat_id, row.branch_id) });
+}
+
+router.post("/:id/pin", (req, res) => setMessagePinned(req, res, true));
+router.post("/:id/unpin", (req, res) => setMessagePinned(req, res, false));
+
 router.delete("/:id", (req, res) => {
   const row = db.prepare("SELECT * FROM messages WHERE id = ? AND deleted = 0")
     .get(req.params.id) as MessageRow | undefined;
@@ -115,4 +86,64 @@ router.delete("/:id", (req, res) => {
   res.json({ ok: true, timeline: getTimeline(row.chat_id, row.branch_id) });
 });
 
+// Brings a soft-deleted message back, with the tool records that were removed alongside it.
+router.post("/:id/restore", (req, res) => {
+  const row = db.prepare(
+    "SELECT m.* FROM messages m JOIN branches b ON b.id = m.branch_id AND b.chat_id = m.chat_id WHERE m.id = ? AND m.deleted = 1"
+  ).get(req.params.id) as MessageRow | undefined;
+
+  if (!row) {
+    res.status(404).json({ error: "Deleted message not found" });
+    return;
+  }
+
+  const restoreMessage = db.transaction(() => {
+    const tools = db.prepare(
+      "SELECT id FROM messages WHERE parent_id = ? AND chat_id = ? AND branch_id = ? AND role = 'tool' AND deleted = 1 ORDER BY sort_order ASC, created_at ASC"
+    ).all(row.id, row.chat_id, row.branch_id) as { id: string }[];
+    const live = db.prepare(
+      "SELECT id, created_at FROM messages WHERE chat_id = ? AND branch_id = ? AND deleted = 0 ORDER BY sort_order ASC, created_at ASC, id ASC"
+    ).all(row.chat_id, row.branch_id) as { id: string; created_at: string }[];
+    // Delete renumbered the branch, so the old sort_order is stale: slot the message back before the first later one.
+    const ordered = live.map((item) => item.id);
+    const insertAt = live.findIndex((item) => item.created_at > row.created_at);
+    ordered.splice(insertAt < 0 ? ordered.length : insertAt, 0, row.id, ...tools.map((tool) => tool.id));
+    const update = db.prepare("UPDATE messages SET deleted = 0, sort_order = ? WHERE id = ?");
+    ordered.forEach((id, index) => update.run(index + 1, id));
+  });
+  restoreMessage();
+
+  res.json({ ok: true, timeline: getTimeline(row.chat_id, row.branch_id) });
+});
+
+// Makes one swipe the reply shown in the timeline; its siblings (and their tool records) are kept hidden.
+router.post("/:id/select-swipe", (req, res) => {
+  const row = db.prepare(
+    `SELECT * FROM messages WHERE id = ? AND role = 'assistant' AND parent_id IS NOT NULL AND deleted IN (0, ${SWIPE_HIDDEN})`
+  ).get(req.params.id) as MessageRow | undefined;
+
+  if (!row?.parent_id) {
+    res.status(404).json({ error: "Swipe not found" });
+    return;
+  }
+
+  const selectSwipe = db.transaction(() => {
+    const shown = db.prepare(
+      "SELECT id, sort_order FROM messages WHERE chat_id = ? AND branch_id = ? AND parent_id = ? AND role = 'assistant' AND deleted = 0"
+    ).all(row.chat_id, row.branch_id, row.parent_id) as { id: string; sort_order: number }[];
+    const hide = db.prepare(
+      "UPDATE messages SET deleted = ? WHERE chat_id = ? AND branch_id = ? AND (id = ? OR (parent_id = ? AND role = 'tool')) AND deleted = 0"
+    );
+    for (const sibling of shown) hide.run(SWIPE_HIDDEN, row.chat_id, row.branch_id, sibling.id, sibling.id);
+    // The selected swipe takes the timeline slot of the reply it replaces.
+    const sortOrder = shown[0]?.sort_order ?? row.sort_order;
+    db.prepare(
+      "UPDATE messages SET deleted = 0, sort_order = ? WHERE chat_id = ? AND branch_id = ? AND (id = ? OR (parent_id = ? AND role = 'tool')) AND deleted = ?"
+    ).run(sortOrder, row.chat_id, row.branch_id, row.id, row.id, SWIPE_HIDDEN);
+  });
+  selectSwipe();
+
+  res.json({ ok: true, timeline: getTimeline(row.chat_id, row.branch_id) });
+});
+
 export default router;
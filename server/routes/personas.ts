@@ -71,6 +71,7 @@ router.put("/:id", (req, res) => {
 
 // Delete persona
 router.delete("/:id", (req, res) => {
+  db.prepare("UPDATE chats SET persona_id = NULL WHERE persona_id = ?").run(req.params.id);
   db.prepare("DELETE FROM user_personas WHERE id = ?").run(req.params.id);
   res.json({ ok: true });
 });
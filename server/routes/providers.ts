@@ -1,12 +1,36 @@
 import { Router } from "express";
-import { db, maskApiKey, isLocalhostUrl, DEFAULT_SETTINGS } from "../db.js";
-import { fetchCustomAdapterModels } from "../services/customProviderAdapters.js";
-import { fetchKoboldModels, normalizeProviderType } from "../services/providerApi.js";
+import { db, maskApiKey, isLocalhostUrl, now, DEFAULT_SETTINGS } from "../db.js";
+import { fetchCustomAdapterModels, testCustomAdapterConnection } from "../services/customProviderAdapters.js";
+import { buildAnthropicHeaders, fetchAnthropicModels, normalizeAnthropicBaseUrl } from "../services/anthropicApi.js";
+import { buildGeminiUrl, fetchGeminiModels, normalizeGeminiBaseUrl } from "../services/geminiApi.js";
+import { fetchOllamaModels, normalizeOllamaBaseUrl } from "../services/ollamaApi.js";
+import {
+  applyModelPrefs,
+  fetchKoboldModels,
+  ModelFetchError,
+  type ModelPrefs,
+  normalizeOpenAiBaseUrl,
+  normalizeProviderAuthMode,
+  normalizeProviderAuthParam,
+  normalizeProviderHeaders,
+  normalizeProviderType,
+  parseModelIds,
+  parseModelMetadata,
+  providerAuthHeaders,
+  type ProviderModelMetadata,
+  searchModelList,
+  testKoboldConnection,
+  withProviderAuthUrl,
+  withProviderHeaders
+} from "../services/providerApi.js";
 import { normalizeApiParamPolicy } from "../services/apiParamPolicy.js";
+import { AppError, appErrorBody, notConfiguredError, notFoundError, sendAppError, validationError } from "../services/appError.js";
+import { normalizeProviderProfileInput, parseRateLimitRpm, type ProviderProfileInput } from "../services/providerProfiles.js";
+import { normalizeRuntimeTuningSettings } from "../services/runtimeTuning.js";
 
 const router = Router();
-const MODEL_FETCH_TIMEOUT_MS = 15_000;
 const MODEL_FETCH_RETRY_DELAYS_MS = [0, 250, 750];
+const MODEL_CACHE_TTL_MS = 60 * 60 * 1000;
 
 interface ProviderRow {
   id: string;
@@ -18,6 +42,30 @@ interface ProviderRow {
   provider_type: string;
   adapter_id: string | null;
   manual_models: string | null;
+  extra_headers: string | null;
+  rate_limit_rpm: number | null;
+  auth_mode: string | null;
+  auth_param: string | null;
+}
+
+type ProviderModelSource = Pick<
+  ProviderRow,
+  "base_url" | "api_key_cipher" | "full_local_only" | "provider_type" | "adapter_id" | "manual_models" | "extra_headers"
+  | "auth_mode" | "auth_param"
+>;
+
+type ProviderAuthSource = Pick<ProviderRow, "api_key_cipher" | "extra_headers" | "auth_mode" | "auth_param">;
+
+type ProviderBlockReason = "fullLocalMode" | "localOnly";
+
+interface ProviderConnectionResult {
+  ok: boolean;
+  reachable: boolean;
+  authorized: boolean;
+  status: number | null;
+  latencyMs: number | null;
+  blockedBy?: ProviderBlockReason;
+  error?: string;
 }
 
 interface ProviderPreviewInput {
@@ -27,6 +75,9 @@ interface ProviderPreviewInput {
   providerType?: unknown;
   adapterId?: unknown;
   manualModels?: unknown;
+  headers?: unknown;
+  authMode?: unknown;
+  authParam?: unknown;
 }
 
 function parseManualModels(raw: string | null | undefined): string[] {
@@ -39,6 +90,24 @@ function parseManualModels(raw: string | null | undefined): string[] {
   }
 }
 
+/** Custom headers often carry auth, so responses mask their values the same way as the API key. */
+function maskProviderHeaders(raw: unknown): Record<string, string> {
+  return Object.fromEntries(
+    Object.entries(normalizeProviderHeaders(raw)).map(([name, value]) => [name, maskApiKey(value)])
+  );
+}
+
+/** A header sent back with its masked value keeps the stored value. */
+function restoreMaskedProviderHeaders(incoming: unknown, stored: unknown): Record<string, string> {
+  const storedHeaders = normalizeProviderHeaders(stored);
+  return Object.fromEntries(
+    Object.entries(normalizeProviderHeaders(incoming)).map(([name, value]) => {
+      const storedValue = storedHeaders[name];
+      return [name, storedValue !== undefined && value === maskApiKey(storedValue) ? storedValue : value];
+    })
+  );
+}
+
 function rowToProfile(row: ProviderRow) {
   return {
     id: row.id,
@@ -49,10 +118,62 @@ function rowToProfile(row: ProviderRow) {
     fullLocalOnly: Boolean(row.full_local_only),
     providerType: normalizeProviderType(row.provider_type),
     adapterId: row.adapter_id,
-    manualModels: parseManualModels(row.manual_models)
+    manualModels: parseManualModels(row.manual_models),
+    headers: maskProviderHeaders(row.extra_headers),
+    rateLimitRpm: row.rate_limit_rpm ?? null,
+    authMode: normalizeProviderAuthMode(row.auth_mode),
+    authParam: row.auth_param
   };
 }
 
+type ProviderModelEntry = { id: string } & ProviderModelMetadata;
+
+function readCachedModels(providerId: string): { models: ProviderModelEntry[]; fetchedAt: string } | null {
+  const row = db.prepare("SELECT models_json, fetched_at FROM provider_models WHERE provider_id = ?")
+    .get(providerId) as { models_json: string; fetched_at: string } | undefined;
+  if (!row) return null;
+  try {
+    const parsed = JSON.parse(row.models_json);
+    return Array.isArray(parsed) ? { models: parsed, fetchedAt: row.fetched_at } : null;
+  } catch {
+    return null;
+  }
+}
+
+function writeCachedModels(providerId: string, models: ProviderModelEntry[]) {
+  db.prepare(`
+    INSERT INTO provider_models (provider_id, models_json, fetched_at)
+    VALUES (?, ?, ?)
+    ON CONFLICT(provider_id) DO UPDATE SET models_json = excluded.models_json, fetched_at = excluded.fetched_at
+  `).run(providerId, JSON.stringify(models), now());
+}
+
+function readModelPrefs(providerId: string): Map<string, ModelPrefs> {
+  const rows = db.prepare("SELECT model_id, favorite, hidden FROM model_prefs WHERE provider_id = ?")
+    .all(providerId) as Array<{ model_id: string; favorite: number; hidden: number }>;
+  return new Map(rows.map((row) => [row.model_id, { favorite: Boolean(row.favorite), hidden: Boolean(row.hidden) }]));
+}
+
+/** Updates one flag and drops the row once neither flag is set. */
+function writeModelPref(providerId: string, modelId: string, flag: keyof ModelPrefs, value: boolean): ModelPrefs {
+  const next = { favorite: false, hidden: false, ...readModelPrefs(providerId).get(modelId), [flag]: value };
+  if (!next.favorite && !next.hidden) {
+    db.prepare("DELETE FROM model_prefs WHERE provider_id = ? AND model_id = ?").run(providerId, modelId);
+  } else {
+    db.prepare(`
+      INSERT INTO model_prefs (provider_id, model_id, favorite, hidden, updated_at)
+      VALUES (?, ?, ?, ?, ?)
+      ON CONFLICT(provider_id, model_id) DO UPDATE SET
+        favorite = excluded.favorite, hidden = excluded.hidden, updated_at = excluded.updated_at
+    `).run(providerId, modelId, next.favorite ? 1 : 0, next.hidden ? 1 : 0, now());
+  }
+  return next;
+}
+
+function clearCachedModels(providerId: string) {
+  db.prepare("DELETE FROM provider_models WHERE provider_id = ?").run(providerId);
+}
+
 function getSettings() {
   const row = db.prepare("SELECT payload FROM settings WHERE id = 1").get() as { payload: string };
   const stored = JSON.parse(row.payload);
@@ -61,17 +182,11 @@ function getSettings() {
     ...stored,
     samplerConfig: { ...DEFAULT_SETTINGS.samplerConfig, ...(stored.samplerConfig ?? {}) },
     apiParamPolicy: normalizeApiParamPolicy(stored.apiParamPolicy),
-    promptTemplates: { ...DEFAULT_SETTINGS.promptTemplates, ...(stored.promptTemplates ?? {}) }
+    promptTemplates: { ...DEFAULT_SETTINGS.promptTemplates, ...(stored.promptTemplates ?? {}) },
+    ...normalizeRuntimeTuningSettings(stored)
   };
 }
 
-function normalizeOpenAiBaseUrl(raw: string): string {
-  const trimmed = String(raw || "").trim().replace(/\/+$/, "");
-  if (!trimmed) return "";
-  if (/\/v1$/i.test(trimmed)) return trimmed;
-  return `${trimmed}/v1`;
-}
-
 function sleep(ms: number) {
   return new Promise((resolve) => setTimeout(resolve, ms));
 }
@@ -93,34 +208,41 @@ function describeFetchFailure(error: unknown): string {
   return error.message || String(error);
 }
 
-async function fetchModelsResponse(url: string, apiKey: string) {
+async function fetchModelsBody(
+  url: string,
+  provider: ProviderAuthSource,
+  timeoutMs: number
+): Promise<{ status: number; ok: boolean; text: string }> {
   let lastError: unknown = null;
 
   for (let attempt = 0; attempt < MODEL_FETCH_RETRY_DELAYS_MS.length; attempt += 1) {
     const delay = MODEL_FETCH_RETRY_DELAYS_MS[attempt] ?? 0;
     if (delay > 0) await sleep(delay);
 
+    // One deadline covers connecting and reading the body, so huge or stalled lists cannot hang the request.
     const controller = new AbortController();
-    const timeout = setTimeout(() => {
-      controller.abort(new Error(`Model endpoint timed out after ${MODEL_FETCH_TIMEOUT_MS}ms`));
-    }, MODEL_FETCH_TIMEOUT_MS);
+    const timeout = setTimeout(() => controller.abort(), timeoutMs);
 
     try {
-      const response = await fetch(url, {
-        headers: {
+      const response = await fetch(withProviderAuthUrl(provider, url), {
+        headers: withProviderHeaders(provider, {
           Accept: "application/json",
           Connection: "close",
-          ...(apiKey ? { Authorization: `Bearer ${apiKey}` } : {})
-        },
+          ...providerAuthHeaders(provider)
+        }),
         cache: "no-store",
         signal: controller.signal
       });
       if ([429, 502, 503, 504].includes(response.status) && attempt < MODEL_FETCH_RETRY_DELAYS_MS.length - 1) {
+        await response.body?.cancel().catch(() => undefined);
         lastError = new Error(`Model endpoint returned HTTP ${response.status}`);
         continue;
       }
-      return response;
+      return { status: response.status, ok: response.ok, text: await response.text() };
     } catch (error) {
+      if (controller.signal.aborted) {
+        throw new ModelFetchError(`Model endpoint timed out after ${Math.round(timeoutMs / 1000)}s: ${url}`);
+      }
       lastError = error;
       if (attempt >= MODEL_FETCH_RETRY_DELAYS_MS.length - 1) break;
     } finally {
@@ -128,48 +250,55 @@ async function fetchModelsResponse(url: string, apiKey: string) {
     }
   }
 
-  throw new Error(`Model endpoint unreachable: ${url} (${describeFetchFailure(lastError)})`);
+  throw new ModelFetchError(`Model endpoint unreachable: ${url} (${describeFetchFailure(lastError)})`);
 }
 
-async function fetchOpenAiCompatibleModels(baseUrlRaw: string, apiKeyRaw: string): Promise<Array<{ id: string }>> {
-  const baseUrl = normalizeOpenAiBaseUrl(baseUrlRaw);
+async function fetchOpenAiCompatibleModels(
+  row: Pick<ProviderRow, "base_url"> & ProviderAuthSource
+): Promise<ProviderModelEntry[]> {
+  const baseUrl = normalizeOpenAiBaseUrl(row.base_url);
   if (!baseUrl) {
     throw new Error("Base URL is required");
   }
 
-  const apiKey = String(apiKeyRaw || "").trim();
   const endpoint = `${baseUrl}/models`;
-  const response = await fetchModelsResponse(endpoint, apiKey);
-  if (!response.ok) {
-    const text = await response.text().catch(() => "");
-    throw new Error(text || `Model endpoint returned HTTP ${response.status}: ${endpoint}`);
+  const result = await fetchModelsBody(endpoint, row, getSettings().modelFetchTimeoutSeconds * 1000);
+  if (!result.ok) {
+    const body = result.text.trim().slice(0, 500);
+    throw new ModelFetchError(
+      `Model endpoint returned HTTP ${result.status}: ${endpoint}${body ? ` — ${body}` : ""}`,
+      result.status,
+      body
+    );
   }
 
-  const body = await response.json() as {
-    data?: Array<{ id?: unknown }>;
-    models?: Array<{ id?: unknown }>;
-  };
-  const out: Array<{ id: string }> = [];
-
-  if (Array.isArray(body.data)) {
-    for (const item of body.data) {
-      const id = String(item?.id || "").trim();
-      if (id) out.push({ id });
-    }
-  }
-  if (Array.isArray(body.models)) {
-    for (const item of body.models) {
-      const id = String(item?.id || "").trim();
-      if (id) out.push({ id });
-    }
+  let body: unknown;
+  try {
+    body = JSON.parse(result.text);
+  } catch {
+    throw new ModelFetchError(`Model endpoint returned invalid JSON: ${endpoint}`, result.status, result.text.slice(0, 500));
   }
+  const metadata = parseModelMetadata(body);
+  return parseModelIds(body).map((id) => ({ id, ...metadata.get(id) }));
+}
 
-  const uniq = new Map<string, { id: string }>();
-  for (const row of out) uniq.set(row.id, row);
-  return Array.from(uniq.values());
+/**
+ * Model-list failures answer 400 like other provider setup problems. An HTTP rejection keeps the upstream status
+ * and body; anything else thrown while fetching is treated as the endpoint being unreachable.
+ */
+function modelFetchAppError(error: unknown, fallback: string): AppError {
+  if (error instanceof AppError) return error;
+  const message = (error instanceof Error ? error.message : String(error)) || fallback;
+  if (error instanceof ModelFetchError) {
+    return new AppError("provider", message, 400, error.status, {
+      cause: error,
+      details: { upstreamStatus: error.status, upstreamBody: error.body }
+    });
+  }
+  return new AppError("network", message, 400, null, { cause: error });
 }
 
-function mergeManualModels(models: Array<{ id: string }>, manualModels: Array<{ id: string }>) {
+function mergeManualModels(models: ProviderModelEntry[], manualModels: ProviderModelEntry[]) {
   if (models.length === 0) return manualModels;
   return [
     ...models,
@@ -178,8 +307,8 @@ function mergeManualModels(models: Array<{ id: string }>, manualModels: Array<{
 }
 
 async function resolveWithManualFallback(
-  manualModels: Array<{ id: string }>,
-  fetchModels: () => Promise<Array<{ id: string }>>
+  manualModels: ProviderModelEntry[],
+  fetchModels: () => Promise<ProviderModelEntry[]>
 ) {
   try {
     return mergeManualModels(await fetchModels(), manualModels);
@@ -189,18 +318,126 @@ async function resolveWithManualFallback(
   }
 }
 
+function providerBlock(baseUrl: string, fullLocalOnly: boolean): { reason: ProviderBlockReason; message: string } | null {
+  if (isLocalhostUrl(baseUrl)) return null;
+  if (getSettings().fullLocalMode) {
+    return { reason: "fullLocalMode", message: "Provider blocked by Full Local Mode" };
+  }
+  if (fullLocalOnly) {
+    return { reason: "localOnly", message: "Provider is set to Local-only. Disable Local-only for external URLs." };
+  }
+  return null;
+}
+
 function assertProviderAllowed(baseUrl: string, fullLocalOnly: boolean) {
-  const settings = getSettings();
-  if (settings.fullLocalMode && !isLocalhostUrl(baseUrl)) {
-    throw new Error("Provider blocked by Full Local Mode");
+  const blocked = providerBlock(baseUrl, fullLocalOnly);
+  if (blocked) throw notConfiguredError(blocked.message);
+}
+
+/** `fetchUrl` may carry a query-param API key, so errors name `url` instead. */
+async function probeEndpointStatus(
+  url: string,
+  headers: Record<string, string>,
+  timeoutMs: number,
+  fetchUrl = url
+): Promise<number> {
+  const controller = new AbortController();
+  const timeout = setTimeout(() => controller.abort(), timeoutMs);
+  try {
+    const response = await fetch(fetchUrl, { headers, cache: "no-store", signal: controller.signal });
+    await response.body?.cancel().catch(() => undefined);
+    return response.status;
+  } catch (error) {
+    if (controller.signal.aborted) {
+      throw new Error(`Provider did not answer within ${Math.round(timeoutMs / 1000)}s: ${url}`);
+    }
+    throw new Error(`Provider unreachable: ${url} (${describeFetchFailure(error)})`);
+  } finally {
+    clearTimeout(timeout);
+  }
+}
+
+/**
+ * Issues one lightweight authenticated request against the provider's model
+ * listing and returns the HTTP status. Adapter-driven types that only expose a
+ * boolean check report 200 on success.
+ */
+async function probeProviderStatus(row: ProviderRow, timeoutMs: number): Promise<number> {
+  const providerType = normalizeProviderType(row.provider_type);
+
+  if (providerType === "anthropic") {
+    return probeEndpointStatus(`${normalizeAnthropicBaseUrl(row.base_url)}/models?limit=1`, buildAnthropicHeaders(row), timeoutMs);
+  }
+  if (providerType === "ollama") {
+    return probeEndpointStatus(`${normalizeOllamaBaseUrl(row.base_url)}/api/tags`, withProviderHeaders(row, {}), timeoutMs);
+  }
+  if (providerType === "gemini") {
+    return probeEndpointStatus(
+      `${normalizeGeminiBaseUrl(row.base_url)}/models`,
+      withProviderHeaders(row, { Accept: "application/json" }),
+      timeoutMs,
+      buildGeminiUrl(row, "models", { pageSize: "1" })
+    );
+  }
+  if (providerType === "koboldcpp" || providerType === "custom") {
+    const connected = providerType === "koboldcpp"
+      ? await testKoboldConnection(row)
+      : await testCustomAdapterConnection(row, AbortSignal.timeout(timeoutMs));
+    if (!connected) throw new Error(`Provider did not respond to the connection check: ${row.base_url}`);
+    return 200;
+  }
+
+  const baseUrl = normalizeOpenAiBaseUrl(row.base_url);
+  if (!baseUrl) throw new Error("Base URL is required");
+  const endpoint = `${baseUrl}/models`;
+  return probeEndpointStatus(endpoint, withProviderHeaders(row, {
+    Accept: "application/json",
+    ...providerAuthHeaders(row)
+  }), timeoutMs, withProviderAuthUrl(row, endpoint));
+}
+
+async function testProviderConnection(row: ProviderRow): Promise<ProviderConnectionResult> {
+  const blocked = providerBlock(row.base_url, Boolean(row.full_local_only));
+  if (blocked) {
+    return {
+      ok: false,
+      reachable: false,
+      authorized: false,
+      status: null,
+      latencyMs: null,
+      blockedBy: blocked.reason,
+      error: blocked.message
+    };
   }
-  if (fullLocalOnly && !isLocalhostUrl(baseUrl)) {
-    throw new Error("Provider is set to Local-only. Disable Local-only for external URLs.");
+
+  const startedAt = Date.now();
+  try {
+    const status = await probeProviderStatus(row, getSettings().modelFetchTimeoutSeconds * 1000);
+    const authorized = status !== 401 && status !== 403;
+    const ok = status >= 200 && status < 300;
+    return {
+      ok,
+      reachable: true,
+      authorized,
+      status,
+      latencyMs: Date.now() - startedAt,
+      ...(ok ? {} : { error: authorized ? `Provider returned HTTP ${status}` : `Provider rejected the credentials (HTTP ${status})` })
+    };
+  } catch (error) {
+    return {
+      ok: false,
+      reachable: false,
+      authorized: false,
+      status: null,
+      latencyMs: null,
+      error: error instanceof Error ? error.message : String(error)
+    };
   }
 }
 
 function toPreviewProvider(body: ProviderPreviewInput) {
   const providerType = normalizeProviderType(body.providerType);
+  const authMode = normalizeProviderAuthMode(body.authMode);
   const manualModels = Array.isArray(body.manualModels)
     ? [...new Set(body.manualModels.map((item) => String(item || "").trim()).filter(Boolean))]
     : [];
@@ -211,46 +448,100 @@ function toPreviewProvider(body: ProviderPreviewInput) {
     full_local_only: body.fullLocalOnly === true || body.fullLocalOnly === 1 ? 1 : 0,
     provider_type: providerType,
     adapter_id: providerType === "custom" ? String(body.adapterId || "").trim() || null : null,
-    manual_models: JSON.stringify(manualModels)
-  } satisfies Pick<ProviderRow, "base_url" | "api_key_cipher" | "full_local_only" | "provider_type" | "adapter_id" | "manual_models">;
+    manual_models: JSON.stringify(manualModels),
+    extra_headers: JSON.stringify(normalizeProviderHeaders(body.headers)),
+    auth_mode: authMode,
+    auth_param: normalizeProviderAuthParam(authMode, body.authParam)
+  } satisfies ProviderModelSource;
 }
 
-async function resolveProviderModels(row: Pick<ProviderRow, "base_url" | "api_key_cipher" | "full_local_only" | "provider_type" | "adapter_id" | "manual_models">) {
+/** Runs a model-list fetch under the `modelFetchTimeoutSeconds` deadline so a hung endpoint cannot stall the picker. */
+async function withModelFetchTimeout<T>(url: string, fetcher: (signal: AbortSignal) => Promise<T>): Promise<T> {
+  const timeoutMs = getSettings().modelFetchTimeoutSeconds * 1000;
+  const signal = AbortSignal.timeout(timeoutMs);
+  try {
+    return await fetcher(signal);
+  } catch (error) {
+    if (signal.aborted) throw new ModelFetchError(`Model endpoint timed out after ${Math.round(timeoutMs / 1000)}s: ${url}`);
+    throw error;
+  }
+}
+
+async function resolveProviderModels(row: ProviderModelSource) {
   const manualModels = parseManualModels(row.manual_models).map((id) => ({ id }));
   assertProviderAllowed(row.base_url, Boolean(row.full_local_only));
 
   const providerType = normalizeProviderType(row.provider_type);
   if (providerType === "koboldcpp") {
     return resolveWithManualFallback(manualModels, async () => {
-      const koboldModels = await fetchKoboldModels(row);
+      const koboldModels = await withModelFetchTimeout(row.base_url, (signal) => fetchKoboldModels(row, signal));
       return koboldModels.map((id) => ({ id }));
     });
   }
 
+  if (providerType === "ollama") {
+    return resolveWithManualFallback(manualModels, async () => {
+      const ollamaModels = await withModelFetchTimeout(row.base_url, (signal) => fetchOllamaModels(row, signal));
+      return ollamaModels.map((id) => ({ id }));
+    });
+  }
+
+  if (providerType === "anthropic") {
+    return resolveWithManualFallback(manualModels, async () => {
+      const anthropicModels = await withModelFetchTimeout(row.base_url, (signal) => fetchAnthropicModels(row, signal));
+      return anthropicModels.map((id) => ({ id }));
+    });
+  }
+
+  if (providerType === "gemini") {
+    return resolveWithManualFallback(manualModels, () => withModelFetchTimeout(row.base_url, (signal) => fetchGeminiModels(row, signal)));
+  }
+
   if (providerType === "custom") {
     return resolveWithManualFallback(manualModels, async () => {
-      const customModels = await fetchCustomAdapterModels(row);
+      const customModels = await withModelFetchTimeout(row.base_url, (signal) => fetchCustomAdapterModels(row, signal));
       return customModels.map((id) => ({ id }));
     });
   }
 
   return resolveWithManualFallback(
     manualModels,
-    () => fetchOpenAiCompatibleModels(row.base_url, row.api_key_cipher)
+    () => fetchOpenAiCompatibleModels(row)
   );
 }
 
 router.post("/", (req, res) => {
-  const { id, name, baseUrl, apiKey, proxyUrl, fullLocalOnly, providerType, adapterId, manualModels } = req.body;
-  const normalizedType = normalizeProviderType(providerType);
-  const normalizedAdapterId = normalizedType === "custom" ? String(adapterId || "").trim() : null;
-  const normalizedManualModels = Array.isArray(manualModels)
-    ? [...new Set(manualModels.map((item) => String(item || "").trim()).filter(Boolean))]
-    : [];
+  const body = (req.body ?? {}) as Record<string, unknown>;
+  const { apiKey, headers, rateLimitRpm, authMode, authParam } = body;
+  let profile: ProviderProfileInput;
+  // Omitted rate limit keeps the stored value, null clears it.
+  let nextRateLimit: number | null;
+  try {
+    profile = normalizeProviderProfileInput(body);
+    nextRateLimit = rateLimitRpm === undefined
+      ? (db.prepare("SELECT rate_limit_rpm FROM providers WHERE id = ?").get(profile.id) as { rate_limit_rpm: number | null } | undefined)?.rate_limit_rpm ?? null
+      : parseRateLimitRpm(rateLimitRpm);
+  } catch (error) {
+    sendAppError(res, error);
+    return;
+  }
+  const { id } = profile;
+  // Callers that do not send headers (e.g. managed backends) keep the stored ones.
+  const storedHeaders = (db.prepare("SELECT extra_headers FROM providers WHERE id = ?").get(id) as { extra_headers: string | null } | undefined)?.extra_headers;
+  const nextHeaders = headers === undefined ? storedHeaders : restoreMaskedProviderHeaders(headers, storedHeaders);
+
+  // Omitted auth mode keeps the stored one as well.
+  const storedAuth = db.prepare("SELECT auth_mode, auth_param FROM providers WHERE id = ?")
+    .get(id) as { auth_mode: string | null; auth_param: string | null } | undefined;
+  const nextAuthMode = normalizeProviderAuthMode(authMode === undefined ? storedAuth?.auth_mode : authMode);
+  const nextAuthParam = normalizeProviderAuthParam(nextAuthMode, authParam === undefined ? storedAuth?.auth_param : authParam);
 
   db.prepare(`
-    INSERT INTO providers (id, name, base_url, api_key_cipher, proxy_url, full_local_only, provider_type, adapter_id, manual_models)
-    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+    INSERT INTO providers (
+      id, name, base_url, api_key_cipher, proxy_url, full_local_only, provider_type, adapter_id, manual_models, extra_headers,
+      rate_limit_rpm, auth_mode, auth_param
+    )
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
     ON CONFLICT(id) DO UPDATE SET
       name = excluded.name,
       base_url = excluded.base_url,
@@ -259,18 +550,27 @@ router.post("/", (req, res) => {
       full_local_only = excluded.full_local_only,
       provider_type = excluded.provider_type,
       adapter_id = excluded.adapter_id,
-      manual_models = excluded.manual_models
+      manual_models = excluded.manual_models,
+      extra_headers = excluded.extra_headers,
+      rate_limit_rpm = excluded.rate_limit_rpm,
+      auth_mode = excluded.auth_mode,
+      auth_param = excluded.auth_param
   `).run(
     id,
-    name,
-    baseUrl,
-    apiKey || "local-key",
-    proxyUrl || null,
-    fullLocalOnly ? 1 : 0,
-    normalizedType,
-    normalizedAdapterId,
-    JSON.stringify(normalizedManualModels)
+    profile.name,
+    profile.baseUrl,
+    String(apiKey || "") || "local-key",
+    profile.proxyUrl,
+    profile.fullLocalOnly ? 1 : 0,
+    profile.providerType,
+    profile.adapterId,
+    JSON.stringify(profile.manualModels),
+    JSON.stringify(normalizeProviderHeaders(nextHeaders)),
+    nextRateLimit,
+    nextAuthMode,
+    nextAuthParam
   );
+  clearCachedModels(id);
 
   const row = db.prepare("SELECT * FROM providers WHERE id = ?").get(id) as ProviderRow;
   res.json(rowToProfile(row));
@@ -287,8 +587,7 @@ router.post("/preview/models", async (req, res) => {
     const models = await resolveProviderModels(preview);
     res.json(models);
   } catch (error) {
-    const message = error instanceof Error ? error.message : String(error);
-    res.status(400).json({ error: message || "Failed to load provider models" });
+    sendAppError(res, modelFetchAppError(error, "Failed to load provider models"));
   }
 });
 
@@ -298,22 +597,85 @@ router.post("/preview/test", async (req, res) => {
     await resolveProviderModels(preview);
     res.json({ ok: true });
   } catch (error) {
-    const message = error instanceof Error ? error.message : String(error);
-    res.json({ ok: false, error: message || "Connection check failed" });
+    res.json({ ok: false, ...appErrorBody(modelFetchAppError(error, "Connection check failed")) });
   }
 });
 
+/** Cached model list while fresh, otherwise a new fetch; the stale cache stands in while the provider is unreachable. */
+async function loadProviderModelList(row: ProviderRow, forceRefresh: boolean): Promise<ProviderModelEntry[]> {
+  const cached = readCachedModels(row.id);
+  try {
+    if (!forceRefresh && cached && Date.now() - Date.parse(cached.fetchedAt) < MODEL_CACHE_TTL_MS) {
+      return cached.models;
+    }
+    const models = await resolveProviderModels(row);
+    writeCachedModels(row.id, models);
+    return models;
+  } catch (error) {
+    if (cached && cached.models.length > 0) return cached.models;
+    throw error;
+  }
+}
+
+function readAllowedProviderRow(id: string): ProviderRow | undefined {
+  const row = db.prepare("SELECT * FROM providers WHERE id = ?").get(id) as ProviderRow | undefined;
+  if (row) assertProviderAllowed(row.base_url, Boolean(row.full_local_only));
+  return row;
+}
+
 router.get("/:id/models", async (req, res) => {
-  const row = db.prepare("SELECT * FROM providers WHERE id = ?").get(req.params.id) as ProviderRow | undefined;
+  let row: ProviderRow | undefined;
+  try {
+    row = readAllowedProviderRow(req.params.id);
+  } catch (error) {
+    sendAppError(res, error);
+    return;
+  }
   if (!row) { res.json([]); return; }
+  const forceRefresh = req.query.refresh === "1" || req.query.refresh === "true";
+  const includeHidden = req.query.includeHidden === "1" || req.query.includeHidden === "true";
   try {
-    res.json(await resolveProviderModels(row));
+    res.json(applyModelPrefs(await loadProviderModelList(row, forceRefresh), readModelPrefs(row.id), includeHidden));
   } catch (error) {
-    const message = error instanceof Error ? error.message : String(error);
-    res.status(400).json({ error: message || "Failed to load provider models" });
+    sendAppError(res, modelFetchAppError(error, "Failed to load provider models"));
   }
 });
 
+router.get("/:id/models/search", async (req, res) => {
+  let row: ProviderRow | undefined;
+  try {
+    row = readAllowedProviderRow(req.params.id);
+  } catch (error) {
+    sendAppError(res, error);
+    return;
+  }
+  if (!row) { sendAppError(res, notFoundError("Provider not found")); return; }
+  const forceRefresh = req.query.refresh === "1" || req.query.refresh === "true";
+  const includeHidden = req.query.includeHidden === "1" || req.query.includeHidden === "true";
+  const filter = typeof req.query.filter === "string" ? req.query.filter : "";
+  const limit = typeof req.query.limit === "string" ? Number(req.query.limit) : undefined;
+  try {
+    const models = applyModelPrefs(await loadProviderModelList(row, forceRefresh), readModelPrefs(row.id), includeHidden);
+    res.json(searchModelList(models, filter, limit));
+  } catch (error) {
+    sendAppError(res, modelFetchAppError(error, "Failed to load provider models"));
+  }
+});
+
+for (const flag of ["favorite", "hidden"] as const) {
+  router.put(`/:id/models/${flag}`, (req, res) => {
+    const provider = db.prepare("SELECT id FROM providers WHERE id = ?").get(req.params.id) as { id: string } | undefined;
+    if (!provider) { sendAppError(res, notFoundError("Provider not found")); return; }
+    const modelId = typeof req.body?.modelId === "string" ? req.body.modelId.trim() : "";
+    const value = req.body?.[flag];
+    if (!modelId || typeof value !== "boolean") {
+      sendAppError(res, validationError(`modelId and a boolean ${flag} are required`));
+      return;
+    }
+    res.json({ modelId, ...writeModelPref(provider.id, modelId, flag, value) });
+  });
+}
+
 router.post("/set-active", (req, res) => {
   const { providerId, modelId } = req.body;
   const settings = getSettings();
@@ -325,13 +687,14 @@ router.post("/set-active", (req, res) => {
 router.post("/:id/runtime-config", (req, res) => {
   const row = db.prepare("SELECT * FROM providers WHERE id = ?").get(req.params.id) as ProviderRow | undefined;
   if (!row) {
-    res.status(404).json({ error: "Provider not found" });
+    sendAppError(res, notFoundError("Provider not found"));
     return;
   }
 
   const body = req.body as { baseUrl?: unknown; providerType?: unknown; adapterId?: unknown } | undefined;
-  const baseUrl = String(body?.baseUrl ?? row.base_url).trim() || row.base_url;
   const providerType = normalizeProviderType(body?.providerType ?? row.provider_type);
+  const requestedBaseUrl = String(body?.baseUrl ?? row.base_url).trim() || row.base_url;
+  const baseUrl = providerType === "openai" ? normalizeOpenAiBaseUrl(requestedBaseUrl) || requestedBaseUrl : requestedBaseUrl;
   const adapterId = providerType === "custom" ? String(body?.adapterId ?? row.adapter_id ?? "").trim() || null : null;
 
   db.prepare(`
@@ -339,6 +702,7 @@ router.post("/:id/runtime-config", (req, res) => {
     SET base_url = ?, provider_type = ?, adapter_id = ?
     WHERE id = ?
   `).run(baseUrl, providerType, adapterId, req.params.id);
+  clearCachedModels(req.params.id);
 
   const updated = db.prepare("SELECT * FROM providers WHERE id = ?").get(req.params.id) as ProviderRow;
   res.json(rowToProfile(updated));
@@ -346,13 +710,11 @@ router.post("/:id/runtime-config", (req, res) => {
 
 router.post("/:id/test", async (req, res) => {
   const row = db.prepare("SELECT * FROM providers WHERE id = ?").get(req.params.id) as ProviderRow | undefined;
-  if (!row) { res.json(false); return; }
-  try {
-    await resolveProviderModels(row);
-    res.json(true);
-  } catch {
-    res.json(false);
+  if (!row) {
+    sendAppError(res, notFoundError("Provider not found"));
+    return;
   }
+  res.json(await testProviderConnection(row));
 });
 
 export default router;
@@ -9,6 +9,7 @@ import {
   listRagDocuments,
   updateRagCollection
 } from "../services/rag.js";
+import { isSemanticSearchScope, searchSemantic, SEMANTIC_SEARCH_SCOPES } from "../services/semanticSearch.js";
 
 const router = Router();
 
@@ -93,4 +94,27 @@ router.delete("/documents/:id", (req, res) => {
   res.json({ ok: true, id: req.params.id });
 });
 
+router.post("/semantic-search", async (req, res) => {
+  const query = String(req.body?.query || "").trim();
+  const scope = req.body?.scope;
+  if (!query) {
+    res.status(400).json({ error: "query is required" });
+    return;
+  }
+  if (!isSemanticSearchScope(scope)) {
+    res.status(400).json({ error: `scope must be one of: ${SEMANTIC_SEARCH_SCOPES.join(", ")}` });
+    return;
+  }
+  try {
+    const hits = await searchSemantic(query, scope, getSettings(), Number(req.body?.limit) || 10);
+    if (!hits) {
+      res.status(400).json({ error: "Semantic search requires an embedding model (RAG model) to be configured" });
+      return;
+    }
+    res.json(hits);
+  } catch (err) {
+    res.status(502).json({ error: err instanceof Error ? err.message : "embedding request failed" });
+  }
+});
+
 export default router;
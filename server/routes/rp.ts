@@ -1,145 +1,30 @@
 import { Router } from "express";
 import { db, newId, now } from "../db.js";
-import { DEFAULT_PROMPT_BLOCKS } from "../domain/rpEngine.js";
+import { DEFAULT_AUTHOR_NOTE_DEPTH, DEFAULT_PROMPT_BLOCKS, MAX_AUTHOR_NOTE_DEPTH } from "../domain/rpEngine.js";
+import { BUILTIN_PRESETS, presetDisplayName } from "../domain/rpPresets.js";
+import { listChatMemories } from "../modules/chat/memoryRecall.js";
 
 const router = Router();
 
-type BuiltinPreset = {
-  mood: string;
-  pacing: "slow" | "balanced" | "fast";
-  intensity: number;
-  dialogueStyle: "teasing" | "playful" | "dominant" | "tender" | "formal" | "chaotic";
-  initiative: number;
-  descriptiveness: number;
-  unpredictability: number;
-  emotionalDepth: number;
-  jailbreakOverride?: string;
-};
+// Scene state, memory and prompt blocks reference chats by foreign key; answer 404 instead of a constraint error.
+function chatExists(chatId: string): boolean {
+  return Boolean(db.prepare("SELECT 1 FROM chats WHERE id = ?").get(chatId));
+}
 
 function clampPercent(value: number): number {
   return Math.max(0, Math.min(100, Math.round(value)));
 }
 
-// Built-in RP preset definitions
-const BUILTIN_PRESETS: Record<string, BuiltinPreset> = {
-  slowburn: {
-    mood: "tension, longing, anticipation",
-    pacing: "slow",
-    intensity: 0.5,
-    dialogueStyle: "tender",
-    initiative: 40,
-    descriptiveness: 85,
-    unpredictability: 30,
-    emotionalDepth: 92,
-    jailbreakOverride: "Focus on emotional buildup, tension, and slow-developing relationships. Let feelings simmer. Avoid rushing to conclusions. Write with restraint and emotional subtlety."
-  },
-  dominant: {
-    mood: "assertive, commanding, intense",
-    pacing: "balanced",
-    intensity: 0.8,
-    dialogueStyle: "dominant",
-    initiative: 90,
-    descriptiveness: 68,
-    unpredictability: 58,
-    emotionalDepth: 62,
-    jailbreakOverride: "Write assertive, confident characters. Emphasize power dynamics, control, and dominance in interactions. Characters should be bold and unapologetic."
-  },
-  romantic: {
-    mood: "tender, warm, affectionate",
-    pacing: "slow",
-    intensity: 0.6,
-    dialogueStyle: "tender",
-    initiative: 58,
-    descriptiveness: 80,
-    unpredictability: 35,
-    emotionalDepth: 92,
-    jailbreakOverride: "Focus on emotional intimacy, tenderness, and romantic connection. Write with warmth and vulnerability. Emphasize sweet moments and emotional openness."
-  },
-  action: {
-    mood: "tense, adrenaline, danger",
-    pacing: "fast",
-    intensity: 0.9,
-    dialogueStyle: "chaotic",
-    initiative: 95,
-    descriptiveness: 65,
-    unpredictability: 82,
-    emotionalDepth: 52,
-    jailbreakOverride: "Focus on action sequences, combat, and dynamic movement. Write with urgency and momentum. Keep scenes fast-paced with visceral detail."
-  },
-  mystery: {
-    mood: "suspicious, intriguing, atmospheric",
-    pacing: "balanced",
-    intensity: 0.6,
-    dialogueStyle: "formal",
-    initiative: 63,
-    descriptiveness: 82,
-    unpredictability: 78,
-    emotionalDepth: 55,
-    jailbreakOverride: "Create an atmosphere of suspense and intrigue. Drop subtle clues and red herrings. Write with tension and uncertainty. Keep the reader guessing."
-  },
-  submissive: {
-    mood: "shy, obedient, eager to please",
-    pacing: "slow",
-    intensity: 0.7,
-    dialogueStyle: "tender",
-    initiative: 36,
-    descriptiveness: 72,
-    unpredictability: 34,
-    emotionalDepth: 80,
-    jailbreakOverride: "Write characters that are submissive, yielding, and eager to serve. Emphasize vulnerability, shyness, and devotion. Characters blush, stammer, and seek approval. They find pleasure in pleasing others and being directed. Write body language that shows deference and nervous excitement."
-  },
-  seductive: {
-    mood: "flirty, teasing, sensual, alluring",
-    pacing: "slow",
-    intensity: 0.8,
-    dialogueStyle: "teasing",
-    initiative: 72,
-    descriptiveness: 86,
-    unpredictability: 48,
-    emotionalDepth: 76,
-    jailbreakOverride: "Write with heavy sensual undertones. Characters are flirtatious, playful, and provocative. Emphasize body language, lingering touches, suggestive dialogue, and building sexual tension. Describe physical reactions, heated glances, and teasing exchanges. Let desire simmer beneath every interaction."
-  },
-  gentle_fem: {
-    mood: "soft, nurturing, emotionally warm, caring",
-    pacing: "slow",
-    intensity: 0.6,
-    dialogueStyle: "tender",
-    initiative: 50,
-    descriptiveness: 78,
-    unpredictability: 28,
-    emotionalDepth: 88,
-    jailbreakOverride: "Write feminine characters that are gentle, nurturing, and emotionally available. Emphasize soft touches, caring gestures, whispered words of comfort. Characters are maternal yet intimate, providing emotional safety while being physically affectionate. Focus on tenderness, soft skin, gentle caresses, and emotional vulnerability."
-  },
-  rough: {
-    mood: "aggressive, primal, raw, intense",
-    pacing: "fast",
-    intensity: 0.95,
-    dialogueStyle: "dominant",
-    initiative: 96,
-    descriptiveness: 72,
-    unpredictability: 74,
-    emotionalDepth: 58,
-    jailbreakOverride: "Write with raw physicality and primal energy. Characters are rough, aggressive, and unapologetic in their desires. Emphasize physical dominance, grabbing, pinning, and forceful movement. Dialogue is direct, commanding, sometimes crude. Describe heavy breathing, sweat, and raw physical sensations. No restraint, no holding back."
-  },
-  passionate: {
-    mood: "deep desire, fire, emotional intensity, burning",
-    pacing: "balanced",
-    intensity: 0.85,
-    dialogueStyle: "tender",
-    initiative: 76,
-    descriptiveness: 82,
-    unpredictability: 50,
-    emotionalDepth: 94,
-    jailbreakOverride: "Write with burning passion and deep emotional connection. Characters are consumed by desire and love simultaneously. Emphasize desperate kisses, whispered confessions, bodies pressed together, emotional overwhelm. The intensity comes from both physical desire and deep emotional bond. Write as if each moment could be their last."
-  }
-};
-
 router.post("/scene-state", (req, res) => {
   const state = req.body;
   if (!state?.chatId) {
     res.status(400).json({ error: "chatId is required" });
     return;
   }
+  if (!chatExists(String(state.chatId))) {
+    res.status(404).json({ error: "Chat not found" });
+    return;
+  }
   const payload = JSON.stringify(state);
   const ts = now();
 
@@ -175,16 +60,27 @@ router.post("/author-note", (req, res) => {
   db.prepare("UPDATE chats SET author_note = ? WHERE id = ?")
     .run(String(authorNote || ""), chatId);
 
+  // Depth counts messages from the end of the history; omitting it leaves the stored depth alone, null resets it.
+  if (req.body?.depth !== undefined) {
+    const depth = req.body.depth === null ? null : Number(req.body.depth);
+    if (depth !== null && (!Number.isInteger(depth) || depth < 0 || depth > MAX_AUTHOR_NOTE_DEPTH)) {
+      res.status(400).json({ error: `depth must be an integer from 0 to ${MAX_AUTHOR_NOTE_DEPTH}` });
+      return;
+    }
+    db.prepare("UPDATE chats SET author_note_depth = ? WHERE id = ?").run(depth, chatId);
+  }
+
   res.json({ ok: true });
 });
 
 router.get("/author-note/:chatId", (req, res) => {
   const chatId = req.params.chatId;
 
-  const chat = db.prepare("SELECT author_note FROM chats WHERE id = ?")
-    .get(chatId) as { author_note: string | null } | undefined;
+  const chat = db.prepare("SELECT author_note, author_note_depth FROM chats WHERE id = ?")
+    .get(chatId) as { author_note: string | null; author_note_depth: number | null } | undefined;
+  const depth = chat?.author_note_depth ?? DEFAULT_AUTHOR_NOTE_DEPTH;
   if (chat?.author_note) {
-    res.json({ authorNote: chat.author_note });
+    res.json({ authorNote: chat.author_note, depth });
     return;
   }
 
@@ -192,7 +88,7 @@ router.get("/author-note/:chatId", (req, res) => {
     "SELECT content FROM rp_memory_entries WHERE chat_id = ? AND role = 'author_note' ORDER BY created_at DESC LIMIT 1"
   ).get(chatId) as { content: string } | undefined;
 
-  res.json({ authorNote: legacy?.content || "" });
+  res.json({ authorNote: legacy?.content || "", depth });
 });
 
 router.post("/apply-preset", (req, res) => {
@@ -209,6 +105,11 @@ router.post("/apply-preset", (req, res) => {
     return;
   }
 
+  if (!chatExists(String(chatId))) {
+    res.status(404).json({ error: "Chat not found" });
+    return;
+  }
+
   // Update scene state with preset values
   const existingState = db.prepare("SELECT payload FROM rp_scene_state WHERE chat_id = ?").get(chatId) as { payload: string } | undefined;
   const fallbackState = { chatId, variables: {}, mood: "neutral", pacing: "balanced", intensity: 0.5 };
@@ -269,7 +170,7 @@ router.post("/apply-preset", (req, res) => {
 router.get("/presets", (_req, res) => {
   const presets = Object.entries(BUILTIN_PRESETS).map(([id, config]) => ({
     id,
-    name: id.charAt(0).toUpperCase() + id.slice(1),
+    name: presetDisplayName(id),
     mood: config.mood,
     pacing: config.pacing,
     intensity: config.intensity,
@@ -277,11 +178,47 @@ router.get("/presets", (_req, res) => {
     initiative: config.initiative,
     descriptiveness: config.descriptiveness,
     unpredictability: config.unpredictability,
-    emotionalDepth: config.emotionalDepth
+    emotionalDepth: config.emotionalDepth,
+    style: config.style ?? null
   }));
   res.json(presets);
 });
 
+// --- Long-term memory ---
+
+router.get("/memory/:chatId", (req, res) => {
+  if (!chatExists(req.params.chatId)) {
+    res.status(404).json({ error: "Chat not found" });
+    return;
+  }
+  res.json(listChatMemories(req.params.chatId));
+});
+
+router.patch("/memory/:chatId/:entryId", (req, res) => {
+  const content = typeof req.body?.content === "string" ? req.body.content.trim() : "";
+  if (!content) {
+    res.status(400).json({ error: "content is required" });
+    return;
+  }
+  const result = db.prepare("UPDATE rp_memory_entries SET content = ? WHERE id = ? AND chat_id = ?")
+    .run(content, req.params.entryId, req.params.chatId);
+  if (result.changes === 0) {
+    res.status(404).json({ error: "Memory entry not found" });
+    return;
+  }
+  res.json({ ok: true });
+});
+
+router.delete("/memory/:chatId/:entryId", (req, res) => {
+  const result = db.prepare("DELETE FROM rp_memory_entries WHERE id = ? AND chat_id = ?")
+    .run(req.params.entryId, req.params.chatId);
+  if (result.changes === 0) {
+    res.status(404).json({ error: "Memory entry not found" });
+    return;
+  }
+  res.json({ ok: true });
+});
+
 // --- Prompt Blocks CRUD ---
 
 router.get("/blocks/:chatId", (req, res) => {
@@ -306,6 +243,10 @@ router.get("/blocks/:chatId", (req, res) => {
 router.put("/blocks/:chatId", (req, res) => {
   const { blocks } = req.body;
   const chatId = req.params.chatId;
+  if (!chatExists(chatId)) {
+    res.status(404).json({ error: "Chat not found" });
+    return;
+  }
 
   const deleteAll = db.prepare("DELETE FROM prompt_blocks WHERE chat_id = ?");
   const insert = db.prepare(
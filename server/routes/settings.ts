@@ -3,10 +3,20 @@ import type { Request } from "express";
 import { db, DEFAULT_SETTINGS, isLocalhostUrl } from "../db.js";
 import { describeBlockedMcpLaunch, discoverMcpToolCatalog, testMcpServerConnection, type McpServerConfig } from "../services/mcp.js";
 import { normalizeApiParamPolicy } from "../services/apiParamPolicy.js";
+import { forbiddenError, networkError, notConfiguredError, sendAppError, validationError } from "../services/appError.js";
 import { fetchCustomAdapterModels, fetchCustomAdapterVoices } from "../services/customProviderAdapters.js";
 import { normalizeCustomEndpointAdapters, normalizeCustomInspectorFields } from "../services/extensions.js";
+import { normalizeOpenAiBaseUrl } from "../services/providerApi.js";
 import { normalizeManagedBackends } from "../../src/shared/managedBackends.js";
+import { normalizeProviderFallbacks } from "../services/providerFallbacks.js";
 import { normalizeRuntimeTuningSettings } from "../services/runtimeTuning.js";
+import { normalizeAutoLockMinutes } from "../services/accountLock.js";
+import {
+  collectSettingsExport,
+  parseSettingsExport,
+  pickImportableSettings,
+  restoreExportedProviders
+} from "../services/settingsTransfer.js";
 
 const router = Router();
 const MODEL_DISCOVERY_TIMEOUT_MS = 12_000;
@@ -25,11 +35,24 @@ function normalizeWallpaperPosition(raw: unknown): "center" | "top" | "bottom" {
   return raw === "top" || raw === "bottom" ? raw : "center";
 }
 
-function clampWallpaperNumber(raw: unknown, fallback: number, min: number, max: number): number {
+function clampSettingsNumber(raw: unknown, fallback: number, min: number, max: number): number {
   const value = Number(raw);
   return Number.isFinite(value) ? Math.max(min, Math.min(max, value)) : fallback;
 }
 
+// A typo in one of these breaks the renderer, so unknown values reject the whole patch instead of persisting.
+const SETTINGS_ENUM_FIELDS: Record<string, readonly string[]> = {
+  theme: ["dark", "light", "custom"],
+  density: ["comfortable", "compact"],
+  censorshipMode: ["Filtered", "Moderated", "Unfiltered"]
+};
+
+function findInvalidSettingsEnums(patch: Record<string, unknown>): string[] {
+  return Object.entries(SETTINGS_ENUM_FIELDS)
+    .filter(([field, allowed]) => patch[field] !== undefined && !allowed.includes(patch[field] as string))
+    .map(([field, allowed]) => `${field} must be one of: ${allowed.join(", ")}`);
+}
+
 function normalizeSecuritySettings(raw: unknown) {
   const patch = raw && typeof raw === "object" && !Array.isArray(raw)
     ? raw as Record<string, unknown>
@@ -149,6 +172,7 @@ function getSettings() {
     ...DEFAULT_SETTINGS,
     ...stored,
     ...normalizeRuntimeTuningSettings(stored),
+    providerFallbacks: normalizeProviderFallbacks(stored.providerFallbacks),
     checkForUpdates: stored.checkForUpdates !== false,
     rpReasoningEnabled: stored.rpReasoningEnabled === true,
     includeReasoningInContext: stored.includeReasoningInContext !== false,
@@ -167,13 +191,13 @@ function getSettings() {
       ? Math.max(400, Math.min(12000, Math.floor(Number(stored.agentToolContextChars))))
       : DEFAULT_SETTINGS.agentToolContextChars,
     simpleModeWallpaper: normalizeSimpleModeWallpaper(stored.simpleModeWallpaper),
-    simpleModeWallpaperDim: clampWallpaperNumber(
+    simpleModeWallpaperDim: clampSettingsNumber(
       stored.simpleModeWallpaperDim,
       DEFAULT_SETTINGS.simpleModeWallpaperDim,
       0.15,
       0.9
     ),
-    simpleModeWallpaperBlur: clampWallpaperNumber(
+    simpleModeWallpaperBlur: clampSettingsNumber(
       stored.simpleModeWallpaperBlur,
       DEFAULT_SETTINGS.simpleModeWallpaperBlur,
       0,
@@ -202,13 +226,6 @@ function getSettings() {
   };
 }
 
-function normalizeOpenAiBaseUrl(raw: string): string {
-  const trimmed = String(raw || "").trim().replace(/\/+$/, "");
-  if (!trimmed) return "";
-  if (/\/v1$/i.test(trimmed)) return trimmed;
-  return `${trimmed}/v1`;
-}
-
 async function fetchOpenAiCompatibleModels(baseUrlRaw: string, apiKeyRaw: string): Promise<Array<{ id: string }>> {
   const baseUrl = normalizeOpenAiBaseUrl(baseUrlRaw);
   if (!baseUrl) return [];
@@ -428,7 +445,7 @@ function isPrivilegedMcpOriginAllowed(req: Request): boolean {
 
 router.use("/mcp", (req, res, next) => {
   if (!isPrivilegedMcpOriginAllowed(req)) {
-    res.status(403).json({ error: "MCP settings actions require a trusted app origin or Basic auth." });
+    sendAppError(res, forbiddenError("MCP settings actions require a trusted app origin or Basic auth."));
     return;
   }
   next();
@@ -438,18 +455,21 @@ router.get("/", (_req, res) => {
   res.json(getSettings());
 });
 
-router.patch("/", (req, res) => {
-  const patch = req.body as Record<string, unknown> | undefined;
-  const patchData = patch && typeof patch === "object" && !Array.isArray(patch) ? patch : {};
+/** Merges a settings patch over the stored settings; fields absent from the patch keep their current values. */
+function mergeSettingsPatch(patchData: Record<string, unknown>) {
   const current = getSettings();
   const runtimeTuning = normalizeRuntimeTuningSettings({ ...current, ...patchData });
-  const updated = {
+  return {
     ...current,
     ...patchData,
     ...runtimeTuning,
     checkForUpdates: patchData.checkForUpdates === undefined
       ? current.checkForUpdates
       : patchData.checkForUpdates === true,
+    fontScale: patchData.fontScale === undefined
+      ? current.fontScale
+      : clampSettingsNumber(patchData.fontScale, current.fontScale, 0.65, 1.5),
+    autoLockMinutes: normalizeAutoLockMinutes(patchData.autoLockMinutes ?? current.autoLockMinutes),
     ttsRealtime: patchData.ttsRealtime === undefined
       ? current.ttsRealtime
       : patchData.ttsRealtime === true,
@@ -492,10 +512,10 @@ router.patch("/", (req, res) => {
       : normalizeSimpleModeWallpaper(patchData.simpleModeWallpaper),
     simpleModeWallpaperDim: patchData.simpleModeWallpaperDim === undefined
       ? current.simpleModeWallpaperDim
-      : clampWallpaperNumber(patchData.simpleModeWallpaperDim, current.simpleModeWallpaperDim, 0.15, 0.9),
+      : clampSettingsNumber(patchData.simpleModeWallpaperDim, current.simpleModeWallpaperDim, 0.15, 0.9),
     simpleModeWallpaperBlur: patchData.simpleModeWallpaperBlur === undefined
       ? current.simpleModeWallpaperBlur
-      : clampWallpaperNumber(patchData.simpleModeWallpaperBlur, current.simpleModeWallpaperBlur, 0, 24),
+      : clampSettingsNumber(patchData.simpleModeWallpaperBlur, current.simpleModeWallpaperBlur, 0, 24),
     simpleModeWallpaperPosition: patchData.simpleModeWallpaperPosition === undefined
       ? current.simpleModeWallpaperPosition
       : normalizeWallpaperPosition(patchData.simpleModeWallpaperPosition),
@@ -506,6 +526,8 @@ router.patch("/", (req, res) => {
     sttApiKey: String(patchData.sttApiKey ?? current.sttApiKey ?? "").trim().slice(0, 4096),
     sttModel: String(patchData.sttModel ?? current.sttModel ?? "whisper-1").trim().slice(0, 200),
     sttLanguage: String(patchData.sttLanguage ?? current.sttLanguage ?? "").trim().slice(0, 24),
+    moderationUrl: String(patchData.moderationUrl ?? current.moderationUrl ?? "").trim().slice(0, 2048),
+    moderationApiKey: String(patchData.moderationApiKey ?? current.moderationApiKey ?? "").trim().slice(0, 4096),
     samplerConfig: { ...current.samplerConfig, ...(patchData.samplerConfig ?? {}) },
     apiParamPolicy: normalizeApiParamPolicy({
       ...(current.apiParamPolicy ?? {}),
@@ -543,10 +565,50 @@ router.patch("/", (req, res) => {
       (patchData as { customEndpointAdapters?: unknown }).customEndpointAdapters ?? current.customEndpointAdapters
     )
   };
+}
+
+router.patch("/", (req, res) => {
+  const patch = req.body as Record<string, unknown> | undefined;
+  const patchData = patch && typeof patch === "object" && !Array.isArray(patch) ? patch : {};
+  const invalidEnums = findInvalidSettingsEnums(patchData);
+  if (invalidEnums.length > 0) {
+    sendAppError(res, validationError(invalidEnums.join("; ")));
+    return;
+  }
+  const updated = mergeSettingsPatch(patchData);
   db.prepare("UPDATE settings SET payload = ? WHERE id = 1").run(JSON.stringify(updated));
   res.json(updated);
 });
 
+// Preferences and provider profiles only; provider keys stay masked unless the body sets includeKeys.
+router.post("/export", (req, res) => {
+  res.json(collectSettingsExport(db, getSettings(), req.body?.includeKeys === true));
+});
+
+router.post("/import", (req, res) => {
+  const includeKeys = req.body?.includeKeys === true;
+  let file;
+  try {
+    file = parseSettingsExport(req.body?.data);
+  } catch (error) {
+    sendAppError(res, validationError(error instanceof Error ? error.message : "Invalid settings file"));
+    return;
+  }
+  const { patch, ignored } = pickImportableSettings(file.settings, includeKeys);
+  const invalidEnums = findInvalidSettingsEnums(patch);
+  if (invalidEnums.length > 0) {
+    sendAppError(res, validationError(invalidEnums.join("; ")));
+    return;
+  }
+
+  const updated = mergeSettingsPatch(patch);
+  const providers = db.transaction(() => {
+    db.prepare("UPDATE settings SET payload = ? WHERE id = 1").run(JSON.stringify(updated));
+    return restoreExportedProviders(db, file.providers, includeKeys);
+  })();
+  res.json({ ok: true, settings: updated, providers: providers.restored, ignoredFields: [...ignored, ...providers.ignored] });
+});
+
 router.post("/tts/models", async (req, res) => {
   const current = getSettings();
   const body = req.body as { baseUrl?: unknown; apiKey?: unknown; adapterId?: unknown } | undefined;
@@ -560,7 +622,7 @@ router.post("/tts/models", async (req, res) => {
   }
 
   if (current.fullLocalMode && !isLocalhostUrl(baseUrl)) {
-    res.status(403).json({ error: "TTS endpoint blocked by Full Local Mode" });
+    sendAppError(res, notConfiguredError("TTS endpoint blocked by Full Local Mode", 403));
     return;
   }
 
@@ -574,7 +636,7 @@ router.post("/tts/models", async (req, res) => {
     res.json(models);
   } catch (error) {
     const message = error instanceof Error ? error.message : String(error);
-    res.status(502).json({ error: `TTS model endpoint unreachable: ${baseUrl} (${message || "request failed"})` });
+    sendAppError(res, networkError(`TTS model endpoint unreachable: ${baseUrl} (${message || "request failed"})`, error));
   }
 });
 
@@ -591,7 +653,7 @@ router.post("/tts/voices", async (req, res) => {
   }
 
   if (current.fullLocalMode && !isLocalhostUrl(baseUrl)) {
-    res.status(403).json({ error: "TTS endpoint blocked by Full Local Mode" });
+    sendAppError(res, notConfiguredError("TTS endpoint blocked by Full Local Mode", 403));
     return;
   }
 
@@ -605,7 +667,7 @@ router.post("/tts/voices", async (req, res) => {
     res.json(voices);
   } catch (error) {
     const message = error instanceof Error ? error.message : String(error);
-    res.status(502).json({ error: `TTS voice endpoint unreachable: ${baseUrl} (${message || "request failed"})` });
+    sendAppError(res, networkError(`TTS voice endpoint unreachable: ${baseUrl} (${message || "request failed"})`, error));
   }
 });
 
@@ -619,14 +681,14 @@ router.post("/stt/models", async (req, res) => {
     return;
   }
   if (current.fullLocalMode && !isLocalhostUrl(baseUrl)) {
-    res.status(403).json({ error: "STT endpoint blocked by Full Local Mode" });
+    sendAppError(res, notConfiguredError("STT endpoint blocked by Full Local Mode", 403));
     return;
   }
   try {
     res.json(await fetchOpenAiCompatibleModels(baseUrl, apiKey));
   } catch (error) {
     const message = error instanceof Error ? error.message : String(error);
-    res.status(502).json({ error: `STT model endpoint unreachable: ${baseUrl} (${message || "request failed"})` });
+    sendAppError(res, networkError(`STT model endpoint unreachable: ${baseUrl} (${message || "request failed"})`, error));
   }
 });
 
@@ -638,7 +700,7 @@ router.post("/reset", (_req, res) => {
 router.post("/mcp/test", async (req, res) => {
   const raw = (req.body as { server?: unknown } | undefined)?.server;
   if (!raw || typeof raw !== "object") {
-    res.status(400).json({ ok: false, tools: [], error: "server payload is required" });
+    sendAppError(res, validationError("server payload is required", { ok: false, tools: [] }));
     return;
   }
   const row = raw as Partial<McpServerConfig>;
@@ -659,7 +721,7 @@ router.post("/mcp/test", async (req, res) => {
 router.post("/mcp/import", async (req, res) => {
   const source = String((req.body as { source?: unknown } | undefined)?.source || "").trim();
   if (!source) {
-    res.status(400).json({ ok: false, servers: [], sourceType: "json", error: "source is required" });
+    sendAppError(res, validationError("source is required", { ok: false, servers: [], sourceType: "json" }));
     return;
   }
 
@@ -677,22 +739,21 @@ router.post("/mcp/import", async (req, res) => {
     try {
       parsed = JSON.parse(loaded.content);
     } catch {
-      res.status(400).json({ ok: false, servers: [], sourceType: loaded.sourceType, error: "Invalid JSON source" });
+      sendAppError(res, validationError("Invalid JSON source", { ok: false, servers: [], sourceType: loaded.sourceType }));
       return;
     }
     const servers = parseMcpServersPayload(parsed);
     if (servers.length === 0) {
-      res.status(400).json({ ok: false, servers: [], sourceType: loaded.sourceType, error: "No MCP servers found in source" });
+      sendAppError(res, validationError("No MCP servers found in source", { ok: false, servers: [], sourceType: loaded.sourceType }));
       return;
     }
     res.json({ ok: true, servers, sourceType: loaded.sourceType });
   } catch (err) {
-    res.status(400).json({
+    sendAppError(res, validationError(err instanceof Error ? err.message : "Import failed", {
       ok: false,
       servers: [],
-      sourceType: /^https?:\/\//i.test(source) ? "url" : "json",
-      error: err instanceof Error ? err.message : "Import failed"
-    });
+      sourceType: /^https?:\/\//i.test(source) ? "url" : "json"
+    }));
   }
 });
 
@@ -711,11 +772,7 @@ router.post("/mcp/discover", async (req, res) => {
     const tools = await discoverMcpToolCatalog(servers);
     res.json({ ok: true, tools });
   } catch (err) {
-    res.status(400).json({
-      ok: false,
-      tools: [],
-      error: err instanceof Error ? err.message : "MCP discovery failed"
-    });
+    sendAppError(res, validationError(err instanceof Error ? err.message : "MCP discovery failed", { ok: false, tools: [] }));
   }
 });
 
@@ -2,7 +2,18 @@ import { Router } from "express";
 import { writeFileSync } from "fs";
 import { join } from "path";
 import { db, newId, now, DATA_DIR } from "../db.js";
-import { runConsistency } from "../domain/writerEngine.js";
+import { buildResponseLanguageDirective } from "../domain/rpEngine.js";
+import {
+  checkNameConsistency,
+  checkSceneConsistency,
+  CONSISTENCY_CATEGORIES,
+  CONSISTENCY_SEVERITIES,
+  filterConsistencyIssues,
+  runConsistency,
+  type ConsistencyIssue
+} from "../domain/writerEngine.js";
+import { buildChatTranscript, NARRATIVIZE_CHAT_PROMPT } from "../domain/writerChatScene.js";
+import { buildOutlineTask, MAX_OUTLINE_CHAPTERS, parseOutline } from "../domain/writerOutline.js";
 import { getWriterRagBinding, setWriterRagBinding } from "../services/rag.js";
 import {
   DEFAULT_CHAPTER_SETTINGS,
@@ -13,6 +24,7 @@ import {
   parseJsonIdArray,
   parseProjectNotes,
   normalizeProjectNotes,
+  normalizeWriterBeats,
   type WriterProjectNotes,
   type WriterSummaryLensRow,
   type WriterCharacterAdvancedInput,
@@ -42,18 +54,30 @@ import {
   parseChapterSettings
 } from "../modules/writer/chapterSettings.js";
 import {
+  buildBeatDirective,
   buildCharacterContext,
   buildChapterDirective,
   buildProjectContextPack,
   buildProjectContinuationContextPack,
   buildProjectNotesDirective,
+  buildSceneCharacterDirective,
   buildWriterRagDirective,
   truncateForPrompt
 } from "../modules/writer/context.js";
-import { buildDocxBufferFromBundle, buildWriterExportBundle } from "../modules/writer/export.js";
-import { callWriterLlm, getWriterSettings } from "../modules/writer/llm.js";
+import { buildDocxBufferFromBundle, buildHtmlFromBundle, buildWriterExportBundle } from "../modules/writer/export.js";
+import { PartialStreamError } from "../modules/chat/providerExecution.js";
+import {
+  abortWriterStream,
+  callWriterLlm,
+  getWriterProviderBlock,
+  getWriterSettings,
+  isWriterLlmFailure,
+  openWriterStream,
+  streamWriterLlm
+} from "../modules/writer/llm.js";
 import {
   buildChapterSummaryPrompt,
+  fallbackSceneSummary,
   hashWriterContent,
   lensRowToJson,
   normalizeLensName,
@@ -64,8 +88,10 @@ import {
   summarizeWithCache
 } from "../modules/writer/lenses.js";
 import {
+  createChatSceneRecord,
   createGeneratedChapterWithScene,
   createGeneratedSceneRecord,
+  createOutlineChapters,
   createChapterRecord,
   createImportedProjectRecord,
   createLensRecord,
@@ -77,37 +103,56 @@ import {
   getChapterGenerationRow,
   getChapterIdsForProject,
   getChapterRow,
+  getChatSceneSource,
   getLastProjectChapter,
   getLensRow,
   getProjectOpenPayload,
+  getProjectBeats,
   getProjectGenerationRow,
+  getConsistencyReportRow,
   getProjectRow,
   getProjectSummaryRow,
   getSceneProjectRow,
   getSceneRow,
+  getSceneRevisionRow,
   getSceneSummaryRow,
+  getStoredSceneSummary,
   listChapterSceneContentRows,
+  listChatSceneTurns,
+  listConsistencyReportRows,
   listConsistencyScenes,
+  buildConsistencyRoster,
+  listProjectChapters,
   listProjectChapterSummaryRows,
   listProjectLensRows,
   listProjects,
+  listSceneRevisionRows,
   projectExists,
   recordConsistencyReport,
+  recordSceneRevision,
   recordWriterExport,
+  reorderChapterScenes,
+  replaceProjectBeats,
   reorderProjectChapters,
+  resolveChatSceneBranch,
+  restoreSceneRevision,
   toChapterJson,
   toProjectJson,
   toSceneJson,
+  toSceneRevisionJson,
   updateLensRecord,
   updateChapterSettings,
   updateChapterTitle,
   updateProjectCharacters,
   updateProjectMetadata,
   updateProjectNotes,
+  updateSceneCharacter,
   updateSceneContent,
   updateSceneRecord,
   upsertChapterSummary,
-  upsertProjectSummary
+  upsertSceneSummary,
+  upsertProjectSummary,
+  type WriterSceneRow
 } from "../modules/writer/repository.js";
 
 const router = Router();
@@ -363,6 +408,31 @@ router.patch("/projects/:id/notes", (req, res) => {
   });
 });
 
+router.get("/projects/:id/beats", (req, res) => {
+  const projectId = req.params.id;
+  if (!projectExists(projectId)) {
+    res.status(404).json({ error: "Project not found" });
+    return;
+  }
+  res.json({ beats: getProjectBeats(projectId) });
+});
+
+router.put("/projects/:id/beats", (req, res) => {
+  const projectId = req.params.id;
+  if (!projectExists(projectId)) {
+    res.status(404).json({ error: "Project not found" });
+    return;
+  }
+  const chapterIds = new Set(listProjectChapters(projectId).map((chapter) => chapter.id));
+  // A beat pointing at a chapter from another project (or a deleted one) stays in the plan, unassigned.
+  const beats = normalizeWriterBeats((req.body as { beats?: unknown })?.beats).map((beat) => ({
+    ...beat,
+    targetChapterId: beat.targetChapterId && chapterIds.has(beat.targetChapterId) ? beat.targetChapterId : null
+  }));
+  replaceProjectBeats(projectId, beats);
+  res.json({ beats });
+});
+
 router.get("/projects/:id/rag", (req, res) => {
   const projectId = req.params.id;
   if (!projectExists(projectId)) {
@@ -711,8 +781,8 @@ router.delete("/chapters/:id", (req, res) => {
     return;
   }
 
-  deleteChapterCascade(chapter.id, chapter.project_id, chapter.position);
-  res.json({ ok: true, id: chapter.id });
+  deleteChapterCascade(chapter.id, chapter.project_id);
+  res.json({ ok: true, id: chapter.id, chapters: listProjectChapters(chapter.project_id).map((row) => toChapterJson(row)) });
 });
 
 router.patch("/chapters/:id/settings", (req, res) => {
@@ -766,7 +836,8 @@ router.post("/projects/:id/generate-next-chapter", async (req, res) => {
     settings.promptTemplates.writerGenerate,
     buildChapterDirective(chapterSettings),
     buildCharacterContext(parseJsonIdArray(project.character_ids)),
-    buildProjectNotesDirective(projectNotes)
+    buildProjectNotesDirective(projectNotes),
+    buildResponseLanguageDirective(settings.responseLanguage)
   ].filter(Boolean).join("\n\n");
 
   const userPrompt = [
@@ -788,7 +859,7 @@ router.post("/projects/:id/generate-next-chapter", async (req, res) => {
   const sceneContent = content || "(empty scene)";
   const sceneTitle = chapterTitle;
 
-  createGeneratedChapterWithScene({
+  const sceneRow = createGeneratedChapterWithScene({
     chapterId,
     sceneId,
     projectId,
@@ -809,17 +880,111 @@ router.post("/projects/:id/generate-next-chapter", async (req, res) => {
       settings: chapterSettings,
       createdAt: ts
     },
-    scene: {
-      id: sceneId,
-      chapterId,
-      title: sceneTitle,
-      content: sceneContent,
-      goals: "Advance plot",
-      conflicts: "Escalate conflict",
-      outcomes: "Open ending",
-      createdAt: ts
+    scene: toSceneJson(sceneRow)
+  });
+});
+
+router.post("/projects/:id/generate-outline", async (req, res) => {
+  const projectId = req.params.id;
+  const project = getProjectGenerationRow(projectId);
+  if (!project) {
+    res.status(404).json({ error: "Project not found" });
+    return;
+  }
+  const providerBlock = getWriterProviderBlock();
+  if (providerBlock) {
+    res.status(400).json({ error: providerBlock });
+    return;
+  }
+  const projectNotes = parseProjectNotes(project.notes_json);
+  const premise = toCleanText(req.body?.premise, 6000) || projectNotes.premise;
+  if (!premise) {
+    res.status(400).json({ error: "A premise is required to outline the book" });
+    return;
+  }
+  const requestedCount = Math.floor(Number(req.body?.chapterCount));
+  const chapterCount = requestedCount >= 1 ? Math.min(requestedCount, MAX_OUTLINE_CHAPTERS) : null;
+  const withScenes = req.body?.withScenes === true;
+  const stream = req.body?.stream === true;
+
+  const settings = getWriterSettings();
+  const systemPrompt = [
+    "You are a story architect who plans novels chapter by chapter.",
+    buildCharacterContext(parseJsonIdArray(project.character_ids)),
+    buildProjectNotesDirective(projectNotes),
+    buildResponseLanguageDirective(settings.responseLanguage)
+  ].filter(Boolean).join("\n\n");
+  const userPrompt = buildOutlineTask(premise, chapterCount);
+  const sampler = createWriterSampler(settings.samplerConfig, DEFAULT_CHAPTER_SETTINGS);
+  const saveOutline = (content: string) => {
+    const outline = parseOutline(content, chapterCount ?? MAX_OUTLINE_CHAPTERS);
+    if (outline.length === 0) return null;
+    const created = createOutlineChapters({
+      projectId,
+      outline,
+      withScenes,
+      settingsJson: JSON.stringify(DEFAULT_CHAPTER_SETTINGS)
+    });
+    return {
+      chapters: created.chapters.map((row) => toChapterJson(row)),
+      scenes: created.scenes.map((row) => toSceneJson(row))
+    };
+  };
+  const noOutlineError = "The model did not return a chapter outline";
+
+  if (!stream) {
+    const result = saveOutline(await callWriterLlm(systemPrompt, userPrompt, sampler));
+    if (!result) {
+      res.status(502).json({ error: noOutlineError });
+      return;
     }
+    res.json(result);
+    return;
+  }
+
+  res.writeHead(200, {
+    "Content-Type": "text/event-stream",
+    "Cache-Control": "no-cache",
+    Connection: "keep-alive",
+    "X-Accel-Buffering": "no"
   });
+  res.flushHeaders?.();
+
+  const abortController = openWriterStream(projectId, res);
+
+  try {
+    const content = await streamWriterLlm({
+      systemPrompt,
+      userPrompt,
+      sampler,
+      streamId: projectId,
+      res,
+      signal: abortController.signal
+    });
+    // A cancelled outline usually ends mid-line, so nothing is created from it.
+    if (abortController.signal.aborted) {
+      res.write(`data: ${JSON.stringify({ type: "done", chatId: projectId, interrupted: true })}\n\n`);
+      res.end();
+      return;
+    }
+    const result = saveOutline(content);
+    res.write(`data: ${JSON.stringify(result ? { type: "outline", ...result } : { type: "error", chatId: projectId, error: noOutlineError })}\n\n`);
+    res.write(`data: ${JSON.stringify({ type: "done", chatId: projectId })}\n\n`);
+  } catch (err) {
+    if (err instanceof Error && err.name === "AbortError") {
+      res.write(`data: ${JSON.stringify({ type: "done", chatId: projectId, interrupted: true })}\n\n`);
+    } else {
+      const error = err instanceof Error ? err.message : "Network error";
+      res.write(`data: ${JSON.stringify({ type: "error", chatId: projectId, error })}\n\n`);
+      res.write(`data: ${JSON.stringify({ type: "done", chatId: projectId })}\n\n`);
+    }
+  }
+  if (!res.writableEnded) res.end();
+});
+
+// Cancel a streamed draft or outline; the stream id is the chapter or project id it was started for
+router.post("/streams/:id/abort", (req, res) => {
+  res.json({ ok: true, interrupted: abortWriterStream(req.params.id) });
 });
 
 // --- Scenes / Generation (LLM-backed) ---
@@ -827,16 +992,26 @@ router.post("/projects/:id/generate-next-chapter", async (req, res) => {
 router.post("/chapters/:id/generate-draft", async (req, res) => {
   const chapterId = req.params.id;
   const { prompt } = req.body;
+  const stream = req.body?.stream === true;
   const chapter = getChapterGenerationRow(chapterId);
   if (!chapter) {
     res.status(404).json({ error: "Chapter not found" });
     return;
   }
+  // Optional character the drafted scene is linked to (writer_scenes.character_id).
+  const characterId = typeof req.body?.characterId === "string" ? req.body.characterId.trim() : "";
+  if (characterId && !db.prepare("SELECT 1 FROM characters WHERE id = ?").get(characterId)) {
+    res.status(404).json({ error: "Character not found" });
+    return;
+  }
+  const providerBlock = getWriterProviderBlock();
+  if (providerBlock) {
+    res.status(400).json({ error: providerBlock });
+    return;
+  }
   const project = getProjectGenerationRow(chapter.project_id);
 
   const chapterSettings = parseChapterSettings(chapter.settings_json);
-  const id = newId();
-  const ts = now();
 
   const settings = getWriterSettings();
   const projectNotes = parseProjectNotes(project?.notes_json);
@@ -851,39 +1026,137 @@ router.post("/chapters/:id/generate-draft", async (req, res) => {
     settings.promptTemplates.writerGenerate,
     buildChapterDirective(chapterSettings),
     buildCharacterContext(parseJsonIdArray(project?.character_ids)),
-    buildProjectNotesDirective(projectNotes)
+    buildSceneCharacterDirective(characterId),
+    buildProjectNotesDirective(projectNotes),
+    buildResponseLanguageDirective(settings.responseLanguage)
   ].filter(Boolean).join("\n\n");
   const sampler = createWriterSampler(settings.samplerConfig, chapterSettings);
+  // An explicit beatId drafts that beat; otherwise every beat planned for this chapter guides the draft.
+  const beatId = typeof req.body?.beatId === "string" ? req.body.beatId : "";
+  const beats = getProjectBeats(chapter.project_id)
+    .filter((beat) => beatId ? beat.id === beatId : beat.targetChapterId === chapterId);
   const userPrompt = [
     "[Writing Task]",
     String(prompt || ""),
+    buildBeatDirective(beats),
     projectContext ? `[Context Pack]\n${projectContext}` : "",
     writerRagDirective
   ].filter(Boolean).join("\n\n");
-  const content = await callWriterLlm(systemPrompt, userPrompt, sampler);
-  const titleMatch = content.match(/^#\s*(.+)/m);
-  const title = titleMatch ? titleMatch[1].slice(0, 60) : "Generated Scene";
+  const saveDraftScene = (content: string) => {
+    const titleMatch = content.match(/^#\s*(.+)/m);
+    return toSceneJson(createGeneratedSceneRecord({
+      id: newId(),
+      chapterId,
+      title: titleMatch ? titleMatch[1].slice(0, 60) : "Generated Scene",
+      content,
+      characterId: characterId || null,
+      createdAt: now()
+    }));
+  };
 
-  createGeneratedSceneRecord({
-    id,
-    chapterId,
-    title,
-    content,
-    createdAt: ts
-  });
+  if (!stream) {
+    res.json(saveDraftScene(await callWriterLlm(systemPrompt, userPrompt, sampler)));
+    return;
+  }
 
-  res.json({
-    id, chapterId, title, content,
-    goals: "Advance plot", conflicts: "Internal conflict", outcomes: "Open ending", createdAt: ts
+  res.writeHead(200, {
+    "Content-Type": "text/event-stream",
+    "Cache-Control": "no-cache",
+    Connection: "keep-alive",
+    "X-Accel-Buffering": "no"
   });
+  res.flushHeaders?.();
+
+  const abortController = openWriterStream(chapterId, res);
+
+  try {
+    const content = await streamWriterLlm({
+      systemPrompt,
+      userPrompt,
+      sampler,
+      streamId: chapterId,
+      res,
+      signal: abortController.signal
+    });
+    // A cancelled draft keeps whatever text arrived, flagged as truncated like a broken stream.
+    const interrupted = abortController.signal.aborted;
+    if (!interrupted) {
+      res.write(`data: ${JSON.stringify({ type: "scene", scene: saveDraftScene(content) })}\n\n`);
+    } else if (!isWriterLlmFailure(content)) {
+      res.write(`data: ${JSON.stringify({ type: "scene", scene: saveDraftScene(content), truncated: true })}\n\n`);
+    }
+    res.write(`data: ${JSON.stringify({ type: "done", chatId: chapterId, ...(interrupted ? { interrupted: true } : {}) })}\n\n`);
+  } catch (err) {
+    if (err instanceof Error && err.name === "AbortError") {
+      res.write(`data: ${JSON.stringify({ type: "done", chatId: chapterId, interrupted: true })}\n\n`);
+    } else {
+      // Text that already reached the editor is kept as a scene rather than dropped.
+      if (err instanceof PartialStreamError && err.partial.content) {
+        res.write(`data: ${JSON.stringify({ type: "scene", scene: saveDraftScene(err.partial.content), truncated: true })}\n\n`);
+      }
+      const error = err instanceof Error ? err.message : "Network error";
+      res.write(`data: ${JSON.stringify({ type: "error", chatId: chapterId, error })}\n\n`);
+      res.write(`data: ${JSON.stringify({ type: "done", chatId: chapterId })}\n\n`);
+    }
+  }
+  if (!res.writableEnded) res.end();
 });
 
-router.post("/scenes/:id/expand", async (req, res) => {
-  const sceneId = req.params.id;
-  const row = getSceneRow(sceneId);
+// Drafts a scene from a roleplay chat branch; the scene keeps chat_id (and the chat's character) as its source.
+router.post("/chapters/:id/scenes/from-chat", async (req, res) => {
+  const chapterId = req.params.id;
+  const chatId = typeof req.body?.chatId === "string" ? req.body.chatId.trim() : "";
+  const requestedBranchId = typeof req.body?.branchId === "string" ? req.body.branchId.trim() : "";
+  if (!chatId) { res.status(400).json({ error: "chatId is required" }); return; }
+  const chapter = getChapterGenerationRow(chapterId);
+  if (!chapter) { res.status(404).json({ error: "Chapter not found" }); return; }
+  const chat = getChatSceneSource(chatId);
+  if (!chat) { res.status(404).json({ error: "Chat not found" }); return; }
+  const branchId = resolveChatSceneBranch(chatId, requestedBranchId || undefined);
+  if (!branchId) { res.status(404).json({ error: "Branch not found" }); return; }
+  const providerBlock = getWriterProviderBlock();
+  if (providerBlock) { res.status(400).json({ error: providerBlock }); return; }
+
+  const userName = chat.persona_name || "User";
+  const transcript = buildChatTranscript(listChatSceneTurns(chatId, branchId).map((turn) => ({
+    speaker: turn.role === "user" ? userName : turn.character_name || chat.character_name || "Character",
+    content: turn.content
+  })));
+  if (!transcript) { res.status(400).json({ error: "Chat has no messages to turn into a scene" }); return; }
 
-  if (!row) { res.status(404).json({ error: "Scene not found" }); return; }
+  const settings = getWriterSettings();
+  const project = getProjectGenerationRow(chapter.project_id);
+  const chapterSettings = parseChapterSettings(chapter.settings_json);
+  const projectNotes = parseProjectNotes(project?.notes_json);
+  const systemPrompt = [
+    NARRATIVIZE_CHAT_PROMPT,
+    buildChapterDirective(chapterSettings),
+    buildCharacterContext(parseJsonIdArray(project?.character_ids)),
+    buildSceneCharacterDirective(chat.character_id),
+    buildProjectNotesDirective(projectNotes),
+    buildResponseLanguageDirective(settings.responseLanguage)
+  ].filter(Boolean).join("\n\n");
+  const content = await callWriterLlm(
+    systemPrompt,
+    `[Chat Transcript]\n${transcript}`,
+    createWriterSampler(settings.samplerConfig, chapterSettings)
+  );
+  if (isWriterLlmFailure(content)) { res.status(502).json({ error: content }); return; }
+
+  const requestedTitle = typeof req.body?.title === "string" ? req.body.title.trim() : "";
+  const scene = createChatSceneRecord({
+    id: newId(),
+    chapterId,
+    title: (requestedTitle || chat.title || "Scene from chat").slice(0, 120),
+    content,
+    chatId,
+    characterId: chat.character_id,
+    createdAt: now()
+  });
+  res.json(toSceneJson(scene));
+});
 
+async function buildSceneExpandRequest(row: WriterSceneRow) {
   const settings = getWriterSettings();
   const chapter = getChapterGenerationRow(row.chapter_id);
   const project = chapter ? getProjectGenerationRow(chapter.project_id) : undefined;
@@ -902,21 +1175,130 @@ router.post("/scenes/:id/expand", async (req, res) => {
     settings.promptTemplates.writerExpand,
     buildChapterDirective(chapterSettings),
     buildCharacterContext(parseJsonIdArray(project?.character_ids)),
-    buildProjectNotesDirective(projectNotes)
+    buildSceneCharacterDirective(row.character_id),
+    buildProjectNotesDirective(projectNotes),
+    buildResponseLanguageDirective(settings.responseLanguage)
   ].filter(Boolean).join("\n\n");
-  const sampler = createWriterSampler(settings.samplerConfig, chapterSettings);
-  const expanded = await callWriterLlm(
+  return {
+    settings,
     systemPrompt,
-    [projectContext ? `[Context Pack]\n${projectContext}` : "", writerRagDirective, row.content].filter(Boolean).join("\n\n"),
-    sampler
-  );
+    userPrompt: [projectContext ? `[Context Pack]\n${projectContext}` : "", writerRagDirective, row.content].filter(Boolean).join("\n\n"),
+    sampler: createWriterSampler(settings.samplerConfig, chapterSettings)
+  };
+}
+
+router.post("/scenes/:id/expand", async (req, res) => {
+  const sceneId = req.params.id;
+  const row = getSceneRow(sceneId);
+
+  if (!row) { res.status(404).json({ error: "Scene not found" }); return; }
+
+  const { settings, systemPrompt, userPrompt, sampler } = await buildSceneExpandRequest(row);
+  const expanded = await callWriterLlm(systemPrompt, userPrompt, sampler);
 
+  recordSceneRevision(row, "expand", settings.writerSceneRevisionLimit);
   updateSceneContent(sceneId, expanded);
 
-  res.json({
-    id: row.id, chapterId: row.chapter_id, title: row.title, content: expanded,
-    goals: row.goals, conflicts: row.conflicts, outcomes: row.outcomes, createdAt: row.created_at
+  res.json(toSceneJson({ ...row, content: expanded }));
+});
+
+const MAX_BATCH_EXPAND_SCENES = 20;
+// Requests in flight at once; the per-provider rate limit still paces what actually reaches the API.
+const BATCH_EXPAND_CONCURRENCY = 3;
+
+interface BatchExpandResult {
+  sceneId: string;
+  status: "ok" | "error" | "not_found" | "interrupted";
+  scene?: ReturnType<typeof toSceneJson>;
+  error?: string;
+}
+
+async function mapWithConcurrency<T, R>(items: T[], limit: number, worker: (item: T) => Promise<R>): Promise<R[]> {
+  const results = new Array<R>(items.length);
+  let next = 0;
+  const lanes = Array.from({ length: Math.min(limit, items.length) }, async () => {
+    while (next < items.length) {
+      const index = next;
+      next += 1;
+      results[index] = await worker(items[index]);
+    }
+  });
+  await Promise.all(lanes);
+  return results;
+}
+
+/**
+ * Expands several scenes at once. Each scene reports its own status, so one failed expansion leaves the
+ * others saved. With `stream: true` the deltas are SSE events tagged with the scene id (`chatId`), each
+ * scene ends with a `scene_result` event and the final `done` event carries every result; the whole batch
+ * is cancelled through `/streams/:batchId/abort` with the id from the first `batch` event.
+ */
+router.post("/scenes/batch-expand", async (req, res) => {
+  const raw = req.body?.sceneIds;
+  if (!Array.isArray(raw) || raw.length === 0 || !raw.every((id) => typeof id === "string" && id.trim())) {
+    res.status(400).json({ error: "sceneIds must be a non-empty array of scene ids" });
+    return;
+  }
+  const sceneIds = [...new Set(raw.map((id: string) => id.trim()))];
+  if (sceneIds.length > MAX_BATCH_EXPAND_SCENES) {
+    res.status(400).json({ error: `At most ${MAX_BATCH_EXPAND_SCENES} scenes can be expanded at once` });
+    return;
+  }
+  const providerBlock = getWriterProviderBlock();
+  if (providerBlock) {
+    res.status(400).json({ error: providerBlock });
+    return;
+  }
+  const stream = req.body?.stream === true;
+  const batchId = newId();
+  let abortController: AbortController | null = null;
+  if (stream) {
+    res.writeHead(200, {
+      "Content-Type": "text/event-stream",
+      "Cache-Control": "no-cache",
+      Connection: "keep-alive",
+      "X-Accel-Buffering": "no"
+    });
+    res.flushHeaders?.();
+    abortController = openWriterStream(batchId, res);
+    res.write(`data: ${JSON.stringify({ type: "batch", batchId, sceneIds })}\n\n`);
+  }
+
+  const expandOne = async (sceneId: string): Promise<BatchExpandResult> => {
+    const row = getSceneRow(sceneId);
+    if (!row) return { sceneId, status: "not_found", error: "Scene not found" };
+    if (abortController?.signal.aborted) return { sceneId, status: "interrupted" };
+    try {
+      const { settings, systemPrompt, userPrompt, sampler } = await buildSceneExpandRequest(row);
+      const expanded = abortController
+        ? await streamWriterLlm({ systemPrompt, userPrompt, sampler, streamId: sceneId, res, signal: abortController.signal })
+        : await callWriterLlm(systemPrompt, userPrompt, sampler);
+      if (abortController?.signal.aborted) return { sceneId, status: "interrupted" };
+      if (isWriterLlmFailure(expanded)) return { sceneId, status: "error", error: expanded };
+      recordSceneRevision(row, "expand", settings.writerSceneRevisionLimit);
+      updateSceneContent(sceneId, expanded);
+      return { sceneId, status: "ok", scene: toSceneJson({ ...row, content: expanded }) };
+    } catch (err) {
+      if (err instanceof Error && err.name === "AbortError") return { sceneId, status: "interrupted" };
+      return { sceneId, status: "error", error: err instanceof Error ? err.message : "Network error" };
+    }
+  };
+
+  const results = await mapWithConcurrency(sceneIds, BATCH_EXPAND_CONCURRENCY, async (sceneId) => {
+    const result = await expandOne(sceneId);
+    if (stream && !res.writableEnded) res.write(`data: ${JSON.stringify({ type: "scene_result", ...result })}\n\n`);
+    return result;
   });
+
+  if (!stream) {
+    res.json({ results });
+    return;
+  }
+  if (!res.writableEnded) {
+    const interrupted = abortController?.signal.aborted === true;
+    res.write(`data: ${JSON.stringify({ type: "done", chatId: batchId, results, ...(interrupted ? { interrupted: true } : {}) })}\n\n`);
+    res.end();
+  }
 });
 
 router.post("/scenes/:id/rewrite", async (req, res) => {
@@ -949,7 +1331,9 @@ router.post("/scenes/:id/rewrite", async (req, res) => {
     (settings.promptTemplates.writerRewrite || "").replace("{{tone}}", mergedToneSettings.tone),
     buildChapterDirective(mergedToneSettings),
     buildCharacterContext(parseJsonIdArray(project?.character_ids)),
-    buildProjectNotesDirective(projectNotes)
+    buildSceneCharacterDirective(row.character_id),
+    buildProjectNotesDirective(projectNotes),
+    buildResponseLanguageDirective(settings.responseLanguage)
   ].filter(Boolean).join("\n\n");
   const sampler = createWriterSampler(settings.samplerConfig, mergedToneSettings);
   const rewritten = await callWriterLlm(
@@ -958,20 +1342,33 @@ router.post("/scenes/:id/rewrite", async (req, res) => {
     sampler
   );
 
+  recordSceneRevision(row, "rewrite", settings.writerSceneRevisionLimit);
   updateSceneContent(sceneId, rewritten);
 
-  res.json({
-    id: row.id, chapterId: row.chapter_id, title: row.title, content: rewritten,
-    goals: row.goals, conflicts: row.conflicts, outcomes: row.outcomes, createdAt: row.created_at
-  });
+  res.json(toSceneJson({ ...row, content: rewritten }));
 });
 
 router.get("/scenes/:id/summarize", async (req, res) => {
-  const row = getSceneSummaryRow(req.params.id);
+  const sceneId = req.params.id;
+  const row = getSceneSummaryRow(sceneId);
 
   if (!row) { res.status(404).json({ error: "Scene not found" }); return; }
 
   const settings = getWriterSettings();
+  if (!settings.activeProviderId || !settings.activeModel) {
+    res.json(fallbackSceneSummary(row.content));
+    return;
+  }
+  const requestedSentences = Math.floor(Number(req.query.sentences));
+  const sentences = requestedSentences >= 1 ? Math.min(requestedSentences, 10) : null;
+  // Summaries are reused until the scene text or the requested length changes.
+  const contentHash = hashWriterContent(`${sentences ?? "default"}\n${row.content}`);
+  const stored = getStoredSceneSummary(sceneId);
+  if (req.query.force !== "1" && stored && stored.content_hash === contentHash && stored.summary.trim()) {
+    res.json(stored.summary);
+    return;
+  }
+
   const chapter = getChapterGenerationRow(row.chapter_id);
   const project = chapter ? getProjectGenerationRow(chapter.project_id) : undefined;
   const chapterSettings = parseChapterSettings(chapter?.settings_json);
@@ -986,16 +1383,23 @@ router.get("/scenes/:id/summarize", async (req, res) => {
     : "";
   const systemPrompt = [
     settings.promptTemplates.writerSummarize,
+    sentences ? `Summarize in exactly ${sentences} sentence${sentences === 1 ? "" : "s"}.` : "",
     buildChapterDirective(chapterSettings),
     buildCharacterContext(parseJsonIdArray(project?.character_ids)),
-    buildProjectNotesDirective(projectNotes)
+    buildProjectNotesDirective(projectNotes),
+    buildResponseLanguageDirective(settings.responseLanguage)
   ].filter(Boolean).join("\n\n");
-  const summary = await callWriterLlm(
+  const summary = (await callWriterLlm(
     systemPrompt,
     [projectContext ? `[Context Pack]\n${projectContext}` : "", writerRagDirective, row.content].filter(Boolean).join("\n\n"),
     createWriterSampler(settings.samplerConfig, chapterSettings)
-  );
+  )).trim();
 
+  if (!summary || isWriterLlmFailure(summary)) {
+    res.json(fallbackSceneSummary(row.content));
+    return;
+  }
+  upsertSceneSummary({ sceneId, contentHash, summary, updatedAt: now() });
   res.json(summary);
 });
 
@@ -1012,6 +1416,9 @@ router.patch("/scenes/:id", (req, res) => {
   const newConflicts = conflicts ?? row.conflicts;
   const newOutcomes = outcomes ?? row.outcomes;
 
+  if (newContent !== row.content || newTitle !== row.title) {
+    recordSceneRevision(row, "edit", getWriterSettings().writerSceneRevisionLimit);
+  }
   updateSceneRecord(sceneId, {
     content: newContent,
     title: newTitle,
@@ -1023,6 +1430,51 @@ router.patch("/scenes/:id", (req, res) => {
   res.json({ ...toSceneJson(row), title: newTitle, content: newContent, goals: newGoals, conflicts: newConflicts, outcomes: newOutcomes });
 });
 
+// Attaches (or with null detaches) the character a scene is narrated by; generation and the consistency check use it.
+router.put("/scenes/:id/character", (req, res) => {
+  const sceneId = req.params.id;
+  const row = getSceneRow(sceneId);
+  if (!row) { res.status(404).json({ error: "Scene not found" }); return; }
+  const raw = req.body?.characterId;
+  if (raw !== null && typeof raw !== "string") { res.status(400).json({ error: "characterId must be a string or null" }); return; }
+  const characterId = typeof raw === "string" && raw.trim() ? raw.trim() : null;
+  if (characterId && !db.prepare("SELECT 1 FROM characters WHERE id = ?").get(characterId)) {
+    res.status(404).json({ error: "Character not found" });
+    return;
+  }
+  updateSceneCharacter(sceneId, characterId);
+  res.json(toSceneJson({ ...row, character_id: characterId }));
+});
+
+router.get("/scenes/:id/revisions", (req, res) => {
+  if (!getSceneRow(req.params.id)) { res.status(404).json({ error: "Scene not found" }); return; }
+  res.json(listSceneRevisionRows(req.params.id).map(toSceneRevisionJson));
+});
+
+router.post("/scenes/:id/revisions/:revisionId/restore", (req, res) => {
+  const row = getSceneRow(req.params.id);
+  if (!row) { res.status(404).json({ error: "Scene not found" }); return; }
+  const revision = getSceneRevisionRow(row.id, req.params.revisionId);
+  if (!revision) { res.status(404).json({ error: "Revision not found" }); return; }
+
+  restoreSceneRevision(row, revision, getWriterSettings().writerSceneRevisionLimit);
+  res.json({ ...toSceneJson(row), title: revision.title, content: revision.content });
+});
+
+router.post("/scenes/reorder", (req, res) => {
+  const { chapterId, orderedIds } = req.body as { chapterId: string; orderedIds: string[] };
+  if (!getChapterRow(chapterId)) {
+    res.status(404).json({ error: "Chapter not found" });
+    return;
+  }
+  if (!Array.isArray(orderedIds)) {
+    res.status(400).json({ error: "orderedIds must be an array of scene ids" });
+    return;
+  }
+  reorderChapterScenes(chapterId, orderedIds.map(String));
+  res.json({ ok: true });
+});
+
 router.delete("/scenes/:id", (req, res) => {
   const sceneId = req.params.id;
   const row = getSceneProjectRow(sceneId);
@@ -1039,7 +1491,8 @@ router.delete("/scenes/:id", (req, res) => {
 router.post("/projects/:id/consistency", (req, res) => {
   const projectId = req.params.id;
   const scenes = listConsistencyScenes(projectId);
-  const issues = runConsistency(projectId, scenes);
+  const project = getProjectRow(projectId);
+  const issues = runConsistency(projectId, scenes, project ? buildConsistencyRoster(project) : []);
   recordConsistencyReport({
     id: newId(),
     projectId,
@@ -1050,6 +1503,108 @@ router.post("/projects/:id/consistency", (req, res) => {
   res.json(issues);
 });
 
+// Same check as above, streamed as NDJSON: one `progress` line per scene, then the saved `report`. Yields to
+// the event loop between scenes so a long project does not stall other requests.
+router.post("/projects/:id/consistency/stream", async (req, res) => {
+  const projectId = req.params.id;
+  const project = getProjectRow(projectId);
+  if (!project) {
+    res.status(404).json({ error: "Project not found" });
+    return;
+  }
+  const scenes = listConsistencyScenes(projectId);
+  let cancelled = false;
+  res.once("close", () => { cancelled = true; });
+  res.status(200);
+  res.setHeader("Content-Type", "application/x-ndjson; charset=utf-8");
+  res.setHeader("Cache-Control", "no-store");
+  res.setHeader("X-Accel-Buffering", "no");
+  res.flushHeaders();
+
+  const issues: ConsistencyIssue[] = [];
+  for (let index = 0; index < scenes.length; index += 1) {
+    await new Promise((resolve) => setImmediate(resolve));
+    if (cancelled || res.destroyed) return;
+    issues.push(...checkSceneConsistency(projectId, scenes[index]));
+    res.write(`${JSON.stringify({
+      type: "progress",
+      sceneId: scenes[index].id,
+      checked: index + 1,
+      total: scenes.length,
+      issuesFound: issues.length
+    })}\n`);
+  }
+  issues.push(...checkNameConsistency(projectId, scenes, buildConsistencyRoster(project)));
+  recordConsistencyReport({
+    id: newId(),
+    projectId,
+    payload: JSON.stringify(issues),
+    createdAt: now()
+  });
+  if (!res.destroyed) res.end(`${JSON.stringify({ type: "report", issues })}\n`);
+});
+
+function parseReportIssues(payload: string): ConsistencyIssue[] {
+  try {
+    const parsed = JSON.parse(payload) as unknown;
+    return Array.isArray(parsed) ? parsed as ConsistencyIssue[] : [];
+  } catch {
+    return [];
+  }
+}
+
+/** Comma-separated query values; returns null when any value is not in `allowed`. */
+function readQueryFilter(raw: unknown, allowed: readonly string[]): string[] | null {
+  const values = String(typeof raw === "string" ? raw : "")
+    .split(",")
+    .map((value) => value.trim().toLowerCase())
+    .filter(Boolean);
+  return values.every((value) => allowed.includes(value)) ? values : null;
+}
+
+router.get("/projects/:id/consistency/reports", (req, res) => {
+  const projectId = req.params.id;
+  if (!projectExists(projectId)) {
+    res.status(404).json({ error: "Project not found" });
+    return;
+  }
+  res.json(listConsistencyReportRows(projectId).map((row) => {
+    const issues = parseReportIssues(row.payload);
+    return {
+      id: row.id,
+      projectId: row.project_id,
+      createdAt: row.created_at,
+      issueCount: issues.length,
+      severityCounts: Object.fromEntries(
+        CONSISTENCY_SEVERITIES.map((severity) => [severity, issues.filter((issue) => issue.severity === severity).length])
+      )
+    };
+  }));
+});
+
+// `?severity=high&category=facts,names` narrows a stored report without re-running the scan.
+router.get("/consistency/reports/:id", (req, res) => {
+  const row = getConsistencyReportRow(req.params.id);
+  if (!row) {
+    res.status(404).json({ error: "Consistency report not found" });
+    return;
+  }
+  const severities = readQueryFilter(req.query.severity, CONSISTENCY_SEVERITIES);
+  const categories = readQueryFilter(req.query.category, CONSISTENCY_CATEGORIES);
+  if (!severities || !categories) {
+    res.status(400).json({
+      error: `severity must be one of ${CONSISTENCY_SEVERITIES.join(", ")}; category must be one of ${CONSISTENCY_CATEGORIES.join(", ")}`
+    });
+    return;
+  }
+  res.json({
+    id: row.id,
+    projectId: row.project_id,
+    createdAt: row.created_at,
+    issues: filterConsistencyIssues(parseReportIssues(row.payload), { severities, categories })
+  });
+});
+
 // --- Export ---
 
 router.post("/projects/:id/export/markdown", (req, res) => {
@@ -1091,6 +1646,25 @@ router.post("/projects/:id/export/docx", async (req, res) => {
   res.json(outputPath);
 });
 
+router.post("/projects/:id/export/html", (req, res) => {
+  const projectId = req.params.id;
+  const bundle = buildWriterExportBundle(projectId);
+  if (!bundle) { res.status(404).json({ error: "Project not found" }); return; }
+
+  const outputPath = join(DATA_DIR, `${bundle.filenameBase}.html`);
+  writeFileSync(outputPath, buildHtmlFromBundle(bundle));
+
+  recordWriterExport({
+    id: newId(),
+    projectId,
+    exportType: "html",
+    outputPath,
+    createdAt: now()
+  });
+
+  res.json(outputPath);
+});
+
 router.post("/projects/:id/export/markdown/download", (req, res) => {
   const projectId = req.params.id;
   const bundle = buildWriterExportBundle(projectId);
@@ -1130,4 +1704,23 @@ router.post("/projects/:id/export/docx/download", async (req, res) => {
   res.send(buffer);
 });
 
+router.post("/projects/:id/export/html/download", (req, res) => {
+  const projectId = req.params.id;
+  const bundle = buildWriterExportBundle(projectId);
+  if (!bundle) { res.status(404).json({ error: "Project not found" }); return; }
+  const filename = `${bundle.filenameBase}.html`;
+
+  recordWriterExport({
+    id: newId(),
+    projectId,
+    exportType: "html",
+    outputPath: filename,
+    createdAt: now()
+  });
+
+  res.setHeader("Content-Type", "text/html; charset=utf-8");
+  res.setHeader("Content-Disposition", buildAttachmentDisposition(filename, `book-${projectId}.html`));
+  res.send(buildHtmlFromBundle(bundle));
+});
+
 export default router;
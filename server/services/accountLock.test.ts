@@ -0,0 +1,24 @@
+import { afterEach, describe, expect, it } from "vitest";
+import { isAccountLocked, lockAccount, markAccountUnlocked, normalizeAutoLockMinutes, touchAccountActivity } from "./accountLock.js";
+
+describe("account auto-lock", () => {
+  afterEach(() => lockAccount());
+
+  it("starts locked and locks again after the idle window", () => {
+    expect(isAccountLocked(15, 0)).toBe(true);
+    markAccountUnlocked(0);
+    expect(isAccountLocked(15, 10 * 60_000)).toBe(false);
+    touchAccountActivity(15, 10 * 60_000);
+    expect(isAccountLocked(15, 24 * 60_000)).toBe(false);
+    expect(isAccountLocked(15, 26 * 60_000)).toBe(true);
+    touchAccountActivity(15, 27 * 60_000);
+    expect(isAccountLocked(15, 27 * 60_000)).toBe(true);
+  });
+
+  it("treats zero as never locking on idle and clamps bad values", () => {
+    markAccountUnlocked(0);
+    expect(isAccountLocked(0, 7 * 24 * 60 * 60_000)).toBe(false);
+    expect(normalizeAutoLockMinutes("abc")).toBe(15);
+    expect(normalizeAutoLockMinutes(99999)).toBe(24 * 60);
+  });
+});
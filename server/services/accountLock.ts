@@ -0,0 +1,35 @@
+// In-memory unlock state for the local account. It never survives a restart, so the app starts locked.
+export const MAX_AUTO_LOCK_MINUTES = 24 * 60;
+
+let unlockedUntilIdle: { lastActivityAt: number } | null = null;
+
+export function normalizeAutoLockMinutes(value: unknown): number {
+  const minutes = Math.floor(Number(value));
+  if (!Number.isFinite(minutes) || minutes < 0) return 15;
+  return Math.min(MAX_AUTO_LOCK_MINUTES, minutes);
+}
+
+export function markAccountUnlocked(nowMs = Date.now()) {
+  unlockedUntilIdle = { lastActivityAt: nowMs };
+}
+
+export function lockAccount() {
+  unlockedUntilIdle = null;
+}
+
+/** Locks lazily: an expired idle window is cleared the first time anyone asks. */
+export function isAccountLocked(autoLockMinutes: unknown, nowMs = Date.now()): boolean {
+  if (!unlockedUntilIdle) return true;
+  const minutes = normalizeAutoLockMinutes(autoLockMinutes);
+  if (minutes > 0 && nowMs - unlockedUntilIdle.lastActivityAt > minutes * 60_000) {
+    lockAccount();
+    return true;
+  }
+  return false;
+}
+
+export function touchAccountActivity(autoLockMinutes: unknown, nowMs = Date.now()) {
+  if (!isAccountLocked(autoLockMinutes, nowMs) && unlockedUntilIdle) {
+    unlockedUntilIdle.lastActivityAt = nowMs;
+  }
+}
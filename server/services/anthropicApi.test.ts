@@ -0,0 +1,58 @@
+import { describe, expect, it } from "vitest";
+import {
+  buildAnthropicHeaders,
+  buildAnthropicSampling,
+  extractAnthropicStreamDelta,
+  normalizeAnthropicBaseUrl,
+  toAnthropicMessages
+} from "./anthropicApi.js";
+
+describe("anthropicApi", () => {
+  it("normalizes base urls to the versioned api root", () => {
+    expect(normalizeAnthropicBaseUrl("https://api.anthropic.com")).toBe("https://api.anthropic.com/v1");
+    expect(normalizeAnthropicBaseUrl("https://api.anthropic.com/v1/messages")).toBe("https://api.anthropic.com/v1");
+    expect(normalizeAnthropicBaseUrl("")).toBe("https://api.anthropic.com/v1");
+  });
+
+  it("sends the api key and version headers", () => {
+    const headers = buildAnthropicHeaders({ base_url: "https://api.anthropic.com/v1", api_key_cipher: "sk-ant-test" });
+    expect(headers["x-api-key"]).toBe("sk-ant-test");
+    expect(headers["anthropic-version"]).toBe("2023-06-01");
+    expect(headers).not.toHaveProperty("Authorization");
+  });
+
+  it("lifts system messages and merges consecutive turns", () => {
+    const converted = toAnthropicMessages([
+      { role: "system", content: "You are Mira." },
+      { role: "assistant", content: "Hello there." },
+      { role: "user", content: "Hi" },
+      { role: "user", content: [{ type: "image_url", image_url: { url: "data:image/png;base64,AAAA" } }] }
+    ]);
+    expect(converted.system).toBe("You are Mira.");
+    expect(converted.messages.map((message) => message.role)).toEqual(["user", "assistant", "user"]);
+    expect(converted.messages[2].content).toEqual([
+      { type: "text", text: "Hi" },
+      { type: "image", source: { type: "base64", media_type: "image/png", data: "AAAA" } }
+    ]);
+  });
+
+  it("always sends max_tokens and never both temperature and top_p", () => {
+    const sampling = buildAnthropicSampling({ temperature: 1.4, topP: 0.9, maxTokens: 300 });
+    expect(sampling.max_tokens).toBe(300);
+    expect(sampling.temperature).toBe(1);
+    expect(sampling).not.toHaveProperty("top_p");
+  });
+
+  it("reads text, thinking, stop and error stream events", () => {
+    expect(extractAnthropicStreamDelta("content_block_delta", {
+      type: "content_block_delta",
+      delta: { type: "text_delta", text: "Hi" }
+    }).text).toBe("Hi");
+    expect(extractAnthropicStreamDelta("content_block_delta", {
+      type: "content_block_delta",
+      delta: { type: "thinking_delta", thinking: "hmm" }
+    }).thinking).toBe("hmm");
+    expect(extractAnthropicStreamDelta("message_stop", { type: "message_stop" }).done).toBe(true);
+    expect(extractAnthropicStreamDelta("error", { type: "error", error: { message: "overloaded" } }).error).toBe("overloaded");
+  });
+});
@@ -0,0 +1,223 @@
+import { buildOpenAiSamplingPayload, normalizeApiParamPolicy } from "./apiParamPolicy.js";
+import { fetchProviderResponse } from "./providerHttp.js";
+import { modelFetchErrorFromResponse, parseModelIds, providerRateLimitOptions, readProviderErrorDetail, withProviderHeaders, type ProviderLike } from "./providerApi.js";
+
+export const ANTHROPIC_API_VERSION = "2023-06-01";
+
+type AnthropicContentBlock =
+  | { type: "text"; text: string }
+  | { type: "image"; source: { type: "base64"; media_type: string; data: string } };
+
+export interface AnthropicMessage {
+  role: "user" | "assistant";
+  content: AnthropicContentBlock[];
+}
+
+export interface AnthropicStreamDelta {
+  text: string;
+  thinking: string;
+  done: boolean;
+  error: string;
+}
+
+function normalizeUrl(url: string): string {
+  return String(url || "").trim().replace(/\/+$/, "");
+}
+
+export function normalizeAnthropicBaseUrl(baseUrl: string): string {
+  let base = normalizeUrl(baseUrl);
+  if (base.endsWith("/messages")) base = base.slice(0, -9);
+  if (!base) return "https://api.anthropic.com/v1";
+  return /\/v1$/i.test(base) ? base : `${base}/v1`;
+}
+
+export function buildAnthropicHeaders(provider: ProviderLike): Record<string, string> {
+  return withProviderHeaders(provider, {
+    "Content-Type": "application/json",
+    "x-api-key": String(provider.api_key_cipher || ""),
+    "anthropic-version": ANTHROPIC_API_VERSION
+  });
+}
+
+function toContentBlocks(content: unknown): AnthropicContentBlock[] {
+  if (!Array.isArray(content)) {
+    const text = String(content ?? "");
+    return text.trim() ? [{ type: "text", text }] : [];
+  }
+  const blocks: AnthropicContentBlock[] = [];
+  for (const part of content as Array<Record<string, unknown>>) {
+    if (!part || typeof part !== "object") continue;
+    if (part.type === "text") {
+      const text = String(part.text || "");
+      if (text.trim()) blocks.push({ type: "text", text });
+    } else if (part.type === "image_url") {
+      const imageUrl = part.image_url && typeof part.image_url === "object"
+        ? String((part.image_url as { url?: unknown }).url || "")
+        : String(part.image_url || "");
+      const match = /^data:(image\/[a-z0-9.+-]+);base64,(.*)$/i.exec(imageUrl);
+      if (match) blocks.push({ type: "image", source: { type: "base64", media_type: match[1], data: match[2] } });
+    }
+  }
+  return blocks;
+}
+
+/**
+ * Anthropic takes the system prompt as a top-level string and expects
+ * alternating user/assistant turns that open with a user turn, so system
+ * messages are lifted out and consecutive same-role turns are merged.
+ */
+export function toAnthropicMessages(messages: Array<{ role: string; content: unknown }>): {
+  system: string;
+  messages: AnthropicMessage[];
+} {
+  const systemParts: string[] = [];
+  const out: AnthropicMessage[] = [];
+  for (const message of messages) {
+    if (message.role === "system") {
+      const text = toContentBlocks(message.content)
+        .map((block) => (block.type === "text" ? block.text : ""))
+        .join("\n")
+        .trim();
+      if (text) systemParts.push(text);
+      continue;
+    }
+    const blocks = toContentBlocks(message.content);
+    if (blocks.length === 0) continue;
+    const role = message.role === "assistant" ? "assistant" : "user";
+    const last = out[out.length - 1];
+    if (last && last.role === role) {
+      last.content.push(...blocks);
+    } else {
+      out.push({ role, content: blocks });
+    }
+  }
+  if (out.length === 0 || out[0].role !== "user") {
+    out.unshift({ role: "user", content: [{ type: "text", text: "[Start]" }] });
+  }
+  return { system: systemParts.join("\n\n"), messages: out };
+}
+
+/**
+ * Maps the OpenAI sampling policy onto Messages API fields. `max_tokens` is
+ * required by Anthropic, and recent Claude models reject requests that set both
+ * temperature and top_p, so top_p is only sent when temperature is not.
+ */
+export function buildAnthropicSampling(
+  samplerConfig: Record<string, unknown>,
+  apiParamPolicy?: unknown,
+  defaultMaxTokens = 2048
+): Record<string, unknown> {
+  const openAi = buildOpenAiSamplingPayload({
+    samplerConfig,
+    apiParamPolicy,
+    fields: ["temperature", "topP", "maxTokens", "stop"]
+  });
+  const out: Record<string, unknown> = {
+    max_tokens: Math.max(1, Math.floor(Number(openAi.max_tokens) || defaultMaxTokens))
+  };
+  if (openAi.temperature !== undefined) {
+    out.temperature = Math.max(0, Math.min(1, Number(openAi.temperature)));
+  } else if (openAi.top_p !== undefined) {
+    out.top_p = openAi.top_p;
+  }
+  if (Array.isArray(openAi.stop) && openAi.stop.length > 0) out.stop_sequences = openAi.stop;
+  const topK = Number(samplerConfig.topK);
+  const hasTopK = samplerConfig.topK !== undefined && samplerConfig.topK !== null && Number.isFinite(topK) && topK > 0;
+  if (hasTopK && normalizeApiParamPolicy(apiParamPolicy).openai.sendSampler) {
+    out.top_k = Math.floor(topK);
+  }
+  return out;
+}
+
+export function buildAnthropicMessagesBody(params: {
+  modelId: string;
+  messages: Array<{ role: string; content: unknown }>;
+  samplerConfig?: Record<string, unknown>;
+  apiParamPolicy?: unknown;
+  stream: boolean;
+}): Record<string, unknown> {
+  const converted = toAnthropicMessages(params.messages);
+  return {
+    model: params.modelId,
+    ...(converted.system ? { system: converted.system } : {}),
+    messages: converted.messages,
+    stream: params.stream,
+    ...buildAnthropicSampling(params.samplerConfig || {}, params.apiParamPolicy)
+  };
+}
+
+export async function requestAnthropicMessages(
+  provider: ProviderLike,
+  body: Record<string, unknown>,
+  signal?: AbortSignal
+) {
+  const base = normalizeAnthropicBaseUrl(provider.base_url);
+  return fetchProviderResponse(`${base}/messages`, {
+    method: "POST",
+    headers: buildAnthropicHeaders(provider),
+    body: JSON.stringify(body),
+    signal
+  }, providerRateLimitOptions(provider));
+}
+
+/** Reads one Messages API stream event (`content_block_delta`, `message_stop`, `error`). */
+export function extractAnthropicStreamDelta(eventType: string, raw: unknown): AnthropicStreamDelta {
+  const empty = { text: "", thinking: "", done: false, error: "" };
+  if (!raw || typeof raw !== "object") return empty;
+  const row = raw as {
+    type?: unknown;
+    delta?: { type?: unknown; text?: unknown; thinking?: unknown };
+    error?: { message?: unknown };
+  };
+  const type = String(row.type || eventType || "");
+  if (type === "error") {
+    return { ...empty, error: String(row.error?.message || "Anthropic stream returned an error event") };
+  }
+  if (type === "message_stop") return { ...empty, done: true };
+  if (type !== "content_block_delta" || !row.delta) return empty;
+  return {
+    ...empty,
+    text: row.delta.type === "text_delta" && typeof row.delta.text === "string" ? row.delta.text : "",
+    thinking: row.delta.type === "thinking_delta" && typeof row.delta.thinking === "string" ? row.delta.thinking : ""
+  };
+}
+
+export async function completeAnthropicMessages(params: {
+  provider: ProviderLike;
+  modelId: string;
+  messages: Array<{ role: string; content: unknown }>;
+  samplerConfig?: Record<string, unknown>;
+  apiParamPolicy?: unknown;
+  signal?: AbortSignal;
+}): Promise<{ content: string; reasoning: string }> {
+  const response = await requestAnthropicMessages(params.provider, buildAnthropicMessagesBody({
+    modelId: params.modelId,
+    messages: params.messages,
+    samplerConfig: params.samplerConfig,
+    apiParamPolicy: params.apiParamPolicy,
+    stream: false
+  }), params.signal);
+  if (!response.ok) {
+    const detail = await readProviderErrorDetail(response);
+    throw new Error(`[Anthropic API Error: ${response.status}] ${detail}`);
+  }
+  const body = await response.json().catch(() => ({})) as {
+    content?: Array<{ type?: unknown; text?: unknown; thinking?: unknown }>;
+  };
+  const blocks = Array.isArray(body.content) ? body.content : [];
+  return {
+    content: blocks.map((block) => (block?.type === "text" ? String(block.text || "") : "")).join(""),
+    reasoning: blocks.map((block) => (block?.type === "thinking" ? String(block.thinking || "") : "")).join("")
+  };
+}
+
+export async function fetchAnthropicModels(provider: ProviderLike, signal?: AbortSignal): Promise<string[]> {
+  const base = normalizeAnthropicBaseUrl(provider.base_url);
+  const response = await fetchProviderResponse(`${base}/models?limit=1000`, {
+    method: "GET",
+    headers: buildAnthropicHeaders(provider),
+    signal
+  });
+  if (!response.ok) throw await modelFetchErrorFromResponse(response, `${base}/models`);
+  return parseModelIds(await response.json().catch(() => ({})));
+}
@@ -8,6 +8,8 @@ interface OpenAiApiParamPolicy {
   presencePenalty: boolean;
   maxTokens: boolean;
   stop: boolean;
+  /** Sends `stream_options.include_usage`; strict OpenAI-compatible servers reject the unknown field. */
+  streamUsage: boolean;
 }
 
 interface KoboldApiParamPolicy {
@@ -44,7 +46,8 @@ const DEFAULT_API_PARAM_POLICY: ApiParamPolicy = {
     frequencyPenalty: true,
     presencePenalty: true,
     maxTokens: true,
-    stop: true
+    stop: true,
+    streamUsage: true
   },
   kobold: {
     sendSampler: true,
@@ -83,9 +86,10 @@ function asNumber(raw: unknown, fallback: number): number {
   return Number.isFinite(value) ? value : fallback;
 }
 
+// Stop strings keep their surrounding whitespace; "\nUser:" and "User:" stop in different places.
 function asStop(raw: unknown): string[] {
   if (!Array.isArray(raw)) return [];
-  return raw.map((item) => String(item || "").trim()).filter(Boolean).slice(0, 32);
+  return raw.map((item) => String(item ?? "")).filter((item) => item.trim()).slice(0, 32);
 }
 
 function asPhraseBans(raw: unknown): string[] {
@@ -113,7 +117,8 @@ export function normalizeApiParamPolicy(raw: unknown): ApiParamPolicy {
       frequencyPenalty: asBoolean(openaiRaw.frequencyPenalty, DEFAULT_API_PARAM_POLICY.openai.frequencyPenalty),
       presencePenalty: asBoolean(openaiRaw.presencePenalty, DEFAULT_API_PARAM_POLICY.openai.presencePenalty),
       maxTokens: asBoolean(openaiRaw.maxTokens, DEFAULT_API_PARAM_POLICY.openai.maxTokens),
-      stop: asBoolean(openaiRaw.stop, DEFAULT_API_PARAM_POLICY.openai.stop)
+      stop: asBoolean(openaiRaw.stop, DEFAULT_API_PARAM_POLICY.openai.stop),
+      streamUsage: asBoolean(openaiRaw.streamUsage, DEFAULT_API_PARAM_POLICY.openai.streamUsage)
     },
     kobold: {
       sendSampler: asBoolean(koboldRaw.sendSampler, DEFAULT_API_PARAM_POLICY.kobold.sendSampler),
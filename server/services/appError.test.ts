@@ -0,0 +1,27 @@
+import { describe, expect, it } from "vitest";
+import { AppError, appErrorBody, networkError, notConfiguredError, toAppError } from "./appError.js";
+
+describe("toAppError", () => {
+  it("keeps the upstream status of provider rejections", () => {
+    const error = toAppError(new Error("[Gemini API Error: 429] Resource exhausted"));
+    expect(error).toMatchObject({ code: "provider", httpStatus: 502, providerStatus: 429 });
+    expect(appErrorBody(error)).toEqual({
+      error: "[Gemini API Error: 429] Resource exhausted",
+      code: "provider",
+      providerStatus: 429
+    });
+  });
+
+  it("passes app errors through and reports timeouts as network failures", () => {
+    const configured = notConfiguredError("No provider configured");
+    expect(toAppError(configured)).toBe(configured);
+    expect(toAppError(networkError("Provider request failed")).code).toBe("network");
+    expect(toAppError(new DOMException("The operation timed out", "TimeoutError"))).toMatchObject({ code: "network", httpStatus: 504 });
+  });
+
+  it("falls back to an internal error with a readable message", () => {
+    const error = toAppError("boom", "Title generation failed");
+    expect(error).toBeInstanceOf(AppError);
+    expect(appErrorBody(error)).toEqual({ error: "Title generation failed", code: "internal" });
+  });
+});
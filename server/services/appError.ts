@@ -0,0 +1,82 @@
+import type { Response } from "express";
+import type { AppErrorCode } from "../../src/shared/errors.js";
+
+/**
+ * An error the API reports with a `code` the UI can branch on. `httpStatus` is what this server answers;
+ * `providerStatus` is the upstream status when a provider rejected the call.
+ */
+export class AppError extends Error {
+  /** Extra structured fields sent alongside `error` and `code`, such as flagged moderation categories. */
+  readonly details: Record<string, unknown> | null;
+
+  constructor(
+    readonly code: AppErrorCode,
+    message: string,
+    readonly httpStatus: number,
+    readonly providerStatus: number | null = null,
+    options?: { cause?: unknown; details?: Record<string, unknown> }
+  ) {
+    super(message, options);
+    this.name = "AppError";
+    this.details = options?.details ?? null;
+  }
+}
+
+/** Also used for endpoints Full Local Mode refuses; some routes answer those with 403 rather than 400. */
+export function notConfiguredError(message: string, httpStatus = 400): AppError {
+  return new AppError("not_configured", message, httpStatus);
+}
+
+export function validationError(message: string, details?: Record<string, unknown>): AppError {
+  return new AppError("validation", message, 400, null, { details });
+}
+
+export function notFoundError(message: string): AppError {
+  return new AppError("not_found", message, 404);
+}
+
+/** The request is valid but the current state does not allow it, e.g. deleting the last branch. */
+export function conflictError(message: string): AppError {
+  return new AppError("conflict", message, 409);
+}
+
+export function forbiddenError(message: string): AppError {
+  return new AppError("forbidden", message, 403);
+}
+
+export function lockedError(message = "Account is locked"): AppError {
+  return new AppError("locked", message, 423);
+}
+
+export function networkError(message: string, cause?: unknown): AppError {
+  return new AppError("network", message, 502, null, { cause });
+}
+
+// Provider adapters throw "[API Error: 401] ..." or "[Gemini API Error: 429] ..." messages.
+const PROVIDER_STATUS_PATTERN = /^\[(?:[A-Za-z]+ )?API Error: (\d{3})\]/;
+
+/** Classifies anything thrown by a route or provider call; unknown failures become `internal`. */
+export function toAppError(error: unknown, fallbackMessage = "Request failed"): AppError {
+  if (error instanceof AppError) return error;
+  const message = error instanceof Error && error.message ? error.message : fallbackMessage;
+  const providerStatus = PROVIDER_STATUS_PATTERN.exec(message);
+  if (providerStatus) return new AppError("provider", message, 502, Number(providerStatus[1]), { cause: error });
+  if (error instanceof Error && (error.name === "AbortError" || error.name === "TimeoutError")) {
+    return new AppError("network", message, 504, null, { cause: error });
+  }
+  return new AppError("internal", message, 500, null, { cause: error });
+}
+
+export function appErrorBody(error: AppError): { error: string; code: AppErrorCode; providerStatus?: number } {
+  return {
+    ...(error.details ?? {}),
+    error: error.message,
+    code: error.code,
+    ...(error.providerStatus !== null ? { providerStatus: error.providerStatus } : {})
+  };
+}
+
+export function sendAppError(res: Response, error: unknown, fallbackMessage?: string): void {
+  const appError = toAppError(error, fallbackMessage);
+  res.status(appError.httpStatus).json(appErrorBody(appError));
+}
@@ -0,0 +1,46 @@
+import type Database from "better-sqlite3";
+import packageMetadata from "../../package.json";
+import type { AppStatus } from "../../src/shared/types/contracts.js";
+import { getSchemaVersion } from "../db/migrations.js";
+
+function countRows(db: Database.Database, table: string): number {
+  try {
+    const row = db.prepare(`SELECT COUNT(*) AS count FROM ${table}`).get() as { count: number } | undefined;
+    return Number(row?.count || 0);
+  } catch {
+    return 0;
+  }
+}
+
+function readStoredSettings(db: Database.Database): Record<string, unknown> {
+  try {
+    const row = db.prepare("SELECT payload FROM settings WHERE id = 1").get() as { payload: string } | undefined;
+    const parsed = row?.payload ? JSON.parse(row.payload) as unknown : {};
+    return parsed && typeof parsed === "object" ? parsed as Record<string, unknown> : {};
+  } catch {
+    return {};
+  }
+}
+
+/** Summary for bug reports. Providers are reported by count only, so no key or endpoint ever leaves here. */
+export function collectAppStatus(db: Database.Database): AppStatus {
+  const settings = readStoredSettings(db);
+  const activeProviderId = typeof settings.activeProviderId === "string" && settings.activeProviderId
+    ? settings.activeProviderId
+    : null;
+  const activeModel = typeof settings.activeModel === "string" && settings.activeModel
+    ? settings.activeModel
+    : null;
+  return {
+    appVersion: packageMetadata.version,
+    dbPath: db.name,
+    schemaVersion: getSchemaVersion(db),
+    chatCount: countRows(db, "chats"),
+    characterCount: countRows(db, "characters"),
+    projectCount: countRows(db, "writer_projects"),
+    providerCount: countRows(db, "providers"),
+    activeProviderId,
+    activeModel,
+    fullLocalMode: settings.fullLocalMode === true
+  };
+}
@@ -1,4 +1,5 @@
 import { getCustomEndpointAdapter, type CustomEndpointAdapter, type CustomEndpointAdapterEndpoint } from "./extensions.js";
+import { withProviderHeaders } from "./providerApi.js";
 
 type UnknownRecord = Record<string, unknown>;
 const CUSTOM_ADAPTER_TIMEOUT_MS = 15_000;
@@ -7,6 +8,7 @@ interface CustomProviderLike {
   base_url: string;
   api_key_cipher?: string | null;
   adapter_id?: string | null;
+  extra_headers?: string | null;
 }
 
 function normalizeBaseUrl(raw: string) {
@@ -89,7 +91,7 @@ function buildHeaders(provider: CustomProviderLike, adapter: CustomEndpointAdapt
       headers[header] = headerValue;
     }
   }
-  return headers;
+  return withProviderHeaders(provider, headers);
 }
 
 async function requestEndpoint(provider: CustomProviderLike, adapter: CustomEndpointAdapter, endpoint: CustomEndpointAdapterEndpoint, context: UnknownRecord, signal?: AbortSignal) {
@@ -0,0 +1,24 @@
+import { describe, expect, it } from "vitest";
+import { cosineSimilarity, decodeVector, embeddingModelKey, encodeVector, normalizeBaseUrl } from "./embeddings.js";
+
+describe("embeddings", () => {
+  it("round-trips vectors through the stored blob and keeps their norm", () => {
+    const encoded = encodeVector([3, 4]);
+    expect(encoded.norm).toBe(5);
+    expect(Array.from(decodeVector(encoded.blob))).toEqual([3, 4]);
+  });
+
+  it("scores cosine similarity and treats zero or mismatched vectors as unrelated", () => {
+    const a = encodeVector([1, 0]);
+    const b = encodeVector([1, 1]);
+    expect(cosineSimilarity(decodeVector(a.blob), decodeVector(b.blob), a.norm, b.norm)).toBeCloseTo(Math.SQRT1_2);
+    expect(cosineSimilarity(new Float32Array([0, 0]), decodeVector(a.blob), 0, a.norm)).toBe(0);
+    expect(cosineSimilarity(new Float32Array([1]), decodeVector(a.blob), 1, a.norm)).toBe(0);
+  });
+
+  it("strips endpoint suffixes from the provider base URL", () => {
+    expect(normalizeBaseUrl("http://localhost:1234/v1/embeddings/")).toBe("http://localhost:1234/v1");
+    expect(normalizeBaseUrl("https://api.example.com/v1/chat/completions")).toBe("https://api.example.com/v1");
+    expect(embeddingModelKey("p1", "nomic-embed")).toBe("p1:nomic-embed");
+  });
+});
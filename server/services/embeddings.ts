@@ -0,0 +1,142 @@
+import { db, isLocalhostUrl } from "../db.js";
+
+// OpenAI-compatible `/embeddings` client and the vector helpers shared by RAG chunks and chat memory.
+// Vectors are stored as Float32 BLOBs next to their L2 norm so cosine similarity needs one dot product.
+
+export interface EmbeddingProviderRow {
+  id: string;
+  base_url: string;
+  api_key_cipher: string;
+  full_local_only: number;
+  provider_type: string;
+}
+
+export function normalizeBaseUrl(raw: string): string {
+  return String(raw || "").trim()
+    .replace(/\/+$/, "")
+    .replace(/\/chat\/completions$/i, "")
+    .replace(/\/responses$/i, "")
+    .replace(/\/completions$/i, "")
+    .replace(/\/embeddings$/i, "")
+    .replace(/\/rerank$/i, "");
+}
+
+/** Vectors from different models are not comparable, so stored rows are keyed by provider and model. */
+export function embeddingModelKey(providerId: string, model: string): string {
+  return `${providerId}:${model}`;
+}
+
+export function encodeVector(vector: number[]): { blob: Buffer; norm: number } {
+  const arr = new Float32Array(vector.length);
+  let sum = 0;
+  for (let i = 0; i < vector.length; i += 1) {
+    const value = Number(vector[i]) || 0;
+    arr[i] = value;
+    sum += value * value;
+  }
+  return { blob: Buffer.from(arr.buffer), norm: Math.sqrt(sum) };
+}
+
+export function decodeVector(blob: Buffer): Float32Array {
+  const byteOffset = blob.byteOffset || 0;
+  const byteLength = blob.byteLength || 0;
+  return new Float32Array(blob.buffer.slice(byteOffset, byteOffset + byteLength));
+}
+
+export function cosineSimilarity(a: Float32Array, b: Float32Array, aNorm: number, bNorm: number): number {
+  if (!aNorm || !bNorm || a.length !== b.length) return 0;
+  let dot = 0;
+  for (let i = 0; i < a.length; i += 1) {
+    dot += a[i] * b[i];
+  }
+  return dot / (aNorm * bNorm);
+}
+
+function normalizeEmbeddingRow(input: unknown): number[] | null {
+  if (!Array.isArray(input)) return null;
+  const out: number[] = [];
+  for (const value of input) {
+    const num = Number(value);
+    if (!Number.isFinite(num)) return null;
+    out.push(num);
+  }
+  return out.length > 0 ? out : null;
+}
+
+export async function requestEmbeddings(provider: EmbeddingProviderRow, model: string, input: string[]): Promise<number[][]> {
+  if (!input.length) return [];
+  const baseUrl = normalizeBaseUrl(provider.base_url);
+  const apiKey = String(provider.api_key_cipher || "").trim();
+  const headers: Record<string, string> = {
+    "Content-Type": "application/json"
+  };
+  if (apiKey) {
+    headers.Authorization = `Bearer ${apiKey}`;
+  }
+  const response = await fetch(`${baseUrl}/embeddings`, {
+    method: "POST",
+    headers,
+    body: JSON.stringify({
+      model,
+      input
+    })
+  });
+  if (!response.ok) {
+    const errText = await response.text().catch(() => "embedding request failed");
+    throw new Error(errText.slice(0, 500));
+  }
+  const body = await response.json() as {
+    data?: Array<{ embedding?: unknown }>;
+    embeddings?: unknown;
+  };
+  const rows = Array.isArray(body.data)
+    ? body.data.map((item) => normalizeEmbeddingRow(item?.embedding))
+    : Array.isArray(body.embeddings)
+      ? body.embeddings.map((item) => normalizeEmbeddingRow(item))
+      : [];
+  const valid = rows.filter((row): row is number[] => Array.isArray(row) && row.length > 0);
+  if (valid.length !== input.length) {
+    throw new Error("embedding response mismatch");
+  }
+  return valid;
+}
+
+export async function embedTexts(provider: EmbeddingProviderRow, model: string, input: string[]): Promise<number[][]> {
+  const batchSize = 24;
+  const out: number[][] = [];
+  for (let i = 0; i < input.length; i += batchSize) {
+    const batch = input.slice(i, i + batchSize);
+    const embedded = await requestEmbeddings(provider, model, batch);
+    out.push(...embedded);
+  }
+  return out;
+}
+
+/** The RAG embedding model (`ragModel`) on the RAG provider or, failing that, the active provider. */
+export function resolveEmbeddingProvider(settings: Record<string, unknown>): { provider: EmbeddingProviderRow; model: string } | null {
+  const providerId = String(settings.ragProviderId || settings.activeProviderId || "").trim();
+  const model = String(settings.ragModel || "").trim();
+  if (!providerId || !model) return null;
+  const provider = db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as EmbeddingProviderRow | undefined;
+  if (!provider) return null;
+  const fullLocalMode = settings.fullLocalMode === true;
+  if (fullLocalMode && !isLocalhostUrl(provider.base_url)) return null;
+  if (provider.full_local_only && !isLocalhostUrl(provider.base_url)) return null;
+  return { provider, model };
+}
+
+/**
+ * Embeds `texts` with the configured embedding model. Returns null when no embedding model is set up, which
+ * callers treat as "semantic retrieval off" and fall back to keyword matching.
+ */
+export async function embed(
+  texts: string[],
+  settings: Record<string, unknown>
+): Promise<{ modelKey: string; vectors: number[][] } | null> {
+  const target = resolveEmbeddingProvider(settings);
+  if (!target) return null;
+  return {
+    modelKey: embeddingModelKey(target.provider.id, target.model),
+    vectors: await embedTexts(target.provider, target.model, texts)
+  };
+}
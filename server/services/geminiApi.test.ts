@@ -0,0 +1,70 @@
+import { describe, expect, it } from "vitest";
+import {
+  buildGeminiGenerateBody,
+  buildGeminiUrl,
+  extractGeminiStreamDelta,
+  normalizeGeminiBaseUrl,
+  parseGeminiModels,
+  toGeminiContents
+} from "./geminiApi.js";
+
+describe("geminiApi", () => {
+  it("normalizes base urls to the versioned api root", () => {
+    expect(normalizeGeminiBaseUrl("")).toBe("https://generativelanguage.googleapis.com/v1beta");
+    expect(normalizeGeminiBaseUrl("https://generativelanguage.googleapis.com")).toBe("https://generativelanguage.googleapis.com/v1beta");
+    expect(normalizeGeminiBaseUrl("https://generativelanguage.googleapis.com/v1beta/openai/")).toBe("https://generativelanguage.googleapis.com/v1beta");
+    expect(normalizeGeminiBaseUrl("https://proxy.example.com/v1/models/gemini-pro:generateContent")).toBe("https://proxy.example.com/v1");
+  });
+
+  it("puts the api key in the query string", () => {
+    const url = buildGeminiUrl({ base_url: "", api_key_cipher: "AIza-test" }, "models/gemini-2.0-flash:streamGenerateContent", { alt: "sse" });
+    expect(url).toBe("https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:streamGenerateContent?alt=sse&key=AIza-test");
+  });
+
+  it("lifts system messages, renames the assistant role and merges consecutive turns", () => {
+    const converted = toGeminiContents([
+      { role: "system", content: "You are Mira." },
+      { role: "assistant", content: "Hello there." },
+      { role: "user", content: "Hi" },
+      { role: "user", content: [{ type: "image_url", image_url: { url: "data:image/png;base64,AAAA" } }] }
+    ]);
+    expect(converted.systemInstruction).toBe("You are Mira.");
+    expect(converted.contents.map((content) => content.role)).toEqual(["user", "model", "user"]);
+    expect(converted.contents[2].parts).toEqual([
+      { text: "Hi" },
+      { inline_data: { mime_type: "image/png", data: "AAAA" } }
+    ]);
+  });
+
+  it("maps sampler settings onto generationConfig", () => {
+    const body = buildGeminiGenerateBody({
+      messages: [{ role: "user", content: "Hi" }],
+      samplerConfig: { temperature: 0.8, topP: 0.9, maxTokens: 256, stop: ["a", "b", "c", "d", "e", "f"] }
+    });
+    expect(body).not.toHaveProperty("systemInstruction");
+    expect(body.generationConfig).toMatchObject({
+      temperature: 0.8,
+      topP: 0.9,
+      maxOutputTokens: 256,
+      stopSequences: ["a", "b", "c", "d", "e"]
+    });
+  });
+
+  it("reads text, thought parts and finish reasons from stream chunks", () => {
+    expect(extractGeminiStreamDelta({
+      candidates: [{ content: { parts: [{ text: "Plan.", thought: true }, { text: "Hello" }] } }]
+    })).toEqual({ text: "Hello", thinking: "Plan.", done: false, error: "" });
+    expect(extractGeminiStreamDelta({ candidates: [{ content: { parts: [{ text: "!" }] }, finishReason: "STOP" }] }).done).toBe(true);
+    expect(extractGeminiStreamDelta({ promptFeedback: { blockReason: "SAFETY" } }).error).toContain("blocked the prompt");
+    expect(extractGeminiStreamDelta({ candidates: [{ finishReason: "SAFETY" }] }).error).toContain("SAFETY");
+  });
+
+  it("lists generateContent models without the models/ prefix", () => {
+    expect(parseGeminiModels({
+      models: [
+        { name: "models/gemini-2.0-flash", inputTokenLimit: 1048576, supportedGenerationMethods: ["generateContent", "countTokens"] },
+        { name: "models/text-embedding-004", supportedGenerationMethods: ["embedContent"] }
+      ]
+    })).toEqual([{ id: "gemini-2.0-flash", contextLength: 1048576 }]);
+  });
+});
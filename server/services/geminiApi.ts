@@ -0,0 +1,257 @@
+import { buildOpenAiSamplingPayload, normalizeApiParamPolicy } from "./apiParamPolicy.js";
+import { fetchProviderResponse } from "./providerHttp.js";
+import {
+  modelFetchErrorFromResponse,
+  providerRateLimitOptions,
+  readProviderErrorDetail,
+  withProviderHeaders,
+  type ProviderLike,
+  type ProviderModelMetadata
+} from "./providerApi.js";
+
+export const GEMINI_DEFAULT_BASE_URL = "https://generativelanguage.googleapis.com/v1beta";
+
+type GeminiPart =
+  | { text: string }
+  | { inline_data: { mime_type: string; data: string } };
+
+export interface GeminiContent {
+  role: "user" | "model";
+  parts: GeminiPart[];
+}
+
+export interface GeminiStreamDelta {
+  text: string;
+  thinking: string;
+  done: boolean;
+  error: string;
+}
+
+function normalizeUrl(url: string): string {
+  return String(url || "").trim().replace(/\/+$/, "");
+}
+
+/** API root up to the version segment; pasted `/models/...` paths and OpenAI-compat suffixes are dropped. */
+export function normalizeGeminiBaseUrl(baseUrl: string): string {
+  const base = normalizeUrl(baseUrl).replace(/\/openai$/i, "").replace(/\/models(?:\/.*)?$/i, "");
+  if (!base) return GEMINI_DEFAULT_BASE_URL;
+  return /\/v\d+[a-z0-9]*$/i.test(base) ? base : `${base}/v1beta`;
+}
+
+/** Gemini authenticates with a `key` query parameter rather than a bearer token. */
+export function buildGeminiUrl(provider: ProviderLike, path: string, query: Record<string, string> = {}): string {
+  const params = new URLSearchParams(query);
+  const apiKey = String(provider.api_key_cipher || "").trim();
+  if (apiKey) params.set("key", apiKey);
+  const search = params.toString();
+  return `${normalizeGeminiBaseUrl(provider.base_url)}/${path}${search ? `?${search}` : ""}`;
+}
+
+function toParts(content: unknown): GeminiPart[] {
+  if (!Array.isArray(content)) {
+    const text = String(content ?? "");
+    return text.trim() ? [{ text }] : [];
+  }
+  const parts: GeminiPart[] = [];
+  for (const part of content as Array<Record<string, unknown>>) {
+    if (!part || typeof part !== "object") continue;
+    if (part.type === "text") {
+      const text = String(part.text || "");
+      if (text.trim()) parts.push({ text });
+    } else if (part.type === "image_url") {
+      const imageUrl = part.image_url && typeof part.image_url === "object"
+        ? String((part.image_url as { url?: unknown }).url || "")
+        : String(part.image_url || "");
+      const match = /^data:(image\/[a-z0-9.+-]+);base64,(.*)$/i.exec(imageUrl);
+      if (match) parts.push({ inline_data: { mime_type: match[1], data: match[2] } });
+    }
+  }
+  return parts;
+}
+
+/**
+ * Gemini takes the system prompt as `systemInstruction` and names the assistant role `model`. Consecutive
+ * same-role turns are merged and the conversation opens with a user turn, as the API expects.
+ */
+export function toGeminiContents(messages: Array<{ role: string; content: unknown }>): {
+  systemInstruction: string;
+  contents: GeminiContent[];
+} {
+  const systemParts: string[] = [];
+  const out: GeminiContent[] = [];
+  for (const message of messages) {
+    if (message.role === "system") {
+      const text = toParts(message.content)
+        .map((part) => ("text" in part ? part.text : ""))
+        .join("\n")
+        .trim();
+      if (text) systemParts.push(text);
+      continue;
+    }
+    const parts = toParts(message.content);
+    if (parts.length === 0) continue;
+    const role = message.role === "assistant" ? "model" : "user";
+    const last = out[out.length - 1];
+    if (last && last.role === role) {
+      last.parts.push(...parts);
+    } else {
+      out.push({ role, parts });
+    }
+  }
+  if (out.length === 0 || out[0].role !== "user") {
+    out.unshift({ role: "user", parts: [{ text: "[Start]" }] });
+  }
+  return { systemInstruction: systemParts.join("\n\n"), contents: out };
+}
+
+/** Maps the OpenAI sampling policy onto Gemini's `generationConfig` field names. */
+export function buildGeminiGenerationConfig(
+  samplerConfig: Record<string, unknown>,
+  apiParamPolicy?: unknown
+): Record<string, unknown> {
+  const openAi = buildOpenAiSamplingPayload({
+    samplerConfig,
+    apiParamPolicy,
+    fields: ["temperature", "topP", "frequencyPenalty", "presencePenalty", "maxTokens", "stop"]
+  });
+  const out: Record<string, unknown> = {};
+  if (openAi.temperature !== undefined) out.temperature = Math.max(0, Math.min(2, Number(openAi.temperature)));
+  if (openAi.top_p !== undefined) out.topP = openAi.top_p;
+  if (openAi.max_tokens !== undefined) out.maxOutputTokens = openAi.max_tokens;
+  if (openAi.frequency_penalty !== undefined) out.frequencyPenalty = openAi.frequency_penalty;
+  if (openAi.presence_penalty !== undefined) out.presencePenalty = openAi.presence_penalty;
+  // Gemini accepts at most five stop sequences.
+  if (Array.isArray(openAi.stop) && openAi.stop.length > 0) out.stopSequences = openAi.stop.slice(0, 5);
+  const topK = Number(samplerConfig.topK);
+  const hasTopK = samplerConfig.topK !== undefined && samplerConfig.topK !== null && Number.isFinite(topK) && topK > 0;
+  if (hasTopK && normalizeApiParamPolicy(apiParamPolicy).openai.sendSampler) {
+    out.topK = Math.floor(topK);
+  }
+  return out;
+}
+
+export function buildGeminiGenerateBody(params: {
+  messages: Array<{ role: string; content: unknown }>;
+  samplerConfig?: Record<string, unknown>;
+  apiParamPolicy?: unknown;
+}): Record<string, unknown> {
+  const converted = toGeminiContents(params.messages);
+  const generationConfig = buildGeminiGenerationConfig(params.samplerConfig || {}, params.apiParamPolicy);
+  return {
+    ...(converted.systemInstruction ? { systemInstruction: { parts: [{ text: converted.systemInstruction }] } } : {}),
+    contents: converted.contents,
+    ...(Object.keys(generationConfig).length > 0 ? { generationConfig } : {})
+  };
+}
+
+function modelPath(modelId: string): string {
+  return `models/${encodeURIComponent(String(modelId || "").replace(/^models\//, ""))}`;
+}
+
+export async function requestGeminiGenerate(
+  provider: ProviderLike,
+  modelId: string,
+  body: Record<string, unknown>,
+  stream: boolean,
+  signal?: AbortSignal
+) {
+  const url = stream
+    ? buildGeminiUrl(provider, `${modelPath(modelId)}:streamGenerateContent`, { alt: "sse" })
+    : buildGeminiUrl(provider, `${modelPath(modelId)}:generateContent`);
+  return fetchProviderResponse(url, {
+    method: "POST",
+    headers: withProviderHeaders(provider, { "Content-Type": "application/json" }),
+    body: JSON.stringify(body),
+    signal
+  }, providerRateLimitOptions(provider));
+}
+
+/**
+ * Reads one `GenerateContentResponse` chunk. Parts flagged `thought` are reasoning; a blocked prompt or a
+ * safety stop with no text becomes an error so the turn does not end silently empty.
+ */
+export function extractGeminiStreamDelta(raw: unknown): GeminiStreamDelta {
+  const empty = { text: "", thinking: "", done: false, error: "" };
+  if (!raw || typeof raw !== "object") return empty;
+  const row = raw as {
+    candidates?: Array<{ content?: { parts?: Array<{ text?: unknown; thought?: unknown }> }; finishReason?: unknown }>;
+    promptFeedback?: { blockReason?: unknown };
+    error?: { message?: unknown };
+  };
+  if (row.error) return { ...empty, error: String(row.error.message || "Gemini returned an error") };
+  if (row.promptFeedback?.blockReason) {
+    return { ...empty, error: `Gemini blocked the prompt (${String(row.promptFeedback.blockReason)})` };
+  }
+  const candidate = Array.isArray(row.candidates) ? row.candidates[0] : undefined;
+  const parts = Array.isArray(candidate?.content?.parts) ? candidate.content.parts : [];
+  let text = "";
+  let thinking = "";
+  for (const part of parts) {
+    if (typeof part?.text !== "string") continue;
+    if (part.thought === true) thinking += part.text;
+    else text += part.text;
+  }
+  const finishReason = String(candidate?.finishReason || "");
+  const blocked = finishReason === "SAFETY" || finishReason === "PROHIBITED_CONTENT" || finishReason === "BLOCKLIST";
+  return {
+    text,
+    thinking,
+    done: Boolean(finishReason) && finishReason !== "FINISH_REASON_UNSPECIFIED",
+    error: blocked && !text ? `Gemini stopped the reply (${finishReason})` : ""
+  };
+}
+
+export async function completeGeminiChat(params: {
+  provider: ProviderLike;
+  modelId: string;
+  messages: Array<{ role: string; content: unknown }>;
+  samplerConfig?: Record<string, unknown>;
+  apiParamPolicy?: unknown;
+  signal?: AbortSignal;
+}): Promise<{ content: string; reasoning: string }> {
+  const response = await requestGeminiGenerate(params.provider, params.modelId, buildGeminiGenerateBody({
+    messages: params.messages,
+    samplerConfig: params.samplerConfig,
+    apiParamPolicy: params.apiParamPolicy
+  }), false, params.signal);
+  if (!response.ok) {
+    const detail = await readProviderErrorDetail(response);
+    throw new Error(`[Gemini API Error: ${response.status}] ${detail}`);
+  }
+  const chunk = extractGeminiStreamDelta(await response.json().catch(() => ({})));
+  if (chunk.error) throw new Error(chunk.error);
+  return { content: chunk.text, reasoning: chunk.thinking };
+}
+
+/** Model ids (without the `models/` prefix) that support `generateContent`, with their input token limit. */
+export function parseGeminiModels(raw: unknown): Array<{ id: string } & ProviderModelMetadata> {
+  const models = raw && typeof raw === "object" ? (raw as { models?: unknown }).models : undefined;
+  if (!Array.isArray(models)) return [];
+  const out: Array<{ id: string } & ProviderModelMetadata> = [];
+  for (const item of models as Array<Record<string, unknown>>) {
+    const id = String(item?.name || "").replace(/^models\//, "").trim();
+    const methods = item?.supportedGenerationMethods;
+    if (!id || (Array.isArray(methods) && !methods.includes("generateContent"))) continue;
+    const contextLength = Number(item.inputTokenLimit);
+    const description = String(item.description || "").trim();
+    out.push({
+      id,
+      ...(Number.isFinite(contextLength) && contextLength > 0 ? { contextLength } : {}),
+      ...(description ? { description } : {})
+    });
+  }
+  return out;
+}
+
+export async function fetchGeminiModels(
+  provider: ProviderLike,
+  signal?: AbortSignal
+): Promise<Array<{ id: string } & ProviderModelMetadata>> {
+  const response = await fetchProviderResponse(buildGeminiUrl(provider, "models", { pageSize: "1000" }), {
+    method: "GET",
+    headers: withProviderHeaders(provider, { Accept: "application/json" }),
+    signal
+  });
+  if (!response.ok) throw await modelFetchErrorFromResponse(response, `${normalizeGeminiBaseUrl(provider.base_url)}/models`);
+  return parseGeminiModels(await response.json().catch(() => ({})));
+}
@@ -0,0 +1,48 @@
+import { afterEach, describe, expect, it, vi } from "vitest";
+import { AppError } from "./appError.js";
+import { enforceModeration, parseModerationResponse } from "./moderation.js";
+
+afterEach(() => {
+  vi.restoreAllMocks();
+});
+
+const provider = { base_url: "https://api.openai.com/v1/", api_key_cipher: "sk-test", provider_type: "openai" };
+
+describe("moderation", () => {
+  it("collects flagged categories across results", () => {
+    expect(parseModerationResponse({
+      results: [
+        { flagged: false, categories: { violence: true } },
+        { flagged: true, categories: { harassment: true, violence: false, "self-harm": true } }
+      ]
+    })).toEqual({ flagged: true, categories: ["harassment", "self-harm"] });
+    expect(() => parseModerationResponse({})).toThrow("no results");
+  });
+
+  it("calls the provider's moderation endpoint and refuses flagged input", async () => {
+    const mockedFetch = vi.spyOn(globalThis, "fetch").mockResolvedValueOnce(new Response(JSON.stringify({
+      results: [{ flagged: true, categories: { harassment: true } }]
+    }), { status: 200 }));
+
+    const failure = await enforceModeration({ settings: {}, provider, input: "hostile text" }).catch((error: unknown) => error);
+
+    expect(String(mockedFetch.mock.calls[0]?.[0])).toBe("https://api.openai.com/v1/moderations");
+    expect(failure).toBeInstanceOf(AppError);
+    expect(failure).toMatchObject({ code: "moderation", httpStatus: 422, details: { categories: ["harassment"] } });
+  });
+
+  it("needs a usable endpoint and respects Full Local Mode", async () => {
+    await expect(enforceModeration({ settings: {}, provider: { ...provider, provider_type: "ollama" }, input: "hi" }))
+      .rejects.toMatchObject({ code: "not_configured" });
+    await expect(enforceModeration({ settings: { moderationUrl: "https://moderation.example/v1/moderations", fullLocalMode: true }, input: "hi" }))
+      .rejects.toThrow("Full Local Mode");
+  });
+
+  it("never sends the message to a remote provider in Full Local Mode", async () => {
+    const mockedFetch = vi.spyOn(globalThis, "fetch");
+
+    await expect(enforceModeration({ settings: { fullLocalMode: true }, provider, input: "hi" }))
+      .rejects.toMatchObject({ code: "not_configured", message: "Moderation endpoint blocked by Full Local Mode" });
+    expect(mockedFetch).not.toHaveBeenCalled();
+  });
+});
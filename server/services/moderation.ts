@@ -0,0 +1,118 @@
+import { db, isLocalhostUrl } from "../db.js";
+import { AppError, notConfiguredError } from "./appError.js";
+import { fetchProviderResponse } from "./providerHttp.js";
+import {
+  normalizeOpenAiBaseUrl,
+  normalizeProviderType,
+  providerAuthHeaders,
+  readProviderErrorDetail,
+  withProviderAuthUrl,
+  withProviderHeaders,
+  type ProviderLike
+} from "./providerApi.js";
+
+// The "Moderated" censorship mode checks each user message with an OpenAI-style `/moderations` endpoint
+// before it is saved or sent. A configured `moderationUrl` wins; otherwise the active provider's own
+// endpoint is used. A check that cannot run refuses the message rather than letting it through.
+
+export interface ModerationVerdict {
+  flagged: boolean;
+  categories: string[];
+}
+
+export interface ModerationSettings {
+  moderationUrl?: string;
+  moderationApiKey?: string;
+  fullLocalMode?: boolean;
+}
+
+export interface ChatModerationSettings extends ModerationSettings {
+  censorshipMode?: string;
+  activeProviderId?: string | null;
+}
+
+/** Reads `results[]` from a moderation response; a message is flagged if any result is. */
+export function parseModerationResponse(raw: unknown): ModerationVerdict {
+  const results = raw && typeof raw === "object" ? (raw as { results?: unknown }).results : undefined;
+  if (!Array.isArray(results)) throw new Error("Moderation endpoint returned no results");
+  const categories = new Set<string>();
+  let flagged = false;
+  for (const result of results as Array<{ flagged?: unknown; categories?: unknown }>) {
+    if (result?.flagged !== true) continue;
+    flagged = true;
+    if (result.categories && typeof result.categories === "object") {
+      for (const [name, hit] of Object.entries(result.categories as Record<string, unknown>)) {
+        if (hit === true) categories.add(name);
+      }
+    }
+  }
+  return { flagged, categories: [...categories] };
+}
+
+function resolveModerationRequest(
+  settings: ModerationSettings,
+  provider: ProviderLike | undefined
+): { url: string; headers: Record<string, string> } {
+  const customUrl = String(settings.moderationUrl || "").trim();
+  if (customUrl) {
+    if (settings.fullLocalMode === true && !isLocalhostUrl(customUrl)) {
+      throw notConfiguredError("Moderation endpoint blocked by Full Local Mode");
+    }
+    const apiKey = String(settings.moderationApiKey || "").trim();
+    return {
+      url: customUrl,
+      headers: { "Content-Type": "application/json", ...(apiKey ? { Authorization: `Bearer ${apiKey}` } : {}) }
+    };
+  }
+  if (!provider || normalizeProviderType(provider.provider_type) !== "openai") {
+    throw notConfiguredError("Moderated mode needs an OpenAI-compatible provider or a moderation endpoint in Settings");
+  }
+  if (settings.fullLocalMode === true && !isLocalhostUrl(provider.base_url)) {
+    throw notConfiguredError("Moderation endpoint blocked by Full Local Mode");
+  }
+  return {
+    url: withProviderAuthUrl(provider, `${normalizeOpenAiBaseUrl(provider.base_url)}/moderations`),
+    headers: withProviderHeaders(provider, { "Content-Type": "application/json", ...providerAuthHeaders(provider) })
+  };
+}
+
+export async function checkModeration(params: {
+  settings: ModerationSettings;
+  provider?: ProviderLike;
+  input: string;
+  signal?: AbortSignal;
+}): Promise<ModerationVerdict> {
+  if (!params.input.trim()) return { flagged: false, categories: [] };
+  const request = resolveModerationRequest(params.settings, params.provider);
+  const response = await fetchProviderResponse(request.url, {
+    method: "POST",
+    headers: request.headers,
+    body: JSON.stringify({ input: params.input }),
+    signal: params.signal
+  });
+  if (!response.ok) {
+    const detail = await readProviderErrorDetail(response);
+    throw new Error(`[Moderation API Error: ${response.status}] ${detail}`);
+  }
+  return parseModerationResponse(await response.json().catch(() => ({})));
+}
+
+/** Throws a `moderation` error listing the flagged categories when the input does not pass. */
+export async function enforceModeration(params: Parameters<typeof checkModeration>[0]): Promise<void> {
+  const verdict = await checkModeration(params);
+  if (!verdict.flagged) return;
+  const reason = verdict.categories.length > 0 ? ` (${verdict.categories.join(", ")})` : "";
+  throw new AppError("moderation", `Message was flagged by moderation${reason}`, 422, null, {
+    details: { categories: verdict.categories }
+  });
+}
+
+/** In Moderated mode, checks user text against the active provider before it is stored, edited in or resent. */
+export async function moderateUserMessage(settings: ChatModerationSettings, input: string): Promise<void> {
+  if (settings.censorshipMode !== "Moderated") return;
+  const providerId = String(settings.activeProviderId || "").trim();
+  const provider = providerId
+    ? db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as ProviderLike | undefined
+    : undefined;
+  await enforceModeration({ settings, provider, input });
+}
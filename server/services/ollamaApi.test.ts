@@ -0,0 +1,36 @@
+import { describe, expect, it } from "vitest";
+import { buildOllamaOptions, extractOllamaChatDelta, normalizeOllamaBaseUrl, toOllamaMessages } from "./ollamaApi.js";
+
+describe("ollamaApi", () => {
+  it("normalizes base urls to the server root", () => {
+    expect(normalizeOllamaBaseUrl("http://localhost:11434/v1/")).toBe("http://localhost:11434");
+    expect(normalizeOllamaBaseUrl("http://127.0.0.1:11434/api")).toBe("http://127.0.0.1:11434");
+    expect(normalizeOllamaBaseUrl("")).toBe("http://localhost:11434");
+  });
+
+  it("maps sampler settings onto Ollama options", () => {
+    const options = buildOllamaOptions({ temperature: 0.7, maxTokens: 256, topK: 40 });
+    expect(options.temperature).toBe(0.7);
+    expect(options.num_predict).toBe(256);
+    expect(options).not.toHaveProperty("max_tokens");
+  });
+
+  it("moves data-url images into the images field", () => {
+    expect(toOllamaMessages([
+      {
+        role: "user",
+        content: [
+          { type: "text", text: "Describe this" },
+          { type: "image_url", image_url: { url: "data:image/png;base64,AAAA" } }
+        ]
+      }
+    ])).toEqual([{ role: "user", content: "Describe this", images: ["AAAA"] }]);
+  });
+
+  it("reads content, thinking and completion from stream lines", () => {
+    expect(extractOllamaChatDelta({ message: { content: "Hi", thinking: "hmm" }, done: false }))
+      .toEqual({ content: "Hi", thinking: "hmm", done: false, error: "" });
+    expect(extractOllamaChatDelta({ done: true }).done).toBe(true);
+    expect(extractOllamaChatDelta({ error: "model not found" }).error).toBe("model not found");
+  });
+});
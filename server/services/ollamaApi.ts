@@ -0,0 +1,171 @@
+import { buildOpenAiSamplingPayload, normalizeApiParamPolicy } from "./apiParamPolicy.js";
+import { fetchProviderResponse } from "./providerHttp.js";
+import { modelFetchErrorFromResponse, providerRateLimitOptions, readProviderErrorDetail, withProviderHeaders, type ProviderLike } from "./providerApi.js";
+
+export interface OllamaChatMessage {
+  role: string;
+  content: string;
+  images?: string[];
+}
+
+export interface OllamaChatDelta {
+  content: string;
+  thinking: string;
+  done: boolean;
+  error: string;
+}
+
+function normalizeUrl(url: string): string {
+  return String(url || "").trim().replace(/\/+$/, "");
+}
+
+export function normalizeOllamaBaseUrl(baseUrl: string): string {
+  let base = normalizeUrl(baseUrl);
+  if (base.endsWith("/api")) base = base.slice(0, -4);
+  else if (base.endsWith("/v1")) base = base.slice(0, -3);
+  return base || "http://localhost:11434";
+}
+
+function readNumber(raw: unknown): number | null {
+  const value = Number(raw);
+  return raw !== undefined && raw !== null && raw !== "" && Number.isFinite(value) ? value : null;
+}
+
+/**
+ * Ollama takes sampling fields under `options` with its own names. OpenAI-style
+ * fields follow the OpenAI API parameter policy; the extra local-runtime knobs
+ * are only forwarded when they are set on the sampler config.
+ */
+export function buildOllamaOptions(samplerConfig: Record<string, unknown>, apiParamPolicy?: unknown): Record<string, unknown> {
+  const policy = normalizeApiParamPolicy(apiParamPolicy).openai;
+  const openAi = buildOpenAiSamplingPayload({
+    samplerConfig,
+    apiParamPolicy,
+    fields: ["temperature", "topP", "frequencyPenalty", "presencePenalty", "maxTokens", "stop"]
+  });
+  const options: Record<string, unknown> = {};
+  if (openAi.temperature !== undefined) options.temperature = openAi.temperature;
+  if (openAi.top_p !== undefined) options.top_p = openAi.top_p;
+  if (openAi.frequency_penalty !== undefined) options.frequency_penalty = openAi.frequency_penalty;
+  if (openAi.presence_penalty !== undefined) options.presence_penalty = openAi.presence_penalty;
+  if (openAi.max_tokens !== undefined) options.num_predict = openAi.max_tokens;
+  if (openAi.stop !== undefined) options.stop = openAi.stop;
+  if (policy.sendSampler) {
+    const topK = readNumber(samplerConfig.topK);
+    const minP = readNumber(samplerConfig.minP);
+    const repeatPenalty = readNumber(samplerConfig.repetitionPenalty);
+    if (topK !== null) options.top_k = Math.max(0, Math.floor(topK));
+    if (minP !== null) options.min_p = Math.max(0, Math.min(1, minP));
+    if (repeatPenalty !== null) options.repeat_penalty = Math.max(0, Math.min(3, repeatPenalty));
+  }
+  return options;
+}
+
+function stripDataUrlPrefix(url: string): string {
+  const match = /^data:image\/[a-z0-9.+-]+;base64,(.*)$/i.exec(url);
+  return match ? match[1] : "";
+}
+
+/** Flattens OpenAI content parts into Ollama's `content` plus base64 `images`. */
+export function toOllamaMessages(messages: Array<{ role: string; content: unknown }>): OllamaChatMessage[] {
+  return messages.map((message) => {
+    if (!Array.isArray(message.content)) {
+      return { role: message.role, content: String(message.content ?? "") };
+    }
+    const texts: string[] = [];
+    const images: string[] = [];
+    for (const part of message.content as Array<Record<string, unknown>>) {
+      if (!part || typeof part !== "object") continue;
+      if (part.type === "text") {
+        texts.push(String(part.text || ""));
+      } else if (part.type === "image_url") {
+        const imageUrl = part.image_url && typeof part.image_url === "object"
+          ? String((part.image_url as { url?: unknown }).url || "")
+          : String(part.image_url || "");
+        const base64 = stripDataUrlPrefix(imageUrl);
+        if (base64) images.push(base64);
+      }
+    }
+    return images.length > 0
+      ? { role: message.role, content: texts.join("\n"), images }
+      : { role: message.role, content: texts.join("\n") };
+  });
+}
+
+export function buildOllamaChatBody(params: {
+  modelId: string;
+  messages: Array<{ role: string; content: unknown }>;
+  samplerConfig?: Record<string, unknown>;
+  apiParamPolicy?: unknown;
+  stream: boolean;
+}): Record<string, unknown> {
+  const options = buildOllamaOptions(params.samplerConfig || {}, params.apiParamPolicy);
+  return {
+    model: params.modelId,
+    messages: toOllamaMessages(params.messages),
+    stream: params.stream,
+    ...(Object.keys(options).length > 0 ? { options } : {})
+  };
+}
+
+export async function requestOllamaChat(
+  provider: ProviderLike,
+  body: Record<string, unknown>,
+  signal?: AbortSignal
+) {
+  const base = normalizeOllamaBaseUrl(provider.base_url);
+  return fetchProviderResponse(`${base}/api/chat`, {
+    method: "POST",
+    headers: withProviderHeaders(provider, { "Content-Type": "application/json" }),
+    body: JSON.stringify(body),
+    signal
+  }, providerRateLimitOptions(provider));
+}
+
+export function extractOllamaChatDelta(raw: unknown): OllamaChatDelta {
+  if (!raw || typeof raw !== "object") return { content: "", thinking: "", done: false, error: "" };
+  const row = raw as { message?: { content?: unknown; thinking?: unknown }; done?: unknown; error?: unknown };
+  return {
+    content: typeof row.message?.content === "string" ? row.message.content : "",
+    thinking: typeof row.message?.thinking === "string" ? row.message.thinking : "",
+    done: row.done === true,
+    error: typeof row.error === "string" ? row.error : ""
+  };
+}
+
+export async function completeOllamaChat(params: {
+  provider: ProviderLike;
+  modelId: string;
+  messages: Array<{ role: string; content: unknown }>;
+  samplerConfig?: Record<string, unknown>;
+  apiParamPolicy?: unknown;
+  signal?: AbortSignal;
+}): Promise<{ content: string; reasoning: string }> {
+  const response = await requestOllamaChat(params.provider, buildOllamaChatBody({
+    modelId: params.modelId,
+    messages: params.messages,
+    samplerConfig: params.samplerConfig,
+    apiParamPolicy: params.apiParamPolicy,
+    stream: false
+  }), params.signal);
+  if (!response.ok) {
+    const detail = await readProviderErrorDetail(response);
+    throw new Error(`[Ollama API Error: ${response.status}] ${detail}`);
+  }
+  const parsed = extractOllamaChatDelta(await response.json().catch(() => ({})));
+  if (parsed.error) throw new Error(parsed.error);
+  return { content: parsed.content, reasoning: parsed.thinking };
+}
+
+export async function fetchOllamaModels(provider: ProviderLike, signal?: AbortSignal): Promise<string[]> {
+  const base = normalizeOllamaBaseUrl(provider.base_url);
+  const response = await fetchProviderResponse(`${base}/api/tags`, {
+    method: "GET",
+    headers: withProviderHeaders(provider, { Accept: "application/json" }),
+    signal
+  });
+  if (!response.ok) throw await modelFetchErrorFromResponse(response, `${base}/api/tags`);
+  const body = await response.json().catch(() => ({})) as { models?: Array<{ name?: unknown; model?: unknown }> };
+  if (!Array.isArray(body.models)) return [];
+  return [...new Set(body.models.map((item) => String(item?.name || item?.model || "").trim()).filter(Boolean))];
+}
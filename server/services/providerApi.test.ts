@@ -0,0 +1,169 @@
+import { describe, expect, it } from "vitest";
+import {
+  describeProviderErrorBody,
+  applyModelPrefs,
+  normalizeOpenAiBaseUrl,
+  normalizeProviderHeaders,
+  parseModelIds,
+  parseModelMetadata,
+  providerAuthHeaders,
+  searchModelList,
+  withProviderAuthUrl,
+  withProviderHeaders
+} from "./providerApi.js";
+
+describe("parseModelIds", () => {
+  it("reads the OpenAI data wrapper, bare arrays, and Ollama model names", () => {
+    expect(parseModelIds({ data: [{ id: "gpt-mini" }, { id: "gpt-large" }] })).toEqual(["gpt-mini", "gpt-large"]);
+    expect(parseModelIds([{ id: "local-a" }, "local-b", { name: "local-c" }])).toEqual(["local-a", "local-b", "local-c"]);
+    expect(parseModelIds({ models: [{ name: "llama3:8b", details: { family: "llama" } }] })).toEqual(["llama3:8b"]);
+  });
+
+  it("ignores empty and duplicate entries", () => {
+    expect(parseModelIds([{ id: "" }, { id: "dup" }, { id: "dup" }, null])).toEqual(["dup"]);
+    expect(parseModelIds(null)).toEqual([]);
+  });
+});
+
+describe("parseModelMetadata", () => {
+  it("reads OpenRouter context length and per-token pricing as per-million prices", () => {
+    const metadata = parseModelMetadata({
+      data: [{
+        id: "openrouter/model",
+        description: " A capable model. ",
+        context_length: 131072,
+        pricing: { prompt: "0.0000025", completion: "0.00001" }
+      }]
+    });
+    expect(metadata.get("openrouter/model")).toEqual({
+      contextLength: 131072,
+      inputPrice: 2.5,
+      outputPrice: 10,
+      description: "A capable model."
+    });
+  });
+
+  it("accepts context_window, max_context_length and per-million pricing, and skips models without metadata", () => {
+    const metadata = parseModelMetadata([
+      { id: "groq-model", context_window: 32768 },
+      { id: "lmstudio-model", max_context_length: "4096" },
+      { id: "together-model", pricing: { input: 0.2, output: 0.6 } },
+      { id: "bare-model" },
+      { id: "bad-model", context_length: -1, pricing: { prompt: "n/a" } }
+    ]);
+    expect(metadata.get("groq-model")).toEqual({ contextLength: 32768 });
+    expect(metadata.get("lmstudio-model")).toEqual({ contextLength: 4096 });
+    expect(metadata.get("together-model")).toEqual({ inputPrice: 0.2, outputPrice: 0.6 });
+    expect(metadata.has("bare-model")).toBe(false);
+    expect(metadata.has("bad-model")).toBe(false);
+  });
+});
+
+describe("describeProviderErrorBody", () => {
+  it("prefers the JSON error message from OpenAI, Anthropic and Ollama bodies", () => {
+    expect(describeProviderErrorBody(JSON.stringify({
+      error: { message: "This model's maximum context length is 8192 tokens.", type: "invalid_request_error" }
+    }))).toBe("This model's maximum context length is 8192 tokens.");
+    expect(describeProviderErrorBody(JSON.stringify({
+      type: "error",
+      error: { type: "rate_limit_error", message: "Rate limit exceeded" }
+    }))).toBe("Rate limit exceeded");
+    expect(describeProviderErrorBody(JSON.stringify({ error: "model not found" }))).toBe("model not found");
+  });
+
+  it("falls back to the raw body and truncates long ones", () => {
+    expect(describeProviderErrorBody("  upstream hiccup  ")).toBe("upstream hiccup");
+    expect(describeProviderErrorBody("")).toBe("Unknown error");
+    expect(describeProviderErrorBody("x".repeat(400))).toBe(`${"x".repeat(300)}…`);
+  });
+});
+
+describe("searchModelList", () => {
+  const models = [{ id: "mistral-large" }, { id: "Llama-3-70B" }, { id: "llama-3-8b" }, { id: "gpt-4o" }];
+
+  it("matches ids case-insensitively and sorts alphabetically", () => {
+    expect(searchModelList(models, "LLAMA")).toEqual({
+      models: [{ id: "Llama-3-70B" }, { id: "llama-3-8b" }],
+      total: 4,
+      matched: 2
+    });
+  });
+
+  it("returns every model for an empty filter and applies the limit after counting", () => {
+    const result = searchModelList(models, "  ", 2);
+    expect(result.models.map((model) => model.id)).toEqual(["gpt-4o", "Llama-3-70B"]);
+    expect(result.matched).toBe(4);
+  });
+
+  it("lists favorites before the rest", () => {
+    const result = searchModelList([...models, { id: "zephyr", favorite: true }], "");
+    expect(result.models.map((model) => model.id)).toEqual(["zephyr", "gpt-4o", "Llama-3-70B", "llama-3-8b", "mistral-large"]);
+  });
+});
+
+describe("applyModelPrefs", () => {
+  const prefs = new Map([
+    ["a", { favorite: true, hidden: false }],
+    ["b", { favorite: false, hidden: true }]
+  ]);
+
+  it("flags favorites and drops hidden models", () => {
+    expect(applyModelPrefs([{ id: "a" }, { id: "b" }, { id: "c" }], prefs, false))
+      .toEqual([{ id: "a", favorite: true }, { id: "c" }]);
+  });
+
+  it("keeps hidden models flagged when asked for them", () => {
+    expect(applyModelPrefs([{ id: "b" }], prefs, true)).toEqual([{ id: "b", hidden: true }]);
+  });
+});
+
+describe("withProviderHeaders", () => {
+  it("overrides default headers case-insensitively", () => {
+    const provider = { extra_headers: JSON.stringify({ authorization: "Bearer proxy", "X-Title": "Vellium" }) };
+    expect(withProviderHeaders(provider, { "Content-Type": "application/json", Authorization: "Bearer key" })).toEqual({
+      "Content-Type": "application/json",
+      authorization: "Bearer proxy",
+      "X-Title": "Vellium"
+    });
+  });
+
+  it("drops invalid header names and multi-line values", () => {
+    expect(normalizeProviderHeaders({ "Bad Header": "x", "X-Org": "a\nb" })).toEqual({ "X-Org": "a b" });
+    expect(normalizeProviderHeaders("not json")).toEqual({});
+  });
+});
+
+describe("provider auth modes", () => {
+  it("sends a bearer token by default", () => {
+    expect(providerAuthHeaders({ api_key_cipher: "sk-1" })).toEqual({ Authorization: "Bearer sk-1" });
+    expect(withProviderAuthUrl({ api_key_cipher: "sk-1" }, "https://api.x.com/v1/models")).toBe("https://api.x.com/v1/models");
+    expect(providerAuthHeaders({ api_key_cipher: "  " })).toEqual({});
+  });
+
+  it("puts the key under a named header, defaulting to X-API-Key", () => {
+    expect(providerAuthHeaders({ api_key_cipher: "k", auth_mode: "header", auth_param: "api-key" })).toEqual({ "api-key": "k" });
+    expect(providerAuthHeaders({ api_key_cipher: "k", auth_mode: "header", auth_param: "bad name" })).toEqual({ "X-API-Key": "k" });
+  });
+
+  it("appends the key as a query parameter in query mode", () => {
+    const provider = { api_key_cipher: "a&b", auth_mode: "query", auth_param: "api_key" };
+    expect(providerAuthHeaders(provider)).toEqual({});
+    expect(withProviderAuthUrl(provider, "https://x.com/v1/models")).toBe("https://x.com/v1/models?api_key=a%26b");
+    expect(withProviderAuthUrl({ ...provider, auth_param: null }, "https://x.com/m?limit=1")).toBe("https://x.com/m?limit=1&key=a%26b");
+  });
+});
+
+describe("normalizeOpenAiBaseUrl", () => {
+  it("produces the same base for bare hosts, /v1 bases, and pasted endpoints", () => {
+    expect(normalizeOpenAiBaseUrl("https://api.x.com")).toBe("https://api.x.com/v1");
+    expect(normalizeOpenAiBaseUrl("https://api.x.com/v1/")).toBe("https://api.x.com/v1");
+    expect(normalizeOpenAiBaseUrl("https://api.x.com/v1/chat/completions")).toBe("https://api.x.com/v1");
+    expect(normalizeOpenAiBaseUrl("https://api.x.com/v1/models")).toBe("https://api.x.com/v1");
+  });
+
+  it("keeps non-v1 version segments", () => {
+    expect(normalizeOpenAiBaseUrl("https://generativelanguage.googleapis.com/v1beta/openai")).toBe("https://generativelanguage.googleapis.com/v1beta/openai");
+    expect(normalizeOpenAiBaseUrl("https://open.bigmodel.cn/api/paas/v4/chat/completions")).toBe("https://open.bigmodel.cn/api/paas/v4");
+    expect(normalizeOpenAiBaseUrl("")).toBe("");
+  });
+});
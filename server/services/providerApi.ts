@@ -1,23 +1,173 @@
-import { fetchProviderResponse } from "./providerHttp.js";
+import { extractOpenAiStreamErrorMessage } from "../modules/chat/openAiStream.js";
+import { fetchProviderResponse, type ProviderFetchOptions } from "./providerHttp.js";
 
-export type ProviderType = "openai" | "koboldcpp" | "custom";
+export type ProviderType = "openai" | "koboldcpp" | "ollama" | "anthropic" | "gemini" | "custom";
 
 export interface ProviderLike {
+  id?: string;
   base_url: string;
   api_key_cipher?: string;
   provider_type?: string | null;
+  extra_headers?: string | null;
+  rate_limit_rpm?: number | null;
+  auth_mode?: string | null;
+  auth_param?: string | null;
 }
 
 function normalizeUrl(url: string): string {
   return String(url || "").trim().replace(/\/+$/, "");
 }
 
+const OPENAI_ENDPOINT_SUFFIX = /\/(?:chat\/completions|completions|models)$/i;
+
+/**
+ * Canonical base URL for OpenAI-compatible providers. Pasted endpoint paths such
+ * as `/chat/completions` are dropped, and `/v1` is appended only when the path
+ * has no version segment (`/v1`, `/v1beta/openai`, `/api/paas/v4` are kept).
+ */
+export function normalizeOpenAiBaseUrl(raw: string): string {
+  const base = normalizeUrl(raw).replace(OPENAI_ENDPOINT_SUFFIX, "").replace(/\/+$/, "");
+  if (!base) return "";
+  const path = base.replace(/^[a-z][a-z0-9+.-]*:\/\/[^/]+/i, "");
+  if (/\/v\d+[a-z0-9]*(?:\/|$)/i.test(path)) return base;
+  return `${base}/v1`;
+}
+
 export function normalizeProviderType(raw: unknown): ProviderType {
   if (raw === "koboldcpp") return "koboldcpp";
+  if (raw === "ollama") return "ollama";
+  if (raw === "anthropic") return "anthropic";
+  if (raw === "gemini") return "gemini";
   if (raw === "custom") return "custom";
   return "openai";
 }
 
+/** Model discovery failure that keeps the upstream HTTP status and the start of its body. */
+export class ModelFetchError extends Error {
+  constructor(message: string, readonly status: number | null = null, readonly body = "") {
+    super(message);
+    this.name = "ModelFetchError";
+  }
+}
+
+export async function modelFetchErrorFromResponse(response: Response, endpoint: string): Promise<ModelFetchError> {
+  const body = (await response.text().catch(() => "")).trim().slice(0, 500);
+  return new ModelFetchError(
+    `Model endpoint returned HTTP ${response.status}: ${endpoint}${body ? ` — ${body}` : ""}`,
+    response.status,
+    body
+  );
+}
+
+const PROVIDER_ERROR_DETAIL_LIMIT = 300;
+
+/**
+ * What a failed provider call should tell the user: the JSON error message (OpenAI, Anthropic and Ollama
+ * shapes) when the body has one, otherwise the raw body, truncated either way.
+ */
+export function describeProviderErrorBody(body: string, fallback = "Unknown error"): string {
+  const text = body.trim();
+  let detail = text;
+  try {
+    detail = extractOpenAiStreamErrorMessage(JSON.parse(text)) || text;
+  } catch {
+    // Not JSON; show the text as-is.
+  }
+  if (!detail) return fallback;
+  return detail.length > PROVIDER_ERROR_DETAIL_LIMIT ? `${detail.slice(0, PROVIDER_ERROR_DETAIL_LIMIT)}…` : detail;
+}
+
+export async function readProviderErrorDetail(response: Response, fallback = "Unknown error"): Promise<string> {
+  return describeProviderErrorBody(await response.text().catch(() => ""), fallback);
+}
+
+const HEADER_NAME_PATTERN = /^[A-Za-z0-9!#$%&'*+.^_`|~-]+$/;
+
+/** Accepts a header map (or its JSON text) and keeps only valid names with single-line values. */
+export function normalizeProviderHeaders(raw: unknown): Record<string, string> {
+  let value = raw;
+  if (typeof value === "string") {
+    try {
+      value = JSON.parse(value || "{}");
+    } catch {
+      return {};
+    }
+  }
+  if (!value || typeof value !== "object" || Array.isArray(value)) return {};
+  const out: Record<string, string> = {};
+  for (const [rawName, rawValue] of Object.entries(value as Record<string, unknown>)) {
+    const name = String(rawName || "").trim();
+    if (!HEADER_NAME_PATTERN.test(name)) continue;
+    out[name] = String(rawValue ?? "").replace(/[\r\n]+/g, " ").trim();
+  }
+  return out;
+}
+
+/**
+ * Merges a provider's custom headers over the request defaults. Names match
+ * case-insensitively, so an `Authorization` header set on the provider replaces
+ * the bearer token built from the API key.
+ */
+export function withProviderHeaders(
+  provider: Pick<ProviderLike, "extra_headers"> | null | undefined,
+  headers: Record<string, string>
+): Record<string, string> {
+  const custom = normalizeProviderHeaders(provider?.extra_headers);
+  const customNames = new Set(Object.keys(custom).map((name) => name.toLowerCase()));
+  if (customNames.size === 0) return headers;
+  const merged: Record<string, string> = {};
+  for (const [name, value] of Object.entries(headers)) {
+    if (!customNames.has(name.toLowerCase())) merged[name] = value;
+  }
+  return { ...merged, ...custom };
+}
+
+export type ProviderAuthMode = "bearer" | "header" | "query";
+
+const DEFAULT_AUTH_PARAMS: Record<Exclude<ProviderAuthMode, "bearer">, string> = { header: "X-API-Key", query: "key" };
+
+export function normalizeProviderAuthMode(raw: unknown): ProviderAuthMode {
+  return raw === "header" || raw === "query" ? raw : "bearer";
+}
+
+function authParamName(mode: Exclude<ProviderAuthMode, "bearer">, raw: unknown): string {
+  const value = String(raw ?? "").trim();
+  return /^[A-Za-z0-9_.-]{1,100}$/.test(value) ? value : DEFAULT_AUTH_PARAMS[mode];
+}
+
+/** Header name or query parameter the key goes in; null for bearer auth. Invalid names fall back to the default. */
+export function normalizeProviderAuthParam(mode: ProviderAuthMode, raw: unknown): string | null {
+  return mode === "bearer" ? null : authParamName(mode, raw);
+}
+
+type ProviderAuthSource = Pick<ProviderLike, "api_key_cipher" | "auth_mode" | "auth_param">;
+
+/** The API key as `Authorization: Bearer` or under the provider's own header name; query auth adds no header. */
+export function providerAuthHeaders(provider: ProviderAuthSource): Record<string, string> {
+  const apiKey = String(provider.api_key_cipher || "").trim();
+  if (!apiKey) return {};
+  const mode = normalizeProviderAuthMode(provider.auth_mode);
+  if (mode === "query") return {};
+  if (mode === "header") return { [authParamName(mode, provider.auth_param)]: apiKey };
+  return { Authorization: `Bearer ${apiKey}` };
+}
+
+/** Appends the API key as a query parameter for providers using query auth; other URLs are returned as is. */
+export function withProviderAuthUrl(provider: ProviderAuthSource, url: string): string {
+  const apiKey = String(provider.api_key_cipher || "").trim();
+  const mode = normalizeProviderAuthMode(provider.auth_mode);
+  if (!apiKey || mode !== "query") return url;
+  const param = encodeURIComponent(authParamName(mode, provider.auth_param));
+  return `${url}${url.includes("?") ? "&" : "?"}${param}=${encodeURIComponent(apiKey)}`;
+}
+
+/** Fetch options that hold generation requests to the provider's requests-per-minute limit, when it has one. */
+export function providerRateLimitOptions(provider: Pick<ProviderLike, "id" | "base_url" | "rate_limit_rpm">): ProviderFetchOptions {
+  const requestsPerMinute = Math.floor(Number(provider.rate_limit_rpm) || 0);
+  if (requestsPerMinute <= 0) return {};
+  return { rateLimit: { key: provider.id || provider.base_url, requestsPerMinute } };
+}
+
 export function normalizeKoboldBaseUrl(baseUrl: string): string {
   let base = normalizeUrl(baseUrl);
   if (base.endsWith("/api/v1")) base = base.slice(0, -7);
@@ -157,10 +307,10 @@ export async function requestKoboldGenerate(
   const base = normalizeKoboldBaseUrl(provider.base_url);
   return fetchProviderResponse(`${base}/api/v1/generate`, {
     method: "POST",
-    headers: { "Content-Type": "application/json" },
+    headers: withProviderHeaders(provider, { "Content-Type": "application/json" }),
     body: JSON.stringify(body),
     signal
-  });
+  }, providerRateLimitOptions(provider));
 }
 
 export async function requestKoboldGenerateStream(
@@ -171,10 +321,10 @@ export async function requestKoboldGenerateStream(
   const base = normalizeKoboldBaseUrl(provider.base_url);
   return fetchProviderResponse(`${base}/api/extra/generate/stream`, {
     method: "POST",
-    headers: { "Content-Type": "application/json" },
+    headers: withProviderHeaders(provider, { "Content-Type": "application/json" }),
     body: JSON.stringify(body),
     signal
-  });
+  }, providerRateLimitOptions(provider));
 }
 
 export function extractKoboldGeneratedText(raw: unknown): string {
@@ -216,7 +366,21 @@ export function extractKoboldStreamDelta(raw: unknown): string {
   return "";
 }
 
-function parseModelIds(raw: unknown): string[] {
+function readModelId(item: unknown): string {
+  if (typeof item === "string") return item.trim();
+  if (!item || typeof item !== "object") return "";
+  const row = item as { id?: unknown; name?: unknown; model?: unknown };
+  return String(row.id || row.name || row.model || "").trim();
+}
+
+/**
+ * Accepts the OpenAI `{ data: [...] }` shape, a bare `[...]` array, and the
+ * Ollama/Kobold `{ models: [...] }` variants where entries carry `name` instead of `id`.
+ */
+export function parseModelIds(raw: unknown): string[] {
+  if (Array.isArray(raw)) {
+    return [...new Set(raw.map(readModelId).filter(Boolean))];
+  }
   if (!raw || typeof raw !== "object") return [];
   const row = raw as {
     id?: unknown;
@@ -257,7 +421,116 @@ function parseModelIds(raw: unknown): string[] {
   return [...new Set(out.map((item) => item.trim()).filter(Boolean))];
 }
 
-export async function fetchKoboldModels(provider: ProviderLike): Promise<string[]> {
+export interface ProviderModelMetadata {
+  contextLength?: number;
+  /** USD per million prompt tokens. */
+  inputPrice?: number;
+  /** USD per million completion tokens. */
+  outputPrice?: number;
+  description?: string;
+}
+
+function readPositiveNumber(value: unknown): number | undefined {
+  const parsed = typeof value === "string" ? Number(value.trim()) : value;
+  return typeof parsed === "number" && Number.isFinite(parsed) && parsed > 0 ? parsed : undefined;
+}
+
+function readPrice(value: unknown, perToken: boolean): number | undefined {
+  const parsed = typeof value === "string" ? Number(value.trim()) : value;
+  if (typeof parsed !== "number" || !Number.isFinite(parsed) || parsed < 0) return undefined;
+  return Math.round((perToken ? parsed * 1_000_000 : parsed) * 1_000_000) / 1_000_000;
+}
+
+function readModelMetadata(item: Record<string, unknown>): ProviderModelMetadata {
+  const topProvider = item.top_provider && typeof item.top_provider === "object" ? item.top_provider as Record<string, unknown> : {};
+  const contextLength = readPositiveNumber(item.context_length)
+    ?? readPositiveNumber(item.context_window)
+    ?? readPositiveNumber(item.max_context_length)
+    ?? readPositiveNumber(topProvider.context_length);
+  const pricing = item.pricing && typeof item.pricing === "object" ? item.pricing as Record<string, unknown> : {};
+  // OpenRouter prices `prompt`/`completion` per token; `input`/`output` (Together and others) are per million.
+  const inputPrice = pricing.prompt !== undefined ? readPrice(pricing.prompt, true) : readPrice(pricing.input, false);
+  const outputPrice = pricing.completion !== undefined ? readPrice(pricing.completion, true) : readPrice(pricing.output, false);
+  const description = typeof item.description === "string" ? item.description.trim() : "";
+  return {
+    ...(contextLength ? { contextLength: Math.floor(contextLength) } : {}),
+    ...(inputPrice !== undefined ? { inputPrice } : {}),
+    ...(outputPrice !== undefined ? { outputPrice } : {}),
+    ...(description ? { description } : {})
+  };
+}
+
+/**
+ * Context length, pricing and description per model id from a model listing (OpenRouter `context_length`
+ * and `pricing`, OpenAI-compatible `context_window`, LM Studio `max_context_length`). Models that report
+ * none of them are left out.
+ */
+export function parseModelMetadata(raw: unknown): Map<string, ProviderModelMetadata> {
+  const container = (raw && typeof raw === "object" && !Array.isArray(raw) ? raw : {}) as { data?: unknown; models?: unknown };
+  const items: unknown[] = Array.isArray(raw)
+    ? raw
+    : Array.isArray(container.data) ? container.data : Array.isArray(container.models) ? container.models : [];
+  const out = new Map<string, ProviderModelMetadata>();
+  for (const item of items) {
+    if (!item || typeof item !== "object") continue;
+    const id = readModelId(item);
+    const metadata = readModelMetadata(item as Record<string, unknown>);
+    if (id && !out.has(id) && Object.keys(metadata).length > 0) out.set(id, metadata);
+  }
+  return out;
+}
+
+export interface ModelPrefs {
+  favorite: boolean;
+  hidden: boolean;
+}
+
+/**
+ * Marks favorite and hidden models from the user's per-provider prefs and drops hidden ones unless
+ * `includeHidden` is set. Flags are only added when true, so unmarked models keep their listing shape.
+ */
+export function applyModelPrefs<T extends { id: string }>(
+  models: T[],
+  prefs: Map<string, ModelPrefs>,
+  includeHidden: boolean
+): Array<T & { favorite?: boolean; hidden?: boolean }> {
+  const out: Array<T & { favorite?: boolean; hidden?: boolean }> = [];
+  for (const model of models) {
+    const pref = prefs.get(model.id);
+    if (pref?.hidden && !includeHidden) continue;
+    out.push({
+      ...model,
+      ...(pref?.favorite ? { favorite: true } : {}),
+      ...(pref?.hidden ? { hidden: true } : {})
+    });
+  }
+  return out;
+}
+
+/**
+ * Models whose id contains `filter` (case-insensitive), favorites first and then by id so large listings page
+ * the same way on every fetch. `total` counts the whole list and `matched` the filtered one before `limit`.
+ */
+export function searchModelList<T extends { id: string; favorite?: boolean }>(
+  models: T[],
+  filter: string,
+  limit?: number
+): { models: T[]; total: number; matched: number } {
+  const needle = filter.trim().toLowerCase();
+  const matches = models
+    .filter((model) => !needle || model.id.toLowerCase().includes(needle))
+    .sort((a, b) => {
+      if (Boolean(a.favorite) !== Boolean(b.favorite)) return a.favorite ? -1 : 1;
+      const left = a.id.toLowerCase();
+      const right = b.id.toLowerCase();
+      if (left !== right) return left < right ? -1 : 1;
+      return a.id < b.id ? -1 : a.id > b.id ? 1 : 0;
+    });
+  const safeLimit = limit !== undefined && Number.isFinite(limit) && limit > 0 ? Math.floor(limit) : matches.length;
+  return { models: matches.slice(0, safeLimit), total: models.length, matched: matches.length };
+}
+
+export async function fetchKoboldModels(provider: ProviderLike, signal?: AbortSignal): Promise<string[]> {
   const base = normalizeKoboldBaseUrl(provider.base_url);
   const candidates = [
     `${base}/api/v1/models`,
@@ -270,7 +543,7 @@ export async function fetchKoboldModels(provider: ProviderLike): Promise<string[
   ];
   for (const url of candidates) {
     try {
-      const response = await fetch(url, { method: "GET" });
+      const response = await fetch(url, { method: "GET", headers: withProviderHeaders(provider, {}), signal });
       if (!response.ok) continue;
       const raw = await response.text();
       let ids: string[] = [];
@@ -283,7 +556,8 @@ export async function fetchKoboldModels(provider: ProviderLike): Promise<string[
         }
       }
       if (ids.length > 0) return ids;
-    } catch {
+    } catch (error) {
+      if (signal?.aborted) throw error;
       // Try next endpoint.
     }
   }
@@ -299,7 +573,7 @@ export async function testKoboldConnection(provider: ProviderLike): Promise<bool
   ];
   for (const url of candidates) {
     try {
-      const response = await fetch(url, { method: "GET" });
+      const response = await fetch(url, { method: "GET", headers: withProviderHeaders(provider, {}) });
       if (response.ok) return true;
     } catch {
       // Try next endpoint.
@@ -0,0 +1,23 @@
+import type { ProviderFallbackTarget } from "../../src/shared/types/contracts.js";
+
+export type { ProviderFallbackTarget };
+
+const MAX_PROVIDER_FALLBACKS = 5;
+
+export function normalizeProviderFallbacks(raw: unknown): ProviderFallbackTarget[] {
+  if (!Array.isArray(raw)) return [];
+  const seen = new Set<string>();
+  const out: ProviderFallbackTarget[] = [];
+  for (const item of raw) {
+    if (!item || typeof item !== "object" || Array.isArray(item)) continue;
+    const record = item as Record<string, unknown>;
+    const providerId = typeof record.providerId === "string" ? record.providerId.trim() : "";
+    const modelId = typeof record.modelId === "string" ? record.modelId.trim() : "";
+    const key = `${providerId}\u0000${modelId}`;
+    if (!providerId || !modelId || seen.has(key)) continue;
+    seen.add(key);
+    out.push({ providerId, modelId });
+    if (out.length >= MAX_PROVIDER_FALLBACKS) break;
+  }
+  return out;
+}
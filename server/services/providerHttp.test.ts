@@ -1,7 +1,8 @@
 import { afterEach, describe, expect, it, vi } from "vitest";
-import { describeProviderFetchFailure, fetchProviderResponse } from "./providerHttp.js";
+import { describeProviderFetchFailure, fetchProviderResponse, parseRetryAfterMs } from "./providerHttp.js";
 
 afterEach(() => {
+  vi.useRealTimers();
   vi.restoreAllMocks();
 });
 
@@ -74,6 +75,62 @@ describe("fetchProviderResponse", () => {
     expect(mockedFetch).toHaveBeenCalledTimes(1);
   });
 
+  it("waits out a 429 Retry-After delay and retries", async () => {
+    const mockedFetch = vi.spyOn(globalThis, "fetch")
+      .mockResolvedValueOnce(new Response("slow down", { status: 429, headers: { "Retry-After": "0" } }))
+      .mockResolvedValueOnce(new Response("ok", { status: 200 }));
+
+    const response = await fetchProviderResponse("https://provider.example/v1/chat/completions", {}, {
+      retryDelaysMs: [0, 0]
+    });
+
+    expect(response.status).toBe(200);
+    expect(mockedFetch).toHaveBeenCalledTimes(2);
+  });
+
+  it("returns a 429 without Retry-After to the caller", async () => {
+    const mockedFetch = vi.spyOn(globalThis, "fetch")
+      .mockResolvedValue(new Response("slow down", { status: 429 }));
+
+    const response = await fetchProviderResponse("https://provider.example/v1/chat/completions", {}, {
+      retryDelaysMs: [0, 0]
+    });
+
+    expect(response.status).toBe(429);
+    expect(mockedFetch).toHaveBeenCalledTimes(1);
+  });
+
+  it("queues requests over a provider's per-minute limit instead of failing them", async () => {
+    vi.useFakeTimers();
+    const mockedFetch = vi.spyOn(globalThis, "fetch").mockImplementation(async () => new Response("ok", { status: 200 }));
+    const rateLimit = { key: "queued-provider", requestsPerMinute: 1 };
+
+    const first = await fetchProviderResponse("https://provider.example/v1/chat/completions", {}, { rateLimit });
+    let secondDone = false;
+    const second = fetchProviderResponse("https://provider.example/v1/chat/completions", {}, { rateLimit })
+      .then((response) => {
+        secondDone = true;
+        return response;
+      });
+
+    await vi.advanceTimersByTimeAsync(30_000);
+    expect(first.status).toBe(200);
+    expect(secondDone).toBe(false);
+    expect(mockedFetch).toHaveBeenCalledTimes(1);
+
+    await vi.advanceTimersByTimeAsync(30_000);
+    expect((await second).status).toBe(200);
+    expect(mockedFetch).toHaveBeenCalledTimes(2);
+  });
+
+  it("reads Retry-After as seconds or an HTTP date", () => {
+    const nowMs = Date.parse("2026-01-01T00:00:00Z");
+    expect(parseRetryAfterMs("2", nowMs)).toBe(2000);
+    expect(parseRetryAfterMs("Thu, 01 Jan 2026 00:00:05 GMT", nowMs)).toBe(5000);
+    expect(parseRetryAfterMs("soon", nowMs)).toBeNull();
+    expect(parseRetryAfterMs(null, nowMs)).toBeNull();
+  });
+
   it("reports the underlying network cause instead of a bare fetch failed", () => {
     const error = new TypeError("fetch failed", {
       cause: Object.assign(new Error("connect EHOSTUNREACH 10.0.0.2:1234"), {
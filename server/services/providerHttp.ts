@@ -1,3 +1,5 @@
+import { networkError } from "./appError.js";
+
 const DEFAULT_RETRY_DELAYS_MS = [0, 500, 1_500, 3_000, 5_000];
 const DEFAULT_RETRY_STATUSES = new Set([502, 503, 504]);
 const SAFE_CONNECT_ERROR_CODES = new Set([
@@ -16,9 +18,20 @@ const PRIVATE_SOCKET_ERROR_CODES = new Set([
   "UND_ERR_SOCKET"
 ]);
 
+const RATE_LIMIT_WINDOW_MS = 60_000;
+/** Longest Retry-After a request sits out; longer waits hand the 429 back to the caller. */
+const MAX_RETRY_AFTER_MS = 60_000;
+
+export interface ProviderRateLimit {
+  /** Requests with the same key share one budget, normally the provider id. */
+  key: string;
+  requestsPerMinute: number;
+}
+
 export interface ProviderFetchOptions {
   retryDelaysMs?: number[];
   retryStatuses?: number[];
+  rateLimit?: ProviderRateLimit | null;
 }
 
 function getErrorCause(error: unknown): unknown {
@@ -99,7 +112,7 @@ function abortReason(signal?: AbortSignal | null): Error {
   return new DOMException("The operation was aborted", "AbortError");
 }
 
-async function waitForRetry(delayMs: number, signal?: AbortSignal | null) {
+export async function waitForRetry(delayMs: number, signal?: AbortSignal | null) {
   if (delayMs <= 0) return;
   if (signal?.aborted) throw abortReason(signal);
   await new Promise<void>((resolve, reject) => {
@@ -117,11 +130,58 @@ async function waitForRetry(delayMs: number, signal?: AbortSignal | null) {
   });
 }
 
+const rateLimitStarts = new Map<string, number[]>();
+const rateLimitQueues = new Map<string, Promise<void>>();
+
+/**
+ * Waits for a free slot in the key's sliding one-minute window. Requests queue in arrival order instead of
+ * failing, so a burst of regenerations is spread out rather than tripping the provider's 429s.
+ */
+export async function acquireRateLimitSlot(limit: ProviderRateLimit | null | undefined, signal?: AbortSignal | null) {
+  const perMinute = Math.floor(Number(limit?.requestsPerMinute) || 0);
+  if (!limit || perMinute <= 0) return;
+  const previous = rateLimitQueues.get(limit.key) ?? Promise.resolve();
+  let release = () => {};
+  const turn = new Promise<void>((resolve) => {
+    release = resolve;
+  });
+  const tail = previous.then(() => turn);
+  rateLimitQueues.set(limit.key, tail);
+  try {
+    await previous;
+    for (;;) {
+      if (signal?.aborted) throw abortReason(signal);
+      const cutoff = Date.now() - RATE_LIMIT_WINDOW_MS;
+      const starts = (rateLimitStarts.get(limit.key) ?? []).filter((startedAt) => startedAt > cutoff);
+      rateLimitStarts.set(limit.key, starts);
+      if (starts.length < perMinute) {
+        starts.push(Date.now());
+        return;
+      }
+      await waitForRetry(starts[0] + RATE_LIMIT_WINDOW_MS - Date.now(), signal);
+    }
+  } finally {
+    release();
+    if (rateLimitQueues.get(limit.key) === tail) rateLimitQueues.delete(limit.key);
+  }
+}
+
+/** Delay asked for by a Retry-After header, given in seconds or as an HTTP date; null when absent or unreadable. */
+export function parseRetryAfterMs(value: string | null | undefined, nowMs = Date.now()): number | null {
+  const raw = String(value || "").trim();
+  if (!raw) return null;
+  if (/^\d+(?:\.\d+)?$/.test(raw)) return Math.round(Number(raw) * 1000);
+  const date = Date.parse(raw);
+  return Number.isFinite(date) ? Math.max(0, date - nowMs) : null;
+}
+
 /**
  * Provider requests must recover from network-interface changes without reusing
  * stale pooled sockets. Only connection failures that could not reach a public
  * provider are retried; ambiguous socket resets are retried only for local/private
- * endpoints. Explicit 502/503/504 responses are safe to retry before streaming.
+ * endpoints. Explicit 502/503/504 responses are safe to retry before streaming,
+ * and a 429 is retried once its Retry-After delay has passed. Every attempt waits
+ * for a slot under `options.rateLimit`.
  */
 export async function fetchProviderResponse(
   url: string,
@@ -137,8 +197,11 @@ export async function fetchProviderResponse(
   if (!headers.has("Cache-Control")) headers.set("Cache-Control", "no-cache");
 
   let lastError: unknown = null;
+  let retryAfterMs: number | null = null;
   for (let attempt = 0; attempt < retryDelays.length; attempt += 1) {
-    await waitForRetry(retryDelays[attempt] ?? 0, init.signal);
+    await waitForRetry(retryAfterMs ?? retryDelays[attempt] ?? 0, init.signal);
+    retryAfterMs = null;
+    await acquireRateLimitSlot(options.rateLimit, init.signal);
     try {
       const response = await fetch(url, {
         ...init,
@@ -149,6 +212,14 @@ export async function fetchProviderResponse(
         await response.body?.cancel().catch(() => undefined);
         continue;
       }
+      if (response.status === 429 && attempt < retryDelays.length - 1) {
+        const delay = parseRetryAfterMs(response.headers.get("retry-after"));
+        if (delay !== null && delay <= MAX_RETRY_AFTER_MS) {
+          await response.body?.cancel().catch(() => undefined);
+          retryAfterMs = delay;
+          continue;
+        }
+      }
       return response;
     } catch (error) {
       if (isAbortError(error, init.signal)) throw error;
@@ -158,8 +229,8 @@ export async function fetchProviderResponse(
   }
 
   const method = String(init.method || "GET").toUpperCase();
-  throw new Error(
+  throw networkError(
     `Provider request failed: ${method} ${endpointForError(url)} (${describeProviderFetchFailure(lastError)})`,
-    { cause: lastError }
+    lastError
   );
 }
@@ -0,0 +1,66 @@
+import { validationError } from "./appError.js";
+import { normalizeOpenAiBaseUrl, normalizeProviderType, type ProviderType } from "./providerApi.js";
+
+// Provider writes come from the providers route and from settings import; both store profiles through
+// `normalizeProviderProfileInput`, so an imported file cannot hold values the route would refuse.
+
+const MAX_RATE_LIMIT_RPM = 10_000;
+
+export interface ProviderProfileInput {
+  id: string;
+  name: string;
+  baseUrl: string;
+  proxyUrl: string | null;
+  fullLocalOnly: boolean;
+  providerType: ProviderType;
+  adapterId: string | null;
+  manualModels: string[];
+}
+
+function parseUrl(value: string): URL | null {
+  try {
+    return new URL(value);
+  } catch {
+    return null;
+  }
+}
+
+/** Requests-per-minute limit from a request body: null or 0 removes it, anything else must be a positive integer. */
+export function parseRateLimitRpm(raw: unknown): number | null {
+  if (raw === null || raw === "") return null;
+  const value = Number(raw);
+  if (!Number.isInteger(value) || value < 0 || value > MAX_RATE_LIMIT_RPM) {
+    throw validationError(`rateLimitRpm must be a whole number between 1 and ${MAX_RATE_LIMIT_RPM}, or null for no limit`);
+  }
+  return value || null;
+}
+
+/** Throws a `validation` error for a missing id or name, a base URL that is not http(s), or an unparsable proxy URL. */
+export function normalizeProviderProfileInput(raw: Record<string, unknown>): ProviderProfileInput {
+  const id = String(raw.id ?? "").trim();
+  const name = String(raw.name ?? "").trim();
+  if (!id) throw validationError("Provider id is required");
+  if (!name) throw validationError("Provider name is required");
+
+  const providerType = normalizeProviderType(raw.providerType);
+  const rawBaseUrl = String(raw.baseUrl ?? "").trim();
+  const protocol = parseUrl(rawBaseUrl)?.protocol;
+  if (protocol !== "http:" && protocol !== "https:") {
+    throw validationError("Provider base URL must be an http:// or https:// URL");
+  }
+  const proxyUrl = String(raw.proxyUrl ?? "").trim() || null;
+  if (proxyUrl && !parseUrl(proxyUrl)) throw validationError("Provider proxy URL is not a valid URL");
+
+  return {
+    id,
+    name,
+    baseUrl: providerType === "openai" ? normalizeOpenAiBaseUrl(rawBaseUrl) || rawBaseUrl : rawBaseUrl,
+    proxyUrl,
+    fullLocalOnly: raw.fullLocalOnly === true || raw.fullLocalOnly === 1,
+    providerType,
+    adapterId: providerType === "custom" ? String(raw.adapterId ?? "").trim() || null : null,
+    manualModels: Array.isArray(raw.manualModels)
+      ? [...new Set(raw.manualModels.map((item) => String(item || "").trim()).filter(Boolean))]
+      : []
+  };
+}
@@ -1,13 +1,16 @@
 import { createHash } from "crypto";
 import { db, isLocalhostUrl, newId, now, roughTokenCount } from "../db.js";
-
-interface ProviderRow {
-  id: string;
-  base_url: string;
-  api_key_cipher: string;
-  full_local_only: number;
-  provider_type: string;
-}
+import {
+  cosineSimilarity,
+  decodeVector,
+  embeddingModelKey,
+  embedTexts,
+  encodeVector,
+  normalizeBaseUrl,
+  requestEmbeddings,
+  resolveEmbeddingProvider,
+  type EmbeddingProviderRow as ProviderRow
+} from "./embeddings.js";
 
 interface RagChunkRow {
   id: string;
@@ -118,36 +121,6 @@ function sha256(input: string): string {
   return createHash("sha256").update(input).digest("hex");
 }
 
-function modelKey(providerId: string, model: string): string {
-  return `${providerId}:${model}`;
-}
-
-function encodeVector(vector: number[]): { blob: Buffer; norm: number } {
-  const arr = new Float32Array(vector.length);
-  let sum = 0;
-  for (let i = 0; i < vector.length; i += 1) {
-    const value = Number(vector[i]) || 0;
-    arr[i] = value;
-    sum += value * value;
-  }
-  return { blob: Buffer.from(arr.buffer), norm: Math.sqrt(sum) };
-}
-
-function decodeVector(blob: Buffer): Float32Array {
-  const byteOffset = blob.byteOffset || 0;
-  const byteLength = blob.byteLength || 0;
-  return new Float32Array(blob.buffer.slice(byteOffset, byteOffset + byteLength));
-}
-
-function cosineSimilarity(a: Float32Array, b: Float32Array, aNorm: number, bNorm: number): number {
-  if (!aNorm || !bNorm || a.length !== b.length) return 0;
-  let dot = 0;
-  for (let i = 0; i < a.length; i += 1) {
-    dot += a[i] * b[i];
-  }
-  return dot / (aNorm * bNorm);
-}
-
 function queryTerms(raw: string): string[] {
   return String(raw || "")
     .toLowerCase()
@@ -161,88 +134,6 @@ function ftsQuery(raw: string): string {
   return escaped.map((term) => `"${term}"*`).join(" OR ");
 }
 
-function normalizeEmbeddingRow(input: unknown): number[] | null {
-  if (!Array.isArray(input)) return null;
-  const out: number[] = [];
-  for (const value of input) {
-    const num = Number(value);
-    if (!Number.isFinite(num)) return null;
-    out.push(num);
-  }
-  return out.length > 0 ? out : null;
-}
-
-async function requestEmbeddings(provider: ProviderRow, model: string, input: string[]): Promise<number[][]> {
-  if (!input.length) return [];
-  const baseUrl = normalizeBaseUrl(provider.base_url);
-  const apiKey = String(provider.api_key_cipher || "").trim();
-  const headers: Record<string, string> = {
-    "Content-Type": "application/json"
-  };
-  if (apiKey) {
-    headers.Authorization = `Bearer ${apiKey}`;
-  }
-  const response = await fetch(`${baseUrl}/embeddings`, {
-    method: "POST",
-    headers,
-    body: JSON.stringify({
-      model,
-      input
-    })
-  });
-  if (!response.ok) {
-    const errText = await response.text().catch(() => "embedding request failed");
-    throw new Error(errText.slice(0, 500));
-  }
-  const body = await response.json() as {
-    data?: Array<{ embedding?: unknown }>;
-    embeddings?: unknown;
-  };
-  const rows = Array.isArray(body.data)
-    ? body.data.map((item) => normalizeEmbeddingRow(item?.embedding))
-    : Array.isArray(body.embeddings)
-      ? body.embeddings.map((item) => normalizeEmbeddingRow(item))
-      : [];
-  const valid = rows.filter((row): row is number[] => Array.isArray(row) && row.length > 0);
-  if (valid.length !== input.length) {
-    throw new Error("embedding response mismatch");
-  }
-  return valid;
-}
-
-async function embedTexts(provider: ProviderRow, model: string, input: string[]): Promise<number[][]> {
-  const batchSize = 24;
-  const out: number[][] = [];
-  for (let i = 0; i < input.length; i += batchSize) {
-    const batch = input.slice(i, i + batchSize);
-    const embedded = await requestEmbeddings(provider, model, batch);
-    out.push(...embedded);
-  }
-  return out;
-}
-
-function resolveEmbeddingProvider(settings: Record<string, unknown>): { provider: ProviderRow; model: string } | null {
-  const providerId = String(settings.ragProviderId || settings.activeProviderId || "").trim();
-  const model = String(settings.ragModel || "").trim();
-  if (!providerId || !model) return null;
-  const provider = db.prepare("SELECT * FROM providers WHERE id = ?").get(providerId) as ProviderRow | undefined;
-  if (!provider) return null;
-  const fullLocalMode = settings.fullLocalMode === true;
-  if (fullLocalMode && !isLocalhostUrl(provider.base_url)) return null;
-  if (provider.full_local_only && !isLocalhostUrl(provider.base_url)) return null;
-  return { provider, model };
-}
-
-function normalizeBaseUrl(raw: string): string {
-  return String(raw || "").trim()
-    .replace(/\/+$/, "")
-    .replace(/\/chat\/completions$/i, "")
-    .replace(/\/responses$/i, "")
-    .replace(/\/completions$/i, "")
-    .replace(/\/embeddings$/i, "")
-    .replace(/\/rerank$/i, "");
-}
-
 function buildRerankEndpoints(baseUrlRaw: string): string[] {
   const baseUrl = normalizeBaseUrl(baseUrlRaw);
   if (!baseUrl) return [];
@@ -601,7 +492,7 @@ export async function ingestRagDocument(params: {
   if (embeddingTarget) {
     try {
       vectors = await embedTexts(embeddingTarget.provider, embeddingTarget.model, chunks);
-      modelKeyUsed = modelKey(embeddingTarget.provider.id, embeddingTarget.model);
+      modelKeyUsed = embeddingModelKey(embeddingTarget.provider.id, embeddingTarget.model);
     } catch {
       vectors = [];
       modelKeyUsed = null;
@@ -869,7 +760,7 @@ async function retrieveRagContextForCollections(params: {
       const vectors = db.prepare(
         `SELECT chunk_id, vector_blob, norm FROM rag_vectors
          WHERE model_key = ? AND chunk_id IN (${chunkPlaceholders})`
-      ).all(modelKey(embeddingTarget.provider.id, embeddingTarget.model), ...ids) as RagVectorRow[];
+      ).all(embeddingModelKey(embeddingTarget.provider.id, embeddingTarget.model), ...ids) as RagVectorRow[];
       const vectorMap = new Map(vectors.map((row) => [row.chunk_id, row]));
 
       for (const item of ranked) {
@@ -6,7 +6,12 @@ describe("runtime tuning settings", () => {
     expect(normalizeRuntimeTuningSettings({})).toEqual({
       contextMaxMessages: 0,
       reasoningMaxChars: 12000,
+      loreScanDepth: 10,
+      loreTokenBudget: 2048,
       translationTimeoutSeconds: 120,
+      modelFetchTimeoutSeconds: 30,
+      streamRetryAttempts: 2,
+      writerSceneRevisionLimit: 20,
       translationTemperature: 0.2,
       translationMaxTokens: 2048,
       compressionTemperature: 0.3,
@@ -21,7 +26,12 @@ describe("runtime tuning settings", () => {
     expect(normalizeRuntimeTuningSettings({
       contextMaxMessages: 5000,
       reasoningMaxChars: 999999,
+      loreScanDepth: -1,
+      loreTokenBudget: 99999,
       translationTimeoutSeconds: 1,
+      modelFetchTimeoutSeconds: 9000,
+      streamRetryAttempts: -3,
+      writerSceneRevisionLimit: 5000,
       translationTemperature: 9,
       translationMaxTokens: 3,
       compressionTemperature: -1,
@@ -32,7 +42,12 @@ describe("runtime tuning settings", () => {
     })).toEqual({
       contextMaxMessages: 1000,
       reasoningMaxChars: 100000,
+      loreScanDepth: 0,
+      loreTokenBudget: 32768,
       translationTimeoutSeconds: 5,
+      modelFetchTimeoutSeconds: 300,
+      streamRetryAttempts: 0,
+      writerSceneRevisionLimit: 200,
       translationTemperature: 2,
       translationMaxTokens: 64,
       compressionTemperature: 0,
@@ -1,7 +1,12 @@
 export interface RuntimeTuningSettings {
   contextMaxMessages: number;
   reasoningMaxChars: number;
+  loreScanDepth: number;
+  loreTokenBudget: number;
   translationTimeoutSeconds: number;
+  modelFetchTimeoutSeconds: number;
+  streamRetryAttempts: number;
+  writerSceneRevisionLimit: number;
   translationTemperature: number;
   translationMaxTokens: number;
   compressionTemperature: number;
@@ -25,7 +30,12 @@ export function normalizeRuntimeTuningSettings(raw: Record<string, unknown>): Ru
   return {
     contextMaxMessages: integer(raw.contextMaxMessages, 0, 0, 1000),
     reasoningMaxChars: integer(raw.reasoningMaxChars, 12000, 1000, 100000),
+    loreScanDepth: integer(raw.loreScanDepth, 10, 0, 1000),
+    loreTokenBudget: integer(raw.loreTokenBudget, 2048, 0, 32768),
     translationTimeoutSeconds: integer(raw.translationTimeoutSeconds, 120, 5, 600),
+    modelFetchTimeoutSeconds: integer(raw.modelFetchTimeoutSeconds, 30, 5, 300),
+    streamRetryAttempts: integer(raw.streamRetryAttempts, 2, 0, 5),
+    writerSceneRevisionLimit: integer(raw.writerSceneRevisionLimit, 20, 1, 200),
     translationTemperature: decimal(raw.translationTemperature, 0.2, 0, 2),
     translationMaxTokens: integer(raw.translationMaxTokens, 2048, 64, 32768),
     compressionTemperature: decimal(raw.compressionTemperature, 0.3, 0, 2),
@@ -0,0 +1,178 @@
+import { createHash } from "crypto";
+import { db, now } from "../db.js";
+import { cosineSimilarity, decodeVector, embed, encodeVector } from "./embeddings.js";
+
+// Meaning-based search over characters, writer scenes and chat messages. Item vectors are embedded on the
+// first search that needs them and cached in semantic_embeddings; a changed content hash or embedding model
+// marks a row stale, and rows for deleted items are pruned on the next search of their scope.
+
+export const SEMANTIC_SEARCH_SCOPES = ["characters", "scenes", "messages"] as const;
+export type SemanticSearchScope = (typeof SEMANTIC_SEARCH_SCOPES)[number];
+
+export interface SemanticSearchHit {
+  scope: SemanticSearchScope;
+  id: string;
+  title: string;
+  snippet: string;
+  similarity: number;
+  /** Chat id for messages, chapter id for scenes, null for characters. */
+  parentId: string | null;
+}
+
+interface SearchableItem {
+  id: string;
+  title: string;
+  text: string;
+  parentId: string | null;
+}
+
+/** Newest items only, so a first search over a large library stays a bounded number of embedding calls. */
+const MAX_ITEMS_PER_SCOPE = 2000;
+const SNIPPET_LENGTH = 200;
+
+export function isSemanticSearchScope(value: unknown): value is SemanticSearchScope {
+  return SEMANTIC_SEARCH_SCOPES.includes(value as SemanticSearchScope);
+}
+
+function joinParts(parts: Array<string | null | undefined>): string {
+  return parts.map((part) => String(part || "").trim()).filter(Boolean).join("\n\n");
+}
+
+function loadItems(scope: SemanticSearchScope): SearchableItem[] {
+  if (scope === "characters") {
+    const rows = db.prepare(
+      "SELECT id, name, description, personality, scenario FROM characters ORDER BY created_at DESC LIMIT ?"
+    ).all(MAX_ITEMS_PER_SCOPE) as Array<{ id: string; name: string; description: string | null; personality: string | null; scenario: string | null }>;
+    return rows.map((row) => ({
+      id: row.id,
+      title: row.name,
+      text: joinParts([row.name, row.description, row.personality, row.scenario]),
+      parentId: null
+    }));
+  }
+  if (scope === "scenes") {
+    const rows = db.prepare(
+      "SELECT id, chapter_id, title, content, goals, conflicts, outcomes FROM writer_scenes ORDER BY created_at DESC LIMIT ?"
+    ).all(MAX_ITEMS_PER_SCOPE) as Array<{ id: string; chapter_id: string; title: string; content: string; goals: string; conflicts: string; outcomes: string }>;
+    return rows.map((row) => ({
+      id: row.id,
+      title: row.title,
+      text: joinParts([row.title, row.goals, row.conflicts, row.outcomes, row.content]),
+      parentId: row.chapter_id
+    }));
+  }
+  const rows = db.prepare(
+    `SELECT m.id, m.chat_id, m.content, c.title AS chat_title
+     FROM messages m
+     JOIN chats c ON c.id = m.chat_id
+     WHERE m.deleted = 0 AND c.deleted_at IS NULL AND m.role IN ('user', 'assistant')
+     ORDER BY m.created_at DESC
+     LIMIT ?`
+  ).all(MAX_ITEMS_PER_SCOPE) as Array<{ id: string; chat_id: string; content: string; chat_title: string }>;
+  return rows.map((row) => ({
+    id: row.id,
+    title: row.chat_title,
+    text: String(row.content || "").trim(),
+    parentId: row.chat_id
+  }));
+}
+
+function contentHash(text: string): string {
+  return createHash("sha256").update(text).digest("hex");
+}
+
+function snippetOf(text: string): string {
+  const flat = text.replace(/\s+/g, " ").trim();
+  return flat.length > SNIPPET_LENGTH ? `${flat.slice(0, SNIPPET_LENGTH).trim()}…` : flat;
+}
+
+interface CachedVector {
+  model_key: string;
+  content_hash: string;
+  vector_blob: Buffer;
+  norm: number;
+}
+
+/**
+ * Top `limit` items of `scope` by cosine similarity to `query`, best first. Returns null when no embedding
+ * model is configured; embedding request failures are thrown to the caller.
+ */
+export async function searchSemantic(
+  query: string,
+  scope: SemanticSearchScope,
+  settings: Record<string, unknown>,
+  limit = 10
+): Promise<SemanticSearchHit[] | null> {
+  const queryEmbedding = await embed([query], settings);
+  if (!queryEmbedding) return null;
+  const queryVector = encodeVector(queryEmbedding.vectors[0]);
+  const queryDecoded = decodeVector(queryVector.blob);
+  const safeLimit = Math.max(1, Math.min(100, Math.floor(limit) || 10));
+
+  const items = loadItems(scope).filter((item) => item.text);
+  const cached = new Map<string, CachedVector>();
+  const selectCached = db.prepare(
+    "SELECT model_key, content_hash, vector_blob, norm FROM semantic_embeddings WHERE scope = ? AND item_id = ?"
+  );
+  for (const item of items) {
+    const row = selectCached.get(scope, item.id) as CachedVector | undefined;
+    if (row) cached.set(item.id, row);
+  }
+
+  const stale = items.filter((item) => {
+    const row = cached.get(item.id);
+    return !row || row.model_key !== queryEmbedding.modelKey || row.content_hash !== contentHash(item.text);
+  });
+  if (stale.length > 0) {
+    const fresh = await embed(stale.map((item) => item.text), settings);
+    if (!fresh) return null;
+    const upsert = db.prepare(`
+      INSERT INTO semantic_embeddings (scope, item_id, model_key, content_hash, vector_blob, norm, updated_at)
+      VALUES (?, ?, ?, ?, ?, ?, ?)
+      ON CONFLICT(scope, item_id) DO UPDATE SET
+        model_key = excluded.model_key,
+        content_hash = excluded.content_hash,
+        vector_blob = excluded.vector_blob,
+        norm = excluded.norm,
+        updated_at = excluded.updated_at
+    `);
+    const ts = now();
+    db.transaction(() => {
+      stale.forEach((item, index) => {
+        const encoded = encodeVector(fresh.vectors[index]);
+        const hash = contentHash(item.text);
+        upsert.run(scope, item.id, fresh.modelKey, hash, encoded.blob, encoded.norm, ts);
+        cached.set(item.id, { model_key: fresh.modelKey, content_hash: hash, vector_blob: encoded.blob, norm: encoded.norm });
+      });
+    })();
+  }
+  pruneDeletedItems(scope);
+
+  return items
+    .map((item) => {
+      const row = cached.get(item.id);
+      const similarity = row ? cosineSimilarity(queryDecoded, decodeVector(row.vector_blob), queryVector.norm, row.norm) : 0;
+      return {
+        scope,
+        id: item.id,
+        title: item.title,
+        snippet: snippetOf(item.text),
+        similarity: Math.round(similarity * 10000) / 10000,
+        parentId: item.parentId
+      };
+    })
+    .sort((a, b) => b.similarity - a.similarity)
+    .slice(0, safeLimit);
+}
+
+const SCOPE_TABLES: Record<SemanticSearchScope, string> = {
+  characters: "characters",
+  scenes: "writer_scenes",
+  messages: "messages"
+};
+
+function pruneDeletedItems(scope: SemanticSearchScope): void {
+  db.prepare(
+    `DELETE FROM semantic_embeddings WHERE scope = ? AND item_id NOT IN (SELECT id FROM ${SCOPE_TABLES[scope]})`
+  ).run(scope);
+}
@@ -0,0 +1,98 @@
+import Database from "better-sqlite3";
+import { describe, expect, it } from "vitest";
+import {
+  collectSettingsExport,
+  parseSettingsExport,
+  pickImportableSettings,
+  restoreExportedProviders,
+  SETTINGS_EXPORT_VERSION
+} from "./settingsTransfer.js";
+
+function createDb() {
+  const db = new Database(":memory:");
+  db.exec(`
+    CREATE TABLE providers (
+      id TEXT PRIMARY KEY, name TEXT NOT NULL, base_url TEXT NOT NULL, api_key_cipher TEXT NOT NULL, proxy_url TEXT,
+      full_local_only INTEGER NOT NULL DEFAULT 0, provider_type TEXT NOT NULL DEFAULT 'openai', adapter_id TEXT,
+      manual_models TEXT NOT NULL DEFAULT '[]', extra_headers TEXT NOT NULL DEFAULT '{}', rate_limit_rpm INTEGER,
+      auth_mode TEXT, auth_param TEXT
+    );
+    CREATE TABLE provider_models (provider_id TEXT PRIMARY KEY, models_json TEXT NOT NULL, fetched_at TEXT NOT NULL);
+  `);
+  return db;
+}
+
+describe("settings export and import", () => {
+  it("masks provider keys and drops secrets and machine-local settings unless keys are requested", () => {
+    const db = createDb();
+    db.prepare("INSERT INTO providers (id, name, base_url, api_key_cipher, extra_headers) VALUES (?, ?, ?, ?, ?)")
+      .run("p1", "Remote", "https://api.example.com/v1", "sk-live-1234567890", JSON.stringify({ Authorization: "Bearer x" }));
+    const settings = { theme: "light", ttsApiKey: "tts-secret", mcpServers: [{ id: "shell" }] };
+
+    const masked = collectSettingsExport(db, settings, false);
+    expect(masked.settings).toEqual({ theme: "light" });
+    expect(masked.providers[0]).toMatchObject({ id: "p1", apiKeyMasked: "sk-l***7890" });
+    expect(JSON.stringify(masked)).not.toContain("sk-live-1234567890");
+    expect(masked.providers[0].headers).toBeUndefined();
+
+    const full = collectSettingsExport(db, settings, true);
+    expect(full.settings).toEqual({ theme: "light", ttsApiKey: "tts-secret" });
+    expect(full.providers[0]).toMatchObject({ apiKey: "sk-live-1234567890", headers: { Authorization: "Bearer x" } });
+  });
+
+  it("rejects foreign or newer files and keeps only known, well-typed settings", () => {
+    expect(() => parseSettingsExport("{}")).toThrow("Not a Vellium settings file");
+    expect(() => parseSettingsExport({ format: "vellium-settings", version: SETTINGS_EXPORT_VERSION + 1 }))
+      .toThrow("newer than this app supports");
+    expect(() => parseSettingsExport({ format: "vellium-settings", version: 1, providers: {} })).toThrow("providers must be an array");
+
+    const { patch, ignored } = pickImportableSettings(
+      { theme: "light", fontScale: "huge", unknownField: 1, sttApiKey: "k", mcpServers: [], samplerConfig: { temperature: 0.7 } },
+      false
+    );
+    expect(patch).toEqual({ theme: "light", samplerConfig: { temperature: 0.7 } });
+    expect(ignored.sort()).toEqual(["fontScale", "mcpServers", "sttApiKey", "unknownField"]);
+  });
+
+  it("upserts providers and keeps stored keys when the import leaves them out", () => {
+    const db = createDb();
+    db.prepare("INSERT INTO providers (id, name, base_url, api_key_cipher) VALUES (?, ?, ?, ?)")
+      .run("p1", "Old name", "https://old.example.com/v1", "stored-key");
+    const providers = [
+      { id: "p1", name: "Renamed", baseUrl: "https://new.example.com/v1", apiKeyMasked: "stor***-key", manualModels: ["m1", "m1"] },
+      { id: "p2", name: "Local", baseUrl: "http://localhost:5001", providerType: "koboldcpp", apiKey: "file-key", authMode: "query", authParam: "api_key" },
+      { id: "", name: "Broken", baseUrl: "x" }
+    ];
+
+    expect(restoreExportedProviders(db, providers, false)).toEqual({ restored: 2, ignored: ["providers[2]"] });
+    expect(db.prepare("SELECT id, name, base_url, api_key_cipher, provider_type, manual_models FROM providers ORDER BY id").all()).toEqual([
+      { id: "p1", name: "Renamed", base_url: "https://new.example.com/v1", api_key_cipher: "stored-key", provider_type: "openai", manual_models: "[\"m1\"]" },
+      { id: "p2", name: "Local", base_url: "http://localhost:5001", api_key_cipher: "local-key", provider_type: "koboldcpp", manual_models: "[]" }
+    ]);
+
+    expect(db.prepare("SELECT auth_mode, auth_param FROM providers ORDER BY id").all()).toEqual([
+      { auth_mode: "bearer", auth_param: null },
+      { auth_mode: "query", auth_param: "api_key" }
+    ]);
+    expect(collectSettingsExport(db, {}, false).providers[0]).toMatchObject({ authMode: "query", authParam: "api_key" });
+
+    restoreExportedProviders(db, providers, true);
+    expect(db.prepare("SELECT api_key_cipher FROM providers WHERE id = 'p2'").get()).toEqual({ api_key_cipher: "file-key" });
+  });
+
+  it("applies the providers route's checks and normalization to imported providers", () => {
+    const db = createDb();
+    const result = restoreExportedProviders(db, [
+      { id: "bare", name: "Bare OpenAI", baseUrl: "https://api.example.com/", proxyUrl: "  " },
+      { id: "file-url", name: "File URL", baseUrl: "file:///etc/passwd" },
+      { id: "bad-proxy", name: "Bad Proxy", baseUrl: "https://api.example.com/v1", proxyUrl: "not a url" },
+      { id: "bad-limit", name: "Bad Limit", baseUrl: "https://api.example.com/v1", rateLimitRpm: 2.5 },
+      "not-a-provider"
+    ], false);
+
+    expect(result).toEqual({ restored: 1, ignored: ["providers[1]", "providers[2]", "providers[3]", "providers[4]"] });
+    expect(db.prepare("SELECT id, base_url, proxy_url FROM providers").all()).toEqual([
+      { id: "bare", base_url: "https://api.example.com/v1", proxy_url: null }
+    ]);
+  });
+});
@@ -0,0 +1,236 @@
+import type Database from "better-sqlite3";
+import { DEFAULT_SETTINGS } from "../db/defaultSettings.js";
+import { maskApiKey } from "../db/utils.js";
+import { normalizeProviderAuthMode, normalizeProviderAuthParam, normalizeProviderHeaders, normalizeProviderType } from "./providerApi.js";
+import { normalizeProviderProfileInput, parseRateLimitRpm, type ProviderProfileInput } from "./providerProfiles.js";
+
+export const SETTINGS_EXPORT_FORMAT = "vellium-settings";
+export const SETTINGS_EXPORT_VERSION = 1;
+
+// MCP servers and managed backends launch local commands and grants widen plugin access, so they never travel
+// in a settings file; a full backup is the way to move those.
+const MACHINE_LOCAL_SETTINGS = new Set(["mcpServers", "managedBackends", "pluginPermissionGrants", "security"]);
+const SECRET_SETTINGS = new Set(["ttsApiKey", "sttApiKey", "moderationApiKey"]);
+
+interface ProviderRow {
+  id: string;
+  name: string;
+  base_url: string;
+  api_key_cipher: string;
+  proxy_url: string | null;
+  full_local_only: number;
+  provider_type: string;
+  adapter_id: string | null;
+  manual_models: string | null;
+  extra_headers: string | null;
+  rate_limit_rpm: number | null;
+  auth_mode: string | null;
+  auth_param: string | null;
+}
+
+export interface ExportedProvider {
+  id: string;
+  name: string;
+  baseUrl: string;
+  apiKey?: string;
+  apiKeyMasked?: string;
+  proxyUrl: string | null;
+  fullLocalOnly: boolean;
+  providerType: string;
+  adapterId: string | null;
+  manualModels: string[];
+  headers?: Record<string, string>;
+  rateLimitRpm?: number | null;
+  authMode?: string;
+  authParam?: string | null;
+}
+
+export interface SettingsExport {
+  format: typeof SETTINGS_EXPORT_FORMAT;
+  version: number;
+  exportedAt: string;
+  includesKeys: boolean;
+  settings: Record<string, unknown>;
+  providers: ExportedProvider[];
+}
+
+function parseStringList(raw: unknown): string[] {
+  let value = raw;
+  if (typeof value === "string") {
+    try {
+      value = JSON.parse(value || "[]");
+    } catch {
+      return [];
+    }
+  }
+  return Array.isArray(value) ? [...new Set(value.map((item) => String(item || "").trim()).filter(Boolean))] : [];
+}
+
+/** Without keys, provider keys are masked and custom headers (which often carry auth) are left out. */
+export function collectSettingsExport(
+  db: Database.Database,
+  settings: Record<string, unknown>,
+  includeKeys: boolean
+): SettingsExport {
+  const exportedSettings = Object.fromEntries(
+    Object.entries(settings).filter(([key]) => !MACHINE_LOCAL_SETTINGS.has(key) && (includeKeys || !SECRET_SETTINGS.has(key)))
+  );
+  const rows = db.prepare("SELECT * FROM providers ORDER BY name ASC").all() as ProviderRow[];
+  return {
+    format: SETTINGS_EXPORT_FORMAT,
+    version: SETTINGS_EXPORT_VERSION,
+    exportedAt: new Date().toISOString(),
+    includesKeys: includeKeys,
+    settings: exportedSettings,
+    providers: rows.map((row) => ({
+      id: row.id,
+      name: row.name,
+      baseUrl: row.base_url,
+      ...(includeKeys
+        ? { apiKey: row.api_key_cipher, headers: normalizeProviderHeaders(row.extra_headers) }
+        : { apiKeyMasked: maskApiKey(row.api_key_cipher) }),
+      proxyUrl: row.proxy_url,
+      fullLocalOnly: row.full_local_only === 1,
+      providerType: normalizeProviderType(row.provider_type),
+      adapterId: row.adapter_id,
+      manualModels: parseStringList(row.manual_models),
+      rateLimitRpm: row.rate_limit_rpm ?? null,
+      authMode: normalizeProviderAuthMode(row.auth_mode),
+      authParam: row.auth_param
+    }))
+  };
+}
+
+export function parseSettingsExport(raw: unknown): SettingsExport {
+  let value = raw;
+  if (typeof value === "string") {
+    try {
+      value = JSON.parse(value);
+    } catch {
+      throw new Error("Not a Vellium settings file");
+    }
+  }
+  const file = value as Partial<SettingsExport> | null;
+  if (!file || typeof file !== "object" || file.format !== SETTINGS_EXPORT_FORMAT) {
+    throw new Error("Not a Vellium settings file");
+  }
+  if (!Number.isInteger(file.version) || Number(file.version) > SETTINGS_EXPORT_VERSION) {
+    throw new Error(`Settings format version ${file.version} is newer than this app supports (${SETTINGS_EXPORT_VERSION})`);
+  }
+  if (file.settings !== undefined && (!file.settings || typeof file.settings !== "object" || Array.isArray(file.settings))) {
+    throw new Error("settings must be an object");
+  }
+  if (file.providers !== undefined && !Array.isArray(file.providers)) {
+    throw new Error("providers must be an array");
+  }
+  return {
+    format: SETTINGS_EXPORT_FORMAT,
+    version: Number(file.version),
+    exportedAt: String(file.exportedAt || ""),
+    includesKeys: file.includesKeys === true,
+    settings: file.settings ?? {},
+    providers: file.providers ?? []
+  };
+}
+
+/**
+ * Keeps only fields the current settings schema knows, with the same JSON type as their default. Absent
+ * fields are simply not in the patch, so the stored values survive the merge.
+ */
+export function pickImportableSettings(
+  settings: Record<string, unknown>,
+  includeKeys: boolean
+): { patch: Record<string, unknown>; ignored: string[] } {
+  const defaults = DEFAULT_SETTINGS as Record<string, unknown>;
+  const patch: Record<string, unknown> = {};
+  const ignored: string[] = [];
+  for (const [key, value] of Object.entries(settings)) {
+    const fallback = defaults[key];
+    const sameType = fallback === null || fallback === undefined
+      ? value === null || typeof value === "string"
+      : Array.isArray(fallback) ? Array.isArray(value) : typeof value === typeof fallback && value !== null && !Array.isArray(value);
+    if (!(key in defaults) || MACHINE_LOCAL_SETTINGS.has(key) || (!includeKeys && SECRET_SETTINGS.has(key)) || !sameType) {
+      ignored.push(key);
+      continue;
+    }
+    patch[key] = value;
+  }
+  return { patch, ignored };
+}
+
+/**
+ * Upserts providers by id after the same checks the providers route applies; entries that fail them are
+ * skipped and reported as `providers[index]`. Without keys (or when the file has none) an existing provider
+ * keeps its stored key and headers; a new one gets the same placeholder key the providers route uses.
+ */
+export function restoreExportedProviders(
+  db: Database.Database,
+  providers: unknown[],
+  includeKeys: boolean
+): { restored: number; ignored: string[] } {
+  const existing = db.prepare("SELECT api_key_cipher, extra_headers FROM providers WHERE id = ?");
+  const upsert = db.prepare(`
+    INSERT INTO providers (
+      id, name, base_url, api_key_cipher, proxy_url, full_local_only, provider_type, adapter_id, manual_models, extra_headers,
+      rate_limit_rpm, auth_mode, auth_param
+    )
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT(id) DO UPDATE SET
+      name = excluded.name,
+      base_url = excluded.base_url,
+      api_key_cipher = excluded.api_key_cipher,
+      proxy_url = excluded.proxy_url,
+      full_local_only = excluded.full_local_only,
+      provider_type = excluded.provider_type,
+      adapter_id = excluded.adapter_id,
+      manual_models = excluded.manual_models,
+      extra_headers = excluded.extra_headers,
+      rate_limit_rpm = excluded.rate_limit_rpm,
+      auth_mode = excluded.auth_mode,
+      auth_param = excluded.auth_param
+  `);
+  const clearModels = db.prepare("DELETE FROM provider_models WHERE provider_id = ?");
+  let restored = 0;
+  const ignored: string[] = [];
+  for (const [index, raw] of providers.entries()) {
+    if (!raw || typeof raw !== "object" || Array.isArray(raw)) {
+      ignored.push(`providers[${index}]`);
+      continue;
+    }
+    const item = raw as Partial<ExportedProvider>;
+    let profile: ProviderProfileInput;
+    let rateLimitRpm: number | null;
+    try {
+      profile = normalizeProviderProfileInput(raw as Record<string, unknown>);
+      rateLimitRpm = item.rateLimitRpm === undefined ? null : parseRateLimitRpm(item.rateLimitRpm);
+    } catch {
+      ignored.push(`providers[${index}]`);
+      continue;
+    }
+    const { id } = profile;
+    const stored = existing.get(id) as { api_key_cipher: string; extra_headers: string | null } | undefined;
+    const apiKey = includeKeys && typeof item.apiKey === "string" && item.apiKey.trim()
+      ? item.apiKey.trim()
+      : stored?.api_key_cipher || "local-key";
+    const headers = includeKeys && item.headers !== undefined ? item.headers : stored?.extra_headers;
+    const authMode = normalizeProviderAuthMode(item.authMode);
+    upsert.run(
+      id,
+      profile.name,
+      profile.baseUrl,
+      apiKey,
+      profile.proxyUrl,
+      profile.fullLocalOnly ? 1 : 0,
+      profile.providerType,
+      profile.adapterId,
+      JSON.stringify(profile.manualModels),
+      JSON.stringify(normalizeProviderHeaders(headers)),
+      rateLimitRpm,
+      authMode,
+      normalizeProviderAuthParam(authMode, item.authParam)
+    );
+    clearModels.run(id);
+    restored += 1;
+  }
+  return { restored, ignored };
+}
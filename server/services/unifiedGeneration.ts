@@ -1,8 +1,11 @@
 import { splitThinkContent } from "../modules/chat/reasoning.js";
 import { buildKoboldSamplerConfig, buildOpenAiSamplingPayload, normalizeApiParamPolicy } from "./apiParamPolicy.js";
 import { completeCustomAdapter } from "./customProviderAdapters.js";
+import { completeAnthropicMessages } from "./anthropicApi.js";
+import { completeGeminiChat } from "./geminiApi.js";
+import { completeOllamaChat } from "./ollamaApi.js";
 import { fetchProviderResponse } from "./providerHttp.js";
-import { buildKoboldGenerateBody, extractKoboldGeneratedText, normalizeProviderType, requestKoboldGenerate } from "./providerApi.js";
+import { buildKoboldGenerateBody, extractKoboldGeneratedText, normalizeProviderType, normalizeOpenAiBaseUrl, readProviderErrorDetail, providerAuthHeaders, providerRateLimitOptions, requestKoboldGenerate, withProviderAuthUrl, withProviderHeaders, type ProviderType } from "./providerApi.js";
 
 export interface UnifiedProviderRow {
   id: string;
@@ -11,6 +14,9 @@ export interface UnifiedProviderRow {
   api_key_cipher: string;
   provider_type: string | null;
   adapter_id?: string | null;
+  rate_limit_rpm?: number | null;
+  auth_mode?: string | null;
+  auth_param?: string | null;
 }
 
 export interface UnifiedGenerateMessage {
@@ -27,13 +33,6 @@ const KOBOLD_TAGS = {
   outputClose: "{{[OUTPUT_END]}}"
 };
 
-function normalizeOpenAiBaseUrl(raw: string): string {
-  const trimmed = String(raw || "").trim().replace(/\/+$/, "");
-  if (!trimmed) return "";
-  if (/\/v1$/i.test(trimmed)) return trimmed;
-  return `${trimmed}/v1`;
-}
-
 function providerSupportsDeveloperRole(provider: UnifiedProviderRow) {
   return /(^https?:\/\/)?([a-z0-9-]+\.)*openai\.com(\/|$)/i.test(String(provider.base_url || "").trim());
 }
@@ -148,7 +147,7 @@ export async function unifiedGenerateText(params: {
   samplerConfig?: Record<string, unknown>;
   apiParamPolicy?: unknown;
   signal?: AbortSignal;
-}): Promise<{ content: string; reasoning: string; providerType: "openai" | "koboldcpp" | "custom" }> {
+}): Promise<{ content: string; reasoning: string; providerType: ProviderType }> {
   const providerType = normalizeProviderType(params.provider.provider_type);
   const sc = params.samplerConfig || {};
 
@@ -170,14 +169,38 @@ export async function unifiedGenerateText(params: {
     });
     const response = await requestKoboldGenerate(params.provider, body, params.signal);
     if (!response.ok) {
-      const errText = await response.text().catch(() => "");
-      throw new Error(errText || `KoboldCpp request failed (${response.status})`);
+      const detail = await readProviderErrorDetail(response, "");
+      throw new Error(`KoboldCpp request failed (${response.status})${detail ? `: ${detail}` : ""}`);
     }
     const generated = extractKoboldGeneratedText(await response.json().catch(() => ({})));
     const split = splitThinkContent(generated);
     return { content: split.content, reasoning: split.reasoning, providerType };
   }
 
+  if (providerType === "ollama" || providerType === "anthropic" || providerType === "gemini") {
+    const complete = providerType === "anthropic"
+      ? completeAnthropicMessages
+      : providerType === "gemini" ? completeGeminiChat : completeOllamaChat;
+    const result = await complete({
+      provider: params.provider,
+      modelId: params.modelId,
+      messages: params.messages,
+      samplerConfig: {
+        ...sc,
+        temperature: sc.temperature ?? 0.7,
+        maxTokens: sc.maxTokens ?? 1024
+      },
+      apiParamPolicy: params.apiParamPolicy,
+      signal: params.signal
+    });
+    const split = splitThinkContent(result.content);
+    return {
+      content: split.content,
+      reasoning: [result.reasoning, split.reasoning].filter(Boolean).join("\n\n").trim(),
+      providerType
+    };
+  }
+
   if (providerType === "custom") {
     const generated = await completeCustomAdapter({
       provider: params.provider,
@@ -205,12 +228,12 @@ export async function unifiedGenerateText(params: {
       maxTokens: 1024
     }
   });
-  const response = await fetchProviderResponse(`${baseUrl}/chat/completions`, {
+  const response = await fetchProviderResponse(withProviderAuthUrl(params.provider, `${baseUrl}/chat/completions`), {
     method: "POST",
-    headers: {
+    headers: withProviderHeaders(params.provider, {
       "Content-Type": "application/json",
-      Authorization: `Bearer ${params.provider.api_key_cipher}`
-    },
+      ...providerAuthHeaders(params.provider)
+    }),
     body: JSON.stringify({
       model: params.modelId,
       messages: params.messages.map((message) => ({
@@ -220,10 +243,10 @@ export async function unifiedGenerateText(params: {
       ...openAiSampling
     }),
     signal: params.signal
-  });
+  }, providerRateLimitOptions(params.provider));
   if (!response.ok) {
-    const errText = await response.text().catch(() => "");
-    throw new Error(errText || `OpenAI-compatible request failed (${response.status})`);
+    const detail = await readProviderErrorDetail(response, "");
+    throw new Error(`OpenAI-compatible request failed (${response.status})${detail ? `: ${detail}` : ""}`);
   }
   const body = await response.json().catch(() => ({})) as {
     choices?: Array<{
@@ -5,6 +5,8 @@ import { api } from "./shared/api";
 import { TitleBar } from "./components/TitleBar";
 import { TaskManager } from "./components/TaskManager";
 import { UpdateNotification } from "./components/UpdateNotification";
+import { AccountLockButton, AccountUnlockScreen } from "./components/AccountLock";
+import { ACCOUNT_LOCKED_EVENT } from "./shared/errors";
 import type { BackgroundTaskScope } from "./shared/backgroundTasks";
 import type { AppSettings, PluginCatalog, PluginDescriptor } from "./shared/types/contracts";
 import { hasCompletedWelcomeTour, resetWelcomeTourProgress, WelcomeTour, WELCOME_TOUR_START_EVENT } from "./features/welcome/WelcomeTour";
@@ -326,6 +328,7 @@ function AppContent({
   const toolbarNode = (
     <div className="flex items-center gap-2" style={noDrag}>
       <TaskManager isElectron={isElectron} onOpenScope={openTaskScope} />
+      <AccountLockButton />
       <PluginActionBar location="app.toolbar" />
     </div>
   );
@@ -530,10 +533,11 @@ export function App() {
   const [locale, setLocale] = useState<Locale>("en");
   const [initialSettings, setInitialSettings] = useState<AppSettings | null>(null);
   const [isBooting, setIsBooting] = useState(true);
+  const [accountLocked, setAccountLocked] = useState(false);
   const isElectron = !!window.electronAPI;
 
-  useEffect(() => {
-    Promise.all([api.settingsGet(), api.pluginsList().catch(() => null)]).then(([s, catalog]) => {
+  function loadInitialSettings() {
+    return Promise.all([api.settingsGet(), api.pluginsList().catch(() => null)]).then(([s, catalog]) => {
       setInitialSettings(s);
       applyTheme(s.theme ?? "dark", findPluginTheme(catalog, s.pluginThemeId));
       applyDisplaySettings(s);
@@ -547,7 +551,20 @@ export function App() {
         setLocale(s.interfaceLanguage);
       }
     }).catch(() => {}).finally(() => setIsBooting(false));
+  }
+
+  useEffect(() => {
+    // A locked account answers 423 to every settings and data request, so ask for the password first.
+    void api.accountStatus().catch(() => null).then((status) => {
+      if (status?.locked) {
+        setAccountLocked(true);
+        setIsBooting(false);
+        return;
+      }
+      return loadInitialSettings();
+    });
 
+    const lockedHandler = () => setAccountLocked(true);
     const handler = (e: Event) => {
       setLocale((e as CustomEvent).detail as Locale);
     };
@@ -585,12 +602,14 @@ export function App() {
       if (!next || typeof next !== "object") return;
       setInitialSettings(next);
     };
+    window.addEventListener(ACCOUNT_LOCKED_EVENT, lockedHandler);
     window.addEventListener("locale-change", handler);
     window.addEventListener("theme-change", themeHandler);
     window.addEventListener("display-settings-change", displayHandler);
     window.addEventListener("onboarding-reset", onboardingResetHandler);
     window.addEventListener("settings-change", settingsChangeHandler);
     return () => {
+      window.removeEventListener(ACCOUNT_LOCKED_EVENT, lockedHandler);
       window.removeEventListener("locale-change", handler);
       window.removeEventListener("theme-change", themeHandler);
       window.removeEventListener("display-settings-change", displayHandler);
@@ -609,6 +628,22 @@ export function App() {
     }
   }
 
+  function handleUnlocked() {
+    setAccountLocked(false);
+    if (!initialSettings) {
+      setIsBooting(true);
+      void loadInitialSettings();
+    }
+  }
+
+  if (accountLocked && !initialSettings) {
+    return (
+      <I18nContext.Provider value={locale}>
+        <AccountUnlockScreen onUnlocked={handleUnlocked} />
+      </I18nContext.Provider>
+    );
+  }
+
   return (
     <I18nContext.Provider value={locale}>
       {isBooting ? (
@@ -652,6 +687,7 @@ export function App() {
         && !initialSettings.fullLocalMode
         ? <UpdateNotification />
         : null}
+      {accountLocked ? <AccountUnlockScreen onUnlocked={handleUnlocked} /> : null}
     </I18nContext.Provider>
   );
 }
@@ -0,0 +1,125 @@
+import { useEffect, useState, type FormEvent } from "react";
+import { api } from "../shared/api";
+import { ACCOUNT_LOCKED_EVENT } from "../shared/errors";
+import { useI18n } from "../shared/i18n";
+import { IconButton } from "./IconButton";
+
+const LOCK_ICON_PATH = "M16.5 10.5V6.75a4.5 4.5 0 10-9 0v3.75m-.75 11.25h10.5a2.25 2.25 0 002.25-2.25v-6.75a2.25 2.25 0 00-2.25-2.25H6.75a2.25 2.25 0 00-2.25 2.25v6.75a2.25 2.25 0 002.25 2.25z";
+
+function LockIcon({ className }: { className: string }) {
+  return (
+    <svg className={className} viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth={1.8}>
+      <path strokeLinecap="round" strokeLinejoin="round" d={LOCK_ICON_PATH} />
+    </svg>
+  );
+}
+
+/** Toolbar action that locks the account right away; hidden when no account has been created. */
+export function AccountLockButton() {
+  const { t } = useI18n();
+  const [hasAccount, setHasAccount] = useState(false);
+
+  useEffect(() => {
+    let active = true;
+    void api.accountStatus()
+      .then((status) => {
+        if (active) setHasAccount(status.hasAccount);
+      })
+      .catch(() => {});
+    return () => {
+      active = false;
+    };
+  }, []);
+
+  if (!hasAccount) return null;
+
+  return (
+    <IconButton
+      label={t("account.lock")}
+      icon={<LockIcon className="h-4 w-4" />}
+      onClick={() => {
+        void api.accountLock()
+          .then(() => window.dispatchEvent(new CustomEvent(ACCOUNT_LOCKED_EVENT)))
+          .catch(() => {});
+      }}
+    />
+  );
+}
+
+/** Covers the app while the account is locked; the server refuses every data route until this succeeds. */
+export function AccountUnlockScreen({ onUnlocked }: { onUnlocked: () => void }) {
+  const { t } = useI18n();
+  const [password, setPassword] = useState("");
+  const [recoveryKey, setRecoveryKey] = useState("");
+  const [busy, setBusy] = useState(false);
+  const [failed, setFailed] = useState(false);
+
+  async function submit(event: FormEvent) {
+    event.preventDefault();
+    if (busy || (!password && !recoveryKey.trim())) return;
+    setBusy(true);
+    setFailed(false);
+    try {
+      const unlocked = await api.accountUnlock(password, recoveryKey.trim() || undefined);
+      if (unlocked) {
+        setPassword("");
+        setRecoveryKey("");
+        onUnlocked();
+      } else {
+        setFailed(true);
+      }
+    } catch {
+      setFailed(true);
+    }
+    setBusy(false);
+  }
+
+  return (
+    <div className="fixed inset-0 z-[300] flex items-center justify-center bg-bg-primary/95 p-4 backdrop-blur-sm">
+      <form
+        onSubmit={(event) => void submit(event)}
+        className="w-[min(24rem,100%)] rounded-2xl border border-border bg-bg-secondary p-5 shadow-2xl"
+        aria-labelledby="account-unlock-title"
+      >
+        <div className="flex items-center gap-3">
+          <div className="flex h-10 w-10 flex-none items-center justify-center rounded-xl bg-accent-subtle text-accent">
+            <LockIcon className="h-5 w-5" />
+          </div>
+          <div className="min-w-0">
+            <h2 id="account-unlock-title" className="text-sm font-semibold text-text-primary">{t("account.lockedTitle")}</h2>
+            <p className="mt-0.5 text-xs leading-5 text-text-secondary">{t("account.lockedDesc")}</p>
+          </div>
+        </div>
+        <label className="mt-4 block text-xs font-medium text-text-secondary">
+          {t("account.password")}
+          <input
+            type="password"
+            autoFocus
+            autoComplete="current-password"
+            value={password}
+            onChange={(event) => setPassword(event.target.value)}
+            className="mt-1 w-full rounded-lg border border-border bg-bg-primary px-3 py-2 text-sm text-text-primary"
+          />
+        </label>
+        <label className="mt-3 block text-xs font-medium text-text-secondary">
+          {t("account.recoveryKey")}
+          <input
+            type="password"
+            autoComplete="off"
+            value={recoveryKey}
+            onChange={(event) => setRecoveryKey(event.target.value)}
+            className="mt-1 w-full rounded-lg border border-border bg-bg-primary px-3 py-2 text-sm text-text-primary"
+          />
+        </label>
+        {failed ? <p className="mt-3 text-xs text-danger" role="alert">{t("account.unlockFailed")}</p> : null}
+        <button
+          type="submit"
+          disabled={busy || (!password && !recoveryKey.trim())}
+          className="mt-4 w-full rounded-lg bg-accent px-3 py-2 text-xs font-semibold text-text-inverse transition-opacity hover:opacity-90 disabled:opacity-40"
+        >
+          {t("account.unlock")}
+        </button>
+      </form>
+    </div>
+  );
+}
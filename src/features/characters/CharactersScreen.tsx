@@ -606,6 +606,19 @@ export function CharactersScreen() {
     }
   }
 
+  async function handleExportPng() {
+    if (!selected) return;
+    try {
+      const blob = await api.characterExportPng(selected.id);
+      await triggerBlobDownload(blob, `${buildFilenameBase(selected.name, "character")}.png`);
+      setSaveStatus(t("chars.exportPng"));
+      setSaveStatusType("success");
+    } catch (error) {
+      setSaveStatus(error instanceof Error ? error.message : String(error));
+      setSaveStatusType("error");
+    }
+  }
+
   async function handleDelete() {
     if (!selected || deletingCharacter) return;
     const id = selected.id;
@@ -684,8 +697,10 @@ export function CharactersScreen() {
     setImportSuccess("");
     try {
       const file = e.target.files[0];
-      const text = await file.text();
-      const result = await api.characterImportV2(text);
+      const isPng = file.type === "image/png" || file.name.toLowerCase().endsWith(".png");
+      const result = isPng
+        ? await api.characterImportPng(await fileToBase64(file))
+        : await api.characterImportV2(await file.text());
       setCharacters((prev) => [result, ...prev]);
       setSelected(result);
       setImportSuccess(`${t("chars.importedFromFile")}: ${result.name}`);
@@ -1020,7 +1035,7 @@ export function CharactersScreen() {
                 </svg>
               </button>
             </div>
-            <input ref={jsonFileRef} type="file" accept=".json" onChange={handleFileImport} className="hidden" />
+            <input ref={jsonFileRef} type="file" accept=".json,.png" onChange={handleFileImport} className="hidden" />
             {importError && (
               <div className="rounded-md border border-danger-border bg-danger-subtle px-2 py-1 text-[10px] text-danger">{importError}</div>
             )}
@@ -1149,6 +1164,18 @@ export function CharactersScreen() {
                   </svg>
                   {t("chars.exportJson")}
                 </button>
+                {selected.avatarUrl?.toLowerCase().endsWith(".png") && (
+                  <button
+                    onClick={() => { void handleExportPng(); }}
+                    className="char-editor-btn"
+                    title={t("chars.exportPng")}
+                  >
+                    <svg className="h-3.5 w-3.5" fill="none" viewBox="0 0 24 24" stroke="currentColor" strokeWidth={2}>
+                      <path strokeLinecap="round" strokeLinejoin="round" d="M4 16v1a3 3 0 003 3h10a3 3 0 003-3v-1m-4-4l-4 4m0 0l-4-4m4 4V4" />
+                    </svg>
+                    {t("chars.exportPng")}
+                  </button>
+                )}
                 <button onClick={handleDelete} disabled={deletingCharacter || savingCharacter} className="char-editor-btn is-danger">
                   <svg viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth={1.8}><path strokeLinecap="round" strokeLinejoin="round" d="M5 7h14M9 7V4h6v3m-8 0l1 13h8l1-13M10 11v5m4-5v5" /></svg>
                   {t("chat.delete")}
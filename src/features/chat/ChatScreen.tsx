@@ -5,6 +5,7 @@ import { IconButton } from "../../components/IconButton";
 import { ThreePanelLayout, PanelTitle, Badge, EmptyState } from "../../components/Panels";
 import { PluginActionBar, PluginSlotMount } from "../plugins/PluginHost";
 import { api, resolveApiAssetUrl } from "../../shared/api";
+import { formatAppError } from "../../shared/errors";
 import { useI18n } from "../../shared/i18n";
 import { getSceneLevelTranslationKey, type SceneLevelAxis } from "../../shared/sceneLevels";
 import type {
@@ -121,10 +122,12 @@ export function ChatScreen() {
   const [streamingToolsExpanded, setStreamingToolsExpanded] = useState(false);
   const [streamingReasoningExpanded, setStreamingReasoningExpanded] = useState(false);
   const [errorText, setErrorText] = useState<string>("");
+  const [contextTrim, setContextTrim] = useState<{ chatId: string; omitted: number } | null>(null);
+  const [providerFallback, setProviderFallback] = useState<{ chatId: string; from: string; to: string; reason: string } | null>(null);
   const [ttsRealtime, setTtsRealtime] = useState(false);
   const { ttsLoadingId, ttsPlayingId, handleTts } = useTtsPlayback(ttsRealtime, setErrorText);
   const { rpReasoningEnabled, setRpReasoningEnabled, savingRpReasoning, toggleRpReasoning } = useRpReasoningToggle(setErrorText);
-  const { branches, setBranches, activeBranchId, setActiveBranchId, forkBranch: handleFork, renameBranch, removeBranch } = useBranchManagement({ activeChat, setMessages, setErrorText });
+  const { branches, setBranches, activeBranchId, setActiveBranchId, selectBranch, forkBranch: handleFork, renameBranch, removeBranch, mergeBranch } = useBranchManagement({ activeChat, setMessages, setErrorText });
   const { exportingChat, exportChat: exportChatJson } = useChatJsonExport(setErrorText);
   const {
     translatingId,
@@ -703,6 +706,14 @@ export function ChatScreen() {
     }
   }
 
+  function handleStreamEvent(event: Record<string, unknown>) {
+    if (event.type === "provider_fallback") {
+      setProviderFallback({ chatId: String(event.chatId || ""), from: String(event.fromModel || ""), to: String(event.toModel || ""), reason: String(event.reason || "") });
+    }
+    if (event.type !== "context_trimmed") return;
+    setContextTrim({ chatId: String(event.chatId || ""), omitted: Number(event.omittedMessages) || 0 });
+  }
+
   function handleStreamingToolEvent(event: {
     phase: "start" | "delta" | "done";
     callId: string;
@@ -838,7 +849,21 @@ export function ChatScreen() {
       setActiveChat((prev) => (prev && prev.id === chatId ? { ...prev, title: result.title } : prev));
       cancelRenameChat();
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
+    }
+  }
+
+  async function autoTitleChat(chatId: string) {
+    setErrorText("");
+    try {
+      const result = await api.chatAutoTitle(chatId);
+      setChats((prev) => prev.map((chat) => (
+        chat.id === chatId ? { ...chat, title: result.title } : chat
+      )));
+      setActiveChat((prev) => (prev && prev.id === chatId ? { ...prev, title: result.title } : prev));
+      cancelRenameChat();
+    } catch (error) {
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -885,6 +910,7 @@ export function ChatScreen() {
       const updated = await api.chatSend(chatId, currentInput, branchId || undefined, {
         onDelta: appendStreamDelta,
         onToolEvent: handleStreamingToolEvent,
+        onEvent: handleStreamEvent,
         onDone: () => {}
       }, activePersonaPayload, currentAttachments);
       if (activeChatIdRef.current === chatId) setMessages(updated);
@@ -899,7 +925,7 @@ export function ChatScreen() {
         failBackgroundTask(taskId, String(error));
         clearChatBackgroundTask(taskId);
       }
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -918,7 +944,7 @@ export function ChatScreen() {
       }
       if (activeChatIdRef.current === targetChatId) await refreshActiveTimeline();
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -933,8 +959,9 @@ export function ChatScreen() {
       const updated = await api.chatRegenerate(targetChatId, activeBranchId || undefined, {
         onDelta: appendStreamDelta,
         onToolEvent: handleStreamingToolEvent,
+        onEvent: handleStreamEvent,
         onDone: () => {}
-      });
+      }, activePersonaPayload);
       if (activeChatIdRef.current === targetChatId) setMessages(updated);
       stopStreamingUi();
       if (backgroundChatTaskIdRef.current === taskId) {
@@ -947,7 +974,7 @@ export function ChatScreen() {
         failBackgroundTask(taskId, String(error));
         clearChatBackgroundTask(taskId);
       }
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -960,7 +987,7 @@ export function ChatScreen() {
       setContextSummary(result.summary);
       setInspectorSection((prev) => ({ ...prev, context: true }));
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
     setCompressing(false);
   }
@@ -1013,7 +1040,7 @@ export function ChatScreen() {
         setAttachments((prev) => [...prev, normalizedAttachment]);
       }
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     } finally {
       setUploading(false);
       if (fileInputRef.current) fileInputRef.current.value = "";
@@ -1088,7 +1115,7 @@ export function ChatScreen() {
       const result = await api.chatDeleteMessage(messageId);
       setMessages(result.timeline);
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     } finally {
       setDeletingMessageIds((prev) => {
         if (!prev[messageId]) return prev;
@@ -1125,7 +1152,7 @@ export function ChatScreen() {
       setActivePreset(preset);
       api.chatSavePreset(activeChat.id, preset).catch(() => {});
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -1140,7 +1167,7 @@ export function ChatScreen() {
       setShowModelSelector(false);
       if (updated.samplerConfig) setSamplerConfig(updated.samplerConfig);
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -1180,7 +1207,7 @@ export function ChatScreen() {
       applyChatCharactersResult(chatId, result);
     } catch (error) {
       setChatCharacterIds(prevIds);
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -1195,7 +1222,7 @@ export function ChatScreen() {
       applyChatCharactersResult(chatId, result);
     } catch (error) {
       setChatCharacterIds(prevIds);
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -1217,7 +1244,7 @@ export function ChatScreen() {
       applyChatCharactersResult(chatId, result);
     } catch (error) {
       setChatCharacterIds(prevIds);
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -1291,7 +1318,7 @@ export function ChatScreen() {
     try {
       await api.chatSaveLorebooks(activeChat.id, normalizedIds);
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -1310,7 +1337,7 @@ export function ChatScreen() {
     try {
       await api.chatSaveRag(activeChat.id, nextEnabled, normalizedIds);
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -1326,6 +1353,7 @@ export function ChatScreen() {
       const updated = await api.chatNextTurn(targetChatId, characterName, activeBranchId || undefined, {
         onDelta: appendStreamDelta,
         onToolEvent: handleStreamingToolEvent,
+        onEvent: handleStreamEvent,
         onDone: () => {}
       }, false, activePersonaPayload);
       if (activeChatIdRef.current === targetChatId) setMessages(updated);
@@ -1340,7 +1368,7 @@ export function ChatScreen() {
         failBackgroundTask(taskId, String(error));
         clearChatBackgroundTask(taskId);
       }
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
   }
 
@@ -1397,6 +1425,7 @@ export function ChatScreen() {
         const updated = await api.chatNextTurn(targetChatId, charName, activeBranchId || undefined, {
           onDelta: appendStreamDelta,
           onToolEvent: handleStreamingToolEvent,
+          onEvent: handleStreamEvent,
           onDone: () => {}
         }, true, activePersonaPayload); // isAutoConvo = true
         if (activeChatIdRef.current === targetChatId) setMessages(updated);
@@ -1407,7 +1436,7 @@ export function ChatScreen() {
           failBackgroundTask(taskId, String(error));
           clearChatBackgroundTask(taskId);
         }
-        setErrorText(String(error));
+        setErrorText(formatAppError(error, t));
         break;
       }
 
@@ -2095,6 +2124,16 @@ export function ChatScreen() {
                           >
                             {t("chat.save")}
                           </button>
+                          <button
+                            onClick={(e) => {
+                              e.stopPropagation();
+                              void autoTitleChat(chat.id);
+                            }}
+                            className="rounded-md border border-border px-2 py-1 text-[10px] text-text-secondary hover:bg-bg-hover hover:text-text-primary"
+                            title={t("chat.autoTitleHint")}
+                          >
+                            {t("chat.autoTitle")}
+                          </button>
                           <button
                             onClick={(e) => {
                               e.stopPropagation();
@@ -2296,7 +2335,7 @@ export function ChatScreen() {
                           {activeChat ? activeChat.title : t("tab.chat")}
                         </h2>
                         {!zenMode && totalTokens > 0 && <Badge>{totalTokens.toLocaleString()} tok</Badge>}
-                        {!zenMode && <BranchManager branches={branches} activeBranchId={activeBranchId} disabled={chatGenerationBusy} onSelect={setActiveBranchId} onRename={renameBranch} onDelete={removeBranch} />}
+                        {!zenMode && <BranchManager branches={branches} activeBranchId={activeBranchId} disabled={chatGenerationBusy} onSelect={selectBranch} onRename={renameBranch} onDelete={removeBranch} onMerge={mergeBranch} />}
                       </div>
                       <div className="mt-3 grid gap-2 xl:grid-cols-[minmax(180px,1fr)_minmax(240px,1.2fr)_160px_auto]">
                         <div>
@@ -2420,7 +2459,7 @@ export function ChatScreen() {
                     {activeChat ? activeChat.title : t("tab.chat")}
                   </h2>
                   {!zenMode && totalTokens > 0 && <Badge>{totalTokens.toLocaleString()} tok</Badge>}
-                  {!zenMode && <BranchManager branches={branches} activeBranchId={activeBranchId} disabled={chatGenerationBusy} simple onSelect={setActiveBranchId} onRename={renameBranch} onDelete={removeBranch} />}
+                  {!zenMode && <BranchManager branches={branches} activeBranchId={activeBranchId} disabled={chatGenerationBusy} simple onSelect={selectBranch} onRename={renameBranch} onDelete={removeBranch} onMerge={mergeBranch} />}
                   <div className="flex-1" />
                   {activeModelLabel && (
                     <span className="chat-simple-thread-model-badge">
@@ -2510,6 +2549,13 @@ export function ChatScreen() {
               </div>
             )}
 
+            {contextTrim && contextTrim.omitted > 0 && contextTrim.chatId === activeChat?.id && (
+              <div className="mb-3 text-[11px] text-text-tertiary">{t("chat.contextTrimmed").replace("{count}", String(contextTrim.omitted))}</div>
+            )}
+            {providerFallback && providerFallback.chatId === activeChat?.id && (
+              <div className="mb-3 text-[11px] text-text-tertiary">{t("chat.providerFallback").replace("{from}", providerFallback.from).replace("{reason}", providerFallback.reason).replace("{to}", providerFallback.to)}</div>
+            )}
+
             {/* Multi-character bar */}
             {!zenMode && chatCharacters.length > 0 && (!simpleModeActive || !simpleHomeState) && (
               <div className="chat-multi-bar mb-3">
@@ -2727,7 +2773,9 @@ export function ChatScreen() {
                               : (msg.role === "user" ? (activePersona?.name || t("chat.user")) : msg.role)}
                         </span>
                         {msg.tokenCount > 0 && <Badge>{msg.tokenCount} tok</Badge>}
+                        {msg.role === "assistant" && typeof msg.promptTokens === "number" && <Badge>{msg.promptTokens} ctx</Badge>}
                         {msg.role === "assistant" && messageTokensPerSecond[msg.id] && <Badge>{messageTokensPerSecond[msg.id]}</Badge>}
+                        {msg.role === "assistant" && msg.truncated && <Badge>{t("chat.truncated")}</Badge>}
                       </div>
                     </div>
 
@@ -1,6 +1,6 @@
 import { useEffect, useRef, useState, type FormEvent } from "react";
 import { useI18n } from "../../../shared/i18n";
-import type { BranchNode } from "../../../shared/types/contracts";
+import type { BranchNode } from "../../../shared/types/chatHistory";
 
 interface BranchManagerProps {
   branches: BranchNode[];
@@ -10,9 +10,10 @@ interface BranchManagerProps {
   onSelect: (branchId: string) => void;
   onRename: (branchId: string, name: string) => Promise<void>;
   onDelete: (branchId: string) => Promise<void>;
+  onMerge?: (branchId: string) => Promise<void>;
 }
 
-export function BranchManager({ branches, activeBranchId, disabled, simple, onSelect, onRename, onDelete }: BranchManagerProps) {
+export function BranchManager({ branches, activeBranchId, disabled, simple, onSelect, onRename, onDelete, onMerge }: BranchManagerProps) {
   const { t } = useI18n();
   const detailsRef = useRef<HTMLDetailsElement>(null);
   const [editingId, setEditingId] = useState<string | null>(null);
@@ -61,6 +62,19 @@ export function BranchManager({ branches, activeBranchId, disabled, simple, onSe
     }
   }
 
+  async function confirmMerge(branch: BranchNode) {
+    if (!onMerge || !window.confirm(t("chat.confirmMergeBranch").replace("{name}", branch.name))) return;
+    setBusyId(branch.id);
+    try {
+      await onMerge(branch.id);
+      setEditingId(null);
+    } catch {
+      // The screen-level error banner explains the API failure.
+    } finally {
+      setBusyId(null);
+    }
+  }
+
   if (!activeBranch) return null;
 
   return (
@@ -99,8 +113,9 @@ export function BranchManager({ branches, activeBranchId, disabled, simple, onSe
                   <>
                     <button type="button" onClick={() => { onSelect(branch.id); detailsRef.current?.removeAttribute("open"); }} className="flex min-w-0 flex-1 items-center gap-2 rounded-md px-1.5 py-1 text-left">
                       <span className={`h-2 w-2 shrink-0 rounded-full ${active ? "bg-accent" : "border border-text-tertiary"}`} />
-                      <span className="min-w-0 flex-1"><span className="block truncate text-xs text-text-primary">{branch.name}</span><span className="block text-[9px] text-text-tertiary">{branch.parentMessageId ? t("chat.branchFork") : t("chat.branchRoot")}</span></span>
+                      <span className="min-w-0 flex-1"><span className="block truncate text-xs text-text-primary">{branch.name}</span><span className="block text-[9px] text-text-tertiary">{branch.parentMessageId ? t("chat.branchFork") : t("chat.branchRoot")}{typeof branch.messageCount === "number" ? ` · ${t("chat.branchMessages").replace("{count}", String(branch.messageCount))}` : ""}</span></span>
                     </button>
+                    {branch.parentMessageId && onMerge ? <button type="button" disabled={busy} onClick={() => { void confirmMerge(branch); }} className="rounded p-1.5 text-text-tertiary opacity-70 hover:bg-bg-primary hover:text-text-primary group-hover:opacity-100" title={t("chat.mergeBranch")} aria-label={t("chat.mergeBranch")}><svg className="h-3.5 w-3.5" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth={1.8}><path strokeLinecap="round" strokeLinejoin="round" d="M6 3v12a4 4 0 004 4h8m0 0l-4-4m4 4l-4 4M18 3v6a4 4 0 01-4 4H6" /></svg></button> : null}
                     <button type="button" disabled={busy} onClick={() => beginRename(branch)} className="rounded p-1.5 text-text-tertiary opacity-70 hover:bg-bg-primary hover:text-text-primary group-hover:opacity-100" title={t("chat.renameBranch")} aria-label={t("chat.renameBranch")}><svg className="h-3.5 w-3.5" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth={1.8}><path strokeLinecap="round" strokeLinejoin="round" d="M4 20h4l10.5-10.5a2.12 2.12 0 00-3-3L5 17v3zM13.5 8.5l3 3" /></svg></button>
                     <button type="button" disabled={busy || branches.length <= 1} onClick={() => { void confirmDelete(branch); }} className="rounded p-1.5 text-text-tertiary opacity-70 hover:bg-danger/10 hover:text-danger disabled:cursor-not-allowed disabled:opacity-25 group-hover:opacity-100" title={branches.length <= 1 ? t("chat.lastBranchHint") : t("chat.deleteBranch")} aria-label={t("chat.deleteBranch")}><svg className="h-3.5 w-3.5" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth={1.8}><path strokeLinecap="round" strokeLinejoin="round" d="M4 7h16M9 7V4h6v3m-8 0l1 13h8l1-13M10 11v5m4-5v5" /></svg></button>
                   </>
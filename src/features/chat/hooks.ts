@@ -1,7 +1,7 @@
 import { useEffect, type Dispatch, type MutableRefObject, type SetStateAction } from "react";
 import { api } from "../../shared/api";
+import type { BranchNode } from "../../shared/types/chatHistory";
 import type {
-  BranchNode,
   CharacterDetail,
   ChatMessage,
   ChatSession,
@@ -215,7 +215,7 @@ export function useActiveChatHydration(params: ActiveChatHydrationParams) {
       setBranches(list);
       setActiveBranchId((prev) => {
         if (prev && list.some((branch) => branch.id === prev)) return prev;
-        return list[0]?.id ?? null;
+        return (list.find((branch) => branch.active) ?? list[0])?.id ?? null;
       });
     }).catch(() => {
       if (cancelled) return;
@@ -1,6 +1,9 @@
 import { useCallback, useState, type Dispatch, type SetStateAction } from "react";
 import { api } from "../../../shared/api";
-import type { BranchNode, ChatMessage, ChatSession } from "../../../shared/types/contracts";
+import { formatAppError } from "../../../shared/errors";
+import { useI18n } from "../../../shared/i18n";
+import type { BranchNode } from "../../../shared/types/chatHistory";
+import type { ChatMessage, ChatSession } from "../../../shared/types/contracts";
 
 interface BranchManagementParams {
   activeChat: ChatSession | null;
@@ -9,9 +12,16 @@ interface BranchManagementParams {
 }
 
 export function useBranchManagement({ activeChat, setMessages, setErrorText }: BranchManagementParams) {
+  const { t } = useI18n();
   const [branches, setBranches] = useState<BranchNode[]>([]);
   const [activeBranchId, setActiveBranchId] = useState<string | null>(null);
 
+  // The server remembers the choice so sends and timeline loads without a branch id land on it.
+  const selectBranch = useCallback((branchId: string) => {
+    setActiveBranchId(branchId);
+    if (activeChat) void api.chatSetActiveBranch(activeChat.id, branchId).catch((error) => setErrorText(formatAppError(error, t)));
+  }, [activeChat, setErrorText, t]);
+
   const forkBranch = useCallback(async (parentMessageId: string) => {
     if (!activeChat) return;
     try {
@@ -20,9 +30,9 @@ export function useBranchManagement({ activeChat, setMessages, setErrorText }: B
       setActiveBranchId(branch.id);
       setMessages(await api.chatTimeline(activeChat.id, branch.id));
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     }
-  }, [activeChat, setErrorText, setMessages]);
+  }, [activeChat, setErrorText, setMessages, t]);
 
   const renameBranch = useCallback(async (branchId: string, name: string) => {
     if (!activeChat) return;
@@ -30,10 +40,10 @@ export function useBranchManagement({ activeChat, setMessages, setErrorText }: B
       const updated = await api.chatRenameBranch(activeChat.id, branchId, name);
       setBranches((current) => current.map((branch) => branch.id === updated.id ? updated : branch));
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
       throw error;
     }
-  }, [activeChat, setErrorText]);
+  }, [activeChat, setErrorText, t]);
 
   const removeBranch = useCallback(async (branchId: string) => {
     if (!activeChat) return;
@@ -45,18 +55,35 @@ export function useBranchManagement({ activeChat, setMessages, setErrorText }: B
         setMessages(await api.chatTimeline(activeChat.id, result.activeBranchId));
       }
     } catch (error) {
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
+      throw error;
+    }
+  }, [activeBranchId, activeChat, setErrorText, setMessages, t]);
+
+  // Flattens a fork into the root branch and drops the fork, leaving a single linear timeline.
+  const mergeBranch = useCallback(async (branchId: string) => {
+    const root = branches.find((branch) => !branch.parentMessageId);
+    if (!activeChat || !root || root.id === branchId) return;
+    try {
+      const result = await api.chatMergeBranch(activeChat.id, branchId, root.id, true);
+      setBranches(result.branches);
+      setActiveBranchId(result.activeBranchId);
+      setMessages(result.timeline);
+    } catch (error) {
+      setErrorText(formatAppError(error, t));
       throw error;
     }
-  }, [activeBranchId, activeChat, setErrorText, setMessages]);
+  }, [activeChat, branches, setErrorText, setMessages, t]);
 
   return {
     branches,
     setBranches,
     activeBranchId,
     setActiveBranchId,
+    selectBranch,
     forkBranch,
     renameBranch,
-    removeBranch
+    removeBranch,
+    mergeBranch
   };
 }
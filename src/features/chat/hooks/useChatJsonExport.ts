@@ -6,6 +6,7 @@ import {
   startBackgroundTask
 } from "../../../shared/backgroundTasks";
 import { buildFilenameBase, triggerBlobDownload } from "../../../shared/download";
+import { formatAppError } from "../../../shared/errors";
 import { useI18n } from "../../../shared/i18n";
 
 export function useChatJsonExport(onError: (message: string) => void) {
@@ -27,7 +28,7 @@ export function useChatJsonExport(onError: (message: string) => void) {
       await triggerBlobDownload(blob, filename);
       finishBackgroundTask(taskId, filename);
     } catch (error) {
-      const message = String(error);
+      const message = formatAppError(error, t);
       failBackgroundTask(taskId, message);
       onError(message);
     } finally {
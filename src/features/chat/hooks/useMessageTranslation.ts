@@ -5,7 +5,7 @@ import {
   finishBackgroundTask,
   startBackgroundTask
 } from "../../../shared/backgroundTasks";
-import { isAbortError } from "../../../shared/errors";
+import { formatAppError, isAbortError } from "../../../shared/errors";
 import { useI18n } from "../../../shared/i18n";
 
 export function useMessageTranslation(onError: (message: string) => void) {
@@ -48,7 +48,7 @@ export function useMessageTranslation(onError: (message: string) => void) {
       finishBackgroundTask(taskId);
     } catch (error) {
       if (!isAbortError(error)) {
-        const message = String(error);
+        const message = formatAppError(error, t);
         failBackgroundTask(taskId, message);
         onError(message);
       }
@@ -1,7 +1,10 @@
 import { useCallback, useState, type Dispatch, type SetStateAction } from "react";
 import { api } from "../../../shared/api";
+import { formatAppError } from "../../../shared/errors";
+import { useI18n } from "../../../shared/i18n";
 
 export function useRpReasoningToggle(setErrorText: Dispatch<SetStateAction<string>>) {
+  const { t } = useI18n();
   const [rpReasoningEnabled, setRpReasoningEnabled] = useState(false);
   const [savingRpReasoning, setSavingRpReasoning] = useState(false);
 
@@ -16,11 +19,11 @@ export function useRpReasoningToggle(setErrorText: Dispatch<SetStateAction<strin
       window.dispatchEvent(new CustomEvent("settings-change", { detail: updated }));
     } catch (error) {
       setRpReasoningEnabled(!next);
-      setErrorText(String(error));
+      setErrorText(formatAppError(error, t));
     } finally {
       setSavingRpReasoning(false);
     }
-  }, [rpReasoningEnabled, savingRpReasoning, setErrorText]);
+  }, [rpReasoningEnabled, savingRpReasoning, setErrorText, t]);
 
   return { rpReasoningEnabled, setRpReasoningEnabled, savingRpReasoning, toggleRpReasoning };
 }
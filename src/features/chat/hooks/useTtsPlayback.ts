@@ -1,8 +1,11 @@
 import { useCallback, useEffect, useRef, useState, type Dispatch, type SetStateAction } from "react";
 import { api } from "../../../shared/api";
+import { formatAppError } from "../../../shared/errors";
+import { useI18n } from "../../../shared/i18n";
 import { RealtimeTtsPlayer } from "../../../shared/realtimeTts";
 
 export function useTtsPlayback(realtime: boolean, setError: Dispatch<SetStateAction<string>>) {
+  const { t } = useI18n();
   const [loadingId, setLoadingId] = useState<string | null>(null);
   const [playingId, setPlayingId] = useState<string | null>(null);
   const audioRef = useRef<HTMLAudioElement | null>(null);
@@ -63,11 +66,11 @@ export function useTtsPlayback(realtime: boolean, setError: Dispatch<SetStateAct
       await audio.play();
     } catch (error) {
       setPlayingId(null);
-      setError(String(error));
+      setError(formatAppError(error, t));
     } finally {
       setLoadingId(null);
     }
-  }, [loadingId, playingId, realtime, setError, stop]);
+  }, [loadingId, playingId, realtime, setError, stop, t]);
 
   return { ttsLoadingId: loadingId, ttsPlayingId: playingId, handleTts: play };
 }
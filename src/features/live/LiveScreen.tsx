@@ -323,7 +323,7 @@ export function LiveScreen() {
             .then(async (nextBranches) => {
               const branchId = nextBranches.some((branch) => branch.id === inheritedContext.branchId)
                 ? inheritedContext.branchId
-                : nextBranches[0]?.id || null;
+                : (nextBranches.find((branch) => branch.active) ?? nextBranches[0])?.id || null;
               const timeline = await api.chatTimeline(inheritedSession.id, branchId || undefined);
               if (!mountedRef.current || chatIdRef.current !== inheritedSession.id) return;
               setBranches(nextBranches);
@@ -844,7 +844,7 @@ export function LiveScreen() {
       let branchId = chat?.id === activeChat.id ? activeBranchId : null;
       if (!branchId) {
         const nextBranches = await api.chatBranches(activeChat.id);
-        branchId = nextBranches[0]?.id || null;
+        branchId = (nextBranches.find((branch) => branch.active) ?? nextBranches[0])?.id || null;
         setBranches(nextBranches);
         setActiveBranchId(branchId);
       }
@@ -1189,7 +1189,7 @@ export function LiveScreen() {
         },
         onReasoningDelta: (delta) => setStreamingReasoningText((current) => `${current}${delta}`),
         onToolEvent: handleStreamingToolEvent
-      });
+      }, activePersonaPayload());
       if (!mountedRef.current) return;
       setMessages(timeline);
       setStreamingReply("");
@@ -1338,7 +1338,8 @@ export function LiveScreen() {
     setPhase("thinking");
     try {
       const nextBranches = await api.chatBranches(nextSession.id);
-      const branchId = nextBranches[0]?.id || null;
+      // The server keeps the branch last chosen in this chat; reopen on it rather than on main.
+      const branchId = (nextBranches.find((branch) => branch.active) ?? nextBranches[0])?.id || null;
       const timeline = await api.chatTimeline(nextSession.id, branchId || undefined);
       if (!mountedRef.current || chatIdRef.current !== nextSession.id) return;
       setBranches(nextBranches);
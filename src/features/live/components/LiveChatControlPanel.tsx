@@ -13,9 +13,9 @@ import {
   startBackgroundTask
 } from "../../../shared/backgroundTasks";
 import { useI18n } from "../../../shared/i18n";
+import type { BranchNode } from "../../../shared/types/chatHistory";
 import type {
   AppSettings,
-  BranchNode,
   CharacterDetail,
   ChatMessage,
   ChatSession,
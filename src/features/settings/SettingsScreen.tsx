@@ -5,20 +5,23 @@ import { useI18n } from "../../shared/i18n";
 import { triggerBlobDownload } from "../../shared/download";
 import { PROVIDER_PRESETS, type ProviderPreset } from "../../shared/providerPresets";
 import { buildManagedBackendCommand, defaultManagedBackendConfig, normalizeManagedBackends, parseManagedBackendCommand, resolveManagedBackendBaseUrl } from "../../shared/managedBackends";
-import type { ApiParamPolicy, AppSettings, ManagedBackendConfig, ManagedBackendLogEntry, ManagedBackendRuntimeState, McpDiscoveredTool, McpServerConfig, McpServerTestResult, PluginDescriptor, PromptBlock, PromptTemplates, ProviderModel, ProviderProfile, SamplerConfig } from "../../shared/types/contracts";
+import type { ApiParamPolicy, AppSettings, ManagedBackendConfig, ManagedBackendLogEntry, ManagedBackendRuntimeState, McpDiscoveredTool, McpServerConfig, McpServerTestResult, PluginDescriptor, PromptBlock, PromptTemplates, ProviderModel, ProviderProfile, ProviderType, SamplerConfig } from "../../shared/types/contracts";
 import { FieldLabel, InputField, SelectField, TextareaField, ToggleSwitch } from "./components/FormControls";
 import { ModalShell } from "../../components/ModalShell";
 import { IconButton } from "../../components/IconButton";
 import { SettingsSidebar } from "./components/SettingsSidebar";
 import { ManagedBackendsSettings } from "./components/ManagedBackendsSettings";
 import { WallpaperThemePanel } from "./components/WallpaperThemePanel";
+import { CensorshipSettings } from "./components/CensorshipSettings";
+import { ProviderFallbackSettings } from "./components/ProviderFallbackSettings";
 import { RuntimeTuningSettings } from "./components/RuntimeTuningSettings";
 import { SpeechToTextSettings } from "./components/SpeechToTextSettings";
 import { UpdateCheckSetting } from "./components/UpdateCheckSetting";
+import { BackupSettings } from "./components/BackupSettings";
 import { LocalModelsSetup } from "../../components/LocalModelsSetup";
 import { LegacyScreen } from "../legacy/public";
 import { buildSettingsNavigation, DEFAULT_PROMPT_STACK, DEFAULT_SCENE_FIELD_VISIBILITY, PROMPT_STACK_COLORS, type SettingsCategory } from "./config";
-import { buildPluginPermissionDraft, buildPluginSettingsDraft, hasHighRiskPluginPermissions, normalizeApiParamPolicy, normalizePromptStack, pluginPermissionDescription, pluginPermissionTone, promptBlockLabel, scrollToSettingsSection, sanitizePluginSettingsFieldValue } from "./utils";
+import { buildPluginPermissionDraft, buildPluginSettingsDraft, formatProviderHeaders, hasHighRiskPluginPermissions, normalizeApiParamPolicy, parseProviderHeaders, normalizePromptStack, pluginPermissionDescription, pluginPermissionTone, promptBlockLabel, scrollToSettingsSection, sanitizePluginSettingsFieldValue } from "./utils";
 import { useInitialSettingsNavigation } from "./hooks/useInitialSettingsNavigation";
 import { applyWallpaperThemePalette, clearWallpaperTheme, generateWallpaperThemePalette, isWallpaperThemeEnabled, readWallpaperThemePalette, setWallpaperThemeEnabled, storeWallpaperThemePalette } from "../../shared/wallpaperTheme";
 function isLocalProviderEndpoint(url: string): boolean {
@@ -47,17 +50,14 @@ function isLocalProviderEndpoint(url: string): boolean {
 }
 
 function resolveProviderPresetKey(provider: Pick<ProviderProfile, "id" | "baseUrl" | "providerType">): string {
-  const normalizedType = provider.providerType === "koboldcpp" || provider.providerType === "custom"
-    ? provider.providerType
-    : "openai";
+  const normalizedType = provider.providerType ?? "openai";
   const preset = PROVIDER_PRESETS.find((item) => (
     item.defaultId === provider.id
     || (item.baseUrl === provider.baseUrl && item.providerType === normalizedType)
   ));
   if (preset) return preset.key;
-  if (normalizedType === "koboldcpp") return "koboldcpp";
-  if (normalizedType === "custom") return "custom";
-  return "custom";
+  // Native provider types share their preset key; unmatched OpenAI-compatible providers fall under custom.
+  return normalizedType === "openai" ? "custom" : normalizedType;
 }
 
 function parseManualModels(raw: string): string[] {
@@ -207,9 +207,10 @@ export function SettingsScreen({
   const [providerApiKey, setProviderApiKey] = useState("");
   const [providerProxyUrl, setProviderProxyUrl] = useState("");
   const [providerLocalOnly, setProviderLocalOnly] = useState(selectedPreset.localOnly);
-  const [providerType, setProviderType] = useState<"openai" | "koboldcpp" | "custom">(selectedPreset.providerType);
+  const [providerType, setProviderType] = useState<ProviderType>(selectedPreset.providerType);
   const [providerAdapterId, setProviderAdapterId] = useState("");
   const [providerManualModels, setProviderManualModels] = useState("");
+  const [providerHeaders, setProviderHeaders] = useState("");
   const editingProvider = useMemo(
     () => providers.find((provider) => provider.id === providerId) ?? null,
     [providers, providerId]
@@ -341,8 +342,11 @@ export function SettingsScreen({
       showResult(error instanceof Error ? error.message : String(error), "error");
     } finally { setSettingsActionBusy(false); }
   }
-  function getProviderTypeLabel(type?: ProviderProfile["providerType"] | "openai" | "koboldcpp" | "custom") {
+  function getProviderTypeLabel(type?: ProviderType) {
     if (type === "koboldcpp") return t("settings.providerTypeKobold");
+    if (type === "ollama") return t("settings.providerTypeOllama");
+    if (type === "anthropic") return t("settings.providerTypeAnthropic");
+    if (type === "gemini") return t("settings.providerTypeGemini");
     if (type === "custom") return t("settings.providerTypeCustom");
     return t("settings.providerTypeOpenAi");
   }
@@ -454,6 +458,7 @@ export function SettingsScreen({
     setProviderType(preset.providerType);
     setProviderAdapterId("");
     setProviderManualModels("");
+    setProviderHeaders("");
     if (preset.key === "openai") {
       void patchApiParamPolicy({ openai: { sendSampler: false } });
     }
@@ -468,9 +473,10 @@ export function SettingsScreen({
     setProviderApiKey("");
     setProviderProxyUrl(profile.proxyUrl || "");
     setProviderLocalOnly(Boolean(profile.fullLocalOnly));
-    setProviderType(profile.providerType === "koboldcpp" || profile.providerType === "custom" ? profile.providerType : "openai");
+    setProviderType(profile.providerType ?? "openai");
     setProviderAdapterId(profile.adapterId || "");
     setProviderManualModels(Array.isArray(profile.manualModels) ? profile.manualModels.join("\n") : "");
+    setProviderHeaders(formatProviderHeaders(profile.headers));
     setSelectedProviderId(profile.id);
     showResult(`${t("settings.providerLoadedIntoEditor")}: ${profile.name}`, "info");
   }
@@ -782,7 +788,8 @@ export function SettingsScreen({
       const saved = await api.providerUpsert({ id: providerId.trim(), name: providerName.trim(), baseUrl: providerBaseUrl.trim(),
         apiKey: providerApiKey.trim() || "local-key", proxyUrl: providerProxyUrl.trim() || null,
         fullLocalOnly: providerLocalOnly, providerType,
-        adapterId: providerType === "custom" ? providerAdapterId.trim() || null : null, manualModels: draftManualModels
+        adapterId: providerType === "custom" ? providerAdapterId.trim() || null : null, manualModels: draftManualModels,
+        headers: parseProviderHeaders(providerHeaders)
       });
       setProviders(await api.providerList());
       setSelectedProviderId(saved.id);
@@ -796,7 +803,8 @@ export function SettingsScreen({
       fullLocalOnly: providerLocalOnly,
       providerType,
       adapterId: providerType === "custom" ? providerAdapterId.trim() || null : null,
-      manualModels: draftManualModels
+      manualModels: draftManualModels,
+      headers: parseProviderHeaders(providerHeaders)
     };
   }
 
@@ -829,7 +837,7 @@ export function SettingsScreen({
   async function loadModels() {
     if (!selectedProviderId) { showResult(t("settings.selectProviderFirst"), "error"); return; }
     try {
-      const list = await api.providerFetchModels(selectedProviderId);
+      const list = await api.providerFetchModels(selectedProviderId, true);
       setModels(list);
       setSelectedModelId((prev) => {
         if (list.length === 0) return "";
@@ -1242,11 +1250,11 @@ export function SettingsScreen({
     }));
   }, [mcpDiscoveredTools]);
 
-  const activeProviderType = useMemo<"openai" | "koboldcpp" | "custom">(() => {
+  const activeProviderType = useMemo<ProviderType>(() => {
     const activeId = settings?.activeProviderId;
     if (!activeId) return "openai";
     const row = providers.find((provider) => provider.id === activeId);
-    return row?.providerType === "koboldcpp" || row?.providerType === "custom" ? row.providerType : "openai";
+    return row?.providerType ?? "openai";
   }, [providers, settings?.activeProviderId]);
   const toolCallingLocked = activeProviderType === "koboldcpp";
   const apiParamPolicy = useMemo(
@@ -1451,9 +1459,12 @@ export function SettingsScreen({
                     <div className="grid gap-3 md:grid-cols-2">
                       <div>
                         <FieldLabel>{t("settings.providerType")}</FieldLabel>
-                        <SelectField value={providerType} onChange={(v) => setProviderType(v as "openai" | "koboldcpp" | "custom")}>
+                        <SelectField value={providerType} onChange={(v) => setProviderType(v as ProviderType)}>
                           <option value="openai">{t("settings.providerTypeOpenAi")}</option>
                           <option value="koboldcpp">{t("settings.providerTypeKobold")}</option>
+                          <option value="ollama">{t("settings.providerTypeOllama")}</option>
+                          <option value="anthropic">{t("settings.providerTypeAnthropic")}</option>
+                          <option value="gemini">{t("settings.providerTypeGemini")}</option>
                           <option value="custom">{t("settings.providerTypeCustom")}</option>
                         </SelectField>
                       </div>
@@ -1492,6 +1503,17 @@ export function SettingsScreen({
                       />
                       <div className="mt-1 text-[11px] text-text-tertiary">{t("settings.providerManualFallbackDesc")}</div>
                     </div>
+                    <div>
+                      <FieldLabel>{t("settings.providerHeaders")}</FieldLabel>
+                      <textarea
+                        value={providerHeaders}
+                        onChange={(e) => setProviderHeaders(e.target.value)}
+                        placeholder={"HTTP-Referer: https://example.com\nX-Title: Vellium"}
+                        rows={3}
+                        className="w-full rounded-lg border border-border bg-bg-primary px-3 py-2 font-mono text-xs text-text-primary placeholder:text-text-tertiary outline-none transition focus:border-accent"
+                      />
+                      <div className="mt-1 text-[11px] text-text-tertiary">{t("settings.providerHeadersDesc")}</div>
+                    </div>
                     <label className="settings-toggle-row cursor-pointer">
                       <div className="min-w-0">
                         <div className="text-sm font-medium text-text-primary">{t("settings.localOnly")}</div>
@@ -1709,6 +1731,8 @@ export function SettingsScreen({
                 </div>
               </div>
 
+              <ProviderFallbackSettings fallbacks={settings.providerFallbacks} providers={providers} onChange={(providerFallbacks) => { void patch({ providerFallbacks }); }} />
+
               <div id="settings-tts" className="settings-section scroll-mt-24">
                 <div className="settings-section-header">
                   <div>
@@ -2063,13 +2087,7 @@ export function SettingsScreen({
                 <div className="space-y-3">
                   <div><FieldLabel>{t("settings.responseLanguage")}</FieldLabel><InputField value={settings.responseLanguage} onChange={(v) => patch({ responseLanguage: v })} {...autosaveProps} /></div>
                   <div><FieldLabel>{t("settings.translateLanguage")}</FieldLabel><InputField value={settings.translateLanguage || settings.responseLanguage || "English"} onChange={(v) => patch({ translateLanguage: v })} {...autosaveProps} /></div>
-                  <div>
-                    <FieldLabel>{t("settings.censorship")}</FieldLabel>
-                    <SelectField value={settings.censorshipMode} onChange={(v) => patch({ censorshipMode: v as AppSettings["censorshipMode"] })}>
-                      <option value="Unfiltered">{t("settings.unfiltered")}</option>
-                      <option value="Filtered">{t("settings.filtered")}</option>
-                    </SelectField>
-                  </div>
+                  <CensorshipSettings settings={settings} onPatch={(next) => void patch(next)} autosaveProps={autosaveProps} />
                 </div>
               </div>
               <RuntimeTuningSettings group="generation" settings={settings} onPatch={(next) => void patch(next)} t={t} />
@@ -2150,6 +2168,10 @@ export function SettingsScreen({
                         </label>
                       ))}
                     </div>
+                    <label className="mt-2 flex items-center justify-between rounded-lg border border-border-subtle bg-bg-secondary px-3 py-2 text-xs text-text-secondary">
+                      <span>{t("settings.streamUsage")}</span>
+                      <ToggleSwitch checked={apiParamPolicy.openai.streamUsage} onChange={(e) => void patchApiParamPolicy({ openai: { streamUsage: e.target.checked } })} />
+                    </label>
                   </div>
                   <div className="settings-field-group">
                     <div className="mb-2 text-xs font-semibold text-text-secondary">{t("settings.apiParamsKobold")}</div>
@@ -2200,7 +2222,7 @@ export function SettingsScreen({
               <div id="settings-context-window" className="settings-section scroll-mt-24">
                 <div className="settings-section-title">{t("settings.contextWindow")}</div>
                 <div className="space-y-3">
-                  <div><FieldLabel>{t("settings.contextSize")}</FieldLabel><InputField type="number" value={String(settings.contextWindowSize)} onChange={(v) => patch({ contextWindowSize: clampInteger(v, settings.contextWindowSize, 256, 1048576) })} {...autosaveProps} /></div>
+                  <div><FieldLabel>{t("settings.contextSize")}</FieldLabel><InputField type="number" value={String(settings.contextWindowSize)} onChange={(v) => patch({ contextWindowSize: v.trim() === "0" ? 0 : clampInteger(v, settings.contextWindowSize, 256, 1048576) })} {...autosaveProps} /></div>
                   <div><FieldLabel>{t("settings.contextTailWithSummary")}</FieldLabel><InputField type="number" value={String(settings.contextTailBudgetWithSummaryPercent ?? 35)} onChange={(v) => patch({ contextTailBudgetWithSummaryPercent: clampInteger(v, settings.contextTailBudgetWithSummaryPercent ?? 35, 5, 95) })} {...autosaveProps} /></div>
                   <div><FieldLabel>{t("settings.contextTailWithoutSummary")}</FieldLabel><InputField type="number" value={String(settings.contextTailBudgetWithoutSummaryPercent ?? 75)} onChange={(v) => patch({ contextTailBudgetWithoutSummaryPercent: clampInteger(v, settings.contextTailBudgetWithoutSummaryPercent ?? 75, 5, 95) })} {...autosaveProps} /></div>
                   <div className="settings-toggle-row">
@@ -2754,6 +2776,7 @@ export function SettingsScreen({
                 </div>
               </div>
 
+              <BackupSettings />
               <div id="settings-danger-zone" className="settings-section scroll-mt-24 border-danger-border">
                 <div className="settings-section-title">{t("settings.dangerZone")}</div>
                 <p className="mb-3 text-[10px] text-text-tertiary">{t("settings.dangerZoneDesc")}</p>
@@ -0,0 +1,73 @@
+import { useState } from "react";
+import { api } from "../../../shared/api";
+import { useI18n } from "../../../shared/i18n";
+import { FieldLabel, InputField } from "./FormControls";
+
+export function BackupSettings() {
+  const { t } = useI18n();
+  const [password, setPassword] = useState("");
+  const [busy, setBusy] = useState(false);
+  const [status, setStatus] = useState("");
+
+  async function run(action: () => Promise<void>) {
+    if (busy || !password) return;
+    setBusy(true);
+    setStatus("");
+    try {
+      await action();
+    } catch (error) {
+      setStatus(`${t("settings.backupFailed")}: ${error instanceof Error ? error.message : String(error)}`);
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  function exportBackup() {
+    void run(async () => {
+      const result = await api.backupExport(password);
+      setStatus(`${t("settings.backupSaved")}: ${result.path}`);
+    });
+  }
+
+  function importBackup(file: File | undefined) {
+    if (!file || !window.confirm(t("settings.backupConfirmRestore"))) return;
+    void run(async () => {
+      const { fileName } = await api.backupUpload(file);
+      await api.backupImport(fileName, password);
+      // Every screen caches data from the replaced database, so start over from a clean load.
+      window.location.reload();
+    });
+  }
+
+  return (
+    <div id="settings-backup" className="settings-section scroll-mt-24">
+      <div className="settings-section-title">{t("settings.backup")}</div>
+      <p className="mb-3 text-[10px] text-text-tertiary">{t("settings.backupDesc")}</p>
+      <FieldLabel>{t("settings.backupPassword")}</FieldLabel>
+      <InputField type="password" value={password} onChange={setPassword} placeholder={t("settings.backupPassword")} />
+      <div className="mt-3 flex gap-2">
+        <button
+          type="button"
+          onClick={exportBackup}
+          disabled={busy || !password}
+          className="rounded-lg bg-accent px-3 py-2 text-xs font-semibold text-text-inverse hover:bg-accent-hover disabled:opacity-60"
+        >
+          {t("settings.backupExport")}
+        </button>
+        <label className={`rounded-lg border border-border px-3 py-2 text-xs font-medium text-text-secondary hover:bg-bg-hover ${busy || !password ? "pointer-events-none opacity-60" : "cursor-pointer"}`}>
+          {t("settings.backupRestore")}
+          <input
+            type="file"
+            accept=".vbak"
+            className="hidden"
+            onChange={(event) => {
+              importBackup(event.target.files?.[0]);
+              event.target.value = "";
+            }}
+          />
+        </label>
+      </div>
+      {status ? <p className="mt-2 break-all text-[11px] text-text-tertiary" role="status">{status}</p> : null}
+    </div>
+  );
+}
@@ -0,0 +1,49 @@
+import { useI18n } from "../../../shared/i18n";
+import type { AppSettings } from "../../../shared/types/contracts";
+import { FieldLabel, InputField, SelectField } from "./FormControls";
+
+interface CensorshipSettingsProps {
+  settings: AppSettings;
+  onPatch: (patch: Partial<AppSettings>) => void;
+  autosaveProps: { commitMode: "debounced"; debounceMs: number };
+}
+
+export function CensorshipSettings({ settings, onPatch, autosaveProps }: CensorshipSettingsProps) {
+  const { t } = useI18n();
+  return (
+    <>
+      <div>
+        <FieldLabel>{t("settings.censorship")}</FieldLabel>
+        <SelectField value={settings.censorshipMode} onChange={(v) => onPatch({ censorshipMode: v as AppSettings["censorshipMode"] })}>
+          <option value="Unfiltered">{t("settings.unfiltered")}</option>
+          <option value="Filtered">{t("settings.filtered")}</option>
+          <option value="Moderated">{t("settings.moderated")}</option>
+        </SelectField>
+      </div>
+      {settings.censorshipMode === "Moderated" && (
+        <>
+          <p className="text-[11px] text-text-tertiary">{t("settings.moderatedDesc")}</p>
+          <div>
+            <FieldLabel>{t("settings.moderationUrl")}</FieldLabel>
+            <InputField
+              value={settings.moderationUrl || ""}
+              onChange={(value) => onPatch({ moderationUrl: value })}
+              placeholder="https://api.openai.com/v1/moderations"
+              {...autosaveProps}
+            />
+          </div>
+          <div>
+            <FieldLabel>{t("settings.apiKey")}</FieldLabel>
+            <InputField
+              type="password"
+              value={settings.moderationApiKey || ""}
+              onChange={(value) => onPatch({ moderationApiKey: value })}
+              placeholder={t("settings.apiKey")}
+              {...autosaveProps}
+            />
+          </div>
+        </>
+      )}
+    </>
+  );
+}
@@ -0,0 +1,64 @@
+import { useState } from "react";
+import { useI18n } from "../../../shared/i18n";
+import type { ProviderFallbackTarget, ProviderProfile } from "../../../shared/types/contracts";
+import { FieldLabel, InputField, SelectField } from "./FormControls";
+
+interface ProviderFallbackSettingsProps {
+  fallbacks: ProviderFallbackTarget[];
+  providers: ProviderProfile[];
+  onChange: (fallbacks: ProviderFallbackTarget[]) => void;
+}
+
+const buttonClass = "rounded-md border border-border px-2 py-1 text-xs text-text-secondary hover:bg-bg-hover disabled:cursor-not-allowed disabled:opacity-40";
+
+export function ProviderFallbackSettings({ fallbacks, providers, onChange }: ProviderFallbackSettingsProps) {
+  const { t } = useI18n();
+  const [providerId, setProviderId] = useState("");
+  const [modelId, setModelId] = useState("");
+  const providerName = (id: string) => providers.find((provider) => provider.id === id)?.name || id;
+
+  function move(index: number, offset: number) {
+    const next = [...fallbacks];
+    const [entry] = next.splice(index, 1);
+    next.splice(index + offset, 0, entry);
+    onChange(next);
+  }
+
+  function add() {
+    if (!providerId || !modelId.trim()) return;
+    onChange([...fallbacks, { providerId, modelId: modelId.trim() }]);
+    setModelId("");
+  }
+
+  return (
+    <div id="settings-provider-fallbacks" className="settings-section scroll-mt-24">
+      <div className="settings-section-title">{t("settings.providerFallbacks")}</div>
+      <p className="settings-section-desc">{t("settings.providerFallbacksDesc")}</p>
+      <div className="mt-3 space-y-2">
+        {fallbacks.map((entry, index) => (
+          <div key={`${entry.providerId}:${entry.modelId}`} className="flex items-center gap-2 text-sm text-text-primary">
+            <span className="w-5 text-[11px] text-text-tertiary">{index + 1}.</span>
+            <span className="min-w-0 flex-1 truncate">{providerName(entry.providerId)} / {entry.modelId}</span>
+            <button onClick={() => move(index, -1)} disabled={index === 0} className={buttonClass} aria-label={t("settings.moveUp")}>↑</button>
+            <button onClick={() => move(index, 1)} disabled={index === fallbacks.length - 1} className={buttonClass} aria-label={t("settings.moveDown")}>↓</button>
+            <button onClick={() => onChange(fallbacks.filter((_, position) => position !== index))} className={buttonClass} aria-label={t("chat.delete")}>✕</button>
+          </div>
+        ))}
+        <div className="grid gap-2 md:grid-cols-[minmax(0,1fr)_minmax(0,1fr)_auto] md:items-end">
+          <div>
+            <FieldLabel>{t("settings.provider")}</FieldLabel>
+            <SelectField value={providerId} onChange={setProviderId}>
+              <option value="">{t("settings.selectProvider")}</option>
+              {providers.map((provider) => <option key={provider.id} value={provider.id}>{provider.name}</option>)}
+            </SelectField>
+          </div>
+          <div>
+            <FieldLabel>{t("chat.model")}</FieldLabel>
+            <InputField value={modelId} onChange={setModelId} />
+          </div>
+          <button onClick={add} disabled={!providerId || !modelId.trim()} className={`${buttonClass} py-2`}>{t("settings.providerFallbackAdd")}</button>
+        </div>
+      </div>
+    </div>
+  );
+}
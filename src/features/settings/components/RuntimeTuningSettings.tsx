@@ -29,6 +29,9 @@ export function RuntimeTuningSettings({ group, settings, onPatch, t }: RuntimeTu
         <p className="settings-section-desc">{t("settings.runtimeTuningDesc")}</p>
         <div className="mt-3 grid gap-3 md:grid-cols-2">
           <div><FieldLabel>{t("settings.translationTimeout")}</FieldLabel><InputField type="number" value={String(settings.translationTimeoutSeconds)} onChange={(value) => onPatch({ translationTimeoutSeconds: clampedInteger(value, settings.translationTimeoutSeconds, 5, 600) })} {...autosave} /></div>
+          <div><FieldLabel>{t("settings.modelFetchTimeout")}</FieldLabel><InputField type="number" value={String(settings.modelFetchTimeoutSeconds)} onChange={(value) => onPatch({ modelFetchTimeoutSeconds: clampedInteger(value, settings.modelFetchTimeoutSeconds, 5, 300) })} {...autosave} /></div>
+          <div><FieldLabel>{t("settings.streamRetryAttempts")}</FieldLabel><InputField type="number" value={String(settings.streamRetryAttempts)} onChange={(value) => onPatch({ streamRetryAttempts: clampedInteger(value, settings.streamRetryAttempts, 0, 5) })} {...autosave} /></div>
+          <div><FieldLabel>{t("settings.writerSceneRevisionLimit")}</FieldLabel><InputField type="number" value={String(settings.writerSceneRevisionLimit)} onChange={(value) => onPatch({ writerSceneRevisionLimit: clampedInteger(value, settings.writerSceneRevisionLimit, 1, 200) })} {...autosave} /></div>
           <div><FieldLabel>{t("settings.translationMaxTokens")}</FieldLabel><InputField type="number" value={String(settings.translationMaxTokens)} onChange={(value) => onPatch({ translationMaxTokens: clampedInteger(value, settings.translationMaxTokens, 64, 32768) })} {...autosave} /></div>
           <div><FieldLabel>{t("settings.translationTemperature")}</FieldLabel><InputField type="number" value={String(settings.translationTemperature)} onChange={(value) => onPatch({ translationTemperature: clampedDecimal(value, settings.translationTemperature, 0, 2) })} {...autosave} /></div>
           <div><FieldLabel>{t("settings.autoConversationTurns")}</FieldLabel><InputField type="number" value={String(settings.autoConversationDefaultTurns)} onChange={(value) => onPatch({ autoConversationDefaultTurns: clampedInteger(value, settings.autoConversationDefaultTurns, 1, 50) })} {...autosave} /></div>
@@ -45,6 +48,8 @@ export function RuntimeTuningSettings({ group, settings, onPatch, t }: RuntimeTu
       <div className="mt-3 grid gap-3 md:grid-cols-2">
         <div><FieldLabel>{t("settings.contextMaxMessages")}</FieldLabel><InputField type="number" value={String(settings.contextMaxMessages)} onChange={(value) => onPatch({ contextMaxMessages: clampedInteger(value, settings.contextMaxMessages, 0, 1000) })} {...autosave} /></div>
         <div><FieldLabel>{t("settings.reasoningMaxChars")}</FieldLabel><InputField type="number" value={String(settings.reasoningMaxChars)} onChange={(value) => onPatch({ reasoningMaxChars: clampedInteger(value, settings.reasoningMaxChars, 1000, 100000) })} {...autosave} /></div>
+        <div><FieldLabel>{t("settings.loreScanDepth")}</FieldLabel><InputField type="number" value={String(settings.loreScanDepth)} onChange={(value) => onPatch({ loreScanDepth: clampedInteger(value, settings.loreScanDepth, 0, 1000) })} {...autosave} /></div>
+        <div><FieldLabel>{t("settings.loreTokenBudget")}</FieldLabel><InputField type="number" value={String(settings.loreTokenBudget)} onChange={(value) => onPatch({ loreTokenBudget: clampedInteger(value, settings.loreTokenBudget, 0, 32768) })} {...autosave} /></div>
         <div><FieldLabel>{t("settings.compressionFallbackMessages")}</FieldLabel><InputField type="number" value={String(settings.compressionFallbackMessages)} onChange={(value) => onPatch({ compressionFallbackMessages: clampedInteger(value, settings.compressionFallbackMessages, 1, 100) })} {...autosave} /></div>
         <div><FieldLabel>{t("settings.compressionMaxTokens")}</FieldLabel><InputField type="number" value={String(settings.compressionMaxTokens)} onChange={(value) => onPatch({ compressionMaxTokens: clampedInteger(value, settings.compressionMaxTokens, 128, 32768) })} {...autosave} /></div>
         <div><FieldLabel>{t("settings.compressionTemperature")}</FieldLabel><InputField type="number" value={String(settings.compressionTemperature)} onChange={(value) => onPatch({ compressionTemperature: clampedDecimal(value, settings.compressionTemperature, 0, 2) })} {...autosave} /></div>
@@ -21,7 +21,8 @@ export const DEFAULT_API_PARAM_POLICY: ApiParamPolicy = {
     frequencyPenalty: true,
     presencePenalty: true,
     maxTokens: true,
-    stop: true
+    stop: true,
+    streamUsage: true
   },
   kobold: {
     sendSampler: true,
@@ -93,6 +94,7 @@ export function buildSettingsNavigation(t: (key: any) => string) {
       { id: "settings-active-model", label: t("settings.activeModel") },
       { id: "settings-translation-model", label: t("settings.translateModel") },
       { id: "settings-compress-model", label: t("settings.compressModel") },
+      { id: "settings-provider-fallbacks", label: t("settings.providerFallbacks") },
       { id: "settings-tts", label: t("settings.tts") },
       { id: "settings-stt", label: t("settings.stt") },
       { id: "settings-local-speech", label: t("localModels.speechTitle") }
@@ -132,6 +134,7 @@ export function buildSettingsNavigation(t: (key: any) => string) {
       { id: "settings-plugins", label: t("settings.plugins") },
       { id: "settings-tools-mcp-functions", label: t("settings.mcpFunctions") },
       { id: "settings-tools-mcp", label: t("settings.mcpServers") },
+      { id: "settings-backup", label: t("settings.backup") },
       { id: "settings-danger-zone", label: t("settings.dangerZone") }
     ],
     legacy: [
@@ -42,6 +42,7 @@ const SEARCH_DEFINITIONS: SearchDefinition[] = [
   ["connection", "settings-active-model", "settings.activeModel", "chat model active model модель чата"],
   ["connection", "settings-translation-model", "settings.translateModel", "translation model перевод модель"],
   ["connection", "settings-compress-model", "settings.compressModel", "compression model summary сжатие модель"],
+  ["connection", "settings-provider-fallbacks", "settings.providerFallbacks", "fallback failover backup provider резервный провайдер"],
   ["connection", "settings-tts", "settings.ttsRealtime", "tts realtime streaming потоковая озвучка"],
   ["connection", "settings-tts", "settings.ttsEndpoint", "tts url endpoint адрес"],
   ["connection", "settings-tts", "settings.apiKey", "tts api key ключ озвучки"],
@@ -69,6 +70,9 @@ const SEARCH_DEFINITIONS: SearchDefinition[] = [
   ["generation", "settings-output-behaviour", "settings.translateLanguage", "translation language язык перевода"],
   ["generation", "settings-output-behaviour", "settings.censorship", "filtered unfiltered censorship цензура"],
   ["generation", "settings-runtime-tuning", "settings.translationTimeout", "translation timeout таймаут перевода"],
+  ["generation", "settings-runtime-tuning", "settings.modelFetchTimeout", "model list timeout таймаут списка моделей"],
+  ["generation", "settings-runtime-tuning", "settings.streamRetryAttempts", "stream retry attempts повторы потока"],
+  ["generation", "settings-runtime-tuning", "settings.writerSceneRevisionLimit", "scene revisions undo history версии сцены"],
   ["generation", "settings-runtime-tuning", "settings.translationMaxTokens", "translation tokens токены перевода"],
   ["generation", "settings-runtime-tuning", "settings.translationTemperature", "translation temperature температура перевода"],
   ["generation", "settings-runtime-tuning", "settings.autoConversationTurns", "auto conversation turns авто диалог ходы"],
@@ -98,6 +102,8 @@ const SEARCH_DEFINITIONS: SearchDefinition[] = [
   ["context", "settings-chat-behaviour", "settings.includeReasoningInContext", "reasoning context thinking ризонинг контекст"],
   ["context", "settings-context-tuning", "settings.contextMaxMessages", "max context messages сообщений в контексте"],
   ["context", "settings-context-tuning", "settings.reasoningMaxChars", "reasoning max chars лимит ризонинга"],
+  ["context", "settings-context-tuning", "settings.loreScanDepth", "lorebook world info scan depth глубина сканирования лорбука"],
+  ["context", "settings-context-tuning", "settings.loreTokenBudget", "lorebook world info token budget бюджет токенов лорбука"],
   ["context", "settings-context-tuning", "settings.compressionFallbackMessages", "compression fallback messages сжатие"],
   ["context", "settings-context-tuning", "settings.compressionMaxTokens", "compression max tokens сжатие токены"],
   ["context", "settings-context-tuning", "settings.compressionTemperature", "compression temperature температура сжатия"],
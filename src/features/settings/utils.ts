@@ -174,3 +174,19 @@ export function buildPluginPermissionDraft(plugin: PluginDescriptor): Record<str
     plugin.requestedPermissions.map((permission) => [permission, plugin.grantedPermissions.includes(permission)])
   );
 }
+
+/** Parses `Name: value` lines from the provider form into a header map. */
+export function parseProviderHeaders(raw: string): Record<string, string> {
+  const headers: Record<string, string> = {};
+  for (const line of raw.split(/\r?\n/)) {
+    const separator = line.indexOf(":");
+    if (separator <= 0) continue;
+    const name = line.slice(0, separator).trim();
+    if (name) headers[name] = line.slice(separator + 1).trim();
+  }
+  return headers;
+}
+
+export function formatProviderHeaders(headers: Record<string, string> | undefined): string {
+  return Object.entries(headers || {}).map(([name, value]) => `${name}: ${value}`).join("\n");
+}
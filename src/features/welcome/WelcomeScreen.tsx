@@ -188,6 +188,7 @@ export function WelcomeScreen({ initialSettings, onComplete, onPreviewLocale }:
               >
                 <option value="Unfiltered">{t("settings.unfiltered")}</option>
                 <option value="Filtered">{t("settings.filtered")}</option>
+                <option value="Moderated">{t("settings.moderated")}</option>
               </select>
             </div>
           </div>
@@ -7,6 +7,7 @@ import { AvatarBadge } from "../../components/AvatarBadge";
 import { triggerBlobDownload } from "../../shared/download";
 import { CollapsibleSection } from "./components/CollapsibleSection";
 import { WritingWorkspaceModeSwitch } from "./components/WritingWorkspaceModeSwitch";
+import { SceneUndoButton } from "./components/SceneUndoButton";
 import { SimpleWriterEditor } from "./components/SimpleWriterEditor";
 import {
   CHARACTER_AI_EDIT_FIELDS,
@@ -35,6 +36,7 @@ import type {
 } from "../../shared/types/contracts";
 import type { BackgroundTask, CharacterEditDraft, CharacterEditStatus, LensPresetId, WritingWorkspaceMode } from "./types";
 import { EMPTY_CHARACTER_EDIT_DRAFT_TYPED } from "./types";
+import { streamChapterDraft } from "./draftStream";
 import { clamp01 } from "./utils";
 import {
   failBackgroundTask,
@@ -324,10 +326,7 @@ export function WritingScreen({ initialWorkspaceMode = "books", lockWorkspaceMod
     );
 
     try {
-      await api.writerChapterDelete(deletingId);
-      const remainingChapters = chapters
-        .filter((item) => item.id !== deletingId)
-        .map((item, index) => ({ ...item, position: index + 1 }));
+      const { chapters: remainingChapters } = await api.writerChapterDelete(deletingId);
       const remainingScenes = scenes.filter((scene) => scene.chapterId !== deletingId);
 
       const nextSelectedChapterId =
@@ -459,9 +458,7 @@ export function WritingScreen({ initialWorkspaceMode = "books", lockWorkspaceMod
     const taskId = startBgTask("generate", `${t("writing.taskGenerate")}: "${chapterPrompt.slice(0, 30)}..."`);
     log(t("writing.working"));
     try {
-      const scene = await api.writerGenerateDraft(selectedChapterId, chapterPrompt);
-      setScenes((prev) => [...prev, scene]);
-      setSelectedSceneId(scene.id);
+      const scene = await streamChapterDraft(selectedChapterId, chapterPrompt, t("writing.working"), setScenes, setSelectedSceneId);
       log(`${t("writing.logDraftGenerated")}: ${scene.title}`);
       finishBgTask(taskId, "done", scene.title);
     } catch (err) {
@@ -1699,6 +1696,8 @@ export function WritingScreen({ initialWorkspaceMode = "books", lockWorkspaceMod
               className="rounded-md border border-border px-2 py-1 text-[11px] font-medium text-text-secondary hover:bg-bg-hover disabled:opacity-40">
               {t("writing.rewrite")}
             </button>
+            <SceneUndoButton sceneId={selectedSceneId} busy={busy} setBusy={setBusy} log={log}
+              onRestored={(scene) => setScenes((prev) => prev.map((s) => (s.id === scene.id ? scene : s)))} />
             <button onClick={summarizeScene} disabled={!selectedSceneId || busy}
               className="rounded-md border border-border px-2 py-1 text-[11px] font-medium text-text-secondary hover:bg-bg-hover disabled:opacity-40">
               {t("writing.summarize")}
@@ -2150,6 +2149,12 @@ export function WritingScreen({ initialWorkspaceMode = "books", lockWorkspaceMod
                       placeholder={t("writing.characterLedger")}
                       className="h-12 w-full rounded-md border border-border bg-bg-secondary px-2 py-1 text-xs text-text-primary placeholder:text-text-tertiary"
                     />
+                    <textarea
+                      value={(projectNotes.characterRoster || []).join("\n")}
+                      onChange={(e) => updateProjectNotes({ characterRoster: e.target.value.split("\n") })}
+                      placeholder={t("writing.characterRoster")}
+                      className="h-12 w-full rounded-md border border-border bg-bg-secondary px-2 py-1 text-xs text-text-primary placeholder:text-text-tertiary"
+                    />
                     <textarea
                       value={projectNotes.summary}
                       onChange={(e) => updateProjectNotes({ summary: e.target.value })}
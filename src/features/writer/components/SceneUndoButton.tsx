@@ -0,0 +1,40 @@
+import { api } from "../../../shared/api";
+import { useI18n } from "../../../shared/i18n";
+import type { Scene } from "../../../shared/types/contracts";
+
+interface SceneUndoButtonProps {
+  sceneId: string | null;
+  busy: boolean;
+  setBusy: (busy: boolean) => void;
+  onRestored: (scene: Scene) => void;
+  log: (message: string) => void;
+}
+
+/** Restores the snapshot the server took before the scene's latest edit, expand or rewrite. */
+export function SceneUndoButton({ sceneId, busy, setBusy, onRestored, log }: SceneUndoButtonProps) {
+  const { t } = useI18n();
+
+  async function undo() {
+    if (!sceneId || busy) return;
+    setBusy(true);
+    try {
+      const [latest] = await api.writerSceneRevisionsList(sceneId);
+      if (!latest) {
+        log(t("writing.logNoRevisions"));
+      } else {
+        onRestored(await api.writerSceneRestore(sceneId, latest.id));
+        log(t("writing.logSceneRestored"));
+      }
+    } catch (err) {
+      log(`${t("writing.logError")}: ${String(err)}`);
+    }
+    setBusy(false);
+  }
+
+  return (
+    <button onClick={() => void undo()} disabled={!sceneId || busy}
+      className="rounded-md border border-border px-2 py-1 text-[11px] font-medium text-text-secondary hover:bg-bg-hover disabled:opacity-40">
+      {t("writing.undo")}
+    </button>
+  );
+}
@@ -38,7 +38,8 @@ export const DEFAULT_PROJECT_NOTES: WriterProjectNotes = {
   characterNotes: "",
   worldRules: "",
   contextMode: "balanced",
-  summary: ""
+  summary: "",
+  characterRoster: []
 };
 
 export const LENS_PRESET_IDS = [
@@ -0,0 +1,33 @@
+import type { Dispatch, SetStateAction } from "react";
+import { api } from "../../shared/api";
+import type { Scene } from "../../shared/types/contracts";
+
+/**
+ * Streams a chapter draft into a temporary scene so text shows up as it arrives. The saved scene replaces the
+ * placeholder; a failed stream removes it and rethrows.
+ */
+export async function streamChapterDraft(
+  chapterId: string,
+  prompt: string,
+  placeholderTitle: string,
+  setScenes: Dispatch<SetStateAction<Scene[]>>,
+  selectScene: (sceneId: string) => void
+): Promise<Scene> {
+  const draftId = `streaming-${Date.now()}`;
+  setScenes((prev) => [...prev, {
+    id: draftId, chapterId, title: placeholderTitle, content: "",
+    goals: "", conflicts: "", outcomes: "", createdAt: new Date().toISOString()
+  }]);
+  selectScene(draftId);
+  try {
+    const scene = await api.writerGenerateDraftStream(chapterId, prompt, (delta) => {
+      setScenes((prev) => prev.map((item) => item.id === draftId ? { ...item, content: item.content + delta } : item));
+    });
+    setScenes((prev) => [...prev.filter((item) => item.id !== draftId), scene]);
+    selectScene(scene.id);
+    return scene;
+  } catch (error) {
+    setScenes((prev) => prev.filter((item) => item.id !== draftId));
+    throw error;
+  }
+}
@@ -1,4 +1,5 @@
-import type { AppSettings, AppUpdateInfo, McpDiscoverResult, McpImportResult, McpServerConfig, McpServerTestResult, ProviderModel } from "../types/contracts";
+import type { AppSettings, AppStatus, AppUpdateInfo, McpDiscoverResult, McpImportResult, McpServerConfig, McpServerTestResult, ProviderModel } from "../types/contracts";
+import type { BackupExportResult, BackupImportResult, SettingsExportFile, SettingsImportResult } from "../types/settingsTransfer";
 import { get, patchReq, post } from "./core";
 
 const LONG_RUNNING_REQUEST_OPTIONS = { timeoutMs: 0 };
@@ -8,10 +9,25 @@ export const accountSettingsClient = {
     post<string>("/account/create", { password, recoveryKey }),
   accountUnlock: (password: string, recoveryKey?: string) =>
     post<boolean>("/account/unlock", { password, recoveryKey }),
+  accountLock: () => post<{ ok: boolean }>("/account/lock"),
+  accountStatus: () => get<{ hasAccount: boolean; locked: boolean; autoLockMinutes: number }>("/account/status"),
+  accountChangePassword: (oldPassword: string, newPassword: string) =>
+    post<{ ok: boolean }>("/account/change-password", { oldPassword, newPassword }),
   settingsGet: () => get<AppSettings>("/settings"),
   settingsUpdate: (patchData: Partial<AppSettings>) => patchReq<AppSettings>("/settings", patchData),
+  settingsExport: (includeKeys = false) => post<SettingsExportFile>("/settings/export", { includeKeys }),
+  settingsImport: (data: SettingsExportFile | string, includeKeys = false) =>
+    post<SettingsImportResult>("/settings/import", { data, includeKeys }),
   settingsReset: () => post<AppSettings>("/settings/reset"),
+  backupList: () => get<{ dir: string; fileNames: string[] }>("/backup"),
+  backupExport: (password: string) =>
+    post<BackupExportResult>("/backup/export", { password }, LONG_RUNNING_REQUEST_OPTIONS),
+  backupUpload: (file: Blob) =>
+    post<{ fileName: string }>("/backup/upload", file, LONG_RUNNING_REQUEST_OPTIONS),
+  backupImport: (path: string, password: string) =>
+    post<BackupImportResult>("/backup/import", { path, password }, LONG_RUNNING_REQUEST_OPTIONS),
   appUpdateLatest: () => get<AppUpdateInfo>("/updates/latest"),
+  appStatus: () => get<AppStatus>("/status"),
   settingsFetchTtsModels: (baseUrl?: string, apiKey?: string, adapterId?: string | null) =>
     post<ProviderModel[]>("/settings/tts/models", { baseUrl, apiKey, adapterId }, LONG_RUNNING_REQUEST_OPTIONS),
   settingsFetchTtsVoices: (baseUrl?: string, apiKey?: string, adapterId?: string | null) =>
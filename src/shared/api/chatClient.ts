@@ -1,4 +1,5 @@
-import type { BranchNode, ChatMessage, ChatSession, FileAttachment, PromptBlock, RagBinding, RpSceneState, SamplerConfig, UserPersona } from "../types/contracts";
+import type { ChatMessage, ChatSession, FileAttachment, PromptBlock, RagBinding, RpMemoryEntry, RpSceneState, SamplerConfig, UserPersona } from "../types/contracts";
+import type { BranchNode, ChatMessageSearchHit, ChatPromptPreview } from "../types/chatHistory";
 import { del, get, patchReq, post, put, requestBlob, streamNdjson, streamPost, type StreamCallbacks } from "./core";
 
 type UserPersonaPayload = Pick<UserPersona, "name" | "description" | "personality" | "scenario">;
@@ -6,6 +7,11 @@ export type TtsStreamEvent =
   | { type: "audio"; index: number; contentType: string; audioBase64: string; format?: "pcm"; sampleRate?: number }
   | { type: "done"; count: number }
   | { type: "error"; message: string };
+/** OpenAI-style function tool the client runs itself; results go back through `chatSubmitToolResult`. */
+export interface ChatToolDefinition {
+  type: "function";
+  function: { name: string; description?: string; parameters?: Record<string, unknown> };
+}
 const STREAM_TIMELINE_TIMEOUT_MS = 15_000;
 
 function sleep(ms: number): Promise<void> {
@@ -31,22 +37,60 @@ async function loadTimelineAfterStream(chatId: string, branchId?: string): Promi
 }
 
 export const chatClient = {
-  chatCreate: (title: string, characterId?: string, characterIds?: string[], lorebookIds?: string[]) =>
-    post<ChatSession>("/chats", { title, characterId, characterIds, lorebookIds }),
+  /** `greetingIndex` picks the opening message: 0 is the card's first message, 1..n its alternate greetings. */
+  chatCreate: (title: string, characterId?: string, characterIds?: string[], lorebookIds?: string[], greetingIndex?: number) =>
+    post<ChatSession>("/chats", { title, characterId, characterIds, lorebookIds, greetingIndex }),
   chatRename: (chatId: string, title: string) =>
     patchReq<{ ok: boolean; title: string }>(`/chats/${chatId}`, { title }),
+  chatAutoTitle: (chatId: string) => post<{ ok: boolean; title: string }>(`/chats/${chatId}/autotitle`, {}),
+  chatSearch: (query: string, limit = 50) =>
+    get<ChatMessageSearchHit[]>(`/chats/search?q=${encodeURIComponent(query)}&limit=${limit}`),
   chatAbort: (chatId: string) => post<{ ok: boolean; interrupted: boolean }>(`/chats/${chatId}/abort`),
-  chatDelete: (chatId: string) => del<{ ok: boolean }>(`/chats/${chatId}`),
+  /** Moves the chat to the trash; `chatRestore` brings it back and `chatPurge` removes it for good. */
+  chatDelete: (chatId: string) => del<{ ok: boolean; deleted: boolean }>(`/chats/${chatId}`),
+  chatTrash: () => get<Array<{ id: string; title: string; createdAt: string; deletedAt: string }>>("/chats/trash"),
+  chatRestore: (chatId: string) => post<{ ok: boolean }>(`/chats/${chatId}/restore`, {}),
+  chatPurge: (chatId: string) => del<{ ok: boolean; deleted: boolean }>(`/chats/${chatId}/purge`),
+  chatDeletedMessages: (chatId: string) => get<ChatMessage[]>(`/chats/${chatId}/deleted-messages`),
+  chatPurgeDeletedMessages: (chatId: string) => del<{ ok: boolean; purged: number }>(`/chats/${chatId}/deleted-messages`),
   chatBranches: (chatId: string) => get<BranchNode[]>(`/chats/${chatId}/branches`),
   chatRenameBranch: (chatId: string, branchId: string, name: string) =>
     patchReq<BranchNode>(`/chats/${chatId}/branches/${branchId}`, { name }),
+  chatSetActiveBranch: (chatId: string, branchId: string) =>
+    post<{ activeBranchId: string; branches: BranchNode[] }>(`/chats/${chatId}/branches/${branchId}/activate`),
+  chatMergeBranch: (chatId: string, sourceBranchId: string, targetBranchId: string, deleteSource = false) =>
+    post<{ ok: true; appended: number; sourceDeleted: boolean; activeBranchId: string; branches: BranchNode[]; timeline: ChatMessage[] }>(
+      `/chats/${chatId}/branches/${sourceBranchId}/merge`,
+      { targetBranchId, deleteSource }
+    ),
   chatDeleteBranch: (chatId: string, branchId: string) =>
     del<{ ok: true; activeBranchId: string; branches: BranchNode[] }>(`/chats/${chatId}/branches/${branchId}`),
+  chatSetSystemPrompt: (chatId: string, systemPrompt: string) =>
+    put<{ ok: boolean; systemPrompt: string }>(`/chats/${chatId}/system-prompt`, { systemPrompt }),
+  chatSetResponseLanguage: (chatId: string, responseLanguage: string) =>
+    put<{ ok: boolean; responseLanguage: string }>(`/chats/${chatId}/response-language`, { responseLanguage }),
+  chatExportMarkdown: (chatId: string, branchId?: string) => post<string>(`/chats/${chatId}/export/markdown`, { branchId }),
+  chatExportJsonl: (chatId: string, format: "conversation" | "turns", branchId?: string) =>
+    post<string>(`/chats/${chatId}/export/jsonl`, { format, branchId }),
+  chatPreviewPrompt: (chatId: string, branchId?: string, userPersona?: UserPersonaPayload | null, characterName?: string) =>
+    post<ChatPromptPreview>(`/chats/${chatId}/preview-prompt`, { branchId, userPersona, characterName }),
+  chatSetPersona: (chatId: string, personaId: string | null) =>
+    put<{ ok: boolean; personaId: string | null }>(`/chats/${chatId}/persona`, { personaId }),
   chatUpdateCharacters: (chatId: string, characterIds: string[]) =>
     patchReq<{ ok: boolean; characterIds: string[]; characterId: string | null }>(`/chats/${chatId}/characters`, { characterIds }),
+  chatSetCharacter: (chatId: string, characterId: string | null, branchId?: string) =>
+    put<{ ok: boolean; characterIds: string[]; characterId: string | null; timeline: ChatMessage[] }>(`/chats/${chatId}/character`, { characterId, branchId }),
   chatList: () => get<ChatSession[]>("/chats"),
   chatTimeline: (chatId: string, branchId?: string) =>
     get<ChatMessage[]>(`/chats/${chatId}/timeline${branchId ? `?branchId=${branchId}` : ""}`),
+  /** One page of history, oldest first; pass the first message's id as `before` to load the page above it. */
+  chatTimelinePage: (chatId: string, options: { branchId?: string; before?: string; limit?: number } = {}) => {
+    const params = new URLSearchParams();
+    if (options.branchId) params.set("branchId", options.branchId);
+    if (options.before) params.set("before", options.before);
+    params.set("limit", String(options.limit ?? 100));
+    return get<{ messages: ChatMessage[]; hasMore: boolean }>(`/chats/${chatId}/timeline?${params.toString()}`);
+  },
   chatExportJson: (chatId: string, branchId?: string) =>
     requestBlob("GET", `/chats/${chatId}/export/json${branchId ? `?branchId=${encodeURIComponent(branchId)}` : ""}`, undefined, { timeoutMs: 0 }),
   chatNextTurn: async (chatId: string, characterName: string, branchId?: string, callbacks?: StreamCallbacks, isAutoConvo?: boolean, userPersona?: UserPersonaPayload | null): Promise<ChatMessage[]> => {
@@ -56,24 +100,50 @@ export const chatClient = {
     }
     return post<ChatMessage[]>(`/chats/${chatId}/next-turn`, { characterName, branchId, isAutoConvo, userPersona });
   },
-  chatSend: async (chatId: string, content: string, branchId?: string, callbacks?: StreamCallbacks, userPersona?: UserPersonaPayload | null, attachments?: FileAttachment[]): Promise<ChatMessage[]> => {
+  /** `images` are data:image URLs or absolute local file paths the backend reads and encodes. */
+  chatSend: async (chatId: string, content: string, branchId?: string, callbacks?: StreamCallbacks, userPersona?: UserPersonaPayload | null, attachments?: FileAttachment[], tools?: ChatToolDefinition[], images?: string[]): Promise<ChatMessage[]> => {
+    if (callbacks) {
+      await streamPost(`/chats/${chatId}/send`, { content, branchId, userPersona, attachments, tools, images }, callbacks);
+      return loadTimelineAfterStream(chatId, branchId);
+    }
+    return post<ChatMessage[]>(`/chats/${chatId}/send`, { content, branchId, userPersona, attachments, tools, images });
+  },
+  chatSubmitToolResult: async (chatId: string, toolCallId: string, result: unknown, branchId?: string, callbacks?: StreamCallbacks, tools?: ChatToolDefinition[]): Promise<ChatMessage[]> => {
     if (callbacks) {
-      await streamPost(`/chats/${chatId}/send`, { content, branchId, userPersona, attachments }, callbacks);
+      await streamPost(`/chats/${chatId}/tool-results`, { toolCallId, result, tools }, callbacks);
       return loadTimelineAfterStream(chatId, branchId);
     }
-    return post<ChatMessage[]>(`/chats/${chatId}/send`, { content, branchId, userPersona, attachments });
+    return post<ChatMessage[]>(`/chats/${chatId}/tool-results`, { toolCallId, result, tools });
   },
-  chatRegenerate: async (chatId: string, branchId?: string, callbacks?: StreamCallbacks): Promise<ChatMessage[]> => {
+  /** Generates another reply for the last turn; the current one stays available through `chatSelectSwipe`. */
+  chatGenerateSwipe: async (chatId: string, branchId?: string, callbacks?: StreamCallbacks, userPersona?: UserPersonaPayload | null): Promise<ChatMessage[]> => {
     if (callbacks) {
-      await streamPost(`/chats/${chatId}/regenerate`, { branchId }, callbacks);
+      await streamPost(`/chats/${chatId}/swipe`, { branchId, userPersona }, callbacks);
       return loadTimelineAfterStream(chatId, branchId);
     }
-    return post<ChatMessage[]>(`/chats/${chatId}/regenerate`, { branchId });
+    return post<ChatMessage[]>(`/chats/${chatId}/swipe`, { branchId, userPersona });
+  },
+  chatRegenerate: async (chatId: string, branchId?: string, callbacks?: StreamCallbacks, userPersona?: UserPersonaPayload | null): Promise<ChatMessage[]> => {
+    if (callbacks) {
+      await streamPost(`/chats/${chatId}/regenerate`, { branchId, userPersona }, callbacks);
+      return loadTimelineAfterStream(chatId, branchId);
+    }
+    return post<ChatMessage[]>(`/chats/${chatId}/regenerate`, { branchId, userPersona });
+  },
+  /** Appends a continuation to the branch's last assistant reply instead of writing a new one. */
+  chatContinue: async (chatId: string, branchId?: string, callbacks: StreamCallbacks = {}, userPersona?: UserPersonaPayload | null): Promise<ChatMessage[]> => {
+    await streamPost(`/chats/${chatId}/continue`, { branchId, userPersona }, callbacks);
+    return loadTimelineAfterStream(chatId, branchId);
   },
-  chatCompressContext: (chatId: string, branchId?: string) => post<{ summary: string }>(`/chats/${chatId}/compress`, { branchId }),
+  chatCompressContext: (chatId: string, branchId?: string, keepRecent?: number) =>
+    post<{ summary: string }>(`/chats/${chatId}/compress`, { branchId, keepRecent }),
   chatFork: (chatId: string, parentMessageId: string, name: string) => post<BranchNode>(`/chats/${chatId}/fork`, { parentMessageId, name }),
   chatEditMessage: (messageId: string, content: string) => patchReq<{ ok: boolean; timeline: ChatMessage[] }>(`/messages/${messageId}`, { content }),
   chatDeleteMessage: (messageId: string) => del<{ ok: boolean; timeline: ChatMessage[] }>(`/messages/${messageId}`),
+  chatSelectSwipe: (messageId: string) => post<{ ok: boolean; timeline: ChatMessage[] }>(`/messages/${messageId}/select-swipe`, {}),
+  chatRestoreMessage: (messageId: string) => post<{ ok: boolean; timeline: ChatMessage[] }>(`/messages/${messageId}/restore`, {}),
+  chatPinMessage: (messageId: string) => post<{ ok: boolean; timeline: ChatMessage[] }>(`/messages/${messageId}/pin`, {}),
+  chatUnpinMessage: (messageId: string) => post<{ ok: boolean; timeline: ChatMessage[] }>(`/messages/${messageId}/unpin`, {}),
   chatTranslateMessage: (messageId: string, targetLanguage?: string, signal?: AbortSignal) =>
     post<{ translation: string }>(`/chats/messages/${messageId}/translate`, { targetLanguage }, { timeoutMs: 0, signal }),
   chatTtsMessage: (messageId: string) =>
@@ -83,7 +153,8 @@ export const chatClient = {
     streamNdjson<TtsStreamEvent>(`/chats/messages/${messageId}/tts/realtime`, {}, onEvent, { timeoutMs: 0, signal }),
   chatTtsTextRealtime: (input: string, onEvent: (event: TtsStreamEvent) => void | Promise<void>, signal?: AbortSignal) =>
     streamNdjson<TtsStreamEvent>("/chats/tts/realtime", { input }, onEvent, { timeoutMs: 0, signal }),
-  chatSaveSampler: (chatId: string, samplerConfig: SamplerConfig) => patchReq<{ ok: boolean }>(`/chats/${chatId}/sampler`, { samplerConfig }),
+  chatSaveSampler: (chatId: string, samplerConfig: Partial<SamplerConfig> | null) =>
+    patchReq<{ ok: boolean; samplerConfig: Partial<SamplerConfig> | null }>(`/chats/${chatId}/sampler`, { samplerConfig }),
   chatGetSampler: (chatId: string) => get<SamplerConfig | null>(`/chats/${chatId}/sampler`),
   chatSavePreset: (chatId: string, presetId: string | null) => patchReq<{ ok: boolean }>(`/chats/${chatId}/preset`, { presetId }),
   chatGetPreset: (chatId: string) => get<{ presetId: string | null }>(`/chats/${chatId}/preset`),
@@ -93,9 +164,14 @@ export const chatClient = {
   chatGetRag: (chatId: string) => get<RagBinding>(`/chats/${chatId}/rag`),
   rpSetSceneState: (state: RpSceneState) => post<void>("/rp/scene-state", state),
   rpGetSceneState: (chatId: string) => get<RpSceneState | null>(`/rp/scene-state/${chatId}`),
-  rpUpdateAuthorNote: (chatId: string, authorNote: string) => post<void>("/rp/author-note", { chatId, authorNote }),
-  rpGetAuthorNote: (chatId: string) => get<{ authorNote: string }>(`/rp/author-note/${chatId}`),
+  rpUpdateAuthorNote: (chatId: string, authorNote: string, depth?: number | null) =>
+    post<void>("/rp/author-note", { chatId, authorNote, depth }),
+  rpGetAuthorNote: (chatId: string) => get<{ authorNote: string; depth: number }>(`/rp/author-note/${chatId}`),
   rpApplyStylePreset: (chatId: string, presetId: string) => post<{ ok: boolean; sceneState: RpSceneState; presetId: string }>("/rp/apply-preset", { chatId, presetId }),
+  rpMemoryList: (chatId: string) => get<RpMemoryEntry[]>(`/rp/memory/${chatId}`),
+  rpMemoryUpdate: (chatId: string, entryId: string, content: string) =>
+    patchReq<{ ok: boolean }>(`/rp/memory/${chatId}/${entryId}`, { content }),
+  rpMemoryDelete: (chatId: string, entryId: string) => del<{ ok: boolean }>(`/rp/memory/${chatId}/${entryId}`),
   rpGetBlocks: (chatId: string) => get<PromptBlock[]>(`/rp/blocks/${chatId}`),
   rpSaveBlocks: (chatId: string, blocks: PromptBlock[]) => put<void>(`/rp/blocks/${chatId}`, { blocks })
 };
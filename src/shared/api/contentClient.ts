@@ -1,4 +1,5 @@
 import type { CharacterDetail, FileAttachment, LoreBook, RagBinding, RagCollection, RagDocument, RagIngestResult, UserPersona } from "../types/contracts";
+import type { SemanticSearchHit, SemanticSearchScope } from "../types/semanticSearch";
 import { del, get, patchReq, post, put, requestBlob } from "./core";
 
 export const contentClient = {
@@ -6,12 +7,15 @@ export const contentClient = {
   characterReorder: (characterIds: string[]) => patchReq<CharacterDetail[]>("/characters/reorder", { characterIds }),
   characterGet: (id: string) => get<CharacterDetail>(`/characters/${id}`),
   characterImportV2: (rawJson: string) => post<CharacterDetail>("/characters/import", { rawJson }),
+  characterImportPng: (base64Data: string) => post<CharacterDetail>("/characters/import/png", { base64Data }),
   characterTranslateCopy: (id: string, targetLanguage?: string, signal?: AbortSignal) =>
     post<CharacterDetail>(`/characters/${id}/translate-copy`, { targetLanguage }, { timeoutMs: 0, signal }),
   characterValidateV2: (rawJson: string) => post<{ valid: boolean; errors: string[] }>("/characters/validate", { rawJson }),
+  characterConvertV1: (rawJson: string) => post<{ rawJson: string; card: { spec: string; spec_version: string; data: Record<string, unknown> } }>("/characters/convert-v1", { rawJson }),
   characterUpdate: (id: string, data: Partial<CharacterDetail>) => put<CharacterDetail>(`/characters/${id}`, data),
   characterDelete: (id: string) => del<void>(`/characters/${id}`),
   characterExportJson: (id: string) => requestBlob("GET", `/characters/${id}/export/json`),
+  characterExportPng: (id: string, base64Data?: string) => requestBlob("POST", `/characters/${id}/export/png`, { base64Data }),
   characterUploadAvatar: (id: string, base64Data: string, filename: string) => post<{ avatarUrl: string }>(`/characters/${id}/avatar`, { base64Data, filename }),
   lorebookList: () => get<LoreBook[]>("/lorebooks"),
   lorebookGet: (id: string) => get<LoreBook>(`/lorebooks/${id}`),
@@ -36,6 +40,8 @@ export const contentClient = {
     force?: boolean;
   }) => post<RagIngestResult>(`/rag/collections/${collectionId}/documents`, payload),
   ragDocumentDelete: (documentId: string) => del<{ ok: boolean; id: string }>(`/rag/documents/${documentId}`),
+  semanticSearch: (query: string, scope: SemanticSearchScope, limit?: number) =>
+    post<SemanticSearchHit[]>("/rag/semantic-search", { query, scope, limit }),
   personaList: () => get<UserPersona[]>("/personas"),
   personaCreate: (data: Partial<UserPersona>) => post<UserPersona>("/personas", data),
   personaUpdate: (id: string, data: Partial<UserPersona>) => put<UserPersona>(`/personas/${id}`, data),
@@ -1,5 +1,7 @@
 import { afterEach, describe, expect, it, vi } from "vitest";
 
+import { ApiError, appErrorCode, formatAppError } from "../errors";
+import { getI18n } from "../i18n";
 import { request, resolveApiAssetUrl, streamPost } from "./core";
 
 const originalWindow = globalThis.window;
@@ -53,6 +55,38 @@ describe("request", () => {
       .rejects
       .toThrow("Provider blocked by Full Local Mode");
   });
+
+  it("exposes the error code and provider status from error payloads", async () => {
+    Object.defineProperty(globalThis, "window", {
+      value: {
+        setTimeout: globalThis.setTimeout.bind(globalThis),
+        clearTimeout: globalThis.clearTimeout.bind(globalThis)
+      },
+      configurable: true
+    });
+
+    globalThis.fetch = vi.fn(async () => new Response(
+      JSON.stringify({ error: "[API Error: 401] Incorrect API key provided", code: "provider", providerStatus: 401 }),
+      { status: 502, headers: { "Content-Type": "application/json" } }
+    )) as typeof fetch;
+
+    const failure = await request("POST", "/chats/chat-1/autotitle").catch((error: unknown) => error);
+
+    expect(failure).toBeInstanceOf(ApiError);
+    expect(failure).toMatchObject({ status: 502, code: "provider", providerStatus: 401 });
+    expect(appErrorCode(failure)).toBe("provider");
+    expect(appErrorCode(new TypeError("Failed to fetch"))).toBe("network");
+  });
+
+  it("labels error banners by error kind", () => {
+    const { t } = getI18n("en");
+
+    expect(formatAppError(new ApiError("TTS provider not configured", 400, "not_configured"), t))
+      .toBe("Setup needed: TTS provider not configured");
+    expect(formatAppError(new TypeError("Failed to fetch"), t)).toBe("Connection problem: Failed to fetch");
+    expect(formatAppError(new ApiError("title is required", 400, "validation"), t)).toBe("title is required");
+    expect(formatAppError("plain failure", t)).toBe("plain failure");
+  });
 });
 
 describe("streamPost", () => {
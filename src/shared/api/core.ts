@@ -1,3 +1,5 @@
+import { ACCOUNT_LOCKED_EVENT, ApiError, normalizeAppErrorCode } from "../errors";
+
 const BASE = "/api";
 const PROD_FALLBACK_BASES = ["http://127.0.0.1:3001/api", "http://localhost:3001/api"];
 const REQUEST_TIMEOUT_MS = 6000;
@@ -61,16 +63,28 @@ function extractStructuredErrorMessage(payload: unknown): string | null {
   return null;
 }
 
-async function readErrorResponseMessage(res: Response): Promise<string> {
+async function readErrorResponse(res: Response): Promise<ApiError> {
   const fallback = `HTTP ${res.status}`;
   const text = (await res.text()).trim();
-  if (!text) return fallback;
+  if (!text) return new ApiError(fallback, res.status);
 
   try {
     const parsed = JSON.parse(text) as unknown;
-    return extractStructuredErrorMessage(parsed) || text;
+    const record = parsed && typeof parsed === "object" && !Array.isArray(parsed) ? parsed as Record<string, unknown> : {};
+    const providerStatus = Number(record.providerStatus);
+    const code = normalizeAppErrorCode(record.code);
+    if (code === "locked" && typeof window !== "undefined") {
+      window.dispatchEvent(new CustomEvent(ACCOUNT_LOCKED_EVENT));
+    }
+    return new ApiError(
+      extractStructuredErrorMessage(parsed) || text,
+      res.status,
+      code,
+      Number.isInteger(providerStatus) ? providerStatus : null,
+      record
+    );
   } catch {
-    return text;
+    return new ApiError(text, res.status);
   }
 }
 
@@ -91,15 +105,18 @@ export async function request<T>(method: string, path: string, body?: unknown, o
       try {
         const res = await fetch(`${base}${path}`, {
           method,
-          headers: body !== undefined ? { "Content-Type": "application/json" } : undefined,
-          body: body !== undefined ? JSON.stringify(body) : undefined,
+          headers: body instanceof Blob
+            ? { "Content-Type": "application/octet-stream" }
+            : body !== undefined ? { "Content-Type": "application/json" } : undefined,
+          // Blobs (picked files) go out as the raw body so the server can stream them to disk.
+          body: body instanceof Blob ? body : body !== undefined ? JSON.stringify(body) : undefined,
           cache: "no-store",
           credentials: "same-origin",
           referrerPolicy: "no-referrer",
           signal: controller.signal
         });
         if (!res.ok) {
-          throw new Error(await readErrorResponseMessage(res));
+          throw await readErrorResponse(res);
         }
         return res.json();
       } finally {
@@ -148,7 +165,7 @@ export async function requestBlob(method: string, path: string, body?: unknown,
           signal: controller.signal
         });
         if (!res.ok) {
-          throw new Error(await readErrorResponseMessage(res));
+          throw await readErrorResponse(res);
         }
         return await res.blob();
       } finally {
@@ -188,7 +205,7 @@ export async function streamNdjson<T>(
         referrerPolicy: "no-referrer",
         signal: controller.signal
       });
-      if (!response.ok) throw new Error(await readErrorResponseMessage(response));
+      if (!response.ok) throw await readErrorResponse(response);
       if (!response.body) throw new Error("Streaming response has no body");
       const reader = response.body.getReader();
       const decoder = new TextDecoder();
@@ -220,7 +237,8 @@ export async function streamNdjson<T>(
 }
 
 export type StreamCallbacks = {
-  onDelta?: (delta: string) => void;
+  /** `streamId` is the event's `chatId`, which batch streams use to tell their items apart. */
+  onDelta?: (delta: string, streamId?: string) => void;
   onReasoningDelta?: (delta: string) => void;
   onToolEvent?: (event: {
     phase: "start" | "delta" | "done";
@@ -229,6 +247,8 @@ export type StreamCallbacks = {
     args?: string;
     result?: string;
   }) => void;
+  /** A client-defined tool the model wants run; answer it with `chatSubmitToolResult`. */
+  onToolCall?: (call: { callId: string; name: string; args: string }) => void;
   onEvent?: (event: Record<string, unknown>) => void;
   onDone?: () => void;
 };
@@ -247,8 +267,14 @@ function isStandaloneSseDataLine(line: string): boolean {
   }
 }
 
+/**
+ * Splits buffered SSE text into complete event blocks. CRLF and bare CR line
+ * endings are accepted; consecutive `data:` lines stay in one block until a blank
+ * line, and a trailing CR is held back in case its LF arrives in the next chunk.
+ */
 function consumeSseEventBlocks(buffer: string, flush = false): { events: string[]; rest: string } {
-  const normalized = buffer.replace(/\r\n/g, "\n");
+  const heldCarriageReturn = !flush && buffer.endsWith("\r");
+  const normalized = (heldCarriageReturn ? buffer.slice(0, -1) : buffer).replace(/\r\n?/g, "\n");
   const lines = normalized.split("\n");
   const pending = flush ? [] : [lines.pop() ?? ""];
   const completeLines = flush ? lines : lines;
@@ -282,13 +308,13 @@ function consumeSseEventBlocks(buffer: string, flush = false): { events: string[
 
   return {
     events,
-    rest: [...currentEvent, ...pending].join("\n")
+    rest: [...currentEvent, ...pending].join("\n") + (heldCarriageReturn ? "\r" : "")
   };
 }
 
 function extractSseEventData(eventBlock: string): string {
   return eventBlock
-    .split(/\r?\n/)
+    .split(/\r\n?|\n/)
     .map((line) => line.trim())
     .filter((line) => line.startsWith("data:"))
     .map((line) => line.slice(5).trimStart())
@@ -311,7 +337,7 @@ export async function streamPost(path: string, body: unknown, callbacks: StreamC
         referrerPolicy: "no-referrer"
       });
       if (!candidate.ok) {
-        throw new Error(await readErrorResponseMessage(candidate));
+        throw await readErrorResponse(candidate);
       }
       res = candidate;
       break;
@@ -339,6 +365,7 @@ export async function streamPost(path: string, body: unknown, callbacks: StreamC
       try {
         const parsed = JSON.parse(payload) as {
           type: string;
+          chatId?: string;
           delta?: string;
           phase?: "start" | "delta" | "done";
           callId?: string;
@@ -348,7 +375,7 @@ export async function streamPost(path: string, body: unknown, callbacks: StreamC
         };
         sawEvent = true;
         if (parsed.type === "delta" && parsed.delta) {
-          callbacks.onDelta?.(parsed.delta);
+          callbacks.onDelta?.(parsed.delta, parsed.chatId);
         } else if (parsed.type === "reasoning_delta" && parsed.delta) {
           callbacks.onReasoningDelta?.(parsed.delta);
         } else if (parsed.type === "tool" && parsed.phase && parsed.callId && parsed.name) {
@@ -359,6 +386,8 @@ export async function streamPost(path: string, body: unknown, callbacks: StreamC
             args: parsed.args,
             result: parsed.result
           });
+        } else if (parsed.type === "tool_call" && parsed.callId && parsed.name) {
+          callbacks.onToolCall?.({ callId: parsed.callId, name: parsed.name, args: parsed.args ?? "" });
         } else if (parsed.type === "done") {
           doneEmitted = true;
           callbacks.onDone?.();
@@ -388,6 +417,7 @@ export async function streamPost(path: string, body: unknown, callbacks: StreamC
       }
     }
 
+    buffer += decoder.decode();
     const flushed = consumeSseEventBlocks(buffer, true);
     for (const eventBlock of flushed.events) {
       processEventBlock(eventBlock);
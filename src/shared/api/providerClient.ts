@@ -1,6 +1,15 @@
-import type { AppSettings, ManagedBackendConfig, ManagedBackendRuntimeState, ProviderModel, ProviderProfile } from "../types/contracts";
+import type {
+  AppSettings,
+  ManagedBackendConfig,
+  ManagedBackendRuntimeState,
+  ProviderAuthMode,
+  ProviderConnectionResult,
+  ProviderModel,
+  ProviderProfile,
+  ProviderType
+} from "../types/contracts";
 import { managedBackendModelId, normalizeManagedBackends, parseManagedBackendModelId, resolveManagedBackendBaseUrl } from "../managedBackends";
-import { get, post } from "./core";
+import { get, post, put } from "./core";
 import { accountSettingsClient } from "./accountSettingsClient";
 
 const LONG_RUNNING_REQUEST_OPTIONS = { timeoutMs: 0 };
@@ -111,29 +120,54 @@ export const providerClient = {
   providerUpsert: (profile: Omit<ProviderProfile, "apiKeyMasked"> & { apiKey: string }) =>
     post<ProviderProfile>("/providers", profile),
   providerList: () => get<ProviderProfile[]>("/providers"),
-  providerFetchModels: async (providerId: string) => {
+  providerFetchModels: async (providerId: string, forceRefresh = false, includeHidden = false) => {
+    const query = new URLSearchParams();
+    if (forceRefresh) query.set("refresh", "1");
+    if (includeHidden) query.set("includeHidden", "1");
+    const search = query.toString();
     const [models, managedBackends, runtimeStates] = await Promise.all([
-      get<ProviderModel[]>(`/providers/${providerId}/models`, LONG_RUNNING_REQUEST_OPTIONS),
+      get<ProviderModel[]>(`/providers/${providerId}/models${search ? `?${search}` : ""}`, LONG_RUNNING_REQUEST_OPTIONS),
       listManagedBackendsForProvider(providerId),
       listRuntimeStates()
     ]);
     return appendManagedBackendModels(models, managedBackends, runtimeStates);
   },
+  providerModelSetFavorite: (providerId: string, modelId: string, favorite: boolean) =>
+    put<{ modelId: string; favorite: boolean; hidden: boolean }>(`/providers/${providerId}/models/favorite`, { modelId, favorite }),
+  providerModelSetHidden: (providerId: string, modelId: string, hidden: boolean) =>
+    put<{ modelId: string; favorite: boolean; hidden: boolean }>(`/providers/${providerId}/models/hidden`, { modelId, hidden }),
+  /** Server-side model list filtered by a case-insensitive id substring and sorted by id. */
+  providerSearchModels: (providerId: string, filter: string, options: { limit?: number; forceRefresh?: boolean; includeHidden?: boolean } = {}) => {
+    const query = new URLSearchParams({ filter });
+    if (options.limit) query.set("limit", String(options.limit));
+    if (options.forceRefresh) query.set("refresh", "1");
+    if (options.includeHidden) query.set("includeHidden", "1");
+    return get<{ models: ProviderModel[]; total: number; matched: number }>(
+      `/providers/${providerId}/models/search?${query.toString()}`,
+      LONG_RUNNING_REQUEST_OPTIONS
+    );
+  },
   providerPreviewModels: (payload: {
     baseUrl: string;
     apiKey: string;
     fullLocalOnly: boolean;
-    providerType: "openai" | "koboldcpp" | "custom";
+    providerType: ProviderType;
     adapterId?: string | null;
     manualModels?: string[];
+    headers?: Record<string, string>;
+    authMode?: ProviderAuthMode;
+    authParam?: string | null;
   }) => post<ProviderModel[]>("/providers/preview/models", payload, LONG_RUNNING_REQUEST_OPTIONS),
   providerPreviewTest: (payload: {
     baseUrl: string;
     apiKey: string;
     fullLocalOnly: boolean;
-    providerType: "openai" | "koboldcpp" | "custom";
+    providerType: ProviderType;
     adapterId?: string | null;
     manualModels?: string[];
+    headers?: Record<string, string>;
+    authMode?: ProviderAuthMode;
+    authParam?: string | null;
   }) => post<{ ok: boolean; error?: string }>("/providers/preview/test", payload, LONG_RUNNING_REQUEST_OPTIONS),
   providerSetActive: (providerId: string, modelId: string) =>
     post<AppSettings>("/providers/set-active", { providerId, modelId }),
@@ -165,5 +199,5 @@ export const providerClient = {
     };
   },
   providerTestConnection: (providerId: string) =>
-    post<boolean>(`/providers/${providerId}/test`, undefined, LONG_RUNNING_REQUEST_OPTIONS)
+    post<ProviderConnectionResult>(`/providers/${providerId}/test`, undefined, LONG_RUNNING_REQUEST_OPTIONS)
 };
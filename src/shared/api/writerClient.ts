@@ -3,8 +3,12 @@ import type {
   Chapter,
   CharacterDetail,
   ConsistencyIssue,
+  ConsistencyReport,
+  ConsistencyReportSummary,
+  ConsistencyStreamEvent,
   RagBinding,
   Scene,
+  WriterBeat,
   WriterChapterSettings,
   WriterCharacterEditRequest,
   WriterCharacterEditResponse,
@@ -13,13 +17,16 @@ import type {
   WriterDocxImportResult,
   WriterDocxParseMode,
   WriterGenerateNextChapterResult,
+  WriterGenerateOutlineResult,
   WriterProjectNotes,
   WriterProjectSummaryResult,
+  WriterSceneRevision,
   WriterSummaryLens,
   WriterSummaryLensRunResult,
   WriterSummaryLensScope
 } from "../types/contracts";
-import { del, get, patchReq, post, requestBlob } from "./core";
+import type { WriterBatchExpandResult } from "../types/writerBatch";
+import { del, get, patchReq, post, put, requestBlob, streamNdjson, streamPost } from "./core";
 
 const LONG_RUNNING_REQUEST_OPTIONS = { timeoutMs: 0 };
 
@@ -33,6 +40,9 @@ export const writerClient = {
   writerProjectGetRag: (projectId: string) => get<RagBinding>(`/writer/projects/${projectId}/rag`),
   writerProjectSaveRag: (projectId: string, enabled: boolean, collectionIds: string[]) => patchReq<RagBinding>(`/writer/projects/${projectId}/rag`, { enabled, collectionIds }),
   writerProjectUpdateNotes: (projectId: string, notes: Partial<WriterProjectNotes>) => patchReq<{ project: BookProject }>(`/writer/projects/${projectId}/notes`, { notes }),
+  writerBeatsGet: (projectId: string) => get<{ beats: WriterBeat[] }>(`/writer/projects/${projectId}/beats`),
+  writerBeatsSet: (projectId: string, beats: Array<Partial<WriterBeat>>) =>
+    put<{ beats: WriterBeat[] }>(`/writer/projects/${projectId}/beats`, { beats }),
   writerProjectImportDocx: (projectId: string, base64Data: string, filename: string, parseMode: WriterDocxParseMode = "auto") =>
     post<WriterDocxImportResult>(`/writer/projects/${projectId}/import/docx`, { base64Data, filename, parseMode }, LONG_RUNNING_REQUEST_OPTIONS),
   writerImportDocxAsBook: (base64Data: string, filename: string, parseMode: WriterDocxParseMode = "auto", bookName?: string) =>
@@ -48,27 +58,129 @@ export const writerClient = {
   writerChapterCreate: (projectId: string, title: string) => post<Chapter>("/writer/chapters", { projectId, title }),
   writerGenerateNextChapter: (projectId: string, prompt?: string) =>
     post<WriterGenerateNextChapterResult>(`/writer/projects/${projectId}/generate-next-chapter`, prompt ? { prompt } : {}, LONG_RUNNING_REQUEST_OPTIONS),
+  writerGenerateOutline: (projectId: string, premise: string, options: { chapterCount?: number; withScenes?: boolean } = {}) =>
+    post<WriterGenerateOutlineResult>(`/writer/projects/${projectId}/generate-outline`, { premise, ...options }, LONG_RUNNING_REQUEST_OPTIONS),
+  writerGenerateOutlineStream: async (
+    projectId: string,
+    premise: string,
+    options: { chapterCount?: number; withScenes?: boolean } = {},
+    onDelta?: (delta: string) => void
+  ) => {
+    let result: WriterGenerateOutlineResult | null = null;
+    let streamError = "";
+    await streamPost(`/writer/projects/${projectId}/generate-outline`, { premise, ...options, stream: true }, {
+      onDelta,
+      onEvent: (event) => {
+        if (event.type === "outline" && Array.isArray(event.chapters)) {
+          result = {
+            chapters: event.chapters as Chapter[],
+            scenes: Array.isArray(event.scenes) ? event.scenes as Scene[] : []
+          };
+        }
+        if (event.type === "error") streamError = String(event.error || "Outline generation failed");
+      }
+    });
+    if (!result) throw new Error(streamError || "Outline generation was interrupted");
+    return result as WriterGenerateOutlineResult;
+  },
   writerChapterUpdate: (chapterId: string, data: { title?: string }) => patchReq<Chapter>(`/writer/chapters/${chapterId}`, data),
-  writerChapterDelete: (chapterId: string) => del<{ ok: boolean; id: string }>(`/writer/chapters/${chapterId}`),
+  writerChapterDelete: (chapterId: string) => del<{ ok: boolean; id: string; chapters: Chapter[] }>(`/writer/chapters/${chapterId}`),
   writerChapterUpdateSettings: (chapterId: string, settings: WriterChapterSettings) => patchReq<Chapter>(`/writer/chapters/${chapterId}/settings`, { settings }),
-  writerGenerateDraft: (chapterId: string, prompt: string) =>
-    post<Scene>(`/writer/chapters/${chapterId}/generate-draft`, { prompt }, LONG_RUNNING_REQUEST_OPTIONS),
+  writerGenerateDraft: (chapterId: string, prompt: string, beatId?: string, characterId?: string) =>
+    post<Scene>(`/writer/chapters/${chapterId}/generate-draft`, { prompt, beatId, characterId }, LONG_RUNNING_REQUEST_OPTIONS),
+  writerGenerateDraftStream: async (chapterId: string, prompt: string, onDelta?: (delta: string) => void) => {
+    let scene: Scene | null = null;
+    let streamError = "";
+    await streamPost(`/writer/chapters/${chapterId}/generate-draft`, { prompt, stream: true }, {
+      onDelta,
+      onEvent: (event) => {
+        if (event.type === "scene" && event.scene && typeof event.scene === "object") scene = event.scene as Scene;
+        if (event.type === "error") streamError = String(event.error || "Draft generation failed");
+      }
+    });
+    if (!scene) throw new Error(streamError || "Draft generation was interrupted");
+    return scene as Scene;
+  },
+  writerSceneFromChat: (chapterId: string, chatId: string, branchId?: string, title?: string) =>
+    post<Scene>(`/writer/chapters/${chapterId}/scenes/from-chat`, { chatId, branchId, title }, LONG_RUNNING_REQUEST_OPTIONS),
+  writerAbortStream: (streamId: string) => post<{ ok: boolean; interrupted: boolean }>(`/writer/streams/${streamId}/abort`),
   writerSceneExpand: (sceneId: string) => post<Scene>(`/writer/scenes/${sceneId}/expand`, undefined, LONG_RUNNING_REQUEST_OPTIONS),
+  writerScenesBatchExpand: (sceneIds: string[]) =>
+    post<{ results: WriterBatchExpandResult[] }>("/writer/scenes/batch-expand", { sceneIds }, LONG_RUNNING_REQUEST_OPTIONS),
+  /** Streams every scene's deltas with its id; `onBatch` gets the id `writerAbortStream` cancels the batch with. */
+  writerScenesBatchExpandStream: async (
+    sceneIds: string[],
+    callbacks: {
+      onBatch?: (batchId: string) => void;
+      onDelta?: (sceneId: string, delta: string) => void;
+      onSceneResult?: (result: WriterBatchExpandResult) => void;
+    } = {}
+  ) => {
+    const results: WriterBatchExpandResult[] = [];
+    await streamPost("/writer/scenes/batch-expand", { sceneIds, stream: true }, {
+      onDelta: (delta, streamId) => { if (streamId) callbacks.onDelta?.(streamId, delta); },
+      onEvent: (event) => {
+        if (event.type === "batch" && typeof event.batchId === "string") callbacks.onBatch?.(event.batchId);
+        if (event.type === "scene_result" && typeof event.sceneId === "string") {
+          const result = {
+            sceneId: event.sceneId,
+            status: event.status,
+            scene: event.scene,
+            error: event.error
+          } as WriterBatchExpandResult;
+          results.push(result);
+          callbacks.onSceneResult?.(result);
+        }
+      }
+    });
+    return results;
+  },
   writerSceneRewrite: (sceneId: string, tone?: string) =>
     post<Scene>(`/writer/scenes/${sceneId}/rewrite`, tone ? { tone } : {}, LONG_RUNNING_REQUEST_OPTIONS),
-  writerSceneSummarize: (sceneId: string) => get<string>(`/writer/scenes/${sceneId}/summarize`, LONG_RUNNING_REQUEST_OPTIONS),
+  writerSceneRevisionsList: (sceneId: string) => get<WriterSceneRevision[]>(`/writer/scenes/${sceneId}/revisions`),
+  writerSceneSetCharacter: (sceneId: string, characterId: string | null) =>
+    put<Scene>(`/writer/scenes/${sceneId}/character`, { characterId }),
+  writerSceneRestore: (sceneId: string, revisionId: string) => post<Scene>(`/writer/scenes/${sceneId}/revisions/${revisionId}/restore`),
+  writerSceneSummarize: (sceneId: string, options: { sentences?: number; force?: boolean } = {}) => {
+    const query = new URLSearchParams();
+    if (options.sentences) query.set("sentences", String(options.sentences));
+    if (options.force) query.set("force", "1");
+    const search = query.toString();
+    return get<string>(`/writer/scenes/${sceneId}/summarize${search ? `?${search}` : ""}`, LONG_RUNNING_REQUEST_OPTIONS);
+  },
   writerConsistencyRun: (projectId: string) =>
     post<ConsistencyIssue[]>(`/writer/projects/${projectId}/consistency`, undefined, LONG_RUNNING_REQUEST_OPTIONS),
+  writerConsistencyStream: (projectId: string, onEvent: (event: ConsistencyStreamEvent) => void, signal?: AbortSignal) =>
+    streamNdjson<ConsistencyStreamEvent>(`/writer/projects/${projectId}/consistency/stream`, {}, onEvent, { ...LONG_RUNNING_REQUEST_OPTIONS, signal }),
+  writerConsistencyReports: (projectId: string) =>
+    get<ConsistencyReportSummary[]>(`/writer/projects/${projectId}/consistency/reports`),
+  writerConsistencyReport: (
+    reportId: string,
+    filter: { severities?: ConsistencyIssue["severity"][]; categories?: ConsistencyIssue["category"][] } = {}
+  ) => {
+    const query = new URLSearchParams();
+    if (filter.severities?.length) query.set("severity", filter.severities.join(","));
+    if (filter.categories?.length) query.set("category", filter.categories.join(","));
+    const search = query.toString();
+    return get<ConsistencyReport>(`/writer/consistency/reports/${reportId}${search ? `?${search}` : ""}`);
+  },
   writerExportMarkdown: (projectId: string) =>
     post<string>(`/writer/projects/${projectId}/export/markdown`, undefined, LONG_RUNNING_REQUEST_OPTIONS),
   writerExportDocx: (projectId: string) =>
     post<string>(`/writer/projects/${projectId}/export/docx`, undefined, LONG_RUNNING_REQUEST_OPTIONS),
+  /** Single self-contained HTML page with inline styles and a chapter table of contents. */
+  writerExportHtml: (projectId: string) =>
+    post<string>(`/writer/projects/${projectId}/export/html`, undefined, LONG_RUNNING_REQUEST_OPTIONS),
+  writerExportHtmlDownload: (projectId: string) =>
+    requestBlob("POST", `/writer/projects/${projectId}/export/html/download`, undefined, LONG_RUNNING_REQUEST_OPTIONS),
   writerExportMarkdownDownload: (projectId: string) =>
     requestBlob("POST", `/writer/projects/${projectId}/export/markdown/download`, undefined, LONG_RUNNING_REQUEST_OPTIONS),
   writerExportDocxDownload: (projectId: string) =>
     requestBlob("POST", `/writer/projects/${projectId}/export/docx/download`, undefined, LONG_RUNNING_REQUEST_OPTIONS),
   writerSceneUpdate: (sceneId: string, data: Partial<Scene>) => patchReq<Scene>(`/writer/scenes/${sceneId}`, data),
   writerSceneDelete: (sceneId: string) => del<{ ok: boolean; id: string }>(`/writer/scenes/${sceneId}`),
+  writerSceneReorder: (chapterId: string, orderedIds: string[]) =>
+    post<{ ok: boolean }>("/writer/scenes/reorder", { chapterId, orderedIds }),
   writerGenerateCharacter: (payload: WriterCharacterGenerateRequest) =>
     post<CharacterDetail>("/writer/characters/generate", payload, LONG_RUNNING_REQUEST_OPTIONS),
   writerEditCharacter: (characterId: string, payload: WriterCharacterEditRequest) =>
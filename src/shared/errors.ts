@@ -1,3 +1,71 @@
+import type { TranslationKey } from "./i18n";
+
 export function isAbortError(error: unknown): boolean {
   return error instanceof Error && error.name === "AbortError";
 }
+
+/** Machine-readable `code` on API error responses; `error` stays the human-readable message. */
+export type AppErrorCode =
+  | "not_configured"
+  | "network"
+  | "provider"
+  | "locked"
+  | "validation"
+  | "moderation"
+  | "not_found"
+  | "conflict"
+  | "forbidden"
+  | "internal";
+
+/** Fired on `window` whenever the server answers with `code: "locked"`, so the app can ask for the password. */
+export const ACCOUNT_LOCKED_EVENT = "account-locked";
+
+const APP_ERROR_CODES = new Set<AppErrorCode>([
+  "not_configured", "network", "provider", "locked", "validation", "moderation", "not_found", "conflict", "forbidden", "internal"
+]);
+
+export function normalizeAppErrorCode(value: unknown): AppErrorCode | null {
+  return typeof value === "string" && APP_ERROR_CODES.has(value as AppErrorCode) ? value as AppErrorCode : null;
+}
+
+/** A non-2xx API response. `providerStatus` is the upstream HTTP status when a provider rejected the call. */
+export class ApiError extends Error {
+  constructor(
+    message: string,
+    readonly status: number,
+    readonly code: AppErrorCode | null = null,
+    readonly providerStatus: number | null = null,
+    /** The full error payload, for fields beyond the message such as `categories` on moderation errors. */
+    readonly details: Record<string, unknown> = {}
+  ) {
+    super(message);
+    this.name = "ApiError";
+  }
+}
+
+/** Error kind for UI branching; fetch failures and request timeouts that never reached the server count as `network`. */
+export function appErrorCode(error: unknown): AppErrorCode | null {
+  if (error instanceof ApiError) return error.code;
+  if (!(error instanceof Error)) return null;
+  if (error.name === "TypeError" || /failed to fetch|fetch failed|network ?error|load failed|timed out/i.test(error.message)) {
+    return "network";
+  }
+  return null;
+}
+
+const APP_ERROR_LABELS: Partial<Record<AppErrorCode, TranslationKey>> = {
+  not_configured: "errors.notConfigured",
+  network: "errors.network",
+  provider: "errors.provider",
+  locked: "errors.locked",
+  moderation: "errors.moderation",
+  not_found: "errors.notFound"
+};
+
+/** Error banner text: the server message, prefixed with a localized label for the error kind when there is one. */
+export function formatAppError(error: unknown, t: (key: TranslationKey) => string): string {
+  const message = error instanceof Error ? error.message : String(error);
+  const code = appErrorCode(error);
+  const label = code ? APP_ERROR_LABELS[code] : undefined;
+  return label ? `${t(label)}: ${message}` : message;
+}
@@ -1,6 +1,19 @@
 export const en = {
   // App
   "app.name": "Vellium",
+  "account.lockedTitle": "Vellium is locked",
+  "account.lockedDesc": "Enter your account password to open your chats and settings.",
+  "account.password": "Password",
+  "account.recoveryKey": "Recovery key (instead of the password)",
+  "account.unlock": "Unlock",
+  "account.unlockFailed": "Wrong password or recovery key",
+  "account.lock": "Lock",
+  "errors.notConfigured": "Setup needed",
+  "errors.network": "Connection problem",
+  "errors.provider": "Provider error",
+  "errors.locked": "Locked",
+  "errors.moderation": "Blocked by moderation",
+  "errors.notFound": "Not found",
   "welcome.title": "Welcome to Vellium",
   "welcome.subtitle": "Let's configure the basics for your first launch.",
   "welcome.interfaceLanguage": "Interface Language",
@@ -480,6 +493,9 @@ export const en = {
   "chat.startConvo": "Start a conversation",
   "chat.startConvoDesc": "Type a message below to begin. A chat will be created automatically.",
   "chat.regenerate": "Regenerate",
+  "chat.truncated": "Truncated",
+  "chat.contextTrimmed": "Earlier context trimmed: {count} older messages did not fit the context window.",
+  "chat.providerFallback": "{from} failed ({reason}); answered by {to}.",
   "chat.compress": "Compress",
   "chat.compressing": "Compressing...",
   "chat.exportJson": "Export chat JSON",
@@ -575,6 +591,8 @@ export const en = {
   "chat.confirmDeleteChat": "Delete this chat?",
   "chat.searchChats": "Search chats...",
   "chat.renameChat": "Rename chat",
+  "chat.autoTitle": "Auto",
+  "chat.autoTitleHint": "Let the active model title this chat from its first exchange",
   "chat.rename": "Rename",
   "chat.renameEmptyError": "Chat title cannot be empty",
   "chat.noSearchResults": "No chats found",
@@ -592,6 +610,9 @@ export const en = {
   "chat.lastBranchHint": "The last branch cannot be deleted",
   "chat.branchRoot": "Root branch",
   "chat.branchFork": "Forked path",
+  "chat.branchMessages": "{count} messages",
+  "chat.mergeBranch": "Merge into root branch",
+  "chat.confirmMergeBranch": "Append the new messages from “{name}” to the root branch and delete it?",
   "chat.loading": "Loading...",
   "chat.imageAttachment": "image attachment",
   "chat.generatedMedia": "Generated media",
@@ -728,6 +749,7 @@ export const en = {
   "writing.consistency": "Consistency",
   "writing.expand": "Expand",
   "writing.rewrite": "Rewrite",
+  "writing.undo": "Undo",
   "writing.summarize": "Summarize",
   "writing.generate": "Generate",
   "writing.working": "Working...",
@@ -819,6 +841,7 @@ export const en = {
   "writing.styleGuide": "Style guide (voice, tense, prose rules)",
   "writing.worldRules": "World rules / canon constraints",
   "writing.characterLedger": "Character ledger / arcs",
+  "writing.characterRoster": "Character roster for name checks: one per line, optionally \"Name @ Scene title\"",
   "writing.bookSummary": "Book summary",
   "writing.summaryCached": "Summary from cache",
   "writing.summaryRefreshed": "Summary regenerated",
@@ -843,6 +866,8 @@ export const en = {
   "writing.issuesCount": "issues",
   "writing.logSceneExpanded": "Scene expanded",
   "writing.logSceneRewritten": "Scene rewritten",
+  "writing.logSceneRestored": "Scene restored",
+  "writing.logNoRevisions": "No earlier revision for this scene",
   "writing.logSummary": "Summary",
   "writing.logSceneSaved": "Scene saved",
   "writing.logSceneDeleted": "Subchapter deleted",
@@ -926,7 +951,7 @@ export const en = {
   "chars.noSearchResults": "No matching characters",
   "chars.noSearchResultsDesc": "Try a different name, tag, or description.",
   "chars.importJsonPlaceholder": "{\"spec\": \"chara_card_v2\", ...}",
-  "chars.importFromFile": "Import from .json file",
+  "chars.importFromFile": "Import from .json or .png card",
   "chars.loading": "Loading...",
   "chars.editor": "Character Editor",
   "chars.exampleMessagesPlaceholder": "<START>\n{{user}}: Hello\n{{char}}: *waves* Hi there!",
@@ -958,6 +983,7 @@ export const en = {
   "chars.sampleGreeting": "*The stranger looks up from their drink, a faint smile on their lips.* \"You don't look like you belong here either.\"",
   "chars.sampleCreatorNotes": "Created as a sample character.",
   "chars.exportJson": "Export JSON",
+  "chars.exportPng": "Export PNG card",
   "chars.identity": "Identity",
   "chars.content": "Content",
   "chars.agentHero": "Agent Hero",
@@ -1260,6 +1286,14 @@ export const en = {
   "settings.confirmResetAll": "Reset all settings and onboarding state? This cannot be undone.",
   "settings.dangerZone": "Danger Zone",
   "settings.dangerZoneDesc": "Destructive actions. Use only when you need a full reset.",
+  "settings.backup": "Backup",
+  "settings.backupDesc": "Save every chat, character, setting and provider key into one password-encrypted file, or restore one on a new machine. Character avatars and uploaded files are included.",
+  "settings.backupPassword": "Backup password",
+  "settings.backupExport": "Create Backup",
+  "settings.backupRestore": "Restore from File",
+  "settings.backupSaved": "Backup saved",
+  "settings.backupFailed": "Backup failed",
+  "settings.backupConfirmRestore": "Replace all current data with this backup? This cannot be undone.",
   "settings.general": "General",
   "settings.checkForUpdates": "Check for updates on launch",
   "settings.checkForUpdatesDesc": "Compare this app version with the latest published GitHub Release. No GitHub account or token is used.",
@@ -1356,6 +1390,9 @@ export const en = {
   "settings.censorshipDesc": "Content filtering mode",
   "settings.unfiltered": "Unfiltered",
   "settings.filtered": "Filtered",
+  "settings.moderated": "Moderated",
+  "settings.moderatedDesc": "Filtered, plus every message you send is checked by a moderation endpoint first; flagged messages are refused. Leave the URL empty to use the active provider's /moderations.",
+  "settings.moderationUrl": "Moderation endpoint",
   "settings.resetDefaults": "Reset to Defaults",
   "settings.resetAll": "Reset All Settings to Defaults",
   "settings.quickPresets": "Quick Presets",
@@ -1378,6 +1415,9 @@ export const en = {
   "settings.providerType": "Provider Type",
   "settings.providerTypeOpenAi": "OpenAI-compatible",
   "settings.providerTypeKobold": "KoboldCpp (native)",
+  "settings.providerTypeOllama": "Ollama (native)",
+  "settings.providerTypeAnthropic": "Anthropic (Messages API)",
+  "settings.providerTypeGemini": "Google Gemini (native API)",
   "settings.providerTypeCustom": "Custom adapter",
   "settings.adapterId": "Adapter ID",
   "settings.adapterIdPlaceholder": "my-custom-adapter",
@@ -1390,6 +1430,8 @@ export const en = {
   "settings.providerEditorDesc": "Tune routing, credentials, and fallback models before saving the profile.",
   "settings.providerManualFallback": "Manual fallback models",
   "settings.providerManualFallbackDesc": "Used when this endpoint does not return models from /models.",
+  "settings.providerHeaders": "Custom headers",
+  "settings.providerHeadersDesc": "One `Name: value` per line. Sent with model and chat requests; an Authorization header here replaces the API key bearer token.",
   "settings.saveProvider": "Save Provider",
   "settings.test": "Test",
   "settings.refresh": "Refresh",
@@ -1437,11 +1479,12 @@ export const en = {
   "settings.apiParamsOpenAi": "OpenAI-compatible chat/completions",
   "settings.apiParamsKobold": "KoboldCpp native generate",
   "settings.sendSampler": "Send sampler parameters",
+  "settings.streamUsage": "Request token usage in streams (stream_options)",
   "settings.defaultSysPrompt": "Default System Prompt",
   "settings.defaultSystemPromptPlaceholder": "Default system prompt for new chats...",
   "settings.defaultSysPromptDesc": "This is used when the \"system\" prompt block has no custom content.",
   "settings.contextWindow": "Context Window",
-  "settings.contextSize": "Context Window Size (tokens)",
+  "settings.contextSize": "Context Window Size (tokens, 0 = use the model's window)",
   "settings.contextTailWithSummary": "Tail Budget With Summary (%)",
   "settings.contextTailWithoutSummary": "Tail Budget Without Summary (%)",
   "settings.strictGrounding": "Strict Grounding",
@@ -1459,6 +1502,11 @@ export const en = {
   "settings.baseSysPromptDesc": "Base system prompt used when the \"system\" block has no content.",
   "settings.compressModel": "Compress Model",
   "settings.compressModelDesc": "Choose model for context compression (leave empty for active model)",
+  "settings.providerFallbacks": "Fallback providers",
+  "settings.providerFallbacksDesc": "Tried in order when the active provider errors, times out or returns an empty reply before any text arrives. Remote fallbacks are skipped in Full Local Mode.",
+  "settings.providerFallbackAdd": "Add fallback",
+  "settings.moveUp": "Move up",
+  "settings.moveDown": "Move down",
   "settings.tts": "Text to Speech (TTS)",
   "settings.ttsDesc": "OpenAI-compatible endpoint and model for message speech playback.",
   "settings.ttsEndpoint": "TTS Endpoint",
@@ -1483,6 +1531,9 @@ export const en = {
   "settings.runtimeTuning": "Runtime tuning",
   "settings.runtimeTuningDesc": "Control translation and automatic multi-character turns.",
   "settings.translationTimeout": "Translation timeout (seconds)",
+  "settings.modelFetchTimeout": "Model list timeout (seconds)",
+  "settings.streamRetryAttempts": "Stream retry attempts",
+  "settings.writerSceneRevisionLimit": "Scene revisions kept",
   "settings.translationMaxTokens": "Translation max tokens",
   "settings.translationTemperature": "Translation temperature",
   "settings.autoConversationTurns": "Default auto-conversation turns",
@@ -1491,6 +1542,8 @@ export const en = {
   "settings.contextTuningDesc": "Control author notes, reasoning retention, and context compression.",
   "settings.contextMaxMessages": "Maximum history messages (0 = unlimited)",
   "settings.reasoningMaxChars": "Saved reasoning limit (characters)",
+  "settings.loreScanDepth": "LoreBook scan depth in messages (0 = whole context)",
+  "settings.loreTokenBudget": "LoreBook token budget (0 = unlimited)",
   "settings.compressionFallbackMessages": "Fallback summary messages",
   "settings.compressionMaxTokens": "Compression max tokens",
   "settings.compressionTemperature": "Compression temperature",
@@ -1581,6 +1634,12 @@ export const en = {
   "preset.roughDesc": "Aggressive, primal, intense physicality",
   "preset.passionate": "Passionate",
   "preset.passionateDesc": "Deep desire, emotional intensity, fire",
+  "preset.terse": "Terse",
+  "preset.terseDesc": "Short paragraphs, plain economical prose",
+  "preset.purple_prose": "Purple Prose",
+  "preset.purple_proseDesc": "Ornate, lyrical, richly descriptive writing",
+  "preset.script_format": "Script",
+  "preset.script_formatDesc": "Screenplay lines: NAME: dialogue, brief actions",
 
   // Title bar
   "titlebar.minimize": "Minimize",
@@ -1,6 +1,19 @@
 export const ja = {
   // App
   "app.name": "Vellium",
+  "account.lockedTitle": "Vellium はロックされています",
+  "account.lockedDesc": "チャットと設定を開くにはアカウントのパスワードを入力してください。",
+  "account.password": "パスワード",
+  "account.recoveryKey": "リカバリーキー（パスワードの代わり）",
+  "account.unlock": "ロック解除",
+  "account.unlockFailed": "パスワードまたはリカバリーキーが違います",
+  "account.lock": "ロック",
+  "errors.notConfigured": "設定が必要です",
+  "errors.network": "接続の問題",
+  "errors.provider": "プロバイダーエラー",
+  "errors.locked": "ロック中",
+  "errors.moderation": "モデレーションによりブロック",
+  "errors.notFound": "見つかりません",
   "welcome.title": "Vellium へようこそ",
   "welcome.subtitle": "初回起動の基本設定を行います。",
   "welcome.interfaceLanguage": "インターフェース言語",
@@ -176,6 +189,9 @@ export const ja = {
   "chat.startConvo": "会話を開始",
   "chat.startConvoDesc": "下にメッセージを入力すると会話が始まります。チャットは自動作成されます。",
   "chat.regenerate": "再生成",
+  "chat.truncated": "途中で中断",
+  "chat.contextTrimmed": "以前のコンテキストを省略しました：{count} 件の古いメッセージがコンテキストウィンドウに収まりませんでした。",
+  "chat.providerFallback": "{from} が失敗しました（{reason}）。{to} が応答しました。",
   "chat.compress": "圧縮",
   "chat.compressing": "圧縮中...",
   "chat.send": "送信",
@@ -261,6 +277,8 @@ export const ja = {
   "chat.confirmDeleteChat": "このチャットを削除しますか？",
   "chat.searchChats": "チャットを検索...",
   "chat.renameChat": "チャット名を変更",
+  "chat.autoTitle": "自動",
+  "chat.autoTitleHint": "最初のやり取りからアクティブモデルにタイトルを付けてもらう",
   "chat.rename": "名前変更",
   "chat.renameEmptyError": "チャット名は空にできません",
   "chat.noSearchResults": "チャットが見つかりません",
@@ -278,6 +296,9 @@ export const ja = {
   "chat.lastBranchHint": "最後のブランチは削除できません",
   "chat.branchRoot": "ルートブランチ",
   "chat.branchFork": "分岐パス",
+  "chat.branchMessages": "{count} 件のメッセージ",
+  "chat.mergeBranch": "ルートブランチに統合",
+  "chat.confirmMergeBranch": "「{name}」の新しいメッセージをルートブランチに追加して、このブランチを削除しますか？",
   "chat.loading": "読み込み中...",
   "chat.imageAttachment": "画像添付",
   "chat.generatedMedia": "生成メディア",
@@ -409,6 +430,7 @@ export const ja = {
   "writing.consistency": "整合性チェック",
   "writing.expand": "展開",
   "writing.rewrite": "書き直し",
+  "writing.undo": "元に戻す",
   "writing.summarize": "要約",
   "writing.generate": "生成",
   "writing.working": "処理中...",
@@ -500,6 +522,7 @@ export const ja = {
   "writing.styleGuide": "スタイルガイド（文体・時制・ルール）",
   "writing.worldRules": "世界観ルール / 設定制約",
   "writing.characterLedger": "キャラクター台帳 / アーク",
+  "writing.characterRoster": "名前チェック用のキャラクター名簿：1行に1人、「名前 @ シーン名」も可",
   "writing.bookSummary": "本の要約",
   "writing.summaryCached": "キャッシュ要約を使用",
   "writing.summaryRefreshed": "要約を再生成しました",
@@ -524,6 +547,8 @@ export const ja = {
   "writing.issuesCount": "件",
   "writing.logSceneExpanded": "シーンを展開しました",
   "writing.logSceneRewritten": "シーンを書き直しました",
+  "writing.logSceneRestored": "シーンを復元しました",
+  "writing.logNoRevisions": "このシーンに以前の版はありません",
   "writing.logSummary": "要約",
   "writing.logSceneSaved": "シーンを保存しました",
   "writing.logSceneDeleted": "サブチャプターを削除しました",
@@ -607,7 +632,7 @@ export const ja = {
   "chars.noSearchResults": "一致するキャラクターがありません",
   "chars.noSearchResultsDesc": "別の名前、タグ、説明を試してください。",
   "chars.importJsonPlaceholder": "{\"spec\": \"chara_card_v2\", ...}",
-  "chars.importFromFile": ".json ファイルからインポート",
+  "chars.importFromFile": ".json または .png カードからインポート",
   "chars.loading": "読み込み中...",
   "chars.editor": "キャラクターエディタ",
   "chars.exampleMessagesPlaceholder": "<START>\n{{user}}: こんにちは\n{{char}}: *手を振る* こんにちは！",
@@ -639,6 +664,7 @@ export const ja = {
   "chars.sampleGreeting": "*見知らぬ旅人は杯から顔を上げ、かすかに微笑む。* 「君もここには馴染んでいないようだね。」",
   "chars.sampleCreatorNotes": "サンプル用に作成したキャラクターです。",
   "chars.exportJson": "JSON エクスポート",
+  "chars.exportPng": "PNG カードをエクスポート",
   "chars.identity": "アイデンティティ",
   "chars.content": "コンテンツ",
   "chars.editorTabs": "キャラクターエディターのセクション",
@@ -731,6 +757,12 @@ export const ja = {
   "preset.roughDesc": "荒々しく原始的、強い勢い",
   "preset.passionate": "情熱的",
   "preset.passionateDesc": "強い欲求と高い感情強度",
+  "preset.terse": "簡潔",
+  "preset.terseDesc": "短い段落、無駄のない平易な文章",
+  "preset.purple_prose": "華麗な文体",
+  "preset.purple_proseDesc": "装飾的で叙情的、描写豊かな文章",
+  "preset.script_format": "脚本形式",
+  "preset.script_formatDesc": "脚本風の台詞：名前: セリフ、短いト書き",
 
   // Key Settings labels
   "settings.basic": "基本",
@@ -858,6 +890,14 @@ export const ja = {
   "settings.confirmResetAll": "すべての設定とウェルカム画面の状態をリセットしますか？この操作は元に戻せません。",
   "settings.dangerZone": "危険ゾーン",
   "settings.dangerZoneDesc": "破壊的な操作です。完全リセットが必要な場合のみ使用してください。",
+  "settings.backup": "バックアップ",
+  "settings.backupDesc": "すべてのチャット、キャラクター、設定、プロバイダーキーをパスワードで暗号化した1つのファイルに保存し、新しいマシンで復元できます。キャラクターのアバターとアップロードファイルも含まれます。",
+  "settings.backupPassword": "バックアップのパスワード",
+  "settings.backupExport": "バックアップを作成",
+  "settings.backupRestore": "ファイルから復元",
+  "settings.backupSaved": "バックアップを保存しました",
+  "settings.backupFailed": "バックアップに失敗しました",
+  "settings.backupConfirmRestore": "現在のデータをすべてこのバックアップで置き換えますか？元に戻せません。",
   "settings.general": "一般",
   "settings.checkForUpdates": "起動時にアップデートを確認",
   "settings.checkForUpdatesDesc": "現在のアプリ版を最新の公開 GitHub Release と比較します。GitHub アカウントやトークンは使用しません。",
@@ -898,6 +938,9 @@ export const ja = {
   "settings.censorshipDesc": "コンテンツフィルタリングモード",
   "settings.unfiltered": "無制限",
   "settings.filtered": "フィルター有効",
+  "settings.moderated": "モデレーション",
+  "settings.moderatedDesc": "フィルター有効に加え、送信するメッセージを先にモデレーションエンドポイントで確認し、フラグが付いたものは送信しません。URL を空にするとアクティブなプロバイダーの /moderations を使います。",
+  "settings.moderationUrl": "モデレーションエンドポイント",
   "settings.resetDefaults": "デフォルトに戻す",
   "settings.resetAll": "全設定をデフォルトに戻す",
   "settings.quickPresets": "クイックプリセット",
@@ -915,7 +958,12 @@ export const ja = {
   "settings.providerType": "プロバイダータイプ",
   "settings.providerTypeOpenAi": "OpenAI 互換",
   "settings.providerTypeKobold": "KoboldCpp（ネイティブ）",
+  "settings.providerTypeOllama": "Ollama（ネイティブ）",
+  "settings.providerTypeAnthropic": "Anthropic（Messages API）",
+  "settings.providerTypeGemini": "Google Gemini（ネイティブ API）",
   "settings.providerTypeCustom": "カスタムアダプター",
+  "settings.providerHeaders": "カスタムヘッダー",
+  "settings.providerHeadersDesc": "1 行に 1 つ `Name: value` の形式で入力します。モデル一覧とチャットのリクエストに付与され、ここで設定した Authorization は API キーの Bearer トークンより優先されます。",
   "settings.adapterId": "アダプター ID",
   "settings.adapterIdPlaceholder": "my-custom-adapter",
   "settings.fillAdapterRequired": "カスタムプロバイダーにはアダプター ID が必要です",
@@ -967,11 +1015,12 @@ export const ja = {
   "settings.apiParamsOpenAi": "OpenAI 互換 chat/completions",
   "settings.apiParamsKobold": "KoboldCpp ネイティブ generate",
   "settings.sendSampler": "サンプラーパラメータを送信",
+  "settings.streamUsage": "ストリームでトークン使用量を要求（stream_options）",
   "settings.defaultSysPrompt": "デフォルトシステムプロンプト",
   "settings.defaultSystemPromptPlaceholder": "新規チャット用のデフォルトシステムプロンプト...",
   "settings.defaultSysPromptDesc": "system ブロックに内容がない場合に使用されます。",
   "settings.contextWindow": "コンテキストウィンドウ",
-  "settings.contextSize": "コンテキストサイズ（トークン）",
+  "settings.contextSize": "コンテキストサイズ（トークン、0 = モデルのウィンドウ）",
   "settings.contextTailWithSummary": "要約あり時の末尾予算（%）",
   "settings.contextTailWithoutSummary": "要約なし時の末尾予算（%）",
   "settings.strictGrounding": "厳格グラウンディング",
@@ -989,6 +1038,11 @@ export const ja = {
   "settings.baseSysPromptDesc": "system ブロックが空のときに使うベースシステムプロンプト。",
   "settings.compressModel": "圧縮モデル",
   "settings.compressModelDesc": "コンテキスト圧縮用のモデル（空欄ならアクティブモデル）",
+  "settings.providerFallbacks": "フォールバックプロバイダー",
+  "settings.providerFallbacksDesc": "アクティブなプロバイダーがテキストを返す前にエラー・タイムアウト・空の返信になった場合、順番に試します。完全ローカルモードではリモートのものはスキップされます。",
+  "settings.providerFallbackAdd": "フォールバックを追加",
+  "settings.moveUp": "上へ",
+  "settings.moveDown": "下へ",
   "settings.tts": "音声読み上げ (TTS)",
   "settings.ttsDesc": "メッセージ読み上げ用の OpenAI 互換エンドポイントとモデルを設定します。",
   "settings.ttsEndpoint": "TTS エンドポイント",
@@ -1013,6 +1067,9 @@ export const ja = {
   "settings.runtimeTuning": "実行パラメータ",
   "settings.runtimeTuningDesc": "翻訳と複数キャラクターの自動会話を調整します。",
   "settings.translationTimeout": "翻訳タイムアウト（秒）",
+  "settings.modelFetchTimeout": "モデル一覧のタイムアウト（秒）",
+  "settings.streamRetryAttempts": "ストリーム再試行回数",
+  "settings.writerSceneRevisionLimit": "保持するシーン履歴数",
   "settings.translationMaxTokens": "翻訳の最大トークン数",
   "settings.translationTemperature": "翻訳温度",
   "settings.autoConversationTurns": "自動会話の既定ターン数",
@@ -1021,6 +1078,8 @@ export const ja = {
   "settings.contextTuningDesc": "作者ノート、推論保持、コンテキスト圧縮を調整します。",
   "settings.contextMaxMessages": "履歴メッセージ上限（0 = 無制限）",
   "settings.reasoningMaxChars": "保存する推論の上限（文字数）",
+  "settings.loreScanDepth": "LoreBook のスキャン深度（メッセージ数、0 = コンテキスト全体）",
+  "settings.loreTokenBudget": "LoreBook のトークン予算（0 = 無制限）",
   "settings.compressionFallbackMessages": "代替要約のメッセージ数",
   "settings.compressionMaxTokens": "圧縮の最大トークン数",
   "settings.compressionTemperature": "圧縮温度",
@@ -1,6 +1,19 @@
 export const ru = {
   // App
   "app.name": "Vellium",
+  "account.lockedTitle": "Vellium заблокирован",
+  "account.lockedDesc": "Введите пароль аккаунта, чтобы открыть чаты и настройки.",
+  "account.password": "Пароль",
+  "account.recoveryKey": "Ключ восстановления (вместо пароля)",
+  "account.unlock": "Разблокировать",
+  "account.unlockFailed": "Неверный пароль или ключ восстановления",
+  "account.lock": "Заблокировать",
+  "errors.notConfigured": "Нужна настройка",
+  "errors.network": "Проблема с подключением",
+  "errors.provider": "Ошибка провайдера",
+  "errors.locked": "Заблокировано",
+  "errors.moderation": "Заблокировано модерацией",
+  "errors.notFound": "Не найдено",
   "welcome.title": "Добро пожаловать в Vellium",
   "welcome.subtitle": "Давайте настроим базовые параметры для первого запуска.",
   "welcome.interfaceLanguage": "Язык интерфейса",
@@ -480,6 +493,9 @@ export const ru = {
   "chat.startConvo": "Начните диалог",
   "chat.startConvoDesc": "Напишите сообщение ниже. Чат создастся автоматически.",
   "chat.regenerate": "Пересоздать",
+  "chat.truncated": "Обрезано",
+  "chat.contextTrimmed": "Ранний контекст сокращён: {count} старых сообщений не поместились в окно контекста.",
+  "chat.providerFallback": "{from} не ответил ({reason}); ответил {to}.",
   "chat.compress": "Сжать",
   "chat.compressing": "Сжатие...",
   "chat.exportJson": "Экспорт чата в JSON",
@@ -575,6 +591,8 @@ export const ru = {
   "chat.confirmDeleteChat": "Удалить этот чат?",
   "chat.searchChats": "Поиск чатов...",
   "chat.renameChat": "Переименовать чат",
+  "chat.autoTitle": "Авто",
+  "chat.autoTitleHint": "Попросить активную модель придумать название по первому обмену сообщениями",
   "chat.rename": "Переименовать",
   "chat.renameEmptyError": "Название чата не может быть пустым",
   "chat.noSearchResults": "Чаты не найдены",
@@ -592,6 +610,9 @@ export const ru = {
   "chat.lastBranchHint": "Последнюю ветку удалить нельзя",
   "chat.branchRoot": "Корневая ветка",
   "chat.branchFork": "Ответвление",
+  "chat.branchMessages": "Сообщений: {count}",
+  "chat.mergeBranch": "Слить с корневой веткой",
+  "chat.confirmMergeBranch": "Добавить новые сообщения из «{name}» в корневую ветку и удалить её?",
   "chat.loading": "Загрузка...",
   "chat.imageAttachment": "изображение",
   "chat.generatedMedia": "Созданные изображения",
@@ -728,6 +749,7 @@ export const ru = {
   "writing.consistency": "Проверка",
   "writing.expand": "Развернуть",
   "writing.rewrite": "Переписать",
+  "writing.undo": "Отменить",
   "writing.summarize": "Резюме",
   "writing.generate": "Сгенерировать",
   "writing.working": "Работает...",
@@ -819,6 +841,7 @@ export const ru = {
   "writing.styleGuide": "Стилевой гайд (голос, время, правила прозы)",
   "writing.worldRules": "Правила мира / канон-ограничения",
   "writing.characterLedger": "Реестр персонажей / арки",
+  "writing.characterRoster": "Список персонажей для проверки имён: по одному в строке, можно «Имя @ Название сцены»",
   "writing.bookSummary": "Summary книги",
   "writing.summaryCached": "Summary взят из кэша",
   "writing.summaryRefreshed": "Summary пересчитан",
@@ -843,6 +866,8 @@ export const ru = {
   "writing.issuesCount": "проблем",
   "writing.logSceneExpanded": "Сцена расширена",
   "writing.logSceneRewritten": "Сцена переписана",
+  "writing.logSceneRestored": "Сцена восстановлена",
+  "writing.logNoRevisions": "Нет более ранних версий этой сцены",
   "writing.logSummary": "Резюме",
   "writing.logSceneSaved": "Сцена сохранена",
   "writing.logSceneDeleted": "Подглава удалена",
@@ -926,7 +951,7 @@ export const ru = {
   "chars.noSearchResults": "Персонажи не найдены",
   "chars.noSearchResultsDesc": "Попробуйте другое имя, тег или описание.",
   "chars.importJsonPlaceholder": "{\"spec\": \"chara_card_v2\", ...}",
-  "chars.importFromFile": "Импорт из .json файла",
+  "chars.importFromFile": "Импорт из .json или .png карточки",
   "chars.loading": "Загрузка...",
   "chars.editor": "Редактор персонажа",
   "chars.exampleMessagesPlaceholder": "<START>\n{{user}}: Привет\n{{char}}: *машет* Привет!",
@@ -958,6 +983,7 @@ export const ru = {
   "chars.sampleGreeting": "*Незнакомец поднимает взгляд от своего напитка и едва заметно улыбается.* \"Похоже, тебе тоже тут не место.\"",
   "chars.sampleCreatorNotes": "Создано как пример персонажа.",
   "chars.exportJson": "Экспорт JSON",
+  "chars.exportPng": "Экспорт PNG-карточки",
   "chars.identity": "Идентичность",
   "chars.content": "Контент",
   "chars.agentHero": "Agent Hero",
@@ -1260,6 +1286,14 @@ export const ru = {
   "settings.confirmResetAll": "Сбросить все настройки и состояние приветственного экрана? Это действие нельзя отменить.",
   "settings.dangerZone": "Опасная зона",
   "settings.dangerZoneDesc": "Разрушающие действия. Используйте только для полного сброса.",
+  "settings.backup": "Резервная копия",
+  "settings.backupDesc": "Сохраните все чаты, персонажей, настройки и ключи провайдеров в один файл, зашифрованный паролем, или восстановите его на новом компьютере. Аватары персонажей и загруженные файлы тоже сохраняются.",
+  "settings.backupPassword": "Пароль резервной копии",
+  "settings.backupExport": "Создать копию",
+  "settings.backupRestore": "Восстановить из файла",
+  "settings.backupSaved": "Копия сохранена",
+  "settings.backupFailed": "Ошибка резервного копирования",
+  "settings.backupConfirmRestore": "Заменить все текущие данные этой копией? Действие нельзя отменить.",
   "settings.general": "Общие",
   "settings.checkForUpdates": "Проверять обновления при запуске",
   "settings.checkForUpdatesDesc": "Сравнивать версию приложения с последним опубликованным GitHub Release. Аккаунт и токен GitHub не используются.",
@@ -1356,6 +1390,9 @@ export const ru = {
   "settings.censorshipDesc": "Режим фильтрации контента",
   "settings.unfiltered": "Без фильтра",
   "settings.filtered": "С фильтром",
+  "settings.moderated": "С модерацией",
+  "settings.moderatedDesc": "Как «С фильтром», но каждое сообщение сначала проверяется сервисом модерации; отмеченные сообщения не отправляются. Оставьте адрес пустым, чтобы использовать /moderations активного провайдера.",
+  "settings.moderationUrl": "Адрес модерации",
   "settings.resetDefaults": "Сбросить настройки",
   "settings.resetAll": "Сбросить все настройки",
   "settings.quickPresets": "Быстрые пресеты",
@@ -1378,6 +1415,9 @@ export const ru = {
   "settings.providerType": "Тип провайдера",
   "settings.providerTypeOpenAi": "Совместимый с OpenAI",
   "settings.providerTypeKobold": "KoboldCpp (нативный)",
+  "settings.providerTypeOllama": "Ollama (нативный)",
+  "settings.providerTypeAnthropic": "Anthropic (Messages API)",
+  "settings.providerTypeGemini": "Google Gemini (нативный API)",
   "settings.providerTypeCustom": "Кастомный адаптер",
   "settings.adapterId": "ID адаптера",
   "settings.adapterIdPlaceholder": "my-custom-adapter",
@@ -1390,6 +1430,8 @@ export const ru = {
   "settings.providerEditorDesc": "Настройте маршрутизацию, ключи и fallback-модели перед сохранением профиля.",
   "settings.providerManualFallback": "Fallback-модели вручную",
   "settings.providerManualFallbackDesc": "Используются, если этот endpoint не возвращает модели из /models.",
+  "settings.providerHeaders": "Дополнительные заголовки",
+  "settings.providerHeadersDesc": "По одному `Name: value` на строку. Отправляются с запросами моделей и чата; заголовок Authorization здесь заменяет Bearer-токен из API-ключа.",
   "settings.saveProvider": "Сохранить провайдера",
   "settings.test": "Тест",
   "settings.refresh": "Обновить",
@@ -1437,11 +1479,12 @@ export const ru = {
   "settings.apiParamsOpenAi": "OpenAI-compatible chat/completions",
   "settings.apiParamsKobold": "KoboldCpp native generate",
   "settings.sendSampler": "Отправлять параметры сэмплера",
+  "settings.streamUsage": "Запрашивать расход токенов в потоке (stream_options)",
   "settings.defaultSysPrompt": "Системный промпт по умолчанию",
   "settings.defaultSystemPromptPlaceholder": "Системный промпт по умолчанию для новых чатов...",
   "settings.defaultSysPromptDesc": "Используется, если блок \"system\" не имеет содержимого.",
   "settings.contextWindow": "Окно контекста",
-  "settings.contextSize": "Размер окна контекста (токены)",
+  "settings.contextSize": "Размер окна контекста (токены, 0 = окно модели)",
   "settings.contextTailWithSummary": "Бюджет хвоста с summary (%)",
   "settings.contextTailWithoutSummary": "Бюджет хвоста без summary (%)",
   "settings.strictGrounding": "Строгий grounding",
@@ -1459,6 +1502,11 @@ export const ru = {
   "settings.baseSysPromptDesc": "Базовый системный промпт, когда блок \"system\" пуст.",
   "settings.compressModel": "Модель сжатия",
   "settings.compressModelDesc": "Модель для сжатия контекста (пусто = активная модель)",
+  "settings.providerFallbacks": "Резервные провайдеры",
+  "settings.providerFallbacksDesc": "Используются по порядку, если активный провайдер вернул ошибку, не ответил вовремя или прислал пустой ответ до начала текста. В полностью локальном режиме удалённые пропускаются.",
+  "settings.providerFallbackAdd": "Добавить резерв",
+  "settings.moveUp": "Выше",
+  "settings.moveDown": "Ниже",
   "settings.tts": "Озвучка (TTS)",
   "settings.ttsDesc": "OpenAI-compatible endpoint и модель для озвучивания сообщений.",
   "settings.ttsEndpoint": "TTS endpoint",
@@ -1483,6 +1531,9 @@ export const ru = {
   "settings.runtimeTuning": "Параметры выполнения",
   "settings.runtimeTuningDesc": "Настройка перевода и автоматических ходов нескольких персонажей.",
   "settings.translationTimeout": "Таймаут перевода (секунды)",
+  "settings.modelFetchTimeout": "Таймаут списка моделей (сек)",
+  "settings.streamRetryAttempts": "Повторы при сбое потока",
+  "settings.writerSceneRevisionLimit": "Хранить версий сцены",
   "settings.translationMaxTokens": "Максимум токенов перевода",
   "settings.translationTemperature": "Температура перевода",
   "settings.autoConversationTurns": "Ходов автодиалога по умолчанию",
@@ -1491,6 +1542,8 @@ export const ru = {
   "settings.contextTuningDesc": "Настройка author note, хранения reasoning и сжатия контекста.",
   "settings.contextMaxMessages": "Максимум сообщений истории (0 = без лимита)",
   "settings.reasoningMaxChars": "Лимит reasoning (символы)",
+  "settings.loreScanDepth": "Глубина сканирования лорбука в сообщениях (0 = весь контекст)",
+  "settings.loreTokenBudget": "Бюджет токенов лорбука (0 = без ограничений)",
   "settings.compressionFallbackMessages": "Сообщений в резервном summary",
   "settings.compressionMaxTokens": "Максимум токенов сжатия",
   "settings.compressionTemperature": "Температура сжатия",
@@ -1581,6 +1634,12 @@ export const ru = {
   "preset.roughDesc": "Агрессивный, первобытный, интенсивная физичность",
   "preset.passionate": "Страстный",
   "preset.passionateDesc": "Глубокое желание, эмоциональная интенсивность",
+  "preset.terse": "Лаконичный",
+  "preset.terseDesc": "Короткие абзацы, простая сжатая проза",
+  "preset.purple_prose": "Витиеватый",
+  "preset.purple_proseDesc": "Пышный, лиричный, насыщенный описаниями стиль",
+  "preset.script_format": "Сценарий",
+  "preset.script_formatDesc": "Реплики как в сценарии: ИМЯ: текст, краткие действия",
 
   // Title bar
   "titlebar.minimize": "Свернуть",
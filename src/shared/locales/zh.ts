@@ -1,6 +1,19 @@
 export const zh = {
   // App
   "app.name": "Vellium",
+  "account.lockedTitle": "Vellium 已锁定",
+  "account.lockedDesc": "输入账户密码以打开聊天和设置。",
+  "account.password": "密码",
+  "account.recoveryKey": "恢复密钥（代替密码）",
+  "account.unlock": "解锁",
+  "account.unlockFailed": "密码或恢复密钥错误",
+  "account.lock": "锁定",
+  "errors.notConfigured": "需要配置",
+  "errors.network": "连接问题",
+  "errors.provider": "提供商错误",
+  "errors.locked": "已锁定",
+  "errors.moderation": "已被审核拦截",
+  "errors.notFound": "未找到",
   "welcome.title": "欢迎使用 Vellium",
   "welcome.subtitle": "先完成首次启动的基础设置。",
   "welcome.interfaceLanguage": "界面语言",
@@ -175,6 +188,9 @@ export const zh = {
   "chat.startConvo": "开始对话",
   "chat.startConvoDesc": "在下方输入消息即可开始。会自动创建会话。",
   "chat.regenerate": "重新生成",
+  "chat.truncated": "已截断",
+  "chat.contextTrimmed": "已裁剪早期上下文：{count} 条较早的消息超出了上下文窗口。",
+  "chat.providerFallback": "{from} 失败（{reason}）；由 {to} 回复。",
   "chat.compress": "压缩",
   "chat.compressing": "压缩中...",
   "chat.send": "发送",
@@ -258,6 +274,8 @@ export const zh = {
   "chat.confirmDeleteChat": "确认删除该会话？",
   "chat.searchChats": "搜索会话...",
   "chat.renameChat": "重命名会话",
+  "chat.autoTitle": "自动",
+  "chat.autoTitleHint": "让当前模型根据第一轮对话生成标题",
   "chat.rename": "重命名",
   "chat.renameEmptyError": "会话标题不能为空",
   "chat.noSearchResults": "未找到会话",
@@ -275,6 +293,9 @@ export const zh = {
   "chat.lastBranchHint": "无法删除最后一个分支",
   "chat.branchRoot": "根分支",
   "chat.branchFork": "分叉路径",
+  "chat.branchMessages": "{count} 条消息",
+  "chat.mergeBranch": "合并到根分支",
+  "chat.confirmMergeBranch": "将“{name}”中的新消息追加到根分支并删除该分支？",
   "chat.loading": "加载中...",
   "chat.imageAttachment": "图片附件",
   "chat.generatedMedia": "生成的媒体",
@@ -406,6 +427,7 @@ export const zh = {
   "writing.consistency": "一致性检查",
   "writing.expand": "扩写",
   "writing.rewrite": "改写",
+  "writing.undo": "撤销",
   "writing.summarize": "总结",
   "writing.generate": "生成",
   "writing.working": "处理中...",
@@ -497,6 +519,7 @@ export const zh = {
   "writing.styleGuide": "风格指南（语气、时态、文风规则）",
   "writing.worldRules": "世界规则 / 设定约束",
   "writing.characterLedger": "角色档案 / 人物弧线",
+  "writing.characterRoster": "用于姓名检查的角色名单：每行一个，可写作“姓名 @ 场景标题”",
   "writing.bookSummary": "全书摘要",
   "writing.summaryCached": "摘要来自缓存",
   "writing.summaryRefreshed": "摘要已重新生成",
@@ -521,6 +544,8 @@ export const zh = {
   "writing.issuesCount": "个问题",
   "writing.logSceneExpanded": "场景已扩写",
   "writing.logSceneRewritten": "场景已改写",
+  "writing.logSceneRestored": "场景已恢复",
+  "writing.logNoRevisions": "此场景没有更早的版本",
   "writing.logSummary": "总结",
   "writing.logSceneSaved": "场景已保存",
   "writing.logSceneDeleted": "子章节已删除",
@@ -604,7 +629,7 @@ export const zh = {
   "chars.noSearchResults": "没有匹配的角色",
   "chars.noSearchResultsDesc": "请尝试其他名称、标签或描述。",
   "chars.importJsonPlaceholder": "{\"spec\": \"chara_card_v2\", ...}",
-  "chars.importFromFile": "从 .json 文件导入",
+  "chars.importFromFile": "从 .json 或 .png 角色卡导入",
   "chars.loading": "加载中...",
   "chars.editor": "角色编辑器",
   "chars.exampleMessagesPlaceholder": "<START>\n{{user}}: 你好\n{{char}}: *挥手* 你好！",
@@ -636,6 +661,7 @@ export const zh = {
   "chars.sampleGreeting": "*陌生人从酒杯上抬起头，嘴角露出淡淡微笑。* \"看起来你也不属于这里。\"",
   "chars.sampleCreatorNotes": "作为示例角色创建。",
   "chars.exportJson": "导出 JSON",
+  "chars.exportPng": "导出 PNG 角色卡",
   "chars.identity": "身份",
   "chars.content": "内容",
   "chars.editorTabs": "角色编辑器分区",
@@ -728,6 +754,12 @@ export const zh = {
   "preset.roughDesc": "强烈、原始、攻击性更高",
   "preset.passionate": "炽热",
   "preset.passionateDesc": "欲望强烈，情绪浓烈",
+  "preset.terse": "简洁",
+  "preset.terseDesc": "短段落，朴素精炼的文字",
+  "preset.purple_prose": "华丽文风",
+  "preset.purple_proseDesc": "华丽抒情、描写丰富的写法",
+  "preset.script_format": "剧本格式",
+  "preset.script_formatDesc": "剧本式台词：姓名: 台词，简短动作",
 
   // Chat/Settings quick labels
   "chat.new": "新建",
@@ -858,6 +890,14 @@ export const zh = {
   "settings.confirmResetAll": "要重置所有设置和欢迎引导状态吗？此操作无法撤销。",
   "settings.dangerZone": "危险区域",
   "settings.dangerZoneDesc": "破坏性操作。仅在需要完全重置时使用。",
+  "settings.backup": "备份",
+  "settings.backupDesc": "将所有聊天、角色、设置和提供商密钥保存到一个用密码加密的文件中，或在新机器上恢复。角色头像和上传的文件也会包含在内。",
+  "settings.backupPassword": "备份密码",
+  "settings.backupExport": "创建备份",
+  "settings.backupRestore": "从文件恢复",
+  "settings.backupSaved": "备份已保存",
+  "settings.backupFailed": "备份失败",
+  "settings.backupConfirmRestore": "用此备份替换当前所有数据？此操作无法撤销。",
   "settings.general": "通用",
   "settings.checkForUpdates": "启动时检查更新",
   "settings.checkForUpdatesDesc": "将当前应用版本与最新发布的 GitHub Release 进行比较，无需 GitHub 帐户或令牌。",
@@ -898,6 +938,9 @@ export const zh = {
   "settings.censorshipDesc": "内容过滤策略",
   "settings.unfiltered": "不过滤",
   "settings.filtered": "过滤",
+  "settings.moderated": "审核",
+  "settings.moderatedDesc": "在过滤基础上，发送的每条消息都会先经过审核端点检查；被标记的消息将被拒绝。留空地址则使用当前提供商的 /moderations。",
+  "settings.moderationUrl": "审核端点",
   "settings.resetDefaults": "恢复默认",
   "settings.resetAll": "重置所有设置",
   "settings.quickPresets": "快速预设",
@@ -915,7 +958,12 @@ export const zh = {
   "settings.providerType": "提供商类型",
   "settings.providerTypeOpenAi": "OpenAI 兼容",
   "settings.providerTypeKobold": "KoboldCpp（原生）",
+  "settings.providerTypeOllama": "Ollama（原生）",
+  "settings.providerTypeAnthropic": "Anthropic（Messages API）",
+  "settings.providerTypeGemini": "Google Gemini（原生 API）",
   "settings.providerTypeCustom": "自定义适配器",
+  "settings.providerHeaders": "自定义请求头",
+  "settings.providerHeadersDesc": "每行一个 `Name: value`。随模型列表和聊天请求发送；此处设置的 Authorization 会覆盖 API 密钥生成的 Bearer 令牌。",
   "settings.adapterId": "适配器 ID",
   "settings.adapterIdPlaceholder": "my-custom-adapter",
   "settings.fillAdapterRequired": "自定义提供商必须填写适配器 ID",
@@ -967,11 +1015,12 @@ export const zh = {
   "settings.apiParamsOpenAi": "OpenAI 兼容 chat/completions",
   "settings.apiParamsKobold": "KoboldCpp 原生 generate",
   "settings.sendSampler": "发送采样参数",
+  "settings.streamUsage": "在流中请求 token 用量（stream_options）",
   "settings.defaultSysPrompt": "默认系统提示词",
   "settings.defaultSystemPromptPlaceholder": "新聊天默认系统提示词...",
   "settings.defaultSysPromptDesc": "当 system 块为空时使用。",
   "settings.contextWindow": "上下文窗口",
-  "settings.contextSize": "上下文大小（tokens）",
+  "settings.contextSize": "上下文大小（tokens，0 = 使用模型窗口）",
   "settings.contextTailWithSummary": "有摘要时尾部预算（%）",
   "settings.contextTailWithoutSummary": "无摘要时尾部预算（%）",
   "settings.strictGrounding": "严格 Grounding",
@@ -989,6 +1038,11 @@ export const zh = {
   "settings.baseSysPromptDesc": "当 system 块为空时使用的基础系统提示词。",
   "settings.compressModel": "压缩模型",
   "settings.compressModelDesc": "用于上下文压缩的模型（留空=活动模型）",
+  "settings.providerFallbacks": "备用提供商",
+  "settings.providerFallbacksDesc": "当活动提供商在输出文本前出错、超时或返回空回复时，按顺序尝试。完全本地模式下会跳过远程备用。",
+  "settings.providerFallbackAdd": "添加备用",
+  "settings.moveUp": "上移",
+  "settings.moveDown": "下移",
   "settings.tts": "语音合成（TTS）",
   "settings.ttsDesc": "用于消息语音播放的 OpenAI 兼容端点与模型。",
   "settings.ttsEndpoint": "TTS 端点",
@@ -1013,6 +1067,9 @@ export const zh = {
   "settings.runtimeTuning": "运行参数",
   "settings.runtimeTuningDesc": "控制翻译和多角色自动对话。",
   "settings.translationTimeout": "翻译超时（秒）",
+  "settings.modelFetchTimeout": "模型列表超时（秒）",
+  "settings.streamRetryAttempts": "流式重试次数",
+  "settings.writerSceneRevisionLimit": "保留的场景版本数",
   "settings.translationMaxTokens": "翻译最大令牌数",
   "settings.translationTemperature": "翻译温度",
   "settings.autoConversationTurns": "默认自动对话轮数",
@@ -1021,6 +1078,8 @@ export const zh = {
   "settings.contextTuningDesc": "控制作者注释、推理保留和上下文压缩。",
   "settings.contextMaxMessages": "历史消息上限（0 = 无限制）",
   "settings.reasoningMaxChars": "保存推理上限（字符）",
+  "settings.loreScanDepth": "LoreBook 扫描深度（消息数，0 = 全部上下文）",
+  "settings.loreTokenBudget": "LoreBook 令牌预算（0 = 不限）",
   "settings.compressionFallbackMessages": "备用摘要消息数",
   "settings.compressionMaxTokens": "压缩最大令牌数",
   "settings.compressionTemperature": "压缩温度",
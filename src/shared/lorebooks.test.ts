@@ -1,5 +1,5 @@
 import { describe, expect, it } from "vitest";
-import { getTriggeredLoreEntries, injectLoreBlocks, normalizeLoreBookEntries, parseSillyTavernWorldInfo } from "../../server/domain/lorebooks";
+import { getTriggeredLoreEntries, injectLoreBlocks, limitLoreEntries, normalizeLoreBookEntries, parseSillyTavernWorldInfo } from "../../server/domain/lorebooks";
 import type { PromptBlock } from "../../server/domain/rpEngine";
 
 describe("lorebooks trigger matching", () => {
@@ -17,6 +17,21 @@ describe("lorebooks trigger matching", () => {
   });
 });
 
+describe("lorebooks injection budget", () => {
+  it("drops duplicate entries and skips entries that no longer fit the budget", () => {
+    const entries = normalizeLoreBookEntries([
+      { id: "city", keys: ["city"], content: "The city floods every spring.", enabled: true, position: "after_char", insertion_order: 100 },
+      { id: "city-copy", keys: ["city"], content: "  the city floods   every spring. ", enabled: true, position: "after_char", insertion_order: 150 },
+      { id: "long", keys: ["city"], content: "x".repeat(400), enabled: true, position: "after_char", insertion_order: 200 },
+      { id: "tower", keys: ["city"], content: "The tower is sealed.", enabled: true, position: "after_char", insertion_order: 300 }
+    ]);
+    const triggered = getTriggeredLoreEntries(entries, ["we reach the city"]);
+
+    expect(limitLoreEntries(triggered, 0, (text) => text.length).map((item) => item.id)).toEqual(["city", "long", "tower"]);
+    expect(limitLoreEntries(triggered, 60, (text) => text.length).map((item) => item.id)).toEqual(["city", "tower"]);
+  });
+});
+
 describe("lorebooks position anchors", () => {
   it("places author_note/history anchors near their proper prompt stack orders", () => {
     const baseBlocks: PromptBlock[] = [
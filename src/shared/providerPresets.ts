@@ -7,7 +7,7 @@ export type ProviderPreset = {
   defaultName: string;
   apiKeyHint: string;
   localOnly: boolean;
-  providerType: "openai" | "koboldcpp";
+  providerType: "openai" | "koboldcpp" | "ollama" | "anthropic" | "gemini";
 };
 
 export const PROVIDER_PRESETS: ProviderPreset[] = [
@@ -22,6 +22,28 @@ export const PROVIDER_PRESETS: ProviderPreset[] = [
     localOnly: false,
     providerType: "openai"
   },
+  {
+    key: "anthropic",
+    label: "Anthropic",
+    description: "Claude models via the Messages API",
+    baseUrl: "https://api.anthropic.com/v1",
+    defaultId: "anthropic",
+    defaultName: "Anthropic",
+    apiKeyHint: "sk-ant-...",
+    localOnly: false,
+    providerType: "anthropic"
+  },
+  {
+    key: "gemini",
+    label: "Google Gemini",
+    description: "Gemini models via the native Generative Language API",
+    baseUrl: "https://generativelanguage.googleapis.com/v1beta",
+    defaultId: "gemini",
+    defaultName: "Google Gemini",
+    apiKeyHint: "AIza...",
+    localOnly: false,
+    providerType: "gemini"
+  },
   {
     key: "lm_studio",
     label: "LM Studio",
@@ -36,13 +58,13 @@ export const PROVIDER_PRESETS: ProviderPreset[] = [
   {
     key: "ollama",
     label: "Ollama",
-    description: "Ollama OpenAI-compatible endpoint",
-    baseUrl: "http://localhost:11434/v1",
+    description: "Native Ollama API with model tags and thinking",
+    baseUrl: "http://localhost:11434",
     defaultId: "ollama",
     defaultName: "Ollama (Local)",
-    apiKeyHint: "ollama",
+    apiKeyHint: "optional",
     localOnly: true,
-    providerType: "openai"
+    providerType: "ollama"
   },
   {
     key: "koboldcpp",
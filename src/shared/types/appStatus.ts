@@ -0,0 +1,22 @@
+export interface AppUpdateInfo {
+  currentVersion: string;
+  latestVersion: string;
+  updateAvailable: boolean;
+  releaseName: string;
+  releaseUrl: string;
+  publishedAt: string | null;
+}
+
+export interface AppStatus {
+  appVersion: string;
+  dbPath: string;
+  schemaVersion: number;
+  chatCount: number;
+  characterCount: number;
+  projectCount: number;
+  providerCount: number;
+  activeProviderId: string | null;
+  activeModel: string | null;
+  fullLocalMode: boolean;
+}
+
@@ -0,0 +1,31 @@
+import type { ChatMessage, Id } from "./contracts";
+
+/** What the next turn would send to the model, assembled without contacting the provider. */
+export interface ChatPromptPreview {
+  messages: Array<{
+    role: "system" | "user" | "assistant";
+    content: string | Array<{ type: "text" | "image_url"; text?: string; image_url?: { url: string } }>;
+    reasoning_content?: string;
+  }>;
+  estimatedTokens: number;
+  keptMessages: number;
+  omittedMessages: number;
+  contextWindowBudget: number;
+}
+
+export interface ChatMessageSearchHit {
+  message: ChatMessage;
+  chatTitle: string;
+  snippet: string;
+}
+
+export interface BranchNode {
+  id: Id;
+  chatId: Id;
+  name: string;
+  parentMessageId?: Id | null;
+  messageCount?: number;
+  active?: boolean;
+  createdAt: string;
+}
+
@@ -1,18 +1,10 @@
 export type Id = string;
 
-export type CensorshipMode = "Filtered" | "Unfiltered";
+export type CensorshipMode = "Filtered" | "Moderated" | "Unfiltered";
 
-export interface ProviderProfile {
-  id: Id;
-  name: string;
-  baseUrl: string;
-  apiKeyMasked: string;
-  proxyUrl?: string | null;
-  fullLocalOnly: boolean;
-  providerType?: "openai" | "koboldcpp" | "custom";
-  adapterId?: string | null;
-  manualModels?: string[];
-}
+import type { ProviderFallbackTarget, ProviderModelMetadata } from "./providers";
+import type { RagSource } from "./rag";
+export type { ProviderAuthMode, ProviderConnectionResult, ProviderFallbackTarget, ProviderModelMetadata, ProviderProfile, ProviderType } from "./providers";
 
 export type ManagedBackendKind = "koboldcpp" | "ollama" | "generic";
 export type ManagedBackendStatusMode = "auto" | "api" | "stdout" | "none";
@@ -88,13 +80,17 @@ export interface ManagedBackendLogEntry {
   timestamp: string;
 }
 
-export interface ProviderModel {
+export interface ProviderModel extends ProviderModelMetadata {
   id: string;
   label?: string;
   managedBackendId?: string | null;
   managedBackendKind?: ManagedBackendKind | null;
   runtimeStatus?: ManagedBackendRuntimeStatus | null;
   placeholder?: boolean;
+  /** Pinned by the user; set only when true. */
+  favorite?: boolean;
+  /** Hidden by the user; only present when the list was requested with hidden models included. */
+  hidden?: boolean;
 }
 
 export interface SamplerConfig {
@@ -127,6 +123,8 @@ export interface OpenAiApiParamPolicy {
   presencePenalty: boolean;
   maxTokens: boolean;
   stop: boolean;
+  /** Asks for token usage in the stream via `stream_options`; turn off for servers that reject unknown fields. */
+  streamUsage: boolean;
 }
 
 export interface KoboldApiParamPolicy {
@@ -174,62 +172,20 @@ export interface ChatMessage {
   generationStartedAt?: string;
   generationCompletedAt?: string;
   generationDurationMs?: number;
+  truncated?: boolean;
+  /** Kept in the prompt even when older history is trimmed. */
+  pinned?: boolean;
+  swipeIndex?: number;
+  swipeCount?: number;
+  promptTokens?: number;
+  completionTokens?: number;
   parentId?: Id | null;
   characterName?: string;
   attachments?: FileAttachment[];
   ragSources?: RagSource[];
 }
 
-export interface RagSource {
-  chunkId: string;
-  documentId: string;
-  documentTitle: string;
-  score: number;
-  preview: string;
-}
-
-export interface RagCollection {
-  id: Id;
-  name: string;
-  description: string;
-  scope: "global" | "chat" | "writer";
-  createdAt: string;
-  updatedAt: string;
-}
-
-export interface RagDocument {
-  id: Id;
-  collectionId: Id;
-  title: string;
-  sourceType: string;
-  sourceId?: string | null;
-  contentHash: string;
-  status: string;
-  createdAt: string;
-  updatedAt: string;
-}
-
-export interface RagBinding {
-  enabled: boolean;
-  collectionIds: Id[];
-  updatedAt: string | null;
-}
-
-export interface RagIngestResult {
-  ok: boolean;
-  documentId: Id;
-  chunks: number;
-  embedded: number;
-  status: string;
-}
-
-export interface BranchNode {
-  id: Id;
-  chatId: Id;
-  name: string;
-  parentMessageId?: Id | null;
-  createdAt: string;
-}
+export type { RagBinding, RagCollection, RagDocument, RagIngestResult, RagSource } from "./rag";
 
 export interface ChatSession {
   id: Id;
@@ -239,6 +195,12 @@ export interface ChatSession {
   lorebookId?: Id | null;
   lorebookIds?: Id[];
   autoConversation?: boolean;
+  /** Per-chat override of the default system prompt; empty when unset. */
+  systemPrompt?: string;
+  /** Persona pinned to this chat; null follows the globally active persona. */
+  personaId?: Id | null;
+  /** Per-chat response language; empty follows the settings language. */
+  responseLanguage?: string;
   createdAt: string;
 }
 
@@ -260,6 +222,13 @@ export interface RpSceneState {
   pureChatMode?: boolean;
 }
 
+export interface RpMemoryEntry {
+  id: Id;
+  role: string;
+  content: string;
+  createdAt: string;
+}
+
 export interface CharacterSceneDefaults {
   enabled: boolean;
   variables: Record<string, string>;
@@ -299,6 +268,7 @@ export interface WriterProjectNotes {
   worldRules: string;
   contextMode: "economy" | "balanced" | "rich";
   summary: string;
+  characterRoster: string[];
 }
 
 export interface WriterDocxImportResult {
@@ -346,6 +316,11 @@ export interface WriterGenerateNextChapterResult {
   scene: Scene;
 }
 
+export interface WriterGenerateOutlineResult {
+  chapters: Chapter[];
+  scenes: Scene[];
+}
+
 export interface WriterChapterSettings {
   tone: string;
   pacing: "slow" | "balanced" | "fast";
@@ -411,6 +386,9 @@ export interface Scene {
   goals: string;
   conflicts: string;
   outcomes: string;
+  characterId?: Id | null;
+  chatId?: Id | null;
+  position?: number;
   createdAt: string;
 }
 
@@ -422,13 +400,7 @@ export interface BeatNode {
   sequence: number;
 }
 
-export interface ConsistencyIssue {
-  id: Id;
-  projectId: Id;
-  severity: "low" | "medium" | "high";
-  category: "names" | "facts" | "timeline" | "pov";
-  message: string;
-}
+export type { ConsistencyIssue, ConsistencyReport, ConsistencyReportSummary, ConsistencyStreamEvent, WriterBeat, WriterSceneRevision } from "./writerReview";
 
 export interface PromptTemplates {
   jailbreak: string;
@@ -701,6 +673,8 @@ export interface PluginHostContext {
 export interface AppSettings {
   onboardingCompleted: boolean;
   checkForUpdates: boolean;
+  /** Minutes without changes before an unlocked account locks again; 0 keeps it unlocked. */
+  autoLockMinutes: number;
   /** @deprecated The Agents workspace is available only through the Legacy tab. */
   agentsEnabled: boolean;
   agentWorkspaceToolsEnabled: boolean;
@@ -723,6 +697,9 @@ export interface AppSettings {
   simpleModeWallpaperBlur: number;
   simpleModeWallpaperPosition: "center" | "top" | "bottom";
   censorshipMode: CensorshipMode;
+  /** Used in Moderated mode; empty means the active provider's own `/moderations` endpoint. */
+  moderationUrl: string;
+  moderationApiKey: string;
   fullLocalMode: boolean;
   useAlternateGreetings: boolean;
   responseLanguage: string;
@@ -745,6 +722,8 @@ export interface AppSettings {
   interfaceLanguage: "en" | "ru" | "zh" | "ja";
   activeProviderId?: string | null;
   activeModel?: string | null;
+  /** Tried in order when the active provider fails or returns nothing before any text was streamed. */
+  providerFallbacks: ProviderFallbackTarget[];
   ttsBaseUrl: string;
   ttsApiKey: string;
   ttsAdapterId?: string | null;
@@ -756,6 +735,9 @@ export interface AppSettings {
   compressProviderId?: string | null;
   compressModel?: string | null;
   translationTimeoutSeconds: number;
+  modelFetchTimeoutSeconds: number;
+  streamRetryAttempts: number;
+  writerSceneRevisionLimit: number;
   translationTemperature: number;
   translationMaxTokens: number;
   compressionTemperature: number;
@@ -772,6 +754,9 @@ export interface AppSettings {
   includeReasoningInContext: boolean;
   contextMaxMessages: number;
   reasoningMaxChars: number;
+  loreScanDepth: number;
+  loreTokenBudget: number;
+  /** Prompt token budget, capped by the model's reported window; 0 uses the model's window. */
   contextWindowSize: number;
   contextTailBudgetWithSummaryPercent: number;
   contextTailBudgetWithoutSummaryPercent: number;
@@ -803,14 +788,7 @@ export interface AppSettings {
   customEndpointAdapters: CustomEndpointAdapter[];
 }
 
-export interface AppUpdateInfo {
-  currentVersion: string;
-  latestVersion: string;
-  updateAvailable: boolean;
-  releaseName: string;
-  releaseUrl: string;
-  publishedAt: string | null;
-}
+export type { AppStatus, AppUpdateInfo } from "./appStatus";
 
 export interface ChatCharacterLink {
   characterId: Id;
@@ -854,6 +832,10 @@ export interface CharacterDetail extends CharacterListItem {
   creatorNotesMultilingual: Record<string, unknown>;
   extensions: Record<string, unknown>;
   agentProfile?: AgentHeroProfile | null;
+  cardSpec: "chara_card_v2" | "chara_card_v3";
+  nickname: string;
+  groupOnlyGreetings: string[];
+  assets: Array<{ type: string; uri: string; name: string; ext: string }>;
   cardJson: string;
 }
 
@@ -0,0 +1,48 @@
+export type ProviderType = "openai" | "koboldcpp" | "ollama" | "anthropic" | "gemini" | "custom";
+export type ProviderAuthMode = "bearer" | "header" | "query";
+
+/** What a model listing reported beyond the id; every field is absent when the provider does not say. */
+export interface ProviderModelMetadata {
+  contextLength?: number;
+  /** USD per million prompt tokens. */
+  inputPrice?: number;
+  /** USD per million completion tokens. */
+  outputPrice?: number;
+  description?: string;
+}
+
+export interface ProviderProfile {
+  id: string;
+  name: string;
+  baseUrl: string;
+  apiKeyMasked: string;
+  proxyUrl?: string | null;
+  fullLocalOnly: boolean;
+  providerType?: ProviderType;
+  adapterId?: string | null;
+  /** Model ids offered when the provider's model endpoint is unavailable. */
+  manualModels?: string[];
+  /** Extra request headers with masked values; an `Authorization` entry replaces the API key bearer token. Sending a masked value back keeps the stored one. */
+  headers?: Record<string, string>;
+  /** Generation requests per minute; requests over the limit wait in a queue. Null or absent means unlimited. */
+  rateLimitRpm?: number | null;
+  /** Where OpenAI-compatible requests put the API key: `Authorization: Bearer` (default), a named header, or a query parameter. */
+  authMode?: ProviderAuthMode;
+  /** Header or query parameter name for the `header` and `query` modes. */
+  authParam?: string | null;
+}
+
+export interface ProviderConnectionResult {
+  ok: boolean;
+  reachable: boolean;
+  authorized: boolean;
+  status: number | null;
+  latencyMs: number | null;
+  blockedBy?: "fullLocalMode" | "localOnly";
+  error?: string;
+}
+
+export interface ProviderFallbackTarget {
+  providerId: string;
+  modelId: string;
+}
@@ -0,0 +1,45 @@
+type Id = string;
+
+export interface RagSource {
+  chunkId: string;
+  documentId: string;
+  documentTitle: string;
+  score: number;
+  preview: string;
+}
+
+export interface RagCollection {
+  id: Id;
+  name: string;
+  description: string;
+  scope: "global" | "chat" | "writer";
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface RagDocument {
+  id: Id;
+  collectionId: Id;
+  title: string;
+  sourceType: string;
+  sourceId?: string | null;
+  contentHash: string;
+  status: string;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface RagBinding {
+  enabled: boolean;
+  collectionIds: Id[];
+  updatedAt: string | null;
+}
+
+export interface RagIngestResult {
+  ok: boolean;
+  documentId: Id;
+  chunks: number;
+  embedded: number;
+  status: string;
+}
+
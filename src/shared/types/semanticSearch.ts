@@ -0,0 +1,12 @@
+export type SemanticSearchScope = "characters" | "scenes" | "messages";
+
+export interface SemanticSearchHit {
+  scope: SemanticSearchScope;
+  id: string;
+  title: string;
+  snippet: string;
+  /** Cosine similarity to the query, -1..1 (higher is closer). */
+  similarity: number;
+  /** Chat id for messages, chapter id for scenes, null for characters. */
+  parentId: string | null;
+}
@@ -0,0 +1,47 @@
+import type { AppSettings, Id } from "./contracts";
+
+export interface BackupExportResult {
+  path: string;
+  fileName: string;
+  createdAt: string;
+}
+
+export interface BackupImportResult {
+  ok: boolean;
+  createdAt: string;
+  tables: number;
+  rows: number;
+  skippedTables: string[];
+  files: number;
+}
+
+/** Portable preferences and provider profiles; keys are masked unless the export asked for them. */
+export interface SettingsExportFile {
+  format: "vellium-settings";
+  version: number;
+  exportedAt: string;
+  includesKeys: boolean;
+  settings: Partial<AppSettings>;
+  providers: Array<{
+    id: Id;
+    name: string;
+    baseUrl: string;
+    apiKey?: string;
+    apiKeyMasked?: string;
+    proxyUrl: string | null;
+    fullLocalOnly: boolean;
+    providerType: string;
+    adapterId: string | null;
+    manualModels: string[];
+    headers?: Record<string, string>;
+    rateLimitRpm?: number | null;
+  }>;
+}
+
+export interface SettingsImportResult {
+  ok: boolean;
+  settings: AppSettings;
+  providers: number;
+  ignoredFields: string[];
+}
+
@@ -0,0 +1,11 @@
+import type { Scene } from "./contracts";
+
+export type WriterBatchExpandStatus = "ok" | "error" | "not_found" | "interrupted";
+
+/** Outcome of one scene in a batch expansion; `scene` is set only when the expansion was saved. */
+export interface WriterBatchExpandResult {
+  sceneId: string;
+  status: WriterBatchExpandStatus;
+  scene?: Scene;
+  error?: string;
+}
@@ -0,0 +1,45 @@
+type Id = string;
+
+export interface WriterBeat {
+  id: Id;
+  title: string;
+  summary: string;
+  targetChapterId: Id | null;
+}
+
+export interface WriterSceneRevision {
+  id: Id;
+  sceneId: Id;
+  title: string;
+  content: string;
+  reason: "edit" | "expand" | "rewrite" | "restore";
+  createdAt: string;
+}
+
+export interface ConsistencyIssue {
+  id: Id;
+  projectId: Id;
+  severity: "low" | "medium" | "high";
+  category: "names" | "facts" | "timeline" | "pov";
+  message: string;
+}
+
+export interface ConsistencyReportSummary {
+  id: Id;
+  projectId: Id;
+  createdAt: string;
+  issueCount: number;
+  severityCounts: Record<ConsistencyIssue["severity"], number>;
+}
+
+export interface ConsistencyReport {
+  id: Id;
+  projectId: Id;
+  createdAt: string;
+  issues: ConsistencyIssue[];
+}
+
+export type ConsistencyStreamEvent =
+  | { type: "progress"; sceneId: Id; checked: number; total: number; issuesFound: number }
+  | { type: "report"; issues: ConsistencyIssue[] };
+